@@ -0,0 +1,120 @@
+use crate::error::Error as NodeError;
+use crate::upload_bytes;
+use openrank_common::attestation::{
+    report_data, AttestationReport, TeeKind, TlsAttestation, REPORT_DATA_LEN,
+};
+use openrank_common::storage::StorageBackend;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+/// Pseudo-files of the Gramine attestation interface (SGX).
+const GRAMINE_REPORT_DATA_PATH: &str = "/dev/attestation/user_report_data";
+const GRAMINE_QUOTE_PATH: &str = "/dev/attestation/quote";
+/// Reports directory of the configfs TSM interface of the kernel (SEV-SNP and TDX, Linux 6.7+).
+const TSM_REPORT_DIR: &str = "/sys/kernel/config/tsm/report";
+
+/// Generates the attestation quotes of the results, binding them to their meta commitment.
+#[derive(Debug)]
+pub struct Attester {
+    tee: TeeKind,
+    /// Number of reports requested, to name the configfs entries.
+    reports: AtomicU64,
+}
+
+impl Attester {
+    pub fn new(tee: TeeKind) -> Self {
+        Self {
+            tee,
+            reports: AtomicU64::new(0),
+        }
+    }
+
+    /// Reads `ATTESTATION_PROVIDER` (sgx, sev-snp or tdx), results not being attested when
+    /// it is not set.
+    pub fn from_env() -> Result<Option<Self>, NodeError> {
+        match std::env::var("ATTESTATION_PROVIDER") {
+            Ok(provider) if !provider.is_empty() => provider
+                .parse()
+                .map(|tee| Some(Self::new(tee)))
+                .map_err(|e| NodeError::ConfigError(format!("{}", e))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the quote (SGX, TDX) or attestation report (SEV-SNP) of the report data.
+    fn quote(&self, report_data: &[u8; REPORT_DATA_LEN]) -> Result<Vec<u8>, NodeError> {
+        match self.tee {
+            TeeKind::Sgx => {
+                fs::write(GRAMINE_REPORT_DATA_PATH, report_data).map_err(attestation_error)?;
+                fs::read(GRAMINE_QUOTE_PATH).map_err(attestation_error)
+            }
+            TeeKind::SevSnp | TeeKind::Tdx => {
+                let n = self.reports.fetch_add(1, Ordering::Relaxed);
+                let dir = PathBuf::from(TSM_REPORT_DIR).join(format!(
+                    "openrank-{}-{}",
+                    std::process::id(),
+                    n
+                ));
+                fs::create_dir(&dir).map_err(attestation_error)?;
+                let quote = self.tsm_quote(&dir, report_data);
+                // The entry is removed with rmdir, its attributes can't be deleted
+                let _ = fs::remove_dir(&dir);
+                quote
+            }
+        }
+    }
+
+    fn tsm_quote(
+        &self,
+        dir: &Path,
+        report_data: &[u8; REPORT_DATA_LEN],
+    ) -> Result<Vec<u8>, NodeError> {
+        let provider = fs::read_to_string(dir.join("provider")).map_err(attestation_error)?;
+        let expected = match self.tee {
+            TeeKind::SevSnp => "sev_guest",
+            _ => "tdx_guest",
+        };
+        if provider.trim() != expected {
+            return Err(NodeError::AttestationError(format!(
+                "TSM provider is {}, expected {}",
+                provider.trim(),
+                expected
+            )));
+        }
+        fs::write(dir.join("inblob"), report_data).map_err(attestation_error)?;
+        fs::read(dir.join("outblob")).map_err(attestation_error)
+    }
+
+    /// Generates the attestation of the results and uploads it under
+    /// `attestation/{results_id}`.
+    pub async fn attest_results(
+        &self,
+        storage: &dyn StorageBackend,
+        results_id: &str,
+        meta_commitment: &[u8],
+    ) -> Result<(), NodeError> {
+        let quote = self.quote(&report_data(meta_commitment))?;
+        let report = AttestationReport::new(self.tee, meta_commitment, &quote);
+        let bytes = serde_json::to_vec(&report).map_err(NodeError::SerdeError)?;
+        upload_bytes(storage, &format!("attestation/{}", results_id), &bytes).await?;
+        info!(
+            "Attestation uploaded: ResultsId({}), Tee({:?})",
+            results_id, self.tee
+        );
+        Ok(())
+    }
+
+    /// Generates the attestation of the TLS certificate of the server, binding the quote to
+    /// its SHA-256 fingerprint.
+    pub fn attest_tls_certificate(&self, fingerprint: &[u8]) -> Result<TlsAttestation, NodeError> {
+        let quote = self.quote(&report_data(fingerprint))?;
+        info!("TLS certificate attested: Tee({:?})", self.tee);
+        Ok(TlsAttestation::new(self.tee, &quote))
+    }
+}
+
+fn attestation_error(e: std::io::Error) -> NodeError {
+    NodeError::AttestationError(e.to_string())
+}
@@ -0,0 +1,600 @@
+use crate::computer::recompute_meta_job;
+use crate::error::Error as NodeError;
+use crate::notify::{Notification, Notifier};
+use crate::paths::{paths, PathManager};
+use crate::sol::OpenRankManager::{MetaComputeResultEvent, OpenRankManagerInstance};
+use crate::txmanager::TxManager;
+use crate::wallet::RoleWallet;
+use crate::{download_meta, download_object_to_file, parse_score_entries_from_file};
+use alloy::eips::BlockNumberOrTag;
+use alloy::hex::{self, ToHexExt};
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Signature, TxHash, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{Log, TransactionRequest};
+use alloy::signers::SignerSync;
+use openrank_common::challenge::{BlockRef, ChallengeWindow, DisputeEvidence};
+use openrank_common::rpc::LogFetcher;
+use openrank_common::runner::Error as ComputeRunnerError;
+use openrank_common::scheme;
+use openrank_common::storage::StorageBackend;
+use openrank_common::{canonical, JobDescription, JobResult, ScoreEntry};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+/// Outcome of the audit of a single result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditStatus {
+    /// Recomputing the job gives the posted commitments.
+    Valid,
+    /// Recomputing the job gives different commitments.
+    Invalid {
+        /// Indices of the sub-jobs whose commitment doesn't match.
+        mismatched_sub_jobs: Vec<usize>,
+        meta_commitment_matches: bool,
+        /// First diverging score of the first mismatched sub-job, proven in both scores trees.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        evidence: Option<Box<DisputeEvidence>>,
+    },
+    /// The job couldn't be recomputed (missing artifacts, unsupported params...).
+    Error { error: String },
+}
+
+/// Audit of a result posted on-chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub compute_id: String,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<TxHash>,
+    pub computer: Option<Address>,
+    pub results_id: String,
+    pub meta_commitment: String,
+    #[serde(flatten)]
+    pub status: AuditStatus,
+}
+
+/// Summary of an audit of every result posted in a block range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditSummary {
+    /// Address of the node that ran the audit and signed the summary.
+    pub auditor: Address,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Unix timestamps (seconds) of the start and end of the audit.
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub valid: usize,
+    pub invalid: usize,
+    pub errors: usize,
+    /// Results whose outcome was read from the audit state of a previous run, instead of
+    /// being recomputed.
+    #[serde(default)]
+    pub reused: usize,
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditSummary {
+    /// Returns the bytes that are signed by the auditor.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, canonical::Error> {
+        canonical::to_vec(self)
+    }
+}
+
+/// Audit summary, signed with the auditor's wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAuditSummary {
+    pub summary: AuditSummary,
+    pub signature: Signature,
+}
+
+impl SignedAuditSummary {
+    /// Recovers the signer of the summary and checks it matches the auditor address.
+    pub fn verify(&self) -> Result<(), NodeError> {
+        let bytes = self
+            .summary
+            .signing_bytes()
+            .map_err(NodeError::CanonicalError)?;
+        let signer = self
+            .signature
+            .recover_address_from_msg(bytes)
+            .map_err(|e| NodeError::IntegrityError(format!("Invalid audit signature: {}", e)))?;
+        if signer != self.summary.auditor {
+            return Err(NodeError::IntegrityError(format!(
+                "Audit signed by {}, expected {}",
+                signer, self.summary.auditor
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Embedded database of the audit outcomes, by compute id, so that a restarted audit skips the
+/// results it already verified. Only conclusive outcomes (valid or invalid) are kept, results
+/// that couldn't be recomputed being retried.
+#[derive(Clone)]
+pub struct AuditStateDb {
+    db: sled::Db,
+}
+
+impl AuditStateDb {
+    pub fn open(dir: impl AsRef<std::path::Path>) -> Result<Self, NodeError> {
+        let db = sled::open(dir).map_err(NodeError::AuditStateError)?;
+        Ok(Self { db })
+    }
+
+    pub fn get(&self, compute_id: U256) -> Result<Option<AuditEntry>, NodeError> {
+        let Some(bytes) = self
+            .db
+            .get(compute_id.to_be_bytes::<32>())
+            .map_err(NodeError::AuditStateError)?
+        else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(NodeError::SerdeError)
+    }
+
+    /// Records the outcome of the audit, flushed to disk before returning. Errors aren't
+    /// recorded.
+    pub fn put(&self, compute_id: U256, entry: &AuditEntry) -> Result<(), NodeError> {
+        if matches!(entry.status, AuditStatus::Error { .. }) {
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec(entry).map_err(NodeError::SerdeError)?;
+        self.db
+            .insert(compute_id.to_be_bytes::<32>(), bytes)
+            .map_err(NodeError::AuditStateError)?;
+        self.db.flush().map_err(NodeError::AuditStateError)?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Reads the scores of a sub-job, downloading them unless they are cached locally.
+async fn load_scores(
+    storage: &dyn StorageBackend,
+    job: &JobDescription,
+    job_result: &JobResult,
+) -> Result<Vec<ScoreEntry>, NodeError> {
+    let path = paths().scores_file(&job_result.scores_id);
+    if !std::path::Path::new(&path).is_file() {
+        download_object_to_file(storage, &job.scores_key(&job_result.scores_id), &path, None)
+            .await?;
+    }
+    let file = std::fs::File::open(&path)
+        .map_err(|e| NodeError::FileError(format!("Failed to open scores file: {}", e)))?;
+    parse_score_entries_from_file(file)
+}
+
+/// Builds the evidence of the first diverging score of a mismatched sub-job.
+async fn dispute_evidence(
+    storage: &dyn StorageBackend,
+    job_description_id: &str,
+    sub_job_id: usize,
+    posted: &JobResult,
+    recomputed: &JobResult,
+) -> Result<Option<Box<DisputeEvidence>>, NodeError> {
+    let scheme_version = recomputed
+        .scheme_version
+        .as_deref()
+        .unwrap_or(scheme::CURRENT_SCHEME_VERSION);
+    let scheme = scheme::find(scheme_version)
+        .ok_or_else(|| NodeError::ConfigError(format!("Unknown scheme: {}", scheme_version)))?;
+    // The scores are stored under the key prefix of the sub-job
+    let meta_job: Vec<JobDescription> =
+        download_meta(storage, job_description_id.to_string(), None).await?;
+    let job = meta_job.get(sub_job_id).ok_or_else(|| {
+        NodeError::IntegrityError(format!("Sub-job {} not in the job description", sub_job_id))
+    })?;
+    let posted_scores = load_scores(storage, job, posted).await?;
+    let recomputed_scores = load_scores(storage, job, recomputed).await?;
+    DisputeEvidence::build(
+        sub_job_id as u32,
+        scheme,
+        &posted_scores,
+        &recomputed_scores,
+    )
+    .map(|evidence| evidence.map(Box::new))
+    .map_err(|e| NodeError::ComputeRunnerError(ComputeRunnerError::Merkle(e)))
+}
+
+/// Recomputes the job of a posted result and compares the commitments.
+async fn audit_result<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    storage: &Arc<dyn StorageBackend>,
+    event: &MetaComputeResultEvent,
+    strict_params: bool,
+) -> Result<(Option<Address>, AuditStatus), NodeError> {
+    let request = contract
+        .metaComputeRequests(event.computeId)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read request: {e:}")))?;
+    let result = contract
+        .metaComputeResults(event.computeId)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read result: {e:}")))?;
+
+    // The result may have been posted moments ago, its meta not visible to every reader yet
+    let posted: Vec<JobResult> = download_meta(
+        storage.as_ref(),
+        event.resultsId.encode_hex(),
+        result.timestamp.try_into().ok(),
+    )
+    .await?;
+    let (recomputed, meta_commitment) = recompute_meta_job(
+        storage.clone(),
+        request.jobDescriptionId.encode_hex(),
+        strict_params,
+        request.timestamp.try_into().ok(),
+    )
+    .await?;
+
+    let mut mismatched_sub_jobs: Vec<usize> = recomputed
+        .iter()
+        .zip(&posted)
+        .enumerate()
+        .filter(|(_, (recomputed, posted))| recomputed.commitment != posted.commitment)
+        .map(|(index, _)| index)
+        .collect();
+    // Sub-jobs missing from either side don't match either
+    mismatched_sub_jobs
+        .extend(posted.len().min(recomputed.len())..posted.len().max(recomputed.len()));
+    let meta_commitment_matches = meta_commitment.inner() == event.commitment.as_slice();
+
+    let status = if mismatched_sub_jobs.is_empty() && meta_commitment_matches {
+        AuditStatus::Valid
+    } else {
+        // The evidence is only reported, as the sub-job index is all the contract takes
+        let evidence = match mismatched_sub_jobs
+            .first()
+            .and_then(|&index| Some((index, posted.get(index)?, recomputed.get(index)?)))
+        {
+            Some((index, posted, recomputed)) => {
+                let job_description_id = request.jobDescriptionId.encode_hex();
+                dispute_evidence(
+                    storage.as_ref(),
+                    &job_description_id,
+                    index,
+                    posted,
+                    recomputed,
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to build dispute evidence: {}", e);
+                    None
+                })
+            }
+            None => None,
+        };
+        AuditStatus::Invalid {
+            mismatched_sub_jobs,
+            meta_commitment_matches,
+            evidence,
+        }
+    };
+    Ok((Some(result.computer), status))
+}
+
+/// Submits the challenge of a sub-job, returning the hash of the transaction once confirmed.
+async fn submit_challenge<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    compute_id: U256,
+    sub_job_id: u32,
+) -> Result<TxHash, NodeError> {
+    wallet.check_spend_allowed()?;
+    let tx = TransactionRequest::default()
+        .with_to(*contract.address())
+        .with_input(
+            contract
+                .submitMetaChallenge(compute_id, sub_job_id)
+                .calldata()
+                .clone(),
+        );
+    let receipt = tx_manager
+        .send(contract.provider(), wallet.address(), tx, |tx_hash| {
+            info!("'submitMetaChallenge' submitted: Tx Hash({:#})", tx_hash);
+            Ok(())
+        })
+        .await?;
+    let tx_hash = receipt.transaction_hash;
+    wallet.record_spend(U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price));
+    if !receipt.status() {
+        return Err(NodeError::TxError(format!(
+            "'submitMetaChallenge' reverted: Tx Hash({:#})",
+            tx_hash
+        )));
+    }
+    info!("'submitMetaChallenge' confirmed: Tx Hash({:#})", tx_hash);
+    Ok(tx_hash)
+}
+
+/// Alerts the operators of a result that doesn't verify, depending on its challenge: already
+/// challenged, window still open (and counting down), or window closed without challenge.
+/// With a `tx_manager` (challenge mode), results whose window is open are challenged on their
+/// first mismatched sub-job.
+#[allow(clippy::too_many_arguments)]
+async fn handle_invalid<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: Option<&TxManager>,
+    notifier: &Notifier,
+    compute_id: U256,
+    result_block: Option<u64>,
+    computer: Option<Address>,
+    mismatched_sub_jobs: &[usize],
+) -> Result<(), NodeError> {
+    let challenge = contract
+        .metaChallenges(compute_id)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read challenge: {e:}")))?;
+    if challenge.challenger != Address::ZERO {
+        notifier
+            .notify(&Notification::ChallengeSubmitted {
+                compute_id: compute_id.to_string(),
+                sub_job_id: challenge.subJobId,
+                challenger: challenge.challenger,
+                tx_hash: None,
+            })
+            .await;
+        return Ok(());
+    }
+
+    let window =
+        ChallengeWindow::Seconds(
+            contract.CHALLENGE_WINDOW().call().await.map_err(|e| {
+                NodeError::TxError(format!("Failed to read challenge window: {e:}"))
+            })?,
+        );
+    let result = contract
+        .metaComputeResults(compute_id)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read result: {e:}")))?;
+    let latest = contract
+        .provider()
+        .get_block_by_number(BlockNumberOrTag::Latest)
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to get latest block: {e:}")))?
+        .ok_or_else(|| NodeError::TxError("No latest block".to_string()))?;
+    let latest = BlockRef::new(latest.header.number, latest.header.timestamp);
+    let result_block = BlockRef::new(
+        result_block.unwrap_or_default(),
+        result.timestamp.try_into().unwrap_or(u64::MAX),
+    );
+    if !window.is_open(&result_block, &latest) {
+        notifier
+            .notify(&Notification::WindowExpired {
+                compute_id: compute_id.to_string(),
+                computer,
+                mismatched_sub_jobs: mismatched_sub_jobs.to_vec(),
+            })
+            .await;
+        return Ok(());
+    }
+    let window_remaining_secs = window.remaining(&result_block, &latest);
+    notifier
+        .notify(&Notification::VerificationFailed {
+            compute_id: compute_id.to_string(),
+            computer,
+            mismatched_sub_jobs: mismatched_sub_jobs.to_vec(),
+            window_remaining_secs,
+            closing_soon: notifier.is_closing_soon(window_remaining_secs),
+        })
+        .await;
+
+    let Some(tx_manager) = tx_manager else {
+        return Ok(());
+    };
+    // Only the meta commitment mismatching: any sub-job reproduces the commitment mismatch
+    let sub_job_id = mismatched_sub_jobs.first().copied().unwrap_or_default() as u32;
+    let notification =
+        match submit_challenge(contract, wallet, tx_manager, compute_id, sub_job_id).await {
+            Ok(tx_hash) => Notification::ChallengeSubmitted {
+                compute_id: compute_id.to_string(),
+                sub_job_id,
+                challenger: wallet.address(),
+                tx_hash: Some(tx_hash),
+            },
+            Err(e) => {
+                error!(
+                    "Audit: ComputeId({}), failed to challenge: {}",
+                    compute_id, e
+                );
+                Notification::ChallengeFailed {
+                    compute_id: compute_id.to_string(),
+                    sub_job_id,
+                    error: e.to_string(),
+                }
+            }
+        };
+    notifier.notify(&notification).await;
+    Ok(())
+}
+
+/// Audits every result posted in the block range, regardless of challenge windows.
+/// Runs in shadow mode unless a `tx_manager` is given: nothing is posted on-chain or uploaded.
+/// In challenge mode, results that don't verify are challenged while their window is open.
+/// Either way, the operators are alerted of them through the `notifier`.
+/// The results already verified by a previous run, as recorded in `state`, are reported with
+/// their recorded outcome unless `reverify` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_audit<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    provider: &PH,
+    wallet: &RoleWallet,
+    storage: &Arc<dyn StorageBackend>,
+    state: &AuditStateDb,
+    notifier: &Notifier,
+    tx_manager: Option<&TxManager>,
+    from_block: u64,
+    to_block: u64,
+    strict_params: bool,
+    reverify: bool,
+) -> Result<SignedAuditSummary, NodeError> {
+    let started_at = now();
+    let filter = contract.MetaComputeResultEvent_filter().filter;
+    let mut log_fetcher = LogFetcher::from_env()
+        .map_err(|e| NodeError::ConfigError(format!("Invalid RPC logs config: {}", e)))?;
+    let logs = log_fetcher
+        .get_logs(provider, &filter, from_block, to_block)
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to get result logs: {}", e)))?;
+    info!(
+        "Auditing {} results posted in blocks {}..={}",
+        logs.len(),
+        from_block,
+        to_block
+    );
+
+    let mut entries = Vec::new();
+    let mut reused = 0;
+    for log in logs {
+        let res: Log<MetaComputeResultEvent> = log
+            .log_decode()
+            .map_err(|e| NodeError::TxError(format!("Failed to decode result log: {}", e)))?;
+        let event = res.data();
+        let previous = if reverify {
+            None
+        } else {
+            state.get(event.computeId)?
+        };
+        let is_reused = previous.is_some();
+        let entry = match previous {
+            Some(entry) => {
+                info!(
+                    "Audit: ComputeId({}), already audited, skipping",
+                    event.computeId
+                );
+                reused += 1;
+                entry
+            }
+            None => {
+                let (computer, status) =
+                    match audit_result(contract, storage, event, strict_params).await {
+                        Ok(outcome) => outcome,
+                        Err(e) => (
+                            None,
+                            AuditStatus::Error {
+                                error: e.to_string(),
+                            },
+                        ),
+                    };
+                match &status {
+                    AuditStatus::Valid => info!("Audit: ComputeId({}), Valid", event.computeId),
+                    // Only challenged in challenge mode, while the window is open
+                    AuditStatus::Invalid {
+                        mismatched_sub_jobs,
+                        meta_commitment_matches,
+                        evidence,
+                    } => error!(
+                        "Audit: ComputeId({}), Invalid, {} SubJob({}): mismatched sub-jobs {:?}, meta commitment matches: {}, first diverging score: {}",
+                        event.computeId,
+                        if tx_manager.is_some() { "challenging" } else { "would challenge" },
+                        mismatched_sub_jobs
+                            .first()
+                            .map(ToString::to_string)
+                            .unwrap_or_else(|| "-".to_string()),
+                        mismatched_sub_jobs,
+                        meta_commitment_matches,
+                        evidence
+                            .as_ref()
+                            .map(|e| e.score_index.to_string())
+                            .unwrap_or_else(|| "-".to_string())
+                    ),
+                    status => error!("Audit: ComputeId({}), {:?}", event.computeId, status),
+                }
+                AuditEntry {
+                    compute_id: event.computeId.to_string(),
+                    block_number: res.block_number,
+                    tx_hash: res.transaction_hash,
+                    computer,
+                    results_id: event.resultsId.encode_hex(),
+                    meta_commitment: hex::encode(event.commitment),
+                    status,
+                }
+            }
+        };
+        // Results found invalid by an earlier shadow run are still challenged in challenge
+        // mode, handle_invalid skipping the ones already challenged
+        if let AuditStatus::Invalid {
+            mismatched_sub_jobs,
+            ..
+        } = &entry.status
+        {
+            if !is_reused || tx_manager.is_some() {
+                if let Err(e) = handle_invalid(
+                    contract,
+                    wallet,
+                    tx_manager,
+                    notifier,
+                    event.computeId,
+                    res.block_number,
+                    entry.computer,
+                    mismatched_sub_jobs,
+                )
+                .await
+                {
+                    warn!(
+                        "Audit: ComputeId({}), failed to check the challenge window: {}",
+                        event.computeId, e
+                    );
+                }
+            }
+        }
+        if !is_reused {
+            state.put(event.computeId, &entry)?;
+        }
+        entries.push(entry);
+    }
+
+    let count = |f: fn(&AuditStatus) -> bool| entries.iter().filter(|e| f(&e.status)).count();
+    let summary = AuditSummary {
+        auditor: wallet.address(),
+        from_block,
+        to_block,
+        started_at,
+        finished_at: now(),
+        valid: count(|s| matches!(s, AuditStatus::Valid)),
+        invalid: count(|s| matches!(s, AuditStatus::Invalid { .. })),
+        errors: count(|s| matches!(s, AuditStatus::Error { .. })),
+        reused,
+        entries,
+    };
+    let bytes = summary.signing_bytes().map_err(NodeError::CanonicalError)?;
+    let signature = wallet
+        .signer()
+        .sign_message_sync(&bytes)
+        .map_err(|e| NodeError::TxError(format!("Failed to sign audit summary: {}", e)))?;
+    Ok(SignedAuditSummary { summary, signature })
+}
+
+/// Writes the signed summary to the audit directory of the manager, returning its path.
+pub fn save_audit(signed: &SignedAuditSummary, state: &PathManager) -> Result<String, NodeError> {
+    std::fs::create_dir_all(state.audit_dir())
+        .map_err(|e| NodeError::FileError(format!("Failed to create audit dir: {}", e)))?;
+    let path = format!(
+        "{}/audit-{}-{}.json",
+        state.audit_dir(),
+        signed.summary.from_block,
+        signed.summary.to_block
+    );
+    let bytes = serde_json::to_vec_pretty(signed).map_err(NodeError::SerdeError)?;
+    std::fs::write(&path, bytes)
+        .map_err(|e| NodeError::FileError(format!("Failed to write audit summary: {}", e)))?;
+    Ok(path)
+}
@@ -0,0 +1,283 @@
+use openrank_common::params::{EigenTrustParams, SybilRankParams};
+use openrank_common::runner::ComputeRunner;
+use openrank_common::scheme;
+use openrank_common::{ScoreEntry, TrustEntry};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Sizes (number of trust edges) of the synthetic graphs of the default suite.
+const DEFAULT_SIZES: [(&str, usize); 3] =
+    [("1m", 1_000_000), ("10m", 10_000_000), ("50m", 50_000_000)];
+/// Average number of outbound edges per node of the synthetic graphs.
+const EDGES_PER_NODE: usize = 10;
+/// Number of seed nodes of the synthetic graphs.
+const SEED_NODES: usize = 100;
+/// Seed of the graph generator, so every run benchmarks the same graphs.
+const GRAPH_SEED: u64 = 42;
+/// Relative slowdown (or memory growth) over the baseline failing the run.
+const DEFAULT_MAX_REGRESSION: f64 = 0.25;
+
+const USAGE: &str = "Usage: bench-report [--sizes 1m,10m,50m] [--out <path>] \
+    [--baseline <path>] [--max-regression <ratio>]";
+
+/// Measurement of a single benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BenchResult {
+    /// Benchmark name, `<size>/<stage>`, e.g. `10m/eigen_trust`.
+    name: String,
+    edges: usize,
+    millis: f64,
+    /// Peak resident set size of the process while benchmarking the graph, in KiB.
+    peak_rss_kib: Option<u64>,
+}
+
+/// Machine readable report of a benchmark run, tracked over commits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BenchReport {
+    commit: Option<String>,
+    version: String,
+    timestamp: u64,
+    threads: usize,
+    results: Vec<BenchResult>,
+}
+
+struct Args {
+    sizes: Vec<(String, usize)>,
+    out: Option<String>,
+    baseline: Option<String>,
+    max_regression: f64,
+}
+
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.strip_suffix(['m', 'M']) {
+        Some(digits) => (digits, 1_000_000),
+        None => match size.strip_suffix(['k', 'K']) {
+            Some(digits) => (digits, 1_000),
+            None => (size, 1),
+        },
+    };
+    digits.parse::<usize>().ok().map(|n| n * unit)
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        sizes: DEFAULT_SIZES
+            .iter()
+            .map(|(name, edges)| (name.to_string(), *edges))
+            .collect(),
+        out: None,
+        baseline: None,
+        max_regression: DEFAULT_MAX_REGRESSION,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or_else(|| USAGE.to_string());
+        match arg.as_str() {
+            "--sizes" => {
+                args.sizes = value()?
+                    .split(',')
+                    .map(|size| {
+                        parse_size(size)
+                            .map(|edges| (size.to_lowercase(), edges))
+                            .ok_or_else(|| format!("Invalid size: {}", size))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--out" => args.out = Some(value()?),
+            "--baseline" => args.baseline = Some(value()?),
+            "--max-regression" => {
+                args.max_regression = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid max regression: {}", e))?;
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(args)
+}
+
+/// Generates a random graph with the given number of edges, the same for a given size.
+fn synthetic_graph(edges: usize) -> (Vec<TrustEntry>, Vec<ScoreEntry>) {
+    let mut rng = StdRng::seed_from_u64(GRAPH_SEED);
+    let nodes = (edges / EDGES_PER_NODE).max(SEED_NODES);
+    let trust = (0..edges)
+        .map(|_| {
+            let from = rng.random_range(0..nodes);
+            let to = rng.random_range(0..nodes);
+            let value = rng.random_range(0.1..1.0);
+            TrustEntry::new(format!("n{}", from), format!("n{}", to), value)
+        })
+        .collect();
+    let seed = (0..SEED_NODES)
+        .map(|i| ScoreEntry::new(format!("n{}", i), 1.0))
+        .collect();
+    (trust, seed)
+}
+
+/// Resets the peak RSS of the process, so it is measured per graph (Linux only).
+fn reset_peak_rss() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Returns the peak RSS of the process in KiB (Linux only).
+fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// Runs every stage on the graph, returning the timings of each.
+fn bench_graph(edges: usize) -> Result<Vec<(&'static str, f64)>, String> {
+    let (trust, seed) = synthetic_graph(edges);
+    let mut runner = ComputeRunner::new(scheme::current().precision);
+    let (res, graph_build) = time(|| {
+        runner.update_trust_map(trust)?;
+        runner.update_seed_map(seed)
+    });
+    res.map_err(|e| e.to_string())?;
+    // Both EigenTrust computations are benchmarked whatever the size, for the partitioned one
+    // to be enabled from the size it pays off (`PARTITIONED_MIN_EDGES`)
+    let mut map_runner = runner.clone().with_partition_min_edges(usize::MAX);
+    let (res, eigen_trust) = time(|| map_runner.compute_et(&EigenTrustParams::default()));
+    res.map_err(|e| e.to_string())?;
+    drop(map_runner);
+    let mut partitioned_runner = runner.clone().with_partition_min_edges(0);
+    let (res, eigen_trust_partitioned) =
+        time(|| partitioned_runner.compute_et(&EigenTrustParams::default()));
+    res.map_err(|e| e.to_string())?;
+    drop(partitioned_runner);
+    let (res, merkle) = time(|| {
+        runner.create_compute_tree(scheme::current())?;
+        runner.get_root_hash()
+    });
+    res.map_err(|e| e.to_string())?;
+    let (res, sybil_rank) = time(|| runner.compute_sr(&SybilRankParams::default()));
+    res.map_err(|e| e.to_string())?;
+    Ok(vec![
+        ("graph_build", graph_build),
+        ("eigen_trust", eigen_trust),
+        ("eigen_trust_partitioned", eigen_trust_partitioned),
+        ("merkle", merkle),
+        ("sybil_rank", sybil_rank),
+    ])
+}
+
+fn git_commit() -> Option<String> {
+    if let Ok(commit) = std::env::var("GIT_COMMIT") {
+        return Some(commit);
+    }
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns the regressions of the report over the baseline, beyond the max regression.
+fn regressions(report: &BenchReport, baseline: &BenchReport, max_regression: f64) -> Vec<String> {
+    let baseline: HashMap<&str, &BenchResult> = baseline
+        .results
+        .iter()
+        .map(|r| (r.name.as_str(), r))
+        .collect();
+    let mut regressions = Vec::new();
+    for result in &report.results {
+        let Some(base) = baseline.get(result.name.as_str()) else {
+            continue;
+        };
+        if result.millis > base.millis * (1.0 + max_regression) {
+            regressions.push(format!(
+                "{}: {:.1}ms, baseline {:.1}ms",
+                result.name, result.millis, base.millis
+            ));
+        }
+        if let (Some(rss), Some(base_rss)) = (result.peak_rss_kib, base.peak_rss_kib) {
+            if rss as f64 > base_rss as f64 * (1.0 + max_regression) {
+                regressions.push(format!(
+                    "{}: peak RSS {}KiB, baseline {}KiB",
+                    result.name, rss, base_rss
+                ));
+            }
+        }
+    }
+    regressions
+}
+
+fn run(args: Args) -> Result<bool, String> {
+    let mut results = Vec::new();
+    for (size, edges) in &args.sizes {
+        eprintln!("Benchmarking {} edges...", size);
+        reset_peak_rss();
+        let timings = bench_graph(*edges)?;
+        let peak_rss_kib = peak_rss_kib();
+        for (stage, millis) in timings {
+            eprintln!("  {}: {:.1}ms", stage, millis);
+            results.push(BenchResult {
+                name: format!("{}/{}", size, stage),
+                edges: *edges,
+                millis,
+                peak_rss_kib,
+            });
+        }
+    }
+
+    let report = BenchReport {
+        commit: git_commit(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        results,
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    match &args.out {
+        Some(path) => std::fs::write(path, &json)
+            .map_err(|e| format!("Failed to write report {}: {}", path, e))?,
+        None => println!("{}", json),
+    }
+
+    let Some(path) = &args.baseline else {
+        return Ok(true);
+    };
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("Failed to read baseline {}: {}", path, e))?;
+    let baseline: BenchReport =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid baseline {}: {}", path, e))?;
+    let regressions = regressions(&report, &baseline, args.max_regression);
+    for regression in &regressions {
+        eprintln!(
+            "Regression over {}%: {}",
+            args.max_regression * 100.0,
+            regression
+        );
+    }
+    Ok(regressions.is_empty())
+}
+
+fn main() -> ExitCode {
+    match parse_args().and_then(run) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(2),
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
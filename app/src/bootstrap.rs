@@ -0,0 +1,226 @@
+use crate::error::Error as NodeError;
+use aws_sdk_s3::types::{BucketLocationConstraint, CreateBucketConfiguration};
+use aws_sdk_s3::Client;
+use openrank_common::storage::{Error as StorageError, LocalStorage, S3Storage, StorageBackend};
+use rand::Rng;
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tracing::{error, info, warn};
+
+/// Region reported for the local storage, which is always where it is expected.
+const LOCAL_REGION: &str = "local";
+
+/// Prefixes the node needs full access to.
+pub const REQUIRED_PREFIXES: [&str; 5] = ["trust/", "seed/", "scores/", "meta/", "manifest/"];
+
+/// Result of a single permission check on a canary key.
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionCheck {
+    pub allowed: bool,
+    pub error: Option<String>,
+}
+
+impl PermissionCheck {
+    fn from_result<T, E: Display>(res: Result<T, E>) -> Self {
+        match res {
+            Ok(_) => Self {
+                allowed: true,
+                error: None,
+            },
+            Err(e) => Self {
+                allowed: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Permissions of the node under a single prefix.
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefixPermissions {
+    pub prefix: String,
+    pub put: PermissionCheck,
+    pub head: PermissionCheck,
+    pub get: PermissionCheck,
+    pub delete: PermissionCheck,
+}
+
+impl PrefixPermissions {
+    pub fn is_ok(&self) -> bool {
+        self.put.allowed && self.head.allowed && self.get.allowed && self.delete.allowed
+    }
+}
+
+/// Report of the storage self-check performed on startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub bucket: String,
+    pub expected_region: String,
+    pub bucket_region: Option<String>,
+    /// Whether the bucket is reached through a custom endpoint, whose S3 compatible service
+    /// may not report the region of the bucket.
+    pub custom_endpoint: bool,
+    pub bucket_created: bool,
+    pub prefixes: Vec<PrefixPermissions>,
+}
+
+impl StorageReport {
+    /// Whether the bucket is in the expected region. An unknown region is accepted behind a
+    /// custom endpoint.
+    pub fn region_matches(&self) -> bool {
+        match &self.bucket_region {
+            Some(region) => region == &self.expected_region,
+            None => self.custom_endpoint,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.region_matches() && self.prefixes.iter().all(|p| p.is_ok())
+    }
+}
+
+impl Display for StorageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "Storage report for bucket '{}':", self.bucket)?;
+        writeln!(
+            f,
+            "  region: expected {}, found {}",
+            self.expected_region,
+            self.bucket_region.as_deref().unwrap_or("<unknown>")
+        )?;
+        for p in &self.prefixes {
+            let checks = [
+                ("PUT", &p.put),
+                ("HEAD", &p.head),
+                ("GET", &p.get),
+                ("DELETE", &p.delete),
+            ];
+            for (op, check) in checks {
+                match &check.error {
+                    None => writeln!(f, "  {}{}: OK", p.prefix, op)?,
+                    Some(e) => writeln!(f, "  {}{}: DENIED ({})", p.prefix, op, e)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Creates the bucket if it doesn't exist yet.
+/// Returns `true` if the bucket was created.
+async fn ensure_bucket(client: &Client, bucket: &str, region: &str) -> Result<bool, NodeError> {
+    let head = client.head_bucket().bucket(bucket).send().await;
+    match head {
+        Ok(_) => Ok(false),
+        Err(e) if e.as_service_error().map(|e| e.is_not_found()) == Some(true) => {
+            info!("Bucket '{}' not found, creating it in {}", bucket, region);
+            let mut req = client.create_bucket().bucket(bucket);
+            // us-east-1 is the default location and must not be passed as a constraint
+            if region != "us-east-1" {
+                let config = CreateBucketConfiguration::builder()
+                    .location_constraint(BucketLocationConstraint::from(region))
+                    .build();
+                req = req.create_bucket_configuration(config);
+            }
+            req.send()
+                .await
+                .map_err(|e| NodeError::AwsError(Box::new(e.into())))?;
+            Ok(true)
+        }
+        Err(e) => Err(NodeError::AwsError(Box::new(e.into()))),
+    }
+}
+
+/// Returns the region the bucket is located in.
+async fn bucket_region(client: &Client, bucket: &str) -> Result<String, NodeError> {
+    let res = client
+        .get_bucket_location()
+        .bucket(bucket)
+        .send()
+        .await
+        .map_err(|e| NodeError::AwsError(Box::new(e.into())))?;
+    // Empty location constraint means us-east-1
+    let region = res
+        .location_constraint()
+        .map(|l| l.as_str())
+        .filter(|l| !l.is_empty())
+        .unwrap_or("us-east-1");
+    Ok(region.to_string())
+}
+
+/// Checks PUT/HEAD/GET/DELETE permissions under the given prefix using a canary key.
+async fn check_prefix(storage: &dyn StorageBackend, prefix: &str) -> PrefixPermissions {
+    let canary_key = format!("{}.canary-{:016x}", prefix, rand::rng().random::<u64>());
+
+    let put = storage.put(&canary_key, b"openrank-canary".to_vec()).await;
+    let head = match storage.size(&canary_key).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(StorageError::NotFound(canary_key.clone())),
+        Err(e) => Err(e),
+    };
+    let get = storage.get(&canary_key).await;
+    let delete = storage.delete(&canary_key).await;
+
+    PrefixPermissions {
+        prefix: prefix.to_string(),
+        put: PermissionCheck::from_result(put),
+        head: PermissionCheck::from_result(head),
+        get: PermissionCheck::from_result(get),
+        delete: PermissionCheck::from_result(delete),
+    }
+}
+
+/// Bootstraps the bucket and verifies the node has all the permissions it needs.
+pub async fn check_storage(
+    storage: &S3Storage,
+    region: &str,
+    custom_endpoint: bool,
+) -> Result<StorageReport, NodeError> {
+    let (client, bucket) = (storage.client(), storage.bucket());
+    let bucket_created = ensure_bucket(client, bucket, region).await?;
+    let bucket_region = match bucket_region(client, bucket).await {
+        Ok(r) => Some(r),
+        Err(e) if custom_endpoint => {
+            warn!(
+                "Failed to get bucket location from the custom endpoint, skipping the region check: {}",
+                e
+            );
+            None
+        }
+        Err(e) => {
+            error!("Failed to get bucket location: {}", e);
+            None
+        }
+    };
+
+    let mut prefixes = Vec::new();
+    for prefix in REQUIRED_PREFIXES {
+        prefixes.push(check_prefix(storage, prefix).await);
+    }
+
+    Ok(StorageReport {
+        bucket: bucket.to_string(),
+        expected_region: region.to_string(),
+        bucket_region,
+        custom_endpoint,
+        bucket_created,
+        prefixes,
+    })
+}
+
+/// Verifies the node has all the permissions it needs on the local storage directory.
+pub async fn check_local_storage(storage: &LocalStorage) -> StorageReport {
+    let mut prefixes = Vec::new();
+    for prefix in REQUIRED_PREFIXES {
+        prefixes.push(check_prefix(storage, prefix).await);
+    }
+
+    StorageReport {
+        bucket: storage.location(),
+        expected_region: LOCAL_REGION.to_string(),
+        bucket_region: Some(LOCAL_REGION.to_string()),
+        custom_endpoint: false,
+        bucket_created: false,
+        prefixes,
+    }
+}
@@ -0,0 +1,70 @@
+use crate::error::Error as NodeError;
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Persisted position of the event polling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Block the next start resumes polling from, included.
+    pub block: u64,
+    /// Unix timestamp (seconds) of the last update.
+    pub updated_at: u64,
+}
+
+/// Block checkpoint of the computer, so that a restarted node polls the events from where it
+/// stopped instead of a fixed lookback window.
+pub struct BlockCheckpoint {
+    path: PathBuf,
+}
+
+impl BlockCheckpoint {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads the checkpoint, `None` on the first start.
+    pub fn load(&self) -> Result<Option<Checkpoint>, NodeError> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(NodeError::SerdeError),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(NodeError::FileError(format!(
+                "Failed to read block checkpoint: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Replaces the checkpoint atomically.
+    pub fn save(&self, block: u64) -> Result<(), NodeError> {
+        let checkpoint = Checkpoint {
+            block,
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        let tmp_path = self.path.with_extension("json.part");
+        serde_json::to_vec_pretty(&checkpoint)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| std::fs::write(&tmp_path, bytes))
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| NodeError::FileError(format!("Failed to save block checkpoint: {}", e)))
+    }
+}
+
+/// Returns the block to resume from: the last polled block, or the block of the oldest
+/// request still pending (queued, running or waiting for a retry), for it to be pulled again.
+pub fn resume_block(last_polled: u64, pending_requests: &HashMap<U256, u64>) -> u64 {
+    pending_requests
+        .values()
+        .copied()
+        .min()
+        .map_or(last_polled, |block| block.min(last_polled))
+}
@@ -0,0 +1,193 @@
+use crate::error::Error as NodeError;
+use crate::sol::OpenRankManager;
+use alloy::primitives::{b256, Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy_sol_types::{SolCall, SolEvent};
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use tracing::{info, warn};
+
+/// Version of the OpenRankManager ABI bundled with this node (`contracts/out`).
+pub const BUNDLED_ABI_VERSION: &str = "1";
+
+/// ERC-1967 storage slot holding the implementation address of a proxy.
+const ERC1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+sol!(
+    #[sol(rpc)]
+    interface IVersioned {
+        function VERSION() external view returns (string memory);
+    }
+);
+
+/// Result of the check of the deployed manager contract against the bundled ABI.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContractCompatReport {
+    pub address: Address,
+    /// Implementation behind the ERC-1967 proxy, if the contract is a proxy.
+    pub implementation: Option<Address>,
+    /// Value of the `VERSION()` getter, if the contract exposes one.
+    pub version: Option<String>,
+    /// Events the node listens to that the contract doesn't emit with the bundled signature.
+    pub missing_events: Vec<String>,
+    /// Functions the node calls that the contract doesn't expose with the bundled signature.
+    pub missing_functions: Vec<String>,
+}
+
+impl ContractCompatReport {
+    pub fn is_compatible(&self) -> bool {
+        self.missing_events.is_empty() && self.missing_functions.is_empty()
+    }
+
+    pub fn version_matches(&self) -> bool {
+        self.version
+            .as_ref()
+            .is_none_or(|version| version == BUNDLED_ABI_VERSION)
+    }
+}
+
+impl Display for ContractCompatReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(
+            f,
+            "OpenRankManager compatibility report for {}:",
+            self.address
+        )?;
+        if let Some(implementation) = self.implementation {
+            writeln!(f, "  implementation: {}", implementation)?;
+        }
+        writeln!(
+            f,
+            "  version: {} (bundled ABI: {})",
+            self.version.as_deref().unwrap_or("unknown"),
+            BUNDLED_ABI_VERSION
+        )?;
+        for event in &self.missing_events {
+            writeln!(f, "  missing event: {}", event)?;
+        }
+        for function in &self.missing_functions {
+            writeln!(f, "  missing function: {}", function)?;
+        }
+        if !self.is_compatible() {
+            writeln!(
+                f,
+                "The deployed contract doesn't match the ABI bundled with this node, so its logs can't be decoded."
+            )?;
+            writeln!(
+                f,
+                "Upgrade the node to a release built against the deployed contract, or check OPENRANK_MANAGER_ADDRESS and CHAIN_RPC_URL point to the intended deployment."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether the code contains the instruction pushing the given constant.
+/// Solidity pushes function selectors with PUSH4 in the dispatcher and event topics with PUSH32.
+fn pushes_constant(code: &[u8], constant: &[u8]) -> bool {
+    let opcode = 0x5f + constant.len() as u8;
+    code.windows(constant.len() + 1)
+        .any(|w| w[0] == opcode && &w[1..] == constant)
+}
+
+/// Checks the deployed manager contract against the bundled ABI: the events the node
+/// decodes and the functions it calls must be present in the contract bytecode.
+pub async fn check_contract<P: Provider>(
+    provider: &P,
+    address: Address,
+) -> Result<ContractCompatReport, NodeError> {
+    let code = provider.get_code_at(address).await?;
+    if code.is_empty() {
+        return Err(NodeError::ConfigError(format!(
+            "No contract deployed at {}, check OPENRANK_MANAGER_ADDRESS and CHAIN_RPC_URL",
+            address
+        )));
+    }
+
+    // Upgradeable deployments keep the logic in the implementation contract
+    let slot = provider
+        .get_storage_at(address, ERC1967_IMPLEMENTATION_SLOT.into())
+        .await?;
+    let implementation = (slot != U256::ZERO).then(|| Address::from_word(slot.into()));
+    let code = match implementation {
+        Some(implementation) => provider.get_code_at(implementation).await?,
+        None => code,
+    };
+
+    let events = [
+        (
+            OpenRankManager::MetaComputeRequestEvent::SIGNATURE,
+            OpenRankManager::MetaComputeRequestEvent::SIGNATURE_HASH,
+        ),
+        (
+            OpenRankManager::MetaComputeResultEvent::SIGNATURE,
+            OpenRankManager::MetaComputeResultEvent::SIGNATURE_HASH,
+        ),
+    ];
+    let functions = [
+        (
+            OpenRankManager::submitMetaComputeResultCall::SIGNATURE,
+            OpenRankManager::submitMetaComputeResultCall::SELECTOR,
+        ),
+        (
+            OpenRankManager::metaComputeResultsCall::SIGNATURE,
+            OpenRankManager::metaComputeResultsCall::SELECTOR,
+        ),
+    ];
+    let missing_events = events
+        .into_iter()
+        .filter(|(_, topic)| !pushes_constant(&code, topic.as_slice()))
+        .map(|(signature, _)| signature.to_string())
+        .collect();
+    let missing_functions = functions
+        .into_iter()
+        .filter(|(_, selector)| !pushes_constant(&code, selector))
+        .map(|(signature, _)| signature.to_string())
+        .collect();
+
+    // The getter is optional, older deployments don't have it
+    let version = IVersioned::new(address, provider)
+        .VERSION()
+        .call()
+        .await
+        .ok();
+
+    Ok(ContractCompatReport {
+        address,
+        implementation,
+        version,
+        missing_events,
+        missing_functions,
+    })
+}
+
+/// Runs the compatibility check on startup. Incompatibilities are fatal unless `allow_incompatible`
+/// is set, in which case they are only reported.
+pub async fn ensure_compatible<P: Provider>(
+    provider: &P,
+    address: Address,
+    allow_incompatible: bool,
+) -> Result<ContractCompatReport, NodeError> {
+    let report = check_contract(provider, address).await?;
+    if !report.version_matches() {
+        warn!(
+            "OpenRankManager reports version {}, this node was built for version {}",
+            report.version.as_deref().unwrap_or_default(),
+            BUNDLED_ABI_VERSION
+        );
+    }
+    if report.is_compatible() {
+        info!(
+            "OpenRankManager at {} is compatible with the bundled ABI",
+            address
+        );
+        return Ok(report);
+    }
+    if allow_incompatible {
+        warn!("{}", report);
+        return Ok(report);
+    }
+    Err(NodeError::ConfigError(report.to_string()))
+}
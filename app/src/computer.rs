@@ -1,74 +1,265 @@
+use crate::attestation::Attester;
+use crate::checkpoint::{self, BlockCheckpoint};
 use crate::error::Error as NodeError;
 use crate::sol::OpenRankManager::{
     MetaComputeRequestEvent, MetaComputeResultEvent, OpenRankManagerInstance,
 };
-use alloy::eips::BlockNumberOrTag;
 use alloy::hex::{self, ToHexExt};
-use alloy::primitives::FixedBytes;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{FixedBytes, TxHash, U256};
 use alloy::providers::Provider;
-use alloy::rpc::types::Log;
-use aws_sdk_s3::Client;
-use openrank_common::{JobDescription, JobResult};
-
+use alloy::rpc::types::{Log, TransactionRequest};
+use alloy::signers::SignerSync;
+use openrank_common::algos::{checkpoint::ScoreCheckpoint, ComputeReport};
+use openrank_common::manifest::{ComputeManifest, SignedComputeManifest};
+use openrank_common::params::{
+    self, AlgoParams, ALGO_ID_EIGEN_TRUST, ALGO_ID_NEGATIVE_EIGEN_TRUST, ALGO_ID_SYBIL_RANK,
+};
+use openrank_common::rpc::LogFetcher;
+use openrank_common::score_meta::build_metadata;
+use openrank_common::{format, JobDescription, JobResult};
+
+use crate::health::LoopStatus;
+use crate::jobqueue::JobQueue;
+use crate::jobstate::{JobRecord, JobStage, JobStateDb};
+use crate::lease::{Acquired, JobLeases};
+use crate::maintenance::hash_file;
+use crate::paths::{paths, PathManager};
+use crate::quarantine::Quarantine;
+use crate::trust_cache::{trust_cache, CachedTrust, TrustKey};
+use crate::txmanager::TxManager;
+use crate::txqueue::{TxIntent, TxQueue, TxState};
+use crate::wallet::RoleWallet;
 use crate::{
-    create_csv_and_hash_from_scores, download_meta, download_seed_data_to_file,
-    download_trust_data_to_file, parse_score_entries_from_file, parse_trust_entries_from_file,
-    upload_file_to_s3_streaming, upload_meta,
+    create_csv_and_hash_from_scores, create_metadata_csv_and_hash,
+    create_ranked_csv_and_hash_from_scores, download_meta, download_meta_pair,
+    download_object_as_bytes, download_seed_data_to_file, download_trust_data_to_file,
+    parse_score_entries_with_filter, upload_bytes, upload_file_streaming, upload_meta,
 };
+use openrank_common::blocklist::Blocklist;
+use openrank_common::hooks::WasmHook;
 use openrank_common::merkle::fixed::DenseMerkleTree;
 use openrank_common::merkle::Hash;
 use openrank_common::runner::{self, ComputeRunner};
+use openrank_common::scheme::Precision;
+use openrank_common::storage::{artifact_key, is_valid_key_prefix, StorageBackend};
+use openrank_common::subgraph::{FilterStats, IncludeList, NodeFilter};
 
+use futures_util::stream::FuturesUnordered;
+use futures_util::{StreamExt, TryStreamExt};
 use sha3::Keccak256;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 use std::time::{Duration, Instant};
 use tokio::fs::create_dir_all;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Algorithm ids supported by the computer.
+pub const SUPPORTED_ALGO_IDS: [u32; 3] = [
+    ALGO_ID_EIGEN_TRUST,
+    ALGO_ID_SYBIL_RANK,
+    ALGO_ID_NEGATIVE_EIGEN_TRUST,
+];
 
 struct MetaComputeHandler {
-    s3_client: Client,
-    bucket_name: String,
+    storage: Arc<dyn StorageBackend>,
+    job_description_id: String,
     meta_job: Vec<JobDescription>,
     job_results: Vec<JobResult>,
     commitments: Vec<Hash>,
+    strict_params: bool,
+    /// Timestamp of the request, used to tell fresh artifacts from missing ones
+    requested_at: Option<u64>,
 }
 
 impl MetaComputeHandler {
     async fn new(
-        s3_client: Client,
-        bucket_name: String,
+        storage: Arc<dyn StorageBackend>,
         meta_compute_req: &MetaComputeRequestEvent,
+        strict_params: bool,
+        requested_at: Option<u64>,
     ) -> Result<Self, NodeError> {
-        let meta_job: Vec<JobDescription> = download_meta(
-            &s3_client,
-            &bucket_name,
+        Self::from_job_description_id(
+            storage,
             meta_compute_req.jobDescriptionId.encode_hex(),
+            strict_params,
+            requested_at,
         )
-        .await?;
+        .await
+    }
+
+    async fn from_job_description_id(
+        storage: Arc<dyn StorageBackend>,
+        job_description_id: String,
+        strict_params: bool,
+        requested_at: Option<u64>,
+    ) -> Result<Self, NodeError> {
+        let meta_job: Vec<JobDescription> =
+            download_meta(storage.as_ref(), job_description_id.clone(), requested_at)
+                .await
+                .map_err(NodeError::artifact(format!("meta/{}", job_description_id)))?;
+        // Ids are used as S3 keys and file names, reject anything but 32 byte hashes
+        for job in &meta_job {
+            for id in [&job.trust_id, &job.seed_id] {
+                let valid = hex::decode(id).map_err(|e| {
+                    NodeError::artifact(format!("meta/{}", job_description_id))(
+                        NodeError::HexError(e),
+                    )
+                })?;
+                if valid.len() != 32 {
+                    return Err(NodeError::artifact(format!("meta/{}", job_description_id))(
+                        NodeError::IntegrityError(format!("Invalid artifact id: {}", id)),
+                    ));
+                }
+            }
+            if let Some(key_prefix) = &job.key_prefix {
+                if !is_valid_key_prefix(key_prefix) {
+                    return Err(NodeError::artifact(format!("meta/{}", job_description_id))(
+                        NodeError::IntegrityError(format!("Invalid key prefix: {}", key_prefix)),
+                    ));
+                }
+            }
+        }
 
         Ok(Self {
-            s3_client,
-            bucket_name,
+            storage,
+            job_description_id,
             meta_job,
             job_results: Vec::new(),
             commitments: Vec::new(),
+            strict_params,
+            requested_at,
         })
     }
 
+    /// S3 artifacts the meta job depends on.
+    fn artifacts(&self) -> Vec<String> {
+        let mut artifacts = vec![format!("meta/{}", self.job_description_id)];
+        for job in &self.meta_job {
+            artifacts.push(job.trust_key());
+            artifacts.push(job.seed_key());
+            if let Some(hook_id) = &job.hook_id {
+                artifacts.push(format!("hooks/{}", hook_id));
+            }
+            if let Some(blocklist_id) = &job.blocklist_id {
+                artifacts.push(format!("blocklist/{}", blocklist_id));
+            }
+            if let Some(include_list_id) = &job.include_list_id {
+                artifacts.push(format!("include/{}", include_list_id));
+            }
+        }
+        artifacts
+    }
+
+    /// Restores the results of a previous run from the job record, if the job got past compute
+    /// and its scores are still available: stored once uploaded, else kept locally.
+    fn restore(&mut self, record: &JobRecord) -> bool {
+        if record.stage < JobStage::Computed
+            || record.job_description_id != self.job_description_id
+            || record.job_results.len() != self.meta_job.len()
+        {
+            return false;
+        }
+        let scores_available = record.stage >= JobStage::Uploaded
+            || record.job_results.iter().all(|job_result| {
+                std::path::Path::new(&paths().scores_file(&job_result.scores_id)).is_file()
+                    && job_result.metadata_id.as_ref().is_none_or(|metadata_id| {
+                        std::path::Path::new(&paths().scores_meta_file(metadata_id)).is_file()
+                    })
+            });
+        if !scores_available {
+            return false;
+        }
+        self.job_results = record.job_results.clone();
+        self.commitments = record.commitments.clone();
+        true
+    }
+
+    /// Removes the cached trust and seed files that don't match their id, so that they are
+    /// downloaded again.
+    async fn evict_corrupted_inputs(&self) -> Result<(), NodeError> {
+        for job in &self.meta_job {
+            for (path, id) in [
+                (paths().trust_file(&job.trust_id), &job.trust_id),
+                (paths().seed_file(&job.seed_id), &job.seed_id),
+            ] {
+                let path = PathBuf::from(path);
+                if !path.is_file() {
+                    continue;
+                }
+                let hash_path = path.clone();
+                let hash = tokio::task::spawn_blocking(move || hash_file(&hash_path))
+                    .await
+                    .map_err(|e| NodeError::FileError(format!("Hashing task failed: {}", e)))?
+                    .map_err(|e| NodeError::FileError(format!("Failed to hash file: {}", e)))?;
+                if &hash != id {
+                    warn!("Removing corrupted cached file: {}", path.display());
+                    std::fs::remove_file(&path).map_err(|e| {
+                        NodeError::FileError(format!("Failed to remove corrupted file: {}", e))
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn download_data(&self) -> Result<(), NodeError> {
         // Create directories for data storage
-        create_dir_all(&format!("./trust/")).await.map_err(|e| {
+        create_dir_all(paths().trust_dir()).await.map_err(|e| {
             NodeError::FileError(format!("Failed to create trust directory: {}", e))
         })?;
-        create_dir_all(&format!("./seed/"))
+        create_dir_all(paths().seed_dir())
             .await
             .map_err(|e| NodeError::FileError(format!("Failed to create seed directory: {}", e)))?;
-        create_dir_all("./scores/").await.map_err(|e| {
+        create_dir_all(paths().scores_dir()).await.map_err(|e| {
             NodeError::FileError(format!("Failed to create scores directory: {}", e))
         })?;
+        create_dir_all(paths().scores_meta_dir())
+            .await
+            .map_err(|e| {
+                NodeError::FileError(format!("Failed to create scores metadata directory: {}", e))
+            })?;
+        create_dir_all(paths().manifest_dir()).await.map_err(|e| {
+            NodeError::FileError(format!("Failed to create manifest directory: {}", e))
+        })?;
+        if score_checkpoint_interval().is_some() {
+            create_dir_all(paths().score_checkpoint_dir())
+                .await
+                .map_err(|e| {
+                    NodeError::FileError(format!("Failed to create checkpoint directory: {}", e))
+                })?;
+        }
+        if out_of_core_trust() {
+            create_dir_all(paths().trust_matrix_dir())
+                .await
+                .map_err(|e| {
+                    NodeError::FileError(format!("Failed to create trust matrix directory: {}", e))
+                })?;
+        }
+
+        // Artifacts stored in EigenDA are read through the certificates carried by the job
+        for job in &self.meta_job {
+            let certs = [
+                (job.trust_key(), &job.trust_cert),
+                (job.seed_key(), &job.seed_cert),
+            ];
+            for (key, cert) in certs {
+                let Some(cert) = cert else {
+                    continue;
+                };
+                let cert = hex::decode(cert).map_err(|e| {
+                    NodeError::artifact(format!("meta/{}", self.job_description_id))(
+                        NodeError::HexError(e),
+                    )
+                })?;
+                self.storage.import_certificate(&key, &cert).await?;
+            }
+        }
 
         info!("STAGE 1: Downloading all data files in parallel...");
 
@@ -76,18 +267,19 @@ impl MetaComputeHandler {
             .meta_job
             .iter()
             .map(|compute_req| {
-                let s3_client = self.s3_client.clone();
-                let bucket_name = self.bucket_name.clone();
+                let storage = self.storage.clone();
+                let key_prefix = compute_req.key_prefix.clone();
                 let trust_id = compute_req.trust_id.clone();
                 let seed_id = compute_req.seed_id.clone();
+                let requested_at = self.requested_at;
                 let trust_id_bytes =
                     FixedBytes::<32>::from_slice(hex::decode(trust_id.clone()).unwrap().as_slice());
                 let seed_id_bytes =
                     FixedBytes::<32>::from_slice(hex::decode(seed_id.clone()).unwrap().as_slice());
 
                 tokio::spawn(async move {
-                    let trust_file_path = format!("./trust/{}", trust_id);
-                    let seed_file_path = format!("./seed/{}", seed_id);
+                    let trust_file_path = paths().trust_file(&trust_id);
+                    let seed_file_path = paths().seed_file(&seed_id);
 
                     // Check if trust file already exists
                     let (trust_result, trust_downloaded) =
@@ -101,10 +293,11 @@ impl MetaComputeHandler {
                             info!("Downloading data: TrustId({:#})", trust_id_bytes);
                             (
                                 download_trust_data_to_file(
-                                    &s3_client,
-                                    &bucket_name,
+                                    storage.as_ref(),
+                                    key_prefix.as_deref(),
                                     &trust_id,
                                     &trust_file_path,
+                                    requested_at,
                                 )
                                 .await,
                                 true,
@@ -120,10 +313,11 @@ impl MetaComputeHandler {
                             info!("Downloading data: SeedId({:#})", seed_id);
                             (
                                 download_seed_data_to_file(
-                                    &s3_client,
-                                    &bucket_name,
+                                    storage.as_ref(),
+                                    key_prefix.as_deref(),
                                     &seed_id,
                                     &seed_file_path,
+                                    requested_at,
                                 )
                                 .await,
                                 true,
@@ -136,8 +330,8 @@ impl MetaComputeHandler {
                         seed_result,
                         trust_downloaded,
                         seed_downloaded,
-                        trust_id,
-                        seed_id,
+                        artifact_key(key_prefix.as_deref(), "trust", &trust_id),
+                        artifact_key(key_prefix.as_deref(), "seed", &seed_id),
                     )
                 })
             })
@@ -151,21 +345,11 @@ impl MetaComputeHandler {
         let mut seed_downloads = 0;
 
         for result in download_results {
-            let (trust_result, seed_result, trust_downloaded, seed_downloaded, trust_id, seed_id) =
+            let (trust_result, seed_result, trust_downloaded, seed_downloaded, trust_key, seed_key) =
                 result.map_err(|e| NodeError::TxError(format!("Download task failed: {}", e)))?;
 
-            trust_result.map_err(|e| {
-                NodeError::FileError(format!(
-                    "Failed to download trust data for {}: {}",
-                    trust_id, e
-                ))
-            })?;
-            seed_result.map_err(|e| {
-                NodeError::FileError(format!(
-                    "Failed to download seed data for {}: {}",
-                    seed_id, e
-                ))
-            })?;
+            trust_result.map_err(NodeError::artifact(trust_key))?;
+            seed_result.map_err(NodeError::artifact(seed_key))?;
 
             if trust_downloaded {
                 trust_downloads += 1;
@@ -187,18 +371,35 @@ impl MetaComputeHandler {
     }
 
     async fn perform_compute(&mut self) -> Result<(), NodeError> {
-        info!("STAGE 2: Computing scores and saving to CSV files in parallel...");
+        let concurrency = sub_job_concurrency();
+        info!(
+            "STAGE 2: Computing scores and saving to CSV files, {} sub-jobs at a time...",
+            concurrency
+        );
 
-        for compute_req in &self.meta_job {
-            let job_result = self.compute_single_job(compute_req).await?;
-            self.job_results.push(job_result.0);
-            self.commitments.push(job_result.1);
+        // Sub-jobs are started and their results kept in order. The first failure ends the
+        // stage, the sub-jobs not started yet being skipped.
+        let results: Vec<(JobResult, Hash)> = futures_util::stream::iter(&self.meta_job)
+            .map(|compute_req| self.compute_single_job(compute_req))
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+        for (job_result, commitment) in results {
+            self.job_results.push(job_result);
+            self.commitments.push(commitment);
         }
 
         info!("STAGE 2 complete: All scores computed and saved to CSV files in parallel");
         Ok(())
     }
 
+    /// Downloads a sub-job artifact kept in memory (blocklist, include-list).
+    async fn download_artifact_bytes(&self, key: &str) -> Result<Vec<u8>, NodeError> {
+        download_object_as_bytes(self.storage.as_ref(), key, self.requested_at)
+            .await
+            .map_err(NodeError::artifact(key))
+    }
+
     async fn compute_single_job(
         &self,
         compute_req: &JobDescription,
@@ -216,51 +417,81 @@ impl MetaComputeHandler {
             trust_id_bytes, seed_id_bytes
         );
 
-        let trust_file = File::open(&format!("./trust/{}", trust_id))
-            .map_err(|e| NodeError::FileError(format!("Failed to open trust file: {e:}")))?;
-        let seed_file = File::open(&format!("./seed/{}", seed_id))
-            .map_err(|e| NodeError::FileError(format!("Failed to open seed file: {e:}")))?;
-
-        let trust_entries = parse_trust_entries_from_file(trust_file)?;
-        let seed_entries = parse_score_entries_from_file(seed_file)?;
-
-        let (scores, compute_root) = self.core_compute(compute_req, trust_entries, seed_entries)?;
-
-        // Create CSV file and compute hash
-        let (file_bytes, scores_id) = create_csv_and_hash_from_scores(scores)?;
-
-        // Save CSV to local file
-        let scores_file_path = format!("./scores/{}.csv", hex::encode(&scores_id));
-        let mut scores_file = File::create(&scores_file_path)
-            .map_err(|e| NodeError::FileError(format!("Failed to create scores file: {}", e)))?;
-        scores_file
-            .write_all(&file_bytes)
-            .map_err(|e| NodeError::FileError(format!("Failed to write scores file: {}", e)))?;
-
-        let commitment_bytes = FixedBytes::<32>::from_slice(compute_root.inner());
-        let scores_id_bytes = FixedBytes::<32>::from_slice(scores_id.as_slice());
-        let commitment = hex::encode(compute_root.inner());
-        let scores_id_hex = hex::encode(scores_id.clone());
-        let job_result = JobResult::new(scores_id_hex.clone(), commitment);
-
-        info!(
-            "Core compute completed: ScoresId({:#}), Commitment({:#})",
-            scores_id_bytes, commitment_bytes
-        );
+        // Nodes outside the subgraph are dropped while parsing, before compute
+        let blocklist = match &compute_req.blocklist_id {
+            Some(blocklist_id) => {
+                info!("Downloading blocklist: BlocklistId({})", blocklist_id);
+                let key = format!("blocklist/{}", blocklist_id);
+                let bytes = self.download_artifact_bytes(&key).await?;
+                let blocklist =
+                    Blocklist::from_bytes_with_id(&bytes, blocklist_id).map_err(|e| {
+                        NodeError::artifact(key)(NodeError::IntegrityError(e.to_string()))
+                    })?;
+                Some(blocklist)
+            }
+            None => None,
+        };
+        let include_list = match &compute_req.include_list_id {
+            Some(include_list_id) => {
+                info!(
+                    "Downloading include-list: IncludeListId({})",
+                    include_list_id
+                );
+                let key = format!("include/{}", include_list_id);
+                let bytes = self.download_artifact_bytes(&key).await?;
+                let include_list = IncludeList::from_bytes_with_id(&bytes, include_list_id)
+                    .map_err(|e| {
+                        NodeError::artifact(key)(NodeError::IntegrityError(e.to_string()))
+                    })?;
+                Some(include_list)
+            }
+            None => None,
+        };
+        let hook = match &compute_req.hook_id {
+            Some(hook_id) => {
+                info!("Downloading post-processing hook: HookId({})", hook_id);
+                let hook_bytes = download_object_as_bytes(
+                    self.storage.as_ref(),
+                    &format!("hooks/{}", hook_id),
+                    self.requested_at,
+                )
+                .await
+                .map_err(NodeError::artifact(format!("hooks/{}", hook_id)))?;
+                let hook = WasmHook::from_bytes_with_id(&hook_bytes, hook_id).map_err(|e| {
+                    NodeError::artifact(format!("hooks/{}", hook_id))(
+                        NodeError::ComputeRunnerError(runner::Error::Hook(e)),
+                    )
+                })?;
+                Some(hook)
+            }
+            None => None,
+        };
 
-        Ok((job_result, Hash::from_slice(commitment_bytes.as_slice())))
+        // Parsing and compute are CPU bound, they run on the blocking pool so that the sub-jobs
+        // computed concurrently run in parallel
+        let compute_req = compute_req.clone();
+        let strict_params = self.strict_params;
+        tokio::task::spawn_blocking(move || {
+            let filter = NodeFilter::new(blocklist.as_ref(), include_list.as_ref());
+            compute_sub_job(&compute_req, filter, hook.as_ref(), strict_params)
+        })
+        .await
+        .map_err(|e| NodeError::TxError(format!("Compute task failed: {}", e)))?
     }
 
-    async fn upload_data(&self) -> Result<(), NodeError> {
-        info!("STAGE 3: Uploading all scores files to S3 in parallel...");
+    async fn upload_data(&mut self) -> Result<(), NodeError> {
+        info!("STAGE 3: Uploading all scores files in parallel...");
 
         let upload_tasks: Vec<_> = self
             .job_results
             .iter()
-            .map(|job_result| {
-                let s3_client = self.s3_client.clone();
-                let bucket_name = self.bucket_name.clone();
+            .zip(&self.meta_job)
+            .map(|(job_result, job)| {
+                let storage = self.storage.clone();
                 let scores_id = job_result.scores_id.clone();
+                let scores_key = job.scores_key(&scores_id);
+                let metadata_id = job_result.metadata_id.clone();
+                let metadata_key = metadata_id.as_deref().map(|id| job.scores_meta_key(id));
                 let scores_id_bytes = FixedBytes::<32>::from_slice(
                     hex::decode(scores_id.clone()).unwrap().as_slice(),
                 );
@@ -268,17 +499,29 @@ impl MetaComputeHandler {
                 tokio::spawn(async move {
                     info!("Uploading scores data for ScoresId({:#})", scores_id_bytes);
 
-                    let scores_file_path = format!("./scores/{}.csv", scores_id);
-                    let upload_result = upload_file_to_s3_streaming(
-                        &s3_client,
-                        &bucket_name,
-                        &format!("scores/{}", scores_id),
-                        &scores_file_path,
-                    )
-                    .await
-                    .map_err(|e| {
-                        NodeError::FileError(format!("Failed to upload scores file: {}", e))
-                    });
+                    let scores_file_path = paths().scores_file(&scores_id);
+                    let mut upload_result =
+                        upload_file_streaming(storage.as_ref(), &scores_key, &scores_file_path)
+                            .await
+                            .map_err(|e| {
+                                NodeError::FileError(format!("Failed to upload scores file: {}", e))
+                            });
+                    if let (Ok(()), Some(metadata_id), Some(metadata_key)) =
+                        (&upload_result, metadata_id, metadata_key)
+                    {
+                        upload_result = upload_file_streaming(
+                            storage.as_ref(),
+                            &metadata_key,
+                            &paths().scores_meta_file(&metadata_id),
+                        )
+                        .await
+                        .map_err(|e| {
+                            NodeError::FileError(format!(
+                                "Failed to upload scores metadata file: {}",
+                                e
+                            ))
+                        });
+                    }
 
                     if upload_result.is_ok() {
                         info!("Upload complete for ScoresId({:#})", scores_id_bytes);
@@ -301,110 +544,646 @@ impl MetaComputeHandler {
             })?;
         }
 
-        info!("STAGE 3 complete: All scores files uploaded to S3 in parallel");
+        // Scores stored in EigenDA are referenced by certificate in the results
+        for (job_result, job) in self.job_results.iter_mut().zip(&self.meta_job) {
+            job_result.scores_cert = self
+                .storage
+                .certificate(&job.scores_key(&job_result.scores_id))
+                .await?
+                .map(hex::encode);
+        }
+
+        info!("STAGE 3 complete: All scores files uploaded in parallel");
         Ok(())
     }
 
     async fn create_commitment_and_post_onchain<PH: Provider>(
         &self,
         contract: &OpenRankManagerInstance<PH>,
+        wallet: &RoleWallet,
+        tx_manager: &TxManager,
+        attester: Option<&Attester>,
+        queue: &TxQueue,
         compute_id: alloy::primitives::Uint<256, 4>,
-    ) -> Result<(), NodeError> {
-        let commitment_tree = DenseMerkleTree::<Keccak256>::new(self.commitments.clone())
-            .map_err(|e| NodeError::ComputeRunnerError(runner::Error::Merkle(e)))?;
-        let meta_commitment = commitment_tree
-            .root()
-            .map_err(|e| NodeError::ComputeRunnerError(runner::Error::Merkle(e)))?;
+    ) -> Result<(String, String, Option<TxHash>), NodeError> {
+        wallet.check_spend_allowed()?;
+
+        let meta_commitment = self.meta_commitment()?;
 
-        let meta_id =
-            upload_meta(&self.s3_client, &self.bucket_name, self.job_results.clone()).await?;
+        let meta_id = upload_meta(self.storage.as_ref(), self.job_results.clone()).await?;
+        // Attested before the submission, so the attestation exists once the result is on-chain
+        if let Some(attester) = attester {
+            attester
+                .attest_results(self.storage.as_ref(), &meta_id, meta_commitment.inner())
+                .await?;
+        }
 
         let meta_commitment_bytes = FixedBytes::from_slice(meta_commitment.inner());
         let meta_id_bytes = FixedBytes::from_slice(
-            hex::decode(meta_id)
-                .map_err(|e| NodeError::HexError(e))?
+            hex::decode(meta_id.clone())
+                .map_err(NodeError::HexError)?
                 .as_slice(),
         );
 
-        info!("Posting commitment on-chain. Calling: 'submitMetaComputeResult'");
-        let res = contract
+        // Persist the intent first, so the submission survives a restart
+        let calldata = contract
             .submitMetaComputeResult(compute_id, meta_commitment_bytes, meta_id_bytes)
-            .send()
-            .await
-            .map_err(|e| NodeError::TxError(format!("{e:}")))?;
-        let tx_hash = *res.tx_hash();
-        info!(
-            "'submitMetaComputeResult' submitted: Tx Hash({:#})",
-            tx_hash
+            .calldata()
+            .clone();
+        let mut intent = TxIntent::new(
+            compute_id,
+            self.job_description_id.clone(),
+            meta_id_bytes,
+            meta_commitment_bytes,
+            calldata,
         );
+        queue.put(&intent)?;
+        let tx_hash = submit_intent(contract, wallet, tx_manager, queue, &mut intent).await?;
 
-        Ok(())
+        Ok((meta_id, hex::encode(meta_commitment.inner()), tx_hash))
+    }
+
+    /// Root of the tree of the sub-job commitments.
+    fn meta_commitment(&self) -> Result<Hash, NodeError> {
+        let commitment_tree = DenseMerkleTree::<Keccak256>::new(self.commitments.clone())
+            .map_err(|e| NodeError::ComputeRunnerError(runner::Error::Merkle(e)))?;
+        commitment_tree
+            .root()
+            .map_err(|e| NodeError::ComputeRunnerError(runner::Error::Merkle(e)))
     }
 
-    fn core_compute(
+    /// Uploads the signed manifest of the compute id, saving a local copy for the proof server.
+    async fn upload_manifest(
         &self,
-        compute_req: &JobDescription,
-        trust_entries: Vec<openrank_common::TrustEntry>,
-        seed_entries: Vec<openrank_common::ScoreEntry>,
-    ) -> Result<(Vec<openrank_common::ScoreEntry>, Hash), NodeError> {
-        let mut runner = ComputeRunner::new();
-        runner
-            .update_trust_map(trust_entries.to_vec())
-            .map_err(NodeError::ComputeRunnerError)?;
-        runner
-            .update_seed_map(seed_entries.to_vec())
-            .map_err(NodeError::ComputeRunnerError)?;
+        wallet: &RoleWallet,
+        state: &PathManager,
+        compute_id: alloy::primitives::Uint<256, 4>,
+        results_id: String,
+        meta_commitment: String,
+        tx_hash: Option<TxHash>,
+    ) -> Result<(), NodeError> {
+        let mut manifest = ComputeManifest::new(
+            compute_id.to_string(),
+            wallet.address(),
+            self.job_description_id.clone(),
+            results_id,
+            meta_commitment,
+            &self.meta_job,
+            &self.job_results,
+        );
+        if let Some(tx_hash) = tx_hash {
+            manifest = manifest.with_receipt(tx_hash);
+        }
+        upload_signed_manifest(self.storage.as_ref(), wallet, state, manifest).await
+    }
+}
 
-        // Check algo_id and call appropriate algorithm
-        match compute_req.algo_id {
-            1 => {
-                // EigenTrust algorithm
-                let alpha = compute_req.params.get("alpha").and_then(|s| s.parse().ok());
-                let delta = compute_req.params.get("delta").and_then(|s| s.parse().ok());
-                runner
-                    .compute_et(alpha, delta)
-                    .map_err(NodeError::ComputeRunnerError)?;
-            }
-            2 => {
-                // SybilRank algorithm
-                let walk_length = compute_req
-                    .params
-                    .get("walk_length")
-                    .and_then(|s| s.parse().ok());
-                runner
-                    .compute_sr(walk_length)
-                    .map_err(NodeError::ComputeRunnerError)?;
-            }
-            _ => {
-                return Err(NodeError::ComputeRunnerError(
-                    openrank_common::runner::Error::Misc(format!(
-                        "Unsupported algorithm ID: {}",
-                        compute_req.algo_id
-                    )),
-                ));
+/// Parses the trust file into a trust map of the precision, dropping the nodes outside the
+/// subgraph. Negative entries are kept as distrust with `negative_trust`, and rejected
+/// otherwise.
+fn build_trust_map(
+    trust_id: &str,
+    trust_key: &str,
+    filter: &NodeFilter,
+    negative_trust: bool,
+    precision: Precision,
+) -> Result<(ComputeRunner, FilterStats), NodeError> {
+    let mut filter_stats = FilterStats::default();
+    let trust_file_path = paths().trust_file(trust_id);
+    let trust_file = File::open(&trust_file_path)
+        .map_err(|e| NodeError::FileError(format!("Failed to open trust file: {e:}")))?;
+
+    // The trust entries are streamed into the trust map rather than collected first.
+    // Malformed files are removed so that they are downloaded again on the next attempt
+    let mut runner = ComputeRunner::new(precision);
+    // Distrust is only held in memory
+    if negative_trust {
+        runner = runner.with_negative_trust();
+    } else if out_of_core_trust() {
+        runner = runner.with_disk_trust(paths().trust_matrix_dir());
+    }
+    let mut read_error = None;
+    let trust_entries = match format::trust_entries(trust_file, precision) {
+        Ok(entries) => entries,
+        Err(e) => {
+            let _ = std::fs::remove_file(&trust_file_path);
+            return Err(NodeError::artifact(trust_key)(NodeError::CsvError(e)));
+        }
+    };
+    let trust_entries = trust_entries
+        .map_while(|entry| entry.map_err(|e| read_error = Some(e)).ok())
+        .filter(|entry| filter.keep_trust(entry, &mut filter_stats));
+    let update_result = runner.update_trust_map(trust_entries);
+    if let Some(e) = read_error {
+        let _ = std::fs::remove_file(&trust_file_path);
+        return Err(NodeError::artifact(trust_key)(NodeError::CsvError(e)));
+    }
+    update_result.map_err(NodeError::ComputeRunnerError)?;
+    Ok((runner, filter_stats))
+}
+
+/// Parses the trust and seed files of a sub-job, computes its scores and saves them to a CSV
+/// file, returning the result and the commitment of the sub-job.
+fn compute_sub_job(
+    compute_req: &JobDescription,
+    filter: NodeFilter,
+    hook: Option<&WasmHook>,
+    strict_params: bool,
+) -> Result<(JobResult, Hash), NodeError> {
+    let seed_id = &compute_req.seed_id;
+    let seed_file_path = paths().seed_file(seed_id);
+    let seed_file = File::open(&seed_file_path)
+        .map_err(|e| NodeError::FileError(format!("Failed to open seed file: {e:}")))?;
+
+    // Check algo_id and parse the params, the trust and seed values being parsed with the
+    // precision of the job
+    let params = AlgoParams::parse(compute_req.algo_id, &compute_req.params, strict_params)
+        .map_err(NodeError::ParamsError)?;
+    let precision = params.precision();
+
+    // Sub-jobs sharing their trust map with a previous one only replace its seed
+    let key = TrustKey::of(compute_req, precision);
+    let (runner, mut filter_stats) = match trust_cache().get(&key) {
+        Some(trust) => {
+            info!("Reusing trust map: TrustId({})", key.trust_id);
+            (trust.runner.clone(), trust.filter_stats)
+        }
+        None => {
+            let (runner, filter_stats) = build_trust_map(
+                &key.trust_id,
+                &compute_req.trust_key(),
+                &filter,
+                key.negative_trust,
+                precision,
+            )?;
+            if trust_cache().is_enabled() {
+                let trust = CachedTrust {
+                    runner: runner.clone(),
+                    filter_stats,
+                };
+                trust_cache().insert(key, Arc::new(trust));
             }
+            (runner, filter_stats)
         }
+    };
+    let seed_entries =
+        parse_score_entries_with_filter(seed_file, &filter, &mut filter_stats, precision).map_err(
+            |e| {
+                let _ = std::fs::remove_file(&seed_file_path);
+                NodeError::artifact(compute_req.seed_key())(e)
+            },
+        )?;
+    if !filter.is_noop() {
+        info!(
+            "Subgraph: kept {} edges and {} seeds, dropped {} edges and {} seeds",
+            filter_stats.kept_edges,
+            filter_stats.kept_seeds,
+            filter_stats.dropped_edges,
+            filter_stats.dropped_seeds
+        );
+    }
 
-        let scores = runner
-            .get_compute_scores()
-            .map_err(NodeError::ComputeRunnerError)?;
+    let (scores, compute_root, report) =
+        core_compute(compute_req, &params, runner, &seed_entries, hook)?;
+
+    // The extended artifact is a sidecar of the scores, left out of the commitment
+    let metadata_id = if params.metadata() {
+        let seed_ids: HashSet<String> = seed_entries.iter().map(|s| s.id().clone()).collect();
+        let metadata = build_metadata(&scores, &seed_ids, params.ranking());
+        let (metadata_bytes, metadata_id) = create_metadata_csv_and_hash(&metadata, precision)?;
+        let metadata_id = hex::encode(metadata_id);
+        std::fs::write(paths().scores_meta_file(&metadata_id), metadata_bytes).map_err(|e| {
+            NodeError::FileError(format!("Failed to write scores metadata file: {}", e))
+        })?;
+        Some(metadata_id)
+    } else {
+        None
+    };
+
+    // Create CSV file and compute hash
+    let (file_bytes, scores_id) = match params.ranking() {
+        Some(mode) => create_ranked_csv_and_hash_from_scores(&scores, mode, precision)?,
+        None => create_csv_and_hash_from_scores(scores, precision)?,
+    };
+
+    // Save CSV to local file
+    let scores_file_path = paths().scores_file(&hex::encode(&scores_id));
+    let mut scores_file = File::create(&scores_file_path)
+        .map_err(|e| NodeError::FileError(format!("Failed to create scores file: {}", e)))?;
+    scores_file
+        .write_all(&file_bytes)
+        .map_err(|e| NodeError::FileError(format!("Failed to write scores file: {}", e)))?;
+
+    let commitment_bytes = FixedBytes::<32>::from_slice(compute_root.inner());
+    let scores_id_bytes = FixedBytes::<32>::from_slice(scores_id.as_slice());
+    let commitment = hex::encode(compute_root.inner());
+    let scores_id_hex = hex::encode(scores_id.clone());
+    let job_result = JobResult::new(scores_id_hex.clone(), commitment)
+        .with_hook_id(compute_req.hook_id.clone())
+        .with_metadata_id(metadata_id)
+        .with_scheme(params.scheme().expect("Checked when computed"))
+        .with_report(report);
+
+    info!(
+        "Core compute completed: ScoresId({:#}), Commitment({:#})",
+        scores_id_bytes, commitment_bytes
+    );
+
+    Ok((job_result, Hash::from_slice(commitment_bytes.as_slice())))
+}
+
+fn core_compute(
+    compute_req: &JobDescription,
+    params: &AlgoParams,
+    mut runner: ComputeRunner,
+    seed_entries: &[openrank_common::ScoreEntry],
+    hook: Option<&WasmHook>,
+) -> Result<
+    (
+        Vec<openrank_common::ScoreEntry>,
+        Hash,
+        Option<ComputeReport>,
+    ),
+    NodeError,
+> {
+    runner
+        .replace_seed_map(seed_entries.to_vec())
+        .map_err(NodeError::ComputeRunnerError)?;
+    if let Some(interval) = score_checkpoint_interval() {
+        let checkpoint =
+            ScoreCheckpoint::new(paths().score_checkpoint_dir(), compute_req, interval);
+        runner = runner.with_checkpoint(checkpoint);
+    }
+
+    let scheme = params
+        .scheme()
+        .map_err(|e| NodeError::ParamsError(params::Error::Hash(e)))?;
+    runner
+        .compute(params)
+        .map_err(NodeError::ComputeRunnerError)?;
+
+    if let Some(hook) = hook {
         runner
-            .create_compute_tree()
-            .map_err(NodeError::ComputeRunnerError)?;
-        let compute_root = runner
-            .get_root_hash()
+            .apply_hook(hook)
             .map_err(NodeError::ComputeRunnerError)?;
+    }
+    if params.ranking().is_some() {
+        runner.sort_by_rank();
+    }
+
+    let scores = runner
+        .get_compute_scores()
+        .map_err(NodeError::ComputeRunnerError)?;
+    runner
+        .create_compute_tree(scheme)
+        .map_err(NodeError::ComputeRunnerError)?;
+    let compute_root = runner
+        .get_root_hash()
+        .map_err(NodeError::ComputeRunnerError)?;
+
+    Ok((scores, compute_root, runner.report().clone()))
+}
 
-        Ok((scores, compute_root))
+/// Iterations between two checkpoints of the EigenTrust scores, read from
+/// `SCORE_CHECKPOINT_INTERVAL`. Unset or 0 disables the checkpoints.
+fn score_checkpoint_interval() -> Option<u32> {
+    std::env::var("SCORE_CHECKPOINT_INTERVAL")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+}
+
+/// Whether the trust matrices are kept on disk rather than in memory, read from
+/// `OUT_OF_CORE_TRUST`.
+fn out_of_core_trust() -> bool {
+    std::env::var("OUT_OF_CORE_TRUST")
+        .map(|v| v == "true")
+        .unwrap_or(false)
+}
+
+/// Number of sub-jobs of a meta job computed at the same time, read from
+/// `SUB_JOB_CONCURRENCY` and defaulting to the number of CPUs.
+fn sub_job_concurrency() -> usize {
+    std::env::var("SUB_JOB_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+}
+
+/// Recomputes every sub-job of the meta job without posting or uploading anything,
+/// returning the sub-job results and the meta commitment.
+pub(crate) async fn recompute_meta_job(
+    storage: Arc<dyn StorageBackend>,
+    job_description_id: String,
+    strict_params: bool,
+    published_at: Option<u64>,
+) -> Result<(Vec<JobResult>, Hash), NodeError> {
+    let mut handler = MetaComputeHandler::from_job_description_id(
+        storage,
+        job_description_id,
+        strict_params,
+        published_at,
+    )
+    .await?;
+    handler.download_data().await?;
+    handler.perform_compute().await?;
+    let meta_commitment = handler.meta_commitment()?;
+    Ok((handler.job_results, meta_commitment))
+}
+
+/// Signs the manifest and uploads it, keeping a local copy for the score-proof server in the
+/// state directory of the manager.
+async fn upload_signed_manifest(
+    storage: &dyn StorageBackend,
+    wallet: &RoleWallet,
+    state: &PathManager,
+    manifest: ComputeManifest,
+) -> Result<(), NodeError> {
+    let compute_id = manifest.compute_id.clone();
+    let manifest_bytes = manifest.signing_bytes().map_err(NodeError::SerdeError)?;
+    let signature = wallet
+        .signer()
+        .sign_message_sync(&manifest_bytes)
+        .map_err(|e| NodeError::TxError(format!("Failed to sign manifest: {}", e)))?;
+    let signed_manifest = SignedComputeManifest::new(manifest, signature);
+    let bytes = serde_json::to_vec(&signed_manifest).map_err(NodeError::SerdeError)?;
+
+    create_dir_all(state.manifest_dir())
+        .await
+        .map_err(|e| NodeError::FileError(format!("Failed to create manifest dir: {}", e)))?;
+    std::fs::write(state.manifest_file(&compute_id), &bytes)
+        .map_err(|e| NodeError::FileError(format!("Failed to write manifest file: {}", e)))?;
+    upload_bytes(storage, &format!("manifest/{}", compute_id), &bytes).await?;
+    info!("Manifest uploaded: ComputeId({})", compute_id);
+
+    Ok(())
+}
+
+/// Submits the result transaction of a queued intent, at least once.
+/// Before sending, the manager contract is checked for an existing result, so a restart
+/// never submits the same result twice once it is on-chain.
+/// Returns the hash of the transaction, if known.
+async fn submit_intent<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    queue: &TxQueue,
+    intent: &mut TxIntent,
+) -> Result<Option<TxHash>, NodeError> {
+    if intent.state == TxState::Confirmed {
+        return Ok(intent.tx_hash);
     }
+
+    // A transaction was sent before the restart, check whether it was included
+    if let (TxState::Submitted, Some(tx_hash)) = (intent.state, intent.tx_hash) {
+        let receipt = contract
+            .provider()
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|e| NodeError::TxError(format!("Failed to get receipt: {e:}")))?;
+        if let Some(receipt) = receipt.filter(|r| r.status()) {
+            let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+            wallet.record_spend(gas_cost);
+            intent.state = TxState::Confirmed;
+            queue.put(intent)?;
+            info!(
+                "'submitMetaComputeResult' already confirmed: Tx Hash({:#})",
+                tx_hash
+            );
+            return Ok(Some(tx_hash));
+        }
+    }
+
+    let existing = contract
+        .metaComputeResults(intent.compute_id)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read result: {e:}")))?;
+    if existing.computeId != U256::ZERO {
+        if existing.computer != wallet.address()
+            || existing.metaCommitment != intent.meta_commitment
+        {
+            queue.remove(intent.compute_id)?;
+            return Err(NodeError::TxError(format!(
+                "Result for compute {} already submitted by {}",
+                intent.compute_id, existing.computer
+            )));
+        }
+        intent.state = TxState::Confirmed;
+        queue.put(intent)?;
+        info!("Result already on-chain: ComputeId({})", intent.compute_id);
+        return Ok(intent.tx_hash);
+    }
+
+    wallet.check_spend_allowed()?;
+    intent.attempts += 1;
+    queue.put(intent)?;
+
+    info!(
+        "Posting commitment on-chain. Calling: 'submitMetaComputeResult' (attempt {})",
+        intent.attempts
+    );
+    let tx = TransactionRequest::default()
+        .with_to(*contract.address())
+        .with_input(intent.calldata.clone());
+    // The hash of every transaction sent, replacements included, is persisted, so a restart
+    // finds the receipt of the last one
+    let receipt = tx_manager
+        .send(contract.provider(), wallet.address(), tx, |tx_hash| {
+            intent.state = TxState::Submitted;
+            intent.tx_hash = Some(tx_hash);
+            info!(
+                "'submitMetaComputeResult' submitted: Tx Hash({:#})",
+                tx_hash
+            );
+            queue.put(intent)
+        })
+        .await?;
+    let tx_hash = receipt.transaction_hash;
+    intent.tx_hash = Some(tx_hash);
+    let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+    wallet.record_spend(gas_cost);
+    if let Err(e) = wallet.refresh_balance(contract.provider()).await {
+        error!("Failed to refresh wallet balance: {}", e);
+    }
+    if !receipt.status() {
+        return Err(NodeError::TxError(format!(
+            "'submitMetaComputeResult' reverted: Tx Hash({:#})",
+            tx_hash
+        )));
+    }
+    intent.state = TxState::Confirmed;
+    queue.put(intent)?;
+    info!(
+        "'submitMetaComputeResult' confirmed: Tx Hash({:#}), GasCost({} wei)",
+        tx_hash, gas_cost
+    );
+
+    Ok(Some(tx_hash))
+}
+
+/// Finishes an intent left over from a previous run: submits the result if needed and
+/// uploads the manifest, rebuilt from the job description and results meta.
+#[allow(clippy::too_many_arguments)]
+async fn finish_intent<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    storage: &dyn StorageBackend,
+    state: &PathManager,
+    queue: &TxQueue,
+    job_state: &JobStateDb,
+    mut intent: TxIntent,
+) -> Result<(), NodeError> {
+    let tx_hash = submit_intent(contract, wallet, tx_manager, queue, &mut intent).await?;
+
+    let results_id = hex::encode(intent.results_id);
+    let (meta_job, job_results): (Vec<JobDescription>, Vec<JobResult>) =
+        download_meta_pair(storage, &intent.job_description_id, &results_id, None).await?;
+    let mut manifest = ComputeManifest::new(
+        intent.compute_id.to_string(),
+        wallet.address(),
+        intent.job_description_id.clone(),
+        results_id,
+        hex::encode(intent.meta_commitment),
+        &meta_job,
+        &job_results,
+    );
+    if let Some(tx_hash) = tx_hash {
+        manifest = manifest.with_receipt(tx_hash);
+    }
+    upload_signed_manifest(storage, wallet, state, manifest).await?;
+    queue.remove(intent.compute_id)?;
+    job_state.set_stage(
+        intent.compute_id,
+        &intent.job_description_id,
+        JobStage::Done,
+    )
+}
+
+/// Drains the outbound transaction queue, finishing submissions interrupted by a restart.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn drain_tx_queue<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    storage: &dyn StorageBackend,
+    state: &PathManager,
+    queue: &TxQueue,
+    job_state: &JobStateDb,
+) -> Result<(), NodeError> {
+    let intents = queue.pending()?;
+    if !intents.is_empty() {
+        info!("Draining {} queued result submissions...", intents.len());
+    }
+    for intent in intents {
+        let compute_id = intent.compute_id;
+        if let Err(e) = finish_intent(
+            contract, wallet, tx_manager, storage, state, queue, job_state, intent,
+        )
+        .await
+        {
+            error!(
+                "Failed to finish queued submission: ComputeId({}), Error({})",
+                compute_id, e
+            );
+        }
+    }
+    Ok(())
 }
 
+/// Pre-warms the inputs of the jobs a previous run left before compute: their cached trust and
+/// seed files are verified and the missing ones fetched, so the jobs resume faster once their
+/// request is handled again. Failures are only logged, the jobs being handled as usual.
+async fn prewarm_unfinished_jobs(
+    storage: &Arc<dyn StorageBackend>,
+    job_state: &JobStateDb,
+    strict_params: bool,
+    shutdown: &CancellationToken,
+) -> Result<(), NodeError> {
+    let records: Vec<JobRecord> = job_state
+        .all()?
+        .into_iter()
+        .filter(|record| record.stage < JobStage::Submitted)
+        .collect();
+    for record in records {
+        if shutdown.is_cancelled() {
+            break;
+        }
+        let mut handler = match MetaComputeHandler::from_job_description_id(
+            storage.clone(),
+            record.job_description_id.clone(),
+            strict_params,
+            None,
+        )
+        .await
+        {
+            Ok(handler) => handler,
+            Err(e) => {
+                warn!(
+                    "Failed to pre-warm job: ComputeId({}), Error({})",
+                    record.compute_id, e
+                );
+                continue;
+            }
+        };
+        // Jobs past compute only need their scores, which are already available
+        if handler.restore(&record) {
+            continue;
+        }
+        info!(
+            "Pre-warming inputs of unfinished job: ComputeId({})",
+            record.compute_id
+        );
+        let res = match handler.evict_corrupted_inputs().await {
+            Ok(()) => handler.download_data().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = res {
+            warn!(
+                "Failed to pre-warm job: ComputeId({}), Error({})",
+                record.compute_id, e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Unix timestamp (seconds) of the block of a log. Most providers leave `block_timestamp`
+/// unset in logs, so it is read from the block header then.
+async fn log_timestamp<P: Provider>(provider: &P, log: &Log) -> Option<u64> {
+    if log.block_timestamp.is_some() {
+        return log.block_timestamp;
+    }
+    let block_number = log.block_number?;
+    match provider.get_block_by_number(block_number.into()).await {
+        Ok(block) => block.map(|block| block.header.timestamp),
+        Err(e) => {
+            warn!("Failed to get block {}: {}", block_number, e);
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn handle_meta_compute_request<PH: Provider>(
     contract: &OpenRankManagerInstance<PH>,
-    s3_client: Client,
-    bucket_name: String,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    attester: Option<&Attester>,
+    queue: &TxQueue,
+    job_state: &JobStateDb,
+    storage: Arc<dyn StorageBackend>,
+    state: &PathManager,
     meta_compute_req: MetaComputeRequestEvent,
     log: Log,
+    strict_params: bool,
+    quarantine: &Mutex<Quarantine>,
 ) -> Result<(), NodeError> {
     let start = Instant::now();
 
@@ -414,13 +1193,104 @@ async fn handle_meta_compute_request<PH: Provider>(
     );
     debug!("Log: {:?}", log);
 
-    let mut handler = MetaComputeHandler::new(s3_client, bucket_name, &meta_compute_req).await?;
-    handler.download_data().await?;
-    handler.perform_compute().await?;
-    handler.upload_data().await?;
+    let compute_id = meta_compute_req.computeId.to_string();
+    let meta_artifact = format!("meta/{}", meta_compute_req.jobDescriptionId.encode_hex());
+    if let Some(artifact) = quarantine
+        .lock()
+        .unwrap()
+        .check([meta_artifact.as_str()], &compute_id)
+    {
+        return Err(NodeError::Quarantined(artifact));
+    }
+
+    let mut handler = MetaComputeHandler::new(
+        storage,
+        &meta_compute_req,
+        strict_params,
+        log_timestamp(contract.provider(), &log).await,
+    )
+    .await?;
+    let artifacts = handler.artifacts();
+    if let Some(artifact) = quarantine
+        .lock()
+        .unwrap()
+        .check(artifacts.iter().map(String::as_str), &compute_id)
+    {
+        return Err(NodeError::Quarantined(artifact));
+    }
+
+    // Resume from the stage the job stopped at in a previous run
+    let mut record = job_state
+        .get(meta_compute_req.computeId)?
+        .filter(|record| record.job_description_id == handler.job_description_id)
+        .unwrap_or_else(|| {
+            JobRecord::new(
+                meta_compute_req.computeId,
+                handler.job_description_id.clone(),
+                JobStage::Received,
+            )
+        });
+    if record.stage == JobStage::Done {
+        info!("Job already done: ComputeId({})", compute_id);
+        return Ok(());
+    }
+    if handler.restore(&record) {
+        info!(
+            "Resuming job after stage {:?}: ComputeId({})",
+            record.stage, compute_id
+        );
+    } else {
+        // Recorded before the download, so an interrupted download is pre-warmed on restart
+        if record.stage == JobStage::Received {
+            job_state.put(&mut record)?;
+        }
+        handler.download_data().await?;
+        record.stage = JobStage::Downloaded;
+        job_state.put(&mut record)?;
+        handler.perform_compute().await?;
+        record.stage = JobStage::Computed;
+        record.job_results = handler.job_results.clone();
+        record.commitments = handler.commitments.clone();
+        job_state.put(&mut record)?;
+    }
+    if record.stage < JobStage::Uploaded {
+        handler.upload_data().await?;
+        record.stage = JobStage::Uploaded;
+        record.job_results = handler.job_results.clone();
+        job_state.put(&mut record)?;
+    }
+    let (results_id, meta_commitment, tx_hash) = handler
+        .create_commitment_and_post_onchain(
+            contract,
+            wallet,
+            tx_manager,
+            attester,
+            queue,
+            meta_compute_req.computeId,
+        )
+        .await?;
+    record.stage = JobStage::Submitted;
+    record.results_id = Some(results_id.clone());
+    record.meta_commitment = Some(meta_commitment.clone());
+    record.tx_hash = tx_hash;
+    job_state.put(&mut record)?;
     handler
-        .create_commitment_and_post_onchain(contract, meta_compute_req.computeId)
+        .upload_manifest(
+            wallet,
+            state,
+            meta_compute_req.computeId,
+            results_id,
+            meta_commitment,
+            tx_hash,
+        )
         .await?;
+    queue.remove(meta_compute_req.computeId)?;
+    record.stage = JobStage::Done;
+    job_state.put(&mut record)?;
+    quarantine
+        .lock()
+        .unwrap()
+        .record_success(artifacts.iter().map(String::as_str));
 
     let elapsed = start.elapsed();
     info!("Total compute time: {:?}", elapsed);
@@ -428,43 +1298,283 @@ async fn handle_meta_compute_request<PH: Provider>(
     Ok(())
 }
 
+/// Outcome of the handling of a request.
+enum JobOutcome {
+    /// The job was computed and its result submitted.
+    Completed,
+    /// The job was already done, by this node or another replica.
+    AlreadyDone,
+    /// The job is retried on the next polls: it is handled by another replica, or failed on
+    /// malformed or quarantined artifacts.
+    Retry,
+    /// The job failed and is not retried.
+    Failed,
+}
+
+/// Handles the request, holding its lease while the job runs.
+#[allow(clippy::too_many_arguments)]
+async fn process_request<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    tx_manager: &TxManager,
+    attester: Option<&Attester>,
+    leases: &JobLeases,
+    queue: &TxQueue,
+    job_state: &JobStateDb,
+    storage: &Arc<dyn StorageBackend>,
+    state: &PathManager,
+    meta_compute_req: &MetaComputeRequestEvent,
+    log: &Log,
+    strict_params: bool,
+    quarantine: &Mutex<Quarantine>,
+) -> JobOutcome {
+    let compute_id = meta_compute_req.computeId;
+    // Only one of the replicas sharing the bucket handles the job, the others retry it on the
+    // next polls in case the lease expires before the result is posted
+    match leases.acquire(compute_id).await {
+        Ok(Acquired::Acquired) => {}
+        Ok(Acquired::Held { owner, expires_at }) => {
+            info!(
+                "Job handled by another replica: ComputeId({}), Replica({}), LeaseExpiresAt({})",
+                compute_id, owner, expires_at
+            );
+            return JobOutcome::Retry;
+        }
+        Ok(Acquired::Done { owner }) => {
+            info!(
+                "Job already done by a replica: ComputeId({}), Replica({})",
+                compute_id, owner
+            );
+            return JobOutcome::AlreadyDone;
+        }
+        Err(e) => {
+            error!("Error acquiring the job lease: {}", e);
+            return JobOutcome::Retry;
+        }
+    }
+
+    let handling = handle_meta_compute_request(
+        contract,
+        wallet,
+        tx_manager,
+        attester,
+        queue,
+        job_state,
+        storage.clone(),
+        state,
+        meta_compute_req.clone(),
+        log.clone(),
+        strict_params,
+        quarantine,
+    );
+    tokio::pin!(handling);
+    let res = loop {
+        tokio::select! {
+            res = &mut handling => break res,
+            _ = tokio::time::sleep(leases.renew_interval()) => {
+                if let Err(e) = leases.renew(compute_id).await {
+                    warn!("Error renewing the job lease: {}", e);
+                }
+            }
+        }
+    };
+    let e = match res {
+        Ok(()) => {
+            if let Err(e) = leases.complete(compute_id).await {
+                warn!("Error marking the job lease done: {}", e);
+            }
+            return JobOutcome::Completed;
+        }
+        Err(e) => e,
+    };
+    if let Err(e) = leases.release(compute_id).await {
+        warn!("Error releasing the job lease: {}", e);
+    }
+    match &e {
+        NodeError::Quarantined(artifact) => {
+            warn!(
+                "Skipping meta compute request until the artifact is released: ComputeId({}), Artifact({})",
+                compute_id, artifact
+            );
+        }
+        e => {
+            error!("Error handling meta compute request: {}", e);
+            let Some(artifact) = e.malformed_artifact() else {
+                return JobOutcome::Failed;
+            };
+            quarantine.lock().unwrap().record_failure(
+                artifact,
+                &compute_id.to_string(),
+                &e.to_string(),
+            );
+        }
+    }
+    JobOutcome::Retry
+}
+
+type Request = (MetaComputeRequestEvent, Log);
+
+/// Queues the request, unless its job is done. The requests past the capacity of the queue
+/// wait with the retries.
+fn enqueue(
+    jobs: &mut JobQueue<Request>,
+    retries: &mut HashMap<U256, Request>,
+    pending: &mut HashMap<U256, u64>,
+    finished_jobs: &HashSet<U256>,
+    meta_compute_req: MetaComputeRequestEvent,
+    log: Log,
+) {
+    let compute_id = meta_compute_req.computeId;
+    if finished_jobs.contains(&compute_id) {
+        // A request retried after its result was posted (e.g. by the replica holding its
+        // lease) must not hold the checkpoint back any more
+        pending.remove(&compute_id);
+        return;
+    }
+    if let Some(block) = log.block_number {
+        pending.entry(compute_id).or_insert(block);
+    }
+    if let Err(request) = jobs.push(compute_id, (meta_compute_req, log)) {
+        debug!("Job queue full, retrying later: ComputeId({})", compute_id);
+        retries.insert(compute_id, request);
+    }
+}
+
+/// Frees the worker of a finished job, and keeps the request for a retry if needed.
+/// Returns whether the job was completed.
+fn finish_job(
+    jobs: &mut JobQueue<Request>,
+    retries: &mut HashMap<U256, Request>,
+    pending: &mut HashMap<U256, u64>,
+    finished_jobs: &mut HashSet<U256>,
+    (meta_compute_req, log, outcome): (MetaComputeRequestEvent, Log, JobOutcome),
+) -> bool {
+    let compute_id = meta_compute_req.computeId;
+    jobs.finish(&compute_id);
+    // Only the requests waiting for a retry hold the checkpoint back
+    if !matches!(outcome, JobOutcome::Retry) {
+        pending.remove(&compute_id);
+    }
+    match outcome {
+        JobOutcome::Completed => {
+            finished_jobs.insert(compute_id);
+            return true;
+        }
+        JobOutcome::AlreadyDone => {
+            finished_jobs.insert(compute_id);
+        }
+        JobOutcome::Retry => {
+            finished_jobs.remove(&compute_id);
+            retries.insert(compute_id, (meta_compute_req, log));
+        }
+        JobOutcome::Failed => {
+            finished_jobs.remove(&compute_id);
+        }
+    }
+    false
+}
+
+fn report_jobs(
+    status: &Mutex<LoopStatus>,
+    jobs: &JobQueue<Request>,
+    retries: &HashMap<U256, Request>,
+) {
+    let mut status = status.lock().unwrap();
+    status.queued_jobs = jobs.queued();
+    status.running_jobs = jobs.running().map(ToString::to_string).collect();
+    status.pending_retries = retries.len();
+}
+
+/// Polls the meta compute requests and handles them with a pool of workers (see `JobQueue`),
+/// the events being polled while the jobs run.
+/// Polling starts from the block checkpoint of the previous run, or `block_history` blocks
+/// back on the first start.
+/// The job state, transaction queue, checkpoint and manifests of the contract are kept under
+/// `state`, as compute ids are only unique per contract.
+#[allow(clippy::too_many_arguments)]
 pub async fn run<PH: Provider>(
     contract: OpenRankManagerInstance<PH>,
     provider: PH,
-    s3_client: Client,
-    bucket_name: &str,
+    wallet: RoleWallet,
+    tx_manager: TxManager,
+    storage: Arc<dyn StorageBackend>,
+    state: PathManager,
     block_history: u64,
     log_pull_seconds: u64,
+    strict_params: bool,
+    quarantine: Arc<Mutex<Quarantine>>,
+    status: Arc<Mutex<LoopStatus>>,
+    shutdown: CancellationToken,
 ) -> Result<(), NodeError> {
     let current_block = provider
         .get_block_number()
         .await
         .map_err(|e| NodeError::TxError(format!("Failed to get block number: {}", e)))?;
-    let starting_block = current_block - block_history;
-    // Meta jobs events
-    let meta_compute_result_filter = contract
-        .MetaComputeResultEvent_filter()
-        .from_block(BlockNumberOrTag::Number(starting_block))
-        .to_block(BlockNumberOrTag::Latest)
-        .filter;
-    let meta_compute_request_filter = contract
-        .MetaComputeRequestEvent_filter()
-        .from_block(BlockNumberOrTag::Number(starting_block))
-        .to_block(BlockNumberOrTag::Latest)
-        .filter;
-
-    info!("Pulling historical logs (last {} blocks)...", block_history);
-
-    let result_logs = provider
-        .get_logs(&meta_compute_result_filter)
+    let checkpoint = BlockCheckpoint::new(state.checkpoint_file());
+    let starting_block = match checkpoint.load()? {
+        Some(checkpoint) => checkpoint.block.min(current_block),
+        None => current_block.saturating_sub(block_history),
+    };
+
+    let attester = Attester::from_env()?;
+    let leases = JobLeases::from_env(storage.clone())?;
+    let queue = TxQueue::open(state.tx_queue_dir())?;
+    let job_state = JobStateDb::open(state.job_state_dir())?;
+    drain_tx_queue(
+        &contract,
+        &wallet,
+        &tx_manager,
+        storage.as_ref(),
+        &state,
+        &queue,
+        &job_state,
+    )
+    .await?;
+    prewarm_unfinished_jobs(&storage, &job_state, strict_params, &shutdown).await?;
+
+    // Meta jobs events, pulled in block ranges the provider accepts
+    let meta_compute_result_filter = contract.MetaComputeResultEvent_filter().filter;
+    let meta_compute_request_filter = contract.MetaComputeRequestEvent_filter().filter;
+    let mut log_fetcher = LogFetcher::from_env()
+        .map_err(|e| NodeError::ConfigError(format!("Invalid RPC logs config: {}", e)))?;
+
+    info!(
+        "Pulling historical logs (last {} blocks)...",
+        current_block - starting_block
+    );
+
+    let result_logs = log_fetcher
+        .get_logs(
+            &provider,
+            &meta_compute_result_filter,
+            starting_block,
+            current_block,
+        )
         .await
         .map_err(|e| NodeError::TxError(format!("Failed to get result logs: {}", e)))?;
-    let request_logs = provider
-        .get_logs(&meta_compute_request_filter)
+    let request_logs = log_fetcher
+        .get_logs(
+            &provider,
+            &meta_compute_request_filter,
+            starting_block,
+            current_block,
+        )
         .await
         .map_err(|e| NodeError::TxError(format!("Failed to get request logs: {}", e)))?;
-
-    let mut finished_jobs = HashSet::new();
+    status.lock().unwrap().poll_succeeded(current_block);
+
+    // Jobs done in a previous run are skipped even if their result is older than the history
+    let mut finished_jobs: HashSet<U256> = job_state
+        .all()?
+        .into_iter()
+        .filter(|record| record.stage == JobStage::Done)
+        .map(|record| record.compute_id)
+        .collect();
+    let mut retries: HashMap<U256, Request> = HashMap::new();
+    // Blocks of the requests not done yet, by compute id
+    let mut pending: HashMap<U256, u64> = HashMap::new();
+    let mut jobs = JobQueue::from_env();
+    let mut completed = 0;
     for log in result_logs {
         let res: Log<MetaComputeResultEvent> = log
             .log_decode()
@@ -476,66 +1586,132 @@ pub async fn run<PH: Provider>(
         let res: Log<MetaComputeRequestEvent> = log
             .log_decode()
             .map_err(|e| NodeError::TxError(format!("Failed to decode request log: {}", e)))?;
-        if finished_jobs.contains(&res.data().computeId) {
-            continue;
-        }
-        if let Err(e) = handle_meta_compute_request(
-            &contract,
-            s3_client.clone(),
-            bucket_name.to_string(),
+        enqueue(
+            &mut jobs,
+            &mut retries,
+            &mut pending,
+            &finished_jobs,
             res.data().clone(),
             log,
-        )
-        .await
-        {
-            finished_jobs.remove(&res.data().computeId);
-            error!("Error handling meta compute request: {}", e);
-        } else {
-            finished_jobs.insert(res.data().computeId);
-        }
+        );
     }
 
+    // The jobs run as futures of this task (the compute itself on the blocking pool), driven
+    // along with the polls
+    let (contract, wallet, tx_manager, attester) =
+        (&contract, &wallet, &tx_manager, attester.as_ref());
+    let (leases, queue, job_state, storage, state, quarantine) =
+        (&leases, &queue, &job_state, &storage, &state, &*quarantine);
+    let start_job = move |meta_compute_req: MetaComputeRequestEvent, log: Log| async move {
+        let outcome = process_request(
+            contract,
+            wallet,
+            tx_manager,
+            attester,
+            leases,
+            queue,
+            job_state,
+            storage,
+            state,
+            &meta_compute_req,
+            &log,
+            strict_params,
+            quarantine,
+        )
+        .await;
+        (meta_compute_req, log, outcome)
+    };
+    let mut running = FuturesUnordered::new();
+
     info!("Pulling new events...");
 
     let mut interval = tokio::time::interval(Duration::from_secs(log_pull_seconds));
     let mut latest_processed_block = current_block;
 
     loop {
-        interval.tick().await; // Wait for the next tick
+        // Start the queued jobs on the free workers
+        while let Some((_, (meta_compute_req, log))) = jobs.start_next() {
+            running.push(start_job(meta_compute_req, log));
+        }
+        report_jobs(&status, &jobs, &retries);
+
+        // Wait for the next tick or the end of a job, or stop taking new events on shutdown
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(done) = running.next(), if !running.is_empty() => {
+                if finish_job(&mut jobs, &mut retries, &mut pending, &mut finished_jobs, done) {
+                    completed += 1;
+                }
+                continue;
+            }
+            _ = shutdown.cancelled() => break,
+        }
+
+        // Retry the requests that failed on malformed artifacts, unless they are quarantined
+        let ready: Vec<U256> = {
+            let quarantine = quarantine.lock().unwrap();
+            retries
+                .keys()
+                .filter(|compute_id| quarantine.blocking(&compute_id.to_string()).is_none())
+                .copied()
+                .collect()
+        };
+        for compute_id in ready {
+            let Some((meta_compute_req, log)) = retries.remove(&compute_id) else {
+                continue;
+            };
+            enqueue(
+                &mut jobs,
+                &mut retries,
+                &mut pending,
+                &finished_jobs,
+                meta_compute_req,
+                log,
+            );
+        }
 
         let current_block = match provider.get_block_number().await {
             Ok(block) => block,
             Err(e) => {
                 error!("Error getting current block number: {}", e);
+                status.lock().unwrap().poll_failed(e);
                 continue;
             }
         };
 
-        let meta_compute_result_filter = contract
-            .MetaComputeResultEvent_filter()
-            .from_block(BlockNumberOrTag::Number(latest_processed_block))
-            .to_block(BlockNumberOrTag::Number(current_block))
-            .filter;
-        let meta_compute_request_filter = contract
-            .MetaComputeRequestEvent_filter()
-            .from_block(BlockNumberOrTag::Number(latest_processed_block))
-            .to_block(BlockNumberOrTag::Number(current_block))
-            .filter;
-
-        let result_logs = match provider.get_logs(&meta_compute_result_filter).await {
+        let result_logs = match log_fetcher
+            .get_logs(
+                &provider,
+                &meta_compute_result_filter,
+                latest_processed_block,
+                current_block,
+            )
+            .await
+        {
             Ok(logs) => logs,
             Err(e) => {
                 error!("Error getting result logs: {}", e);
+                status.lock().unwrap().poll_failed(e);
                 continue;
             }
         };
-        let request_logs = match provider.get_logs(&meta_compute_request_filter).await {
+        let request_logs = match log_fetcher
+            .get_logs(
+                &provider,
+                &meta_compute_request_filter,
+                latest_processed_block,
+                current_block,
+            )
+            .await
+        {
             Ok(logs) => logs,
             Err(e) => {
                 error!("Error getting request logs: {}", e);
+                status.lock().unwrap().poll_failed(e);
                 continue;
             }
         };
+        status.lock().unwrap().poll_succeeded(current_block);
 
         for log in result_logs {
             let res: Log<MetaComputeResultEvent> = match log.log_decode() {
@@ -556,25 +1732,54 @@ pub async fn run<PH: Provider>(
                     continue;
                 }
             };
-            if finished_jobs.contains(&res.data().computeId) {
-                continue;
-            }
-            if let Err(e) = handle_meta_compute_request(
-                &contract,
-                s3_client.clone(),
-                bucket_name.to_string(),
+            enqueue(
+                &mut jobs,
+                &mut retries,
+                &mut pending,
+                &finished_jobs,
                 res.data().clone(),
                 log,
-            )
-            .await
-            {
-                finished_jobs.remove(&res.data().computeId);
-                error!("Error handling meta compute request: {}", e);
-            } else {
-                finished_jobs.insert(res.data().computeId);
-            }
+            );
         }
-
         latest_processed_block = current_block;
+        let resume_block = checkpoint::resume_block(latest_processed_block, &pending);
+        if let Err(e) = checkpoint.save(resume_block) {
+            warn!("{}", e);
+        }
+    }
+
+    // The running jobs are seen through, the queued ones are pulled again on the next start
+    info!("Waiting for {} running jobs...", running.len());
+    while let Some(done) = running.next().await {
+        if finish_job(
+            &mut jobs,
+            &mut retries,
+            &mut pending,
+            &mut finished_jobs,
+            done,
+        ) {
+            completed += 1;
+        }
+    }
+    report_jobs(&status, &jobs, &retries);
+    if let Err(e) = checkpoint.save(checkpoint::resume_block(latest_processed_block, &pending)) {
+        warn!("{}", e);
     }
+
+    // Jobs are checkpointed in the job state at every stage, so the unfinished ones resume
+    // where they stopped on the next start
+    let unfinished = job_state
+        .all()?
+        .iter()
+        .filter(|record| record.stage < JobStage::Done)
+        .count();
+    info!(
+        "Computer stopped: {} jobs completed in this run, {} unfinished, {} queued, {} waiting for retry, {} submissions queued",
+        completed,
+        unfinished,
+        jobs.queued(),
+        retries.len(),
+        queue.pending()?.len()
+    );
+    Ok(())
 }
@@ -0,0 +1,452 @@
+use crate::error::Error as NodeError;
+use aws_credential_types::provider::{
+    error::CredentialsError, future, ProvideCredentials, SharedCredentialsProvider,
+};
+use aws_credential_types::Credentials;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+
+/// CID of the parent instance, as seen from inside a Nitro Enclave.
+pub const PARENT_CID: u32 = 3;
+/// Default vsock port the parent-side helper listens on.
+pub const DEFAULT_SECRETS_PORT: u32 = 8000;
+/// Time either side is given to send its message, so that a stuck peer can't hold a
+/// connection (and the enclave waiting on it) forever.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest request or response accepted, a line of JSON.
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+/// Connections served at once by the parent-side helper, further ones waiting to be accepted.
+const MAX_PARENT_CONNECTIONS: usize = 16;
+
+/// Request sent by the enclave to the parent-side helper, one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretRequest {
+    /// Temporary AWS credentials of the parent instance.
+    AwsCredentials,
+    /// A named secret, such as a wallet mnemonic.
+    Secret { name: String },
+}
+
+/// Response of the parent-side helper.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretResponse {
+    AwsCredentials {
+        access_key_id: String,
+        secret_access_key: String,
+        session_token: Option<String>,
+        /// Expiry as a unix timestamp in seconds.
+        expiration: Option<u64>,
+    },
+    Secret {
+        value: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Minimal blocking vsock sockets, on top of libc.
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::time::Duration;
+
+    /// Not exported by every libc flavour (e.g. musl).
+    const AF_VSOCK: libc::c_int = 40;
+
+    fn socket() -> io::Result<OwnedFd> {
+        // SAFETY: plain syscall, the returned descriptor is checked before use
+        let fd = unsafe { libc::socket(AF_VSOCK, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor was just created and is owned by nobody else
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn addr(cid: u32, port: u32) -> libc::sockaddr_vm {
+        // SAFETY: sockaddr_vm is plain old data, all zeroes is a valid value
+        let mut addr: libc::sockaddr_vm = unsafe { std::mem::zeroed() };
+        addr.svm_family = AF_VSOCK as libc::sa_family_t;
+        addr.svm_cid = cid;
+        addr.svm_port = port;
+        addr
+    }
+
+    const ADDR_LEN: libc::socklen_t = std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t;
+
+    pub fn connect(cid: u32, port: u32) -> io::Result<File> {
+        let fd = socket()?;
+        let addr = addr(cid, port);
+        // SAFETY: `addr` is a valid sockaddr_vm of ADDR_LEN bytes
+        let res = unsafe {
+            libc::connect(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_vm as *const libc::sockaddr,
+                ADDR_LEN,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(File::from(fd))
+    }
+
+    /// Sets the read and write timeouts of the stream.
+    pub fn set_timeouts(stream: &File, timeout: Duration) -> io::Result<()> {
+        let timeval = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        for option in [libc::SO_RCVTIMEO, libc::SO_SNDTIMEO] {
+            // SAFETY: `timeval` is a valid timeval, of the given length
+            let res = unsafe {
+                libc::setsockopt(
+                    stream.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    option,
+                    &timeval as *const libc::timeval as *const libc::c_void,
+                    std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+                )
+            };
+            if res < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    pub struct Listener(OwnedFd);
+
+    impl Listener {
+        pub fn bind(port: u32) -> io::Result<Self> {
+            let fd = socket()?;
+            let addr = addr(libc::VMADDR_CID_ANY, port);
+            // SAFETY: `addr` is a valid sockaddr_vm of ADDR_LEN bytes
+            let res = unsafe {
+                libc::bind(
+                    fd.as_raw_fd(),
+                    &addr as *const libc::sockaddr_vm as *const libc::sockaddr,
+                    ADDR_LEN,
+                )
+            };
+            if res < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: plain syscall on a descriptor we own
+            if unsafe { libc::listen(fd.as_raw_fd(), 16) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self(fd))
+        }
+
+        /// Accepts a connection, returning it along with the CID of the peer.
+        pub fn accept(&self) -> io::Result<(File, u32)> {
+            let mut peer = addr(0, 0);
+            let mut len = ADDR_LEN;
+            // SAFETY: `peer` is a valid, writable sockaddr_vm of `len` bytes
+            let fd = unsafe {
+                libc::accept4(
+                    self.0.as_raw_fd(),
+                    &mut peer as *mut libc::sockaddr_vm as *mut libc::sockaddr,
+                    &mut len,
+                    libc::SOCK_CLOEXEC,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the descriptor was just accepted and is owned by nobody else
+            Ok((
+                File::from(unsafe { OwnedFd::from_raw_fd(fd) }),
+                peer.svm_cid,
+            ))
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::fs::File;
+    use std::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "vsock is only supported on linux",
+        )
+    }
+
+    pub fn connect(_cid: u32, _port: u32) -> io::Result<File> {
+        Err(unsupported())
+    }
+
+    pub fn set_timeouts(_stream: &File, _timeout: std::time::Duration) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub struct Listener;
+
+    impl Listener {
+        pub fn bind(_port: u32) -> io::Result<Self> {
+            Err(unsupported())
+        }
+
+        pub fn accept(&self) -> io::Result<(File, u32)> {
+            Err(unsupported())
+        }
+    }
+}
+
+/// Sends a single request over the stream and reads the response.
+fn exchange(stream: File, request: &SecretRequest) -> io::Result<SecretResponse> {
+    sys::set_timeouts(&stream, IO_TIMEOUT)?;
+    let mut writer = stream.try_clone()?;
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+
+    Ok(serde_json::from_str(&read_message(stream)?)?)
+}
+
+/// Reads a line of at most `MAX_MESSAGE_BYTES`.
+fn read_message(stream: File) -> io::Result<String> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_BYTES)).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message truncated or too long",
+        ));
+    }
+    Ok(line)
+}
+
+/// Enclave-side client, fetching secrets from the parent-side helper over vsock.
+#[derive(Debug, Clone)]
+pub struct EnclaveClient {
+    cid: u32,
+    port: u32,
+}
+
+impl EnclaveClient {
+    pub fn new(cid: u32, port: u32) -> Self {
+        Self { cid, port }
+    }
+
+    /// Creates the client from the environment:
+    /// - `ENCLAVE_PARENT_CID`, defaulting to 3,
+    /// - `ENCLAVE_SECRETS_PORT`, defaulting to 8000.
+    pub fn from_env() -> Result<Self, NodeError> {
+        let cid = env_u32("ENCLAVE_PARENT_CID")?.unwrap_or(PARENT_CID);
+        let port = env_u32("ENCLAVE_SECRETS_PORT")?.unwrap_or(DEFAULT_SECRETS_PORT);
+        Ok(Self::new(cid, port))
+    }
+
+    async fn request(&self, request: SecretRequest) -> Result<SecretResponse, NodeError> {
+        let (cid, port) = (self.cid, self.port);
+        let response = tokio::task::spawn_blocking(move || {
+            let stream = sys::connect(cid, port)?;
+            exchange(stream, &request)
+        })
+        .await
+        .map_err(|e| NodeError::EnclaveError(e.to_string()))?
+        .map_err(|e| NodeError::EnclaveError(format!("vsock {}:{}: {}", cid, port, e)))?;
+        if let SecretResponse::Error { message } = response {
+            return Err(NodeError::EnclaveError(message));
+        }
+        Ok(response)
+    }
+
+    /// Fetches a named secret from the parent.
+    pub async fn secret(&self, name: &str) -> Result<String, NodeError> {
+        let request = SecretRequest::Secret {
+            name: name.to_string(),
+        };
+        match self.request(request).await? {
+            SecretResponse::Secret { value } => Ok(value),
+            other => Err(NodeError::EnclaveError(format!(
+                "Unexpected response: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Fetches temporary AWS credentials from the parent.
+    pub async fn aws_credentials(&self) -> Result<Credentials, NodeError> {
+        match self.request(SecretRequest::AwsCredentials).await? {
+            SecretResponse::AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token,
+                expiration,
+            } => Ok(Credentials::new(
+                access_key_id,
+                secret_access_key,
+                session_token,
+                expiration.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                "enclave-vsock",
+            )),
+            _ => Err(NodeError::EnclaveError(
+                "Unexpected response to AWS credentials request".to_string(),
+            )),
+        }
+    }
+}
+
+impl ProvideCredentials for EnclaveClient {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(async move {
+            self.aws_credentials()
+                .await
+                .map_err(CredentialsError::provider_error)
+        })
+    }
+}
+
+fn env_u32(var: &str) -> Result<Option<u32>, NodeError> {
+    match std::env::var(var) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid {}: {}", var, e))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parent-side helper, serving the enclave with the credentials of the parent instance
+/// and with the secrets listed in `ENCLAVE_SECRETS` (comma separated environment variable
+/// names). Secrets that are not listed are never handed out.
+///
+/// Only the enclave of CID `ENCLAVE_CID` (the `--enclave-cid` it was run with) is served, the
+/// connections of other peers being closed without an answer. Each connection is served on its
+/// own thread, at most `MAX_PARENT_CONNECTIONS` at once.
+pub async fn run_parent_helper(port: u32) -> Result<(), NodeError> {
+    let enclave_cid = env_u32("ENCLAVE_CID")?.ok_or_else(|| {
+        NodeError::ConfigError("ENCLAVE_CID must be set to the CID of the enclave".to_string())
+    })?;
+    let allowed: Arc<HashSet<String>> = Arc::new(
+        std::env::var("ENCLAVE_SECRETS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+    );
+    let config = aws_config::load_from_env().await;
+    let credentials = config.credentials_provider();
+    let runtime = tokio::runtime::Handle::current();
+
+    let listener = sys::Listener::bind(port)
+        .map_err(|e| NodeError::EnclaveError(format!("Failed to bind vsock port: {}", e)))?;
+    info!(
+        "Serving enclave {} secrets on vsock port {}",
+        enclave_cid, port
+    );
+
+    let connections = Arc::new(Semaphore::new(MAX_PARENT_CONNECTIONS));
+    tokio::task::spawn_blocking(move || loop {
+        let permit = runtime
+            .block_on(connections.clone().acquire_owned())
+            .expect("the connections semaphore is never closed");
+        let (stream, peer_cid) = match listener.accept() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to accept vsock connection: {}", e);
+                continue;
+            }
+        };
+        if peer_cid != enclave_cid {
+            warn!("Refused vsock connection from CID {}", peer_cid);
+            continue;
+        }
+        let allowed = allowed.clone();
+        let credentials = credentials.clone();
+        let handle = runtime.clone();
+        runtime.spawn_blocking(move || {
+            let _permit = permit;
+            let respond = |request| respond(request, peer_cid, &allowed, credentials, &handle);
+            if let Err(e) = serve_connection(stream, respond) {
+                error!("Failed to serve enclave {}: {}", peer_cid, e);
+            }
+        });
+    })
+    .await
+    .map_err(|e| NodeError::EnclaveError(e.to_string()))
+}
+
+/// Answers a request of the enclave.
+fn respond(
+    request: SecretRequest,
+    peer_cid: u32,
+    allowed: &HashSet<String>,
+    credentials: Option<SharedCredentialsProvider>,
+    runtime: &tokio::runtime::Handle,
+) -> SecretResponse {
+    match request {
+        SecretRequest::AwsCredentials => {
+            let Some(provider) = credentials else {
+                return error_response("No AWS credentials provider configured");
+            };
+            match runtime.block_on(provider.provide_credentials()) {
+                Ok(creds) => SecretResponse::AwsCredentials {
+                    access_key_id: creds.access_key_id().to_string(),
+                    secret_access_key: creds.secret_access_key().to_string(),
+                    session_token: creds.session_token().map(str::to_string),
+                    expiration: creds.expiry().and_then(|t| {
+                        t.duration_since(SystemTime::UNIX_EPOCH)
+                            .ok()
+                            .map(|d| d.as_secs())
+                    }),
+                },
+                Err(e) => error_response(&format!("Failed to load credentials: {}", e)),
+            }
+        }
+        SecretRequest::Secret { name } => {
+            if !allowed.contains(&name) {
+                warn!("Enclave {} requested unlisted secret '{}'", peer_cid, name);
+                return error_response(&format!("Secret '{}' is not available", name));
+            }
+            match std::env::var(&name) {
+                Ok(value) => SecretResponse::Secret { value },
+                Err(_) => error_response(&format!("Secret '{}' is not set", name)),
+            }
+        }
+    }
+}
+
+fn error_response(message: &str) -> SecretResponse {
+    SecretResponse::Error {
+        message: message.to_string(),
+    }
+}
+
+fn serve_connection(
+    stream: File,
+    respond: impl FnOnce(SecretRequest) -> SecretResponse,
+) -> io::Result<()> {
+    sys::set_timeouts(&stream, IO_TIMEOUT)?;
+    let mut writer = stream.try_clone()?;
+    let line = read_message(stream)?;
+    let response = match serde_json::from_str(&line) {
+        Ok(request) => respond(request),
+        Err(e) => error_response(&format!("Invalid request: {}", e)),
+    };
+    let mut bytes = serde_json::to_vec(&response)?;
+    bytes.push(b'\n');
+    writer.write_all(&bytes)
+}
@@ -1,11 +1,14 @@
 use alloy::{
-    hex::FromHexError, signers::local::LocalSignerError, transports::RpcError,
+    hex::FromHexError, primitives::U256, signers::local::LocalSignerError, transports::RpcError,
     transports::TransportError, transports::TransportErrorKind,
 };
-use aws_sdk_s3::{primitives::ByteStreamError, Error as AwsError};
+use aws_sdk_s3::Error as AwsError;
 use csv::Error as CsvError;
+use openrank_common::canonical::Error as CanonicalError;
 use openrank_common::eigenda::EigenDAError;
+use openrank_common::params::Error as ParamsError;
 use openrank_common::runner::Error as ComputeRunnerError;
+use openrank_common::storage::Error as StorageError;
 use serde_json::Error as SerdeError;
 
 #[derive(thiserror::Error, Debug)]
@@ -21,7 +24,7 @@ pub enum Error {
     #[error("Serde error: {0}")]
     SerdeError(SerdeError),
     #[error("Aws error: {0}")]
-    AwsError(AwsError),
+    AwsError(Box<AwsError>),
     #[error("File error: {0}")]
     FileError(String),
     #[error("Csv error: {0}")]
@@ -30,10 +33,81 @@ pub enum Error {
     ComputeRunnerError(ComputeRunnerError),
     #[error("Tx Error: {0}")]
     TxError(String),
-    #[error("ByteStreamError: {0}")]
-    ByteStreamError(ByteStreamError),
+    #[error("Storage error: {0}")]
+    StorageError(StorageError),
     #[error("EigenDA error: {0}")]
     EigenDAError(EigenDAError),
+    #[error("Canonical JSON error: {0}")]
+    CanonicalError(CanonicalError),
+    #[error("Params error: {0}")]
+    ParamsError(ParamsError),
+    #[error("Object not found: {0}")]
+    NotFound(String),
+    #[error("Integrity error: {0}")]
+    IntegrityError(String),
+    #[error("Enclave error: {0}")]
+    EnclaveError(String),
+    #[error("Attestation error: {0}")]
+    AttestationError(String),
+    #[error("Config error: {0}")]
+    ConfigError(String),
+    #[error("TLS error: {0}")]
+    TlsError(String),
+    #[error("Insufficient balance for role '{role}': {balance} wei, required {required} wei")]
+    InsufficientBalance {
+        role: String,
+        balance: U256,
+        required: U256,
+    },
+    #[error("Daily spend limit of {limit} wei reached for role '{role}'")]
+    SpendLimitReached { role: String, limit: U256 },
+    #[error("Job state error: {0}")]
+    JobStateError(sled::Error),
+    #[error("Audit state error: {0}")]
+    AuditStateError(sled::Error),
+    #[error("Artifact quarantined: {0}")]
+    Quarantined(String),
+    #[error("Artifact '{artifact}': {source}")]
+    ArtifactError {
+        artifact: String,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Wraps the error with the S3 artifact (e.g. `trust/<id>`) it occurred on.
+    pub fn artifact(artifact: impl Into<String>) -> impl FnOnce(Error) -> Error {
+        let artifact = artifact.into();
+        move |source| Error::ArtifactError {
+            artifact,
+            source: Box::new(source),
+        }
+    }
+
+    /// Returns the artifact the error occurred on, if it is caused by malformed content.
+    /// Transient errors (network, missing objects) aren't attributed to the artifact.
+    pub fn malformed_artifact(&self) -> Option<&str> {
+        match self {
+            Error::ArtifactError { artifact, source } => match source.as_ref() {
+                Error::IntegrityError(_)
+                | Error::SerdeError(_)
+                | Error::CsvError(_)
+                | Error::HexError(_)
+                | Error::ComputeRunnerError(_) => Some(artifact),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<StorageError> for Error {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NotFound(key) => Error::NotFound(key),
+            err => Error::StorageError(err),
+        }
+    }
 }
 
 impl From<EigenDAError> for Error {
@@ -0,0 +1,137 @@
+use crate::server::{is_compute_id, ManagerScope, ServerError, ServerState};
+use alloy::primitives::{Address, TxHash};
+use axum::{
+    extract::{Path, Query, State},
+    response::Html,
+    routing::get,
+    Json, Router,
+};
+use openrank_common::manifest::{SignedComputeManifest, SubJobManifest};
+use serde::{Deserialize, Serialize};
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+/// The explorer page, a single self-contained HTML file calling the JSON endpoints.
+const INDEX_HTML: &str = include_str!("explorer.html");
+
+/// A compute job, as listed by the explorer.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub compute_id: String,
+    /// Manager contract the job was requested from, none when the node isn't configured with
+    /// managers.
+    pub manager: Option<Address>,
+    pub computer: Address,
+    pub meta_commitment: String,
+    pub scheme_version: String,
+    pub sub_jobs: usize,
+    pub tx_hash: Option<TxHash>,
+    /// Unix timestamp (seconds) the manifest was written at, i.e. the job completed.
+    pub completed_at: Option<u64>,
+}
+
+/// A compute job with its sub-jobs and their commitments.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    pub job_description_id: String,
+    pub results_id: String,
+    /// Whether the manifest signature matches the computer address.
+    pub signature_valid: bool,
+    pub sub_jobs: Vec<SubJobManifest>,
+}
+
+/// Query parameters of the job endpoint.
+#[derive(Debug, Deserialize)]
+pub struct JobQuery {
+    /// Address of the manager contract, needed when the node serves several.
+    pub manager: Option<String>,
+}
+
+fn read_job(scope: &ManagerScope, compute_id: &str) -> Option<JobDetail> {
+    let path = scope.paths.manifest_file(compute_id);
+    let bytes = std::fs::read(&path).ok()?;
+    let signed: SignedComputeManifest = match serde_json::from_slice(&bytes) {
+        Ok(signed) => signed,
+        Err(e) => {
+            warn!("Failed to parse manifest {}: {}", path, e);
+            return None;
+        }
+    };
+    let completed_at = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let signature_valid = signed.verify().is_ok();
+    let manifest = signed.manifest;
+    Some(JobDetail {
+        summary: JobSummary {
+            compute_id: manifest.compute_id,
+            manager: scope.address,
+            computer: manifest.computer,
+            meta_commitment: manifest.meta_commitment,
+            scheme_version: manifest.proof_spec_version,
+            sub_jobs: manifest.sub_jobs.len(),
+            tx_hash: manifest.receipt.map(|r| r.tx_hash),
+            completed_at,
+        },
+        job_description_id: manifest.job_description_id,
+        results_id: manifest.results_id,
+        signature_valid,
+        sub_jobs: manifest.sub_jobs,
+    })
+}
+
+async fn index_handler() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+/// Lists the jobs this node computed for every manager, most recent first.
+async fn jobs_handler(State(state): State<ServerState>) -> Json<Vec<JobSummary>> {
+    let mut jobs: Vec<JobSummary> = state
+        .manager_scopes()
+        .iter()
+        .flat_map(|scope| {
+            std::fs::read_dir(scope.paths.manifest_dir())
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                        .filter(|name| is_compute_id(name))
+                        .filter_map(|name| read_job(scope, &name))
+                        .map(|job| job.summary)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        })
+        .collect();
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.completed_at));
+    Json(jobs)
+}
+
+async fn job_handler(
+    State(state): State<ServerState>,
+    Path(compute_id): Path<String>,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<JobDetail>, ServerError> {
+    if !is_compute_id(&compute_id) {
+        return Err(ServerError::NotFound(format!(
+            "Invalid compute id: {}",
+            compute_id
+        )));
+    }
+    let scope = state.manager_scope(query.manager.as_deref())?;
+    read_job(&scope, &compute_id)
+        .map(Json)
+        .ok_or_else(|| ServerError::NotFound(format!("Job {} not found", compute_id)))
+}
+
+/// Routes of the read-only explorer: the page and the job endpoints it uses.
+/// Score searches go through the existing `/score-proof` endpoint.
+pub fn router() -> Router<ServerState> {
+    Router::new()
+        .route("/explorer", get(index_handler))
+        .route("/explorer/api/jobs", get(jobs_handler))
+        .route("/explorer/api/jobs/{compute_id}", get(job_handler))
+}
@@ -0,0 +1,196 @@
+use crate::bootstrap::StorageReport;
+use alloy::providers::{DynProvider, Provider};
+use openrank_common::storage::StorageBackend;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time the RPC and storage probes are given to answer.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Age of the last successful poll the loop is considered stalled at, when none is configured.
+pub const DEFAULT_MAX_POLL_AGE: Duration = Duration::from_secs(120);
+/// Key read to check the storage is reachable, whether it exists or not.
+const STORAGE_PROBE_KEY: &str = "health/probe";
+
+/// Progress of the compute loop, updated as it runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoopStatus {
+    /// Unix timestamp (seconds) the loop started at.
+    pub started_at: u64,
+    /// Latest block the events were processed up to.
+    pub last_processed_block: Option<u64>,
+    /// Unix timestamp (seconds) of the last successful poll of the events.
+    pub last_poll: Option<u64>,
+    /// Error of the last poll, if it failed.
+    pub last_poll_error: Option<String>,
+    /// Jobs waiting for a worker.
+    pub queued_jobs: usize,
+    /// Compute ids of the jobs being handled.
+    pub running_jobs: Vec<String>,
+    /// Jobs waiting to be retried.
+    pub pending_retries: usize,
+}
+
+impl Default for LoopStatus {
+    fn default() -> Self {
+        Self {
+            started_at: now(),
+            last_processed_block: None,
+            last_poll: None,
+            last_poll_error: None,
+            queued_jobs: 0,
+            running_jobs: Vec::new(),
+            pending_retries: 0,
+        }
+    }
+}
+
+impl LoopStatus {
+    pub fn poll_succeeded(&mut self, block: u64) {
+        self.last_processed_block = Some(block);
+        self.last_poll = Some(now());
+        self.last_poll_error = None;
+    }
+
+    pub fn poll_failed(&mut self, error: impl ToString) {
+        self.last_poll_error = Some(error.to_string());
+    }
+
+    /// Seconds since the last successful poll, or since the start if the loop never polled.
+    pub fn poll_age(&self) -> u64 {
+        now().saturating_sub(self.last_poll.unwrap_or(self.started_at))
+    }
+}
+
+/// Outcome of a check of the health report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<(), String>) -> Self {
+        Self {
+            name,
+            ok: result.is_ok(),
+            detail: result.err(),
+        }
+    }
+}
+
+/// Report of /healthz and /readyz, the node being degraded when a check fails.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub checks: Vec<Check>,
+    #[serde(rename = "loop")]
+    pub loop_status: LoopStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<StorageReport>,
+}
+
+impl HealthReport {
+    fn new(checks: Vec<Check>, loop_status: LoopStatus, storage: Option<StorageReport>) -> Self {
+        Self {
+            healthy: checks.iter().all(|check| check.ok),
+            checks,
+            loop_status,
+            storage,
+        }
+    }
+}
+
+/// State of the components of the node the health endpoints report on.
+#[derive(Clone)]
+pub struct NodeHealth {
+    pub loop_status: Arc<Mutex<LoopStatus>>,
+    /// Provider of the RPC the node reads events from, probed for connectivity.
+    pub rpc: Option<DynProvider>,
+    /// Age of the last successful poll the loop is considered stalled at.
+    pub max_poll_age: Duration,
+}
+
+impl Default for NodeHealth {
+    fn default() -> Self {
+        Self::new(Arc::default(), None, DEFAULT_MAX_POLL_AGE)
+    }
+}
+
+impl NodeHealth {
+    pub fn new(
+        loop_status: Arc<Mutex<LoopStatus>>,
+        rpc: Option<DynProvider>,
+        max_poll_age: Duration,
+    ) -> Self {
+        Self {
+            loop_status,
+            rpc,
+            max_poll_age,
+        }
+    }
+
+    fn loop_check(&self, status: &LoopStatus) -> Check {
+        let age = status.poll_age();
+        let result = if age <= self.max_poll_age.as_secs() {
+            Ok(())
+        } else {
+            Err(format!(
+                "No successful event poll for {}s{}",
+                age,
+                status
+                    .last_poll_error
+                    .as_ref()
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default()
+            ))
+        };
+        Check::new("event_poll", result)
+    }
+
+    /// Liveness: the compute loop still polls the events.
+    pub fn liveness(&self) -> HealthReport {
+        let status = self.loop_status.lock().unwrap().clone();
+        HealthReport::new(vec![self.loop_check(&status)], status, None)
+    }
+
+    /// Readiness: the storage is reachable, the RPC answers, and the compute loop polls the
+    /// events. The node doesn't start when the storage self-check fails, so its report is only
+    /// returned for reference.
+    pub async fn readiness(
+        &self,
+        storage: &dyn StorageBackend,
+        storage_report: &StorageReport,
+    ) -> HealthReport {
+        let storage_check =
+            match tokio::time::timeout(PROBE_TIMEOUT, storage.size(STORAGE_PROBE_KEY)).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("Timed out".to_string()),
+            };
+        let rpc_check = match &self.rpc {
+            Some(rpc) => match tokio::time::timeout(PROBE_TIMEOUT, rpc.get_block_number()).await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("Timed out".to_string()),
+            },
+            None => Ok(()),
+        };
+        let status = self.loop_status.lock().unwrap().clone();
+        let checks = vec![
+            Check::new("storage", storage_check),
+            Check::new("rpc", rpc_check),
+            self.loop_check(&status),
+        ];
+        HealthReport::new(checks, status, Some(storage_report.clone()))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
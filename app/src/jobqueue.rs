@@ -0,0 +1,88 @@
+use alloy::primitives::U256;
+use std::collections::{HashSet, VecDeque};
+use tracing::info;
+
+/// Number of jobs computed at the same time when none is configured.
+const DEFAULT_WORKERS: usize = 1;
+/// Number of jobs waiting for a worker when none is configured.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Jobs waiting for a worker, and the ones being handled. A job is queued once: a request
+/// seen again while it is queued or running is ignored.
+pub struct JobQueue<T> {
+    workers: usize,
+    capacity: usize,
+    pending: VecDeque<(U256, T)>,
+    running: HashSet<U256>,
+}
+
+impl<T> JobQueue<T> {
+    pub fn new(workers: usize, capacity: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            capacity,
+            pending: VecDeque::new(),
+            running: HashSet::new(),
+        }
+    }
+
+    /// Reads the number of workers from `COMPUTE_WORKERS`, defaulting to 1, and the capacity
+    /// of the queue from `JOB_QUEUE_SIZE`, defaulting to 64.
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let queue = Self::new(
+            var("COMPUTE_WORKERS", DEFAULT_WORKERS),
+            var("JOB_QUEUE_SIZE", DEFAULT_CAPACITY),
+        );
+        info!(
+            "Job queue: Workers({}), Capacity({})",
+            queue.workers, queue.capacity
+        );
+        queue
+    }
+
+    pub fn contains(&self, compute_id: &U256) -> bool {
+        self.running.contains(compute_id) || self.pending.iter().any(|(id, _)| id == compute_id)
+    }
+
+    /// Queues the job, unless it is already queued or running. The job is given back when the
+    /// queue is full.
+    pub fn push(&mut self, compute_id: U256, job: T) -> Result<(), T> {
+        if self.contains(&compute_id) {
+            return Ok(());
+        }
+        if self.pending.len() >= self.capacity {
+            return Err(job);
+        }
+        self.pending.push_back((compute_id, job));
+        Ok(())
+    }
+
+    /// Takes the next job to start, if a worker is free.
+    pub fn start_next(&mut self) -> Option<(U256, T)> {
+        if self.running.len() >= self.workers {
+            return None;
+        }
+        let (compute_id, job) = self.pending.pop_front()?;
+        self.running.insert(compute_id);
+        Some((compute_id, job))
+    }
+
+    /// Frees the worker of a job.
+    pub fn finish(&mut self, compute_id: &U256) {
+        self.running.remove(compute_id);
+    }
+
+    pub fn queued(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn running(&self) -> impl Iterator<Item = &U256> {
+        self.running.iter()
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::Error as NodeError;
+use alloy::primitives::{TxHash, U256};
+use openrank_common::merkle::Hash;
+use openrank_common::JobResult;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Last stage a compute job completed, in pipeline order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStage {
+    /// Request picked up, trust and seed data being downloaded.
+    Received,
+    /// Trust and seed data downloaded.
+    Downloaded,
+    /// Scores computed and saved locally.
+    Computed,
+    /// Scores uploaded.
+    Uploaded,
+    /// Result submitted on-chain, the submission being tracked by the tx queue.
+    Submitted,
+    /// Manifest uploaded, nothing left to do.
+    Done,
+}
+
+/// State of a compute job, updated as it moves through the pipeline.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub compute_id: U256,
+    pub job_description_id: String,
+    pub stage: JobStage,
+    /// Sub-job results, once computed.
+    #[serde(default)]
+    pub job_results: Vec<JobResult>,
+    /// Sub-job commitments, once computed.
+    #[serde(default)]
+    pub commitments: Vec<Hash>,
+    pub results_id: Option<String>,
+    pub meta_commitment: Option<String>,
+    pub tx_hash: Option<TxHash>,
+    /// Unix timestamp (seconds) of the last update.
+    pub updated_at: u64,
+}
+
+impl JobRecord {
+    pub fn new(compute_id: U256, job_description_id: String, stage: JobStage) -> Self {
+        Self {
+            compute_id,
+            job_description_id,
+            stage,
+            job_results: Vec::new(),
+            commitments: Vec::new(),
+            results_id: None,
+            meta_commitment: None,
+            tx_hash: None,
+            updated_at: 0,
+        }
+    }
+}
+
+/// Embedded database of the compute job states, by compute id, so that a restarted node
+/// resumes every job at the stage it stopped at instead of starting over.
+#[derive(Clone)]
+pub struct JobStateDb {
+    db: sled::Db,
+}
+
+impl JobStateDb {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, NodeError> {
+        let db = sled::open(dir).map_err(NodeError::JobStateError)?;
+        Ok(Self { db })
+    }
+
+    fn key(compute_id: U256) -> [u8; 32] {
+        compute_id.to_be_bytes()
+    }
+
+    pub fn get(&self, compute_id: U256) -> Result<Option<JobRecord>, NodeError> {
+        let Some(bytes) = self
+            .db
+            .get(Self::key(compute_id))
+            .map_err(NodeError::JobStateError)?
+        else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(NodeError::SerdeError)
+    }
+
+    /// Inserts or updates the record, flushed to disk before returning.
+    pub fn put(&self, record: &mut JobRecord) -> Result<(), NodeError> {
+        record.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let bytes = serde_json::to_vec(record).map_err(NodeError::SerdeError)?;
+        self.db
+            .insert(Self::key(record.compute_id), bytes)
+            .map_err(NodeError::JobStateError)?;
+        self.db.flush().map_err(NodeError::JobStateError)?;
+        Ok(())
+    }
+
+    /// Moves the job to the stage, creating its record if needed.
+    pub fn set_stage(
+        &self,
+        compute_id: U256,
+        job_description_id: &str,
+        stage: JobStage,
+    ) -> Result<(), NodeError> {
+        let mut record = self
+            .get(compute_id)?
+            .unwrap_or_else(|| JobRecord::new(compute_id, job_description_id.to_string(), stage));
+        record.stage = stage;
+        self.put(&mut record)
+    }
+
+    /// Returns all the records, by compute id.
+    pub fn all(&self) -> Result<Vec<JobRecord>, NodeError> {
+        let mut records = Vec::new();
+        for entry in self.db.iter() {
+            let (_, bytes) = entry.map_err(NodeError::JobStateError)?;
+            records.push(serde_json::from_slice(&bytes).map_err(NodeError::SerdeError)?);
+        }
+        Ok(records)
+    }
+}
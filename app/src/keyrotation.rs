@@ -0,0 +1,263 @@
+use crate::computer::drain_tx_queue;
+use crate::error::Error as NodeError;
+use crate::jobstate::JobStateDb;
+use crate::paths::{paths, PathManager};
+use crate::sol::OpenRankManager::OpenRankManagerInstance;
+use crate::txmanager::TxManager;
+use crate::txqueue::TxQueue;
+use crate::wallet::{key_path, parse_key, RoleWallet, SpendingPolicy};
+use alloy::hex;
+use alloy::primitives::{Address, Signature, TxHash, U256};
+use alloy::providers::Provider;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use openrank_common::storage::StorageBackend;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Record of a key rotation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationRecord {
+    pub role: String,
+    pub old_address: Address,
+    pub new_address: Address,
+    /// Transaction allowlisting the new address, if the rotation sent one.
+    pub registration_tx: Option<TxHash>,
+    /// Number of in-flight submissions finished with the old key before the switch.
+    pub drained: usize,
+    /// Unix timestamp (seconds) of the switch.
+    pub rotated_at: u64,
+}
+
+/// Rotation record signed by the old key, vouching for the new one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRotationRecord {
+    pub record: RotationRecord,
+    pub signature: Signature,
+}
+
+impl SignedRotationRecord {
+    /// Recovers the signer of the record and checks it matches the old address.
+    pub fn verify(&self) -> Result<(), NodeError> {
+        let bytes = serde_json::to_vec(&self.record).map_err(NodeError::SerdeError)?;
+        let signer = self
+            .signature
+            .recover_address_from_msg(&bytes)
+            .map_err(|e| NodeError::IntegrityError(format!("Invalid signature: {}", e)))?;
+        if signer != self.record.old_address {
+            return Err(NodeError::IntegrityError(format!(
+                "Rotation record signed by {}, expected {}",
+                signer, self.record.old_address
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn pending_key_path(role: &str) -> String {
+    format!("{}.pending", key_path(role))
+}
+
+/// Writes the key readable by the owner only, replacing the file atomically.
+fn write_key(path: &str, signer: &PrivateKeySigner) -> Result<(), NodeError> {
+    let tmp_path = format!("{}.part", path);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .map_err(|e| NodeError::FileError(format!("Failed to create key file: {}", e)))?;
+    file.write_all(hex::encode_prefixed(signer.to_bytes()).as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|e| NodeError::FileError(format!("Failed to write key file: {}", e)))
+}
+
+/// Returns the key the role is rotated to: the pending key of an interrupted rotation, else the
+/// key imported from `NEW_{ROLE}_KEY` (hex private key or mnemonic), else a new random key.
+/// It is kept as pending until the switch, so running the rotation again resumes it.
+fn pending_key(role: &str) -> Result<PrivateKeySigner, NodeError> {
+    let path = pending_key_path(role);
+    if let Ok(key) = std::fs::read_to_string(&path) {
+        info!("Resuming the rotation to the pending key in {}", path);
+        return parse_key(&key);
+    }
+    let signer = match std::env::var(format!("NEW_{}_KEY", role.to_uppercase())) {
+        Ok(key) => parse_key(&key)?,
+        Err(_) => PrivateKeySigner::random(),
+    };
+    std::fs::create_dir_all(paths().keys_dir())
+        .map_err(|e| NodeError::FileError(format!("Failed to create keys dir: {}", e)))?;
+    write_key(&path, &signer)?;
+    Ok(signer)
+}
+
+/// Allowlists the new address with the old key, unless it is allowlisted already.
+/// Only the contract owner can allowlist computers, so this fails for nodes not owning it.
+async fn register<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    new_address: Address,
+) -> Result<Option<TxHash>, NodeError> {
+    let registered = contract
+        .isAllowlistedComputer(new_address)
+        .call()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to read allowlist: {e:}")))?;
+    if registered {
+        info!("New address {} is already allowlisted", new_address);
+        return Ok(None);
+    }
+
+    wallet.check_spend_allowed()?;
+    info!("Allowlisting new address {}", new_address);
+    let receipt = contract
+        .allowlistComputer(new_address)
+        .send()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to send 'allowlistComputer': {e:}")))?
+        .get_receipt()
+        .await
+        .map_err(|e| NodeError::TxError(format!("Failed to get receipt: {e:}")))?;
+    wallet.record_spend(U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price));
+    if !receipt.status() {
+        return Err(NodeError::TxError(format!(
+            "'allowlistComputer' reverted: Tx Hash({:#})",
+            receipt.transaction_hash
+        )));
+    }
+    Ok(Some(receipt.transaction_hash))
+}
+
+/// Rotates the signing key of the role, while the node is stopped:
+/// - creates (or imports) the new key, kept as pending until the switch,
+/// - allowlists the new address on the manager contract, unless `skip_registration`,
+/// - finishes the in-flight submissions with the old key,
+/// - switches the active key file atomically, the node using it from its next start,
+/// - appends the rotation, signed by the old key, to the rotation log.
+///
+/// Any failure leaves the old key active, and running the rotation again resumes it.
+pub async fn rotate_key<PH: Provider>(
+    contract: &OpenRankManagerInstance<PH>,
+    wallet: &RoleWallet,
+    storage: &dyn StorageBackend,
+    state: &PathManager,
+    skip_registration: bool,
+) -> Result<SignedRotationRecord, NodeError> {
+    let role = wallet.role();
+    let new_signer = pending_key(role)?;
+    let new_address = new_signer.address();
+    if new_address == wallet.address() {
+        let _ = std::fs::remove_file(pending_key_path(role));
+        return Err(NodeError::ConfigError(
+            "The new key is the current one".to_string(),
+        ));
+    }
+    info!(
+        "Rotating key of role '{}': {} -> {}",
+        role,
+        wallet.address(),
+        new_address
+    );
+
+    let registration_tx = if skip_registration {
+        warn!("Skipping the registration of {}", new_address);
+        None
+    } else {
+        register(contract, wallet, new_address).await.map_err(|e| {
+            NodeError::ConfigError(format!(
+                "Failed to allowlist {}: {}. Have the contract owner call \
+                 allowlistComputer({}) and run the rotation again",
+                new_address, e, new_address
+            ))
+        })?
+    };
+
+    // Submissions signed with the old key must be finished before it is retired
+    let queue = TxQueue::open(state.tx_queue_dir())?;
+    let job_state = JobStateDb::open(state.job_state_dir())?;
+    let drained = queue.pending()?.len();
+    drain_tx_queue(
+        contract,
+        wallet,
+        &TxManager::from_env()?,
+        storage,
+        state,
+        &queue,
+        &job_state,
+    )
+    .await?;
+    let in_flight = queue.pending()?.len();
+    if in_flight > 0 {
+        return Err(NodeError::TxError(format!(
+            "{} submissions are still in flight with the old key, run the rotation again",
+            in_flight
+        )));
+    }
+
+    let balance = contract
+        .provider()
+        .get_balance(new_address)
+        .await
+        .map_err(NodeError::from)?;
+    let policy = SpendingPolicy::from_env(role)?;
+    if balance < policy.min_balance {
+        warn!(
+            "New address {} holds {} wei, below the {} wei the node needs to start",
+            new_address, balance, policy.min_balance
+        );
+    }
+
+    // A key replaced by a previous rotation is kept, in case it still holds funds
+    let active_path = key_path(role);
+    if Path::new(&active_path).exists() {
+        let retired_path = format!(
+            "{}/{}.{}.retired",
+            paths().keys_dir(),
+            role,
+            wallet.address()
+        );
+        std::fs::rename(&active_path, &retired_path)
+            .map_err(|e| NodeError::FileError(format!("Failed to retire old key: {}", e)))?;
+    }
+    std::fs::rename(pending_key_path(role), &active_path)
+        .map_err(|e| NodeError::FileError(format!("Failed to switch key: {}", e)))?;
+
+    let record = RotationRecord {
+        role: role.to_string(),
+        old_address: wallet.address(),
+        new_address,
+        registration_tx,
+        drained,
+        rotated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    };
+    let bytes = serde_json::to_vec(&record).map_err(NodeError::SerdeError)?;
+    let signature = wallet
+        .signer()
+        .sign_message_sync(&bytes)
+        .map_err(|e| NodeError::TxError(format!("Failed to sign rotation record: {}", e)))?;
+    let signed = SignedRotationRecord { record, signature };
+
+    let mut line = serde_json::to_vec(&signed).map_err(NodeError::SerdeError)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(paths().rotation_log())
+        .and_then(|mut file| file.write_all(&line))
+        .map_err(|e| NodeError::FileError(format!("Failed to write rotation log: {}", e)))?;
+    info!(
+        "Key of role '{}' rotated to {}, effective from the next start",
+        role, new_address
+    );
+    Ok(signed)
+}
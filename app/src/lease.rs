@@ -0,0 +1,216 @@
+use crate::error::Error as NodeError;
+use alloy::primitives::U256;
+use openrank_common::storage::StorageBackend;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+const DEFAULT_LEASE_SECS: u64 = 600;
+
+/// A generation of the lease of a job, stored under `lease/{compute_id}/{generation}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaseEntry {
+    /// Replica holding the lease.
+    pub owner: String,
+    /// Unix timestamp (seconds) the lease can be taken over from.
+    pub expires_at: u64,
+    /// Whether the job was done by the owner, in which case the lease is never taken over.
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// Outcome of acquiring the lease of a job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Acquired {
+    /// The job is ours to handle.
+    Acquired,
+    /// Another replica is handling the job.
+    Held { owner: String, expires_at: u64 },
+    /// A replica already handled the job.
+    Done { owner: String },
+}
+
+/// Leases of the jobs, so that only one of the computer replicas sharing a bucket handles a
+/// given compute id. A replica stopping without releasing its leases has its jobs taken over
+/// once they expire.
+///
+/// Every change of the lease is a new generation, written with a conditional put: of the
+/// replicas racing for a job (or to take it over), the single one creating the next
+/// generation holds the lease. The latest generation is the current state of the lease.
+pub struct JobLeases {
+    storage: Arc<dyn StorageBackend>,
+    owner: String,
+    duration: Duration,
+}
+
+impl JobLeases {
+    pub fn new(storage: Arc<dyn StorageBackend>, owner: String, duration: Duration) -> Self {
+        Self {
+            storage,
+            owner,
+            duration,
+        }
+    }
+
+    /// Reads the replica id from `REPLICA_ID`, defaulting to the hostname, and the lease
+    /// duration from `JOB_LEASE_SECS`, defaulting to 600.
+    ///
+    /// The replica id must be stable across restarts, for a restarted replica to pick up its
+    /// own jobs without waiting for their leases to expire, and unique among the replicas.
+    pub fn from_env(storage: Arc<dyn StorageBackend>) -> Result<Self, NodeError> {
+        let owner = std::env::var("REPLICA_ID")
+            .ok()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|owner| owner.trim().to_string())
+            .filter(|owner| !owner.is_empty())
+            .unwrap_or("computer".to_string());
+        let secs = match std::env::var("JOB_LEASE_SECS") {
+            Ok(secs) => secs
+                .parse()
+                .map_err(|e| NodeError::ConfigError(format!("Invalid JOB_LEASE_SECS: {}", e)))?,
+            Err(_) => DEFAULT_LEASE_SECS,
+        };
+        info!("Job leases: Replica({}), Duration({}s)", owner, secs);
+        Ok(Self::new(storage, owner, Duration::from_secs(secs)))
+    }
+
+    /// Interval the leases held are renewed at while their job is handled.
+    pub fn renew_interval(&self) -> Duration {
+        self.duration / 3
+    }
+
+    fn prefix(compute_id: U256) -> String {
+        format!("lease/{}/", compute_id)
+    }
+
+    /// Returns the latest generation of the lease and its entry, if any.
+    async fn latest(&self, compute_id: U256) -> Result<Option<(u64, LeaseEntry)>, NodeError> {
+        let keys = self.storage.list(&Self::prefix(compute_id)).await?;
+        let Some((generation, key)) = keys
+            .iter()
+            .filter_map(|key| Some((key.rsplit('/').next()?.parse::<u64>().ok()?, key)))
+            .max_by_key(|(generation, _)| *generation)
+        else {
+            return Ok(None);
+        };
+        let bytes = self.storage.get(key).await?;
+        let entry = serde_json::from_slice(&bytes).map_err(NodeError::SerdeError)?;
+        Ok(Some((generation, entry)))
+    }
+
+    /// Writes the next generation of the lease, returning whether no other replica wrote it
+    /// first.
+    async fn write(
+        &self,
+        compute_id: U256,
+        generation: u64,
+        expires_at: u64,
+        done: bool,
+    ) -> Result<bool, NodeError> {
+        let entry = LeaseEntry {
+            owner: self.owner.clone(),
+            expires_at,
+            done,
+        };
+        let bytes = serde_json::to_vec(&entry).map_err(NodeError::SerdeError)?;
+        // Zero padded for the generations to list in order
+        let key = format!("{}{:020}", Self::prefix(compute_id), generation);
+        Ok(self.storage.put_if_absent(&key, bytes).await?)
+    }
+
+    fn state(&self, entry: LeaseEntry) -> Option<Acquired> {
+        if entry.done {
+            return Some(Acquired::Done { owner: entry.owner });
+        }
+        if entry.owner != self.owner && entry.expires_at > now() {
+            return Some(Acquired::Held {
+                owner: entry.owner,
+                expires_at: entry.expires_at,
+            });
+        }
+        None
+    }
+
+    /// Acquires the lease of the job, free or expired or already ours.
+    pub async fn acquire(&self, compute_id: U256) -> Result<Acquired, NodeError> {
+        let latest = self.latest(compute_id).await?;
+        let next = match latest {
+            Some((generation, entry)) => {
+                if let Some(state) = self.state(entry) {
+                    return Ok(state);
+                }
+                generation + 1
+            }
+            None => 0,
+        };
+        let expires_at = now() + self.duration.as_secs();
+        if self.write(compute_id, next, expires_at, false).await? {
+            return Ok(Acquired::Acquired);
+        }
+        // Another replica acquired it first
+        match self.latest(compute_id).await? {
+            Some((_, entry)) => Ok(self.state(entry.clone()).unwrap_or(Acquired::Held {
+                owner: entry.owner,
+                expires_at: entry.expires_at,
+            })),
+            None => Err(NodeError::NotFound(Self::prefix(compute_id))),
+        }
+    }
+
+    /// Extends the lease of a job being handled.
+    pub async fn renew(&self, compute_id: U256) -> Result<(), NodeError> {
+        if self.acquire(compute_id).await? != Acquired::Acquired {
+            warn!(
+                "Lease lost while handling the job: ComputeId({})",
+                compute_id
+            );
+        }
+        Ok(())
+    }
+
+    /// Releases the lease of a job that failed, for another replica to take it over
+    /// without waiting for it to expire.
+    pub async fn release(&self, compute_id: U256) -> Result<(), NodeError> {
+        if let Some((generation, entry)) = self.latest(compute_id).await? {
+            if entry.owner == self.owner && !entry.done {
+                self.write(compute_id, generation + 1, 0, false).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the job done, its lease then never being taken over, and removes the previous
+    /// generations.
+    pub async fn complete(&self, compute_id: U256) -> Result<(), NodeError> {
+        let Some((generation, _)) = self.latest(compute_id).await? else {
+            return Ok(());
+        };
+        if !self.write(compute_id, generation + 1, 0, true).await? {
+            warn!(
+                "Lease changed before the job was marked done: ComputeId({})",
+                compute_id
+            );
+            return Ok(());
+        }
+        for key in self.storage.list(&Self::prefix(compute_id)).await? {
+            let previous = key
+                .rsplit('/')
+                .next()
+                .and_then(|g| g.parse::<u64>().ok())
+                .is_some_and(|g| g <= generation);
+            if previous {
+                self.storage.delete(&key).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -1,49 +1,213 @@
+pub mod attestation;
+pub mod audit;
+pub mod bootstrap;
+pub mod checkpoint;
+pub mod compat;
 pub mod computer;
+pub mod enclave;
 pub mod error;
+#[cfg(feature = "explorer")]
+pub mod explorer;
+pub mod health;
+pub mod jobqueue;
+pub mod jobstate;
+pub mod keyrotation;
+pub mod lease;
+pub mod maintenance;
+pub mod managers;
+pub mod notify;
+pub mod paths;
+pub mod quarantine;
+pub mod ratelimit;
+pub mod repair;
 pub mod server;
 pub mod sol;
+pub mod tls;
+pub mod trust_cache;
+pub mod txmanager;
+pub mod txqueue;
+pub mod wallet;
 
 pub use crate::error::Error;
+use crate::maintenance::hash_file;
+use crate::paths::paths;
 use alloy::hex;
-use aws_sdk_s3::Client as S3Client;
+use openrank_common::canonical;
+use openrank_common::ranking::{write_ranked_csv, RankingMode};
+use openrank_common::scheme::Precision;
+use openrank_common::score_meta::{write_metadata_csv, ScoreMetadata};
+use openrank_common::storage::{artifact_key, Error as StorageError, StorageBackend};
+use openrank_common::subgraph::{FilterStats, NodeFilter};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sha3::{Digest, Keccak256};
-use std::fs::File;
+use std::future::Future;
 use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Artifacts published less than this many seconds ago may not be visible to every reader yet.
+pub const FRESH_ARTIFACT_WINDOW_SECS: u64 = 300;
+/// Maximum number of retries when a fresh artifact is not found.
+const NOT_FOUND_MAX_RETRIES: u32 = 5;
+/// Backoff before the first retry, doubled on every attempt.
+const NOT_FOUND_BASE_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Numbers the temporary files of the downloads.
+static DOWNLOAD_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns how many times a missing object should be retried, given when it was published
+/// (unix timestamp in seconds). Objects of unknown age are not retried: they are read when
+/// resuming jobs or replaying our own submissions, long after they were published.
+fn not_found_retries(published_at: Option<u64>) -> u32 {
+    let Some(published_at) = published_at else {
+        return 0;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    if now.saturating_sub(published_at) < FRESH_ARTIFACT_WINDOW_SECS {
+        NOT_FOUND_MAX_RETRIES
+    } else {
+        0
+    }
+}
+
+/// Reads an object, retrying with backoff while a fresh object is not visible yet.
+async fn read_with_retry<T, F, Fut>(
+    object_key: &str,
+    published_at: Option<u64>,
+    mut read: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, StorageError>>,
+{
+    let max_retries = not_found_retries(published_at);
+    let mut backoff = NOT_FOUND_BASE_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match read().await {
+            Ok(output) => return Ok(output),
+            Err(StorageError::NotFound(_)) => {
+                if attempt >= max_retries {
+                    return Err(Error::NotFound(object_key.to_string()));
+                }
+                attempt += 1;
+                warn!(
+                    "Object '{}' not found, retrying in {:?} ({}/{})",
+                    object_key, backoff, attempt, max_retries
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Checks an uploaded object is readable with the expected size (read-after-write),
+/// retrying with backoff while it is not visible yet. Sizes are the ones of the uploaded
+/// data, compressed storages returning the uncompressed size recorded at upload.
+async fn verify_uploaded_object(
+    storage: &dyn StorageBackend,
+    object_key: &str,
+    expected_len: u64,
+) -> Result<(), Error> {
+    let mut backoff = NOT_FOUND_BASE_BACKOFF;
+    for attempt in 0..=NOT_FOUND_MAX_RETRIES {
+        match storage.size(object_key).await? {
+            Some(len) if len == expected_len => return Ok(()),
+            Some(len) => {
+                warn!(
+                    "Object '{}' has size {}, expected {} ({}/{})",
+                    object_key, len, expected_len, attempt, NOT_FOUND_MAX_RETRIES
+                );
+            }
+            None => {
+                warn!(
+                    "Uploaded object '{}' not visible yet ({}/{})",
+                    object_key, attempt, NOT_FOUND_MAX_RETRIES
+                );
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    Err(Error::NotFound(object_key.to_string()))
+}
 
 pub async fn upload_meta<T: Serialize>(
-    client: &S3Client,
-    bucket_name: &str,
+    storage: &dyn StorageBackend,
     meta: T,
 ) -> Result<String, Error> {
-    let mut bytes = serde_json::to_vec(&meta).map_err(Error::SerdeError)?;
-
-    let mut hasher = Keccak256::new();
-    hasher
-        .write_all(&mut bytes)
-        .map_err(|e| Error::FileError(format!("Failed to write to hasher: {}", e)))?;
-    let hash = hasher.finalize().to_vec();
-    upload_bytes_to_s3(
-        client,
-        bucket_name,
-        &format!("meta/{}", hex::encode(hash.clone())),
-        &bytes,
-    )
-    .await?;
-    Ok(hex::encode(hash))
+    let (bytes, meta_id) = canonical::hash(&meta).map_err(Error::CanonicalError)?;
+    upload_bytes(storage, &format!("meta/{}", meta_id), &bytes).await?;
+    Ok(meta_id)
 }
 
 pub async fn download_meta<T: DeserializeOwned>(
-    client: &S3Client,
-    bucket_name: &str,
+    storage: &dyn StorageBackend,
     meta_id: String,
+    published_at: Option<u64>,
 ) -> Result<T, Error> {
-    download_json_metadata_from_s3(client, bucket_name, &meta_id).await
+    download_json_metadata(storage, &meta_id, published_at).await
 }
 
-/// Creates CSV data from score entries and returns both CSV bytes and its Keccak256 hash.
-pub fn create_csv_and_hash_from_scores<I>(scores: I) -> Result<(Vec<u8>, Vec<u8>), Error>
+/// Downloads the job description and the results meta of a compute request concurrently.
+pub async fn download_meta_pair<D: DeserializeOwned, R: DeserializeOwned>(
+    storage: &dyn StorageBackend,
+    job_description_id: &str,
+    results_id: &str,
+    published_at: Option<u64>,
+) -> Result<(D, R), Error> {
+    tokio::try_join!(
+        download_json_metadata(storage, job_description_id, published_at),
+        download_json_metadata(storage, results_id, published_at),
+    )
+}
+
+/// Checks the meta bytes hash to the meta id.
+fn verify_meta_bytes(meta_id: &str, bytes: &[u8]) -> bool {
+    hex::encode(Keccak256::digest(bytes)) == meta_id.trim_start_matches("0x").to_lowercase()
+}
+
+/// Reads meta bytes from the disk cache, dropping the entry if it is corrupted.
+async fn read_cached_meta(meta_id: &str) -> Option<Vec<u8>> {
+    let path = format!("{}/{}", paths().meta_cache_dir(), meta_id);
+    let bytes = tokio::fs::read(&path).await.ok()?;
+    if verify_meta_bytes(meta_id, &bytes) {
+        return Some(bytes);
+    }
+    warn!("Cached meta '{}' is corrupted, removing it", meta_id);
+    let _ = tokio::fs::remove_file(&path).await;
+    None
+}
+
+/// Writes meta bytes to the disk cache. The cache is best effort, failures are only logged.
+async fn write_cached_meta(meta_id: &str, bytes: &[u8]) {
+    let path = format!("{}/{}", paths().meta_cache_dir(), meta_id);
+    let tmp_path = format!("{}.part", path);
+    let res = async {
+        tokio::fs::create_dir_all(paths().meta_cache_dir()).await?;
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, &path).await
+    }
+    .await;
+    if let Err(e) = res {
+        warn!("Failed to cache meta '{}': {}", meta_id, e);
+    }
+}
+
+/// Creates CSV data from score entries, the values formatted with the precision of the job,
+/// and returns both CSV bytes and its Keccak256 hash.
+pub fn create_csv_and_hash_from_scores<I>(
+    scores: I,
+    precision: Precision,
+) -> Result<(Vec<u8>, Vec<u8>), Error>
 where
     I: IntoIterator<Item = openrank_common::ScoreEntry>,
 {
@@ -51,10 +215,10 @@ where
 
     let scores_vec = Vec::new();
     let mut wtr = csv::Writer::from_writer(scores_vec);
-    wtr.write_record(&["i", "v"]).map_err(Error::CsvError)?;
+    wtr.write_record(["i", "v"]).map_err(Error::CsvError)?;
 
     for score in scores {
-        wtr.write_record(&[score.id(), score.value().to_string().as_str()])
+        wtr.write_record([score.id(), precision.format(*score.value()).as_str()])
             .map_err(Error::CsvError)?;
     }
 
@@ -71,8 +235,39 @@ where
     Ok((csv_bytes, hash))
 }
 
+/// Creates the ranked CSV data (`i,v,r`) from score entries sorted in ranking order,
+/// and returns both CSV bytes and its Keccak256 hash.
+pub fn create_ranked_csv_and_hash_from_scores(
+    scores: &[openrank_common::ScoreEntry],
+    mode: RankingMode,
+    precision: Precision,
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut csv_bytes = Vec::new();
+    write_ranked_csv(&mut csv_bytes, scores, mode, precision).map_err(Error::CsvError)?;
+    let hash = Keccak256::digest(&csv_bytes).to_vec();
+
+    Ok((csv_bytes, hash))
+}
+
+/// Creates the extended scores CSV data (`i,v,r,p,f`) from score metadata,
+/// and returns both CSV bytes and its Keccak256 hash.
+pub fn create_metadata_csv_and_hash(
+    metadata: &[ScoreMetadata],
+    precision: Precision,
+) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut csv_bytes = Vec::new();
+    write_metadata_csv(&mut csv_bytes, metadata, precision).map_err(Error::CsvError)?;
+    let hash = Keccak256::digest(&csv_bytes).to_vec();
+
+    Ok((csv_bytes, hash))
+}
+
 /// Creates CSV file from score entries, saves it to disk, and returns its Keccak256 hash.
-pub fn create_csv_file_and_hash_from_scores<I>(scores: I, file_path: &str) -> Result<Vec<u8>, Error>
+pub fn create_csv_file_and_hash_from_scores<I>(
+    scores: I,
+    file_path: &str,
+    precision: Precision,
+) -> Result<Vec<u8>, Error>
 where
     I: IntoIterator<Item = openrank_common::ScoreEntry>,
 {
@@ -83,25 +278,23 @@ where
         .map_err(|e| Error::FileError(format!("Failed to create file {}: {}", file_path, e)))?;
 
     let mut wtr = csv::Writer::from_writer(file);
-    wtr.write_record(&["i", "v"]).map_err(Error::CsvError)?;
+    wtr.write_record(["i", "v"]).map_err(Error::CsvError)?;
 
     let mut csv_bytes = Vec::new();
     let mut temp_wtr = csv::Writer::from_writer(&mut csv_bytes);
-    temp_wtr
-        .write_record(&["i", "v"])
-        .map_err(Error::CsvError)?;
+    temp_wtr.write_record(["i", "v"]).map_err(Error::CsvError)?;
 
     for score in scores {
         let id = score.id();
-        let value_str = score.value().to_string();
+        let value_str = precision.format(*score.value());
 
         // Write to file
-        wtr.write_record(&[id, &value_str])
+        wtr.write_record([id, &value_str])
             .map_err(Error::CsvError)?;
 
         // Write to temp buffer for hashing
         temp_wtr
-            .write_record(&[id, &value_str])
+            .write_record([id, &value_str])
             .map_err(Error::CsvError)?;
     }
 
@@ -116,208 +309,262 @@ where
 
     let mut hasher = Keccak256::new();
     hasher
-        .write_all(&csv_bytes)
+        .write_all(csv_bytes)
         .map_err(|e| Error::FileError(format!("Failed to write to hasher: {}", e)))?;
     let hash = hasher.finalize().to_vec();
 
     Ok(hash)
 }
 
-/// Downloads an S3 object and saves it to a local file.
-pub async fn download_s3_object_to_file(
-    s3_client: &S3Client,
-    bucket_name: &str,
+/// Downloads an object and saves it to a local file.
+pub async fn download_object_to_file(
+    storage: &dyn StorageBackend,
     object_key: &str,
     file_path: &str,
+    published_at: Option<u64>,
 ) -> Result<(), Error> {
-    let mut file = File::create(file_path)
-        .map_err(|e| Error::FileError(format!("Failed to create file {}: {}", file_path, e)))?;
-
-    let mut response = s3_client
-        .get_object()
-        .bucket(bucket_name)
-        .key(object_key)
-        .send()
-        .await
-        .map_err(|e| Error::AwsError(e.into()))?;
-
-    while let Some(bytes) = response.body.next().await {
-        let chunk = bytes.map_err(Error::ByteStreamError)?;
-        file.write_all(&chunk).map_err(|e| {
-            Error::FileError(format!("Failed to write to file {}: {}", file_path, e))
-        })?;
-    }
-
-    Ok(())
+    read_with_retry(object_key, published_at, || {
+        storage.get_to_file(object_key, Path::new(file_path))
+    })
+    .await
 }
 
-/// Downloads an S3 object and returns the data as bytes.
-pub async fn download_s3_object_as_bytes(
-    s3_client: &S3Client,
-    bucket_name: &str,
+/// Downloads an object and returns the data as bytes.
+pub async fn download_object_as_bytes(
+    storage: &dyn StorageBackend,
     object_key: &str,
+    published_at: Option<u64>,
 ) -> Result<Vec<u8>, Error> {
-    let mut response = s3_client
-        .get_object()
-        .bucket(bucket_name)
-        .key(object_key)
-        .send()
-        .await
-        .map_err(|e| Error::AwsError(e.into()))?;
-
-    let mut data = Vec::new();
-    while let Some(bytes) = response.body.next().await {
-        let chunk = bytes.map_err(Error::ByteStreamError)?;
-        data.extend_from_slice(&chunk);
-    }
-
-    Ok(data)
+    read_with_retry(object_key, published_at, || storage.get(object_key)).await
 }
 
-/// Uploads raw bytes to S3 with the specified key.
-pub async fn upload_bytes_to_s3(
-    s3_client: &S3Client,
-    bucket_name: &str,
+/// Uploads raw bytes with the specified key.
+pub async fn upload_bytes(
+    storage: &dyn StorageBackend,
     object_key: &str,
     data: &[u8],
 ) -> Result<(), Error> {
-    use aws_sdk_s3::primitives::ByteStream;
-
-    let body = ByteStream::from(data.to_vec());
-
-    s3_client
-        .put_object()
-        .bucket(bucket_name)
-        .key(object_key)
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| Error::AwsError(e.into()))?;
-
-    Ok(())
+    storage.put(object_key, data.to_vec()).await?;
+    verify_uploaded_object(storage, object_key, data.len() as u64).await
 }
 
-/// Uploads a file to S3 using streaming without loading the entire file into memory.
-pub async fn upload_file_to_s3_streaming(
-    s3_client: &S3Client,
-    bucket_name: &str,
+/// Uploads a file, streamed by backends supporting it instead of loaded in memory.
+pub async fn upload_file_streaming(
+    storage: &dyn StorageBackend,
     object_key: &str,
     file_path: &str,
 ) -> Result<(), Error> {
-    use aws_sdk_s3::primitives::ByteStream;
-    use tokio::fs::File;
-
-    // Open the file asynchronously
-    let file = File::open(file_path)
+    let file_len = tokio::fs::metadata(file_path)
         .await
-        .map_err(|e| Error::FileError(format!("Failed to open file {}: {}", file_path, e)))?;
+        .map_err(|e| Error::FileError(format!("Failed to read metadata {}: {}", file_path, e)))?
+        .len();
+    storage.put_file(object_key, Path::new(file_path)).await?;
+    verify_uploaded_object(storage, object_key, file_len).await
+}
 
-    // Create a ByteStream from the file
-    let body = ByteStream::read_from()
-        .file(file)
-        .build()
+/// Downloads a content addressed object to a file, checking its Keccak256 hash matches the id.
+/// The download goes to a temporary file moved into place once verified, so a corrupted or
+/// partial object never ends up in the local cache.
+async fn download_verified_to_file(
+    storage: &dyn StorageBackend,
+    object_key: &str,
+    id: &str,
+    file_path: &str,
+    published_at: Option<u64>,
+) -> Result<(), Error> {
+    // Unique to the download, as concurrent jobs may download the same object
+    let tmp_path = format!(
+        "{}.{}.part",
+        file_path,
+        DOWNLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    download_object_to_file(storage, object_key, &tmp_path, published_at).await?;
+    let hash_path = tmp_path.clone();
+    let hash = tokio::task::spawn_blocking(move || hash_file(Path::new(&hash_path)))
         .await
-        .map_err(|e| {
-            Error::FileError(format!(
-                "Failed to create stream from file {}: {}",
-                file_path, e
-            ))
-        })?;
-
-    // Upload using the streaming body
-    s3_client
-        .put_object()
-        .bucket(bucket_name)
-        .key(object_key)
-        .body(body)
-        .send()
+        .map_err(|e| Error::FileError(format!("Hashing task failed: {}", e)))?
+        .map_err(|e| Error::FileError(format!("Failed to hash {}: {}", tmp_path, e)))?;
+    if hash != id.trim_start_matches("0x").to_lowercase() {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(Error::IntegrityError(format!(
+            "'{}' does not match its hash (got {})",
+            object_key, hash
+        )));
+    }
+    tokio::fs::rename(&tmp_path, file_path)
         .await
-        .map_err(|e| Error::AwsError(e.into()))?;
-
-    Ok(())
+        .map_err(|e| Error::FileError(format!("Failed to rename {}: {}", tmp_path, e)))
 }
 
-/// Downloads trust CSV data from S3 using "trust/{id}" key pattern and saves to file.
+/// Downloads trust CSV data using "trust/{id}" key pattern, under the key prefix of the tenant
+/// if any, and saves to file, once checked against the id.
 pub async fn download_trust_data_to_file(
-    s3_client: &S3Client,
-    bucket_name: &str,
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
     trust_id: &str,
     file_path: &str,
+    published_at: Option<u64>,
 ) -> Result<(), Error> {
-    let object_key = format!("trust/{}", trust_id);
-    download_s3_object_to_file(s3_client, bucket_name, &object_key, file_path).await
+    let object_key = artifact_key(key_prefix, "trust", trust_id);
+    download_verified_to_file(storage, &object_key, trust_id, file_path, published_at).await
 }
 
-/// Downloads seed CSV data from S3 using "seed/{id}" key pattern and saves to file.
+/// Downloads seed CSV data using "seed/{id}" key pattern, under the key prefix of the tenant
+/// if any, and saves to file, once checked against the id.
 pub async fn download_seed_data_to_file(
-    s3_client: &S3Client,
-    bucket_name: &str,
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
     seed_id: &str,
     file_path: &str,
+    published_at: Option<u64>,
 ) -> Result<(), Error> {
-    let object_key = format!("seed/{}", seed_id);
-    download_s3_object_to_file(s3_client, bucket_name, &object_key, file_path).await
+    let object_key = artifact_key(key_prefix, "seed", seed_id);
+    download_verified_to_file(storage, &object_key, seed_id, file_path, published_at).await
 }
 
-/// Downloads JSON metadata from S3 using "meta/{id}" key pattern and parses it into the specified type.
-pub async fn download_json_metadata_from_s3<T>(
-    s3_client: &S3Client,
-    bucket_name: &str,
+/// Downloads JSON metadata using "meta/{id}" key pattern and parses it into the specified type.
+/// Meta is content addressed, so the bytes are checked against the id and cached on disk.
+pub async fn download_json_metadata<T>(
+    storage: &dyn StorageBackend,
     meta_id: &str,
+    published_at: Option<u64>,
 ) -> Result<T, Error>
 where
     T: DeserializeOwned,
 {
-    let object_key = format!("meta/{}", meta_id);
-    let mut response = s3_client
-        .get_object()
-        .bucket(bucket_name)
-        .key(&object_key)
-        .send()
-        .await
-        .map_err(|e| Error::AwsError(e.into()))?;
-
-    let mut data = Vec::new();
-    while let Some(bytes) = response.body.next().await {
-        let chunk = bytes.map_err(Error::ByteStreamError)?;
-        data.extend_from_slice(&chunk);
-    }
+    let data = match read_cached_meta(meta_id).await {
+        Some(data) => data,
+        None => {
+            let object_key = format!("meta/{}", meta_id);
+            let data = download_object_as_bytes(storage, &object_key, published_at).await?;
+            if !verify_meta_bytes(meta_id, &data) {
+                return Err(Error::IntegrityError(format!(
+                    "meta '{}' does not match its hash",
+                    meta_id
+                )));
+            }
+            write_cached_meta(meta_id, &data).await;
+            data
+        }
+    };
 
     let metadata: T = serde_json::from_slice(&data).map_err(Error::SerdeError)?;
     Ok(metadata)
 }
 
-/// Parses CSV data from a file handle into TrustEntry vectors.
+/// Parses CSV or Parquet data from a file handle into TrustEntry vectors.
 pub fn parse_trust_entries_from_file(
     file: std::fs::File,
 ) -> Result<Vec<openrank_common::TrustEntry>, Error> {
-    let mut reader = csv::Reader::from_reader(file);
+    parse_trust_entries_with_filter(
+        file,
+        &NodeFilter::default(),
+        &mut FilterStats::default(),
+        Precision::F64,
+    )
+}
+
+/// Parses CSV or Parquet data from a file handle into TrustEntry vectors, dropping the edges
+/// outside the filter subgraph while reading. CSV values are parsed with the precision.
+pub fn parse_trust_entries_with_filter(
+    file: std::fs::File,
+    filter: &NodeFilter,
+    stats: &mut FilterStats,
+    precision: Precision,
+) -> Result<Vec<openrank_common::TrustEntry>, Error> {
     let mut entries = Vec::new();
 
-    for result in reader.records() {
-        let record: csv::StringRecord = result.map_err(Error::CsvError)?;
-        let (from, to, value): (String, String, f32) =
-            record.deserialize(None).map_err(Error::CsvError)?;
-        let trust_entry = openrank_common::TrustEntry::new(from, to, value);
-        entries.push(trust_entry);
+    for result in
+        openrank_common::format::trust_entries(file, precision).map_err(Error::CsvError)?
+    {
+        let trust_entry = result.map_err(Error::CsvError)?;
+        if filter.keep_trust(&trust_entry, stats) {
+            entries.push(trust_entry);
+        }
     }
 
     Ok(entries)
 }
 
-/// Parses CSV data from a file handle into ScoreEntry vectors.
+/// Parses CSV or Parquet data from a file handle into ScoreEntry vectors.
 pub fn parse_score_entries_from_file(
     file: std::fs::File,
 ) -> Result<Vec<openrank_common::ScoreEntry>, Error> {
-    let mut reader = csv::Reader::from_reader(file);
+    parse_score_entries_with_filter(
+        file,
+        &NodeFilter::default(),
+        &mut FilterStats::default(),
+        Precision::F64,
+    )
+}
+
+/// Parses CSV or Parquet data from a file handle into ScoreEntry vectors, dropping the entries
+/// of nodes outside the filter while reading. CSV values are parsed with the precision.
+pub fn parse_score_entries_with_filter(
+    file: std::fs::File,
+    filter: &NodeFilter,
+    stats: &mut FilterStats,
+    precision: Precision,
+) -> Result<Vec<openrank_common::ScoreEntry>, Error> {
     let mut entries = Vec::new();
 
-    for result in reader.records() {
-        let record: csv::StringRecord = result.map_err(Error::CsvError)?;
-        let (id, value): (String, f32) = record.deserialize(None).map_err(Error::CsvError)?;
-        let score_entry = openrank_common::ScoreEntry::new(id, value);
-        entries.push(score_entry);
+    for result in
+        openrank_common::format::score_entries(file, precision).map_err(Error::CsvError)?
+    {
+        let score_entry = result.map_err(Error::CsvError)?;
+        if filter.keep_seed(&score_entry, stats) {
+            entries.push(score_entry);
+        }
     }
 
     Ok(entries)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openrank_common::params::{AlgoParams, EigenTrustParams};
+    use openrank_common::runner::ComputeRunner;
+    use openrank_common::{scheme, ScoreEntry, TrustEntry};
+
+    #[test]
+    fn should_keep_the_scores_id_and_root_of_f32_jobs() {
+        let trust = [
+            ("alice", "bob", 1.0),
+            ("alice", "carol", 2.0),
+            ("bob", "carol", 3.0),
+            ("carol", "alice", 1.0),
+            ("carol", "bob", 1.0),
+        ]
+        .map(|(from, to, value)| TrustEntry::new(from.to_string(), to.to_string(), value));
+        let params = AlgoParams::EigenTrust(EigenTrustParams::default());
+        let precision = params.precision();
+        assert_eq!(precision, Precision::F32);
+
+        let mut runner = ComputeRunner::new(precision);
+        runner.update_trust_map(trust.to_vec()).unwrap();
+        runner
+            .update_seed_map(vec![ScoreEntry::new("alice".to_string(), 1.0)])
+            .unwrap();
+        runner.compute(&params).unwrap();
+        runner
+            .create_compute_tree(scheme::for_precision(precision))
+            .unwrap();
+
+        let scores = runner.get_compute_scores().unwrap();
+        let (csv_bytes, scores_id) = create_csv_and_hash_from_scores(scores, precision).unwrap();
+        // Values, scores id and root of the job before the runner was generic over precision.
+        assert_eq!(
+            String::from_utf8(csv_bytes).unwrap(),
+            "i,v\nalice,0.38983044\nbob,0.2372881\ncarol,0.37288147\n"
+        );
+        assert_eq!(
+            hex::encode(scores_id),
+            "f1751b3d7baca2f3b088feaedc8baea6f4dba1ca541458aecfdf2d5777c996ee"
+        );
+        assert_eq!(
+            runner.get_root_hash().unwrap().to_hex(),
+            "1abf424e1f7284bc4d2e19f65d97c7e497f460b2d623f2c4141f56d9bf6744d0"
+        );
+    }
+}
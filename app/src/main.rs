@@ -1,73 +1,488 @@
-use alloy::hex::FromHex;
-use alloy::primitives::Address;
-use alloy::providers::ProviderBuilder;
-use alloy::rpc::client::RpcClient;
-use alloy::signers::local::coins_bip39::English;
-use alloy::signers::local::MnemonicBuilder;
-use alloy::transports::http::reqwest::Url;
-use aws_config::from_env;
+use alloy::hex;
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::signers::SignerSync;
+use aws_config::{from_env, Region};
 use aws_sdk_s3::Client;
 use dotenv::dotenv;
+use futures_util::future::try_join_all;
+use openrank_app::attestation::Attester;
+use openrank_app::enclave::{self, EnclaveClient};
+use openrank_app::health::{self, LoopStatus, NodeHealth};
+use openrank_app::maintenance::{self, MaintenanceReport};
+use openrank_app::notify::Notifier;
+use openrank_app::paths::{paths, PathManager};
+use openrank_app::quarantine::{self, Quarantine};
+use openrank_app::ratelimit::RateLimiter;
 use openrank_app::sol::OpenRankManager;
-use openrank_app::{computer, server};
+use openrank_app::tls::TlsCertificate;
+use openrank_app::txmanager::TxManager;
+use openrank_app::wallet::RoleWallet;
+use openrank_app::{audit, bootstrap, compat, computer, keyrotation, managers, repair, server};
+use openrank_common::eigenda::EigenDAProxyClient;
+use openrank_common::identity::{NodeIdentity, SignedNodeIdentity};
 use openrank_common::logs::setup_tracing;
-use tracing::info;
+use openrank_common::net::NetConfig;
+use openrank_common::rpc::RpcConfig;
+use openrank_common::storage::{
+    CompressedStorage, Compression, EigenDAStorage, LocalStorage, ParallelDownload, S3Config,
+    S3Storage, StorageBackend,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
 
-const BUCKET_NAME: &str = "openrank-data-dev";
-const BLOCK_HISTORY: u64 = 1000;
-const LOG_PULL_INTERVAL_SECONDS: u64 = 10;
+/// Blocks pulled on the first start, before any block checkpoint.
+const DEFAULT_BLOCK_HISTORY: u64 = 1000;
+const DEFAULT_LOG_PULL_INTERVAL_SECONDS: u64 = 10;
 const SERVER_PORT: u16 = 3000;
 
+/// Parses the block range of the `--audit-range <from> <to>` mode.
+fn audit_range() -> Result<Option<(u64, u64)>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(pos) = args.iter().position(|a| a == "--audit-range") else {
+        return Ok(None);
+    };
+    let block = |i: usize| {
+        args.get(i)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| "Usage: --audit-range <from_block> <to_block>".to_string())
+    };
+    Ok(Some((block(pos + 1)?, block(pos + 2)?)))
+}
+
+/// Returns the value of the `--flag <value>` option, if given.
+fn flag_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let pos = args.iter().position(|a| a == flag)?;
+    args.get(pos + 1).filter(|a| !a.starts_with("--")).cloned()
+}
+
+/// Parses the value of the `--flag <value>` option, or else of the environment variable.
+fn setting<T: std::str::FromStr>(flag: &str, var: &str) -> Result<Option<T>, String> {
+    let Some(value) = flag_value(flag).or_else(|| std::env::var(var).ok()) else {
+        return Ok(None);
+    };
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid {} (or {}): {}", flag, var, value))
+}
+
+/// Parses the artifact of the `--repair <artifact> [--force]` mode.
+fn repair_artifact() -> Result<Option<String>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(pos) = args.iter().position(|a| a == "--repair") else {
+        return Ok(None);
+    };
+    args.get(pos + 1)
+        .filter(|a| !a.starts_with("--"))
+        .cloned()
+        .map(Some)
+        .ok_or_else(|| "Usage: --repair <kind/id | id> [--force]".to_string())
+}
+
+/// Cancels the token on the first SIGINT or SIGTERM, so that the computer stops taking new
+/// events and finishes the job in flight. A second signal exits right away, jobs being
+/// checkpointed at every stage.
+async fn watch_shutdown(shutdown: CancellationToken) {
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            error!("Failed to listen for SIGTERM: {}", e);
+            return;
+        }
+    };
+    for i in 0..2 {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+        if i == 0 {
+            info!("Shutdown requested, finishing the job in flight (signal again to force)");
+            shutdown.cancel();
+        }
+    }
+    warn!("Forced shutdown, unfinished jobs resume on the next start");
+    std::process::exit(130);
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
     dotenv().ok();
     setup_tracing();
+    // The local state lives under the data directory, --data-dir taking precedence over DATA_DIR
+    if let Some(data_dir) = flag_value("--data-dir") {
+        PathManager::new(data_dir).init();
+    }
+    info!("Data directory: {}", paths().data_dir());
+
+    // On the parent instance, only serve credentials and secrets to the enclave
+    if std::env::args().any(|a| a == "--enclave-parent") {
+        let port = std::env::var("ENCLAVE_SECRETS_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(enclave::DEFAULT_SECRETS_PORT);
+        enclave::run_parent_helper(port)
+            .await
+            .map_err(|e| format!("Enclave parent helper failed: {}", e))?;
+        return Ok(());
+    }
 
     let rpc_url = std::env::var("CHAIN_RPC_URL").expect("CHAIN_RPC_URL must be set.");
-    let manager_address =
-        std::env::var("OPENRANK_MANAGER_ADDRESS").expect("OPENRANK_MANAGER_ADDRESS must be set.");
-    let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
-    let config = from_env().region("us-west-2").load().await;
-    let client = Client::new(&config);
-
-    let wallet = MnemonicBuilder::<English>::default()
-        .phrase(mnemonic)
-        .index(0)
-        .map_err(|e| format!("Failed to set mnemonic index: {}", e))?
-        .build()
-        .map_err(|e| format!("Failed to build wallet: {}", e))?;
+    // Comma separated managers, each optionally scoped to a prefix (`<address>=<prefix>`)
+    let managers = managers::parse_managers(
+        &std::env::var("OPENRANK_MANAGER_ADDRESS").expect("OPENRANK_MANAGER_ADDRESS must be set."),
+    )
+    .map_err(|e| format!("Invalid OPENRANK_MANAGER_ADDRESS: {}", e))?;
+    // Repairs, key rotations and audits act on a single manager
+    let single_manager = |mode: &str| match managers.as_slice() {
+        [manager] => Ok(manager.clone()),
+        _ => Err(format!(
+            "{} handles a single manager, set OPENRANK_MANAGER_ADDRESS to one of them",
+            mode
+        )),
+    };
+    // Lookback of the first start, later starts resuming from the block checkpoint
+    let block_history =
+        setting("--block-history", "BLOCK_HISTORY")?.unwrap_or(DEFAULT_BLOCK_HISTORY);
+    let log_pull_seconds = setting("--poll-interval", "LOG_PULL_INTERVAL_SECONDS")?
+        .unwrap_or(DEFAULT_LOG_PULL_INTERVAL_SECONDS);
+    if log_pull_seconds == 0 {
+        return Err("The poll interval must be at least 1 second".into());
+    }
+    // Reject unknown job params by default, set to "false" to ignore them
+    let strict_params = std::env::var("STRICT_JOB_PARAMS")
+        .map(|v| v != "false")
+        .unwrap_or(true);
+    // Refuse to start against a manager contract whose events don't match the bundled ABI,
+    // set to "true" to only warn
+    let allow_incompatible_contract = std::env::var("ALLOW_INCOMPATIBLE_CONTRACT")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    // Inside a Nitro Enclave, credentials and keys are delivered by the parent over vsock
+    let enclave = if std::env::args().any(|a| a == "--enclave") {
+        Some(EnclaveClient::from_env().map_err(|e| format!("Invalid enclave config: {}", e))?)
+    } else {
+        None
+    };
+
+    // Bucket, region and endpoint flags take precedence over the environment
+    let bucket = S3Config::from_env().with_overrides(
+        flag_value("--s3-bucket"),
+        flag_value("--s3-region"),
+        flag_value("--s3-endpoint"),
+    );
+    let region = Region::new(bucket.region.clone());
+    // Proxy, IPv6 preference and extra root certificates of the outbound connections
+    let net = NetConfig::from_env().map_err(|e| format!("Invalid network config: {}", e))?;
+    let loader = from_env().region(region).http_client(
+        net.s3_http_client()
+            .map_err(|e| format!("Failed to create S3 HTTP client: {}", e))?,
+    );
+    let config = match &enclave {
+        Some(enclave) => loader.credentials_provider(enclave.clone()).load().await,
+        None => loader.load().await,
+    };
+    // S3 compatible services (e.g. MinIO) are reached through a custom endpoint
+    let mut s3_config = aws_sdk_s3::config::Builder::from(&config);
+    if let Some(endpoint) = &bucket.endpoint {
+        s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+    }
+    let s3_storage = S3Storage::new(Client::from_conf(s3_config.build()), &bucket.bucket)
+        .with_parallel_download(ParallelDownload::from_env());
+    // A local directory replaces S3 when set, to run without AWS credentials
+    let local_storage = std::env::var("STORAGE_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(LocalStorage::new);
+    let mut storage: Arc<dyn StorageBackend> = match &local_storage {
+        Some(local_storage) => Arc::new(local_storage.clone()),
+        None => Arc::new(s3_storage.clone()),
+    };
+    // Artifacts are stored in EigenDA when a proxy is set, the storage above keeping the index
+    if let Ok(proxy_url) = std::env::var("EIGENDA_PROXY_URL") {
+        let http_client = net
+            .reqwest_client()
+            .map_err(|e| format!("Failed to create EigenDA HTTP client: {}", e))?;
+        let client = EigenDAProxyClient::with_client(proxy_url, http_client);
+        client
+            .health()
+            .await
+            .map_err(|e| format!("EigenDA proxy check failed: {}", e))?;
+        storage = Arc::new(EigenDAStorage::new(client, storage));
+    }
+    // The CSV artifacts are compressed when STORAGE_COMPRESSION is set, and compressed objects
+    // are always read back transparently
+    let compression =
+        Compression::from_env().map_err(|e| format!("Invalid storage compression: {}", e))?;
+    storage = Arc::new(CompressedStorage::new(storage, compression));
+    info!("Using storage: {}", storage.location());
+
+    let wallet = match &enclave {
+        Some(enclave) => RoleWallet::from_enclave("computer", enclave).await,
+        None => RoleWallet::from_env("computer"),
+    }
+    .map_err(|e| format!("Failed to load computer wallet: {}", e))?;
     info!("Wallet address: {}", wallet.address());
 
-    let rpc_url_parsed = Url::parse(&rpc_url)
-        .map_err(|e| format!("Failed to parse RPC URL '{}': {}", rpc_url, e))?;
+    // Restore a damaged S3 artifact from the local state, then exit
+    if let Some(artifact) = repair_artifact()? {
+        let storage = single_manager("--repair")?.storage(&storage);
+        let force = std::env::args().any(|a| a == "--force");
+        let report = repair::repair_artifact(storage.as_ref(), wallet.address(), &artifact, force)
+            .await
+            .map_err(|e| format!("Repair of {} failed: {}", artifact, e))?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let rpc_client = RpcConfig::from_env(&rpc_url)
+        .and_then(|c| c.client())
+        .map_err(|e| format!("Failed to create RPC client: {}", e))?;
     let provider_http = ProviderBuilder::new()
-        .wallet(wallet.clone())
-        .connect_client(RpcClient::new_http(rpc_url_parsed));
+        .wallet(wallet.signer().clone())
+        .connect_client(rpc_client);
+
+    for manager in &managers {
+        compat::ensure_compatible(&provider_http, manager.address, allow_incompatible_contract)
+            .await
+            .map_err(|e| format!("Contract compatibility check failed for {}: {}", manager, e))?;
+    }
+
+    // Rotate the signing key while the node is stopped, then exit. Runs before the balance
+    // check, so a drained or compromised key can still be rotated
+    if std::env::args().any(|a| a == "--rotate-key") {
+        if enclave.is_some() {
+            return Err(
+                "Key rotation isn't supported inside an enclave, rotate the key \
+                        delivered by the parent instead"
+                    .into(),
+            );
+        }
+        let manager = single_manager("--rotate-key")?;
+        let skip_registration = std::env::args().any(|a| a == "--skip-registration");
+        let signed = keyrotation::rotate_key(
+            &OpenRankManager::new(manager.address, provider_http.clone()),
+            &wallet,
+            manager.storage(&storage).as_ref(),
+            &manager.paths(),
+            skip_registration,
+        )
+        .await
+        .map_err(|e| format!("Key rotation failed: {}", e))?;
+        println!("{}", serde_json::to_string_pretty(&signed)?);
+        return Ok(());
+    }
+
+    wallet
+        .check_min_balance(&provider_http)
+        .await
+        .map_err(|e| format!("Wallet check failed: {}", e))?;
+
+    // Audit every result posted in the range, independently of the live loop, then exit.
+    // Results verified by a previous run are skipped, unless --reverify is given. Invalid
+    // results are only challenged with --challenge, and alerted of through the NOTIFY_* webhooks
+    if let Some((from_block, to_block)) = audit_range()? {
+        let manager = single_manager("--audit-range")?;
+        let state = audit::AuditStateDb::open(manager.paths().audit_state_dir())?;
+        let notifier = Notifier::from_env(
+            net.reqwest_client()
+                .map_err(|e| format!("Failed to create notification HTTP client: {}", e))?,
+        );
+        let tx_manager = if std::env::args().any(|a| a == "--challenge") {
+            Some(TxManager::from_env().map_err(|e| format!("Invalid transaction config: {}", e))?)
+        } else {
+            None
+        };
+        let signed = audit::run_audit(
+            &OpenRankManager::new(manager.address, provider_http.clone()),
+            &provider_http,
+            &wallet,
+            &manager.storage(&storage),
+            &state,
+            &notifier,
+            tx_manager.as_ref(),
+            from_block,
+            to_block,
+            strict_params,
+            std::env::args().any(|a| a == "--reverify"),
+        )
+        .await
+        .map_err(|e| format!("Audit failed: {}", e))?;
+        let path = audit::save_audit(&signed, &manager.paths())?;
+        let summary = &signed.summary;
+        println!(
+            "Audited {} results ({} from a previous run): {} valid, {} invalid, {} errors. Signed summary saved to {}",
+            summary.entries.len(),
+            summary.reused,
+            summary.valid,
+            summary.invalid,
+            summary.errors,
+            path
+        );
+        if summary.invalid > 0 {
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+
+    // The fingerprint of the certificate is published in the identity, attested in a TEE, for
+    // the clients to pin it (self-signed certificates are only trusted this way)
+    let tls_certificate =
+        TlsCertificate::from_env().map_err(|e| format!("Invalid TLS config: {}", e))?;
+    let tls_attestation = match (&tls_certificate, Attester::from_env()?) {
+        (Some(certificate), Some(attester)) => Some(
+            attester
+                .attest_tls_certificate(&certificate.fingerprint())
+                .map_err(|e| format!("Failed to attest TLS certificate: {}", e))?,
+        ),
+        _ => None,
+    };
+    let tls_acceptor = tls_certificate
+        .as_ref()
+        .map(TlsCertificate::acceptor)
+        .transpose()
+        .map_err(|e| format!("Invalid TLS config: {}", e))?;
 
-    let manager_address = Address::from_hex(manager_address)
-        .map_err(|e| format!("Failed to parse manager address: {}", e))?;
-    let manager_contract = OpenRankManager::new(manager_address, provider_http.clone());
+    let identity = NodeIdentity {
+        address: wallet.address(),
+        roles: vec!["computer".to_string()],
+        algo_ids: computer::SUPPORTED_ALGO_IDS.to_vec(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        attestation: None,
+        domains: managers.iter().map(|m| m.address.to_string()).collect(),
+        tls_certificate_sha256: tls_certificate
+            .as_ref()
+            .map(|certificate| hex::encode(certificate.fingerprint())),
+        tls_attestation,
+    };
+    let identity_bytes = identity
+        .signing_bytes()
+        .map_err(|e| format!("Failed to serialize node identity: {}", e))?;
+    let identity_signature = wallet
+        .signer()
+        .sign_message_sync(&identity_bytes)
+        .map_err(|e| format!("Failed to sign node identity: {}", e))?;
+
+    let storage_report = match &local_storage {
+        Some(local_storage) => bootstrap::check_local_storage(local_storage).await,
+        None => bootstrap::check_storage(&s3_storage, &bucket.region, bucket.endpoint.is_some())
+            .await
+            .map_err(|e| format!("Storage self-check failed: {}", e))?,
+    };
+    if !storage_report.is_ok() {
+        eprintln!("{}", storage_report);
+        return Err("Storage self-check failed, see the permission report above".into());
+    }
+    info!("Storage self-check passed for {}", storage.location());
+
+    // Periodically audit the local state, the first run happens on startup
+    let maintenance_report = Arc::new(Mutex::new(MaintenanceReport::default()));
+    let maintenance_interval = std::env::var("MAINTENANCE_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(maintenance::DEFAULT_MAINTENANCE_INTERVAL);
+    tokio::spawn(maintenance::run(
+        maintenance_interval,
+        managers.iter().map(|m| m.paths()).collect(),
+        maintenance_report.clone(),
+    ));
+
+    // Artifacts failing this many times in a row are quarantined until released via the admin API
+    let quarantine_threshold = std::env::var("QUARANTINE_AFTER_FAILURES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(quarantine::DEFAULT_QUARANTINE_THRESHOLD);
+    let quarantine = Arc::new(Mutex::new(
+        Quarantine::load(paths().quarantine_file(), quarantine_threshold)
+            .map_err(|e| format!("Failed to load quarantine: {}", e))?,
+    ));
+
+    // The node is reported degraded when the compute loop didn't poll the events for this long
+    let max_poll_age = std::env::var("HEALTH_MAX_POLL_AGE_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(health::DEFAULT_MAX_POLL_AGE);
+    let loop_status = Arc::new(Mutex::new(LoopStatus::default()));
+    let node_health = NodeHealth::new(
+        loop_status.clone(),
+        Some(provider_http.clone().erased()),
+        max_poll_age,
+    );
+
+    let server_state = server::ServerState::new(
+        SignedNodeIdentity::new(identity, identity_signature),
+        storage_report,
+        storage.clone(),
+        vec![wallet.status()],
+        maintenance_report,
+        quarantine.clone(),
+        std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty()),
+    )
+    .with_managers(managers.clone())
+    .with_rpc(Some(provider_http.clone().erased()))
+    .with_health(node_health)
+    .with_api_keys(
+        std::env::var("API_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+    )
+    .with_rate_limiter(RateLimiter::from_env())
+    .with_trust_forwarded_for(
+        std::env::var("TRUST_FORWARDED_FOR")
+            .map(|v| v == "true")
+            .unwrap_or(false),
+    );
 
     // Start the server in a background thread
     let server_addr = std::net::SocketAddr::from(([0, 0, 0, 0], SERVER_PORT));
     tokio::spawn(async move {
-        info!("Starting score-proof server on {}", server_addr);
-        if let Err(e) = server::run_server(server_addr).await {
+        match &tls_certificate {
+            Some(certificate) => info!(
+                "Starting score-proof server on https://{}, certificate SHA-256: {}",
+                server_addr,
+                hex::encode(certificate.fingerprint())
+            ),
+            None => info!("Starting score-proof server on {}", server_addr),
+        }
+        if let Err(e) = server::run_server(server_addr, server_state, tls_acceptor).await {
             eprintln!("Server failed: {}", e);
         }
     });
 
-    if let Err(e) = computer::run(
-        manager_contract,
-        provider_http,
-        client,
-        BUCKET_NAME,
-        BLOCK_HISTORY,
-        LOG_PULL_INTERVAL_SECONDS,
-    )
-    .await
-    {
+    let shutdown = CancellationToken::new();
+    tokio::spawn(watch_shutdown(shutdown.clone()));
+
+    // One compute loop per manager, sharing the wallet (and its nonces) and the quarantine
+    let tx_manager =
+        TxManager::from_env().map_err(|e| format!("Invalid transaction config: {}", e))?;
+    let loops = managers.iter().map(|manager| {
+        info!("Watching manager {}", manager);
+        computer::run(
+            OpenRankManager::new(manager.address, provider_http.clone()),
+            provider_http.clone(),
+            wallet.clone(),
+            tx_manager.clone(),
+            manager.storage(&storage),
+            manager.paths(),
+            block_history,
+            log_pull_seconds,
+            strict_params,
+            quarantine.clone(),
+            loop_status.clone(),
+            shutdown.clone(),
+        )
+    });
+    if let Err(e) = try_join_all(loops).await {
         eprintln!("Computer failed: {}", e);
         std::process::exit(1);
     }
@@ -0,0 +1,212 @@
+use crate::paths::{paths, PathManager};
+use crate::txqueue::TxIntent;
+use alloy::hex;
+use openrank_common::manifest::SignedComputeManifest;
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+/// Default interval between two maintenance runs.
+pub const DEFAULT_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Files modified more recently than this may still be written to, and are left alone.
+const MIN_FILE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Local directories holding content addressed artifacts, along with the file name suffix
+/// following the artifact id.
+fn artifact_dirs() -> [(String, &'static str); 5] {
+    let paths = paths();
+    [
+        (paths.trust_dir(), ""),
+        (paths.seed_dir(), ""),
+        (paths.scores_dir(), ".csv"),
+        (paths.scores_meta_dir(), ".csv"),
+        (paths.meta_cache_dir(), ""),
+    ]
+}
+
+/// Findings of the last maintenance run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceReport {
+    /// Unix timestamp (seconds) of the last run.
+    pub last_run: Option<u64>,
+    /// Number of cached artifacts whose hash was checked.
+    pub checked_files: u64,
+    /// Cached artifacts not matching their id. They were removed and will be downloaded again.
+    pub corrupted_files: Vec<String>,
+    /// Leftover temporary files that were removed.
+    pub removed_temp_files: u64,
+    /// Local manifests that can't be parsed or whose signature doesn't verify.
+    pub invalid_manifests: Vec<String>,
+    /// Queued transaction intents that can't be parsed. They were renamed to `*.corrupt`
+    /// so they don't block the queue.
+    pub invalid_intents: Vec<String>,
+}
+
+impl MaintenanceReport {
+    pub fn has_findings(&self) -> bool {
+        !self.corrupted_files.is_empty()
+            || !self.invalid_manifests.is_empty()
+            || !self.invalid_intents.is_empty()
+    }
+}
+
+fn is_settled(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| modified.elapsed().unwrap_or_default() >= MIN_FILE_AGE)
+        .unwrap_or(false)
+}
+
+pub(crate) fn hash_file(path: &Path) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Keccak256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Returns the settled files of the directory. Missing directories are treated as empty.
+fn settled_files(dir: &str) -> Vec<(std::path::PathBuf, String)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && is_settled(p))
+        .filter_map(|p| {
+            let name = p.file_name()?.to_str()?.to_string();
+            Some((p, name))
+        })
+        .collect()
+}
+
+/// Checks every cached artifact against its id, removing the corrupted ones.
+fn audit_artifacts(report: &mut MaintenanceReport) {
+    for (dir, suffix) in artifact_dirs() {
+        for (path, name) in settled_files(&dir) {
+            if name.ends_with(".part") {
+                if std::fs::remove_file(&path).is_ok() {
+                    report.removed_temp_files += 1;
+                }
+                continue;
+            }
+            let Some(id) = name.strip_suffix(suffix) else {
+                continue;
+            };
+            report.checked_files += 1;
+            match hash_file(&path) {
+                Ok(hash) if hash == id => {}
+                Ok(_) => {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        warn!("Failed to remove {}: {}", path.display(), e);
+                    }
+                    report.corrupted_files.push(path.display().to_string());
+                }
+                Err(e) => warn!("Failed to hash {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+/// Checks the local manifests parse and verify. They are only reported, since the
+/// score-proof server has no other local copy.
+fn audit_manifests(paths: &PathManager, report: &mut MaintenanceReport) {
+    for (path, _) in settled_files(&paths.manifest_dir()) {
+        let valid = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<SignedComputeManifest>(&bytes).ok())
+            .is_some_and(|manifest| manifest.verify().is_ok());
+        if !valid {
+            report.invalid_manifests.push(path.display().to_string());
+        }
+    }
+}
+
+/// Compacts the outbound transaction queue: removes leftover temporary files and moves
+/// unreadable intents aside.
+fn compact_tx_queue(paths: &PathManager, report: &mut MaintenanceReport) {
+    for (path, name) in settled_files(&paths.tx_queue_dir()) {
+        if name.ends_with(".part") {
+            if std::fs::remove_file(&path).is_ok() {
+                report.removed_temp_files += 1;
+            }
+            continue;
+        }
+        if !name.ends_with(".json") {
+            continue;
+        }
+        let valid = std::fs::read(&path)
+            .ok()
+            .is_some_and(|bytes| serde_json::from_slice::<TxIntent>(&bytes).is_ok());
+        if !valid {
+            if let Err(e) = std::fs::rename(&path, path.with_extension("corrupt")) {
+                warn!("Failed to move aside {}: {}", path.display(), e);
+            }
+            report.invalid_intents.push(path.display().to_string());
+        }
+    }
+}
+
+/// Audits the local state once: the shared artifact caches, and the manifests and queue of
+/// each manager, given by the paths of its local state.
+pub fn audit_local_state(managers: &[PathManager]) -> MaintenanceReport {
+    let mut report = MaintenanceReport::default();
+    for paths in managers {
+        compact_tx_queue(paths, &mut report);
+    }
+    audit_artifacts(&mut report);
+    for paths in managers {
+        audit_manifests(paths, &mut report);
+    }
+    report.last_run = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .ok();
+    report
+}
+
+/// Runs the maintenance task forever, publishing the last report for the metrics endpoint.
+pub async fn run(
+    interval: Duration,
+    managers: Vec<PathManager>,
+    report: Arc<Mutex<MaintenanceReport>>,
+) {
+    let managers = Arc::new(managers);
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let managers = managers.clone();
+        let audit = move || audit_local_state(&managers);
+        let new_report = match tokio::task::spawn_blocking(audit).await {
+            Ok(report) => report,
+            Err(e) => {
+                error!("Maintenance task failed: {}", e);
+                continue;
+            }
+        };
+        if new_report.has_findings() {
+            error!(
+                "Maintenance found corrupted local state: CorruptedFiles({:?}), InvalidManifests({:?}), InvalidIntents({:?})",
+                new_report.corrupted_files, new_report.invalid_manifests, new_report.invalid_intents
+            );
+        } else {
+            info!(
+                "Maintenance completed: CheckedFiles({}), RemovedTempFiles({})",
+                new_report.checked_files, new_report.removed_temp_files
+            );
+        }
+        *report.lock().unwrap() = new_report;
+    }
+}
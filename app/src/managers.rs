@@ -0,0 +1,104 @@
+use crate::error::Error as NodeError;
+use crate::paths::{paths, PathManager};
+use alloy::hex::FromHex;
+use alloy::primitives::Address;
+use openrank_common::storage::{is_valid_key_prefix, PrefixedStorage, StorageBackend};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A manager contract watched by the node, parsed from `<address>` or `<address>=<prefix>`.
+///
+/// A manager with a prefix keeps its objects under `<prefix>/` in the bucket and its local
+/// state under `managers/<prefix>/` in the data directory, so that the deployments sharing the
+/// node don't mix their jobs. A manager without one uses the bucket and data directory as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagerConfig {
+    pub address: Address,
+    pub prefix: Option<String>,
+}
+
+impl ManagerConfig {
+    /// Storage of the manager, the given one scoped to its prefix.
+    pub fn storage(&self, storage: &Arc<dyn StorageBackend>) -> Arc<dyn StorageBackend> {
+        match &self.prefix {
+            Some(prefix) => Arc::new(PrefixedStorage::new(storage.clone(), prefix)),
+            None => storage.clone(),
+        }
+    }
+
+    /// Paths of the local state of the manager.
+    pub fn paths(&self) -> PathManager {
+        match &self.prefix {
+            Some(prefix) => paths().scoped(prefix),
+            None => paths().clone(),
+        }
+    }
+}
+
+impl FromStr for ManagerConfig {
+    type Err = NodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match s.split_once('=') {
+            Some((address, prefix)) => (address.trim(), Some(prefix.trim())),
+            None => (s.trim(), None),
+        };
+        let address = Address::from_hex(address).map_err(|e| {
+            NodeError::ConfigError(format!("Invalid manager address '{}': {}", address, e))
+        })?;
+        let prefix = match prefix {
+            Some(prefix) if is_valid_key_prefix(prefix) => Some(prefix.to_string()),
+            Some(prefix) => {
+                return Err(NodeError::ConfigError(format!(
+                    "Invalid prefix '{}' of manager {}, use letters, digits, '-', '_' and '.'",
+                    prefix, address
+                )))
+            }
+            None => None,
+        };
+        Ok(Self { address, prefix })
+    }
+}
+
+impl Display for ManagerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.prefix {
+            Some(prefix) => write!(f, "{} ({})", self.address, prefix),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+/// Parses the comma separated managers of `OPENRANK_MANAGER_ADDRESS`. Addresses and prefixes
+/// must be unique, and at most one manager may go without a prefix.
+pub fn parse_managers(value: &str) -> Result<Vec<ManagerConfig>, NodeError> {
+    let managers = value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(ManagerConfig::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if managers.is_empty() {
+        return Err(NodeError::ConfigError(
+            "No manager address configured".to_string(),
+        ));
+    }
+    let mut addresses = HashSet::new();
+    let mut prefixes = HashSet::new();
+    for manager in &managers {
+        if !addresses.insert(manager.address) {
+            return Err(NodeError::ConfigError(format!(
+                "Manager {} is configured twice",
+                manager.address
+            )));
+        }
+        if !prefixes.insert(manager.prefix.clone()) {
+            return Err(NodeError::ConfigError(match &manager.prefix {
+                Some(prefix) => format!("Prefix '{}' is used by several managers", prefix),
+                None => "Only one manager may go without a prefix".to_string(),
+            }));
+        }
+    }
+    Ok(managers)
+}
@@ -0,0 +1,204 @@
+use alloy::primitives::{Address, TxHash};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Time a webhook is given to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Remaining challenge window under which a verification failure is reported as closing soon,
+/// when none is configured.
+pub const DEFAULT_WINDOW_WARNING_SECS: u64 = 3600;
+
+/// Event of the life of a challenge the operators are alerted of.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    /// A posted result doesn't verify, while its challenge window is open.
+    VerificationFailed {
+        compute_id: String,
+        computer: Option<Address>,
+        mismatched_sub_jobs: Vec<usize>,
+        /// Seconds left before the challenge window closes.
+        window_remaining_secs: u64,
+        /// Whether the window closes within the warning threshold.
+        closing_soon: bool,
+    },
+    /// The result was challenged, by this node or another challenger.
+    ChallengeSubmitted {
+        compute_id: String,
+        sub_job_id: u32,
+        challenger: Address,
+        tx_hash: Option<TxHash>,
+    },
+    /// The challenge of a result couldn't be submitted or reverted.
+    ChallengeFailed {
+        compute_id: String,
+        sub_job_id: u32,
+        error: String,
+    },
+    /// The challenge window of a result that doesn't verify closed without a challenge.
+    WindowExpired {
+        compute_id: String,
+        computer: Option<Address>,
+        mismatched_sub_jobs: Vec<usize>,
+    },
+}
+
+impl Notification {
+    /// One line description, the text of the Slack messages.
+    pub fn message(&self) -> String {
+        match self {
+            Self::VerificationFailed {
+                compute_id,
+                mismatched_sub_jobs,
+                window_remaining_secs,
+                closing_soon,
+                ..
+            } => format!(
+                "{}Verification failed for ComputeId({}), mismatched sub-jobs {:?}: challenge window closes in {}",
+                if *closing_soon { "[CLOSING SOON] " } else { "" },
+                compute_id,
+                mismatched_sub_jobs,
+                format_duration(*window_remaining_secs)
+            ),
+            Self::ChallengeSubmitted {
+                compute_id,
+                sub_job_id,
+                challenger,
+                tx_hash,
+            } => format!(
+                "ComputeId({}) challenged on SubJob({}) by {}{}",
+                compute_id,
+                sub_job_id,
+                challenger,
+                tx_hash
+                    .map(|tx_hash| format!(": Tx Hash({:#})", tx_hash))
+                    .unwrap_or_default()
+            ),
+            Self::ChallengeFailed {
+                compute_id,
+                sub_job_id,
+                error,
+            } => format!(
+                "Failed to challenge ComputeId({}) on SubJob({}): {}",
+                compute_id, sub_job_id, error
+            ),
+            Self::WindowExpired {
+                compute_id,
+                mismatched_sub_jobs,
+                ..
+            } => format!(
+                "Challenge window of ComputeId({}) closed without challenge, mismatched sub-jobs {:?}",
+                compute_id, mismatched_sub_jobs
+            ),
+        }
+    }
+}
+
+/// Formats a number of seconds as hours, minutes and seconds, e.g. `1h 02m 05s`.
+fn format_duration(secs: u64) -> String {
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+    }
+}
+
+/// Sends the notifications to the configured webhooks: a generic one, receiving the
+/// notification as JSON, and a Slack incoming webhook, receiving its message.
+///
+/// Notifications are best effort: failing to deliver one is logged and the caller goes on.
+#[derive(Clone)]
+pub struct Notifier {
+    client: reqwest::Client,
+    webhook_url: Option<String>,
+    slack_webhook_url: Option<String>,
+    window_warning_secs: u64,
+}
+
+impl Notifier {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            webhook_url: None,
+            slack_webhook_url: None,
+            window_warning_secs: DEFAULT_WINDOW_WARNING_SECS,
+        }
+    }
+
+    /// Reads the webhooks from `NOTIFY_WEBHOOK_URL` and `NOTIFY_SLACK_WEBHOOK_URL`, and the
+    /// warning threshold from `NOTIFY_WINDOW_WARNING_SECS`.
+    pub fn from_env(client: reqwest::Client) -> Self {
+        let url = |name: &str| std::env::var(name).ok().filter(|url| !url.is_empty());
+        let mut notifier = Self::new(client);
+        notifier.webhook_url = url("NOTIFY_WEBHOOK_URL");
+        notifier.slack_webhook_url = url("NOTIFY_SLACK_WEBHOOK_URL");
+        if let Some(secs) = std::env::var("NOTIFY_WINDOW_WARNING_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            notifier.window_warning_secs = secs;
+        }
+        notifier
+    }
+
+    pub fn with_webhook_url(mut self, url: impl Into<String>) -> Self {
+        self.webhook_url = Some(url.into());
+        self
+    }
+
+    pub fn with_slack_webhook_url(mut self, url: impl Into<String>) -> Self {
+        self.slack_webhook_url = Some(url.into());
+        self
+    }
+
+    pub fn with_window_warning_secs(mut self, secs: u64) -> Self {
+        self.window_warning_secs = secs;
+        self
+    }
+
+    /// Whether a window with this many seconds left is reported as closing soon.
+    pub fn is_closing_soon(&self, window_remaining_secs: u64) -> bool {
+        window_remaining_secs <= self.window_warning_secs
+    }
+
+    /// Whether any webhook is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.webhook_url.is_some() || self.slack_webhook_url.is_some()
+    }
+
+    /// Sends the notification to every configured webhook.
+    pub async fn notify(&self, notification: &Notification) {
+        if !self.is_enabled() {
+            return;
+        }
+        info!("Notifying: {}", notification.message());
+        if let Some(url) = &self.webhook_url {
+            self.post(url, notification).await;
+        }
+        if let Some(url) = &self.slack_webhook_url {
+            let body = serde_json::json!({ "text": notification.message() });
+            self.post(url, &body).await;
+        }
+    }
+
+    async fn post(&self, url: &str, body: &impl Serialize) {
+        let res = self
+            .client
+            .post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(body).expect("Serializable notification"))
+            .send()
+            .await
+            .and_then(|res| res.error_for_status());
+        if let Err(e) = res {
+            // The URL may embed a secret (e.g. Slack webhooks), only the host is logged
+            let host = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(ToString::to_string))
+                .unwrap_or_default();
+            warn!("Failed to send notification to {}: {}", host, e);
+        }
+    }
+}
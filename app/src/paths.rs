@@ -0,0 +1,157 @@
+use std::sync::OnceLock;
+
+/// Data directory when none is configured, the working directory.
+pub const DEFAULT_DATA_DIR: &str = ".";
+
+static PATHS: OnceLock<PathManager> = OnceLock::new();
+
+/// Locations of the local state of the node (cached artifacts, manifests, queues, keys...),
+/// all under the data directory, so that the node can run from a read-only image with the
+/// data directory on a mounted volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathManager {
+    data_dir: String,
+}
+
+impl PathManager {
+    pub fn new(data_dir: impl Into<String>) -> Self {
+        let data_dir: String = data_dir.into();
+        let trimmed = data_dir.trim_end_matches('/');
+        Self {
+            data_dir: if trimmed.is_empty() && !data_dir.is_empty() {
+                "/".to_string()
+            } else {
+                trimmed.to_string()
+            },
+        }
+    }
+
+    /// Reads the data directory from `DATA_DIR`, defaulting to the working directory.
+    pub fn from_env() -> Self {
+        match std::env::var("DATA_DIR") {
+            Ok(dir) if !dir.is_empty() => Self::new(dir),
+            _ => Self::new(DEFAULT_DATA_DIR),
+        }
+    }
+
+    /// Sets the paths of the node. Must be called before any path is used, returns whether
+    /// they were set.
+    pub fn init(self) -> bool {
+        PATHS.set(self).is_ok()
+    }
+
+    pub fn data_dir(&self) -> &str {
+        &self.data_dir
+    }
+
+    /// Paths of a manager contract sharing the node with others, under `managers/<name>`.
+    /// Only its state keyed by compute id (job state, tx queue, checkpoint and manifests) is
+    /// read from there, the artifact caches and keys being shared.
+    pub fn scoped(&self, name: &str) -> Self {
+        Self::new(self.path(&format!("managers/{}", name)))
+    }
+
+    fn path(&self, name: &str) -> String {
+        match self.data_dir.as_str() {
+            "/" => format!("/{}", name),
+            dir => format!("{}/{}", dir, name),
+        }
+    }
+
+    pub fn trust_dir(&self) -> String {
+        self.path("trust")
+    }
+
+    pub fn seed_dir(&self) -> String {
+        self.path("seed")
+    }
+
+    pub fn scores_dir(&self) -> String {
+        self.path("scores")
+    }
+
+    pub fn scores_meta_dir(&self) -> String {
+        self.path("scores-meta")
+    }
+
+    pub fn manifest_dir(&self) -> String {
+        self.path("manifest")
+    }
+
+    /// EigenTrust scores saved while computing, see `SCORE_CHECKPOINT_INTERVAL`.
+    pub fn score_checkpoint_dir(&self) -> String {
+        self.path("score-checkpoints")
+    }
+
+    /// Temporary files of the trust matrices kept on disk, see `OUT_OF_CORE_TRUST`.
+    pub fn trust_matrix_dir(&self) -> String {
+        self.path("trust-matrix")
+    }
+
+    /// Cache of the meta artifacts (job descriptions and results).
+    pub fn meta_cache_dir(&self) -> String {
+        self.path("meta-cache")
+    }
+
+    pub fn tx_queue_dir(&self) -> String {
+        self.path("txqueue")
+    }
+
+    pub fn job_state_dir(&self) -> String {
+        self.path("jobstate")
+    }
+
+    pub fn audit_dir(&self) -> String {
+        self.path("audits")
+    }
+
+    pub fn audit_state_dir(&self) -> String {
+        self.path("auditstate")
+    }
+
+    pub fn keys_dir(&self) -> String {
+        self.path("keys")
+    }
+
+    /// Gas spent by each role on the current day, see `RoleWallet::record_spend`.
+    pub fn spend_dir(&self) -> String {
+        self.path("spend")
+    }
+
+    pub fn checkpoint_file(&self) -> String {
+        self.path("checkpoint.json")
+    }
+
+    pub fn quarantine_file(&self) -> String {
+        self.path("quarantine.json")
+    }
+
+    pub fn rotation_log(&self) -> String {
+        self.path("keys/rotations.jsonl")
+    }
+
+    pub fn trust_file(&self, trust_id: &str) -> String {
+        format!("{}/{}", self.trust_dir(), trust_id)
+    }
+
+    pub fn seed_file(&self, seed_id: &str) -> String {
+        format!("{}/{}", self.seed_dir(), seed_id)
+    }
+
+    pub fn scores_file(&self, scores_id: &str) -> String {
+        format!("{}/{}.csv", self.scores_dir(), scores_id)
+    }
+
+    pub fn scores_meta_file(&self, metadata_id: &str) -> String {
+        format!("{}/{}.csv", self.scores_meta_dir(), metadata_id)
+    }
+
+    pub fn manifest_file(&self, compute_id: &str) -> String {
+        format!("{}/{}", self.manifest_dir(), compute_id)
+    }
+}
+
+/// Returns the paths of the node, read from `DATA_DIR` unless set with `PathManager::init`.
+pub fn paths() -> &'static PathManager {
+    PATHS.get_or_init(PathManager::from_env)
+}
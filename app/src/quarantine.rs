@@ -0,0 +1,154 @@
+use crate::error::Error as NodeError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, warn};
+
+/// Number of consecutive failures after which an artifact is quarantined.
+pub const DEFAULT_QUARANTINE_THRESHOLD: u32 = 3;
+
+/// Failures of a single S3 artifact (e.g. `trust/<id>`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactFailures {
+    /// Consecutive failures, reset when the artifact is processed successfully.
+    pub failures: u32,
+    pub last_error: String,
+    /// Unix timestamp (seconds) of the last failure.
+    pub last_failed_at: u64,
+    /// Unix timestamp (seconds) the artifact was quarantined at, if it is.
+    pub quarantined_at: Option<u64>,
+    /// Compute ids of the jobs that failed on the artifact.
+    pub compute_ids: BTreeSet<String>,
+}
+
+/// Tracks artifact failures, quarantining artifacts after repeated failures so the jobs
+/// using them are skipped until an operator releases them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Quarantine {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    threshold: u32,
+    pub artifacts: BTreeMap<String, ArtifactFailures>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl Quarantine {
+    /// Loads the persisted state, a missing file being an empty quarantine.
+    pub fn load(path: impl AsRef<Path>, threshold: u32) -> Result<Self, NodeError> {
+        let path = path.as_ref().to_path_buf();
+        let mut quarantine = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(NodeError::SerdeError)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                return Err(NodeError::FileError(format!(
+                    "Failed to read quarantine: {}",
+                    e
+                )))
+            }
+        };
+        quarantine.path = path;
+        quarantine.threshold = threshold.max(1);
+        Ok(quarantine)
+    }
+
+    fn save(&self) {
+        let tmp_path = self.path.with_extension("json.part");
+        let res = serde_json::to_vec_pretty(self)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| std::fs::write(&tmp_path, bytes))
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path));
+        if let Err(e) = res {
+            warn!("Failed to persist quarantine: {}", e);
+        }
+    }
+
+    /// Records a failure of the job on the artifact, quarantining it once the threshold is reached.
+    pub fn record_failure(&mut self, artifact: &str, compute_id: &str, error: &str) {
+        let threshold = self.threshold;
+        let entry = self.artifacts.entry(artifact.to_string()).or_default();
+        entry.failures += 1;
+        entry.last_error = error.to_string();
+        entry.last_failed_at = now();
+        entry.compute_ids.insert(compute_id.to_string());
+        if entry.quarantined_at.is_none() && entry.failures >= threshold {
+            entry.quarantined_at = Some(entry.last_failed_at);
+            error!(
+                "Artifact quarantined after {} failures, jobs using it are skipped until it is released: Artifact({}), ComputeIds({:?}), Error({})",
+                entry.failures, artifact, entry.compute_ids, error
+            );
+        }
+        self.save();
+    }
+
+    /// Resets the failures of artifacts that were processed successfully.
+    pub fn record_success<'a>(&mut self, artifacts: impl IntoIterator<Item = &'a str>) {
+        let mut changed = false;
+        for artifact in artifacts {
+            if self
+                .artifacts
+                .get(artifact)
+                .is_some_and(|e| e.quarantined_at.is_none())
+            {
+                self.artifacts.remove(artifact);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    /// Returns the first quarantined artifact among the ones of the job, adding the job to
+    /// the ones skipped because of it.
+    pub fn check<'a>(
+        &mut self,
+        artifacts: impl IntoIterator<Item = &'a str>,
+        compute_id: &str,
+    ) -> Option<String> {
+        let artifact = artifacts.into_iter().find(|artifact| {
+            self.artifacts
+                .get(*artifact)
+                .is_some_and(|e| e.quarantined_at.is_some())
+        })?;
+        let entry = self.artifacts.get_mut(artifact)?;
+        if entry.compute_ids.insert(compute_id.to_string()) {
+            self.save();
+        }
+        Some(artifact.to_string())
+    }
+
+    /// Returns the quarantined artifact the job is blocked on, if any.
+    pub fn blocking(&self, compute_id: &str) -> Option<&str> {
+        self.artifacts
+            .iter()
+            .find(|(_, e)| e.quarantined_at.is_some() && e.compute_ids.contains(compute_id))
+            .map(|(artifact, _)| artifact.as_str())
+    }
+
+    /// Returns the quarantined artifacts.
+    pub fn quarantined(&self) -> BTreeMap<String, ArtifactFailures> {
+        self.artifacts
+            .iter()
+            .filter(|(_, e)| e.quarantined_at.is_some())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Releases the artifact, so the jobs using it are processed again.
+    /// Returns whether the artifact was tracked.
+    pub fn release(&mut self, artifact: &str) -> bool {
+        let released = self.artifacts.remove(artifact).is_some();
+        if released {
+            self.save();
+        }
+        released
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Number of clients tracked, past which the least recently seen ones are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Tokens of a client, refilled continuously up to the burst.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Position of the client in the recency order.
+    seq: u64,
+}
+
+/// Buckets of the tracked clients, along with the order they were last seen in.
+#[derive(Default)]
+struct Clients {
+    buckets: HashMap<IpAddr, Bucket>,
+    recency: BTreeMap<u64, IpAddr>,
+    next_seq: u64,
+}
+
+/// Per-client rate limiter of the server endpoints, a token bucket per IP address: each
+/// request takes a token, the bucket holding up to `burst` tokens and being refilled with
+/// `per_minute` tokens a minute.
+///
+/// IPv6 clients are limited per /64 network, the addresses of a network being free to use for
+/// its owner. Up to `MAX_TRACKED_CLIENTS` clients are tracked, a new client making the least
+/// recently seen one forgotten (and start again from a full bucket).
+pub struct RateLimiter {
+    burst: f64,
+    refill_per_sec: f64,
+    max_clients: usize,
+    clients: Mutex<Clients>,
+}
+
+impl RateLimiter {
+    /// Limits every client to `per_minute` requests a minute, in bursts of up to `burst`
+    /// requests (both at least 1).
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            burst: burst.max(1) as f64,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            max_clients: MAX_TRACKED_CLIENTS,
+            clients: Mutex::new(Clients::default()),
+        }
+    }
+
+    /// Reads the limit from `RATE_LIMIT_PER_MINUTE` and the burst from `RATE_LIMIT_BURST`,
+    /// defaulting to the limit. Requests aren't limited when no limit (or 0) is set.
+    pub fn from_env() -> Option<Self> {
+        let per_minute: u32 = std::env::var("RATE_LIMIT_PER_MINUTE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|per_minute| *per_minute > 0)?;
+        let burst = std::env::var("RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(per_minute);
+        Some(Self::new(per_minute, burst))
+    }
+
+    /// Takes a token of the client. Returns the number of seconds until the next token when
+    /// the client has none left.
+    pub fn check(&self, client: IpAddr) -> Result<(), u64> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: IpAddr, now: Instant) -> Result<(), u64> {
+        let client = client_key(client);
+        let mut clients = self.clients.lock().unwrap();
+        let Clients {
+            buckets,
+            recency,
+            next_seq,
+        } = &mut *clients;
+        let seq = *next_seq;
+        *next_seq += 1;
+        if !buckets.contains_key(&client) && buckets.len() >= self.max_clients {
+            if let Some((_, forgotten)) = recency.pop_first() {
+                buckets.remove(&forgotten);
+            }
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+            seq,
+        });
+        recency.remove(&bucket.seq);
+        recency.insert(seq, client);
+        bucket.seq = seq;
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        let tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.burst);
+        bucket.updated = now;
+        if tokens >= 1.0 {
+            bucket.tokens = tokens - 1.0;
+            return Ok(());
+        }
+        bucket.tokens = tokens;
+        Err(((1.0 - tokens) / self.refill_per_sec).ceil() as u64)
+    }
+}
+
+/// Address the client is limited by: its /64 network for IPv6 (IPv4-mapped addresses being
+/// IPv4 clients), its address for IPv4.
+fn client_key(client: IpAddr) -> IpAddr {
+    match client.to_canonical() {
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(
+            u128::from(ip) & 0xffff_ffff_ffff_ffff_0000_0000_0000_0000,
+        )),
+        ip => ip,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn should_limit_bursts_and_refill() {
+        // 6 requests a minute: a token every 10s
+        let limiter = RateLimiter::new(6, 3);
+        let client: IpAddr = "10.0.0.1".parse().unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(client, start), Ok(()));
+        }
+        assert_eq!(limiter.check_at(client, start), Err(10));
+        // Other clients have their own bucket
+        assert_eq!(limiter.check_at("10.0.0.2".parse().unwrap(), start), Ok(()));
+
+        let at = |secs| start + Duration::from_secs(secs);
+        assert_eq!(limiter.check_at(client, at(4)), Err(6));
+        assert_eq!(limiter.check_at(client, at(10)), Ok(()));
+        assert_eq!(limiter.check_at(client, at(10)), Err(10));
+        // Refilled up to the burst only
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(client, at(1000)), Ok(()));
+        }
+        assert_eq!(limiter.check_at(client, at(1000)), Err(10));
+    }
+
+    #[test]
+    fn should_limit_ipv6_clients_per_network() {
+        let limiter = RateLimiter::new(60, 1);
+        let now = Instant::now();
+        assert_eq!(
+            limiter.check_at("2001:db8::1".parse().unwrap(), now),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.check_at("2001:db8::ffff:1234".parse().unwrap(), now),
+            Err(1)
+        );
+        assert_eq!(
+            limiter.check_at("2001:db8:0:1::1".parse().unwrap(), now),
+            Ok(())
+        );
+        // IPv4-mapped addresses share the bucket of the IPv4 address
+        assert_eq!(limiter.check_at("10.0.0.1".parse().unwrap(), now), Ok(()));
+        assert_eq!(
+            limiter.check_at("::ffff:10.0.0.1".parse().unwrap(), now),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn should_forget_least_recently_seen_clients() {
+        let mut limiter = RateLimiter::new(60, 1);
+        limiter.max_clients = 2;
+        let now = Instant::now();
+        let [a, b, c]: [IpAddr; 3] =
+            ["10.0.0.1", "10.0.0.2", "10.0.0.3"].map(|ip| ip.parse().unwrap());
+        assert_eq!(limiter.check_at(a, now), Ok(()));
+        assert_eq!(limiter.check_at(b, now), Ok(()));
+        assert_eq!(limiter.check_at(a, now), Err(1));
+        // b is the least recently seen, a keeps its empty bucket
+        assert_eq!(limiter.check_at(c, now), Ok(()));
+        assert_eq!(limiter.check_at(a, now), Err(1));
+        let clients = limiter.clients.lock().unwrap();
+        assert_eq!(clients.buckets.len(), 2);
+        assert_eq!(clients.recency.len(), 2);
+        assert!(!clients.buckets.contains_key(&b));
+    }
+}
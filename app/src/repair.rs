@@ -0,0 +1,150 @@
+use crate::error::Error as NodeError;
+use crate::maintenance::hash_file;
+use crate::paths::paths;
+use crate::{download_object_as_bytes, upload_file_streaming};
+use alloy::hex;
+use alloy::primitives::Address;
+use openrank_common::manifest::SignedComputeManifest;
+use openrank_common::storage::StorageBackend;
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Kinds of artifacts kept locally, along with the local directory and the file name suffix
+/// following the artifact id.
+fn local_artifacts() -> [(&'static str, String, &'static str); 6] {
+    let paths = paths();
+    [
+        ("trust", paths.trust_dir(), ""),
+        ("seed", paths.seed_dir(), ""),
+        ("scores", paths.scores_dir(), ".csv"),
+        ("scores-meta", paths.scores_meta_dir(), ".csv"),
+        ("meta", paths.meta_cache_dir(), ""),
+        ("manifest", paths.manifest_dir(), ""),
+    ]
+}
+
+/// State of the stored object before the repair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteState {
+    Intact,
+    Missing,
+    Corrupted,
+}
+
+/// Outcome of the repair of an artifact.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairReport {
+    /// Storage key of the artifact, e.g. `trust/<id>`.
+    pub key: String,
+    /// Local copy the artifact was restored from.
+    pub local_path: String,
+    pub remote: RemoteState,
+    /// Whether the local copy was uploaded.
+    pub uploaded: bool,
+}
+
+/// Checks the bytes are a valid copy of the artifact: content addressed artifacts must hash
+/// to their id, manifests must be signed by the producer for the compute id.
+fn is_valid_copy(kind: &str, id: &str, producer: Address, bytes: &[u8]) -> bool {
+    if kind != "manifest" {
+        return hex::encode(Keccak256::digest(bytes)) == id;
+    }
+    serde_json::from_slice::<SignedComputeManifest>(bytes).is_ok_and(|signed| {
+        signed.verify().is_ok()
+            && signed.manifest.compute_id == id
+            && signed.manifest.computer == producer
+    })
+}
+
+/// Finds the local copy of the artifact, given as `<kind>/<id>` or as a bare id, in which
+/// case every local cache is searched. Returns the kind, id and local path.
+fn locate(artifact: &str) -> Result<(&'static str, String, String), NodeError> {
+    let (kind, id) = match artifact.split_once('/') {
+        Some((kind, id)) => (Some(kind), id),
+        None => (None, artifact),
+    };
+    let id = id.trim_start_matches("0x").to_lowercase();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(NodeError::ConfigError(format!(
+            "Invalid artifact id: {}",
+            artifact
+        )));
+    }
+    let mut kinds = local_artifacts()
+        .into_iter()
+        .filter(|(k, _, _)| kind.is_none_or(|kind| kind == *k))
+        .peekable();
+    if kinds.peek().is_none() {
+        return Err(NodeError::ConfigError(format!(
+            "Unknown artifact kind '{}', expected one of trust, seed, scores, scores-meta, meta, manifest",
+            kind.unwrap_or_default()
+        )));
+    }
+    kinds
+        .map(|(kind, dir, suffix)| (kind, format!("{}/{}{}", dir, id, suffix)))
+        .find(|(_, path)| Path::new(path).is_file())
+        .map(|(kind, path)| (kind, id.clone(), path))
+        .ok_or_else(|| NodeError::NotFound(format!("No local copy of {}", artifact)))
+}
+
+/// Restores a damaged or deleted stored artifact from the local state, without recomputing it.
+/// The local copy is verified first, and only uploaded if the stored object is missing or doesn't
+/// verify, unless `force` is set.
+pub async fn repair_artifact(
+    storage: &dyn StorageBackend,
+    producer: Address,
+    artifact: &str,
+    force: bool,
+) -> Result<RepairReport, NodeError> {
+    let (kind, id, local_path) = locate(artifact)?;
+    let key = format!("{}/{}", kind, id);
+
+    let local_valid = if kind == "manifest" {
+        let bytes = std::fs::read(&local_path)
+            .map_err(|e| NodeError::FileError(format!("Failed to read {}: {}", local_path, e)))?;
+        is_valid_copy(kind, &id, producer, &bytes)
+    } else {
+        hash_file(Path::new(&local_path))
+            .map_err(|e| NodeError::FileError(format!("Failed to hash {}: {}", local_path, e)))?
+            == id
+    };
+    if !local_valid {
+        return Err(NodeError::IntegrityError(format!(
+            "Local copy {} doesn't verify, refusing to upload it",
+            local_path
+        )));
+    }
+
+    // The object is old, so a missing one is reported right away instead of retried
+    let remote = match download_object_as_bytes(storage, &key, Some(0)).await {
+        Ok(bytes) if is_valid_copy(kind, &id, producer, &bytes) => RemoteState::Intact,
+        Ok(_) => RemoteState::Corrupted,
+        Err(NodeError::NotFound(_)) => RemoteState::Missing,
+        Err(e) => return Err(e),
+    };
+    if remote == RemoteState::Intact && !force {
+        info!("Artifact {} is intact, nothing to repair", key);
+        return Ok(RepairReport {
+            key,
+            local_path,
+            remote,
+            uploaded: false,
+        });
+    }
+
+    warn!(
+        "Repairing artifact {} ({:?}) from {}",
+        key, remote, local_path
+    );
+    upload_file_streaming(storage, &key, &local_path).await?;
+    info!("Artifact {} restored", key);
+    Ok(RepairReport {
+        key,
+        local_path,
+        remote,
+        uploaded: true,
+    })
+}
@@ -1,43 +1,284 @@
-use axum::{extract::Query, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use crate::bootstrap::StorageReport;
+use crate::download_meta_pair;
+use crate::health::{HealthReport, NodeHealth};
+use crate::maintenance::MaintenanceReport;
+use crate::managers::ManagerConfig;
+use crate::paths::{paths, PathManager};
+use crate::quarantine::{ArtifactFailures, Quarantine};
+use crate::ratelimit::RateLimiter;
+use crate::tls::serve_tls;
+use crate::wallet::WalletStatus;
+use alloy::primitives::{Address, U256};
+use alloy::providers::DynProvider;
+use axum::{
+    extract::{ConnectInfo, Path as UrlPath, Query, Request, State},
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderMap, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
 use openrank_common::{
-    merkle::{fixed::DenseMerkleTree, hash_leaf, Hash},
-    parse_score_entries_from_file, JobResult,
+    contract::ManagerReader,
+    identity::SignedNodeIdentity,
+    manifest::{ComputeManifest, SignedComputeManifest, SubJobManifest},
+    merkle::{fixed::DenseMerkleTree, Hash},
+    parse_score_entries_from_file,
+    proof::ScoreProof,
+    ranking::top_scores,
+    scheme::{self, Scheme, SCHEMES},
+    score_meta::{parse_metadata_csv, ScoreMetadata},
+    storage::{Error as StorageError, StorageBackend},
+    JobDescription, JobResult, ScoreEntry, ScoreEntryReader,
 };
 use serde::{Deserialize, Serialize};
-use sha3::Keccak256;
-use std::{fs::File, net::SocketAddr, path::Path};
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    fs::File,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use tokio_rustls::TlsAcceptor;
 use tracing::{error, info};
 
+/// State shared between the server handlers
+#[derive(Clone)]
+pub struct ServerState {
+    /// The signed identity of this node
+    pub identity: Arc<SignedNodeIdentity>,
+    /// The result of the storage self-check performed on startup
+    pub storage_report: Arc<StorageReport>,
+    /// Storage the artifacts missing locally (manifests, scores) are fetched from
+    pub storage: Arc<dyn StorageBackend>,
+    /// Managers whose jobs are served, each scoped to its prefix in the storage and data
+    /// directory. The storage and data directory are used as is when empty
+    pub managers: Arc<[ManagerConfig]>,
+    /// RPC the manifests missing from the storage are rebuilt from, using the request and
+    /// result posted on-chain
+    pub rpc: Option<DynProvider>,
+    /// Balance and spending of the wallet of each role
+    pub wallets: Vec<Arc<Mutex<WalletStatus>>>,
+    /// Findings of the last local state maintenance run
+    pub maintenance: Arc<Mutex<MaintenanceReport>>,
+    /// Artifacts failing or quarantined by the compute loop
+    pub quarantine: Arc<Mutex<Quarantine>>,
+    /// Bearer token of the admin endpoints, which are disabled when not set
+    pub admin_token: Option<Arc<str>>,
+    /// State of the components reported by /healthz and /readyz
+    pub health: NodeHealth,
+    /// API keys accepted by the score endpoints, which are open when there are none
+    pub api_keys: Arc<HashSet<String>>,
+    /// Per-client rate limit of the score endpoints, unlimited when not set
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Whether clients are identified by the X-Forwarded-For header set by a reverse proxy,
+    /// rather than by the address of the connection
+    pub trust_forwarded_for: bool,
+}
+
+impl ServerState {
+    pub fn new(
+        identity: SignedNodeIdentity,
+        storage_report: StorageReport,
+        storage: Arc<dyn StorageBackend>,
+        wallets: Vec<Arc<Mutex<WalletStatus>>>,
+        maintenance: Arc<Mutex<MaintenanceReport>>,
+        quarantine: Arc<Mutex<Quarantine>>,
+        admin_token: Option<String>,
+    ) -> Self {
+        Self {
+            identity: Arc::new(identity),
+            storage_report: Arc::new(storage_report),
+            storage,
+            managers: Arc::from([]),
+            rpc: None,
+            wallets,
+            maintenance,
+            quarantine,
+            admin_token: admin_token.map(Arc::from),
+            health: NodeHealth::default(),
+            api_keys: Arc::new(HashSet::new()),
+            rate_limiter: None,
+            trust_forwarded_for: false,
+        }
+    }
+
+    pub fn with_managers(mut self, managers: Vec<ManagerConfig>) -> Self {
+        self.managers = Arc::from(managers);
+        self
+    }
+
+    pub fn with_rpc(mut self, rpc: Option<DynProvider>) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    pub fn with_health(mut self, health: NodeHealth) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn with_api_keys(mut self, api_keys: HashSet<String>) -> Self {
+        self.api_keys = Arc::new(api_keys);
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter.map(Arc::new);
+        self
+    }
+
+    pub fn with_trust_forwarded_for(mut self, trust_forwarded_for: bool) -> Self {
+        self.trust_forwarded_for = trust_forwarded_for;
+        self
+    }
+}
+
+/// Storage and local paths of the jobs of one manager.
+pub struct ManagerScope {
+    /// Address of the manager, none when the node isn't configured with managers
+    pub address: Option<Address>,
+    pub storage: Arc<dyn StorageBackend>,
+    pub paths: PathManager,
+    /// Reader of the manager contract, when the address of the manager and an RPC are known
+    pub reader: Option<ManagerReader<DynProvider>>,
+}
+
+impl ServerState {
+    fn scope(&self, manager: Option<&ManagerConfig>) -> ManagerScope {
+        match manager {
+            Some(manager) => ManagerScope {
+                address: Some(manager.address),
+                storage: manager.storage(&self.storage),
+                paths: manager.paths(),
+                reader: self
+                    .rpc
+                    .clone()
+                    .map(|rpc| ManagerReader::new(manager.address, rpc)),
+            },
+            None => ManagerScope {
+                address: None,
+                storage: self.storage.clone(),
+                paths: paths().clone(),
+                reader: None,
+            },
+        }
+    }
+
+    /// Resolves the `manager` parameter of a request. It may be left out when the node serves
+    /// a single manager, and then names the manager without a prefix.
+    pub fn manager_scope(&self, manager: Option<&str>) -> Result<ManagerScope, ServerError> {
+        let config = match manager {
+            Some(manager) => {
+                let address = Address::from_str(manager).map_err(|_| {
+                    ServerError::BadRequest(format!("Invalid manager address: {}", manager))
+                })?;
+                let config = self.managers.iter().find(|m| m.address == address);
+                Some(config.ok_or_else(|| {
+                    ServerError::NotFound(format!("Manager not served: {}", address))
+                })?)
+            }
+            None if self.managers.len() <= 1 => self.managers.first(),
+            None => Some(
+                self.managers
+                    .iter()
+                    .find(|m| m.prefix.is_none())
+                    .ok_or_else(|| {
+                        ServerError::BadRequest(
+                            "Several managers are served, set the manager parameter".to_string(),
+                        )
+                    })?,
+            ),
+        };
+        Ok(self.scope(config))
+    }
+
+    /// Scopes of every served manager.
+    pub fn manager_scopes(&self) -> Vec<ManagerScope> {
+        if self.managers.is_empty() {
+            return vec![self.scope(None)];
+        }
+        self.managers.iter().map(|m| self.scope(Some(m))).collect()
+    }
+}
+
 /// Query parameters for the /score-proof endpoint
 #[derive(Debug, Deserialize)]
 pub struct ScoreProofQuery {
-    /// The compute ID (hex-encoded hash of the meta job results)
+    /// The compute ID, as assigned by the manager contract
     pub compute_id: String,
+    /// Address of the manager contract, needed when the node serves several
+    pub manager: Option<String>,
     /// The user ID to get the score proof for
     pub user_id: String,
 }
 
 /// Response structure containing the score inclusion proof
+pub type ScoreProofResponse = ScoreProof;
+
+/// Maximum number of users proven by one /score-proofs request
+const MAX_BATCH_USER_IDS: usize = 10_000;
+
+/// Body of the /score-proofs endpoint
+#[derive(Debug, Deserialize)]
+pub struct ScoreProofsRequest {
+    /// The compute ID, as assigned by the manager contract
+    pub compute_id: String,
+    /// Address of the manager contract, needed when the node serves several
+    pub manager: Option<String>,
+    /// The user IDs to get the score proofs for
+    pub user_ids: Vec<String>,
+}
+
+/// Response of the /score-proofs endpoint
 #[derive(Debug, Serialize)]
-pub struct ScoreProofResponse {
-    /// The compute ID
+pub struct ScoreProofsResponse {
+    /// The proofs of the users found, in the order of the request
+    pub proofs: Vec<ScoreProofResponse>,
+    /// The users not found in any job
+    pub not_found: Vec<String>,
+}
+
+/// Scores returned by a /scores request when no limit is given
+const DEFAULT_SCORES_LIMIT: usize = 100;
+/// Maximum number of scores returned by one /scores request
+const MAX_SCORES_LIMIT: usize = 10_000;
+
+/// Query parameters for the /scores endpoint
+#[derive(Debug, Deserialize)]
+pub struct ScoresQuery {
+    /// The compute ID, as assigned by the manager contract
     pub compute_id: String,
-    /// The user ID
-    pub user_id: String,
-    /// The user's score value
-    pub score: f32,
-    /// The index of the score in the scores tree
-    pub score_index: usize,
-    /// Merkle path for the score in the scores tree (leaf to root)
-    pub scores_tree_path: Vec<Hash>,
-    /// The scores tree root (commitment)
-    pub scores_tree_root: Hash,
-    /// The index of this job's commitment in the meta tree
-    pub meta_index: usize,
-    /// Merkle path for the commitment in the meta tree (leaf to root)
-    pub meta_tree_path: Vec<Hash>,
-    /// The meta tree root (final commitment)
-    pub meta_tree_root: Hash,
+    /// Address of the manager contract, needed when the node serves several
+    pub manager: Option<String>,
+    /// Index of the job in the meta job, the first one by default
+    #[serde(default)]
+    pub sub_job: usize,
+    /// Number of scores skipped, in ranking order
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// Only the scores greater than or equal to this one are returned and counted
+    pub min_score: Option<f64>,
+}
+
+/// Response of the /scores endpoint
+#[derive(Debug, Serialize)]
+pub struct ScoresResponse {
+    pub compute_id: String,
+    pub sub_job: usize,
+    pub scores_id: String,
+    /// Number of scores of the job, above `min_score` if given
+    pub total: usize,
+    pub offset: usize,
+    /// The scores of the page, in ranking order
+    pub scores: Vec<ScoreEntry>,
 }
 
 /// Error response structure
@@ -50,6 +291,10 @@ pub struct ErrorResponse {
 #[derive(Debug)]
 pub enum ServerError {
     NotFound(String),
+    BadRequest(String),
+    Unauthorized(String),
+    /// Rate limit reached, with the number of seconds to wait before retrying
+    TooManyRequests(u64),
     InternalError(String),
 }
 
@@ -57,47 +302,275 @@ impl IntoResponse for ServerError {
     fn into_response(self) -> axum::response::Response {
         let (status, message) = match self {
             ServerError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            ServerError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ServerError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            ServerError::TooManyRequests(retry_after) => {
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, retry_after.to_string())],
+                    Json(ErrorResponse {
+                        error: format!("Rate limit reached, retry in {}s", retry_after),
+                    }),
+                )
+                    .into_response();
+            }
             ServerError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
         (status, Json(ErrorResponse { error: message })).into_response()
     }
 }
 
-/// Handler for the /score-proof endpoint
-async fn score_proof_handler(
-    Query(params): Query<ScoreProofQuery>,
-) -> Result<Json<ScoreProofResponse>, ServerError> {
+/// Makes sure an artifact is on the local disk, downloading it from the storage when missing,
+/// e.g. after the node was redeployed without its local state. Artifacts addressed by their
+/// Keccak256 hash are checked against `hash` before being cached. Returns whether the
+/// artifact is available.
+async fn ensure_local_copy(
+    storage: &dyn StorageBackend,
+    local_path: &str,
+    key: &str,
+    hash: Option<&str>,
+) -> Result<bool, ServerError> {
+    if Path::new(local_path).exists() {
+        return Ok(true);
+    }
+    let bytes = match storage.get(key).await {
+        Ok(bytes) => bytes,
+        Err(StorageError::NotFound(_)) => return Ok(false),
+        Err(e) => {
+            error!("Failed to download {}: {}", key, e);
+            return Err(ServerError::InternalError(format!(
+                "Failed to download {}: {}",
+                key, e
+            )));
+        }
+    };
+    if let Some(hash) = hash {
+        if alloy::hex::encode(Keccak256::digest(&bytes)) != hash.trim_start_matches("0x") {
+            error!("Downloaded {} does not match its hash", key);
+            return Err(ServerError::InternalError(format!(
+                "{} does not match its hash",
+                key
+            )));
+        }
+    }
+    cache_local_copy(storage, local_path, key, &bytes).await?;
+    Ok(true)
+}
+
+/// Writes a downloaded artifact to the local disk, through a temporary file so that a
+/// concurrent request never reads a partial file.
+async fn cache_local_copy(
+    storage: &dyn StorageBackend,
+    local_path: &str,
+    key: &str,
+    bytes: &[u8],
+) -> Result<(), ServerError> {
+    let tmp_path = format!("{}.part", local_path);
+    let res = async {
+        if let Some(dir) = Path::new(local_path).parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, local_path).await
+    }
+    .await;
+    if let Err(e) = res {
+        error!("Failed to cache {} to {}: {}", key, local_path, e);
+        return Err(ServerError::InternalError(format!(
+            "Failed to cache {}: {}",
+            key, e
+        )));
+    }
+    info!("Fetched {} from {}", local_path, storage.location());
+    Ok(())
+}
+
+/// Reads the extended scores artifact of a job, if the node has it. The artifact is optional,
+/// so failing to read it doesn't fail the proofs.
+async fn load_job_metadata(
+    storage: &dyn StorageBackend,
+    sub_job: &SubJobManifest,
+    metadata_id: &str,
+) -> Option<Vec<ScoreMetadata>> {
+    let metadata_path = paths().scores_meta_file(metadata_id);
+    let key = sub_job.scores_meta_key(metadata_id);
+    ensure_local_copy(storage, &metadata_path, &key, Some(metadata_id))
+        .await
+        .ok()?;
+    let metadata_file = File::open(&metadata_path).ok()?;
+    parse_metadata_csv(metadata_file)
+        .inspect_err(|e| error!("Failed to parse metadata file {}: {}", metadata_path, e))
+        .ok()
+}
+
+/// Compute ids are decimal integers, anything else can't name a manifest.
+pub(crate) fn is_compute_id(compute_id: &str) -> bool {
+    !compute_id.is_empty() && compute_id.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_manifest(bytes: &[u8]) -> Result<SignedComputeManifest, ServerError> {
+    serde_json::from_slice(bytes).map_err(|e| {
+        error!("Failed to parse manifest file: {}", e);
+        ServerError::InternalError(format!("Failed to parse manifest: {}", e))
+    })
+}
+
+/// Rebuilds the manifest of a compute whose manifest was never uploaded, e.g. when the computer
+/// stopped right after posting the result, from the request and the result posted on-chain and
+/// the meta artifacts they reference. It isn't signed, so it isn't cached.
+async fn manifest_from_chain(
+    storage: &dyn StorageBackend,
+    reader: &ManagerReader<DynProvider>,
+    compute_id: &str,
+) -> Result<ComputeManifest, ServerError> {
+    let not_found = || ServerError::NotFound(format!("Compute ID not found: {}", compute_id));
+    let id = U256::from_str(compute_id).map_err(|_| not_found())?;
+    let (request, result) =
+        tokio::try_join!(reader.request(id), reader.result(id)).map_err(|e| {
+            error!("Failed to read compute {} on-chain: {}", compute_id, e);
+            ServerError::InternalError(format!("Failed to read compute on-chain: {}", e))
+        })?;
+    let (Some(request), Some(result)) = (request, result) else {
+        return Err(not_found());
+    };
+    let job_description_id = alloy::hex::encode(request.job_description_id);
+    let results_id = alloy::hex::encode(result.results_id);
+    let (jobs, results): (Vec<JobDescription>, Vec<JobResult>) =
+        download_meta_pair(storage, &job_description_id, &results_id, None)
+            .await
+            .map_err(|e| {
+                error!(
+                    "Failed to download the meta of compute {}: {}",
+                    compute_id, e
+                );
+                ServerError::InternalError(format!("Failed to download the meta: {}", e))
+            })?;
+    if jobs.len() != results.len() {
+        return Err(ServerError::InternalError(format!(
+            "{} job descriptions for {} job results",
+            jobs.len(),
+            results.len()
+        )));
+    }
     info!(
-        "Received score-proof request for compute_id: {}, user_id: {}",
-        params.compute_id, params.user_id
+        "Rebuilt the manifest of compute {} from the chain",
+        compute_id
     );
+    Ok(ComputeManifest::new(
+        compute_id.to_string(),
+        result.computer,
+        job_description_id,
+        results_id,
+        alloy::hex::encode(result.meta_commitment),
+        &jobs,
+        &results,
+    ))
+}
 
-    // Load job results from local file system
-    let meta_path = format!("./meta/{}", params.compute_id);
-    let meta_file = File::open(&meta_path).map_err(|e| {
-        error!("Failed to open meta file {}: {}", meta_path, e);
-        ServerError::NotFound(format!("Compute ID not found: {}", params.compute_id))
-    })?;
+/// Loads the compute manifest, fetching it from the storage when missing locally. A fetched
+/// manifest is only cached and served once its signature is verified. Without a manifest in
+/// the storage, it is rebuilt from the chain when the manager can be read.
+pub(crate) async fn load_manifest(
+    scope: &ManagerScope,
+    compute_id: &str,
+) -> Result<ComputeManifest, ServerError> {
+    let storage = scope.storage.as_ref();
+    if !is_compute_id(compute_id) {
+        return Err(ServerError::BadRequest(format!(
+            "Invalid compute id: {}",
+            compute_id
+        )));
+    }
+    let manifest_path = scope.paths.manifest_file(compute_id);
+    if let Ok(bytes) = tokio::fs::read(&manifest_path).await {
+        return Ok(parse_manifest(&bytes)?.manifest);
+    }
 
-    let job_results: Vec<JobResult> = serde_json::from_reader(meta_file).map_err(|e| {
-        error!("Failed to parse meta file: {}", e);
-        ServerError::InternalError(format!("Failed to parse job results: {}", e))
-    })?;
+    let key = format!("manifest/{}", compute_id);
+    let bytes = match storage.get(&key).await {
+        Ok(bytes) => bytes,
+        Err(StorageError::NotFound(_)) => {
+            return match &scope.reader {
+                Some(reader) => manifest_from_chain(storage, reader, compute_id).await,
+                None => Err(ServerError::NotFound(format!(
+                    "Compute ID not found: {}",
+                    compute_id
+                ))),
+            };
+        }
+        Err(e) => {
+            error!("Failed to download {}: {}", key, e);
+            return Err(ServerError::InternalError(format!(
+                "Failed to download {}: {}",
+                key, e
+            )));
+        }
+    };
+    let signed_manifest = parse_manifest(&bytes)?;
+    if let Err(e) = signed_manifest.verify() {
+        error!("Downloaded {} has an invalid signature: {}", key, e);
+        return Err(ServerError::InternalError(format!(
+            "{} has an invalid signature: {}",
+            key, e
+        )));
+    }
+    cache_local_copy(storage, &manifest_path, &key, &bytes).await?;
+    Ok(signed_manifest.manifest)
+}
+
+/// Builds the score proofs of the users of a compute. The scores tree of a job is built once,
+/// for all the requested users in it, and the meta tree once for all the proofs. Each user is
+/// proven in the first job scoring it; the users scored by no job are returned apart.
+async fn build_score_proofs(
+    scope: &ManagerScope,
+    compute_id: &str,
+    user_ids: &[String],
+) -> Result<(Vec<ScoreProofResponse>, Vec<String>), ServerError> {
+    let manifest = load_manifest(scope, compute_id).await?;
+    let storage = scope.storage.as_ref();
+    let job_results = &manifest.sub_jobs;
 
     if job_results.is_empty() {
         return Err(ServerError::NotFound("No job results found".to_string()));
     }
 
-    // Find which job contains the user and build the trees
-    let mut found_job_index: Option<usize> = None;
-    let mut found_score_index: Option<usize> = None;
-    let mut found_score_value: Option<f32> = None;
-    let mut scores_tree: Option<DenseMerkleTree<Keccak256>> = None;
+    // Build the meta tree from all job commitments
+    let commitment_hashes: Vec<Hash> = job_results
+        .iter()
+        .map(|jr| {
+            let commitment_bytes = alloy::hex::decode(&jr.commitment).unwrap_or_default();
+            Hash::from_slice(&commitment_bytes)
+        })
+        .collect();
+
+    let meta_tree = DenseMerkleTree::<Keccak256>::new(commitment_hashes).map_err(|e| {
+        error!("Failed to build meta tree: {}", e);
+        ServerError::InternalError(format!("Failed to build meta tree: {}", e))
+    })?;
+
+    let meta_tree_root = meta_tree.root().map_err(|e| {
+        error!("Failed to get meta tree root: {}", e);
+        ServerError::InternalError(format!("Failed to get meta tree root: {}", e))
+    })?;
+
+    let mut pending: HashSet<&str> = user_ids.iter().map(String::as_str).collect();
+    let mut proofs: HashMap<&str, ScoreProofResponse> = HashMap::new();
 
     for (job_idx, job_result) in job_results.iter().enumerate() {
-        let scores_path = format!("./scores/{}.csv", job_result.scores_id);
+        if pending.is_empty() {
+            break;
+        }
 
-        if !Path::new(&scores_path).exists() {
+        let scores_path = paths().scores_file(&job_result.scores_id);
+        let scores_key = job_result.scores_key();
+        if !ensure_local_copy(
+            storage,
+            &scores_path,
+            &scores_key,
+            Some(&job_result.scores_id),
+        )
+        .await?
+        {
             continue;
         }
 
@@ -111,92 +584,211 @@ async fn score_proof_handler(
             ServerError::InternalError(format!("Failed to parse scores: {}", e))
         })?;
 
-        // Check if user exists in this job's scores
-        for (score_idx, entry) in score_entries.iter().enumerate() {
-            if entry.id() == &params.user_id {
-                found_job_index = Some(job_idx);
-                found_score_index = Some(score_idx);
-                found_score_value = Some(*entry.value());
+        // Find the requested users scored by this job
+        let found: Vec<(usize, &str)> = score_entries
+            .iter()
+            .enumerate()
+            .filter_map(|(score_idx, entry)| {
+                pending
+                    .get(entry.id().as_str())
+                    .map(|user_id| (score_idx, *user_id))
+            })
+            .collect();
+        if found.is_empty() {
+            continue;
+        }
+
+        // Build the scores merkle tree, with the leaf encoding and hash function of the job's scheme
+        let scheme_version = manifest.scheme_version(job_result);
+        let scheme = scheme::find(&scheme_version).ok_or_else(|| {
+            ServerError::InternalError(format!("Unknown scheme: {}", scheme_version))
+        })?;
+        let score_hashes: Vec<Hash> = score_entries
+            .iter()
+            .map(|e| scheme.hash_leaf(*e.value()))
+            .collect();
+        let scores_tree = scheme.hash.dense_tree(score_hashes).map_err(|e| {
+            error!("Failed to build scores tree: {}", e);
+            ServerError::InternalError(format!("Failed to build scores tree: {}", e))
+        })?;
 
-                // Build the scores merkle tree
-                let score_hashes: Vec<Hash> = score_entries
-                    .iter()
-                    .map(|e| hash_leaf::<Keccak256>(e.value().to_be_bytes().to_vec()))
-                    .collect();
+        let scores_tree_root = scores_tree.root().map_err(|e| {
+            error!("Failed to get scores tree root: {}", e);
+            ServerError::InternalError(format!("Failed to get scores tree root: {}", e))
+        })?;
 
-                scores_tree = Some(DenseMerkleTree::<Keccak256>::new(score_hashes).map_err(
-                    |e| {
-                        error!("Failed to build scores tree: {}", e);
-                        ServerError::InternalError(format!("Failed to build scores tree: {}", e))
-                    },
-                )?);
+        // Generate meta tree path
+        let meta_tree_path = meta_tree.generate_path(job_idx).map_err(|e| {
+            error!("Failed to generate meta tree path: {}", e);
+            ServerError::InternalError(format!("Failed to generate meta tree path: {}", e))
+        })?;
 
-                break;
-            }
+        let metadata = match &job_result.metadata_id {
+            Some(metadata_id) => load_job_metadata(storage, job_result, metadata_id).await,
+            None => None,
+        };
+
+        for (score_index, user_id) in found {
+            // Generate scores tree path
+            let scores_tree_path = scores_tree.generate_path(score_index).map_err(|e| {
+                error!("Failed to generate scores tree path: {}", e);
+                ServerError::InternalError(format!("Failed to generate scores tree path: {}", e))
+            })?;
+            let metadata = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(score_index))
+                .filter(|entry| entry.id == user_id)
+                .cloned();
+
+            pending.remove(user_id);
+            proofs.insert(
+                user_id,
+                ScoreProofResponse {
+                    compute_id: compute_id.to_string(),
+                    user_id: user_id.to_string(),
+                    score: *score_entries[score_index].value(),
+                    score_index,
+                    scores_tree_path,
+                    scores_tree_root: scores_tree_root.clone(),
+                    meta_index: job_idx,
+                    meta_tree_path: meta_tree_path.clone(),
+                    meta_tree_root: meta_tree_root.clone(),
+                    scheme_version: scheme_version.clone(),
+                    metadata,
+                },
+            );
         }
+    }
 
-        if found_job_index.is_some() {
-            break;
+    // Proofs are returned in the order of the request
+    let mut ordered = Vec::with_capacity(proofs.len());
+    let mut not_found = Vec::new();
+    for user_id in user_ids {
+        match proofs.remove(user_id.as_str()) {
+            Some(proof) => ordered.push(proof),
+            None if pending.remove(user_id.as_str()) => not_found.push(user_id.clone()),
+            None => {}
         }
     }
+    Ok((ordered, not_found))
+}
 
-    let job_index = found_job_index.ok_or_else(|| {
+/// Handler for the /score-proof endpoint. The manifest and scores missing locally are
+/// fetched from the storage and cached.
+async fn score_proof_handler(
+    State(state): State<ServerState>,
+    Query(params): Query<ScoreProofQuery>,
+) -> Result<Json<ScoreProofResponse>, ServerError> {
+    info!(
+        "Received score-proof request for compute_id: {}, user_id: {}",
+        params.compute_id, params.user_id
+    );
+
+    let scope = state.manager_scope(params.manager.as_deref())?;
+    let (mut proofs, _) = build_score_proofs(
+        &scope,
+        &params.compute_id,
+        std::slice::from_ref(&params.user_id),
+    )
+    .await?;
+    let response = proofs.pop().ok_or_else(|| {
         ServerError::NotFound(format!("User {} not found in any job", params.user_id))
     })?;
-    let score_index = found_score_index.unwrap();
-    let score_value = found_score_value.unwrap();
-    let scores_tree = scores_tree.unwrap();
-
-    // Generate scores tree path
-    let scores_tree_path = scores_tree.generate_path(score_index).map_err(|e| {
-        error!("Failed to generate scores tree path: {}", e);
-        ServerError::InternalError(format!("Failed to generate scores tree path: {}", e))
-    })?;
 
-    let scores_tree_root = scores_tree.root().map_err(|e| {
-        error!("Failed to get scores tree root: {}", e);
-        ServerError::InternalError(format!("Failed to get scores tree root: {}", e))
-    })?;
+    info!("Successfully generated score proof");
+    Ok(Json(response))
+}
 
-    // Build the meta tree from all job commitments
-    let commitment_hashes: Vec<Hash> = job_results
-        .iter()
-        .map(|jr| {
-            let commitment_bytes = alloy::hex::decode(&jr.commitment).unwrap_or_default();
-            Hash::from_slice(&commitment_bytes)
-        })
-        .collect();
+/// Handler for the /score-proofs endpoint, proving many users of a compute at once
+async fn score_proofs_handler(
+    State(state): State<ServerState>,
+    Json(request): Json<ScoreProofsRequest>,
+) -> Result<Json<ScoreProofsResponse>, ServerError> {
+    info!(
+        "Received score-proofs request for compute_id: {}, {} user(s)",
+        request.compute_id,
+        request.user_ids.len()
+    );
+    if request.user_ids.len() > MAX_BATCH_USER_IDS {
+        return Err(ServerError::BadRequest(format!(
+            "At most {} user ids per request",
+            MAX_BATCH_USER_IDS
+        )));
+    }
 
-    let meta_tree = DenseMerkleTree::<Keccak256>::new(commitment_hashes).map_err(|e| {
-        error!("Failed to build meta tree: {}", e);
-        ServerError::InternalError(format!("Failed to build meta tree: {}", e))
-    })?;
+    let scope = state.manager_scope(request.manager.as_deref())?;
+    let (proofs, not_found) =
+        build_score_proofs(&scope, &request.compute_id, &request.user_ids).await?;
 
-    // Generate meta tree path
-    let meta_tree_path = meta_tree.generate_path(job_index).map_err(|e| {
-        error!("Failed to generate meta tree path: {}", e);
-        ServerError::InternalError(format!("Failed to generate meta tree path: {}", e))
-    })?;
+    info!(
+        "Successfully generated {} score proof(s), {} user(s) not found",
+        proofs.len(),
+        not_found.len()
+    );
+    Ok(Json(ScoreProofsResponse { proofs, not_found }))
+}
 
-    let meta_tree_root = meta_tree.root().map_err(|e| {
-        error!("Failed to get meta tree root: {}", e);
-        ServerError::InternalError(format!("Failed to get meta tree root: {}", e))
+/// Handler for the /scores endpoint, returning a page of the scores of a job in ranking order.
+/// The scores file is streamed, only the scores up to the end of the page are kept.
+async fn scores_handler(
+    State(state): State<ServerState>,
+    Query(params): Query<ScoresQuery>,
+) -> Result<Json<ScoresResponse>, ServerError> {
+    let limit = params.limit.unwrap_or(DEFAULT_SCORES_LIMIT);
+    if limit > MAX_SCORES_LIMIT {
+        return Err(ServerError::BadRequest(format!(
+            "At most {} scores per request",
+            MAX_SCORES_LIMIT
+        )));
+    }
+    let scope = state.manager_scope(params.manager.as_deref())?;
+    let manifest = load_manifest(&scope, &params.compute_id).await?;
+    let job_result = manifest.sub_jobs.get(params.sub_job).ok_or_else(|| {
+        ServerError::NotFound(format!(
+            "Job {} not found in compute {}",
+            params.sub_job, params.compute_id
+        ))
     })?;
 
-    let response = ScoreProofResponse {
-        compute_id: params.compute_id,
-        user_id: params.user_id,
-        score: score_value,
-        score_index,
-        scores_tree_path,
-        scores_tree_root,
-        meta_index: job_index,
-        meta_tree_path,
-        meta_tree_root,
-    };
+    let scores_path = paths().scores_file(&job_result.scores_id);
+    let scores_key = job_result.scores_key();
+    if !ensure_local_copy(
+        scope.storage.as_ref(),
+        &scores_path,
+        &scores_key,
+        Some(&job_result.scores_id),
+    )
+    .await?
+    {
+        return Err(ServerError::NotFound(format!(
+            "Scores not found: {}",
+            job_result.scores_id
+        )));
+    }
 
-    info!("Successfully generated score proof");
-    Ok(Json(response))
+    let (offset, min_score) = (params.offset, params.min_score);
+    let (scores, total) = tokio::task::spawn_blocking(move || {
+        let scores_file = File::open(&scores_path).map_err(|e| {
+            ServerError::InternalError(format!("Failed to open scores file: {}", e))
+        })?;
+        let scores = ScoreEntryReader::new(scores_file).filter(|score| match (score, min_score) {
+            (Ok(score), Some(min_score)) => *score.value() >= min_score,
+            _ => true,
+        });
+        top_scores(scores, offset, limit)
+            .map_err(|e| ServerError::InternalError(format!("Failed to parse scores: {}", e)))
+    })
+    .await
+    .map_err(|e| ServerError::InternalError(format!("Scores task failed: {}", e)))??;
+
+    Ok(Json(ScoresResponse {
+        compute_id: params.compute_id,
+        sub_job: params.sub_job,
+        scores_id: job_result.scores_id.clone(),
+        total,
+        offset,
+        scores,
+    }))
 }
 
 /// Health check endpoint
@@ -204,21 +796,407 @@ async fn health_handler() -> &'static str {
     "OK"
 }
 
+/// Handler for the /identity endpoint
+async fn identity_handler(State(state): State<ServerState>) -> Json<SignedNodeIdentity> {
+    Json(state.identity.as_ref().clone())
+}
+
+/// Schemes endpoint, listing every known score and commitment scheme
+async fn schemes_handler() -> Json<&'static [Scheme]> {
+    Json(SCHEMES)
+}
+
+fn health_response(report: HealthReport) -> (StatusCode, Json<HealthReport>) {
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Liveness endpoint, failing when the compute loop stopped polling the events
+async fn liveness_handler(State(state): State<ServerState>) -> impl IntoResponse {
+    health_response(state.health.liveness())
+}
+
+/// Readiness endpoint, checking the storage, the RPC and the compute loop
+async fn ready_handler(State(state): State<ServerState>) -> impl IntoResponse {
+    health_response(
+        state
+            .health
+            .readiness(state.storage.as_ref(), &state.storage_report)
+            .await,
+    )
+}
+
+/// Checks the bearer token of an admin request. The digests of the tokens are compared rather
+/// than the tokens, so that the time taken doesn't tell how much of the token was guessed.
+fn authorize_admin(state: &ServerState, headers: &HeaderMap) -> Result<(), ServerError> {
+    let Some(token) = &state.admin_token else {
+        return Err(ServerError::NotFound("Admin API disabled".to_string()));
+    };
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let matches = provided.is_some_and(|provided| {
+        Keccak256::digest(provided.as_bytes()) == Keccak256::digest(token.as_bytes())
+    });
+    if !matches {
+        return Err(ServerError::Unauthorized("Invalid admin token".to_string()));
+    }
+    Ok(())
+}
+
+/// Header carrying the API key of the score endpoints
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Address of the client of a request: the first address of X-Forwarded-For when the server
+/// is behind a trusted proxy, the address of the connection otherwise.
+fn client_ip(state: &ServerState, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+    let forwarded = || {
+        headers
+            .get("x-forwarded-for")?
+            .to_str()
+            .ok()?
+            .split(',')
+            .next()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    state
+        .trust_forwarded_for
+        .then(forwarded)
+        .flatten()
+        .unwrap_or(peer.ip())
+}
+
+/// Guards the score endpoints, building trees or reading whole scores files per request:
+/// checks the API key when keys are configured, then the rate limit of the client.
+async fn score_access(
+    State(state): State<ServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ServerError> {
+    if !state.api_keys.is_empty() {
+        let provided = request
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok());
+        if !provided.is_some_and(|key| state.api_keys.contains(key)) {
+            return Err(ServerError::Unauthorized("Invalid API key".to_string()));
+        }
+    }
+    if let Some(rate_limiter) = &state.rate_limiter {
+        let client = client_ip(&state, request.headers(), peer);
+        rate_limiter
+            .check(client)
+            .map_err(ServerError::TooManyRequests)?;
+    }
+    Ok(next.run(request).await)
+}
+
+/// Admin endpoint listing the failing and quarantined artifacts
+async fn quarantine_handler(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+) -> Result<Json<BTreeMap<String, ArtifactFailures>>, ServerError> {
+    authorize_admin(&state, &headers)?;
+    Ok(Json(state.quarantine.lock().unwrap().artifacts.clone()))
+}
+
+/// Admin endpoint releasing an artifact, e.g. `DELETE /admin/quarantine/trust/<id>`,
+/// so the jobs using it are processed again
+async fn release_handler(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    UrlPath((kind, id)): UrlPath<(String, String)>,
+) -> Result<StatusCode, ServerError> {
+    authorize_admin(&state, &headers)?;
+    let artifact = format!("{}/{}", kind, id);
+    if !state.quarantine.lock().unwrap().release(&artifact) {
+        return Err(ServerError::NotFound(format!(
+            "Artifact {} not tracked",
+            artifact
+        )));
+    }
+    info!("Artifact released from quarantine: {}", artifact);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Metrics endpoint, in the Prometheus text format
+async fn metrics_handler(State(state): State<ServerState>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# TYPE openrank_wallet_balance_wei gauge");
+    for wallet in &state.wallets {
+        let status = wallet.lock().unwrap();
+        let _ = writeln!(
+            out,
+            "openrank_wallet_balance_wei{{role=\"{}\",address=\"{}\"}} {}",
+            status.role, status.address, status.balance
+        );
+    }
+    let _ = writeln!(out, "# TYPE openrank_wallet_spent_today_wei gauge");
+    for wallet in &state.wallets {
+        let status = wallet.lock().unwrap();
+        let _ = writeln!(
+            out,
+            "openrank_wallet_spent_today_wei{{role=\"{}\",address=\"{}\"}} {}",
+            status.role, status.address, status.spent_today
+        );
+    }
+
+    let maintenance = state.maintenance.lock().unwrap();
+    let gauges = [
+        (
+            "openrank_maintenance_last_run_timestamp",
+            maintenance.last_run.unwrap_or_default(),
+        ),
+        (
+            "openrank_maintenance_checked_files",
+            maintenance.checked_files,
+        ),
+        (
+            "openrank_maintenance_corrupted_files",
+            maintenance.corrupted_files.len() as u64,
+        ),
+        (
+            "openrank_maintenance_removed_temp_files",
+            maintenance.removed_temp_files,
+        ),
+        (
+            "openrank_maintenance_invalid_manifests",
+            maintenance.invalid_manifests.len() as u64,
+        ),
+        (
+            "openrank_maintenance_invalid_intents",
+            maintenance.invalid_intents.len() as u64,
+        ),
+    ];
+    drop(maintenance);
+    let quarantined = state.quarantine.lock().unwrap().quarantined().len() as u64;
+    for (name, value) in gauges
+        .into_iter()
+        .chain([("openrank_quarantined_artifacts", quarantined)])
+    {
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
 /// Create the router with all endpoints
-pub fn create_router() -> Router {
-    Router::new()
+pub fn create_router(state: ServerState) -> Router {
+    let score_routes = Router::new()
         .route("/score-proof", get(score_proof_handler))
+        .route("/score-proofs", post(score_proofs_handler))
+        .route("/scores", get(scores_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), score_access));
+    let router = Router::new()
+        .merge(score_routes)
         .route("/health", get(health_handler))
+        .route("/identity", get(identity_handler))
+        .route("/healthz", get(liveness_handler))
+        .route("/readyz", get(ready_handler))
+        .route("/schemes", get(schemes_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/admin/quarantine", get(quarantine_handler))
+        .route("/admin/quarantine/{kind}/{id}", delete(release_handler));
+    #[cfg(feature = "explorer")]
+    let router = router.merge(crate::explorer::router());
+    router.with_state(state)
 }
 
 /// Run the server on the specified address
-pub async fn run_server(addr: SocketAddr) -> Result<(), std::io::Error> {
-    let app = create_router();
+/// Runs the server, over TLS when an acceptor is given.
+pub async fn run_server(
+    addr: SocketAddr,
+    state: ServerState,
+    tls: Option<TlsAcceptor>,
+) -> Result<(), std::io::Error> {
+    let app = create_router(state);
 
     info!("Starting server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    if let Some(acceptor) = tls {
+        return serve_tls(listener, acceptor, app).await;
+    }
+    // The address of the clients is needed by the rate limiter
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::primitives::{Address, Signature, U256};
+    use openrank_common::identity::NodeIdentity;
+    use openrank_common::storage::LocalStorage;
+    use tower::ServiceExt;
+
+    fn state() -> ServerState {
+        let identity = NodeIdentity {
+            address: Address::ZERO,
+            roles: vec![],
+            algo_ids: vec![],
+            version: "test".to_string(),
+            attestation: None,
+            domains: vec![],
+            tls_certificate_sha256: None,
+            tls_attestation: None,
+        };
+        let storage_report = StorageReport {
+            bucket: "test".to_string(),
+            expected_region: "us-east-1".to_string(),
+            bucket_region: None,
+            custom_endpoint: false,
+            bucket_created: false,
+            prefixes: vec![],
+        };
+        ServerState::new(
+            SignedNodeIdentity::new(identity, Signature::new(U256::ZERO, U256::ZERO, false)),
+            storage_report,
+            Arc::new(LocalStorage::new(std::env::temp_dir())),
+            vec![],
+            Arc::new(Mutex::new(MaintenanceReport::default())),
+            Arc::new(Mutex::new(Quarantine::default())),
+            None,
+        )
+    }
+
+    async fn get_scores(router: &Router, api_key: Option<&str>) -> Response {
+        get(router, "/scores", api_key).await
+    }
+
+    async fn get(router: &Router, uri: &str, api_key: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(uri);
+        if let Some(api_key) = api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        let peer: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+        let request = request
+            .extension(ConnectInfo(peer))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_guard_score_endpoints() {
+        let router = create_router(
+            state()
+                .with_api_keys(HashSet::from(["key".to_string()]))
+                .with_rate_limiter(Some(RateLimiter::new(6, 2))),
+        );
+        for api_key in [None, Some("other")] {
+            let response = get_scores(&router, api_key).await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // Past the guard, the request is rejected for its missing query
+        for _ in 0..2 {
+            let response = get_scores(&router, Some("key")).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        let response = get_scores(&router, Some("key")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "10");
+
+        // Unguarded endpoints are not limited
+        let request = Request::builder()
+            .uri("/schemes")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_compute_ids() {
+        let router = create_router(state());
+        for uri in [
+            "/scores?compute_id=..%2F..%2Fsecrets",
+            "/score-proof?compute_id=0x1f&user_id=a",
+            "/score-proof?compute_id=&user_id=a",
+        ] {
+            let response = get(&router, uri, None).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+        assert!(is_compute_id("42"));
+        assert!(!is_compute_id("../42"));
+    }
+
+    #[test]
+    fn should_check_the_admin_token() {
+        let mut state = state();
+        state.admin_token = Some(Arc::from("secret"));
+        for (header, allowed) in [
+            (Some("Bearer secret"), true),
+            (Some("Bearer secre"), false),
+            (Some("secret"), false),
+            (None, false),
+        ] {
+            let mut headers = HeaderMap::new();
+            if let Some(header) = header {
+                headers.insert(AUTHORIZATION, header.parse().unwrap());
+            }
+            assert_eq!(authorize_admin(&state, &headers).is_ok(), allowed);
+        }
+    }
+
+    #[test]
+    fn should_resolve_the_manager_of_a_request() {
+        let a: ManagerConfig = "0x0000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let b: ManagerConfig = "0x0000000000000000000000000000000000000002=b"
+            .parse()
+            .unwrap();
+
+        // Without managers, the storage and data directory are used as is
+        let scope = state().manager_scope(None).unwrap();
+        assert_eq!((scope.address, scope.paths), (None, paths().clone()));
+
+        let state = state().with_managers(vec![a.clone(), b.clone()]);
+        let scope = state.manager_scope(None).unwrap();
+        assert_eq!(scope.address, Some(a.address));
+        let scope = state.manager_scope(Some(&b.address.to_string())).unwrap();
+        assert_eq!((scope.address, scope.paths), (Some(b.address), b.paths()));
+        assert!(matches!(
+            state.manager_scope(Some("0x0000000000000000000000000000000000000003")),
+            Err(ServerError::NotFound(_))
+        ));
+        assert!(matches!(
+            state.manager_scope(Some("b")),
+            Err(ServerError::BadRequest(_))
+        ));
+
+        // With prefixed managers only, the manager must be named
+        let state = state.with_managers(vec![b]);
+        assert!(state.manager_scope(None).is_ok());
+        let c: ManagerConfig = "0x0000000000000000000000000000000000000003=c"
+            .parse()
+            .unwrap();
+        let state = state.with_managers(vec![a.clone(), c.clone()]);
+        assert!(state.manager_scope(None).is_ok());
+        let state = state.with_managers(vec![
+            "0x0000000000000000000000000000000000000002=b"
+                .parse()
+                .unwrap(),
+            c,
+        ]);
+        assert!(matches!(
+            state.manager_scope(None),
+            Err(ServerError::BadRequest(_))
+        ));
+    }
+}
@@ -1,7 +1,7 @@
 use alloy::sol;
 
 sol!(
-    #[allow(missing_docs)]
+    #[allow(missing_docs, clippy::too_many_arguments)]
     #[sol(rpc)]
     OpenRankManager,
     "../contracts/out/OpenRankManager.sol/OpenRankManager.json"
@@ -0,0 +1,188 @@
+use crate::error::Error as NodeError;
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use openrank_common::attestation::certificate_fingerprint;
+use openssl::asn1::{Asn1Integer, Asn1Time};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::ServerConfig;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
+
+/// Time a client is given to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Validity of the self-signed certificates, generated again on every start.
+const SELF_SIGNED_VALIDITY_DAYS: u32 = 365;
+
+/// Certificate (chain) and private key the server is served over TLS with.
+pub struct TlsCertificate {
+    chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl TlsCertificate {
+    /// Reads the certificate chain (leaf first) and the private key from PEM files.
+    pub fn from_pem_files(cert_path: &str, key_path: &str) -> Result<Self, NodeError> {
+        let chain = CertificateDer::pem_file_iter(cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| tls_error(format!("Failed to read certificate {}: {}", cert_path, e)))?;
+        if chain.is_empty() {
+            return Err(tls_error(format!("No certificate in {}", cert_path)));
+        }
+        let key = PrivateKeyDer::from_pem_file(key_path)
+            .map_err(|e| tls_error(format!("Failed to read private key {}: {}", key_path, e)))?;
+        Ok(Self { chain, key })
+    }
+
+    /// Generates a self-signed certificate for the names (DNS names or IP addresses), with a
+    /// new P-256 key that never leaves the memory of the node. Clients pin its fingerprint,
+    /// published (and attested) in the identity of the node.
+    pub fn self_signed(names: &[String]) -> Result<Self, NodeError> {
+        let generate = || -> Result<(Vec<u8>, Vec<u8>), openssl::error::ErrorStack> {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+            let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+
+            let mut name = X509NameBuilder::new()?;
+            name.append_entry_by_nid(Nid::COMMONNAME, "openrank-node")?;
+            let name = name.build();
+            let mut serial = BigNum::new()?;
+            serial.rand(128, openssl::bn::MsbOption::MAYBE_ZERO, false)?;
+
+            let mut builder = X509::builder()?;
+            builder.set_version(2)?;
+            builder.set_serial_number(Asn1Integer::from_bn(&serial)?.as_ref())?;
+            builder.set_subject_name(&name)?;
+            builder.set_issuer_name(&name)?;
+            builder.set_pubkey(&key)?;
+            builder.set_not_before(Asn1Time::days_from_now(0)?.as_ref())?;
+            builder.set_not_after(Asn1Time::days_from_now(SELF_SIGNED_VALIDITY_DAYS)?.as_ref())?;
+            builder.append_extension(BasicConstraints::new().build()?)?;
+            if !names.is_empty() {
+                let mut alt_names = SubjectAlternativeName::new();
+                for name in names {
+                    match name.parse::<IpAddr>() {
+                        Ok(_) => alt_names.ip(name),
+                        Err(_) => alt_names.dns(name),
+                    };
+                }
+                let alt_names = alt_names.build(&builder.x509v3_context(None, None))?;
+                builder.append_extension(alt_names)?;
+            }
+            builder.sign(&key, MessageDigest::sha256())?;
+            Ok((builder.build().to_der()?, key.private_key_to_pkcs8()?))
+        };
+        let (cert, key) =
+            generate().map_err(|e| tls_error(format!("Failed to generate certificate: {}", e)))?;
+        Ok(Self {
+            chain: vec![CertificateDer::from(cert)],
+            key: PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key)),
+        })
+    }
+
+    /// Reads the certificate from `TLS_CERT_PATH` and `TLS_KEY_PATH`, or generates a
+    /// self-signed one for the comma separated `TLS_SELF_SIGNED_NAMES` when `TLS_SELF_SIGNED`
+    /// is set. The server is served over plain HTTP when neither is set.
+    pub fn from_env() -> Result<Option<Self>, NodeError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        match (var("TLS_CERT_PATH"), var("TLS_KEY_PATH")) {
+            (Some(cert_path), Some(key_path)) => {
+                return Self::from_pem_files(&cert_path, &key_path).map(Some)
+            }
+            (None, None) => {}
+            _ => {
+                return Err(NodeError::ConfigError(
+                    "TLS_CERT_PATH and TLS_KEY_PATH must be set together".to_string(),
+                ))
+            }
+        }
+        if var("TLS_SELF_SIGNED").as_deref() != Some("true") {
+            return Ok(None);
+        }
+        let names: Vec<String> = var("TLS_SELF_SIGNED_NAMES")
+            .unwrap_or_else(|| "localhost".to_string())
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        Self::self_signed(&names).map(Some)
+    }
+
+    /// DER encoding of the leaf certificate.
+    pub fn certificate_der(&self) -> &[u8] {
+        self.chain[0].as_ref()
+    }
+
+    /// SHA-256 fingerprint of the leaf certificate.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        certificate_fingerprint(self.certificate_der())
+    }
+
+    /// Builds the acceptor terminating the TLS connections, negotiating HTTP/2 or HTTP/1.1.
+    pub fn acceptor(&self) -> Result<TlsAcceptor, NodeError> {
+        let mut config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(self.chain.clone(), self.key.clone_key())
+            .map_err(|e| tls_error(format!("Invalid certificate: {}", e)))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn tls_error(message: String) -> NodeError {
+    NodeError::TlsError(message)
+}
+
+/// Serves the router over TLS. Each connection is handshaken and served on its own task, the
+/// address of the client being passed to the handlers as `ConnectInfo<SocketAddr>`.
+pub async fn serve_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+) -> Result<(), std::io::Error> {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        info!("TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                    Err(_) => {
+                        info!("TLS handshake with {} timed out", peer);
+                        return;
+                    }
+                };
+            let service = TowerToHyperService::new(app.layer(Extension(ConnectInfo(peer))));
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                info!("Connection with {} closed: {}", peer, e);
+            }
+        });
+    }
+}
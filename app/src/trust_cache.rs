@@ -0,0 +1,102 @@
+use openrank_common::params::ALGO_ID_NEGATIVE_EIGEN_TRUST;
+use openrank_common::runner::ComputeRunner;
+use openrank_common::scheme::Precision;
+use openrank_common::subgraph::FilterStats;
+use openrank_common::JobDescription;
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::info;
+
+/// Number of trust maps kept when none is configured.
+const DEFAULT_CACHE_SIZE: usize = 1;
+
+static TRUST_CACHE: OnceLock<TrustCache> = OnceLock::new();
+
+/// Trust map a sub-job runs over: the trust data, minus the nodes filtered out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustKey {
+    pub trust_id: String,
+    pub blocklist_id: Option<String>,
+    pub include_list_id: Option<String>,
+    /// Whether the negative trust entries are kept as distrust, for the algorithms using them.
+    pub negative_trust: bool,
+    /// Precision of the trust values, the one the scores of the job are computed with.
+    pub precision: Precision,
+}
+
+impl TrustKey {
+    pub fn of(job: &JobDescription, precision: Precision) -> Self {
+        Self {
+            trust_id: job.trust_id.clone(),
+            blocklist_id: job.blocklist_id.clone(),
+            include_list_id: job.include_list_id.clone(),
+            negative_trust: job.algo_id == ALGO_ID_NEGATIVE_EIGEN_TRUST,
+            precision,
+        }
+    }
+}
+
+/// A trust map built for a sub-job, without its seed.
+pub struct CachedTrust {
+    pub runner: ComputeRunner,
+    /// Trust edges kept and dropped by the filter of the sub-job.
+    pub filter_stats: FilterStats,
+}
+
+/// Trust maps of the latest sub-jobs, so that the sub-jobs sharing their trust data (the same
+/// graph computed for several seeds, within a meta job or across jobs) don't parse it again.
+/// The least recently used map is evicted first.
+pub struct TrustCache {
+    capacity: usize,
+    entries: Mutex<Vec<(TrustKey, Arc<CachedTrust>)>>,
+}
+
+impl TrustCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reads the number of trust maps kept from `TRUST_CACHE_SIZE`, defaulting to 1. Every
+    /// map is held in memory, 0 disables the cache.
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("TRUST_CACHE_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_SIZE);
+        info!("Trust cache: Capacity({})", capacity);
+        Self::new(capacity)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn get(&self, key: &TrustKey) -> Option<Arc<CachedTrust>> {
+        let mut entries = self.entries.lock().unwrap();
+        let position = entries.iter().position(|(k, _)| k == key)?;
+        // Moved to the back, the most recently used
+        let entry = entries.remove(position);
+        let trust = entry.1.clone();
+        entries.push(entry);
+        Some(trust)
+    }
+
+    pub fn insert(&self, key: TrustKey, trust: Arc<CachedTrust>) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(k, _)| k != &key);
+        entries.push((key, trust));
+        if entries.len() > self.capacity {
+            entries.remove(0);
+        }
+    }
+}
+
+/// Returns the trust cache of the node, sized from `TRUST_CACHE_SIZE`.
+pub fn trust_cache() -> &'static TrustCache {
+    TRUST_CACHE.get_or_init(TrustCache::from_env)
+}
@@ -0,0 +1,244 @@
+use crate::error::Error as NodeError;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, TxHash};
+use alloy::providers::{PendingTransactionError, Provider, WatchTxError};
+use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+const DEFAULT_RECEIPT_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_FEE_BUMP_PERCENT: u128 = 20;
+/// Nodes reject replacements raising the fees by less than 10%.
+const MIN_FEE_BUMP_PERCENT: u128 = 10;
+const DEFAULT_MAX_REPLACEMENTS: u32 = 3;
+
+/// Gas price and retry policy of the outbound transactions.
+#[derive(Debug, Clone)]
+pub struct TxPolicy {
+    /// Time waited for the receipt before the transaction is replaced with higher fees.
+    pub receipt_timeout: Duration,
+    /// Percentage the fees are raised by on each replacement.
+    pub fee_bump_percent: u128,
+    /// Number of replacements before giving up on the transaction.
+    pub max_replacements: u32,
+    /// Cap of the max fee per gas (in wei), replacements never go above it.
+    pub max_fee_per_gas: Option<u128>,
+}
+
+impl Default for TxPolicy {
+    fn default() -> Self {
+        Self {
+            receipt_timeout: Duration::from_secs(DEFAULT_RECEIPT_TIMEOUT_SECS),
+            fee_bump_percent: DEFAULT_FEE_BUMP_PERCENT,
+            max_replacements: DEFAULT_MAX_REPLACEMENTS,
+            max_fee_per_gas: None,
+        }
+    }
+}
+
+impl TxPolicy {
+    /// Reads the policy from the environment:
+    /// - `TX_RECEIPT_TIMEOUT_SECS`, defaulting to 120,
+    /// - `TX_FEE_BUMP_PERCENT`, defaulting to 20 and at least 10,
+    /// - `TX_MAX_REPLACEMENTS`, defaulting to 3,
+    /// - `TX_MAX_FEE_PER_GAS_WEI`, uncapped if not set.
+    pub fn from_env() -> Result<Self, NodeError> {
+        let default = Self::default();
+        let fee_bump_percent =
+            parse_var("TX_FEE_BUMP_PERCENT")?.unwrap_or(default.fee_bump_percent);
+        if fee_bump_percent < MIN_FEE_BUMP_PERCENT {
+            return Err(NodeError::ConfigError(format!(
+                "TX_FEE_BUMP_PERCENT must be at least {}",
+                MIN_FEE_BUMP_PERCENT
+            )));
+        }
+        Ok(Self {
+            receipt_timeout: parse_var("TX_RECEIPT_TIMEOUT_SECS")?
+                .map(Duration::from_secs)
+                .unwrap_or(default.receipt_timeout),
+            fee_bump_percent,
+            max_replacements: parse_var("TX_MAX_REPLACEMENTS")?.unwrap_or(default.max_replacements),
+            max_fee_per_gas: parse_var("TX_MAX_FEE_PER_GAS_WEI")?,
+        })
+    }
+}
+
+fn parse_var<T: std::str::FromStr>(var: &str) -> Result<Option<T>, NodeError>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(var) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid {}: {}", var, e))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Sends transactions and sees them through: the nonce is assigned here, the receipt is
+/// awaited with a timeout, and a transaction not mined in time is replaced by one with the
+/// same nonce and bumped fees (replace-by-fee).
+///
+/// Clones share the nonces, so every submission of the node (of any role) goes through the
+/// same bookkeeping, and two transactions of an address never get the same nonce.
+#[derive(Debug, Clone, Default)]
+pub struct TxManager {
+    policy: TxPolicy,
+    /// Next nonce of each address, as far as the node knows.
+    nonces: Arc<Mutex<HashMap<Address, u64>>>,
+}
+
+impl TxManager {
+    pub fn new(policy: TxPolicy) -> Self {
+        Self {
+            policy,
+            nonces: Arc::default(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, NodeError> {
+        Ok(Self::new(TxPolicy::from_env()?))
+    }
+
+    /// Assigns the next nonce of the address, read from the pending transactions of the chain
+    /// the first time.
+    async fn next_nonce<P: Provider>(&self, provider: &P, from: Address) -> Result<u64, NodeError> {
+        let mut nonces = self.nonces.lock().await;
+        let nonce = match nonces.get(&from) {
+            Some(nonce) => *nonce,
+            None => provider
+                .get_transaction_count(from)
+                .pending()
+                .await
+                .map_err(|e| NodeError::TxError(format!("Failed to get nonce: {e:}")))?,
+        };
+        nonces.insert(from, nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Forgets the nonce of the address, to read it again from the chain on the next
+    /// transaction, e.g. after a transaction that may not have reached the mempool.
+    async fn reset_nonce(&self, from: Address) {
+        self.nonces.lock().await.remove(&from);
+    }
+
+    /// Returns the receipt of the first of the transactions that was mined, if any.
+    async fn find_receipt<P: Provider>(
+        provider: &P,
+        tx_hashes: &[TxHash],
+    ) -> Result<Option<TransactionReceipt>, NodeError> {
+        for tx_hash in tx_hashes {
+            let receipt = provider
+                .get_transaction_receipt(*tx_hash)
+                .await
+                .map_err(|e| NodeError::TxError(format!("Failed to get receipt: {e:}")))?;
+            if receipt.is_some() {
+                return Ok(receipt);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Sends the transaction from the address and waits for it to be mined, replacing it with
+    /// higher fees whenever the receipt doesn't come within the timeout. `on_sent` is called
+    /// with the hash of every transaction sent, the original and the replacements, before its
+    /// receipt is awaited.
+    ///
+    /// Returns the receipt of whichever of them was mined, which may have reverted.
+    pub async fn send<P: Provider>(
+        &self,
+        provider: &P,
+        from: Address,
+        tx: TransactionRequest,
+        mut on_sent: impl FnMut(TxHash) -> Result<(), NodeError>,
+    ) -> Result<TransactionReceipt, NodeError> {
+        let fees = provider
+            .estimate_eip1559_fees()
+            .await
+            .map_err(|e| NodeError::TxError(format!("Failed to estimate fees: {e:}")))?;
+        let cap = self.policy.max_fee_per_gas.unwrap_or(u128::MAX);
+        let mut max_fee_per_gas = fees.max_fee_per_gas.min(cap);
+        let mut max_priority_fee_per_gas = fees.max_priority_fee_per_gas.min(max_fee_per_gas);
+
+        let nonce = self.next_nonce(provider, from).await?;
+        let mut sent: Vec<TxHash> = Vec::new();
+        for replacement in 0..=self.policy.max_replacements {
+            let tx = tx
+                .clone()
+                .with_from(from)
+                .with_nonce(nonce)
+                .with_max_fee_per_gas(max_fee_per_gas)
+                .with_max_priority_fee_per_gas(max_priority_fee_per_gas);
+            let pending = match provider.send_transaction(tx).await {
+                Ok(pending) => pending,
+                Err(e) => {
+                    // A replacement is refused once the previous transaction is mined
+                    if let Some(receipt) = Self::find_receipt(provider, &sent).await? {
+                        return Ok(receipt);
+                    }
+                    if sent.is_empty() {
+                        self.reset_nonce(from).await;
+                    }
+                    return Err(NodeError::TxError(format!("{e:}")));
+                }
+            };
+            let tx_hash = *pending.tx_hash();
+            sent.push(tx_hash);
+            on_sent(tx_hash)?;
+            if replacement > 0 {
+                info!(
+                    "Transaction replaced: Tx Hash({:#}), Nonce({}), MaxFeePerGas({} wei)",
+                    tx_hash, nonce, max_fee_per_gas
+                );
+            }
+
+            match pending
+                .with_timeout(Some(self.policy.receipt_timeout))
+                .get_receipt()
+                .await
+            {
+                Ok(receipt) => return Ok(receipt),
+                Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout)) => {}
+                Err(e) => return Err(NodeError::TxError(format!("Failed to get receipt: {e:}"))),
+            }
+            // One of the transactions replaced earlier may have been mined instead
+            if let Some(receipt) = Self::find_receipt(provider, &sent).await? {
+                return Ok(receipt);
+            }
+
+            let bumped_fee = bump(max_fee_per_gas, self.policy.fee_bump_percent);
+            if bumped_fee > cap {
+                warn!(
+                    "Transaction not mined after {:?} and its fees reached the cap: Tx Hash({:#})",
+                    self.policy.receipt_timeout, tx_hash
+                );
+                break;
+            }
+            warn!(
+                "Transaction not mined after {:?}, bumping its fees: Tx Hash({:#})",
+                self.policy.receipt_timeout, tx_hash
+            );
+            max_fee_per_gas = bumped_fee;
+            max_priority_fee_per_gas =
+                bump(max_priority_fee_per_gas, self.policy.fee_bump_percent).min(max_fee_per_gas);
+        }
+
+        // The last transaction stays in the mempool and may still be mined, its nonce is
+        // read again from the chain for the next one
+        self.reset_nonce(from).await;
+        Err(NodeError::TxError(format!(
+            "Transaction with nonce {} not mined after {} attempts",
+            nonce,
+            sent.len()
+        )))
+    }
+}
+
+/// Raises the fee by the percentage, rounding up so that small fees still increase.
+fn bump(fee: u128, percent: u128) -> u128 {
+    fee.saturating_mul(100 + percent).div_ceil(100)
+}
@@ -0,0 +1,133 @@
+use crate::error::Error as NodeError;
+use alloy::primitives::{Bytes, FixedBytes, TxHash, U256};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+/// State of an outbound transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxState {
+    /// Not sent yet, or sending failed before a hash was known.
+    Pending,
+    /// Sent, waiting for the receipt.
+    Submitted,
+    /// Included on-chain, follow-up work (e.g. the manifest upload) is not done yet.
+    Confirmed,
+}
+
+/// Intent to submit the result of a compute request, persisted before the transaction is sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxIntent {
+    pub compute_id: U256,
+    pub job_description_id: String,
+    pub results_id: FixedBytes<32>,
+    pub meta_commitment: FixedBytes<32>,
+    /// Calldata of the `submitMetaComputeResult` call.
+    pub calldata: Bytes,
+    pub state: TxState,
+    pub tx_hash: Option<TxHash>,
+    pub attempts: u32,
+    /// Unix timestamp (seconds) the intent was created at.
+    pub created_at: u64,
+}
+
+impl TxIntent {
+    pub fn new(
+        compute_id: U256,
+        job_description_id: String,
+        results_id: FixedBytes<32>,
+        meta_commitment: FixedBytes<32>,
+        calldata: Bytes,
+    ) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            compute_id,
+            job_description_id,
+            results_id,
+            meta_commitment,
+            calldata,
+            state: TxState::Pending,
+            tx_hash: None,
+            attempts: 0,
+            created_at,
+        }
+    }
+}
+
+/// On-disk queue of outbound transactions, one small JSON file per compute id.
+/// Intents are written atomically and removed once all their work is done, so the queue
+/// only ever holds in-flight submissions.
+#[derive(Debug, Clone)]
+pub struct TxQueue {
+    dir: PathBuf,
+}
+
+impl TxQueue {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, NodeError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| NodeError::FileError(format!("Failed to create tx queue dir: {}", e)))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, compute_id: U256) -> PathBuf {
+        self.dir.join(format!("{}.json", compute_id))
+    }
+
+    /// Inserts or updates the intent.
+    pub fn put(&self, intent: &TxIntent) -> Result<(), NodeError> {
+        let path = self.path(intent.compute_id);
+        let tmp_path = path.with_extension("json.part");
+        let bytes = serde_json::to_vec(intent).map_err(NodeError::SerdeError)?;
+        std::fs::write(&tmp_path, bytes)
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| NodeError::FileError(format!("Failed to persist tx intent: {}", e)))
+    }
+
+    pub fn remove(&self, compute_id: U256) -> Result<(), NodeError> {
+        match std::fs::remove_file(self.path(compute_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(NodeError::FileError(
+                format!("Failed to remove tx intent: {}", e),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns all queued intents, oldest first. Intents that can't be parsed are renamed to
+    /// `*.corrupt`, so that one bad file doesn't block the others.
+    pub fn pending(&self) -> Result<Vec<TxIntent>, NodeError> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| NodeError::FileError(format!("Failed to read tx queue dir: {}", e)))?;
+        let mut intents = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| NodeError::FileError(format!("Failed to read tx queue: {}", e)))?
+                .path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let bytes = std::fs::read(&path)
+                .map_err(|e| NodeError::FileError(format!("Failed to read tx intent: {}", e)))?;
+            match serde_json::from_slice::<TxIntent>(&bytes) {
+                Ok(intent) => intents.push(intent),
+                Err(e) => {
+                    error!(
+                        "Moving aside corrupted tx intent: Path({}), Error({})",
+                        path.display(),
+                        e
+                    );
+                    std::fs::rename(&path, path.with_extension("corrupt")).map_err(|e| {
+                        NodeError::FileError(format!("Failed to move aside tx intent: {}", e))
+                    })?;
+                }
+            }
+        }
+        intents.sort_by_key(|i| (i.created_at, i.compute_id));
+        Ok(intents)
+    }
+}
@@ -0,0 +1,288 @@
+use crate::enclave::EnclaveClient;
+use crate::error::Error as NodeError;
+use crate::paths::paths;
+use alloy::primitives::{Address, U256};
+use alloy::providers::Provider;
+use alloy::signers::local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Path of the active key file of the role.
+pub fn key_path(role: &str) -> String {
+    format!("{}/{}.key", paths().keys_dir(), role)
+}
+
+/// Path of the file recording the spend of the role on the current day.
+pub fn spend_path(role: &str) -> String {
+    format!("{}/{}.json", paths().spend_dir(), role)
+}
+
+/// Spend of a role on a day, persisted so that a restart doesn't reset the daily limit.
+#[derive(Debug, Serialize, Deserialize)]
+struct DailySpend {
+    /// Days since unix epoch.
+    day: u64,
+    spent: U256,
+}
+
+/// Spending policy of a single role.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingPolicy {
+    /// Minimum balance (in wei) required to start the role.
+    pub min_balance: U256,
+    /// Maximum amount (in wei) spent on gas per UTC day.
+    pub max_daily_spend: Option<U256>,
+}
+
+impl SpendingPolicy {
+    /// Reads the spending policy of the role from the environment.
+    pub fn from_env(role: &str) -> Result<Self, NodeError> {
+        let prefix = role.to_uppercase();
+        Ok(Self {
+            min_balance: parse_wei(&format!("{}_MIN_BALANCE_WEI", prefix))?.unwrap_or_default(),
+            max_daily_spend: parse_wei(&format!("{}_MAX_DAILY_SPEND_WEI", prefix))?,
+        })
+    }
+}
+
+/// Balance and spending of a role wallet, as reported by the server.
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletStatus {
+    pub role: String,
+    pub address: Address,
+    pub balance: U256,
+    pub spent_today: U256,
+    /// Day (days since unix epoch) `spent_today` refers to.
+    pub day: u64,
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// Writes the spend through a temporary file, so that a crash never leaves a partial file.
+fn save_spend(spend_path: &str, spend: &DailySpend) {
+    let tmp_path = format!("{}.part", spend_path);
+    let res = serde_json::to_vec(spend)
+        .map_err(std::io::Error::other)
+        .and_then(|bytes| {
+            if let Some(dir) = std::path::Path::new(spend_path).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&tmp_path, bytes)
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, spend_path));
+    if let Err(e) = res {
+        warn!("Failed to persist spend to {}: {}", spend_path, e);
+    }
+}
+
+fn parse_wei(var: &str) -> Result<Option<U256>, NodeError> {
+    match std::env::var(var) {
+        Ok(value) => U256::from_str(&value)
+            .map(Some)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid {}: {}", var, e))),
+        Err(_) => Ok(None),
+    }
+}
+
+fn signer_from_mnemonic(mnemonic: String) -> Result<PrivateKeySigner, NodeError> {
+    MnemonicBuilder::<English>::default()
+        .phrase(mnemonic)
+        .index(0)
+        .map_err(NodeError::LocalSignerError)?
+        .build()
+        .map_err(NodeError::LocalSignerError)
+}
+
+/// Parses a key given either as a hex private key or as a mnemonic (first account).
+pub fn parse_key(key: &str) -> Result<PrivateKeySigner, NodeError> {
+    let key = key.trim();
+    if key.contains(' ') {
+        signer_from_mnemonic(key.to_string())
+    } else {
+        PrivateKeySigner::from_str(key)
+            .map_err(|e| NodeError::ConfigError(format!("Invalid private key: {}", e)))
+    }
+}
+
+/// Wallet of a single role (e.g. "computer"), with its own key and spending policy.
+/// Clones share the status, so the daily spend is tracked across the loops using the wallet.
+#[derive(Clone)]
+pub struct RoleWallet {
+    role: String,
+    signer: PrivateKeySigner,
+    policy: SpendingPolicy,
+    status: Arc<Mutex<WalletStatus>>,
+    /// File the daily spend is persisted to, not persisted when none.
+    spend_path: Option<String>,
+}
+
+impl RoleWallet {
+    pub fn new(role: &str, signer: PrivateKeySigner, policy: SpendingPolicy) -> Self {
+        let status = WalletStatus {
+            role: role.to_string(),
+            address: signer.address(),
+            balance: U256::ZERO,
+            spent_today: U256::ZERO,
+            day: today(),
+        };
+        Self {
+            role: role.to_string(),
+            signer,
+            policy,
+            status: Arc::new(Mutex::new(status)),
+            spend_path: None,
+        }
+    }
+
+    /// Persists the daily spend to `spend_path`, resuming from the spend recorded there today.
+    pub fn with_persisted_spend(mut self, spend_path: String) -> Self {
+        match std::fs::read(&spend_path) {
+            Ok(bytes) => match serde_json::from_slice::<DailySpend>(&bytes) {
+                Ok(spend) if spend.day == today() => {
+                    info!(
+                        "Resuming the daily spend of role '{}': Spent({} wei)",
+                        self.role, spend.spent
+                    );
+                    self.status.lock().unwrap().spent_today = spend.spent;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Ignoring invalid spend file {}: {}", spend_path, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read spend file {}: {}", spend_path, e),
+        }
+        self.spend_path = Some(spend_path);
+        self
+    }
+
+    /// Creates the role wallet from the environment:
+    /// - the rotated key in `./keys/{role}.key` if any, else `{ROLE}_MNEMONIC`, falling back
+    ///   to `MNEMONIC`,
+    /// - `{ROLE}_MIN_BALANCE_WEI`, defaulting to 0,
+    /// - `{ROLE}_MAX_DAILY_SPEND_WEI`, unlimited if not set.
+    pub fn from_env(role: &str) -> Result<Self, NodeError> {
+        let prefix = role.to_uppercase();
+        if let Ok(key) = std::fs::read_to_string(key_path(role)) {
+            info!("Using rotated key of role '{}'", role);
+            let signer = parse_key(&key)?;
+            return Ok(Self::new(role, signer, SpendingPolicy::from_env(role)?)
+                .with_persisted_spend(spend_path(role)));
+        }
+        let mnemonic = std::env::var(format!("{}_MNEMONIC", prefix))
+            .or_else(|_| std::env::var("MNEMONIC"))
+            .map_err(|_| {
+                NodeError::ConfigError(format!("{}_MNEMONIC or MNEMONIC must be set", prefix))
+            })?;
+        Self::from_mnemonic(role, mnemonic)
+    }
+
+    /// Creates the role wallet with the mnemonic delivered by the enclave parent, falling back
+    /// to the shared `MNEMONIC` secret. The spending policy is read from the environment.
+    pub async fn from_enclave(role: &str, enclave: &EnclaveClient) -> Result<Self, NodeError> {
+        let prefix = role.to_uppercase();
+        let mnemonic = match enclave.secret(&format!("{}_MNEMONIC", prefix)).await {
+            Ok(mnemonic) => mnemonic,
+            Err(_) => enclave.secret("MNEMONIC").await?,
+        };
+        Self::from_mnemonic(role, mnemonic)
+    }
+
+    fn from_mnemonic(role: &str, mnemonic: String) -> Result<Self, NodeError> {
+        let signer = signer_from_mnemonic(mnemonic)?;
+        Ok(Self::new(role, signer, SpendingPolicy::from_env(role)?)
+            .with_persisted_spend(spend_path(role)))
+    }
+
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    pub fn signer(&self) -> &PrivateKeySigner {
+        &self.signer
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Returns the shared status of the wallet.
+    pub fn status(&self) -> Arc<Mutex<WalletStatus>> {
+        self.status.clone()
+    }
+
+    /// Fetches the current balance of the wallet.
+    pub async fn refresh_balance<P: Provider>(&self, provider: &P) -> Result<U256, NodeError> {
+        let balance = provider
+            .get_balance(self.address())
+            .await
+            .map_err(NodeError::from)?;
+        self.status.lock().unwrap().balance = balance;
+        Ok(balance)
+    }
+
+    /// Refuses to start the role if its balance is below the configured minimum.
+    pub async fn check_min_balance<P: Provider>(&self, provider: &P) -> Result<(), NodeError> {
+        let balance = self.refresh_balance(provider).await?;
+        info!(
+            "Wallet for role '{}': Address({}), Balance({} wei)",
+            self.role,
+            self.address(),
+            balance
+        );
+        if balance < self.policy.min_balance {
+            return Err(NodeError::InsufficientBalance {
+                role: self.role.clone(),
+                balance,
+                required: self.policy.min_balance,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the daily spending limit has not been reached yet.
+    pub fn check_spend_allowed(&self) -> Result<(), NodeError> {
+        let Some(limit) = self.policy.max_daily_spend else {
+            return Ok(());
+        };
+        let mut status = self.status.lock().unwrap();
+        if status.day != today() {
+            status.day = today();
+            status.spent_today = U256::ZERO;
+        }
+        if status.spent_today >= limit {
+            return Err(NodeError::SpendLimitReached {
+                role: self.role.clone(),
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Records gas spent by the role, persisting the spend of the day.
+    pub fn record_spend(&self, amount: U256) {
+        let mut status = self.status.lock().unwrap();
+        if status.day != today() {
+            status.day = today();
+            status.spent_today = U256::ZERO;
+        }
+        status.spent_today = status.spent_today.saturating_add(amount);
+        status.balance = status.balance.saturating_sub(amount);
+        if let Some(spend_path) = &self.spend_path {
+            let spend = DailySpend {
+                day: status.day,
+                spent: status.spent_today,
+            };
+            save_spend(spend_path, &spend);
+        }
+    }
+}
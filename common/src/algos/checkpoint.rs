@@ -0,0 +1,186 @@
+use alloy::hex;
+use serde::Serialize;
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::value::Value;
+use crate::JobDescription;
+
+/// Bytes of a score in the checkpoint file: peer index and score.
+const SCORE_RECORD_LEN: usize = 16;
+
+/// Scores of an EigenTrust run saved every `interval` iterations, so that a run interrupted
+/// (e.g. by a crash of the node) resumes from its last checkpoint rather than from the seed.
+///
+/// The scores are saved by peer index, which only identifies the same peer for the same
+/// trust and seed data: the file is named after `key`, hashing the inputs of the run.
+/// Resumed runs give the same scores as uninterrupted ones.
+///
+/// Checkpoints are best effort: failing to read or write one is logged and the run goes on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreCheckpoint {
+    path: PathBuf,
+    interval: u32,
+}
+
+/// Inputs of a run, hashed into the checkpoint key.
+#[derive(Serialize)]
+struct CheckpointInputs<'a> {
+    trust_id: &'a str,
+    seed_id: &'a str,
+    blocklist_id: Option<&'a str>,
+    include_list_id: Option<&'a str>,
+    algo_id: u32,
+    params: BTreeMap<&'a String, &'a String>,
+}
+
+impl ScoreCheckpoint {
+    /// Checkpoint of the job, in the directory, saved every `interval` iterations (at least 1).
+    pub fn new(dir: impl AsRef<Path>, job: &JobDescription, interval: u32) -> Self {
+        Self {
+            path: dir.as_ref().join(format!("{}.bin", Self::key(job))),
+            interval: interval.max(1),
+        }
+    }
+
+    /// Hex encoded Keccak256 hash of the inputs the scores of the job depend on: the trust
+    /// and seed data, the nodes filtered out of the graph, the algorithm and its params.
+    pub fn key(job: &JobDescription) -> String {
+        let inputs = CheckpointInputs {
+            trust_id: &job.trust_id,
+            seed_id: &job.seed_id,
+            blocklist_id: job.blocklist_id.as_deref(),
+            include_list_id: job.include_list_id.as_deref(),
+            algo_id: job.algo_id,
+            params: job.params.iter().collect(),
+        };
+        let bytes = serde_json::to_vec(&inputs).expect("Serializable inputs");
+        hex::encode(Keccak256::digest(bytes))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Whether the scores of the iteration are saved.
+    pub fn is_due(&self, iteration: u32) -> bool {
+        iteration > 0 && iteration.is_multiple_of(self.interval)
+    }
+
+    /// Reads the iteration and the scores of the last checkpoint, `None` without one. Scores
+    /// are saved as f64, read back exactly with the precision they were computed with.
+    pub fn load<V: Value>(&self) -> Option<(u32, BTreeMap<u64, V>)> {
+        match self.read() {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                warn!("Failed to read score checkpoint {:?}: {}", self.path, e);
+                None
+            }
+        }
+    }
+
+    fn read<V: Value>(&self) -> std::io::Result<Option<(u32, BTreeMap<u64, V>)>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        let iteration = u32::from_le_bytes(header[..4].try_into().expect("4 bytes"));
+        let len = u64::from_le_bytes(header[4..].try_into().expect("8 bytes"));
+        let mut scores = BTreeMap::new();
+        let mut record = [0; SCORE_RECORD_LEN];
+        for _ in 0..len {
+            reader.read_exact(&mut record)?;
+            let index = u64::from_le_bytes(record[..8].try_into().expect("8 bytes"));
+            let score = f64::from_le_bytes(record[8..].try_into().expect("8 bytes"));
+            scores.insert(index, V::from_f64(score));
+        }
+        if reader.read(&mut [0])? != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Trailing bytes",
+            ));
+        }
+        Ok(Some((iteration, scores)))
+    }
+
+    /// Replaces the checkpoint atomically with the scores of the iteration.
+    pub fn save<V: Value>(&self, iteration: u32, scores: &BTreeMap<u64, V>) {
+        if let Err(e) = self.write(iteration, scores) {
+            warn!("Failed to save score checkpoint {:?}: {}", self.path, e);
+        }
+    }
+
+    fn write<V: Value>(&self, iteration: u32, scores: &BTreeMap<u64, V>) -> std::io::Result<()> {
+        let tmp_path = self.path.with_extension("bin.part");
+        let mut writer = BufWriter::new(std::fs::File::create(&tmp_path)?);
+        writer.write_all(&iteration.to_le_bytes())?;
+        writer.write_all(&(scores.len() as u64).to_le_bytes())?;
+        for (index, score) in scores {
+            writer.write_all(&index.to_le_bytes())?;
+            writer.write_all(&score.to_f64().to_le_bytes())?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
+    }
+
+    /// Removes the checkpoint, once the run is over.
+    pub fn remove(&self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove score checkpoint {:?}: {}", self.path, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn job(seed_id: &str) -> JobDescription {
+        JobDescription::new(
+            "job".to_string(),
+            "trust".to_string(),
+            seed_id.to_string(),
+            0,
+            HashMap::from([("alpha".to_string(), "0.5".to_string())]),
+        )
+    }
+
+    #[test]
+    fn should_save_and_load_scores() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = ScoreCheckpoint::new(dir.path(), &job("seed"), 0);
+        assert_eq!(checkpoint.interval(), 1);
+        assert_ne!(
+            ScoreCheckpoint::key(&job("seed")),
+            ScoreCheckpoint::key(&job("other"))
+        );
+        assert_eq!(checkpoint.load::<f64>(), None);
+
+        let scores = BTreeMap::from([(0, 0.25), (2, 0.75)]);
+        checkpoint.save(4, &scores);
+        assert_eq!(checkpoint.load(), Some((4, scores)));
+
+        // Truncated files are ignored
+        std::fs::write(checkpoint.path(), [1, 2, 3]).unwrap();
+        assert_eq!(checkpoint.load::<f64>(), None);
+        checkpoint.remove();
+        assert!(!checkpoint.path().exists());
+    }
+}
@@ -0,0 +1,156 @@
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::runner::OutboundLocalTrust;
+use crate::value::Value;
+
+/// Returns the peers reachable from the seed peers by following positive trust edges.
+/// Peers without an outbound trust entry (e.g. seed peers outside the trust matrix) are
+/// reachable but lead nowhere.
+fn find_reachable_peers<V: Value>(
+    lt: &BTreeMap<u64, OutboundLocalTrust<V>>,
+    seed: &BTreeMap<u64, V>,
+) -> HashSet<u64> {
+    let mut to_visit: Vec<&u64> = seed.keys().collect();
+    let mut visited = HashSet::new();
+    while let Some(i) = to_visit.pop() {
+        if !visited.insert(*i) {
+            continue;
+        }
+        let Some(outbound) = lt.get(i) else {
+            continue;
+        };
+        for (j, v) in outbound.outbound_trust_scores() {
+            if !visited.contains(j) && *v > V::ZERO {
+                to_visit.push(j);
+            }
+        }
+    }
+    visited
+}
+
+/// Pre-processes a mutable local trust matrix `lt` by modifying it in-place, identically
+/// for every algorithm:
+///
+/// - Falls back to a uniform seed over all peers if the seed trust sums to 0.
+/// - Ensures all peers have outbound trust, redistributing to seed peers if necessary.
+/// - Removes the peers that aren't reachable from the seed peers.
+pub(crate) fn pre_process<V: Value>(
+    lt: &mut BTreeMap<u64, OutboundLocalTrust<V>>,
+    seed: &mut BTreeMap<u64, V>,
+    count: u64,
+) {
+    // Calculate the sum of all seed trust values.
+    let sum: V = seed.par_iter().map(|(_, v)| v).sum();
+
+    if sum == V::ZERO {
+        for i in 0..count {
+            seed.insert(i, V::ONE);
+        }
+    }
+
+    for from in 0..count {
+        let sum = lt.get(&from).map_or(V::ZERO, |lt| *lt.outbound_sum());
+        // If peer does not have outbound trust,
+        // his trust will be distributed to seed peers based on their seed/pre-trust
+        if sum == V::ZERO {
+            let single_lt = OutboundLocalTrust::from_score_map(seed);
+            lt.insert(from, single_lt);
+        }
+    }
+
+    let reachable = find_reachable_peers(lt, seed);
+    lt.retain(|from, _| reachable.contains(from));
+}
+
+/// Normalizes the `lt` matrix by dividing each element by the sum of its row.
+pub(crate) fn normalise_lt<V: Value>(
+    lt: &BTreeMap<u64, OutboundLocalTrust<V>>,
+) -> BTreeMap<u64, OutboundLocalTrust<V>> {
+    lt.par_iter()
+        .fold(BTreeMap::new, |mut lt_norm, (from, from_map)| {
+            let from_map_norm = from_map.norm();
+            lt_norm.insert(*from, from_map_norm);
+            lt_norm
+        })
+        .reduce(BTreeMap::new, |mut acc, lt_norm| {
+            acc.extend(lt_norm);
+            acc
+        })
+}
+
+/// Normalizes the scores, to eliminate the rounding error
+pub(crate) fn normalise_scores<V: Value>(scores: &BTreeMap<u64, V>) -> BTreeMap<u64, V> {
+    // Calculate the sum of all seed trust values.
+    let sum: V = scores.par_iter().map(|(_, v)| v).sum();
+
+    if sum == V::ZERO {
+        return scores.clone();
+    }
+
+    scores
+        .par_iter()
+        .fold(BTreeMap::new, |mut scores, (i, value)| {
+            scores.insert(*i, *value / sum);
+            scores
+        })
+        .reduce(BTreeMap::new, |mut acc, scores| {
+            acc.extend(scores);
+            acc
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lt(edges: &[(u64, u64, f64)]) -> BTreeMap<u64, OutboundLocalTrust<f64>> {
+        let mut lt: BTreeMap<u64, OutboundLocalTrust<f64>> = BTreeMap::new();
+        for (from, to, value) in edges {
+            lt.entry(*from).or_default().insert(*to, *value);
+        }
+        lt
+    }
+
+    #[test]
+    fn should_find_reachable_peers_with_missing_entries() {
+        // Seed 5 has no outbound entry, 3 is an isolated node
+        let lt = lt(&[(0, 1, 1.0), (1, 2, 1.0), (3, 3, 1.0)]);
+        let seed = BTreeMap::from([(0, 1.0), (5, 1.0)]);
+        let reachable = find_reachable_peers(&lt, &seed);
+        assert_eq!(reachable, HashSet::from([0, 1, 2, 5]));
+
+        // Disconnected seed, only reaches itself
+        let seed = BTreeMap::from([(7, 1.0)]);
+        assert_eq!(find_reachable_peers(&lt, &seed), HashSet::from([7]));
+    }
+
+    #[test]
+    fn should_pre_process_disconnected_seeds() {
+        // 2 has no outbound trust, 3 is an isolated node trusting only 4
+        let mut lt = lt(&[(0, 1, 1.0), (1, 2, 1.0), (3, 4, 1.0)]);
+        // Seed 9 is outside the trust matrix
+        let mut seed = BTreeMap::from([(0, 1.0), (9, 1.0)]);
+        pre_process(&mut lt, &mut seed, 5);
+
+        // Peers without outbound trust trust the seed peers
+        assert_eq!(lt[&2].get(&0), Some(1.0));
+        assert_eq!(lt[&2].get(&9), Some(1.0));
+        // Peers unreachable from the seed are removed
+        assert_eq!(lt.keys().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn should_normalise() {
+        let lt = normalise_lt(&lt(&[(0, 1, 1.0), (0, 2, 3.0), (1, 0, 2.0)]));
+        assert_eq!(lt[&0].get(&1), Some(0.25));
+        assert_eq!(lt[&0].get(&2), Some(0.75));
+        assert_eq!(lt[&1].get(&0), Some(1.0));
+
+        let scores = normalise_scores(&BTreeMap::from([(0, 1.0), (1, 3.0)]));
+        assert_eq!(scores, BTreeMap::from([(0, 0.25), (1, 0.75)]));
+        // All-zero scores are left as is
+        let zeros = BTreeMap::from([(0, 0.0), (1, 0.0)]);
+        assert_eq!(normalise_scores(&zeros), zeros);
+    }
+}
@@ -0,0 +1,151 @@
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::BTreeMap;
+
+use crate::runner::OutboundLocalTrust;
+use crate::value::Value;
+
+/// Normalised local trust matrix compiled into compressed sparse rows, iterated instead of
+/// the nested maps of the trust map.
+///
+/// The matrix is stored transposed: row `i` holds the peers trusting `i` (`cols`) along with
+/// their trust in it (`values`), in ascending peer order. Every score of an iteration is then
+/// summed from a contiguous slice, independently of the others, which parallelises without
+/// merging partial results.
+pub(crate) struct CsrMatrix<V> {
+    /// Entries of row `i` are `offsets[i]..offsets[i + 1]`.
+    offsets: Vec<usize>,
+    cols: Vec<u64>,
+    values: Vec<V>,
+}
+
+impl<V: Value> CsrMatrix<V> {
+    /// Compiles the (normalised) local trust matrix.
+    pub(crate) fn from_lt(lt: &BTreeMap<u64, OutboundLocalTrust<V>>) -> Self {
+        let num_peers = lt
+            .iter()
+            .flat_map(|(from, from_map)| {
+                std::iter::once(from).chain(from_map.outbound_trust_scores().keys())
+            })
+            .max()
+            .map(|max| *max as usize + 1)
+            .unwrap_or(0);
+
+        // Offsets from the number of peers trusting each peer
+        let mut offsets = vec![0; num_peers + 1];
+        for from_map in lt.values() {
+            for to in from_map.outbound_trust_scores().keys() {
+                offsets[*to as usize + 1] += 1;
+            }
+        }
+        for i in 0..num_peers {
+            offsets[i + 1] += offsets[i];
+        }
+
+        // Filled by ascending truster, the order of the trust map
+        let num_entries = offsets[num_peers];
+        let mut cols = vec![0; num_entries];
+        let mut values = vec![V::ZERO; num_entries];
+        let mut cursors = offsets[..num_peers].to_vec();
+        for (from, from_map) in lt {
+            for (to, value) in from_map.outbound_trust_scores() {
+                let position = &mut cursors[*to as usize];
+                cols[*position] = *from;
+                values[*position] = *value;
+                *position += 1;
+            }
+        }
+
+        Self {
+            offsets,
+            cols,
+            values,
+        }
+    }
+
+    /// Number of entries (edges) of the matrix.
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Number of peers of the matrix, one more than the highest peer index.
+    pub(crate) fn num_peers(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Peers trusting the peer and their trust in it, empty when no one trusts it.
+    pub(crate) fn row(&self, to: usize) -> (&[u64], &[V]) {
+        let (start, end) = (self.offsets[to], self.offsets[to + 1]);
+        (&self.cols[start..end], &self.values[start..end])
+    }
+
+    /// Computes the trust each peer receives from the scores of the peers trusting it. Only the
+    /// peers trusted by at least one peer have a score, as with the trust map.
+    pub(crate) fn propagate(&self, scores: &BTreeMap<u64, V>) -> BTreeMap<u64, V> {
+        let num_peers = self.num_peers();
+        // Scores of peers outside the matrix don't propagate
+        let mut dense = vec![V::ZERO; num_peers];
+        for (i, score) in scores.range(..num_peers as u64) {
+            dense[*i as usize] = *score;
+        }
+
+        let next_scores: Vec<(u64, V)> = (0..num_peers)
+            .into_par_iter()
+            .filter_map(|to| {
+                let (cols, values) = self.row(to);
+                if cols.is_empty() {
+                    return None;
+                }
+                Some((to as u64, row_sum(cols, values, &dense)))
+            })
+            .collect();
+        // Sorted by peer, so the map is built without rebalancing
+        next_scores.into_iter().collect()
+    }
+}
+
+/// Trust received through the row from the dense scores.
+pub(crate) fn row_sum<V: Value>(cols: &[u64], values: &[V], scores: &[V]) -> V {
+    cols.iter().zip(values).fold(V::ZERO, |sum, (from, value)| {
+        sum + *value * scores[*from as usize]
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_propagate_like_the_trust_map() {
+        let mut lt: BTreeMap<u64, OutboundLocalTrust<f64>> = BTreeMap::new();
+        for (from, to, value) in [
+            (0, 1, 0.25),
+            (0, 2, 0.75),
+            (1, 0, 1.0),
+            (3, 2, 0.5),
+            (3, 5, 0.5),
+        ] {
+            lt.entry(from).or_default().insert(to, value);
+        }
+        let csr = CsrMatrix::from_lt(&lt);
+        assert_eq!(csr.len(), 5);
+
+        // Peer 7 is outside the matrix, 4 is trusted by no one
+        let scores = BTreeMap::from([(0, 0.5), (1, 0.25), (3, 0.125), (7, 0.125)]);
+        let mut expected = BTreeMap::new();
+        for (from, from_map) in &lt {
+            let origin_score = scores.get(from).unwrap_or(&0.0);
+            for (to, value) in from_map.outbound_trust_scores() {
+                *expected.entry(*to).or_insert(0.0) += value * origin_score;
+            }
+        }
+        assert_eq!(csr.propagate(&scores), expected);
+        assert_eq!(
+            expected.keys().copied().collect::<Vec<_>>(),
+            vec![0, 1, 2, 5]
+        );
+
+        assert!(CsrMatrix::from_lt(&BTreeMap::new())
+            .propagate(&scores)
+            .is_empty());
+    }
+}
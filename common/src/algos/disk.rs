@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Instant;
+use tracing::info;
+
+use super::checkpoint::ScoreCheckpoint;
+use super::common::normalise_scores;
+use super::et::{converge, DELTA, PRE_TRUST_WEIGHT};
+use super::sr::{walk, WALK_LENGTH};
+use super::ComputeReport;
+use crate::runner::OutboundLocalTrust;
+use crate::trust_matrix::DiskTrustMatrix;
+use crate::value::Value;
+
+/// Pre-processed trust matrix on disk: `common::pre_process` applied without rewriting the
+/// edges. The rows are normalised as they are streamed, the peers without outbound trust
+/// distributing theirs to the seed peers, and the rows of unreachable peers being skipped.
+struct PreparedMatrix<'a, V> {
+    matrix: &'a DiskTrustMatrix,
+    count: u64,
+    /// Peers reachable from the seed peers.
+    reachable: HashSet<u64>,
+    /// Normalised row of the peers without outbound trust.
+    seed_row: OutboundLocalTrust<V>,
+}
+
+impl<'a, V: Value> PreparedMatrix<'a, V> {
+    /// Falls back to a uniform seed if the seed trust sums to 0, and finds the reachable peers,
+    /// as `common::pre_process` does.
+    fn new(
+        matrix: &'a DiskTrustMatrix,
+        seed: &mut BTreeMap<u64, V>,
+        count: u64,
+    ) -> std::io::Result<Self> {
+        let sum: V = seed.values().sum();
+        if sum == V::ZERO {
+            for i in 0..count {
+                seed.insert(i, V::ONE);
+            }
+        }
+
+        let mut lookup = matrix.lookup()?;
+        let mut to_visit: Vec<u64> = seed.keys().copied().collect();
+        let mut reachable = HashSet::new();
+        while let Some(i) = to_visit.pop() {
+            if !reachable.insert(i) {
+                continue;
+            }
+            // Peers without outbound trust lead to the seed peers, all visited already
+            let row = lookup.row::<V>(i)?;
+            for (j, v) in row.outbound_trust_scores() {
+                if !reachable.contains(j) && *v > V::ZERO {
+                    to_visit.push(*j);
+                }
+            }
+        }
+
+        Ok(Self {
+            matrix,
+            count,
+            reachable,
+            seed_row: OutboundLocalTrust::from_score_map(seed).norm(),
+        })
+    }
+
+    /// Computes the trust each peer receives from the scores of the peers trusting it,
+    /// streaming the rows once.
+    fn propagate(&self, scores: &BTreeMap<u64, V>) -> std::io::Result<BTreeMap<u64, V>> {
+        let mut next_scores = BTreeMap::new();
+        let mut add_row = |from: u64, row: &OutboundLocalTrust<V>| {
+            if !self.reachable.contains(&from) {
+                return;
+            }
+            let normalised;
+            let row = if *row.outbound_sum() == V::ZERO {
+                &self.seed_row
+            } else {
+                normalised = row.norm();
+                &normalised
+            };
+            let origin_score = scores.get(&from).copied().unwrap_or(V::ZERO);
+            for (to, value) in row.outbound_trust_scores() {
+                *next_scores.entry(*to).or_insert(V::ZERO) += *value * origin_score;
+            }
+        };
+        for row in self.matrix.rows()? {
+            let (from, row) = row?;
+            add_row(from, &row);
+        }
+        // Peers only in the seed have no row on disk
+        let empty = OutboundLocalTrust::new();
+        for from in self.matrix.len()..self.count {
+            add_row(from, &empty);
+        }
+        Ok(next_scores)
+    }
+}
+
+/// Runs `et::eigen_trust_run` on a trust matrix on disk. The matrix is streamed twice per
+/// iteration, only the scores being held in memory.
+pub fn eigen_trust_run_disk<V: Value>(
+    matrix: &DiskTrustMatrix,
+    mut seed: BTreeMap<u64, V>,
+    count: u64,
+    alpha: Option<f64>,
+    delta: Option<f64>,
+    max_iter: Option<u32>,
+    checkpoint: Option<&ScoreCheckpoint>,
+) -> std::io::Result<(Vec<(u64, V)>, ComputeReport)> {
+    let start = Instant::now();
+    let mut report = ComputeReport {
+        lt_size_before: matrix.num_rows(),
+        seed_size_before: seed.len(),
+        ..Default::default()
+    };
+    info!("ALPHA: {}", alpha.unwrap_or(PRE_TRUST_WEIGHT));
+    info!("DELTA: {}", delta.unwrap_or(DELTA));
+    if let Some(max_iter) = max_iter {
+        info!("MAX_ITER: {}", max_iter);
+    }
+    let prepared = prepare(matrix, &mut seed, count, &mut report, start)?;
+    let start = Instant::now();
+    let seed = normalise_scores(&seed);
+    report.normalise_ms = start.elapsed().as_millis() as u64;
+
+    let scores = converge(
+        &seed,
+        alpha,
+        delta,
+        max_iter,
+        checkpoint,
+        &mut report,
+        |scores| prepared.propagate(scores),
+    )?;
+    Ok((scores.into_iter().collect(), report))
+}
+
+/// Runs `sr::sybil_rank_run` on a trust matrix on disk. The matrix is streamed once per
+/// walk step, only the scores being held in memory.
+pub fn sybil_rank_run_disk<V: Value>(
+    matrix: &DiskTrustMatrix,
+    mut seed: BTreeMap<u64, V>,
+    count: u64,
+    walk_length: Option<u32>,
+) -> std::io::Result<(Vec<(u64, V)>, ComputeReport)> {
+    let start = Instant::now();
+    let walk_len = walk_length.unwrap_or(WALK_LENGTH);
+    let mut report = ComputeReport {
+        lt_size_before: matrix.num_rows(),
+        seed_size_before: seed.len(),
+        ..Default::default()
+    };
+    info!("WALK_LENGTH: {}", walk_len);
+    let prepared = prepare(matrix, &mut seed, count, &mut report, start)?;
+    let start = Instant::now();
+    let seed = normalise_scores(&seed);
+    report.normalise_ms = start.elapsed().as_millis() as u64;
+
+    let scores = walk(&seed, walk_len, &mut report, |scores| {
+        prepared.propagate(scores)
+    })?;
+    Ok((scores.into_iter().collect(), report))
+}
+
+fn prepare<'a, V: Value>(
+    matrix: &'a DiskTrustMatrix,
+    seed: &mut BTreeMap<u64, V>,
+    count: u64,
+    report: &mut ComputeReport,
+    start: Instant,
+) -> std::io::Result<PreparedMatrix<'a, V>> {
+    info!(
+        "PRE_PROCESS_START, LT_SIZE: {}, LT_EDGES: {}, SEED_SIZE: {}",
+        matrix.num_rows(),
+        matrix.num_edges(),
+        seed.len()
+    );
+    let prepared = PreparedMatrix::new(matrix, seed, count)?;
+    info!(
+        "PRE_PROCESS_FINISH: {:?}, LT_SIZE: {}, SEED_SIZE: {}",
+        start.elapsed(),
+        prepared.reachable.len(),
+        seed.len()
+    );
+    report.pre_process_ms = start.elapsed().as_millis() as u64;
+    // Every peer has a row once pre-processed, the unreachable ones being removed
+    report.lt_size_after = prepared.reachable.len();
+    report.seed_size_after = seed.len();
+    Ok(prepared)
+}
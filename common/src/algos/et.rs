@@ -1,116 +1,59 @@
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::{
-    collections::{BTreeMap, HashSet},
-    time::Instant,
-};
+use std::{collections::BTreeMap, convert::Infallible, time::Instant};
 use tracing::info;
 
+use super::checkpoint::ScoreCheckpoint;
+use super::common::{normalise_lt, normalise_scores, pre_process};
+use super::csr::CsrMatrix;
+use super::partitioned::PartitionedRun;
+use super::ComputeReport;
 use crate::runner::OutboundLocalTrust;
+use crate::value::Value;
 
 /// The trust weight given to the seed trust vector in the trust matrix calculation.
-const PRE_TRUST_WEIGHT: f32 = 0.25;
+pub const PRE_TRUST_WEIGHT: f64 = 0.25;
 
 /// The threshold value used for convergence check in the trust matrix calculation.
 ///
 /// If the absolute difference between the current score and the next score is
 /// less than `DELTA`, the score has converged.
-const DELTA: f32 = 0.000001;
+pub const DELTA: f64 = 0.000001;
 
-fn find_reachable_peers(
-    lt: &BTreeMap<u64, OutboundLocalTrust>,
-    seed: &BTreeMap<u64, f32>,
-) -> HashSet<u64> {
-    let mut to_visit: Vec<&u64> = seed.keys().collect();
-    let mut visited = HashSet::new();
-    while let Some(i) = to_visit.pop() {
-        if visited.contains(i) {
-            continue;
-        }
-        visited.insert(*i);
-        for (j, v) in lt.get(i).unwrap().outbound_trust_scores() {
-            if !visited.contains(j) && *v > 0.0 {
-                to_visit.push(j);
-            }
-        }
-    }
-    visited
-}
-
-/// Pre-processes a mutable local trust matrix `lt` by modifying it in-place:
-///
-/// - Removes self-trust (diagonal entries), as prohibited by EigenTrust.
-fn pre_process(
-    lt: &mut BTreeMap<u64, OutboundLocalTrust>,
-    seed: &mut BTreeMap<u64, f32>,
-    count: u64,
-) {
-    // Calculate the sum of all seed trust values.
-    let sum: f32 = seed.par_iter().map(|(_, v)| v).sum();
-
-    if sum == 0.0 {
-        for i in 0..count {
-            seed.insert(i, 1.0);
-        }
-    }
-
-    for from in 0..count {
-        let sum = lt.get(&from).map(|lt| lt.outbound_sum()).unwrap_or(&0.0);
-        // If peer does not have outbound trust,
-        // his trust will be distributed to seed peers based on their seed/pre-trust
-        if *sum == 0.0 {
-            let single_lt = OutboundLocalTrust::from_score_map(seed);
-            lt.insert(from, single_lt);
-        }
-    }
-
-    let reachable = find_reachable_peers(lt, seed);
-    lt.retain(|from, _| reachable.contains(from));
-}
-
-/// Normalizes the `lt` matrix by dividing each element by the sum of its row.
-fn normalise_lt(lt: &BTreeMap<u64, OutboundLocalTrust>) -> BTreeMap<u64, OutboundLocalTrust> {
-    lt.par_iter()
-        .fold(BTreeMap::new, |mut lt_norm, (from, from_map)| {
-            let from_map_norm = from_map.norm();
-            lt_norm.insert(*from, from_map_norm);
-            lt_norm
-        })
-        .reduce(BTreeMap::new, |mut acc, lt_norm| {
-            acc.extend(lt_norm);
-            acc
-        })
-}
-
-/// Normalizes the scores, to eliminate the rounding error
-fn normalise_scores(scores: &BTreeMap<u64, f32>) -> BTreeMap<u64, f32> {
-    // Calculate the sum of all seed trust values.
-    let sum: f32 = scores.par_iter().map(|(_, v)| v).sum();
-
-    scores
-        .par_iter()
-        .fold(BTreeMap::new, |mut scores, (i, value)| {
-            scores.insert(*i, *value / sum);
-            scores
-        })
-        .reduce(BTreeMap::new, |mut acc, scores| {
-            acc.extend(scores);
-            acc
-        })
-}
+/// The weight of the distrust subtracted from the scores, with negative trust entries.
+pub const DISTRUST_WEIGHT: f64 = 1.0;
 
 /// Performs the positive EigenTrust algorithm on the given local trust matrix (`lt`) and seed trust values (`seed`).
-/// The algorithm iteratively updates the scores of each node until convergence.
-/// It returns a vector of tuples containing the node ID and the final score.
-pub fn eigen_trust_run(
-    mut lt: BTreeMap<u64, OutboundLocalTrust>,
-    mut seed: BTreeMap<u64, f32>,
+/// The algorithm iteratively updates the scores of each node until convergence,
+/// or until `max_iter` iterations are performed, if set.
+/// It returns a vector of tuples containing the node ID and the final score, and the
+/// convergence diagnostics of the run.
+/// With a `checkpoint`, the scores are saved periodically and the run resumes from the last
+/// saved ones.
+/// Trust matrices of at least `partition_min_edges` edges run partitioned (see
+/// `partitioned::PARTITIONED_MIN_EDGES`). The threshold only depends on the graph, so that
+/// the nodes computing and verifying a job run it the same way.
+#[allow(clippy::too_many_arguments)]
+pub fn eigen_trust_run<V: Value>(
+    mut lt: BTreeMap<u64, OutboundLocalTrust<V>>,
+    mut seed: BTreeMap<u64, V>,
     count: u64,
-    alpha: Option<f32>,
-    delta: Option<f32>,
-) -> Vec<(u64, f32)> {
+    alpha: Option<f64>,
+    delta: Option<f64>,
+    max_iter: Option<u32>,
+    checkpoint: Option<&ScoreCheckpoint>,
+    partition_min_edges: usize,
+) -> (Vec<(u64, V)>, ComputeReport) {
     let start = Instant::now();
+    let mut report = ComputeReport {
+        lt_size_before: lt.len(),
+        seed_size_before: seed.len(),
+        ..Default::default()
+    };
     info!("ALPHA: {}", alpha.unwrap_or(PRE_TRUST_WEIGHT));
     info!("DELTA: {}", delta.unwrap_or(DELTA));
+    if let Some(max_iter) = max_iter {
+        info!("MAX_ITER: {}", max_iter);
+    }
     info!(
         "PRE_PROCESS_START, LT_SIZE: {}, SEED_SIZE: {}",
         lt.len(),
@@ -123,29 +66,80 @@ pub fn eigen_trust_run(
         lt.len(),
         seed.len()
     );
+    report.pre_process_ms = start.elapsed().as_millis() as u64;
+    report.lt_size_after = lt.len();
+    report.seed_size_after = seed.len();
     info!("NORMALISE_LT_SEED");
+    let start = Instant::now();
     seed = normalise_scores(&seed);
     lt = normalise_lt(&lt);
+    report.normalise_ms = start.elapsed().as_millis() as u64;
+    let lt = compile(lt);
 
-    // Initialize the scores of each node to the seed trust values.
+    if lt.len() >= partition_min_edges {
+        info!("PARTITIONED, LT_EDGES: {}", lt.len());
+        let scores = PartitionedRun::new(&lt, &seed, alpha).converge(
+            delta,
+            max_iter,
+            checkpoint,
+            &mut report,
+        );
+        return (scores.into_iter().collect(), report);
+    }
+    let Ok(scores) = converge::<_, Infallible>(
+        &seed,
+        alpha,
+        delta,
+        max_iter,
+        checkpoint,
+        &mut report,
+        |scores| Ok(lt.propagate(scores)),
+    );
+    (scores.into_iter().collect(), report)
+}
+
+/// Iterates the scores, starting from the (normalised) seed, until convergence or until
+/// `max_iter` iterations are performed. `propagate` computes the trust the peers receive from
+/// the given scores, the pre-trust being applied here, so that the trust matrix can be held in
+/// memory or streamed from disk alike.
+/// The iteration and scores are the whole state of the loop, so resuming them from a
+/// checkpoint gives the same scores as an uninterrupted run.
+pub(crate) fn converge<V: Value, E>(
+    seed: &BTreeMap<u64, V>,
+    alpha: Option<f64>,
+    delta: Option<f64>,
+    max_iter: Option<u32>,
+    checkpoint: Option<&ScoreCheckpoint>,
+    report: &mut ComputeReport,
+    mut propagate: impl FnMut(&BTreeMap<u64, V>) -> Result<BTreeMap<u64, V>, E>,
+) -> Result<BTreeMap<u64, V>, E> {
+    // Initialize the scores of each node to the seed trust values, or to the checkpoint.
     let mut scores = seed.clone();
+    let mut i = 0;
+    if let Some((iteration, saved)) = checkpoint.and_then(|c| c.load()) {
+        info!("RESUME_FROM_CHECKPOINT, ITER: {}", iteration);
+        report.resumed_from = Some(iteration);
+        scores = saved;
+        i = iteration;
+    }
     // Iterate until convergence.
 
     info!("COMPUTE_START");
     let start = Instant::now();
-    let mut i = 0;
     loop {
         // Calculate the n+1 scores of each node.
-        let n_plus_1_scores = iteration(&lt, &seed, &scores, alpha);
+        let n_plus_1_scores = apply_pre_trust(propagate(&scores)?, seed, alpha);
         // Normalise n+1 scores.
         let n_plus_1_scores = normalise_scores(&n_plus_1_scores);
         // Calculate the n+2 scores of each node.
-        let n_plus_2_scores = iteration(&lt, &seed, &n_plus_1_scores, alpha);
+        let n_plus_2_scores = apply_pre_trust(propagate(&n_plus_1_scores)?, seed, alpha);
         // Normalise n+2 scores
         let n_plus_2_scores = normalise_scores(&n_plus_2_scores);
         // Check for convergence.
         let (is_converged, delta) = is_converged(&n_plus_1_scores, &n_plus_2_scores, delta);
         info!("ITER: {}, CONVERGED: {}, DELTA: {}", i, is_converged, delta);
+        report.final_delta = Some(delta.to_f64());
+        report.converged = Some(is_converged);
         if is_converged {
             // Return previous iteration, since the scores are converged.
             scores = n_plus_1_scores;
@@ -155,6 +149,16 @@ pub fn eigen_trust_run(
             scores = n_plus_2_scores;
         }
         i += 1;
+        if max_iter.is_some_and(|max_iter| i >= max_iter) {
+            info!("MAX_ITER_REACHED: {}", i);
+            break;
+        }
+        if let Some(checkpoint) = checkpoint.filter(|c| c.is_due(i)) {
+            checkpoint.save(i, &scores);
+        }
+    }
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.remove();
     }
     info!(
         "COMPUTE_END: {:?}, NUM_SCORES: {}, NUM_ITER: {}",
@@ -162,41 +166,46 @@ pub fn eigen_trust_run(
         scores.len(),
         i
     );
-    scores.into_iter().collect()
+    report.iterations = i;
+    report.compute_ms = start.elapsed().as_millis() as u64;
+    Ok(scores)
 }
 
 /// Given the previous scores (`scores`) and the next scores (`next_scores`), checks if the scores have converged.
 /// It returns `true` if the scores have converged and `false` otherwise.
-pub fn is_converged(
-    scores: &BTreeMap<u64, f32>,
-    next_scores: &BTreeMap<u64, f32>,
-    delta: Option<f32>,
-) -> (bool, f32) {
+pub fn is_converged<V: Value>(
+    scores: &BTreeMap<u64, V>,
+    next_scores: &BTreeMap<u64, V>,
+    delta: Option<f64>,
+) -> (bool, V) {
     // Iterate over the scores and check if they have converged.
     let total_delta = scores
         .par_iter()
         .fold(
-            || 0.0,
+            || V::ZERO,
             |sum, (i, v)| {
                 // Get the next score of the node.
-                let next_score = next_scores.get(i).unwrap_or(&0.0);
-                (next_score - v).abs() + sum
+                let next_score = next_scores.get(i).copied().unwrap_or(V::ZERO);
+                (next_score - *v).abs() + sum
             },
         )
-        .reduce(|| 0.0, |sum_a, sum_b| sum_a + sum_b);
-    (total_delta <= delta.unwrap_or(DELTA), total_delta)
+        .reduce(|| V::ZERO, |sum_a, sum_b| sum_a + sum_b);
+    (
+        total_delta <= V::from_f64(delta.unwrap_or(DELTA)),
+        total_delta,
+    )
 }
 
 /// It performs a single iteration of the positive run EigenTrust algorithm on the given local trust matrix (`lt`),
 /// seed trust values (`seed`), and previous scores (`scores`).
 /// It returns `true` if the scores have converged and `false` otherwise.
-pub fn convergence_check(
-    mut lt: BTreeMap<u64, OutboundLocalTrust>,
-    mut seed: BTreeMap<u64, f32>,
-    scores: &BTreeMap<u64, f32>,
+pub fn convergence_check<V: Value>(
+    mut lt: BTreeMap<u64, OutboundLocalTrust<V>>,
+    mut seed: BTreeMap<u64, V>,
+    scores: &BTreeMap<u64, V>,
     count: u64,
-    alpha: Option<f32>,
-    delta: Option<f32>,
+    alpha: Option<f64>,
+    delta: Option<f64>,
 ) -> bool {
     info!(
         "PRE_PROCESS_START, LT_SIZE: {}, SEED_SIZE: {}",
@@ -212,6 +221,7 @@ pub fn convergence_check(
     info!("NORMALISE_LT_SEED");
     seed = normalise_scores(&seed);
     lt = normalise_lt(&lt);
+    let lt = compile(lt);
 
     info!("CONVERGENCE_START");
     let start = Instant::now();
@@ -231,41 +241,148 @@ pub fn convergence_check(
     is_converged
 }
 
-fn iteration(
-    lt: &BTreeMap<u64, OutboundLocalTrust>,
-    seed: &BTreeMap<u64, f32>,
-    scores: &BTreeMap<u64, f32>,
-    alpha: Option<f32>,
-) -> BTreeMap<u64, f32> {
-    // Step 1-3: Compute raw contributions per node
-    let mut next_scores = lt
-        .par_iter()
-        .map(|(from, from_map)| {
-            let origin_score = scores.get(from).unwrap_or(&0.0);
-            let mut partial = BTreeMap::new();
-            for (to, value) in from_map.outbound_trust_scores() {
-                let score = *value * origin_score;
-                let to_score = partial.get(to).unwrap_or(&0.0);
-                partial.insert(*to, to_score + score);
-            }
-            partial
+/// Subtracts the distrust from the EigenTrust scores, for trust with negative entries: every
+/// peer distrusts the peers of its negative entries (normalised by row) with its own score,
+/// weighted by `distrust_weight`. Only the distrust of trusted peers counts, and it is applied
+/// once rather than propagated, as the distrust of a distrusted peer means nothing.
+/// Scores are floored at 0 and not normalised again: they add up to 1 minus the distrust.
+pub fn apply_distrust<V: Value>(
+    scores: Vec<(u64, V)>,
+    distrust: &BTreeMap<u64, OutboundLocalTrust<V>>,
+    distrust_weight: Option<f64>,
+) -> Vec<(u64, V)> {
+    let weight = V::from_f64(distrust_weight.unwrap_or(DISTRUST_WEIGHT));
+    let trust: BTreeMap<u64, V> = scores.iter().copied().collect();
+    let mut received: BTreeMap<u64, V> = BTreeMap::new();
+    for (from, from_map) in distrust {
+        let Some(score) = trust.get(from) else {
+            continue;
+        };
+        for (to, value) in from_map.norm().outbound_trust_scores() {
+            *received.entry(*to).or_default() += *score * *value;
+        }
+    }
+    info!(
+        "APPLY_DISTRUST, WEIGHT: {}, PEERS: {}",
+        weight,
+        received.len()
+    );
+    scores
+        .into_iter()
+        .map(|(i, score)| {
+            let distrust = received.get(&i).copied().unwrap_or(V::ZERO);
+            (i, (score - weight * distrust).max(V::ZERO))
         })
-        .reduce(
-            || BTreeMap::new(),
-            |mut acc, partial| {
-                for (k, v) in partial {
-                    *acc.entry(k).or_insert(0.0) += v;
-                }
-                acc
-            },
-        );
+        .collect()
+}
+
+/// Compiles the normalised trust map into CSR arrays, dropping the map, so that the
+/// iterations read the trust from contiguous memory.
+fn compile<V: Value>(lt: BTreeMap<u64, OutboundLocalTrust<V>>) -> CsrMatrix<V> {
+    let start = Instant::now();
+    let csr = CsrMatrix::from_lt(&lt);
+    info!(
+        "COMPILE_CSR: {:?}, LT_EDGES: {}",
+        start.elapsed(),
+        csr.len()
+    );
+    csr
+}
 
+fn iteration<V: Value>(
+    lt: &CsrMatrix<V>,
+    seed: &BTreeMap<u64, V>,
+    scores: &BTreeMap<u64, V>,
+    alpha: Option<f64>,
+) -> BTreeMap<u64, V> {
+    // Step 1-3: Compute raw contributions per node
+    apply_pre_trust(lt.propagate(scores), seed, alpha)
+}
+
+/// Weighs the received trust with the pre-trust of the nodes.
+fn apply_pre_trust<V: Value>(
+    mut next_scores: BTreeMap<u64, V>,
+    seed: &BTreeMap<u64, V>,
+    alpha: Option<f64>,
+) -> BTreeMap<u64, V> {
     // Step 4: Apply pre-trust weighted normalization
-    let alpha = alpha.unwrap_or(PRE_TRUST_WEIGHT);
+    let alpha = V::from_f64(alpha.unwrap_or(PRE_TRUST_WEIGHT));
     for (i, v) in &mut next_scores {
-        let pre_trust = seed.get(i).unwrap_or(&0.0);
-        *v = alpha * pre_trust + *v * (1.0 - alpha);
+        let pre_trust = seed.get(i).copied().unwrap_or(V::ZERO);
+        *v = alpha * pre_trust + *v * (V::ONE - alpha);
     }
 
     next_scores
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::JobDescription;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_apply_distrust_of_trusted_peers() {
+        let mut distrust: BTreeMap<u64, OutboundLocalTrust<f64>> = BTreeMap::new();
+        // 0 distrusts 2 and 3 equally, 4 (without score) distrusts 1
+        for (from, to, value) in [(0, 2, 2.0), (0, 3, 2.0), (4, 1, 1.0)] {
+            distrust.entry(from).or_default().insert(to, value);
+        }
+        let scores = vec![(0, 0.5), (1, 0.125), (2, 0.25), (3, 0.125)];
+        assert_eq!(
+            apply_distrust(scores.clone(), &distrust, None),
+            vec![(0, 0.5), (1, 0.125), (2, 0.0), (3, 0.0)]
+        );
+        assert_eq!(
+            apply_distrust(scores.clone(), &distrust, Some(0.5)),
+            vec![(0, 0.5), (1, 0.125), (2, 0.125), (3, 0.0)]
+        );
+        assert_eq!(
+            apply_distrust(scores.clone(), &BTreeMap::new(), None),
+            scores
+        );
+    }
+
+    #[test]
+    fn should_resume_from_checkpoint() {
+        let mut lt: BTreeMap<u64, OutboundLocalTrust<f64>> = BTreeMap::new();
+        for (from, to, value) in [(0, 1, 0.5), (0, 2, 0.5), (1, 2, 1.0), (2, 0, 1.0)] {
+            lt.entry(from).or_default().insert(to, value);
+        }
+        let lt = CsrMatrix::from_lt(&lt);
+        let seed = BTreeMap::from([(0, 1.0)]);
+        let delta = Some(1e-12);
+        let run = |checkpoint, fail_at: Option<u32>| {
+            let mut calls = 0;
+            let mut report = ComputeReport::default();
+            let scores = converge(&seed, None, delta, None, checkpoint, &mut report, |s| {
+                calls += 1;
+                match fail_at {
+                    Some(fail_at) if calls == fail_at => Err(()),
+                    _ => Ok(lt.propagate(s)),
+                }
+            });
+            (scores, report)
+        };
+        let (expected, expected_report) = run(None, None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobDescription::new(
+            "job".to_string(),
+            "trust".to_string(),
+            "seed".to_string(),
+            0,
+            HashMap::new(),
+        );
+        let checkpoint = ScoreCheckpoint::new(dir.path(), &job, 2);
+        // Interrupted during the 5th iteration, after the checkpoint of the 4th
+        assert!(run(Some(&checkpoint), Some(9)).0.is_err());
+        assert_eq!(checkpoint.load::<f64>().map(|(i, _)| i), Some(4));
+
+        let (scores, report) = run(Some(&checkpoint), None);
+        assert_eq!(scores, expected);
+        assert_eq!(report.resumed_from, Some(4));
+        assert_eq!(report.iterations, expected_report.iterations);
+        assert!(!checkpoint.path().exists());
+    }
+}
@@ -1,2 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// Score checkpoints resuming long EigenTrust runs.
+pub mod checkpoint;
+/// Pre-processing and normalization shared by every algorithm, so they can't diverge.
+pub mod common;
+mod csr;
+/// EigenTrust and SybilRank on a trust matrix held on disk.
+pub mod disk;
 pub mod et;
+/// EigenTrust over dense score vectors sharded across the threads, for large graphs.
+pub mod partitioned;
 pub mod sr;
+
+/// Convergence diagnostics of a compute run, kept alongside the scores for audits.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComputeReport {
+    /// Number of iterations (EigenTrust) or walk steps (SybilRank) performed.
+    pub iterations: u32,
+    /// Delta of the last convergence check, `None` for algorithms without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_delta: Option<f64>,
+    /// Whether the scores converged before reaching the iteration cap, `None` for algorithms
+    /// without a convergence check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub converged: Option<bool>,
+    /// Iteration the run resumed from, when it started from a checkpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resumed_from: Option<u32>,
+    /// Sizes of the local trust matrix and the seed, before and after the pre-processing.
+    pub lt_size_before: usize,
+    pub lt_size_after: usize,
+    pub seed_size_before: usize,
+    pub seed_size_after: usize,
+    /// Runtime of each phase, in milliseconds.
+    pub pre_process_ms: u64,
+    pub normalise_ms: u64,
+    pub compute_ms: u64,
+}
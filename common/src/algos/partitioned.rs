@@ -0,0 +1,252 @@
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tracing::info;
+
+use super::checkpoint::ScoreCheckpoint;
+use super::csr::{row_sum, CsrMatrix};
+use super::et::{DELTA, PRE_TRUST_WEIGHT};
+use super::ComputeReport;
+use crate::value::Value;
+
+/// Edges of the trust matrix from which EigenTrust runs partitioned. On smaller graphs an
+/// iteration is too short for the score maps to matter, the `eigen_trust` and
+/// `eigen_trust_partitioned` stages of `bench-report` being on par up to about this size.
+pub const PARTITIONED_MIN_EDGES: usize = 1_000_000;
+
+/// Destination peers of a partition. The partitions are fixed rather than split by rayon, so
+/// the partial sums are reduced in the same order whatever the number of threads, and every
+/// machine computes the same scores.
+const PARTITION_PEERS: usize = 4096;
+
+/// EigenTrust iterations over dense score vectors, the destination peers being sharded across
+/// the threads in fixed partitions.
+///
+/// Each partition computes the received trust, the pre-trust and the partial sum of its peers
+/// in a single pass, writing to its slice of a preallocated vector: nothing is merged, and the
+/// normalisation and the convergence check are in-place passes over the same partitions.
+/// As in `et::converge`, only the peers trusted by at least one peer have a score, and the
+/// checkpoints are interchangeable with the ones of the map computation.
+pub(crate) struct PartitionedRun<'a, V> {
+    lt: &'a CsrMatrix<V>,
+    seed: Vec<V>,
+    alpha: V,
+}
+
+impl<'a, V: Value> PartitionedRun<'a, V> {
+    /// Run of the compiled (normalised) trust matrix from the normalised seed.
+    pub(crate) fn new(lt: &'a CsrMatrix<V>, seed: &BTreeMap<u64, V>, alpha: Option<f64>) -> Self {
+        Self {
+            lt,
+            seed: to_dense(seed, lt.num_peers()),
+            alpha: V::from_f64(alpha.unwrap_or(PRE_TRUST_WEIGHT)),
+        }
+    }
+
+    /// Iterates the scores until convergence or until `max_iter` iterations are performed,
+    /// like `et::converge`.
+    pub(crate) fn converge(
+        &self,
+        delta: Option<f64>,
+        max_iter: Option<u32>,
+        checkpoint: Option<&ScoreCheckpoint>,
+        report: &mut ComputeReport,
+    ) -> BTreeMap<u64, V> {
+        let num_peers = self.lt.num_peers();
+        let mut scores = self.seed.clone();
+        let mut i = 0;
+        if let Some((iteration, saved)) = checkpoint.and_then(|c| c.load()) {
+            info!("RESUME_FROM_CHECKPOINT, ITER: {}", iteration);
+            report.resumed_from = Some(iteration);
+            scores = to_dense(&saved, num_peers);
+            i = iteration;
+        }
+        let mut n_plus_1_scores = vec![V::ZERO; num_peers];
+        let mut n_plus_2_scores = vec![V::ZERO; num_peers];
+
+        info!(
+            "COMPUTE_START, PARTITIONS: {}",
+            num_peers.div_ceil(PARTITION_PEERS)
+        );
+        let start = Instant::now();
+        loop {
+            self.step(&scores, &mut n_plus_1_scores);
+            self.step(&n_plus_1_scores, &mut n_plus_2_scores);
+            let total_delta = distance(&n_plus_1_scores, &n_plus_2_scores);
+            let is_converged = total_delta <= V::from_f64(delta.unwrap_or(DELTA));
+            info!(
+                "ITER: {}, CONVERGED: {}, DELTA: {}",
+                i, is_converged, total_delta
+            );
+            report.final_delta = Some(total_delta.to_f64());
+            report.converged = Some(is_converged);
+            if is_converged {
+                // Return previous iteration, since the scores are converged.
+                std::mem::swap(&mut scores, &mut n_plus_1_scores);
+                break;
+            }
+            std::mem::swap(&mut scores, &mut n_plus_2_scores);
+            i += 1;
+            if max_iter.is_some_and(|max_iter| i >= max_iter) {
+                info!("MAX_ITER_REACHED: {}", i);
+                break;
+            }
+            if let Some(checkpoint) = checkpoint.filter(|c| c.is_due(i)) {
+                checkpoint.save(i, &self.to_map(&scores));
+            }
+        }
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.remove();
+        }
+        let scores = self.to_map(&scores);
+        info!(
+            "COMPUTE_END: {:?}, NUM_SCORES: {}, NUM_ITER: {}",
+            start.elapsed(),
+            scores.len(),
+            i
+        );
+        report.iterations = i;
+        report.compute_ms = start.elapsed().as_millis() as u64;
+        scores
+    }
+
+    /// Computes the normalised next scores of the scores: the received trust weighed with the
+    /// pre-trust, divided by their sum.
+    fn step(&self, scores: &[V], next_scores: &mut [V]) {
+        let alpha = self.alpha;
+        let sums: Vec<V> = next_scores
+            .par_chunks_mut(PARTITION_PEERS)
+            .enumerate()
+            .map(|(partition, next_scores)| {
+                let first = partition * PARTITION_PEERS;
+                let mut sum = V::ZERO;
+                for (k, next_score) in next_scores.iter_mut().enumerate() {
+                    let (cols, values) = self.lt.row(first + k);
+                    *next_score = if cols.is_empty() {
+                        V::ZERO
+                    } else {
+                        alpha * self.seed[first + k]
+                            + row_sum(cols, values, scores) * (V::ONE - alpha)
+                    };
+                    sum += *next_score;
+                }
+                sum
+            })
+            .collect();
+        let sum: V = sums.iter().sum();
+        if sum == V::ZERO {
+            return;
+        }
+        next_scores
+            .par_chunks_mut(PARTITION_PEERS)
+            .for_each(|next_scores| next_scores.iter_mut().for_each(|v| *v /= sum));
+    }
+
+    /// Scores of the peers trusted by at least one peer.
+    fn to_map(&self, scores: &[V]) -> BTreeMap<u64, V> {
+        scores
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.lt.row(*i).0.is_empty())
+            .map(|(i, score)| (i as u64, *score))
+            .collect()
+    }
+}
+
+/// Scores of the peers of the matrix as a dense vector, the other peers being dropped as they
+/// don't propagate.
+fn to_dense<V: Value>(scores: &BTreeMap<u64, V>, num_peers: usize) -> Vec<V> {
+    let mut dense = vec![V::ZERO; num_peers];
+    for (i, score) in scores.range(..num_peers as u64) {
+        dense[*i as usize] = *score;
+    }
+    dense
+}
+
+/// Sum of the absolute differences of the scores, reduced in partition order.
+fn distance<V: Value>(scores: &[V], next_scores: &[V]) -> V {
+    let sums: Vec<V> = scores
+        .par_chunks(PARTITION_PEERS)
+        .zip(next_scores.par_chunks(PARTITION_PEERS))
+        .map(|(scores, next_scores)| {
+            scores
+                .iter()
+                .zip(next_scores)
+                .map(|(v, next)| (*next - *v).abs())
+                .sum::<V>()
+        })
+        .collect();
+    sums.iter().sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algos::et::converge;
+    use crate::runner::OutboundLocalTrust;
+    use crate::JobDescription;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+
+    #[test]
+    fn should_converge_like_the_map_computation() {
+        // More peers than a partition, 4 being trusted by no one
+        let mut lt: BTreeMap<u64, OutboundLocalTrust<f64>> = BTreeMap::new();
+        let num_peers = PARTITION_PEERS as u64 * 2 + 5;
+        for from in (0..num_peers).filter(|from| *from != 4) {
+            for to in [(from + 1) % num_peers, (from * 7 + 3) % num_peers] {
+                if to != 4 {
+                    lt.entry(from).or_default().insert(to, 0.5);
+                }
+            }
+        }
+        let lt = CsrMatrix::from_lt(&lt);
+        // Seed 4 has no score, as no one trusts it
+        let seed = BTreeMap::from([(0, 0.5), (4, 0.25), (9000, 0.25)]);
+        let delta = Some(1e-12);
+
+        let mut expected_report = ComputeReport::default();
+        let map_run = |max_iter, report: &mut ComputeReport| {
+            let Ok(scores) =
+                converge::<_, Infallible>(&seed, None, delta, max_iter, None, report, |scores| {
+                    Ok(lt.propagate(scores))
+                });
+            scores
+        };
+        let expected = map_run(None, &mut expected_report);
+        let after_4 = map_run(Some(4), &mut ComputeReport::default());
+        let run = PartitionedRun::new(&lt, &seed, None);
+        let mut report = ComputeReport::default();
+        let scores = run.converge(delta, None, None, &mut report);
+        assert_eq!(report.iterations, expected_report.iterations);
+        assert_eq!(
+            scores.keys().collect::<Vec<_>>(),
+            expected.keys().collect::<Vec<_>>()
+        );
+        assert!(!scores.contains_key(&4));
+        let assert_close = |scores: &BTreeMap<u64, f64>| {
+            for (i, score) in scores {
+                assert!((score - expected[i]).abs() < 1e-12);
+            }
+        };
+        assert_close(&scores);
+
+        // Resumes from the checkpoints of the map computation
+        let dir = tempfile::tempdir().unwrap();
+        let job = JobDescription::new(
+            "job".to_string(),
+            "trust".to_string(),
+            "seed".to_string(),
+            0,
+            HashMap::new(),
+        );
+        let checkpoint = ScoreCheckpoint::new(dir.path(), &job, 2);
+        checkpoint.save(4, &after_4);
+        let mut report = ComputeReport::default();
+        assert_close(&run.converge(delta, None, Some(&checkpoint), &mut report));
+        assert_eq!(report.resumed_from, Some(4));
+        assert_eq!(report.iterations, expected_report.iterations);
+        assert!(!checkpoint.path().exists());
+    }
+}
@@ -1,131 +1,41 @@
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::{
-    collections::{BTreeMap, HashSet},
-    time::Instant,
-};
+use std::{collections::BTreeMap, convert::Infallible, time::Instant};
 use tracing::info;
 
+use super::common::{normalise_lt, normalise_scores, pre_process};
+use super::ComputeReport;
 use crate::runner::OutboundLocalTrust;
+use crate::value::Value;
 
 /// The number of random walk steps to perform in the Sybil Rank algorithm.
-const WALK_LENGTH: u32 = 10;
-
-fn find_reachable_peers(
-    lt: &BTreeMap<u64, OutboundLocalTrust>,
-    seed: &BTreeMap<u64, f32>,
-) -> HashSet<u64> {
-    let mut to_visit: Vec<&u64> = seed.keys().collect();
-    let mut visited = HashSet::new();
-    while let Some(i) = to_visit.pop() {
-        if visited.contains(i) {
-            continue;
-        }
-        visited.insert(*i);
-        for (j, v) in lt.get(i).unwrap().outbound_trust_scores() {
-            if !visited.contains(j) && *v > 0.0 {
-                to_visit.push(j);
-            }
-        }
-    }
-    visited
-}
-
-/// Pre-processes a mutable local trust matrix `lt` by modifying it in-place:
-///
-/// - Removes self-trust (diagonal entries), as prohibited by Sybil Rank.
-/// - Ensures all nodes have outbound trust, redistributing to seed peers if necessary.
-fn pre_process(
-    lt: &mut BTreeMap<u64, OutboundLocalTrust>,
-    seed: &mut BTreeMap<u64, f32>,
-    count: u64,
-) {
-    // Calculate the sum of all seed trust values.
-    let sum: f32 = seed.par_iter().map(|(_, v)| v).sum();
-
-    if sum == 0.0 {
-        for i in 0..count {
-            seed.insert(i, 1.0);
-        }
-    }
-
-    for from in 0..count {
-        let sum = lt.get(&from).map(|lt| lt.outbound_sum()).unwrap_or(&0.0);
-        // If peer does not have outbound trust,
-        // his trust will be distributed to seed peers based on their seed/pre-trust
-        if *sum == 0.0 {
-            let single_lt = OutboundLocalTrust::from_score_map(seed);
-            lt.insert(from, single_lt);
-        }
-    }
-
-    let reachable = find_reachable_peers(lt, seed);
-    lt.retain(|from, _| reachable.contains(from));
-}
-
-/// Normalizes the `lt` matrix by dividing each element by the sum of its row.
-fn normalise_lt(lt: &BTreeMap<u64, OutboundLocalTrust>) -> BTreeMap<u64, OutboundLocalTrust> {
-    lt.par_iter()
-        .fold(BTreeMap::new, |mut lt_norm, (from, from_map)| {
-            let from_map_norm = from_map.norm();
-            lt_norm.insert(*from, from_map_norm);
-            lt_norm
-        })
-        .reduce(BTreeMap::new, |mut acc, lt_norm| {
-            acc.extend(lt_norm);
-            acc
-        })
-}
-
-/// Normalizes the scores, to eliminate the rounding error
-fn normalise_scores(scores: &BTreeMap<u64, f32>) -> BTreeMap<u64, f32> {
-    // Calculate the sum of all seed trust values.
-    let sum: f32 = scores.par_iter().map(|(_, v)| v).sum();
-
-    if sum == 0.0 {
-        return scores.clone();
-    }
-
-    scores
-        .par_iter()
-        .fold(BTreeMap::new, |mut scores, (i, value)| {
-            scores.insert(*i, *value / sum);
-            scores
-        })
-        .reduce(BTreeMap::new, |mut acc, scores| {
-            acc.extend(scores);
-            acc
-        })
-}
+pub const WALK_LENGTH: u32 = 10;
 
 /// Performs a single deterministic walk step following the trust edges.
 /// This is the core of SybilRank - no damping/restart, just pure edge following.
 /// Returns the new distribution after one step of the random walk.
-fn fixed_walk_step(
-    lt: &BTreeMap<u64, OutboundLocalTrust>,
-    current_scores: &BTreeMap<u64, f32>,
-) -> BTreeMap<u64, f32> {
+fn fixed_walk_step<V: Value>(
+    lt: &BTreeMap<u64, OutboundLocalTrust<V>>,
+    current_scores: &BTreeMap<u64, V>,
+) -> BTreeMap<u64, V> {
     // Compute the distribution after following edges (no restart probability)
     let next_scores = lt
         .par_iter()
         .map(|(from, from_map)| {
-            let origin_score = current_scores.get(from).unwrap_or(&0.0);
+            let origin_score = current_scores.get(from).copied().unwrap_or(V::ZERO);
             let mut partial = BTreeMap::new();
             for (to, value) in from_map.outbound_trust_scores() {
                 let score = *value * origin_score;
-                let to_score = partial.get(to).unwrap_or(&0.0);
+                let to_score = partial.get(to).copied().unwrap_or(V::ZERO);
                 partial.insert(*to, to_score + score);
             }
             partial
         })
-        .reduce(
-            || BTreeMap::new(),
-            |mut acc, partial| {
-                for (k, v) in partial {
-                    *acc.entry(k).or_insert(0.0) += v;
-                }
-                acc
-            },
-        );
+        .reduce(BTreeMap::new, |mut acc, partial| {
+            for (k, v) in partial {
+                *acc.entry(k).or_insert(V::ZERO) += v;
+            }
+            acc
+        });
 
     next_scores
 }
@@ -134,15 +44,21 @@ fn fixed_walk_step(
 /// The algorithm performs random walks of exactly `walk_length` steps from seed nodes.
 /// The key insight is that walks from honest nodes stay in honest regions, while walks from
 /// Sybil nodes spread more broadly, allowing discrimination between honest and Sybil nodes.
-/// It returns a vector of tuples containing the node ID and the final score.
-pub fn sybil_rank_run(
-    mut lt: BTreeMap<u64, OutboundLocalTrust>,
-    mut seed: BTreeMap<u64, f32>,
+/// It returns a vector of tuples containing the node ID and the final score, and the
+/// diagnostics of the run.
+pub fn sybil_rank_run<V: Value>(
+    mut lt: BTreeMap<u64, OutboundLocalTrust<V>>,
+    mut seed: BTreeMap<u64, V>,
     count: u64,
     walk_length: Option<u32>,
-) -> Vec<(u64, f32)> {
+) -> (Vec<(u64, V)>, ComputeReport) {
     let start = Instant::now();
     let walk_len = walk_length.unwrap_or(WALK_LENGTH);
+    let mut report = ComputeReport {
+        lt_size_before: lt.len(),
+        seed_size_before: seed.len(),
+        ..Default::default()
+    };
 
     info!("WALK_LENGTH: {}", walk_len);
     info!(
@@ -158,11 +74,31 @@ pub fn sybil_rank_run(
         lt.len(),
         seed.len()
     );
+    report.pre_process_ms = start.elapsed().as_millis() as u64;
+    report.lt_size_after = lt.len();
+    report.seed_size_after = seed.len();
 
     info!("NORMALISE_LT_SEED");
+    let start = Instant::now();
     seed = normalise_scores(&seed);
     lt = normalise_lt(&lt);
+    report.normalise_ms = start.elapsed().as_millis() as u64;
+
+    let Ok(final_scores) = walk::<_, Infallible>(&seed, walk_len, &mut report, |scores| {
+        Ok(fixed_walk_step(&lt, scores))
+    });
+    (final_scores.into_iter().collect(), report)
+}
 
+/// Performs exactly `walk_len` walk steps from the (normalised) seed, `step` following the
+/// trust edges from the given scores, so that the trust matrix can be held in memory or
+/// streamed from disk alike.
+pub(crate) fn walk<V: Value, E>(
+    seed: &BTreeMap<u64, V>,
+    walk_len: u32,
+    report: &mut ComputeReport,
+    mut step: impl FnMut(&BTreeMap<u64, V>) -> Result<BTreeMap<u64, V>, E>,
+) -> Result<BTreeMap<u64, V>, E> {
     info!("SYBIL_RANK_START");
     let start = Instant::now();
 
@@ -171,7 +107,7 @@ pub fn sybil_rank_run(
 
     // Perform exactly walk_len steps - no convergence checking
     for _step in 0..walk_len {
-        current_scores = fixed_walk_step(&lt, &current_scores);
+        current_scores = step(&current_scores)?;
         current_scores = normalise_scores(&current_scores);
     }
 
@@ -183,6 +119,7 @@ pub fn sybil_rank_run(
         final_scores.len(),
         walk_len
     );
-
-    final_scores.into_iter().collect()
+    report.iterations = walk_len;
+    report.compute_ms = start.elapsed().as_millis() as u64;
+    Ok(final_scores)
 }
@@ -0,0 +1,215 @@
+use alloy::hex;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Size of the report data of SGX, SEV-SNP and TDX reports.
+pub const REPORT_DATA_LEN: usize = 64;
+
+/// Trusted execution environment an attestation report comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TeeKind {
+    Sgx,
+    SevSnp,
+    Tdx,
+}
+
+impl FromStr for TeeKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sgx" => Ok(Self::Sgx),
+            "sev-snp" | "snp" => Ok(Self::SevSnp),
+            "tdx" => Ok(Self::Tdx),
+            other => Err(Error::UnknownTee(other.to_string())),
+        }
+    }
+}
+
+impl TeeKind {
+    /// Offset of the report data in the quote (SGX DCAP v3 and TDX v4 quotes) or report
+    /// (SEV-SNP attestation report).
+    fn report_data_offset(&self) -> usize {
+        match self {
+            // Quote header (48 bytes), then the report body with the report data at 320
+            Self::Sgx => 48 + 320,
+            Self::SevSnp => 0x50,
+            // Quote header (48 bytes), then the TD report body with the report data at 520
+            Self::Tdx => 48 + 520,
+        }
+    }
+}
+
+/// Returns the report data binding a quote to the meta commitment of a result: the
+/// commitment, zero padded.
+pub fn report_data(meta_commitment: &[u8]) -> [u8; REPORT_DATA_LEN] {
+    let mut report_data = [0u8; REPORT_DATA_LEN];
+    let len = meta_commitment.len().min(REPORT_DATA_LEN);
+    report_data[..len].copy_from_slice(&meta_commitment[..len]);
+    report_data
+}
+
+/// Attestation of the results of a compute id, uploaded under `attestation/{results_id}`
+/// next to the results meta. The quote proves the results were produced in a genuine TEE, and
+/// its report data is the meta commitment submitted on-chain.
+///
+/// `check_binding` only checks the quote is bound to the commitment, the quote itself (its
+/// signature and certificate chain) is verified with the tooling of the vendor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationReport {
+    pub tee: TeeKind,
+    /// Hex encoded meta commitment the quote is bound to.
+    pub meta_commitment: String,
+    /// Base64 encoded quote (SGX, TDX) or attestation report (SEV-SNP).
+    pub quote: String,
+}
+
+impl AttestationReport {
+    pub fn new(tee: TeeKind, meta_commitment: &[u8], quote: &[u8]) -> Self {
+        Self {
+            tee,
+            meta_commitment: hex::encode(meta_commitment),
+            quote: STANDARD.encode(quote),
+        }
+    }
+
+    pub fn quote_bytes(&self) -> Result<Vec<u8>, Error> {
+        STANDARD
+            .decode(&self.quote)
+            .map_err(|e| Error::InvalidQuote(e.to_string()))
+    }
+
+    /// Returns the report data of the quote.
+    pub fn quote_report_data(&self) -> Result<Vec<u8>, Error> {
+        quote_report_data(self.tee, &self.quote)
+    }
+
+    /// Checks the report is for the meta commitment (hex encoded), and its quote bound to it.
+    pub fn check_binding(&self, meta_commitment: &str) -> Result<(), Error> {
+        let expected = meta_commitment.trim_start_matches("0x").to_lowercase();
+        if self.meta_commitment.trim_start_matches("0x").to_lowercase() != expected {
+            return Err(Error::CommitmentMismatch {
+                expected,
+                actual: self.meta_commitment.clone(),
+            });
+        }
+        let commitment = hex::decode(&expected).map_err(|e| Error::InvalidQuote(e.to_string()))?;
+        if self.quote_report_data()? != report_data(&commitment) {
+            return Err(Error::ReportDataMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Attestation of the TLS certificate served by a node, published in its identity. The report
+/// data of the quote is the SHA-256 fingerprint of the DER certificate, so clients pinning the
+/// certificate know the TLS connections end in the TEE.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsAttestation {
+    pub tee: TeeKind,
+    /// Base64 encoded quote (SGX, TDX) or attestation report (SEV-SNP).
+    pub quote: String,
+}
+
+impl TlsAttestation {
+    pub fn new(tee: TeeKind, quote: &[u8]) -> Self {
+        Self {
+            tee,
+            quote: STANDARD.encode(quote),
+        }
+    }
+
+    /// Checks the quote is bound to the DER certificate.
+    pub fn check_binding(&self, certificate_der: &[u8]) -> Result<(), Error> {
+        let fingerprint = certificate_fingerprint(certificate_der);
+        if quote_report_data(self.tee, &self.quote)? != report_data(&fingerprint) {
+            return Err(Error::CertificateMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 fingerprint of a DER certificate, as shown by browsers and `openssl x509
+/// -fingerprint -sha256`.
+pub fn certificate_fingerprint(certificate_der: &[u8]) -> [u8; 32] {
+    Sha256::digest(certificate_der).into()
+}
+
+/// Returns the report data of the (base64 encoded) quote of the TEE.
+fn quote_report_data(tee: TeeKind, quote: &str) -> Result<Vec<u8>, Error> {
+    let quote = STANDARD
+        .decode(quote)
+        .map_err(|e| Error::InvalidQuote(e.to_string()))?;
+    let offset = tee.report_data_offset();
+    quote
+        .get(offset..offset + REPORT_DATA_LEN)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| {
+            Error::InvalidQuote(format!("{} bytes, too short for {:?}", quote.len(), tee))
+        })
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unknown TEE: {0} (expected sgx, sev-snp or tdx)")]
+    UnknownTee(String),
+    #[error("Invalid quote: {0}")]
+    InvalidQuote(String),
+    #[error("Attestation is for meta commitment {actual}, expected {expected}")]
+    CommitmentMismatch { expected: String, actual: String },
+    #[error("Report data of the quote is not the meta commitment")]
+    ReportDataMismatch,
+    #[error("Report data of the quote is not the certificate fingerprint")]
+    CertificateMismatch,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_check_quote_binding() {
+        let commitment = [7u8; 32];
+        let mut quote = vec![0u8; 1184];
+        quote[0x50..0x50 + REPORT_DATA_LEN].copy_from_slice(&report_data(&commitment));
+        let report = AttestationReport::new(TeeKind::SevSnp, &commitment, &quote);
+        report.check_binding(&hex::encode(commitment)).unwrap();
+
+        assert!(matches!(
+            report.check_binding(&hex::encode([8u8; 32])),
+            Err(Error::CommitmentMismatch { .. })
+        ));
+        // Same quote read as another TEE, whose report data is elsewhere
+        let report = AttestationReport::new(TeeKind::Tdx, &commitment, &quote);
+        assert!(matches!(
+            report.check_binding(&hex::encode(commitment)),
+            Err(Error::ReportDataMismatch)
+        ));
+        let report = AttestationReport::new(TeeKind::Sgx, &commitment, &quote[..100]);
+        assert!(matches!(
+            report.check_binding(&hex::encode(commitment)),
+            Err(Error::InvalidQuote(_))
+        ));
+    }
+
+    #[test]
+    fn should_check_tls_certificate_binding() {
+        let certificate = b"certificate der";
+        let fingerprint = certificate_fingerprint(certificate);
+        assert_eq!(
+            hex::encode(fingerprint),
+            hex::encode(Sha256::digest(certificate))
+        );
+        let mut quote = vec![0u8; 1184];
+        quote[48 + 520..48 + 520 + REPORT_DATA_LEN].copy_from_slice(&report_data(&fingerprint));
+        let attestation = TlsAttestation::new(TeeKind::Tdx, &quote);
+        attestation.check_binding(certificate).unwrap();
+        assert!(matches!(
+            attestation.check_binding(b"other certificate"),
+            Err(Error::CertificateMismatch)
+        ));
+    }
+}
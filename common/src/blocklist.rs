@@ -0,0 +1,93 @@
+use alloy::hex;
+use sha3::{Digest, Keccak256};
+use std::collections::HashSet;
+
+/// Ids excluded from a job, removed from the graph before compute.
+///
+/// A blocklist is a UTF-8 file listing one id per line, blank lines and lines starting
+/// with `#` being ignored. It is referenced by its Keccak256 hash in the job description
+/// and stored under `blocklist/{id}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blocklist {
+    id: String,
+    ids: HashSet<String>,
+}
+
+impl Blocklist {
+    /// Parses the blocklist from its bytes.
+    pub fn new(bytes: &[u8]) -> Result<Self, Error> {
+        let (id, ids) = parse_id_list(bytes)?;
+        Ok(Self { id, ids })
+    }
+
+    /// Parses the blocklist, checking the bytes match the expected blocklist id.
+    pub fn from_bytes_with_id(bytes: &[u8], blocklist_id: &str) -> Result<Self, Error> {
+        let blocklist = Self::new(bytes)?;
+        check_id(&blocklist.id, blocklist_id)?;
+        Ok(blocklist)
+    }
+
+    /// Returns the blocklist id (hex encoded Keccak256 hash of the file bytes).
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+}
+
+/// Parses a list of ids, one per line, returning its id (hex encoded Keccak256 hash of the
+/// bytes) and the listed ids.
+pub(crate) fn parse_id_list(bytes: &[u8]) -> Result<(String, HashSet<String>), Error> {
+    let text = std::str::from_utf8(bytes).map_err(|e| Error::Utf8(e.to_string()))?;
+    let ids = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    Ok((hex::encode(Keccak256::digest(bytes)), ids))
+}
+
+pub(crate) fn check_id(actual: &str, expected: &str) -> Result<(), Error> {
+    if actual != expected.trim_start_matches("0x") {
+        return Err(Error::HashMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Id list is not valid UTF-8: {0}")]
+    Utf8(String),
+    #[error("Id list hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: String, actual: String },
+}
+
+#[cfg(test)]
+mod test {
+    use super::Blocklist;
+
+    #[test]
+    fn should_parse_blocklist() {
+        let bytes = b"# known bots\nbot1\n\n  bot2  \n";
+        let blocklist = Blocklist::new(bytes).unwrap();
+        assert_eq!(blocklist.len(), 2);
+        assert!(blocklist.contains("bot2"));
+        assert!(!blocklist.contains("# known bots"));
+        assert!(Blocklist::from_bytes_with_id(bytes, blocklist.id()).is_ok());
+        assert!(Blocklist::from_bytes_with_id(b"bot1\n", blocklist.id()).is_err());
+    }
+}
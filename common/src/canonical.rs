@@ -0,0 +1,123 @@
+use serde::Serialize;
+use serde_json::{Number, Value};
+use sha3::{Digest, Keccak256};
+
+/// Serializes the value as canonical JSON, the encoding meta ids are computed from:
+/// - no insignificant whitespace,
+/// - object keys sorted by their UTF-8 bytes,
+/// - strings escaped as in RFC 8259, with only `"`, `\` and control characters escaped,
+/// - integers written as is, and floats with an integral value (e.g. `1.0`) written as integers,
+/// - other floats written in their shortest round-trip decimal form, without an exponent.
+///
+/// Non-finite floats are written as `null`, as serde_json does.
+///
+/// Test vectors, shared with other implementations, are in `test-vectors/canonical-json.json`.
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let value = serde_json::to_value(value).map_err(Error::Serde)?;
+    let mut out = Vec::new();
+    write_value(&mut out, &value)?;
+    Ok(out)
+}
+
+/// Returns the canonical JSON bytes of the value, along with its meta id
+/// (hex encoded Keccak256 hash of the bytes).
+pub fn hash<T: Serialize>(value: &T) -> Result<(Vec<u8>, String), Error> {
+    let bytes = to_vec(value)?;
+    let id = alloy::hex::encode(Keccak256::digest(&bytes));
+    Ok((bytes, id))
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Null => out.extend_from_slice(b"null"),
+        Value::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        Value::Number(n) => write_number(out, n)?,
+        Value::String(s) => write_string(out, s)?,
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(out, key)?;
+                out.push(b':');
+                write_value(out, item)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_number(out: &mut Vec<u8>, n: &Number) -> Result<(), Error> {
+    if n.is_u64() || n.is_i64() {
+        out.extend_from_slice(n.to_string().as_bytes());
+        return Ok(());
+    }
+    let f = n.as_f64().ok_or(Error::InvalidNumber)?;
+    // Rust's `Display` for f64 is the shortest round-trip form, never using an exponent
+    let s = if f == 0.0 {
+        "0".to_string()
+    } else {
+        f.to_string()
+    };
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+    serde_json::to_writer(&mut *out, s).map_err(Error::Serde)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Serde error: {0}")]
+    Serde(serde_json::Error),
+    #[error("Number can't be represented as a float")]
+    InvalidNumber,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash, to_vec};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    #[derive(Deserialize)]
+    struct Vector {
+        input: Value,
+        canonical: String,
+        keccak256: String,
+    }
+
+    #[test]
+    fn should_match_test_vectors() {
+        let vectors: Vec<Vector> =
+            serde_json::from_str(include_str!("../test-vectors/canonical-json.json")).unwrap();
+        for vector in vectors {
+            let bytes = to_vec(&vector.input).unwrap();
+            assert_eq!(String::from_utf8(bytes).unwrap(), vector.canonical);
+            let (_, id) = hash(&vector.input).unwrap();
+            assert_eq!(id, vector.keccak256);
+        }
+    }
+
+    #[test]
+    fn should_ignore_map_order() {
+        let a: Value = serde_json::from_str(r#"{"b": 1, "a": [1.50, 2.0]}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":[1.5,2],"b":1}"#).unwrap();
+        assert_eq!(to_vec(&a).unwrap(), to_vec(&b).unwrap());
+    }
+}
@@ -0,0 +1,269 @@
+use crate::hashing::HashedTree;
+use crate::merkle::{self, Hash};
+use crate::scheme::{self, Scheme};
+use crate::ScoreEntry;
+use alloy_primitives::{Address, TxHash, U256};
+use serde::{Deserialize, Serialize};
+
+/// Position of a block on the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+impl BlockRef {
+    pub fn new(number: u64, timestamp: u64) -> Self {
+        Self { number, timestamp }
+    }
+}
+
+/// Length of the window in which a compute result can be challenged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeWindow {
+    /// Seconds since the result block timestamp, as enforced by the manager contract.
+    Seconds(u64),
+    /// Blocks since the result block, for providers whose timestamps can't be relied on.
+    Blocks(u64),
+}
+
+impl ChallengeWindow {
+    /// Returns the time (or number of blocks) elapsed since the result was posted.
+    /// If the latest block is behind the result block (clock skew, lagging provider),
+    /// no time is considered elapsed.
+    pub fn elapsed(&self, result: &BlockRef, latest: &BlockRef) -> u64 {
+        match self {
+            Self::Seconds(_) => latest.timestamp.saturating_sub(result.timestamp),
+            Self::Blocks(_) => latest.number.saturating_sub(result.number),
+        }
+    }
+
+    /// Returns the time (or number of blocks) left before the window closes.
+    pub fn remaining(&self, result: &BlockRef, latest: &BlockRef) -> u64 {
+        self.len().saturating_sub(self.elapsed(result, latest))
+    }
+
+    /// Checks whether the result can still be challenged.
+    pub fn is_open(&self, result: &BlockRef, latest: &BlockRef) -> bool {
+        self.elapsed(result, latest) <= self.len()
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            Self::Seconds(len) | Self::Blocks(len) => *len,
+        }
+    }
+}
+
+/// A challenge of a compute result, as submitted to the manager contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub compute_id: U256,
+    /// Sub-job of the results the challenge is about.
+    pub sub_job_id: u32,
+    pub challenger: Address,
+    /// Unix timestamp (seconds) of the challenge.
+    pub timestamp: u64,
+    pub tx_hash: Option<TxHash>,
+}
+
+/// A score of a sub-job, with its path in the scores tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreLeafProof {
+    pub id: String,
+    pub value: f64,
+    pub leaf: Hash,
+    /// Merkle path of the leaf in the scores tree (leaf to root).
+    pub path: Vec<Hash>,
+}
+
+/// Evidence of a dispute over a single score: the first index where the posted scores and the
+/// recomputed ones commit to different leaves, proven in both scores trees.
+/// Only the scores are merkleized, the trust and seed data are identified by their content ids.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisputeEvidence {
+    pub sub_job_id: u32,
+    /// Version of the scheme both scores trees are built with.
+    pub scheme_version: String,
+    pub score_index: usize,
+    pub posted_root: Hash,
+    pub recomputed_root: Hash,
+    /// Posted score at the index, none when the posted scores are shorter.
+    pub posted: Option<ScoreLeafProof>,
+    /// Recomputed score at the index, none when the recomputed scores are shorter.
+    pub recomputed: Option<ScoreLeafProof>,
+}
+
+impl DisputeEvidence {
+    /// Builds the evidence of the first diverging score of the sub-job, or none if the scores
+    /// commit to the same leaves.
+    pub fn build(
+        sub_job_id: u32,
+        scheme: &Scheme,
+        posted: &[ScoreEntry],
+        recomputed: &[ScoreEntry],
+    ) -> Result<Option<Self>, merkle::Error> {
+        let posted_leaves: Vec<Hash> = posted
+            .iter()
+            .map(|e| scheme.hash_leaf(*e.value()))
+            .collect();
+        let recomputed_leaves: Vec<Hash> = recomputed
+            .iter()
+            .map(|e| scheme.hash_leaf(*e.value()))
+            .collect();
+        let Some(score_index) = first_divergence(&posted_leaves, &recomputed_leaves) else {
+            return Ok(None);
+        };
+
+        let posted_tree = scheme.hash.dense_tree(posted_leaves.clone())?;
+        let recomputed_tree = scheme.hash.dense_tree(recomputed_leaves.clone())?;
+        let proof = |tree: &HashedTree, entries: &[ScoreEntry], leaves: &[Hash]| {
+            entries
+                .get(score_index)
+                .map(|entry| {
+                    Ok(ScoreLeafProof {
+                        id: entry.id().clone(),
+                        value: *entry.value(),
+                        leaf: leaves[score_index].clone(),
+                        path: tree.generate_path(score_index)?,
+                    })
+                })
+                .transpose()
+        };
+        Ok(Some(Self {
+            sub_job_id,
+            scheme_version: scheme.version.to_string(),
+            score_index,
+            posted_root: posted_tree.root()?,
+            recomputed_root: recomputed_tree.root()?,
+            posted: proof(&posted_tree, posted, &posted_leaves)?,
+            recomputed: proof(&recomputed_tree, recomputed, &recomputed_leaves)?,
+        }))
+    }
+
+    /// Checks the paths of the evidence against the roots, with the hash function of its
+    /// scheme, and that the leaves differ. Evidence of an unknown scheme doesn't verify.
+    pub fn verify(&self) -> bool {
+        let Some(scheme) = scheme::find(&self.scheme_version) else {
+            return false;
+        };
+        let verify = |proof: &ScoreLeafProof, root: &Hash| {
+            scheme
+                .hash
+                .verify_path(&proof.leaf, self.score_index as u64, &proof.path, root)
+        };
+        let posted_ok = self
+            .posted
+            .as_ref()
+            .is_none_or(|p| verify(p, &self.posted_root));
+        let recomputed_ok = self
+            .recomputed
+            .as_ref()
+            .is_none_or(|p| verify(p, &self.recomputed_root));
+        let differ = match (&self.posted, &self.recomputed) {
+            (Some(posted), Some(recomputed)) => posted.leaf != recomputed.leaf,
+            (None, None) => false,
+            _ => true,
+        };
+        posted_ok && recomputed_ok && differ
+    }
+}
+
+/// Returns the first index where the leaves differ, the shorter list ending counting as a
+/// difference.
+fn first_divergence(posted: &[Hash], recomputed: &[Hash]) -> Option<usize> {
+    posted
+        .iter()
+        .zip(recomputed)
+        .position(|(posted, recomputed)| posted != recomputed)
+        .or_else(|| (posted.len() != recomputed.len()).then(|| posted.len().min(recomputed.len())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BlockRef, ChallengeWindow, DisputeEvidence};
+    use crate::scheme;
+    use crate::ScoreEntry;
+
+    #[test]
+    fn should_check_window() {
+        let window = ChallengeWindow::Seconds(3600);
+        let result = BlockRef::new(100, 1_000);
+        assert!(window.is_open(&result, &BlockRef::new(110, 4_600)));
+        assert!(!window.is_open(&result, &BlockRef::new(120, 4_601)));
+        assert_eq!(window.remaining(&result, &BlockRef::new(110, 2_000)), 2_600);
+
+        let window = ChallengeWindow::Blocks(10);
+        assert!(window.is_open(&result, &BlockRef::new(110, 100_000)));
+        assert!(!window.is_open(&result, &BlockRef::new(111, 1_000)));
+    }
+
+    #[test]
+    fn should_handle_skewed_timestamps() {
+        let result = BlockRef::new(100, 1_000);
+        // Latest block reported behind the result block
+        let latest = BlockRef::new(99, 900);
+
+        let window = ChallengeWindow::Seconds(3600);
+        assert_eq!(window.elapsed(&result, &latest), 0);
+        assert_eq!(window.remaining(&result, &latest), 3600);
+        assert!(window.is_open(&result, &latest));
+
+        let window = ChallengeWindow::Blocks(10);
+        assert_eq!(window.elapsed(&result, &latest), 0);
+        assert!(window.is_open(&result, &latest));
+
+        let window = ChallengeWindow::Seconds(0);
+        assert!(!window.is_open(&result, &BlockRef::new(u64::MAX, u64::MAX - 1)));
+        assert!(window.is_open(&BlockRef::new(0, u64::MAX), &BlockRef::new(0, 0)));
+    }
+
+    #[test]
+    fn should_build_dispute_evidence() {
+        let scheme = scheme::current();
+        let scores = |values: &[f64]| -> Vec<ScoreEntry> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| ScoreEntry::new(format!("0x{:02x}", i), *v))
+                .collect()
+        };
+        let posted = scores(&[0.4, 0.3, 0.2, 0.05, 0.05]);
+
+        assert!(DisputeEvidence::build(0, scheme, &posted, &posted)
+            .unwrap()
+            .is_none());
+
+        let recomputed = scores(&[0.4, 0.3, 0.1, 0.1, 0.1]);
+        let evidence = DisputeEvidence::build(2, scheme, &posted, &recomputed)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evidence.sub_job_id, 2);
+        assert_eq!(evidence.score_index, 2);
+        assert_eq!(evidence.posted.as_ref().unwrap().value, 0.2);
+        assert_eq!(evidence.recomputed.as_ref().unwrap().value, 0.1);
+        assert!(evidence.verify());
+
+        // Tampered path
+        let mut tampered = evidence.clone();
+        tampered.posted.as_mut().unwrap().path[0] = tampered.recomputed_root.clone();
+        assert!(!tampered.verify());
+
+        // Posted scores missing an entry
+        let evidence = DisputeEvidence::build(0, scheme, &posted[..4], &posted)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evidence.score_index, 4);
+        assert!(evidence.posted.is_none());
+        assert!(evidence.verify());
+
+        // Paths are verified with the hash function of the scheme
+        let blake3 = scheme::find(scheme::SCHEME_V3).unwrap();
+        let mut evidence = DisputeEvidence::build(0, blake3, &posted, &recomputed)
+            .unwrap()
+            .unwrap();
+        assert!(evidence.verify());
+        evidence.scheme_version = scheme::SCHEME_V2.to_string();
+        assert!(!evidence.verify());
+    }
+}
@@ -0,0 +1,229 @@
+use alloy::primitives::{Address, B256, U256};
+use alloy::providers::Provider;
+use alloy::sol;
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Number of compute ids read concurrently by the batch reads.
+pub const BATCH_SIZE: usize = 32;
+
+sol!(
+    #[sol(rpc)]
+    interface IOpenRankManagerStorage {
+        function idCounter() external view returns (uint256);
+        function metaComputeRequests(uint256) external view returns (address user, uint256 id, bytes32 jobDescriptionId, uint256 timestamp);
+        function metaComputeResults(uint256) external view returns (address computer, uint256 computeId, bytes32 metaCommitment, bytes32 resultsId, uint256 timestamp);
+    }
+);
+
+use IOpenRankManagerStorage::{
+    metaComputeRequestsReturn, metaComputeResultsReturn, IOpenRankManagerStorageInstance,
+};
+
+/// A meta compute request, as stored by the manager contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetaComputeRequest {
+    pub compute_id: U256,
+    pub user: Address,
+    pub job_description_id: B256,
+    /// Unix timestamp (seconds) of the request.
+    pub timestamp: u64,
+}
+
+impl MetaComputeRequest {
+    /// Decodes the storage struct, a zeroed struct meaning no request was made for the id.
+    pub fn from_storage(
+        compute_id: U256,
+        raw: metaComputeRequestsReturn,
+    ) -> Result<Option<Self>, Error> {
+        if raw.user.is_zero() && raw.id.is_zero() && raw.jobDescriptionId.is_zero() {
+            return Ok(None);
+        }
+        if raw.id != compute_id {
+            return Err(Error::Inconsistent(compute_id, raw.id));
+        }
+        Ok(Some(Self {
+            compute_id,
+            user: raw.user,
+            job_description_id: raw.jobDescriptionId,
+            timestamp: to_timestamp(raw.timestamp)?,
+        }))
+    }
+}
+
+/// A meta compute result, as stored by the manager contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetaComputeResult {
+    pub compute_id: U256,
+    pub computer: Address,
+    pub meta_commitment: B256,
+    pub results_id: B256,
+    /// Unix timestamp (seconds) of the submission.
+    pub timestamp: u64,
+}
+
+impl MetaComputeResult {
+    /// Decodes the storage struct, a zeroed struct meaning no result was posted for the id.
+    pub fn from_storage(
+        compute_id: U256,
+        raw: metaComputeResultsReturn,
+    ) -> Result<Option<Self>, Error> {
+        if raw.computer.is_zero() && raw.computeId.is_zero() && raw.metaCommitment.is_zero() {
+            return Ok(None);
+        }
+        if raw.computeId != compute_id {
+            return Err(Error::Inconsistent(compute_id, raw.computeId));
+        }
+        Ok(Some(Self {
+            compute_id,
+            computer: raw.computer,
+            meta_commitment: raw.metaCommitment,
+            results_id: raw.resultsId,
+            timestamp: to_timestamp(raw.timestamp)?,
+        }))
+    }
+}
+
+fn to_timestamp(timestamp: U256) -> Result<u64, Error> {
+    timestamp
+        .try_into()
+        .map_err(|_| Error::InvalidTimestamp(timestamp))
+}
+
+/// Typed reads of the requests and results stored by the manager contract.
+pub struct ManagerReader<P: Provider> {
+    contract: IOpenRankManagerStorageInstance<P>,
+}
+
+impl<P: Provider> ManagerReader<P> {
+    pub fn new(address: Address, provider: P) -> Self {
+        Self {
+            contract: IOpenRankManagerStorage::new(address, provider),
+        }
+    }
+
+    /// Returns the id of the last compute request, 0 if none was made.
+    pub async fn last_compute_id(&self) -> Result<U256, Error> {
+        self.contract
+            .idCounter()
+            .call()
+            .await
+            .map_err(|e| Error::Call(e.to_string()))
+    }
+
+    /// Reads the request with the given id, `None` if it doesn't exist.
+    pub async fn request(&self, compute_id: U256) -> Result<Option<MetaComputeRequest>, Error> {
+        let raw = self
+            .contract
+            .metaComputeRequests(compute_id)
+            .call()
+            .await
+            .map_err(|e| Error::Call(e.to_string()))?;
+        MetaComputeRequest::from_storage(compute_id, raw)
+    }
+
+    /// Reads the result of the request with the given id, `None` if none was posted.
+    pub async fn result(&self, compute_id: U256) -> Result<Option<MetaComputeResult>, Error> {
+        let raw = self
+            .contract
+            .metaComputeResults(compute_id)
+            .call()
+            .await
+            .map_err(|e| Error::Call(e.to_string()))?;
+        MetaComputeResult::from_storage(compute_id, raw)
+    }
+
+    /// Reads the requests with ids in the range, in order, skipping the missing ones.
+    pub async fn requests(
+        &self,
+        compute_ids: RangeInclusive<u64>,
+    ) -> Result<Vec<MetaComputeRequest>, Error> {
+        let mut requests = Vec::new();
+        for chunk in compute_ids.collect::<Vec<_>>().chunks(BATCH_SIZE) {
+            let reads = chunk.iter().map(|id| self.request(U256::from(*id)));
+            requests.extend(try_join_all(reads).await?.into_iter().flatten());
+        }
+        Ok(requests)
+    }
+
+    /// Reads the results of the requests with ids in the range, in order, skipping the
+    /// requests without a result.
+    pub async fn results(
+        &self,
+        compute_ids: RangeInclusive<u64>,
+    ) -> Result<Vec<MetaComputeResult>, Error> {
+        let mut results = Vec::new();
+        for chunk in compute_ids.collect::<Vec<_>>().chunks(BATCH_SIZE) {
+            let reads = chunk.iter().map(|id| self.result(U256::from(*id)));
+            results.extend(try_join_all(reads).await?.into_iter().flatten());
+        }
+        Ok(results)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Contract call failed: {0}")]
+    Call(String),
+    #[error("Stored struct for compute id {0} has compute id {1}")]
+    Inconsistent(U256, U256),
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(U256),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_decode_storage_structs() {
+        let compute_id = U256::from(7);
+        let missing = metaComputeRequestsReturn {
+            user: Address::ZERO,
+            id: U256::ZERO,
+            jobDescriptionId: B256::ZERO,
+            timestamp: U256::ZERO,
+        };
+        assert_eq!(
+            MetaComputeRequest::from_storage(compute_id, missing).unwrap(),
+            None
+        );
+
+        let raw = metaComputeRequestsReturn {
+            user: Address::repeat_byte(1),
+            id: compute_id,
+            jobDescriptionId: B256::repeat_byte(2),
+            timestamp: U256::from(1_700_000_000),
+        };
+        let request = MetaComputeRequest::from_storage(compute_id, raw.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.user, Address::repeat_byte(1));
+        assert_eq!(request.timestamp, 1_700_000_000);
+        assert!(MetaComputeRequest::from_storage(U256::from(8), raw).is_err());
+
+        let missing = metaComputeResultsReturn {
+            computer: Address::ZERO,
+            computeId: U256::ZERO,
+            metaCommitment: B256::ZERO,
+            resultsId: B256::ZERO,
+            timestamp: U256::ZERO,
+        };
+        assert_eq!(
+            MetaComputeResult::from_storage(compute_id, missing).unwrap(),
+            None
+        );
+        let raw = metaComputeResultsReturn {
+            computer: Address::repeat_byte(3),
+            computeId: compute_id,
+            metaCommitment: B256::repeat_byte(4),
+            resultsId: B256::repeat_byte(5),
+            timestamp: U256::MAX,
+        };
+        assert!(matches!(
+            MetaComputeResult::from_storage(compute_id, raw),
+            Err(Error::InvalidTimestamp(_))
+        ));
+    }
+}
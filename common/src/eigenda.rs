@@ -31,6 +31,11 @@ impl EigenDAProxyClient {
         }
     }
 
+    /// Creates the client with the given HTTP client, e.g. one built from the `NetConfig`.
+    pub fn with_client(url: String, client: Client) -> Self {
+        Self { url, client }
+    }
+
     pub async fn health(&self) -> Result<(), EigenDAError> {
         let health_url = format!("{}/health", self.url);
         let resp = self.client.get(&health_url).send().await?;
@@ -0,0 +1,575 @@
+use crate::normalize::{write_seed_csv, write_trust_csv};
+use crate::scheme::Precision;
+use crate::{ScoreEntry, ScoreEntryReader, TrustEntry, TrustEntryReader};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+/// Magic bytes Parquet files start with.
+pub const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+/// Format of a trust, seed or scores file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    #[default]
+    Csv,
+    /// Parquet file with the columns of the CSV files, in the same order: `i`, `j`, `v` for
+    /// trust and `i`, `v` for seed and scores. Needs the `parquet` feature.
+    Parquet,
+    /// JSON array of the entries, as `{"id", "value"}` or `{"from", "to", "value"}` objects.
+    /// Written only, for tools like jq.
+    Json,
+    /// One JSON object per line, as for `Json`. Written only.
+    Jsonl,
+}
+
+impl FromStr for DataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "parquet" => Ok(Self::Parquet),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => Err(format!("Unknown data format: {}", s)),
+        }
+    }
+}
+
+impl Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::Parquet => write!(f, "parquet"),
+            Self::Json => write!(f, "json"),
+            Self::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+
+impl DataFormat {
+    /// Detects the format of a file from its first bytes, anything but Parquet being read as CSV.
+    /// JSON is never detected, being an output format only.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(PARQUET_MAGIC) {
+            Self::Parquet
+        } else {
+            Self::Csv
+        }
+    }
+
+    /// Detects the format of a file, rewinding it to its start.
+    pub fn of_file(file: &mut File) -> std::io::Result<Self> {
+        let mut magic = [0; PARQUET_MAGIC.len()];
+        let mut read = 0;
+        while read < magic.len() {
+            match file.read(&mut magic[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self::detect(&magic[..read]))
+    }
+}
+
+/// Entries streamed from a file, whatever its format. The errors of Parquet files are
+/// reported as I/O errors of the CSV reader, so that callers handle both formats alike.
+pub type Entries<T> = Box<dyn Iterator<Item = Result<T, csv::Error>>>;
+
+/// Streams the trust entries of a CSV or Parquet file, detecting its format. Values are read
+/// with the precision (see `TrustEntryReader::with_precision`).
+pub fn trust_entries(
+    mut file: File,
+    precision: Precision,
+) -> Result<Entries<TrustEntry>, csv::Error> {
+    match DataFormat::of_file(&mut file)? {
+        DataFormat::Parquet => columnar::trust_entries(file, precision),
+        _ => Ok(Box::new(
+            TrustEntryReader::new(file).with_precision(precision),
+        )),
+    }
+}
+
+/// Streams the score entries of a CSV or Parquet file, detecting its format. Values are read
+/// with the precision (see `ScoreEntryReader::with_precision`).
+pub fn score_entries(
+    mut file: File,
+    precision: Precision,
+) -> Result<Entries<ScoreEntry>, csv::Error> {
+    match DataFormat::of_file(&mut file)? {
+        DataFormat::Parquet => columnar::score_entries(file, precision),
+        _ => Ok(Box::new(
+            ScoreEntryReader::new(file).with_precision(precision),
+        )),
+    }
+}
+
+/// Streams the trust entries of downloaded bytes, detecting their format. Values are read
+/// with the precision.
+pub fn trust_entries_from_bytes(
+    bytes: Vec<u8>,
+    precision: Precision,
+) -> Result<Entries<TrustEntry>, csv::Error> {
+    match DataFormat::detect(&bytes) {
+        DataFormat::Parquet => columnar::trust_entries(columnar::buffer(bytes), precision),
+        _ => Ok(Box::new(
+            TrustEntryReader::new(Cursor::new(bytes)).with_precision(precision),
+        )),
+    }
+}
+
+/// Streams the score entries of downloaded bytes, detecting their format. Values are read
+/// with the precision.
+pub fn score_entries_from_bytes(
+    bytes: Vec<u8>,
+    precision: Precision,
+) -> Result<Entries<ScoreEntry>, csv::Error> {
+    match DataFormat::detect(&bytes) {
+        DataFormat::Parquet => columnar::score_entries(columnar::buffer(bytes), precision),
+        _ => Ok(Box::new(
+            ScoreEntryReader::new(Cursor::new(bytes)).with_precision(precision),
+        )),
+    }
+}
+
+/// Writes the trust entries in the format, as `i,j,v`.
+pub fn write_trust<W: Write + Send>(
+    writer: W,
+    entries: &[TrustEntry],
+    format: DataFormat,
+) -> Result<(), Error> {
+    match format {
+        DataFormat::Csv => Ok(write_trust_csv(writer, entries)?),
+        DataFormat::Parquet => columnar::write_trust(writer, entries),
+        DataFormat::Json | DataFormat::Jsonl => write_json(writer, entries, format),
+    }
+}
+
+/// Writes the seed or score entries in the format, as `i,v`.
+pub fn write_scores<W: Write + Send>(
+    writer: W,
+    entries: &[ScoreEntry],
+    format: DataFormat,
+) -> Result<(), Error> {
+    match format {
+        DataFormat::Csv => Ok(write_seed_csv(writer, entries)?),
+        DataFormat::Parquet => columnar::write_scores(writer, entries),
+        DataFormat::Json | DataFormat::Jsonl => write_json(writer, entries, format),
+    }
+}
+
+/// Writes the entries as a JSON array, or as JSON lines, ending with a newline.
+fn write_json<W: Write, T: Serialize>(
+    mut writer: W,
+    entries: &[T],
+    format: DataFormat,
+) -> Result<(), Error> {
+    if format == DataFormat::Jsonl {
+        for entry in entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+    } else {
+        serde_json::to_writer(&mut writer, entries)?;
+        writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+    }
+    writer.flush().map_err(serde_json::Error::io)?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+mod columnar {
+    use super::{Entries, Error, Precision};
+    use crate::{ScoreEntry, TrustEntry};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, Float64Type};
+    use arrow_array::{Array, ArrayRef, Float64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::reader::ChunkReader;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    /// Rows read or written per record batch.
+    const BATCH_SIZE: usize = 64 * 1024;
+
+    /// Downloaded bytes, in a buffer the Parquet reader can read from.
+    pub fn buffer(bytes: Vec<u8>) -> bytes::Bytes {
+        bytes.into()
+    }
+
+    /// Streams the entries of the record batches of a Parquet file, one batch at a time.
+    struct BatchEntries<T> {
+        reader: ParquetRecordBatchReader,
+        entries: std::vec::IntoIter<T>,
+        precision: Precision,
+        parse: fn(&RecordBatch, Precision) -> Result<Vec<T>, Error>,
+    }
+
+    impl<T> Iterator for BatchEntries<T> {
+        type Item = Result<T, csv::Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(entry) = self.entries.next() {
+                    return Some(Ok(entry));
+                }
+                let batch = match self.reader.next()? {
+                    Ok(batch) => batch,
+                    Err(e) => return Some(Err(Error::from(e).into())),
+                };
+                match (self.parse)(&batch, self.precision) {
+                    Ok(entries) => self.entries = entries.into_iter(),
+                    Err(e) => return Some(Err(e.into())),
+                }
+            }
+        }
+    }
+
+    fn read<T: 'static, R: ChunkReader + 'static>(
+        reader: R,
+        precision: Precision,
+        parse: fn(&RecordBatch, Precision) -> Result<Vec<T>, Error>,
+    ) -> Result<Entries<T>, csv::Error> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(reader)
+            .and_then(|builder| builder.with_batch_size(BATCH_SIZE).build())
+            .map_err(Error::from)?;
+        Ok(Box::new(BatchEntries {
+            reader,
+            entries: Vec::new().into_iter(),
+            precision,
+            parse,
+        }))
+    }
+
+    pub fn trust_entries<R: ChunkReader + 'static>(
+        reader: R,
+        precision: Precision,
+    ) -> Result<Entries<TrustEntry>, csv::Error> {
+        read(reader, precision, |batch, precision| {
+            let (from, to, value) = (
+                strings(batch, 0)?,
+                strings(batch, 1)?,
+                floats(batch, 2, precision)?,
+            );
+            Ok(from
+                .into_iter()
+                .zip(to)
+                .zip(value)
+                .map(|((from, to), value)| TrustEntry::new(from, to, value))
+                .collect())
+        })
+    }
+
+    pub fn score_entries<R: ChunkReader + 'static>(
+        reader: R,
+        precision: Precision,
+    ) -> Result<Entries<ScoreEntry>, csv::Error> {
+        read(reader, precision, |batch, precision| {
+            let (id, value) = (strings(batch, 0)?, floats(batch, 1, precision)?);
+            Ok(id
+                .into_iter()
+                .zip(value)
+                .map(|(id, value)| ScoreEntry::new(id, value))
+                .collect())
+        })
+    }
+
+    /// Column of the batch at the index, which can't hold nulls.
+    fn column(batch: &RecordBatch, index: usize) -> Result<&ArrayRef, Error> {
+        let column = batch
+            .columns()
+            .get(index)
+            .ok_or_else(|| Error::Schema(format!("Missing column {}", index)))?;
+        if column.null_count() > 0 {
+            return Err(Error::Schema(format!("Null values in column {}", index)));
+        }
+        Ok(column)
+    }
+
+    fn strings(batch: &RecordBatch, index: usize) -> Result<Vec<String>, Error> {
+        let column = column(batch, index)?;
+        match column.data_type() {
+            DataType::Utf8 => Ok(column
+                .as_string::<i32>()
+                .iter()
+                .flatten()
+                .map(String::from)
+                .collect()),
+            DataType::LargeUtf8 => Ok(column
+                .as_string::<i64>()
+                .iter()
+                .flatten()
+                .map(String::from)
+                .collect()),
+            other => Err(Error::Schema(format!(
+                "Column {} is {}, expected strings",
+                index, other
+            ))),
+        }
+    }
+
+    /// Values of a float column, f64 ones being narrowed to f32 for the f32 precision, as the
+    /// CSV readers parse them.
+    fn floats(batch: &RecordBatch, index: usize, precision: Precision) -> Result<Vec<f64>, Error> {
+        let column = column(batch, index)?;
+        match column.data_type() {
+            DataType::Float64 if precision == Precision::F32 => Ok(column
+                .as_primitive::<Float64Type>()
+                .values()
+                .iter()
+                .map(|v| f64::from(*v as f32))
+                .collect()),
+            DataType::Float64 => Ok(column.as_primitive::<Float64Type>().values().to_vec()),
+            DataType::Float32 => Ok(column
+                .as_primitive::<Float32Type>()
+                .values()
+                .iter()
+                .map(|v| f64::from(*v))
+                .collect()),
+            other => Err(Error::Schema(format!(
+                "Column {} is {}, expected floats",
+                index, other
+            ))),
+        }
+    }
+
+    /// Writes the columns in record batches, compressed with Snappy.
+    fn write<W: Write + Send>(
+        writer: W,
+        names: &[&str],
+        rows: usize,
+        batch: impl Fn(std::ops::Range<usize>) -> Vec<ArrayRef>,
+    ) -> Result<(), Error> {
+        let fields: Vec<Field> = names
+            .iter()
+            .map(|name| {
+                let data_type = if *name == "v" {
+                    DataType::Float64
+                } else {
+                    DataType::Utf8
+                };
+                Field::new(*name, data_type, false)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut wtr = ArrowWriter::try_new(writer, schema.clone(), Some(props))?;
+        for start in (0..rows).step_by(BATCH_SIZE) {
+            let columns = batch(start..rows.min(start + BATCH_SIZE));
+            wtr.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+        }
+        wtr.close()?;
+        Ok(())
+    }
+
+    pub fn write_trust<W: Write + Send>(writer: W, entries: &[TrustEntry]) -> Result<(), Error> {
+        write(writer, &["i", "j", "v"], entries.len(), |range| {
+            let entries = &entries[range];
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.from()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.to()),
+                )),
+                Arc::new(Float64Array::from_iter_values(
+                    entries.iter().map(|e| *e.value()),
+                )),
+            ]
+        })
+    }
+
+    pub fn write_scores<W: Write + Send>(writer: W, entries: &[ScoreEntry]) -> Result<(), Error> {
+        write(writer, &["i", "v"], entries.len(), |range| {
+            let entries = &entries[range];
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.id()),
+                )),
+                Arc::new(Float64Array::from_iter_values(
+                    entries.iter().map(|e| *e.value()),
+                )),
+            ]
+        })
+    }
+}
+
+/// Stand-in for the Parquet readers and writers when the `parquet` feature is disabled.
+#[cfg(not(feature = "parquet"))]
+mod columnar {
+    use super::{Entries, Error, Precision};
+    use crate::{ScoreEntry, TrustEntry};
+    use std::io::Write;
+
+    pub fn buffer(bytes: Vec<u8>) -> Vec<u8> {
+        bytes
+    }
+
+    pub fn trust_entries<R>(
+        _reader: R,
+        _precision: Precision,
+    ) -> Result<Entries<TrustEntry>, csv::Error> {
+        Err(Error::ParquetDisabled.into())
+    }
+
+    pub fn score_entries<R>(
+        _reader: R,
+        _precision: Precision,
+    ) -> Result<Entries<ScoreEntry>, csv::Error> {
+        Err(Error::ParquetDisabled.into())
+    }
+
+    pub fn write_trust<W: Write>(_writer: W, _entries: &[TrustEntry]) -> Result<(), Error> {
+        Err(Error::ParquetDisabled)
+    }
+
+    pub fn write_scores<W: Write>(_writer: W, _entries: &[ScoreEntry]) -> Result<(), Error> {
+        Err(Error::ParquetDisabled)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Parquet support is not enabled, build with the `parquet` feature")]
+    ParquetDisabled,
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "parquet")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[error("Invalid schema: {0}")]
+    Schema(String),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<Error> for csv::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Csv(e) => e,
+            e => std::io::Error::other(e).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_detect_format() {
+        assert_eq!(DataFormat::detect(b"PAR1\x15\x04"), DataFormat::Parquet);
+        assert_eq!(DataFormat::detect(b"i,j,v\n"), DataFormat::Csv);
+        assert_eq!(DataFormat::detect(b""), DataFormat::Csv);
+        assert_eq!("parquet".parse(), Ok(DataFormat::Parquet));
+        assert_eq!("jsonl".parse(), Ok(DataFormat::Jsonl));
+        assert!("xml".parse::<DataFormat>().is_err());
+
+        let scores: Vec<ScoreEntry> =
+            score_entries_from_bytes(b"i,v\na,0.5\nb,0.25\n".to_vec(), Precision::F64)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[1].id(), "b");
+    }
+
+    #[test]
+    fn should_read_bytes_with_the_precision() {
+        let bytes = b"i,j,v\na,b,0.1\n".to_vec();
+        let read: Vec<TrustEntry> = trust_entries_from_bytes(bytes.clone(), Precision::F32)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(*read[0].value(), f64::from(0.1f32));
+        let read: Vec<TrustEntry> = trust_entries_from_bytes(bytes, Precision::F64)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(*read[0].value(), 0.1);
+    }
+
+    #[test]
+    fn should_write_json() {
+        let scores =
+            [("a", 0.75), ("b", 0.25)].map(|(id, value)| ScoreEntry::new(id.into(), value));
+        let mut bytes = Vec::new();
+        write_scores(&mut bytes, &scores, DataFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "[{\"id\":\"a\",\"value\":0.75},{\"id\":\"b\",\"value\":0.25}]\n"
+        );
+
+        let mut bytes = Vec::new();
+        write_scores(&mut bytes, &scores, DataFormat::Jsonl).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "{\"id\":\"a\",\"value\":0.75}\n{\"id\":\"b\",\"value\":0.25}\n"
+        );
+
+        let trust = [TrustEntry::new("a".into(), "b".into(), 1.0)];
+        let mut bytes = Vec::new();
+        write_trust(&mut bytes, &trust, DataFormat::Jsonl).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "{\"from\":\"a\",\"to\":\"b\",\"value\":1.0}\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn should_roundtrip_parquet() {
+        let trust = [("a", "b", 1.0), ("b", "c", 0.5), ("c", "a", 2.0)]
+            .map(|(from, to, value)| TrustEntry::new(from.into(), to.into(), value));
+        let mut bytes = Vec::new();
+        write_trust(&mut bytes, &trust, DataFormat::Parquet).unwrap();
+        assert_eq!(DataFormat::detect(&bytes), DataFormat::Parquet);
+        let read: Vec<TrustEntry> = trust_entries_from_bytes(bytes, Precision::F64)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, trust);
+
+        let scores =
+            [("a", 0.75), ("b", 0.25)].map(|(id, value)| ScoreEntry::new(id.into(), value));
+        let mut bytes = Vec::new();
+        write_scores(&mut bytes, &scores, DataFormat::Parquet).unwrap();
+        let read: Vec<ScoreEntry> = score_entries_from_bytes(bytes, Precision::F64)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, scores);
+
+        let scores = [ScoreEntry::new("a".into(), 0.1)];
+        let mut bytes = Vec::new();
+        write_scores(&mut bytes, &scores, DataFormat::Parquet).unwrap();
+        let read: Vec<ScoreEntry> = score_entries_from_bytes(bytes, Precision::F32)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(*read[0].value(), f64::from(0.1f32));
+
+        assert!(trust_entries_from_bytes(b"PAR1 not parquet".to_vec(), Precision::F64).is_err());
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn should_reject_parquet_without_feature() {
+        assert!(trust_entries_from_bytes(b"PAR1".to_vec(), Precision::F64).is_err());
+        assert!(write_scores(Vec::new(), &[], DataFormat::Parquet).is_err());
+    }
+}
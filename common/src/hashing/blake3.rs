@@ -0,0 +1,320 @@
+//! BLAKE3 in its default hash mode with a 32 bytes output, following the reference
+//! implementation of the specification. Only what the merkle trees need is implemented:
+//! no keyed hashing, key derivation or extendable output.
+
+use sha3::digest::{consts::U32, FixedOutput, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+const OUT_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+
+const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+/// The mixing function, mixing a column or a diagonal of the state with two message words.
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn round(state: &mut [u32; 16], m: &[u32; 16]) {
+    // Columns
+    g(state, 0, 4, 8, 12, m[0], m[1]);
+    g(state, 1, 5, 9, 13, m[2], m[3]);
+    g(state, 2, 6, 10, 14, m[4], m[5]);
+    g(state, 3, 7, 11, 15, m[6], m[7]);
+    // Diagonals
+    g(state, 0, 5, 10, 15, m[8], m[9]);
+    g(state, 1, 6, 11, 12, m[10], m[11]);
+    g(state, 2, 7, 8, 13, m[12], m[13]);
+    g(state, 3, 4, 9, 14, m[14], m[15]);
+}
+
+fn permute(m: &mut [u32; 16]) {
+    let mut permuted = [0; 16];
+    for i in 0..16 {
+        permuted[i] = m[MSG_PERMUTATION[i]];
+    }
+    *m = permuted;
+}
+
+fn compress(
+    chaining_value: &[u32; 8],
+    block_words: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut block = *block_words;
+    for i in 0..7 {
+        round(&mut state, &block);
+        if i < 6 {
+            permute(&mut block);
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= chaining_value[i];
+    }
+    state
+}
+
+fn first_8_words(compression_output: [u32; 16]) -> [u32; 8] {
+    let mut words = [0; 8];
+    words.copy_from_slice(&compression_output[..8]);
+    words
+}
+
+fn words_from_le_bytes(bytes: &[u8; BLOCK_LEN]) -> [u32; 16] {
+    let mut words = [0; 16];
+    for (word, chunk) in words.iter_mut().zip(bytes.as_chunks::<4>().0) {
+        *word = u32::from_le_bytes(*chunk);
+    }
+    words
+}
+
+/// Input of the last compression of a chunk or parent node, kept to either chain it into the
+/// parent or produce the root output.
+struct CompressionInput {
+    input_chaining_value: [u32; 8],
+    block_words: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl CompressionInput {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            self.counter,
+            self.block_len,
+            self.flags,
+        ))
+    }
+
+    fn root_output_bytes(&self) -> [u8; OUT_LEN] {
+        let words = compress(
+            &self.input_chaining_value,
+            &self.block_words,
+            0,
+            self.block_len,
+            self.flags | ROOT,
+        );
+        let mut bytes = [0; OUT_LEN];
+        for (chunk, word) in bytes.as_chunks_mut::<4>().0.iter_mut().zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ChunkState {
+    chaining_value: [u32; 8],
+    chunk_counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: u8,
+    blocks_compressed: u8,
+}
+
+impl ChunkState {
+    fn new(chunk_counter: u64) -> Self {
+        Self {
+            chaining_value: IV,
+            chunk_counter,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed as usize + self.block_len as usize
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            // A full block is only compressed once more input comes, as the last block of the
+            // chunk is compressed with the end flag.
+            if self.block_len as usize == BLOCK_LEN {
+                let block_words = words_from_le_bytes(&self.block);
+                self.chaining_value = first_8_words(compress(
+                    &self.chaining_value,
+                    &block_words,
+                    self.chunk_counter,
+                    BLOCK_LEN as u32,
+                    self.start_flag(),
+                ));
+                self.blocks_compressed += 1;
+                self.block = [0; BLOCK_LEN];
+                self.block_len = 0;
+            }
+            let want = BLOCK_LEN - self.block_len as usize;
+            let take = want.min(input.len());
+            let start = self.block_len as usize;
+            self.block[start..start + take].copy_from_slice(&input[..take]);
+            self.block_len += take as u8;
+            input = &input[take..];
+        }
+    }
+
+    fn output(&self) -> CompressionInput {
+        CompressionInput {
+            input_chaining_value: self.chaining_value,
+            block_words: words_from_le_bytes(&self.block),
+            counter: self.chunk_counter,
+            block_len: self.block_len as u32,
+            flags: self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+fn parent_output(left_child_cv: [u32; 8], right_child_cv: [u32; 8]) -> CompressionInput {
+    let mut block_words = [0; 16];
+    block_words[..8].copy_from_slice(&left_child_cv);
+    block_words[8..].copy_from_slice(&right_child_cv);
+    CompressionInput {
+        input_chaining_value: IV,
+        block_words,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT,
+    }
+}
+
+/// BLAKE3 hasher, usable wherever a `Digest` is expected (e.g. `DenseMerkleTree<Blake3>`).
+#[derive(Clone, Debug)]
+pub struct Blake3 {
+    chunk_state: ChunkState,
+    /// Chaining values of the completed subtrees, merged as soon as they have a sibling.
+    cv_stack: Vec<[u32; 8]>,
+}
+
+impl Default for Blake3 {
+    fn default() -> Self {
+        Self {
+            chunk_state: ChunkState::new(0),
+            cv_stack: Vec::new(),
+        }
+    }
+}
+
+impl Blake3 {
+    fn add_chunk_chaining_value(&mut self, mut new_cv: [u32; 8], mut total_chunks: u64) {
+        // Each trailing zero bit of the chunk count is a completed subtree to merge.
+        while total_chunks & 1 == 0 {
+            let left = self.cv_stack.pop().expect("Subtree to merge");
+            new_cv = parent_output(left, new_cv).chaining_value();
+            total_chunks >>= 1;
+        }
+        self.cv_stack.push(new_cv);
+    }
+}
+
+impl HashMarker for Blake3 {}
+
+impl OutputSizeUser for Blake3 {
+    type OutputSize = U32;
+}
+
+impl Update for Blake3 {
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            if self.chunk_state.len() == CHUNK_LEN {
+                let chunk_cv = self.chunk_state.output().chaining_value();
+                let total_chunks = self.chunk_state.chunk_counter + 1;
+                self.add_chunk_chaining_value(chunk_cv, total_chunks);
+                self.chunk_state = ChunkState::new(total_chunks);
+            }
+            let want = CHUNK_LEN - self.chunk_state.len();
+            let take = want.min(input.len());
+            self.chunk_state.update(&input[..take]);
+            input = &input[take..];
+        }
+    }
+}
+
+impl FixedOutput for Blake3 {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        let mut output = self.chunk_state.output();
+        for left in self.cv_stack.iter().rev() {
+            output = parent_output(*left, output.chaining_value());
+        }
+        out.copy_from_slice(&output.root_output_bytes());
+    }
+}
+
+impl Reset for Blake3 {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Blake3;
+    use sha3::Digest;
+
+    #[test]
+    fn should_match_blake3_test_vectors() {
+        let hash = |input: &[u8]| alloy::hex::encode(Blake3::digest(input));
+        assert_eq!(
+            hash(b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        assert_eq!(
+            hash(b"abc"),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+
+        // Inputs over several chunks, fed at once or in pieces, give the same hash
+        let input: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+        let mut hasher = Blake3::new();
+        for piece in input.chunks(77) {
+            hasher.update(piece);
+        }
+        assert_eq!(alloy::hex::encode(hasher.finalize()), hash(&input));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::merkle::{
+    self,
+    fixed::{DenseMerkleTree, StreamingDenseMerkleTree},
+    Hash,
+};
+use serde::{Deserialize, Serialize};
+use sha3::Keccak256;
+use std::fmt::Display;
+use std::str::FromStr;
+
+mod blake3;
+
+pub use blake3::Blake3;
+
+/// Hash function of a merkle tree, selected at runtime (e.g. from the scheme of a sub-job).
+///
+/// The trees are generic over the `Digest`, this dispatches to the tree of the selected
+/// function, so callers don't need to name the hasher type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// Keccak256, the only function the manager contract verifies.
+    #[default]
+    Keccak256,
+    /// BLAKE3, faster to build the trees only verified off-chain.
+    Blake3,
+}
+
+/// Where the hashes of a tree are verified, restricting the functions it can be built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashDomain {
+    /// Verified by the manager contract: meta commitments and f32 score leaves.
+    OnChain,
+    /// Only verified by the nodes and the clients, e.g. the trees of f64 scores.
+    OffChain,
+}
+
+impl Display for HashDomain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OnChain => write!(f, "on-chain"),
+            Self::OffChain => write!(f, "off-chain"),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    /// Checks whether the trees of the domain can be built with the function.
+    pub fn supports(&self, domain: HashDomain) -> bool {
+        match domain {
+            HashDomain::OnChain => *self == Self::Keccak256,
+            HashDomain::OffChain => true,
+        }
+    }
+
+    /// Returns an error if the trees of the domain can't be built with the function.
+    pub fn check(&self, domain: HashDomain) -> Result<(), Error> {
+        if !self.supports(domain) {
+            return Err(Error::Unsupported(*self, domain));
+        }
+        Ok(())
+    }
+
+    /// Hashes the given data into a leaf.
+    pub fn hash_leaf(&self, preimage: Vec<u8>) -> Hash {
+        match self {
+            Self::Keccak256 => merkle::hash_leaf::<Keccak256>(preimage),
+            Self::Blake3 => merkle::hash_leaf::<Blake3>(preimage),
+        }
+    }
+
+    /// Builds the dense tree of the leaves.
+    pub fn dense_tree(&self, leaves: Vec<Hash>) -> Result<HashedTree, merkle::Error> {
+        Ok(match self {
+            Self::Keccak256 => HashedTree::Keccak256(DenseMerkleTree::new(leaves)?),
+            Self::Blake3 => HashedTree::Blake3(DenseMerkleTree::new(leaves)?),
+        })
+    }
+
+    /// Builds the root of the dense tree of the leaves, streaming them.
+    /// See `StreamingDenseMerkleTree`.
+    pub fn root_from_iter<I: IntoIterator<Item = Hash>>(&self, leaves: I) -> Hash {
+        match self {
+            Self::Keccak256 => StreamingDenseMerkleTree::<Keccak256>::root_from_iter(leaves),
+            Self::Blake3 => StreamingDenseMerkleTree::<Blake3>::root_from_iter(leaves),
+        }
+    }
+
+    /// Verifies a Merkle path (proof), see `merkle::verify_path`.
+    pub fn verify_path(
+        &self,
+        leaf: &Hash,
+        index: u64,
+        path: &[Hash],
+        expected_root: &Hash,
+    ) -> bool {
+        match self {
+            Self::Keccak256 => merkle::verify_path::<Keccak256>(leaf, index, path, expected_root),
+            Self::Blake3 => merkle::verify_path::<Blake3>(leaf, index, path, expected_root),
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keccak256" => Ok(Self::Keccak256),
+            "blake3" => Ok(Self::Blake3),
+            _ => Err(format!("Unknown hash function: {}", s)),
+        }
+    }
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Keccak256 => write!(f, "keccak256"),
+            Self::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+/// Dense tree built with the hash function selected at runtime.
+#[derive(Clone, Debug)]
+pub enum HashedTree {
+    Keccak256(DenseMerkleTree<Keccak256>),
+    Blake3(DenseMerkleTree<Blake3>),
+}
+
+impl HashedTree {
+    pub fn root(&self) -> Result<Hash, merkle::Error> {
+        match self {
+            Self::Keccak256(tree) => tree.root(),
+            Self::Blake3(tree) => tree.root(),
+        }
+    }
+
+    pub fn generate_path(&self, index: usize) -> Result<Vec<Hash>, merkle::Error> {
+        match self {
+            Self::Keccak256(tree) => tree.generate_path(index),
+            Self::Blake3(tree) => tree.generate_path(index),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("{0} can't be used for {1} commitments, use keccak256")]
+    Unsupported(HashAlgorithm, HashDomain),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_check_hash_domains() {
+        assert!(HashAlgorithm::Keccak256.check(HashDomain::OnChain).is_ok());
+        assert!(HashAlgorithm::Blake3.check(HashDomain::OffChain).is_ok());
+        assert_eq!(
+            HashAlgorithm::Blake3.check(HashDomain::OnChain),
+            Err(Error::Unsupported(
+                HashAlgorithm::Blake3,
+                HashDomain::OnChain
+            ))
+        );
+        assert_eq!("blake3".parse(), Ok(HashAlgorithm::Blake3));
+        assert_eq!(HashAlgorithm::Keccak256.to_string(), "keccak256");
+    }
+
+    #[test]
+    fn should_build_trees_with_the_selected_hash() {
+        let leaves: Vec<Hash> = (0..5u8).map(|i| Hash::from_slice(&[i])).collect();
+        for hash in [HashAlgorithm::Keccak256, HashAlgorithm::Blake3] {
+            let tree = hash.dense_tree(leaves.clone()).unwrap();
+            let root = tree.root().unwrap();
+            assert_eq!(hash.root_from_iter(leaves.clone()), root);
+            let path = tree.generate_path(3).unwrap();
+            assert!(hash.verify_path(&leaves[3], 3, &path, &root));
+        }
+        let keccak = HashAlgorithm::Keccak256.root_from_iter(leaves.clone());
+        assert_ne!(HashAlgorithm::Blake3.root_from_iter(leaves), keccak);
+    }
+}
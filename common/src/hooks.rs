@@ -0,0 +1,179 @@
+use alloy::hex;
+use sha3::{Digest, Keccak256};
+use wasmi::{Config, Engine, Linker, Module, Store};
+
+/// Maximum amount of fuel (roughly, instructions) a hook can consume per score.
+const FUEL_PER_SCORE: u64 = 10_000;
+/// Base amount of fuel given to every hook run.
+const BASE_FUEL: u64 = 1_000_000;
+
+/// Sandboxed WASM score post-processing hook.
+///
+/// A hook is a WASM module, referenced by its Keccak256 hash, that transforms
+/// the computed score vector before it is committed. The module must export:
+/// - `memory`: the linear memory,
+/// - `alloc(len: i32) -> i32`: allocates `len` bytes and returns the pointer,
+/// - `process(ptr: i32, count: i32) -> i32`: transforms `count` little-endian f32
+///   scores located at `ptr` in place, returning 0 on success.
+///
+/// No host functions are provided to the module, and its execution is bounded by fuel,
+/// so the same module produces the same output on every node.
+pub struct WasmHook {
+    id: String,
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmHook {
+    /// Compiles the hook module from its bytes.
+    pub fn new(bytes: &[u8]) -> Result<Self, Error> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(|e| Error::Wasm(e.to_string()))?;
+        let id = hex::encode(Keccak256::digest(bytes));
+        Ok(Self { id, engine, module })
+    }
+
+    /// Compiles the hook module, checking the bytes match the expected hook id.
+    pub fn from_bytes_with_id(bytes: &[u8], hook_id: &str) -> Result<Self, Error> {
+        let hook = Self::new(bytes)?;
+        if hook.id != hook_id.trim_start_matches("0x") {
+            return Err(Error::HashMismatch {
+                expected: hook_id.to_string(),
+                actual: hook.id,
+            });
+        }
+        Ok(hook)
+    }
+
+    /// Returns the hook id (hex encoded Keccak256 hash of the module bytes).
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Runs the hook over the scores, transforming them in place.
+    pub fn run(&self, scores: &mut [f32]) -> Result<(), Error> {
+        let mut store = Store::new(&self.engine, ());
+        let fuel = BASE_FUEL.saturating_add(FUEL_PER_SCORE.saturating_mul(scores.len() as u64));
+        store
+            .set_fuel(fuel)
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+
+        let linker = <Linker<()>>::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or(Error::MissingExport("memory"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|_| Error::MissingExport("alloc"))?;
+        let process = instance
+            .get_typed_func::<(i32, i32), i32>(&store, "process")
+            .map_err(|_| Error::MissingExport("process"))?;
+
+        let count = i32::try_from(scores.len()).map_err(|_| Error::TooManyScores)?;
+        let len = count.checked_mul(4).ok_or(Error::TooManyScores)?;
+        let ptr = alloc
+            .call(&mut store, len)
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+
+        let bytes: Vec<u8> = scores.iter().flat_map(|s| s.to_le_bytes()).collect();
+        memory
+            .write(&mut store, ptr as u32 as usize, &bytes)
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+
+        let status = process
+            .call(&mut store, (ptr, count))
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+        if status != 0 {
+            return Err(Error::HookFailed(status));
+        }
+
+        let mut out = vec![0u8; bytes.len()];
+        memory
+            .read(&store, ptr as u32 as usize, &mut out)
+            .map_err(|e| Error::Wasm(e.to_string()))?;
+        for (score, chunk) in scores.iter_mut().zip(out.as_chunks::<4>().0) {
+            *score = f32::from_le_bytes(*chunk);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("WASM error: {0}")]
+    Wasm(String),
+    #[error("Hook is missing export: {0}")]
+    MissingExport(&'static str),
+    #[error("Hook returned non-zero status: {0}")]
+    HookFailed(i32),
+    #[error("Too many scores for the hook memory")]
+    TooManyScores,
+    #[error("Hook hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: String, actual: String },
+}
+
+#[cfg(test)]
+mod test {
+    use super::WasmHook;
+
+    /// Buckets each score into tiers: 1.0 if above 0.5, 0.0 otherwise.
+    const TIERS_HOOK: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param $len i32) (result i32)
+                i32.const 0)
+            (func (export "process") (param $ptr i32) (param $count i32) (result i32)
+                (local $i i32)
+                (local $addr i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $i) (local.get $count)))
+                        (local.set $addr
+                            (i32.add (local.get $ptr) (i32.mul (local.get $i) (i32.const 4))))
+                        (f32.store (local.get $addr)
+                            (select
+                                (f32.const 1)
+                                (f32.const 0)
+                                (f32.gt (f32.load (local.get $addr)) (f32.const 0.5))))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br $next)))
+                i32.const 0))
+    "#;
+
+    #[test]
+    fn should_run_hook() {
+        let bytes = wat::parse_str(TIERS_HOOK).unwrap();
+        let hook = WasmHook::new(&bytes).unwrap();
+        let mut scores = vec![0.1, 0.7, 0.5, 0.9];
+        hook.run(&mut scores).unwrap();
+        assert_eq!(scores, vec![0.0, 1.0, 0.0, 1.0]);
+
+        let id = hook.id().to_string();
+        assert!(WasmHook::from_bytes_with_id(&bytes, &id).is_ok());
+        assert!(WasmHook::from_bytes_with_id(&bytes, "00").is_err());
+    }
+
+    #[test]
+    fn should_stop_infinite_hook() {
+        let bytes = wat::parse_str(
+            r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "process") (param i32 i32) (result i32)
+                    (loop $l (br $l))
+                    i32.const 0))
+            "#,
+        )
+        .unwrap();
+        let hook = WasmHook::new(&bytes).unwrap();
+        assert!(hook.run(&mut [0.5]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::attestation::TlsAttestation;
+use alloy_primitives::{Address, Signature};
+use serde::{Deserialize, Serialize};
+
+/// Identity of an OpenRank node, as served by the `/identity` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeIdentity {
+    /// Address of the node's signing wallet.
+    pub address: Address,
+    /// Roles the node is running (e.g. "computer").
+    pub roles: Vec<String>,
+    /// Algorithm ids the node is able to compute.
+    pub algo_ids: Vec<u32>,
+    /// Software version of the node.
+    pub version: String,
+    /// TEE attestation report, if available.
+    pub attestation: Option<String>,
+    /// Domains (manager contract addresses) served by the node.
+    pub domains: Vec<String>,
+    /// Hex encoded SHA-256 fingerprint of the certificate served over TLS, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_certificate_sha256: Option<String>,
+    /// TEE attestation of the TLS certificate, if available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_attestation: Option<TlsAttestation>,
+}
+
+impl NodeIdentity {
+    /// Returns the bytes that are signed by the node to prove the identity.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+/// Node identity, signed with the node's wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedNodeIdentity {
+    pub identity: NodeIdentity,
+    pub signature: Signature,
+}
+
+impl SignedNodeIdentity {
+    pub fn new(identity: NodeIdentity, signature: Signature) -> Self {
+        Self {
+            identity,
+            signature,
+        }
+    }
+
+    /// Recovers the signer of the identity and checks it matches the claimed address.
+    pub fn verify(&self) -> Result<(), Error> {
+        let bytes = self.identity.signing_bytes().map_err(Error::Serde)?;
+        let signer = self
+            .signature
+            .recover_address_from_msg(bytes)
+            .map_err(|e| Error::Signature(e.to_string()))?;
+        if signer != self.identity.address {
+            return Err(Error::SignerMismatch {
+                expected: self.identity.address,
+                actual: signer,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Serde error: {0}")]
+    Serde(serde_json::Error),
+    #[error("Signature error: {0}")]
+    Signature(String),
+    #[error("Signer mismatch: expected {expected}, got {actual}")]
+    SignerMismatch { expected: Address, actual: Address },
+}
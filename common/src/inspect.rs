@@ -0,0 +1,222 @@
+use crate::{ScoreEntry, TrustEntry};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Statistics of a trust graph.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TrustStats {
+    /// Ids appearing on either side of an edge.
+    pub nodes: usize,
+    /// Entries of the file.
+    pub entries: usize,
+    /// Distinct (from, to) pairs.
+    pub edges: usize,
+    /// Entries whose (from, to) pair appeared earlier in the file.
+    pub duplicate_edges: usize,
+    /// Entries of a node trusting itself.
+    pub self_trust: usize,
+    /// Entries with a negative value.
+    pub negative_values: usize,
+    /// Nodes trusting no other node, whose trust is redistributed to the seed.
+    pub dangling_nodes: usize,
+    /// Nodes with outbound edges summing to zero, which can't be normalized.
+    pub zero_sum_rows: usize,
+}
+
+/// Coverage of the trust graph by the seed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SeedStats {
+    /// Entries of the file.
+    pub entries: usize,
+    /// Seed ids that are nodes of the graph.
+    pub in_graph: usize,
+    /// Seed ids that aren't nodes of the graph, and don't spread any trust.
+    pub not_in_graph: usize,
+    /// Share of the seed weight on nodes of the graph.
+    pub weight_coverage: f64,
+    /// Share of the nodes of the graph that are seeded.
+    pub graph_coverage: f64,
+}
+
+/// Counts of the trust values in equal-width buckets between the min and max value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new(values: &[f64], buckets: usize) -> Self {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut counts = vec![0; buckets.max(1)];
+        if values.is_empty() {
+            return Self {
+                min: 0.0,
+                max: 0.0,
+                counts,
+            };
+        }
+        let width = (max - min) / counts.len() as f64;
+        for value in values {
+            let bucket = if width > 0.0 {
+                ((value - min) / width) as usize
+            } else {
+                0
+            };
+            // The max value falls in the last bucket
+            counts[bucket.min(buckets.max(1) - 1)] += 1;
+        }
+        Self { min, max, counts }
+    }
+
+    /// Returns the bounds of every bucket.
+    pub fn bounds(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let width = (self.max - self.min) / self.counts.len() as f64;
+        (0..self.counts.len()).map(move |i| {
+            (
+                self.min + width * i as f64,
+                self.min + width * (i + 1) as f64,
+            )
+        })
+    }
+}
+
+/// Report of `openrank inspect` on a trust and seed dataset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatasetReport {
+    pub trust: TrustStats,
+    pub seed: SeedStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub histogram: Option<Histogram>,
+}
+
+/// Inspects a trust and seed dataset, streaming their entries. With `histogram_buckets`, the
+/// trust values are kept to build their histogram.
+pub fn inspect<E>(
+    trust: impl IntoIterator<Item = Result<TrustEntry, E>>,
+    seed: impl IntoIterator<Item = Result<ScoreEntry, E>>,
+    histogram_buckets: Option<usize>,
+) -> Result<DatasetReport, E> {
+    let mut stats = TrustStats::default();
+    let mut nodes: HashMap<String, usize> = HashMap::new();
+    let mut index = |id: &String| {
+        let next = nodes.len();
+        *nodes.entry(id.clone()).or_insert(next)
+    };
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    // Number of outbound edges and their sum, per node
+    let mut rows: HashMap<usize, (usize, f64)> = HashMap::new();
+    let mut values = Vec::new();
+    for entry in trust {
+        let entry = entry?;
+        stats.entries += 1;
+        let (from, to) = (index(entry.from()), index(entry.to()));
+        if !edges.insert((from, to)) {
+            stats.duplicate_edges += 1;
+        }
+        if from == to {
+            stats.self_trust += 1;
+        }
+        if *entry.value() < 0.0 {
+            stats.negative_values += 1;
+        }
+        let row = rows.entry(from).or_default();
+        row.0 += 1;
+        row.1 += entry.value();
+        if histogram_buckets.is_some() {
+            values.push(*entry.value());
+        }
+    }
+    stats.nodes = nodes.len();
+    stats.edges = edges.len();
+    stats.dangling_nodes = nodes.len() - rows.len();
+    stats.zero_sum_rows = rows.values().filter(|(_, sum)| *sum == 0.0).count();
+
+    let mut seed_stats = SeedStats::default();
+    let (mut total_weight, mut graph_weight) = (0.0, 0.0);
+    let mut seeded = HashSet::new();
+    for entry in seed {
+        let entry = entry?;
+        seed_stats.entries += 1;
+        total_weight += entry.value();
+        if nodes.contains_key(entry.id()) {
+            seed_stats.in_graph += 1;
+            graph_weight += entry.value();
+            seeded.insert(entry.id().clone());
+        } else {
+            seed_stats.not_in_graph += 1;
+        }
+    }
+    seed_stats.weight_coverage = if total_weight != 0.0 {
+        graph_weight / total_weight
+    } else {
+        0.0
+    };
+    seed_stats.graph_coverage = if nodes.is_empty() {
+        0.0
+    } else {
+        seeded.len() as f64 / nodes.len() as f64
+    };
+
+    Ok(DatasetReport {
+        trust: stats,
+        seed: seed_stats,
+        histogram: histogram_buckets.map(|buckets| Histogram::new(&values, buckets)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{inspect, Histogram};
+    use crate::{ScoreEntry, TrustEntry};
+
+    #[test]
+    fn should_inspect_dataset() {
+        let trust = [
+            ("a", "b", 1.0),
+            ("a", "c", 2.0),
+            ("a", "b", 1.0),
+            ("b", "b", 1.0),
+            ("c", "a", 0.0),
+            ("d", "a", 3.0),
+        ]
+        .map(|(from, to, value)| Ok::<_, ()>(TrustEntry::new(from.into(), to.into(), value)));
+        let seed = [("a", 0.5), ("e", 0.25), ("b", 0.25)]
+            .map(|(id, value)| Ok(ScoreEntry::new(id.into(), value)));
+        let report = inspect(trust, seed, Some(3)).unwrap();
+
+        let trust = &report.trust;
+        assert_eq!(trust.nodes, 4);
+        assert_eq!(trust.entries, 6);
+        assert_eq!(trust.edges, 5);
+        assert_eq!(trust.duplicate_edges, 1);
+        assert_eq!(trust.self_trust, 1);
+        assert_eq!(trust.negative_values, 0);
+        assert_eq!(trust.dangling_nodes, 0);
+        assert_eq!(trust.zero_sum_rows, 1);
+
+        assert_eq!(report.seed.entries, 3);
+        assert_eq!(report.seed.in_graph, 2);
+        assert_eq!(report.seed.not_in_graph, 1);
+        assert_eq!(report.seed.weight_coverage, 0.75);
+        assert_eq!(report.seed.graph_coverage, 0.5);
+
+        let histogram = report.histogram.unwrap();
+        assert_eq!((histogram.min, histogram.max), (0.0, 3.0));
+        assert_eq!(histogram.counts, [1, 3, 2]);
+    }
+
+    #[test]
+    fn should_count_dangling_nodes() {
+        let trust = [("a", "b", 1.0), ("b", "c", 1.0)]
+            .map(|(from, to, value)| Ok::<_, ()>(TrustEntry::new(from.into(), to.into(), value)));
+        let report = inspect(trust, [], None).unwrap();
+        assert_eq!(report.trust.dangling_nodes, 1);
+        assert!(report.histogram.is_none());
+
+        let histogram = Histogram::new(&[2.0, 2.0], 4);
+        assert_eq!(histogram.counts, [2, 0, 0, 0]);
+    }
+}
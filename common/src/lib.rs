@@ -1,13 +1,42 @@
 pub mod algos;
+pub mod attestation;
+pub mod blocklist;
+pub mod canonical;
+pub mod challenge;
+pub mod contract;
 pub mod eigenda;
+pub mod format;
+pub mod hashing;
+pub mod hooks;
+pub mod identity;
+pub mod inspect;
 pub mod logs;
+pub mod manifest;
 pub mod merkle;
+pub mod metrics;
+pub mod net;
+pub mod normalize;
+pub mod params;
+pub mod proof;
+pub mod ranking;
+pub mod rpc;
 pub mod runner;
+pub mod scheme;
+pub mod score_meta;
+pub mod snapshot;
+pub mod storage;
+pub mod subgraph;
+pub mod trust_matrix;
+pub mod updates;
+pub mod value;
 
 use alloy_primitives::TxHash;
-use alloy_rlp::{BufMut, Decodable, Encodable, Error as RlpError, Result as RlpResult};
+use alloy_rlp::{
+    BufMut, Decodable, Encodable, Error as RlpError, Header, Result as RlpResult, EMPTY_LIST_CODE,
+};
 use csv::StringRecord;
 use getset::Getters;
+use scheme::Precision;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs::File, io::Read};
 
@@ -26,76 +55,188 @@ pub fn format_hex(hex: String) -> String {
 #[getset(get = "pub")]
 pub struct ScoreEntry {
     id: String,
-    value: f32,
+    value: f64,
 }
 
 impl ScoreEntry {
-    pub fn new(id: String, value: f32) -> Self {
+    pub fn new(id: String, value: f64) -> Self {
         Self { id, value }
     }
 }
 
+impl ScoreEntry {
+    fn payload_length(&self) -> usize {
+        ENTRY_ENCODING_VERSION.length() + self.id.length() + VALUE_FIELD_LENGTH
+    }
+}
+
+/// Encoded as the RLP list `[version, id, value]`, see `ENTRY_ENCODING_VERSION`.
 impl Encodable for ScoreEntry {
     fn encode(&self, out: &mut dyn BufMut) {
+        list_header(self.payload_length()).encode(out);
+        ENTRY_ENCODING_VERSION.encode(out);
         self.id.encode(out);
-        out.put_f32(self.value);
+        encode_value(self.value, out);
+    }
+
+    fn length(&self) -> usize {
+        list_length(self.payload_length())
     }
 }
 
 impl Decodable for ScoreEntry {
     fn decode(buf: &mut &[u8]) -> RlpResult<Self> {
-        let id = String::decode(buf)?;
-        let mut value_bytes = [0; 4];
-        let size = buf
-            .read(&mut value_bytes)
-            .map_err(|_| RlpError::Custom("Failed to read bytes"))?;
-        if size != 4 {
-            return RlpResult::Err(RlpError::UnexpectedLength);
+        if !is_list(buf) {
+            return decode_legacy_score_entry(buf);
         }
-        let value = f32::from_be_bytes(value_bytes);
+        let (version, mut payload) = decode_versioned_payload(buf)?;
+        let id = String::decode(&mut payload)?;
+        let value = decode_value(version, &mut payload)?;
+        check_payload_consumed(payload)?;
         Ok(ScoreEntry { id, value })
     }
 }
 
+/// Decodes the legacy encoding: the RLP encoded id followed by the raw big-endian value.
+fn decode_legacy_score_entry(buf: &mut &[u8]) -> RlpResult<ScoreEntry> {
+    let id = String::decode(buf)?;
+    let value = decode_legacy_f32(buf)?;
+    Ok(ScoreEntry { id, value })
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Getters)]
 #[getset(get = "pub")]
 pub struct TrustEntry {
     from: String,
     to: String,
-    value: f32,
+    value: f64,
 }
 
 impl TrustEntry {
-    pub fn new(from: String, to: String, value: f32) -> Self {
+    pub fn new(from: String, to: String, value: f64) -> Self {
         Self { from, to, value }
     }
 }
 
+impl TrustEntry {
+    fn payload_length(&self) -> usize {
+        ENTRY_ENCODING_VERSION.length() + self.from.length() + self.to.length() + VALUE_FIELD_LENGTH
+    }
+}
+
+/// Encoded as the RLP list `[version, from, to, value]`, see `ENTRY_ENCODING_VERSION`.
 impl Encodable for TrustEntry {
     fn encode(&self, out: &mut dyn BufMut) {
+        list_header(self.payload_length()).encode(out);
+        ENTRY_ENCODING_VERSION.encode(out);
         self.from.encode(out);
         self.to.encode(out);
-        out.put_f32(self.value);
+        encode_value(self.value, out);
+    }
+
+    fn length(&self) -> usize {
+        list_length(self.payload_length())
     }
 }
 
 impl Decodable for TrustEntry {
     fn decode(buf: &mut &[u8]) -> RlpResult<Self> {
-        let from = String::decode(buf)?;
-        let to = String::decode(buf)?;
-        let mut value_bytes = [0; 4];
-        let size = buf
-            .read(&mut value_bytes)
-            .map_err(|_| RlpError::Custom("Failed to read bytes"))?;
-        if size != 4 {
-            return RlpResult::Err(RlpError::UnexpectedLength);
+        if !is_list(buf) {
+            return decode_legacy_trust_entry(buf);
         }
-        let value = f32::from_be_bytes(value_bytes);
+        let (version, mut payload) = decode_versioned_payload(buf)?;
+        let from = String::decode(&mut payload)?;
+        let to = String::decode(&mut payload)?;
+        let value = decode_value(version, &mut payload)?;
+        check_payload_consumed(payload)?;
         Ok(TrustEntry { from, to, value })
     }
 }
 
-/// Common job description used across computer, challenger, and rxp modules
+/// Decodes the legacy encoding: the RLP encoded endpoints followed by the raw big-endian value.
+fn decode_legacy_trust_entry(buf: &mut &[u8]) -> RlpResult<TrustEntry> {
+    let from = String::decode(buf)?;
+    let to = String::decode(buf)?;
+    let value = decode_legacy_f32(buf)?;
+    Ok(TrustEntry { from, to, value })
+}
+
+/// Version of the canonical RLP encoding of the entries, its first field.
+///
+/// Entries are encoded as RLP lists, their values as 8-byte strings (big-endian f64), so every
+/// field is length-prefixed and entries can be nested in lists. Version 1 entries, with 4-byte
+/// (big-endian f32) values, and the legacy encoding, without list header and with the raw f32
+/// value bytes, are still decoded, entries never starting with a list header in the latter.
+pub const ENTRY_ENCODING_VERSION: u8 = 2;
+/// Version of the entries with f32 values.
+const ENTRY_ENCODING_VERSION_F32: u8 = 1;
+/// Length of an encoded value field: the string header and the 8 value bytes.
+const VALUE_FIELD_LENGTH: usize = 9;
+
+fn list_header(payload_length: usize) -> Header {
+    Header {
+        list: true,
+        payload_length,
+    }
+}
+
+fn list_length(payload_length: usize) -> usize {
+    list_header(payload_length).length() + payload_length
+}
+
+fn encode_value(value: f64, out: &mut dyn BufMut) {
+    value.to_be_bytes().encode(out);
+}
+
+fn decode_value(version: u8, buf: &mut &[u8]) -> RlpResult<f64> {
+    if version == ENTRY_ENCODING_VERSION_F32 {
+        return <[u8; 4]>::decode(buf).map(|bytes| f32::from_be_bytes(bytes) as f64);
+    }
+    <[u8; 8]>::decode(buf).map(f64::from_be_bytes)
+}
+
+fn decode_legacy_f32(buf: &mut &[u8]) -> RlpResult<f64> {
+    let mut value_bytes = [0; 4];
+    let size = buf
+        .read(&mut value_bytes)
+        .map_err(|_| RlpError::Custom("Failed to read bytes"))?;
+    if size != 4 {
+        return RlpResult::Err(RlpError::UnexpectedLength);
+    }
+    Ok(f32::from_be_bytes(value_bytes) as f64)
+}
+
+fn is_list(buf: &[u8]) -> bool {
+    buf.first().is_some_and(|b| *b >= EMPTY_LIST_CODE)
+}
+
+/// Decodes the list header and the version of a canonical entry, returning the version and the
+/// remaining fields.
+fn decode_versioned_payload<'a>(buf: &mut &'a [u8]) -> RlpResult<(u8, &'a [u8])> {
+    let header = Header::decode(buf)?;
+    if !header.list {
+        return Err(RlpError::UnexpectedString);
+    }
+    if buf.len() < header.payload_length {
+        return Err(RlpError::InputTooShort);
+    }
+    let (mut payload, rest) = buf.split_at(header.payload_length);
+    *buf = rest;
+    let version = u8::decode(&mut payload)?;
+    if version != ENTRY_ENCODING_VERSION && version != ENTRY_ENCODING_VERSION_F32 {
+        return Err(RlpError::Custom("Unsupported entry encoding version"));
+    }
+    Ok((version, payload))
+}
+
+fn check_payload_consumed(payload: &[u8]) -> RlpResult<()> {
+    if !payload.is_empty() {
+        return Err(RlpError::Custom("Trailing bytes in entry"));
+    }
+    Ok(())
+}
+
+/// Common job description used across the computer, the audit and the SDK
 #[derive(Serialize, Deserialize, Clone)]
 pub struct JobDescription {
     pub name: String,
@@ -103,6 +244,28 @@ pub struct JobDescription {
     pub seed_id: String,
     pub algo_id: u32,
     pub params: HashMap<String, String>,
+    /// Id of the WASM post-processing hook, applied to the scores before committing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_id: Option<String>,
+    /// Id of the blocklist of nodes removed from the graph before compute
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocklist_id: Option<String>,
+    /// Id of the include-list restricting compute to the subgraph induced by its nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_list_id: Option<String>,
+    /// Hex encoded EigenDA certificate of the trust data, when stored in EigenDA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_cert: Option<String>,
+    /// Hex encoded EigenDA certificate of the seed data, when stored in EigenDA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed_cert: Option<String>,
+    /// Key prefix of the tenant, the trust, seed and scores artifacts being stored under it
+    /// (e.g. `acme/trust/<id>`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+    /// Id of the manifest of the dataset snapshot the trust and seed were taken from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
 }
 
 impl JobDescription {
@@ -119,15 +282,88 @@ impl JobDescription {
             seed_id,
             algo_id,
             params,
+            hook_id: None,
+            blocklist_id: None,
+            include_list_id: None,
+            trust_cert: None,
+            seed_cert: None,
+            key_prefix: None,
+            snapshot_id: None,
         }
     }
+
+    pub fn with_hook_id(mut self, hook_id: Option<String>) -> Self {
+        self.hook_id = hook_id;
+        self
+    }
+
+    pub fn with_blocklist_id(mut self, blocklist_id: Option<String>) -> Self {
+        self.blocklist_id = blocklist_id;
+        self
+    }
+
+    pub fn with_include_list_id(mut self, include_list_id: Option<String>) -> Self {
+        self.include_list_id = include_list_id;
+        self
+    }
+
+    pub fn with_certificates(
+        mut self,
+        trust_cert: Option<String>,
+        seed_cert: Option<String>,
+    ) -> Self {
+        self.trust_cert = trust_cert;
+        self.seed_cert = seed_cert;
+        self
+    }
+
+    pub fn with_key_prefix(mut self, key_prefix: Option<String>) -> Self {
+        self.key_prefix = key_prefix;
+        self
+    }
+
+    pub fn with_snapshot_id(mut self, snapshot_id: Option<String>) -> Self {
+        self.snapshot_id = snapshot_id;
+        self
+    }
+
+    pub fn trust_key(&self) -> String {
+        storage::artifact_key(self.key_prefix.as_deref(), "trust", &self.trust_id)
+    }
+
+    pub fn seed_key(&self) -> String {
+        storage::artifact_key(self.key_prefix.as_deref(), "seed", &self.seed_id)
+    }
+
+    pub fn scores_key(&self, scores_id: &str) -> String {
+        storage::artifact_key(self.key_prefix.as_deref(), "scores", scores_id)
+    }
+
+    pub fn scores_meta_key(&self, metadata_id: &str) -> String {
+        storage::artifact_key(self.key_prefix.as_deref(), "scores-meta", metadata_id)
+    }
 }
 
-/// Common job result used across computer, challenger, and rxp modules
+/// Common job result used across the computer, the audit and the SDK
 #[derive(Serialize, Deserialize, Clone)]
 pub struct JobResult {
     pub scores_id: String,
     pub commitment: String,
+    /// Id of the WASM post-processing hook that was applied to the scores
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_id: Option<String>,
+    /// Version of the scheme the commitment was produced with, see `scheme`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme_version: Option<String>,
+    /// Hex encoded EigenDA certificate of the scores, when stored in EigenDA
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores_cert: Option<String>,
+    /// Id of the extended scores artifact (per-score metadata), when requested by the params
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_id: Option<String>,
+    /// Convergence diagnostics of the compute, left out of the commitment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<algos::ComputeReport>,
 }
 
 impl JobResult {
@@ -135,8 +371,33 @@ impl JobResult {
         Self {
             scores_id,
             commitment,
+            hook_id: None,
+            scheme_version: Some(scheme::CURRENT_SCHEME_VERSION.to_string()),
+            scores_cert: None,
+            metadata_id: None,
+            report: None,
         }
     }
+
+    pub fn with_hook_id(mut self, hook_id: Option<String>) -> Self {
+        self.hook_id = hook_id;
+        self
+    }
+
+    pub fn with_metadata_id(mut self, metadata_id: Option<String>) -> Self {
+        self.metadata_id = metadata_id;
+        self
+    }
+
+    pub fn with_report(mut self, report: Option<algos::ComputeReport>) -> Self {
+        self.report = report;
+        self
+    }
+
+    pub fn with_scheme(mut self, scheme: &scheme::Scheme) -> Self {
+        self.scheme_version = Some(scheme.version.to_string());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -145,6 +406,12 @@ pub struct JobMetadata {
     results_tx_hash: Option<TxHash>,
 }
 
+impl Default for JobMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JobMetadata {
     pub fn new() -> Self {
         Self {
@@ -170,32 +437,280 @@ impl JobMetadata {
     }
 }
 
-/// Helper function to parse trust entries from a CSV file
-pub fn parse_trust_entries_from_file(file: File) -> Result<Vec<TrustEntry>, csv::Error> {
-    let mut reader = csv::Reader::from_reader(file);
-    let mut entries = Vec::new();
+/// Streams the trust entries of a CSV file one record at a time, so that the trust map of
+/// very large graphs can be built without holding every entry in memory.
+pub struct TrustEntryReader<R> {
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    precision: Precision,
+}
+
+impl<R: Read> TrustEntryReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: csv::Reader::from_reader(reader),
+            record: StringRecord::new(),
+            precision: Precision::F64,
+        }
+    }
+
+    /// Reader stripping the whitespace around the fields.
+    pub fn trimmed(reader: R) -> Self {
+        Self {
+            reader: csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(reader),
+            record: StringRecord::new(),
+            precision: Precision::F64,
+        }
+    }
+
+    /// Parses the values with the precision of the computation, rather than as f64 rounded
+    /// to it afterwards.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+}
+
+impl<R: Read> Iterator for TrustEntryReader<R> {
+    type Item = Result<TrustEntry, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => {
+                Some(match self.precision {
+                    Precision::F32 => self.record.deserialize(None).map(
+                        |(from, to, value): (String, String, f32)| {
+                            TrustEntry::new(from, to, value.into())
+                        },
+                    ),
+                    Precision::F64 => self.record.deserialize(None).map(
+                        |(from, to, value): (String, String, f64)| TrustEntry::new(from, to, value),
+                    ),
+                })
+            }
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Streams the score entries of a CSV file one record at a time. The extra columns of ranked
+/// score files are ignored.
+pub struct ScoreEntryReader<R> {
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    precision: Precision,
+}
+
+impl<R: Read> ScoreEntryReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: csv::Reader::from_reader(reader),
+            record: StringRecord::new(),
+            precision: Precision::F64,
+        }
+    }
+
+    /// Reader stripping the whitespace around the fields.
+    pub fn trimmed(reader: R) -> Self {
+        Self {
+            reader: csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(reader),
+            record: StringRecord::new(),
+            precision: Precision::F64,
+        }
+    }
 
-    for result in reader.records() {
-        let record: StringRecord = result?;
-        let (from, to, value): (String, String, f32) = record.deserialize(None)?;
-        let trust_entry = TrustEntry::new(from, to, value);
-        entries.push(trust_entry);
+    /// Parses the values with the precision of the computation, rather than as f64 rounded
+    /// to it afterwards.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
     }
+}
+
+impl<R: Read> Iterator for ScoreEntryReader<R> {
+    type Item = Result<ScoreEntry, csv::Error>;
 
-    Ok(entries)
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.record) {
+            Ok(true) => Some(match self.precision {
+                Precision::F32 => self
+                    .record
+                    .deserialize(None)
+                    .map(|(id, value): (String, f32)| ScoreEntry::new(id, value.into())),
+                Precision::F64 => self
+                    .record
+                    .deserialize(None)
+                    .map(|(id, value): (String, f64)| ScoreEntry::new(id, value)),
+            }),
+            Ok(false) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
 }
 
-/// Helper function to parse score entries from a CSV file
+/// Helper function to parse trust entries from a CSV or Parquet file
+pub fn parse_trust_entries_from_file(file: File) -> Result<Vec<TrustEntry>, csv::Error> {
+    format::trust_entries(file, Precision::F64)?.collect()
+}
+
+/// Helper function to parse score entries from a CSV or Parquet file
 pub fn parse_score_entries_from_file(file: File) -> Result<Vec<ScoreEntry>, csv::Error> {
-    let mut reader = csv::Reader::from_reader(file);
-    let mut entries = Vec::new();
+    format::score_entries(file, Precision::F64)?.collect()
+}
 
-    for result in reader.records() {
-        let record: StringRecord = result?;
-        let (id, value): (String, f32) = record.deserialize(None)?;
-        let score_entry = ScoreEntry::new(id, value);
-        entries.push(score_entry);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_id(rng: &mut StdRng) -> String {
+        let len = rng.gen_range(0..80);
+        (0..len).map(|_| rng.gen::<char>()).collect()
+    }
+
+    fn same_entry(a: &TrustEntry, b: &TrustEntry) -> bool {
+        // Compared bitwise so NaN values round-trip too
+        a.from == b.from && a.to == b.to && a.value.to_bits() == b.value.to_bits()
+    }
+
+    #[test]
+    fn should_round_trip_entries() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..1000 {
+            let score = ScoreEntry::new(random_id(&mut rng), f64::from_bits(rng.gen()));
+            let bytes = alloy_rlp::encode(&score);
+            assert_eq!(bytes.len(), score.length());
+            let decoded = ScoreEntry::decode(&mut bytes.as_slice()).unwrap();
+            assert_eq!(decoded.id, score.id);
+            assert_eq!(decoded.value.to_bits(), score.value.to_bits());
+
+            let trust = TrustEntry::new(
+                random_id(&mut rng),
+                random_id(&mut rng),
+                f64::from_bits(rng.gen()),
+            );
+            let bytes = alloy_rlp::encode(&trust);
+            assert_eq!(bytes.len(), trust.length());
+            let decoded = TrustEntry::decode(&mut bytes.as_slice()).unwrap();
+            assert!(same_entry(&decoded, &trust));
+        }
     }
 
-    Ok(entries)
+    #[test]
+    fn should_round_trip_entry_lists() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let entries: Vec<TrustEntry> = (0..100)
+            .map(|_| TrustEntry::new(random_id(&mut rng), random_id(&mut rng), rng.gen()))
+            .collect();
+        let bytes = alloy_rlp::encode(&entries);
+        let decoded = Vec::<TrustEntry>::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, entries);
+
+        let scores = vec![
+            ScoreEntry::new("a".into(), 0.5),
+            ScoreEntry::new("".into(), 1.0),
+        ];
+        let bytes = alloy_rlp::encode(&scores);
+        assert_eq!(
+            Vec::<ScoreEntry>::decode(&mut bytes.as_slice()).unwrap(),
+            scores
+        );
+    }
+
+    #[test]
+    fn should_decode_legacy_entries() {
+        let mut bytes = Vec::new();
+        "alice".to_string().encode(&mut bytes);
+        "bob".to_string().encode(&mut bytes);
+        bytes.extend_from_slice(&0.25f32.to_be_bytes());
+        let mut buf = bytes.as_slice();
+        let entry = TrustEntry::decode(&mut buf).unwrap();
+        assert_eq!(entry, TrustEntry::new("alice".into(), "bob".into(), 0.25));
+        assert!(buf.is_empty());
+
+        let mut bytes = Vec::new();
+        "alice".to_string().encode(&mut bytes);
+        bytes.extend_from_slice(&0.5f32.to_be_bytes());
+        let entry = ScoreEntry::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(entry, ScoreEntry::new("alice".into(), 0.5));
+
+        // Truncated value
+        let mut bytes = Vec::new();
+        "alice".to_string().encode(&mut bytes);
+        bytes.extend_from_slice(&[0, 0]);
+        assert!(ScoreEntry::decode(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn should_stream_trust_entries() {
+        let csv = "i,j,v\nalice,bob,0.5\nbob,carol,2\n";
+        let entries: Vec<TrustEntry> = TrustEntryReader::new(csv.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                TrustEntry::new("alice".into(), "bob".into(), 0.5),
+                TrustEntry::new("bob".into(), "carol".into(), 2.0),
+            ]
+        );
+
+        let mut reader = TrustEntryReader::new("i,j,v\nalice,bob,0.5\nbob,carol,x\n".as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn should_decode_f32_entries() {
+        let mut bytes = Vec::new();
+        list_header(1 + "alice".length() + 5).encode(&mut bytes);
+        ENTRY_ENCODING_VERSION_F32.encode(&mut bytes);
+        "alice".to_string().encode(&mut bytes);
+        0.1f32.to_be_bytes().encode(&mut bytes);
+        let entry = ScoreEntry::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(entry, ScoreEntry::new("alice".into(), 0.1f32 as f64));
+    }
+
+    #[test]
+    fn should_reject_invalid_entries() {
+        let score = ScoreEntry::new("alice".into(), 0.5);
+        let bytes = alloy_rlp::encode(&score);
+        // Unknown version
+        let mut unknown = bytes.clone();
+        unknown[1] = ENTRY_ENCODING_VERSION + 1;
+        assert!(ScoreEntry::decode(&mut unknown.as_slice()).is_err());
+        // Every truncation
+        for len in 0..bytes.len() {
+            assert!(ScoreEntry::decode(&mut &bytes[..len]).is_err());
+        }
+        // Score decoded as a trust entry
+        assert!(TrustEntry::decode(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn should_not_panic_on_random_bytes() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let valid = alloy_rlp::encode(TrustEntry::new("alice".into(), "bob".into(), 1.0));
+        for _ in 0..10_000 {
+            let mut bytes: Vec<u8> = if rng.gen_bool(0.5) {
+                (0..rng.gen_range(0..64)).map(|_| rng.gen()).collect()
+            } else {
+                // Mutations of a valid encoding
+                let mut bytes = valid.clone();
+                let i = rng.gen_range(0..bytes.len());
+                bytes[i] = rng.gen();
+                bytes
+            };
+            bytes.truncate(rng.gen_range(0..=bytes.len()));
+            let _ = ScoreEntry::decode(&mut bytes.as_slice());
+            let _ = TrustEntry::decode(&mut bytes.as_slice());
+            let _ = Vec::<TrustEntry>::decode(&mut bytes.as_slice());
+        }
+    }
 }
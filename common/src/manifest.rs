@@ -0,0 +1,186 @@
+use crate::scheme::CURRENT_SCHEME_VERSION;
+use crate::storage::artifact_key;
+use crate::{JobDescription, JobResult};
+use alloy_primitives::{Address, Signature, TxHash};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Inputs, outputs and commitment of a single sub-job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubJobManifest {
+    pub name: String,
+    pub algo_id: u32,
+    pub params: HashMap<String, String>,
+    pub trust_id: String,
+    pub seed_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocklist_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_list_id: Option<String>,
+    pub scores_id: String,
+    pub commitment: String,
+    /// Version of the scheme of the commitment, when it isn't the one of the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme_version: Option<String>,
+    /// Id of the extended scores artifact, not covered by the commitment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_id: Option<String>,
+    /// Key prefix of the tenant the artifacts are stored under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+    /// Id of the manifest of the dataset snapshot of the trust and seed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+}
+
+impl SubJobManifest {
+    pub fn new(job: &JobDescription, result: &JobResult) -> Self {
+        Self {
+            name: job.name.clone(),
+            algo_id: job.algo_id,
+            params: job.params.clone(),
+            trust_id: job.trust_id.clone(),
+            seed_id: job.seed_id.clone(),
+            hook_id: result.hook_id.clone(),
+            blocklist_id: job.blocklist_id.clone(),
+            include_list_id: job.include_list_id.clone(),
+            scores_id: result.scores_id.clone(),
+            commitment: result.commitment.clone(),
+            scheme_version: result
+                .scheme_version
+                .clone()
+                .filter(|version| version != CURRENT_SCHEME_VERSION),
+            metadata_id: result.metadata_id.clone(),
+            key_prefix: job.key_prefix.clone(),
+            snapshot_id: job.snapshot_id.clone(),
+        }
+    }
+
+    pub fn trust_key(&self) -> String {
+        artifact_key(self.key_prefix.as_deref(), "trust", &self.trust_id)
+    }
+
+    pub fn seed_key(&self) -> String {
+        artifact_key(self.key_prefix.as_deref(), "seed", &self.seed_id)
+    }
+
+    pub fn scores_key(&self) -> String {
+        artifact_key(self.key_prefix.as_deref(), "scores", &self.scores_id)
+    }
+
+    pub fn scores_meta_key(&self, metadata_id: &str) -> String {
+        artifact_key(self.key_prefix.as_deref(), "scores-meta", metadata_id)
+    }
+}
+
+/// Receipt of the on-chain result submission.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultReceipt {
+    pub tx_hash: TxHash,
+}
+
+/// Everything needed to consume the results of a compute id, uploaded under `manifest/{compute_id}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComputeManifest {
+    pub compute_id: String,
+    /// Address of the computer that produced the results.
+    pub computer: Address,
+    pub job_description_id: String,
+    pub results_id: String,
+    pub meta_commitment: String,
+    /// Version of the scheme the commitments were produced with, see `scheme`, unless their
+    /// sub-job sets another one.
+    pub proof_spec_version: String,
+    pub sub_jobs: Vec<SubJobManifest>,
+    pub receipt: Option<ResultReceipt>,
+}
+
+impl ComputeManifest {
+    pub fn new(
+        compute_id: String,
+        computer: Address,
+        job_description_id: String,
+        results_id: String,
+        meta_commitment: String,
+        jobs: &[JobDescription],
+        results: &[JobResult],
+    ) -> Self {
+        let sub_jobs = jobs
+            .iter()
+            .zip(results)
+            .map(|(job, result)| SubJobManifest::new(job, result))
+            .collect();
+        Self {
+            compute_id,
+            computer,
+            job_description_id,
+            results_id,
+            meta_commitment,
+            proof_spec_version: CURRENT_SCHEME_VERSION.to_string(),
+            sub_jobs,
+            receipt: None,
+        }
+    }
+
+    pub fn with_receipt(mut self, tx_hash: TxHash) -> Self {
+        self.receipt = Some(ResultReceipt { tx_hash });
+        self
+    }
+
+    /// Returns the version of the scheme the commitment of the sub-job was produced with.
+    pub fn scheme_version(&self, sub_job: &SubJobManifest) -> String {
+        sub_job
+            .scheme_version
+            .clone()
+            .unwrap_or_else(|| self.proof_spec_version.clone())
+    }
+
+    /// Returns the bytes that are signed by the computer.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+}
+
+/// Compute manifest, signed with the computer's wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedComputeManifest {
+    pub manifest: ComputeManifest,
+    pub signature: Signature,
+}
+
+impl SignedComputeManifest {
+    pub fn new(manifest: ComputeManifest, signature: Signature) -> Self {
+        Self {
+            manifest,
+            signature,
+        }
+    }
+
+    /// Recovers the signer of the manifest and checks it matches the computer address.
+    pub fn verify(&self) -> Result<(), Error> {
+        let bytes = self.manifest.signing_bytes().map_err(Error::Serde)?;
+        let signer = self
+            .signature
+            .recover_address_from_msg(bytes)
+            .map_err(|e| Error::Signature(e.to_string()))?;
+        if signer != self.manifest.computer {
+            return Err(Error::SignerMismatch {
+                expected: self.manifest.computer,
+                actual: signer,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Serde error: {0}")]
+    Serde(serde_json::Error),
+    #[error("Signature error: {0}")]
+    Signature(String),
+    #[error("Signer mismatch: expected {expected}, got {actual}")]
+    SignerMismatch { expected: Address, actual: Address },
+}
@@ -63,7 +63,7 @@ where
             let level_nodes = self.nodes.get(&level).ok_or(merkle::Error::NodesNotFound)?;
 
             // Determine the sibling index
-            let sibling_index = if current_index % 2 == 0 {
+            let sibling_index = if current_index.is_multiple_of(2) {
                 current_index + 1
             } else {
                 current_index - 1
@@ -86,32 +86,9 @@ where
     }
 
     /// Verifies a Merkle path (proof) for a given leaf hash and index against the expected root.
-    ///
-    /// This function takes a leaf hash, its index in the tree, and a path of sibling hashes,
-    /// then reconstructs the root by hashing up the tree and compares it to the expected root.
-    ///
-    /// # Arguments
-    /// * `leaf` - The hash of the leaf to verify
-    /// * `index` - The index of the leaf in the tree (0-based)
-    /// * `path` - The Merkle path (sibling hashes from leaf level to root level)
-    /// * `expected_root` - The expected root hash to verify against
-    ///
-    /// # Returns
-    /// `true` if the path is valid and leads to the expected root, `false` otherwise.
+    /// See `merkle::verify_path`.
     pub fn verify_path(leaf: &Hash, index: usize, path: &[Hash], expected_root: &Hash) -> bool {
-        let mut current = leaf.clone();
-        let mut current_index = index;
-
-        for sibling in path {
-            if current_index % 2 == 0 {
-                current = hash_two::<H>(current, sibling.clone());
-            } else {
-                current = hash_two::<H>(sibling.clone(), current);
-            }
-            current_index /= 2;
-        }
-
-        current == *expected_root
+        merkle::verify_path::<H>(leaf, index as u64, path, expected_root)
     }
 
     /// Builds a Merkle tree from the given leaf nodes.
@@ -158,11 +135,134 @@ where
     }
 }
 
+/// Streaming builder of the `DenseMerkleTree` root.
+/// Leaves are pushed one by one (e.g. while reading or writing a scores file),
+/// and only the pending left node of each level is kept in memory,
+/// so the root of `n` leaves is computed with O(log n) memory.
+/// The resulting root is the same as `DenseMerkleTree::new(leaves).root()`.
+pub struct StreamingDenseMerkleTree<H>
+where
+    H: Digest,
+{
+    /// Pending left node for each level, waiting for its right sibling.
+    frontier: Vec<Option<Hash>>,
+    /// Number of leaves pushed so far.
+    num_leaves: usize,
+    /// PhantomData for the hasher
+    _h: PhantomData<H>,
+}
+
+impl<H> Default for StreamingDenseMerkleTree<H>
+where
+    H: Digest,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> StreamingDenseMerkleTree<H>
+where
+    H: Digest,
+{
+    pub fn new() -> Self {
+        Self {
+            frontier: Vec::new(),
+            num_leaves: 0,
+            _h: PhantomData,
+        }
+    }
+
+    /// Builds the root from an iterator of leaves.
+    pub fn root_from_iter<I: IntoIterator<Item = Hash>>(leaves: I) -> Hash {
+        let mut tree = Self::new();
+        for leaf in leaves {
+            tree.push(leaf);
+        }
+        tree.root()
+    }
+
+    /// Returns the number of leaves pushed so far.
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Appends a leaf to the tree.
+    pub fn push(&mut self, leaf: Hash) {
+        self.num_leaves += 1;
+        let mut node = leaf;
+        let mut level = 0;
+        loop {
+            if level == self.frontier.len() {
+                self.frontier.push(None);
+            }
+            match self.frontier[level].take() {
+                Some(left) => {
+                    node = hash_two::<H>(left, node);
+                    level += 1;
+                }
+                None => {
+                    self.frontier[level] = Some(node);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the root of the tree, padding the leaves with default hashes
+    /// up to the next power of two.
+    pub fn root(&self) -> Hash {
+        let next_power_of_two = self.num_leaves.next_power_of_two();
+        let top_level = next_power_of_two.trailing_zeros() as usize;
+
+        let mut default = Hash::default();
+        // Rightmost node of the current level, built from a partial subtree
+        let mut partial: Option<Hash> = None;
+        for level in 0..top_level {
+            let left = self.frontier.get(level).cloned().flatten();
+            partial = match (left, partial) {
+                (Some(left), Some(right)) => Some(hash_two::<H>(left, right)),
+                (Some(left), None) => Some(hash_two::<H>(left, default.clone())),
+                (None, Some(left)) => Some(hash_two::<H>(left, default.clone())),
+                (None, None) => None,
+            };
+            default = hash_two::<H>(default.clone(), default);
+        }
+        let top = self
+            .frontier
+            .get(top_level)
+            .cloned()
+            .flatten()
+            .or(partial)
+            .unwrap_or_default();
+
+        // Dense tree has one more level above the padded leaves
+        hash_two::<H>(top, default)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::merkle::{fixed::DenseMerkleTree, Hash};
+    use crate::merkle::{
+        fixed::{DenseMerkleTree, StreamingDenseMerkleTree},
+        verify_path, Hash,
+    };
+    use rand::thread_rng;
     use sha3::Keccak256;
 
+    #[test]
+    fn should_match_streaming_tree_root() {
+        for n in 0..40u8 {
+            let leaves: Vec<Hash> = (0..n).map(|i| Hash::from_bytes([i + 1; 32])).collect();
+            let expected = DenseMerkleTree::<Keccak256>::new(leaves.clone())
+                .unwrap()
+                .root()
+                .unwrap();
+            let root = StreamingDenseMerkleTree::<Keccak256>::root_from_iter(leaves);
+            assert_eq!(root, expected, "Root mismatch for {} leaves", n);
+        }
+    }
+
     #[test]
     fn should_build_fixed_tree() {
         // Testing build_tree and find_path functions with arity 2
@@ -226,7 +326,7 @@ mod test {
         let mut current = leaf0.clone();
         let mut index = 0usize;
         for sibling in &path {
-            if index % 2 == 0 {
+            if index.is_multiple_of(2) {
                 current = hash_two::<Keccak256>(current, sibling.clone());
             } else {
                 current = hash_two::<Keccak256>(sibling.clone(), current);
@@ -286,4 +386,24 @@ mod test {
             "Path verification should fail for wrong root"
         );
     }
+
+    #[test]
+    fn should_verify_paths_of_padded_tree() {
+        let rng = &mut thread_rng();
+        let leaves: Vec<Hash> = (0..13).map(|_| Hash::random(rng)).collect();
+        let merkle = DenseMerkleTree::<Keccak256>::new(leaves.clone()).unwrap();
+        let root = merkle.root().unwrap();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = merkle.generate_path(i).unwrap();
+            assert!(verify_path::<Keccak256>(leaf, i as u64, &path, &root));
+        }
+
+        // Indices beyond the tree must not alias the leaves with the same low bits
+        let path = merkle.generate_path(0).unwrap();
+        let aliased = (1u64 << path.len()) as usize;
+        assert!(!DenseMerkleTree::<Keccak256>::verify_path(
+            &leaves[0], aliased, &path, &root
+        ));
+        assert!(merkle.generate_path(16).is_err());
+    }
 }
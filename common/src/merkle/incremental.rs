@@ -84,6 +84,32 @@ where
         }
     }
 
+    /// Generates a Merkle path (proof) for the leaf at the given index: the sibling hashes
+    /// from the leaf level up to (but not including) the root, default nodes standing in for
+    /// the siblings not inserted yet.
+    pub fn generate_path(&self, index: u64) -> Result<Vec<Hash>, merkle::Error> {
+        if (self.num_levels as u32) < u64::BITS && index >> self.num_levels != 0 {
+            return Err(merkle::Error::NodesNotFound);
+        }
+        let mut path = Vec::with_capacity(self.num_levels as usize);
+        let mut curr_index = index;
+        for i in 0..self.num_levels {
+            let sibling = self
+                .nodes
+                .get(&(i, curr_index ^ 1))
+                .unwrap_or(&self.default[&(i, 0)]);
+            path.push(sibling.clone());
+            curr_index = next_index(curr_index);
+        }
+        Ok(path)
+    }
+
+    /// Verifies a Merkle path (proof) for a given leaf hash and index against the expected root.
+    /// See `merkle::verify_path`.
+    pub fn verify_path(leaf: &Hash, index: u64, path: &[Hash], expected_root: &Hash) -> bool {
+        merkle::verify_path::<H>(leaf, index, path, expected_root)
+    }
+
     /// Insert multiple leaves to tree.
     pub fn insert_batch(&mut self, mut index: u64, leaves: Vec<Hash>) {
         for leaf in leaves {
@@ -96,6 +122,7 @@ where
 #[cfg(test)]
 mod test {
     use crate::merkle::{incremental::DenseIncrementalMerkleTree, Hash};
+    use rand::thread_rng;
     use sha3::Keccak256;
 
     #[test]
@@ -132,4 +159,44 @@ mod test {
             "27ae5ba08d7291c96c8cbddcc148bf48a6d68c7974b94356f53754ef6171d757".to_string()
         );
     }
+
+    #[test]
+    fn should_generate_and_verify_path() {
+        let rng = &mut thread_rng();
+        let leaves: Vec<Hash> = (0..11).map(|_| Hash::random(rng)).collect();
+        let mut merkle = DenseIncrementalMerkleTree::<Keccak256>::new(8);
+        merkle.insert_batch(0, leaves.clone());
+        let root = merkle.root().unwrap();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = merkle.generate_path(i as u64).unwrap();
+            assert_eq!(path.len(), 8);
+            assert!(DenseIncrementalMerkleTree::<Keccak256>::verify_path(
+                leaf, i as u64, &path, &root
+            ));
+            assert!(!DenseIncrementalMerkleTree::<Keccak256>::verify_path(
+                leaf,
+                i as u64 ^ 1,
+                &path,
+                &root
+            ));
+        }
+
+        // Default leaves past the inserted ones are part of the tree too
+        let path = merkle.generate_path(200).unwrap();
+        assert!(DenseIncrementalMerkleTree::<Keccak256>::verify_path(
+            &Hash::default(),
+            200,
+            &path,
+            &root
+        ));
+        assert!(merkle.generate_path(256).is_err());
+
+        // Tampered path
+        let mut path = merkle.generate_path(3).unwrap();
+        path[2] = Hash::random(rng);
+        assert!(!DenseIncrementalMerkleTree::<Keccak256>::verify_path(
+            &leaves[3], 3, &path, &root
+        ));
+    }
 }
@@ -23,9 +23,9 @@ impl Hash {
         if slice.len() > 32 {
             bytes.copy_from_slice(&slice[..32]);
         } else {
-            bytes[..slice.len()].copy_from_slice(&slice);
+            bytes[..slice.len()].copy_from_slice(slice);
         }
-        Self(<[u8; 32]>::try_from(bytes).unwrap())
+        Self(bytes)
     }
 
     pub fn from_bytes(bytes: [u8; 32]) -> Self {
@@ -104,6 +104,36 @@ pub fn hash_leaf<H: Digest>(preimage: Vec<u8>) -> Hash {
     Hash(bytes)
 }
 
+/// Verifies a Merkle path (proof) of the leaf at the index against the expected root.
+///
+/// The root is rebuilt by hashing the leaf with the sibling hashes of the path, from the leaf
+/// level up, the current node being on the left when its index at the level is even. Paths of
+/// both the fixed and the incremental trees are verified this way, so clients only need the
+/// leaf, its index, the path and the root (e.g. from a score proof) to check the inclusion.
+///
+/// Returns `false` if the index doesn't fit in a tree of the path height.
+pub fn verify_path<H: Digest>(
+    leaf: &Hash,
+    index: u64,
+    path: &[Hash],
+    expected_root: &Hash,
+) -> bool {
+    if path.len() < u64::BITS as usize && index >> path.len() != 0 {
+        return false;
+    }
+    let mut current = leaf.clone();
+    let mut current_index = index;
+    for sibling in path {
+        current = if current_index.is_multiple_of(2) {
+            hash_two::<H>(current, sibling.clone())
+        } else {
+            hash_two::<H>(sibling.clone(), current)
+        };
+        current_index /= 2;
+    }
+    current == *expected_root
+}
+
 #[derive(thiserror::Error, Debug)]
 /// An error type for the merkle tree.
 pub enum Error {
@@ -0,0 +1,343 @@
+use crate::ranking::rank_order;
+use crate::ScoreEntry;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Default number of top entries compared by the overlap and churn metrics.
+pub const DEFAULT_TOP_K: usize = 100;
+
+/// Kendall rank correlation (tau-b, accounting for ties) between two paired samples,
+/// computed in O(n log n) with Knight's algorithm.
+///
+/// Returns `None` if the samples have different lengths, fewer than two elements,
+/// or if either of them is constant.
+pub fn kendall_tau(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len();
+    if n != y.len() || n < 2 {
+        return None;
+    }
+
+    let mut pairs: Vec<(f64, f64)> = x.iter().copied().zip(y.iter().copied()).collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    let total = pairs_count(n as u64);
+    let x_ties = tied_pairs(&pairs, |a, b| a.0 == b.0);
+    let joint_ties = tied_pairs(&pairs, |a, b| a == b);
+
+    let mut ys: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+    let discordant = sort_counting_inversions(&mut ys);
+    let y_ties = tied_pairs(&ys, |a, b| a == b);
+
+    if x_ties == total || y_ties == total {
+        return None;
+    }
+    let numerator =
+        total as f64 - x_ties as f64 - y_ties as f64 + joint_ties as f64 - 2.0 * discordant as f64;
+    let denominator = ((total - x_ties) as f64 * (total - y_ties) as f64).sqrt();
+    Some(numerator / denominator)
+}
+
+/// Spearman rank correlation between two paired samples: the Pearson correlation of their
+/// ranks, tied values sharing their average rank.
+///
+/// Returns `None` if the samples have different lengths, fewer than two elements,
+/// or if either of them is constant.
+pub fn spearman_rho(x: &[f64], y: &[f64]) -> Option<f64> {
+    if x.len() != y.len() || x.len() < 2 {
+        return None;
+    }
+    pearson(&fractional_ranks(x), &fractional_ranks(y))
+}
+
+/// Returns the 1-based rank of every value, tied values getting their average rank.
+fn fractional_ranks(values: &[f64]) -> Vec<f64> {
+    let n = values.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; n];
+    let mut start = 0;
+    while start < n {
+        let mut end = start + 1;
+        while end < n && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let n = x.len() as f64;
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y) {
+        cov += (a - mean_x) * (b - mean_y);
+        var_x += (a - mean_x) * (a - mean_x);
+        var_y += (b - mean_y) * (b - mean_y);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+fn pairs_count(n: u64) -> u64 {
+    n * n.saturating_sub(1) / 2
+}
+
+/// Counts the pairs of adjacent-equal elements in a sorted slice.
+fn tied_pairs<T>(sorted: &[T], eq: impl Fn(&T, &T) -> bool) -> u64 {
+    let mut ties = 0;
+    let mut run = 1u64;
+    for i in 1..sorted.len() {
+        if eq(&sorted[i - 1], &sorted[i]) {
+            run += 1;
+        } else {
+            ties += pairs_count(run);
+            run = 1;
+        }
+    }
+    ties + pairs_count(run)
+}
+
+/// Sorts the values with a bottom-up merge sort, returning the number of inversions
+/// (pairs `i < j` with `values[i] > values[j]`).
+fn sort_counting_inversions(values: &mut Vec<f64>) -> u64 {
+    let n = values.len();
+    let mut buf = vec![0.0; n];
+    let mut inversions = 0;
+    let mut width = 1;
+    while width < n {
+        let mut start = 0;
+        while start < n {
+            let mid = (start + width).min(n);
+            let end = (start + 2 * width).min(n);
+            let (mut i, mut j, mut k) = (start, mid, start);
+            while i < mid && j < end {
+                if values[i] <= values[j] {
+                    buf[k] = values[i];
+                    i += 1;
+                } else {
+                    buf[k] = values[j];
+                    inversions += (mid - i) as u64;
+                    j += 1;
+                }
+                k += 1;
+            }
+            buf[k..k + mid - i].copy_from_slice(&values[i..mid]);
+            k += mid - i;
+            buf[k..k + end - j].copy_from_slice(&values[j..end]);
+            start = end;
+        }
+        std::mem::swap(values, &mut buf);
+        width *= 2;
+    }
+    inversions
+}
+
+/// Returns the ids ordered by descending score, ties being ordered by id so the ranking
+/// is deterministic.
+pub fn ranking(scores: &[ScoreEntry]) -> Vec<&str> {
+    let mut sorted: Vec<&ScoreEntry> = scores.iter().collect();
+    sorted.sort_by(|a, b| rank_order((&a.id, a.value), (&b.id, b.value)));
+    sorted.into_iter().map(|s| s.id.as_str()).collect()
+}
+
+/// Pairs the values of the ids scored in both sets, in the order of `a`.
+pub fn align(a: &[ScoreEntry], b: &[ScoreEntry]) -> (Vec<f64>, Vec<f64>) {
+    let b_values: HashMap<&str, f64> = b.iter().map(|s| (s.id.as_str(), s.value)).collect();
+    a.iter()
+        .filter_map(|s| Some((s.value, *b_values.get(s.id.as_str())?)))
+        .unzip()
+}
+
+/// Jaccard overlap (size of the intersection over size of the union) of the top `k` ids
+/// of both sets. Two empty sets fully overlap.
+pub fn top_k_overlap(a: &[ScoreEntry], b: &[ScoreEntry], k: usize) -> f64 {
+    let top_a: HashSet<&str> = ranking(a).into_iter().take(k).collect();
+    let top_b: HashSet<&str> = ranking(b).into_iter().take(k).collect();
+    let union = top_a.union(&top_b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    top_a.intersection(&top_b).count() as f64 / union as f64
+}
+
+/// How much the membership and ranking changed from one score set to the next.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Churn {
+    /// Ids only scored in the second set.
+    pub added: usize,
+    /// Ids only scored in the first set.
+    pub removed: usize,
+    /// Ids in the top `k` of the second set, but not of the first one.
+    pub entered_top_k: usize,
+    /// Ids in the top `k` of the first set, but not of the second one.
+    pub left_top_k: usize,
+    /// Mean absolute rank change of the ids scored in both sets.
+    pub mean_rank_shift: f64,
+    /// Largest absolute rank change of the ids scored in both sets.
+    pub max_rank_shift: usize,
+}
+
+/// Computes the churn from the score set `a` to the score set `b`.
+pub fn churn(a: &[ScoreEntry], b: &[ScoreEntry], k: usize) -> Churn {
+    let ranking_a = ranking(a);
+    let ranking_b = ranking(b);
+    let ranks_b: HashMap<&str, usize> = ranking_b
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+
+    let mut churn = Churn::default();
+    let mut common = 0;
+    let mut total_shift = 0;
+    for (rank_a, id) in ranking_a.iter().enumerate() {
+        match ranks_b.get(id) {
+            Some(&rank_b) => {
+                let shift = rank_a.abs_diff(rank_b);
+                common += 1;
+                total_shift += shift;
+                churn.max_rank_shift = churn.max_rank_shift.max(shift);
+                if rank_a < k && rank_b >= k {
+                    churn.left_top_k += 1;
+                }
+                if rank_a >= k && rank_b < k {
+                    churn.entered_top_k += 1;
+                }
+            }
+            None => {
+                churn.removed += 1;
+                if rank_a < k {
+                    churn.left_top_k += 1;
+                }
+            }
+        }
+    }
+    churn.added = ranking_b.len() - common;
+    let ranks_a: HashSet<&str> = ranking_a.into_iter().collect();
+    churn.entered_top_k += ranking_b
+        .iter()
+        .take(k)
+        .filter(|id| !ranks_a.contains(*id))
+        .count();
+    if common > 0 {
+        churn.mean_rank_shift = total_shift as f64 / common as f64;
+    }
+    churn
+}
+
+/// All comparison metrics between two score sets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    /// Number of ids scored in both sets, over which the correlations are computed.
+    pub common: usize,
+    pub kendall_tau: Option<f64>,
+    pub spearman_rho: Option<f64>,
+    pub top_k: usize,
+    pub top_k_overlap: f64,
+    pub churn: Churn,
+}
+
+/// Compares the score set `a` to the score set `b`.
+pub fn compare(a: &[ScoreEntry], b: &[ScoreEntry], k: usize) -> Comparison {
+    let (x, y) = align(a, b);
+    Comparison {
+        common: x.len(),
+        kendall_tau: kendall_tau(&x, &y),
+        spearman_rho: spearman_rho(&x, &y),
+        top_k: k,
+        top_k_overlap: top_k_overlap(a, b, k),
+        churn: churn(a, b, k),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{churn, kendall_tau, spearman_rho, top_k_overlap, Churn};
+    use crate::ScoreEntry;
+
+    fn scores(entries: &[(&str, f64)]) -> Vec<ScoreEntry> {
+        entries
+            .iter()
+            .map(|(id, v)| ScoreEntry::new(id.to_string(), *v))
+            .collect()
+    }
+
+    /// Reference O(n^2) tau-b.
+    fn naive_tau(x: &[f64], y: &[f64]) -> f64 {
+        let (mut c, mut d, mut tx, mut ty) = (0i64, 0i64, 0i64, 0i64);
+        for i in 0..x.len() {
+            for j in i + 1..x.len() {
+                let dx = x[i] - x[j];
+                let dy = y[i] - y[j];
+                if dx == 0.0 && dy == 0.0 {
+                } else if dx == 0.0 {
+                    tx += 1;
+                } else if dy == 0.0 {
+                    ty += 1;
+                } else if (dx > 0.0) == (dy > 0.0) {
+                    c += 1;
+                } else {
+                    d += 1;
+                }
+            }
+        }
+        (c - d) as f64 / (((c + d + tx) * (c + d + ty)) as f64).sqrt()
+    }
+
+    #[test]
+    fn should_compute_kendall_tau() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(kendall_tau(&x, &x), Some(1.0));
+        assert_eq!(kendall_tau(&x, &[5.0, 4.0, 3.0, 2.0, 1.0]), Some(-1.0));
+        assert_eq!(kendall_tau(&x, &[1.0; 5]), None);
+        assert_eq!(kendall_tau(&x, &[1.0]), None);
+
+        let x = [0.3, 0.1, 0.1, 0.7, 0.5, 0.5, 0.2, 0.9, 0.1];
+        let y = [0.2, 0.2, 0.1, 0.8, 0.4, 0.6, 0.6, 0.7, 0.1];
+        let tau = kendall_tau(&x, &y).unwrap();
+        assert!((tau - naive_tau(&x, &y)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn should_compute_spearman_rho() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(spearman_rho(&x, &[10.0, 20.0, 30.0, 40.0, 50.0]), Some(1.0));
+        assert_eq!(spearman_rho(&x, &[5.0, 4.0, 3.0, 2.0, 1.0]), Some(-1.0));
+        assert_eq!(spearman_rho(&x, &[1.0; 5]), None);
+
+        // Ranks of y are [1.5, 1.5, 3, 4], d^2 = [0.25, 0.25, 0, 0]
+        let rho = spearman_rho(&[1.0, 2.0, 3.0, 4.0], &[1.0, 1.0, 2.0, 3.0]).unwrap();
+        assert!((rho - 0.9486832980505138).abs() < 1e-12);
+    }
+
+    #[test]
+    fn should_compute_overlap_and_churn() {
+        let a = scores(&[("a", 0.4), ("b", 0.3), ("c", 0.2), ("d", 0.1)]);
+        let b = scores(&[("b", 0.5), ("a", 0.3), ("e", 0.15), ("c", 0.05)]);
+        // Top 2 are {a, b} in both
+        assert_eq!(top_k_overlap(&a, &b, 2), 1.0);
+        // Top 3 are {a, b, c} and {a, b, e}
+        assert_eq!(top_k_overlap(&a, &b, 3), 0.5);
+        assert_eq!(top_k_overlap(&[], &[], 3), 1.0);
+
+        assert_eq!(
+            churn(&a, &b, 3),
+            Churn {
+                added: 1,
+                removed: 1,
+                entered_top_k: 1,
+                left_top_k: 1,
+                mean_rank_shift: 1.0,
+                max_rank_shift: 1,
+            }
+        );
+    }
+}
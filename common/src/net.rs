@@ -0,0 +1,184 @@
+use alloy::transports::http::reqwest as alloy_reqwest;
+use aws_smithy_http_client::proxy::ProxyConfig;
+use aws_smithy_http_client::tls::{rustls_provider::CryptoMode, Provider, TlsContext, TrustStore};
+use aws_smithy_http_client::{Builder, Connector};
+use aws_smithy_runtime_api::client::dns::{DnsFuture, ResolveDns, ResolveDnsError};
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Outbound networking options, applied to every HTTP client of the node: the S3 client, the
+/// chain RPC transport and the EigenDA proxy client.
+///
+/// Read from the environment by `from_env`:
+/// - `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` (or their lowercase forms):
+///   proxies, honoured by all the clients,
+/// - `OUTBOUND_PREFER_IPV6`: connect over IPv6 first on dual-stack hosts, falling back to IPv4,
+/// - `OUTBOUND_CA_BUNDLE`: path of a PEM bundle of extra root certificates, trusted on top of
+///   the system ones, e.g. for a TLS-intercepting proxy.
+#[derive(Debug, Clone, Default)]
+pub struct NetConfig {
+    pub prefer_ipv6: bool,
+    /// Extra root certificates, PEM encoded.
+    pub ca_bundle: Option<Vec<u8>>,
+}
+
+impl NetConfig {
+    pub fn from_env() -> Result<Self, Error> {
+        let prefer_ipv6 = match std::env::var("OUTBOUND_PREFER_IPV6") {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| Error::InvalidConfig(format!("OUTBOUND_PREFER_IPV6={}", value)))?,
+            Err(_) => false,
+        };
+        let ca_bundle = match std::env::var("OUTBOUND_CA_BUNDLE") {
+            Ok(path) => {
+                let bundle =
+                    std::fs::read(&path).map_err(|e| Error::CaBundle(path, e.to_string()))?;
+                Some(bundle)
+            }
+            Err(_) => None,
+        };
+        Ok(Self {
+            prefer_ipv6,
+            ca_bundle,
+        })
+    }
+
+    /// Builds a `reqwest` client, as used by the EigenDA proxy client.
+    pub fn reqwest_client(&self) -> Result<reqwest::Client, Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(bundle) = &self.ca_bundle {
+            for cert in reqwest::Certificate::from_pem_bundle(bundle)
+                .map_err(|e| Error::InvalidConfig(format!("Invalid CA bundle: {}", e)))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if self.prefer_ipv6 {
+            builder = builder.dns_resolver(Arc::new(PreferIpv6Resolver));
+        }
+        builder.build().map_err(|e| Error::Http(e.to_string()))
+    }
+
+    /// Applies the options to a builder of the client of the chain RPC transport.
+    pub fn apply_to_rpc_builder(
+        &self,
+        mut builder: alloy_reqwest::ClientBuilder,
+    ) -> Result<alloy_reqwest::ClientBuilder, Error> {
+        if let Some(bundle) = &self.ca_bundle {
+            for cert in alloy_reqwest::Certificate::from_pem_bundle(bundle)
+                .map_err(|e| Error::InvalidConfig(format!("Invalid CA bundle: {}", e)))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if self.prefer_ipv6 {
+            builder = builder.dns_resolver(PreferIpv6Resolver);
+        }
+        Ok(builder)
+    }
+
+    /// Builds the HTTP client of the S3 SDK. Unlike the SDK default one, it honours the proxy
+    /// environment variables.
+    pub fn s3_http_client(&self) -> Result<SharedHttpClient, Error> {
+        let mut trust_store = TrustStore::default();
+        if let Some(bundle) = &self.ca_bundle {
+            trust_store = trust_store.with_pem_certificate(bundle.clone());
+        }
+        let tls_context = TlsContext::builder()
+            .with_trust_store(trust_store)
+            .build()
+            .map_err(|e| Error::Http(e.to_string()))?;
+        let proxy_config = ProxyConfig::from_env();
+        let prefer_ipv6 = self.prefer_ipv6;
+        Ok(
+            Builder::new().build_with_connector_fn(move |settings, components| {
+                let mut builder = Connector::builder().proxy_config(proxy_config.clone());
+                builder.set_connector_settings(settings.cloned());
+                if let Some(components) = components {
+                    builder.set_sleep_impl(components.sleep_impl());
+                }
+                let builder = builder
+                    .tls_provider(Provider::Rustls(CryptoMode::AwsLc))
+                    .tls_context(tls_context.clone());
+                if prefer_ipv6 {
+                    builder.build_with_resolver(PreferIpv6Resolver)
+                } else {
+                    builder.build()
+                }
+            }),
+        )
+    }
+}
+
+/// Puts the IPv6 addresses first, keeping the resolver order otherwise. Connections are
+/// attempted in that order, the IPv4 ones being raced in shortly after (happy eyeballs).
+fn prefer_ipv6(addrs: &mut [SocketAddr]) {
+    addrs.sort_by_key(SocketAddr::is_ipv4);
+}
+
+/// DNS resolver returning the IPv6 addresses of the host first.
+#[derive(Debug, Clone, Copy)]
+struct PreferIpv6Resolver;
+
+impl PreferIpv6Resolver {
+    async fn lookup(name: &str) -> std::io::Result<Vec<SocketAddr>> {
+        let mut addrs: Vec<SocketAddr> = tokio::net::lookup_host((name, 0)).await?.collect();
+        prefer_ipv6(&mut addrs);
+        Ok(addrs)
+    }
+}
+
+impl reqwest::dns::Resolve for PreferIpv6Resolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs = Self::lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+impl alloy_reqwest::dns::Resolve for PreferIpv6Resolver {
+    fn resolve(&self, name: alloy_reqwest::dns::Name) -> alloy_reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs = Self::lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as alloy_reqwest::dns::Addrs)
+        })
+    }
+}
+
+impl ResolveDns for PreferIpv6Resolver {
+    fn resolve_dns<'a>(&'a self, name: &'a str) -> DnsFuture<'a> {
+        DnsFuture::new(async move {
+            let addrs = Self::lookup(name).await.map_err(ResolveDnsError::new)?;
+            Ok(addrs.into_iter().map(|addr| addr.ip()).collect())
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid network config: {0}")]
+    InvalidConfig(String),
+    #[error("Failed to read CA bundle '{0}': {1}")]
+    CaBundle(String, String),
+    #[error("Failed to build HTTP client: {0}")]
+    Http(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_put_ipv6_addresses_first() {
+        let mut addrs: Vec<SocketAddr> = ["1.1.1.1:443", "[2001:db8::1]:443", "8.8.8.8:443"]
+            .into_iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        prefer_ipv6(&mut addrs);
+        let ordered: Vec<String> = addrs.iter().map(ToString::to_string).collect();
+        assert_eq!(ordered, ["[2001:db8::1]:443", "1.1.1.1:443", "8.8.8.8:443"]);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{ScoreEntry, TrustEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+
+/// How the duplicate entries of an edge (or of a seed peer) are merged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// The last entry wins, as in `ComputeRunner::update_trust_map`.
+    #[default]
+    Last,
+    /// The values of the entries are added up.
+    Sum,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last" => Ok(Self::Last),
+            "sum" => Ok(Self::Sum),
+            _ => Err(format!("Unknown duplicate policy: {}", s)),
+        }
+    }
+}
+
+impl Display for DuplicatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Last => write!(f, "last"),
+            Self::Sum => write!(f, "sum"),
+        }
+    }
+}
+
+/// Merges the entries by key with the policy, sorted by key. Zero values are dropped, a zero
+/// weight removing the entry as in the updates. Negative values are rejected unless
+/// `allow_negative`.
+fn merge<K: Ord>(
+    entries: impl IntoIterator<Item = (K, f64)>,
+    policy: DuplicatePolicy,
+    allow_negative: bool,
+) -> Result<Vec<(K, f64)>, Error> {
+    let mut merged: BTreeMap<K, f64> = BTreeMap::new();
+    for (key, value) in entries {
+        if !value.is_finite() || (value < 0.0 && !allow_negative) {
+            return Err(Error::InvalidValue(value));
+        }
+        let merged_value = merged.entry(key).or_default();
+        match policy {
+            DuplicatePolicy::Last => *merged_value = value,
+            DuplicatePolicy::Sum => *merged_value += value,
+        }
+    }
+    Ok(merged
+        .into_iter()
+        .filter(|(_, value)| *value != 0.0)
+        .collect())
+}
+
+/// Canonical form of the trust entries: ids stripped of surrounding whitespace, one entry per
+/// edge (merged with the policy) and the edges sorted by (from, to). Datasets with the same
+/// edges then have the same bytes once written, and the same content id.
+pub fn canonicalize_trust(
+    entries: impl IntoIterator<Item = TrustEntry>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<TrustEntry>, Error> {
+    canonicalize(entries, policy, false)
+}
+
+/// Canonical form of trust entries with negative (distrust) entries, for EigenTrust with
+/// negative trust. With the `Sum` policy, the trust and the distrust of an edge offset each
+/// other.
+pub fn canonicalize_signed_trust(
+    entries: impl IntoIterator<Item = TrustEntry>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<TrustEntry>, Error> {
+    canonicalize(entries, policy, true)
+}
+
+fn canonicalize(
+    entries: impl IntoIterator<Item = TrustEntry>,
+    policy: DuplicatePolicy,
+    allow_negative: bool,
+) -> Result<Vec<TrustEntry>, Error> {
+    let entries = entries.into_iter().map(|entry| {
+        let key = (
+            entry.from().trim().to_string(),
+            entry.to().trim().to_string(),
+        );
+        (key, *entry.value())
+    });
+    Ok(merge(entries, policy, allow_negative)?
+        .into_iter()
+        .map(|((from, to), value)| TrustEntry::new(from, to, value))
+        .collect())
+}
+
+/// Canonical form of the seed entries: ids stripped of surrounding whitespace, one entry per
+/// peer (merged with the policy) and the peers sorted by id.
+pub fn canonicalize_seed(
+    entries: impl IntoIterator<Item = ScoreEntry>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<ScoreEntry>, Error> {
+    let entries = entries
+        .into_iter()
+        .map(|entry| (entry.id().trim().to_string(), *entry.value()));
+    Ok(merge(entries, policy, false)?
+        .into_iter()
+        .map(|(id, value)| ScoreEntry::new(id, value))
+        .collect())
+}
+
+/// Writes the trust entries as a CSV (`i,j,v`), values in their shortest form.
+pub fn write_trust_csv<W: Write>(writer: W, entries: &[TrustEntry]) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["i", "j", "v"])?;
+    for entry in entries {
+        wtr.write_record([entry.from(), entry.to(), &entry.value().to_string()])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the seed entries as a CSV (`i,v`), values in their shortest form.
+pub fn write_seed_csv<W: Write>(writer: W, entries: &[ScoreEntry]) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["i", "v"])?;
+    for entry in entries {
+        wtr.write_record([entry.id(), &entry.value().to_string()])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid value: {0}")]
+    InvalidValue(f64),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TrustEntryReader;
+
+    #[test]
+    fn should_canonicalize_trust() {
+        let csv = "i,j,v\n b ,a, 1.50\na,c,1\na, b,2\nb,a,0.5\nc,a,1\nc,a,0\n";
+        let entries: Vec<TrustEntry> = TrustEntryReader::trimmed(csv.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        let last = canonicalize_trust(entries.clone(), DuplicatePolicy::Last).unwrap();
+        write_trust_csv(&mut bytes, &last).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "i,j,v\na,b,2\na,c,1\nb,a,0.5\n"
+        );
+
+        let sum = canonicalize_trust(entries.clone(), DuplicatePolicy::Sum).unwrap();
+        let sum: Vec<(&str, &str, f64)> = sum
+            .iter()
+            .map(|e| (e.from().as_str(), e.to().as_str(), *e.value()))
+            .collect();
+        assert_eq!(
+            sum,
+            [
+                ("a", "b", 2.0),
+                ("a", "c", 1.0),
+                ("b", "a", 2.0),
+                ("c", "a", 1.0)
+            ]
+        );
+
+        // The order of the input doesn't matter
+        let reversed = canonicalize_trust(entries.into_iter().rev(), DuplicatePolicy::Sum).unwrap();
+        assert_eq!(
+            reversed.iter().map(|e| *e.value()).collect::<Vec<_>>(),
+            [2.0, 1.0, 2.0, 1.0]
+        );
+
+        let invalid = [TrustEntry::new("a".into(), "b".into(), -1.0)];
+        assert!(canonicalize_trust(invalid.clone(), DuplicatePolicy::Last).is_err());
+
+        // Distrust is kept with negative trust, offsetting the trust of the same edge
+        let signed = canonicalize_signed_trust(invalid, DuplicatePolicy::Last).unwrap();
+        assert_eq!(*signed[0].value(), -1.0);
+        let offset = [
+            TrustEntry::new("a".into(), "b".into(), 1.0),
+            TrustEntry::new("a".into(), "b".into(), -1.0),
+        ];
+        assert!(canonicalize_signed_trust(offset, DuplicatePolicy::Sum)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn should_canonicalize_seed() {
+        let entries = [("b", 0.25), ("a ", 0.5), ("b", 0.25)]
+            .map(|(id, value)| ScoreEntry::new(id.into(), value));
+        let mut bytes = Vec::new();
+        let sum = canonicalize_seed(entries, DuplicatePolicy::Sum).unwrap();
+        write_seed_csv(&mut bytes, &sum).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), "i,v\na,0.5\nb,0.5\n");
+    }
+}
@@ -0,0 +1,363 @@
+use crate::hashing::{self, HashAlgorithm};
+use crate::ranking::RankingMode;
+use crate::scheme::{self, Precision, Scheme};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// Algorithm id of EigenTrust.
+pub const ALGO_ID_EIGEN_TRUST: u32 = 1;
+/// Algorithm id of SybilRank.
+pub const ALGO_ID_SYBIL_RANK: u32 = 2;
+/// Algorithm id of EigenTrust with negative (distrust) trust entries.
+pub const ALGO_ID_NEGATIVE_EIGEN_TRUST: u32 = 3;
+
+/// Typed parameters of a single algorithm.
+///
+/// Parameters are carried in `JobDescription::params` as a string map,
+/// this trait describes how the map is parsed into (and built from) typed structs.
+pub trait ParamsSchema: Serialize + DeserializeOwned {
+    /// Keys accepted by the algorithm.
+    const KEYS: &'static [&'static str];
+
+    /// Parses the params from the string map.
+    /// In `strict` mode, unknown keys (e.g. typos like "aplha") are rejected.
+    fn from_map(params: &HashMap<String, String>, strict: bool) -> Result<Self, Error> {
+        if strict {
+            if let Some(key) = params.keys().find(|k| !Self::KEYS.contains(&k.as_str())) {
+                return Err(Error::UnknownParam(key.clone()));
+            }
+        }
+        let value = serde_json::to_value(params).map_err(Error::Serde)?;
+        serde_json::from_value(value).map_err(Error::Serde)
+    }
+
+    /// Converts the params back to the string map, skipping unset values.
+    fn to_map(&self) -> HashMap<String, String> {
+        let mut map = HashMap::new();
+        if let Ok(serde_json::Value::Object(obj)) = serde_json::to_value(self) {
+            for (k, v) in obj {
+                match v {
+                    serde_json::Value::Null => {}
+                    serde_json::Value::String(s) => {
+                        map.insert(k, s);
+                    }
+                    v => {
+                        map.insert(k, v.to_string());
+                    }
+                }
+            }
+        }
+        map
+    }
+}
+
+/// Deserializes an optional value from its string representation.
+fn opt_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    s.map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// EigenTrust parameters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EigenTrustParams {
+    /// Weight of the pre-trust (seed) vector, `algos::et::PRE_TRUST_WEIGHT` (0.25) if unset.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub alpha: Option<f64>,
+    /// Convergence threshold, `algos::et::DELTA` (1e-6) if unset.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub delta: Option<f64>,
+    /// Maximum number of iterations.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub max_iter: Option<u32>,
+    /// Emits the scores in ranking order, with a rank column.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub ranking: Option<RankingMode>,
+    /// Also produces the extended scores artifact, with per-score metadata.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub metadata: Option<bool>,
+    /// Precision of the committed scores, f32 if unset.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub precision: Option<Precision>,
+    /// Hash function of the scores tree, keccak256 if unset. Only f64 scores can be committed
+    /// with another function, the f32 ones being verified on-chain.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub hash: Option<HashAlgorithm>,
+}
+
+impl ParamsSchema for EigenTrustParams {
+    const KEYS: &'static [&'static str] = &[
+        "alpha",
+        "delta",
+        "max_iter",
+        "ranking",
+        "metadata",
+        "precision",
+        "hash",
+    ];
+}
+
+/// Parameters of EigenTrust with negative trust entries: the EigenTrust ones, and the weight
+/// of the distrust.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NegativeEigenTrustParams {
+    #[serde(flatten)]
+    pub et: EigenTrustParams,
+    /// Weight of the distrust subtracted from the scores,
+    /// `algos::et::DISTRUST_WEIGHT` (1.0) if unset.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub distrust_weight: Option<f64>,
+}
+
+impl ParamsSchema for NegativeEigenTrustParams {
+    const KEYS: &'static [&'static str] = &[
+        "alpha",
+        "delta",
+        "max_iter",
+        "ranking",
+        "metadata",
+        "precision",
+        "hash",
+        "distrust_weight",
+    ];
+}
+
+/// SybilRank parameters.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SybilRankParams {
+    /// Number of random walk steps.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub walk_length: Option<u32>,
+    /// Emits the scores in ranking order, with a rank column.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub ranking: Option<RankingMode>,
+    /// Also produces the extended scores artifact, with per-score metadata.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub metadata: Option<bool>,
+    /// Precision of the committed scores, f32 if unset.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub precision: Option<Precision>,
+    /// Hash function of the scores tree, keccak256 if unset. Only f64 scores can be committed
+    /// with another function, the f32 ones being verified on-chain.
+    #[serde(default, deserialize_with = "opt_from_str")]
+    pub hash: Option<HashAlgorithm>,
+}
+
+impl ParamsSchema for SybilRankParams {
+    const KEYS: &'static [&'static str] =
+        &["walk_length", "ranking", "metadata", "precision", "hash"];
+}
+
+/// Parameters of a job, typed according to its algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlgoParams {
+    EigenTrust(EigenTrustParams),
+    SybilRank(SybilRankParams),
+    NegativeEigenTrust(NegativeEigenTrustParams),
+}
+
+impl AlgoParams {
+    /// Parses the params map of a job with the given algorithm id.
+    /// Params selecting a hash function their precision can't be committed with are rejected.
+    pub fn parse(
+        algo_id: u32,
+        params: &HashMap<String, String>,
+        strict: bool,
+    ) -> Result<Self, Error> {
+        let params = match algo_id {
+            ALGO_ID_EIGEN_TRUST => Self::EigenTrust(EigenTrustParams::from_map(params, strict)?),
+            ALGO_ID_SYBIL_RANK => Self::SybilRank(SybilRankParams::from_map(params, strict)?),
+            ALGO_ID_NEGATIVE_EIGEN_TRUST => {
+                Self::NegativeEigenTrust(NegativeEigenTrustParams::from_map(params, strict)?)
+            }
+            _ => return Err(Error::UnsupportedAlgorithm(algo_id)),
+        };
+        params.scheme().map_err(Error::Hash)?;
+        Ok(params)
+    }
+
+    /// Returns the algorithm id of the params.
+    pub fn algo_id(&self) -> u32 {
+        match self {
+            Self::EigenTrust(_) => ALGO_ID_EIGEN_TRUST,
+            Self::SybilRank(_) => ALGO_ID_SYBIL_RANK,
+            Self::NegativeEigenTrust(_) => ALGO_ID_NEGATIVE_EIGEN_TRUST,
+        }
+    }
+
+    /// Whether the algorithm accepts negative trust entries. The other algorithms reject them.
+    pub fn accepts_negative_trust(&self) -> bool {
+        matches!(self, Self::NegativeEigenTrust(_))
+    }
+
+    /// Returns the ranking mode of the scores, if the job asks for ranked output.
+    pub fn ranking(&self) -> Option<RankingMode> {
+        match self {
+            Self::EigenTrust(p) => p.ranking,
+            Self::SybilRank(p) => p.ranking,
+            Self::NegativeEigenTrust(p) => p.et.ranking,
+        }
+    }
+
+    /// Checks whether the job asks for the extended scores artifact.
+    pub fn metadata(&self) -> bool {
+        match self {
+            Self::EigenTrust(p) => p.metadata,
+            Self::SybilRank(p) => p.metadata,
+            Self::NegativeEigenTrust(p) => p.et.metadata,
+        }
+        .unwrap_or(false)
+    }
+
+    /// Returns the precision of the committed scores.
+    pub fn precision(&self) -> Precision {
+        match self {
+            Self::EigenTrust(p) => p.precision,
+            Self::SybilRank(p) => p.precision,
+            Self::NegativeEigenTrust(p) => p.et.precision,
+        }
+        .unwrap_or_default()
+    }
+
+    /// Returns the hash function of the scores tree.
+    pub fn hash(&self) -> HashAlgorithm {
+        match self {
+            Self::EigenTrust(p) => p.hash,
+            Self::SybilRank(p) => p.hash,
+            Self::NegativeEigenTrust(p) => p.et.hash,
+        }
+        .unwrap_or_default()
+    }
+
+    /// Returns the scheme the scores of the job are committed with.
+    pub fn scheme(&self) -> Result<&'static Scheme, hashing::Error> {
+        scheme::select(self.precision(), self.hash())
+    }
+
+    /// Converts the params to the string map stored in `JobDescription`.
+    pub fn to_map(&self) -> HashMap<String, String> {
+        match self {
+            Self::EigenTrust(p) => p.to_map(),
+            Self::SybilRank(p) => p.to_map(),
+            Self::NegativeEigenTrust(p) => p.to_map(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unsupported algorithm ID: {0}")]
+    UnsupportedAlgorithm(u32),
+    #[error("Unknown parameter: {0}")]
+    UnknownParam(String),
+    #[error("Invalid parameter value: {0}")]
+    Serde(serde_json::Error),
+    #[error("Invalid hash function: {0}")]
+    Hash(hashing::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_et_params() {
+        let params = map(&[("alpha", "0.5"), ("max_iter", "10"), ("ranking", "dense")]);
+        let parsed = AlgoParams::parse(ALGO_ID_EIGEN_TRUST, &params, true).unwrap();
+        assert_eq!(
+            parsed,
+            AlgoParams::EigenTrust(EigenTrustParams {
+                alpha: Some(0.5),
+                delta: None,
+                max_iter: Some(10),
+                ranking: Some(RankingMode::Dense),
+                metadata: None,
+                precision: None,
+                hash: None,
+            })
+        );
+        assert_eq!(parsed.to_map(), params);
+        assert_eq!(parsed.scheme().unwrap().version, scheme::SCHEME_V1);
+
+        let params = map(&[("walk_length", "5"), ("precision", "f64")]);
+        let parsed = AlgoParams::parse(ALGO_ID_SYBIL_RANK, &params, true).unwrap();
+        assert_eq!(parsed.scheme().unwrap().version, scheme::SCHEME_V2);
+        assert_eq!(parsed.to_map(), params);
+    }
+
+    #[test]
+    fn should_parse_negative_et_params() {
+        let params = map(&[("alpha", "0.5"), ("distrust_weight", "0.75")]);
+        let parsed = AlgoParams::parse(ALGO_ID_NEGATIVE_EIGEN_TRUST, &params, true).unwrap();
+        assert_eq!(
+            parsed,
+            AlgoParams::NegativeEigenTrust(NegativeEigenTrustParams {
+                et: EigenTrustParams {
+                    alpha: Some(0.5),
+                    ..Default::default()
+                },
+                distrust_weight: Some(0.75),
+            })
+        );
+        assert!(parsed.accepts_negative_trust());
+        assert_eq!(parsed.algo_id(), ALGO_ID_NEGATIVE_EIGEN_TRUST);
+        assert_eq!(parsed.to_map(), params);
+
+        // The distrust weight is specific to the negative variant
+        assert!(matches!(
+            AlgoParams::parse(ALGO_ID_EIGEN_TRUST, &params, true),
+            Err(Error::UnknownParam(k)) if k == "distrust_weight"
+        ));
+    }
+
+    #[test]
+    fn should_check_the_hash_of_the_precision() {
+        let params = map(&[("precision", "f64"), ("hash", "blake3")]);
+        let parsed = AlgoParams::parse(ALGO_ID_EIGEN_TRUST, &params, true).unwrap();
+        assert_eq!(parsed.scheme().unwrap().version, scheme::SCHEME_V3);
+        assert_eq!(parsed.to_map(), params);
+
+        // f32 scores are verified on-chain, with keccak256 only
+        let params = map(&[("hash", "blake3")]);
+        assert!(matches!(
+            AlgoParams::parse(ALGO_ID_SYBIL_RANK, &params, true),
+            Err(Error::Hash(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_unknown_params_in_strict_mode() {
+        let params = map(&[("aplha", "0.5")]);
+        assert!(matches!(
+            AlgoParams::parse(ALGO_ID_EIGEN_TRUST, &params, true),
+            Err(Error::UnknownParam(k)) if k == "aplha"
+        ));
+        let parsed = AlgoParams::parse(ALGO_ID_EIGEN_TRUST, &params, false).unwrap();
+        assert_eq!(parsed, AlgoParams::EigenTrust(EigenTrustParams::default()));
+    }
+
+    #[test]
+    fn should_reject_invalid_values() {
+        let params = map(&[("walk_length", "ten")]);
+        assert!(matches!(
+            AlgoParams::parse(ALGO_ID_SYBIL_RANK, &params, true),
+            Err(Error::Serde(_))
+        ));
+        assert!(matches!(
+            AlgoParams::parse(99, &params, true),
+            Err(Error::UnsupportedAlgorithm(99))
+        ));
+    }
+}
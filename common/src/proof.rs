@@ -0,0 +1,170 @@
+use crate::merkle::{self, Hash};
+use crate::scheme::{self, SCHEME_V1};
+use crate::score_meta::ScoreMetadata;
+use serde::{Deserialize, Serialize};
+use sha3::Keccak256;
+
+/// Inclusion proof of a user's score in the commitment of a compute, as served by the
+/// `/score-proof` endpoint of the nodes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreProof {
+    /// The compute ID
+    pub compute_id: String,
+    /// The user ID
+    pub user_id: String,
+    /// The user's score value
+    pub score: f64,
+    /// The index of the score in the scores tree
+    pub score_index: usize,
+    /// Merkle path for the score in the scores tree (leaf to root)
+    pub scores_tree_path: Vec<Hash>,
+    /// The scores tree root (commitment)
+    pub scores_tree_root: Hash,
+    /// The index of this job's commitment in the meta tree
+    pub meta_index: usize,
+    /// Merkle path for the commitment in the meta tree (leaf to root)
+    pub meta_tree_path: Vec<Hash>,
+    /// The meta tree root (final commitment)
+    pub meta_tree_root: Hash,
+    /// Version of the scheme the commitment of the user's job was produced with. Proofs from
+    /// older servers don't carry it, they all use the first one.
+    #[serde(default = "default_scheme_version")]
+    pub scheme_version: String,
+    /// Rank, percentile and flags of the score, when the job produced the extended artifact.
+    /// Not covered by the proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ScoreMetadata>,
+}
+
+fn default_scheme_version() -> String {
+    SCHEME_V1.to_string()
+}
+
+/// Checks a score proof against the meta commitment posted on-chain for its compute.
+///
+/// The leaf of the score is hashed with the encoding and the hash function of the scheme of
+/// the proof, then walked up the scores tree to its root, itself walked up the Keccak256 meta
+/// tree to the meta commitment. Unlike `verifyScoreProof` of the manager contract, proofs of
+/// every scheme are verified, f64 scores and BLAKE3 scores trees included.
+pub fn verify_score_proof(
+    proof: &ScoreProof,
+    expected_onchain_commitment: &Hash,
+) -> Result<(), Error> {
+    let scheme = scheme::find(&proof.scheme_version)
+        .ok_or_else(|| Error::UnknownScheme(proof.scheme_version.clone()))?;
+    if !scheme.hash.verify_path(
+        &scheme.hash_leaf(proof.score),
+        proof.score_index as u64,
+        &proof.scores_tree_path,
+        &proof.scores_tree_root,
+    ) {
+        return Err(Error::InvalidScoresPath);
+    }
+    if !merkle::verify_path::<Keccak256>(
+        &proof.scores_tree_root,
+        proof.meta_index as u64,
+        &proof.meta_tree_path,
+        &proof.meta_tree_root,
+    ) {
+        return Err(Error::InvalidMetaPath);
+    }
+    if proof.meta_tree_root != *expected_onchain_commitment {
+        return Err(Error::CommitmentMismatch {
+            expected: expected_onchain_commitment.clone(),
+            got: proof.meta_tree_root.clone(),
+        });
+    }
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("Unknown scheme '{0}'")]
+    UnknownScheme(String),
+    #[error("Score is not in the scores tree")]
+    InvalidScoresPath,
+    #[error("Scores tree root is not in the meta tree")]
+    InvalidMetaPath,
+    #[error("Meta tree root {got} is not the on-chain commitment {expected}")]
+    CommitmentMismatch { expected: Hash, got: Hash },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hashing::HashAlgorithm;
+    use crate::scheme::SCHEME_V3;
+
+    /// Proof of the second score of the second of two sub-jobs.
+    fn proof(scheme_version: &str) -> ScoreProof {
+        let scheme = scheme::find(scheme_version).unwrap();
+        let scores = [0.125, 0.25, 0.5, 0.125];
+        let scores_tree = scheme
+            .hash
+            .dense_tree(scores.iter().map(|s| scheme.hash_leaf(*s)).collect())
+            .unwrap();
+        let scores_tree_root = scores_tree.root().unwrap();
+        let other_root = scheme.hash_leaf(1.0);
+        let meta_tree = HashAlgorithm::Keccak256
+            .dense_tree(vec![other_root, scores_tree_root.clone()])
+            .unwrap();
+        ScoreProof {
+            compute_id: "1".to_string(),
+            user_id: "bob".to_string(),
+            score: scores[1],
+            score_index: 1,
+            scores_tree_path: scores_tree.generate_path(1).unwrap(),
+            scores_tree_root,
+            meta_index: 1,
+            meta_tree_path: meta_tree.generate_path(1).unwrap(),
+            meta_tree_root: meta_tree.root().unwrap(),
+            scheme_version: scheme_version.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn should_verify_score_proofs() {
+        for scheme_version in [SCHEME_V1, SCHEME_V3] {
+            let proof = proof(scheme_version);
+            let commitment = proof.meta_tree_root.clone();
+            assert_eq!(verify_score_proof(&proof, &commitment), Ok(()));
+
+            // Served as JSON, without the scheme version by older servers
+            let mut json = serde_json::to_value(&proof).unwrap();
+            let parsed: ScoreProof = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(parsed, proof);
+            json.as_object_mut().unwrap().remove("scheme_version");
+            let parsed: ScoreProof = serde_json::from_value(json).unwrap();
+            assert_eq!(parsed.scheme_version, SCHEME_V1);
+
+            let mut tampered = proof.clone();
+            tampered.score = 0.5;
+            assert_eq!(
+                verify_score_proof(&tampered, &commitment),
+                Err(Error::InvalidScoresPath)
+            );
+            let mut tampered = proof.clone();
+            tampered.meta_index = 0;
+            assert_eq!(
+                verify_score_proof(&tampered, &commitment),
+                Err(Error::InvalidMetaPath)
+            );
+            let other = Hash::from_bytes([1; 32]);
+            assert_eq!(
+                verify_score_proof(&proof, &other),
+                Err(Error::CommitmentMismatch {
+                    expected: other.clone(),
+                    got: commitment
+                })
+            );
+        }
+
+        let mut unknown = proof(SCHEME_V1);
+        unknown.scheme_version = "v0".to_string();
+        assert_eq!(
+            verify_score_proof(&unknown, &unknown.meta_tree_root.clone()),
+            Err(Error::UnknownScheme("v0".to_string()))
+        );
+    }
+}
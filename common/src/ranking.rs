@@ -0,0 +1,207 @@
+use crate::scheme::Precision;
+use crate::ScoreEntry;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+
+/// How ranks are assigned to tied scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingMode {
+    /// Tied scores share a rank, the next score gets the following rank (1, 2, 2, 3).
+    Dense,
+    /// Tied scores share a rank, the next score skips the tied ranks (1, 2, 2, 4).
+    Competition,
+}
+
+impl FromStr for RankingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dense" => Ok(Self::Dense),
+            "competition" => Ok(Self::Competition),
+            _ => Err(format!("Unknown ranking mode: {}", s)),
+        }
+    }
+}
+
+impl Display for RankingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dense => write!(f, "dense"),
+            Self::Competition => write!(f, "competition"),
+        }
+    }
+}
+
+/// Ranking order: descending score, ties ordered by ascending id so the order is deterministic.
+pub fn rank_order(a: (&str, f64), b: (&str, f64)) -> Ordering {
+    b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0))
+}
+
+/// Sorts the scores in ranking order.
+pub fn sort_scores(scores: &mut [ScoreEntry]) {
+    scores.sort_by(|a, b| rank_order((a.id(), *a.value()), (b.id(), *b.value())));
+}
+
+/// Score ordered by its position in the ranking order, the last ranked being the greatest.
+struct Ranked(ScoreEntry);
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        rank_order(
+            (self.0.id(), *self.0.value()),
+            (other.0.id(), *other.0.value()),
+        )
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// Returns the scores at `offset..offset + limit` in ranking order, and the total number of
+/// scores. The scores are streamed, at most `offset + limit` of them are kept in memory.
+pub fn top_scores<E>(
+    scores: impl IntoIterator<Item = Result<ScoreEntry, E>>,
+    offset: usize,
+    limit: usize,
+) -> Result<(Vec<ScoreEntry>, usize), E> {
+    let keep = offset.saturating_add(limit);
+    let mut heap = BinaryHeap::new();
+    let mut total = 0;
+    for score in scores {
+        let score = score?;
+        total += 1;
+        if keep == 0 {
+            continue;
+        }
+        heap.push(Ranked(score));
+        if heap.len() > keep {
+            // Drops the last ranked
+            heap.pop();
+        }
+    }
+    let page = heap
+        .into_sorted_vec()
+        .into_iter()
+        .skip(offset)
+        .map(|Ranked(score)| score)
+        .collect();
+    Ok((page, total))
+}
+
+/// Returns the 1-based rank of every score of a slice sorted in ranking order.
+pub fn ranks(sorted: &[ScoreEntry], mode: RankingMode) -> Vec<u64> {
+    let mut ranks = Vec::with_capacity(sorted.len());
+    let mut rank = 0;
+    for (i, score) in sorted.iter().enumerate() {
+        let tied = i > 0 && sorted[i - 1].value() == score.value();
+        if !tied {
+            rank = match mode {
+                RankingMode::Dense => rank + 1,
+                RankingMode::Competition => i as u64 + 1,
+            };
+        }
+        ranks.push(rank);
+    }
+    ranks
+}
+
+/// Writes the scores, sorted in ranking order, as a CSV with an extra rank column (`i,v,r`),
+/// the values formatted with the precision of the job. These are the bytes committed as the
+/// scores artifact of ranked jobs.
+pub fn write_ranked_csv<W: Write>(
+    writer: W,
+    sorted: &[ScoreEntry],
+    mode: RankingMode,
+    precision: Precision,
+) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["i", "v", "r"])?;
+    for (score, rank) in sorted.iter().zip(ranks(sorted, mode)) {
+        wtr.write_record([
+            score.id().as_str(),
+            precision.format(*score.value()).as_str(),
+            rank.to_string().as_str(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ranks, sort_scores, top_scores, write_ranked_csv, RankingMode};
+    use crate::scheme::Precision;
+    use crate::{parse_score_entries_from_file, ScoreEntry};
+
+    #[test]
+    fn should_rank_ties_deterministically() {
+        let mut scores: Vec<ScoreEntry> = [("d", 0.1), ("c", 0.3), ("a", 0.3), ("b", 0.5)]
+            .into_iter()
+            .map(|(id, v)| ScoreEntry::new(id.to_string(), v))
+            .collect();
+        sort_scores(&mut scores);
+        let ids: Vec<&str> = scores.iter().map(|s| s.id().as_str()).collect();
+        assert_eq!(ids, ["b", "a", "c", "d"]);
+        assert_eq!(ranks(&scores, RankingMode::Dense), [1, 2, 2, 3]);
+        assert_eq!(ranks(&scores, RankingMode::Competition), [1, 2, 2, 4]);
+
+        let mut bytes = Vec::new();
+        write_ranked_csv(
+            &mut bytes,
+            &scores,
+            RankingMode::Competition,
+            Precision::F64,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "i,v,r\nb,0.5,1\na,0.3,2\nc,0.3,2\nd,0.1,4\n"
+        );
+
+        // Ranked files are still readable as plain score files
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ranked-scores.csv");
+        std::fs::write(&path, bytes).unwrap();
+        let parsed = parse_score_entries_from_file(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(parsed, scores);
+    }
+
+    #[test]
+    fn should_page_top_scores() {
+        let scores: Vec<ScoreEntry> = [("d", 0.1), ("c", 0.3), ("e", 0.2), ("a", 0.3), ("b", 0.5)]
+            .into_iter()
+            .map(|(id, v)| ScoreEntry::new(id.to_string(), v))
+            .collect();
+        let page = |offset, limit| {
+            let (page, total) =
+                top_scores(scores.iter().cloned().map(Ok::<_, ()>), offset, limit).unwrap();
+            assert_eq!(total, 5);
+            page.iter().map(|s| s.id().clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(page(0, 3), ["b", "a", "c"]);
+        assert_eq!(page(2, 2), ["c", "e"]);
+        assert_eq!(page(4, 10), ["d"]);
+        assert!(page(5, 10).is_empty());
+        assert!(page(0, 0).is_empty());
+
+        let res = top_scores([Ok(scores[0].clone()), Err("bad record")], 0, 10);
+        assert_eq!(res, Err("bad record"));
+    }
+}
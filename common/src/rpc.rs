@@ -0,0 +1,372 @@
+use crate::net::{self, NetConfig};
+use alloy::providers::Provider;
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::types::{Filter, Log};
+use alloy::transports::http::reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION},
+    Client, Url,
+};
+use alloy::transports::http::Http;
+use alloy::transports::layers::FallbackService;
+use alloy::transports::TransportResult;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Largest block range of a single `eth_getLogs` request, when `CHAIN_RPC_LOGS_MAX_BLOCKS`
+/// isn't set.
+pub const DEFAULT_LOGS_MAX_BLOCKS: u64 = 10_000;
+/// Attempts of a batch failing for another reason than its range, before giving up.
+const LOGS_MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry of a failed batch, doubled at every retry.
+const LOGS_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Connection options of the chain RPC provider.
+///
+/// Read from the environment by `from_env`:
+/// - `CHAIN_RPC_URL`: comma separated list of URLs, the first one being the primary,
+/// - `CHAIN_RPC_HEADERS`: semicolon separated list of `Name: value` headers,
+/// - `CHAIN_RPC_BEARER_TOKEN`: bearer token sent in the `Authorization` header,
+/// - `CHAIN_RPC_BASIC_AUTH`: `user:password` credentials sent in the `Authorization` header,
+/// - `CHAIN_RPC_TIMEOUT_SECS`: request timeout,
+/// - the outbound networking options of `NetConfig`.
+#[derive(Debug, Clone, Default)]
+pub struct RpcConfig {
+    pub urls: Vec<Url>,
+    pub headers: Vec<(String, String)>,
+    pub bearer_token: Option<String>,
+    pub basic_auth: Option<(String, String)>,
+    pub timeout: Option<Duration>,
+    pub net: NetConfig,
+}
+
+impl RpcConfig {
+    /// Parses a comma separated list of RPC URLs.
+    pub fn new(urls: &str) -> Result<Self, Error> {
+        let urls = urls
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(|u| Url::parse(u).map_err(|e| Error::InvalidUrl(u.to_string(), e.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if urls.is_empty() {
+            return Err(Error::NoUrls);
+        }
+        Ok(Self {
+            urls,
+            ..Default::default()
+        })
+    }
+
+    /// Creates the config from the given URL list, reading the remaining options from the
+    /// environment.
+    pub fn from_env(urls: &str) -> Result<Self, Error> {
+        let mut config = Self::new(urls)?;
+        if let Ok(headers) = std::env::var("CHAIN_RPC_HEADERS") {
+            config.headers = parse_headers(&headers)?;
+        }
+        config.bearer_token = std::env::var("CHAIN_RPC_BEARER_TOKEN").ok();
+        if let Ok(basic_auth) = std::env::var("CHAIN_RPC_BASIC_AUTH") {
+            let (user, password) = basic_auth.split_once(':').ok_or(Error::InvalidBasicAuth)?;
+            config.basic_auth = Some((user.to_string(), password.to_string()));
+        }
+        if let Ok(timeout) = std::env::var("CHAIN_RPC_TIMEOUT_SECS") {
+            let secs = timeout
+                .parse()
+                .map_err(|_| Error::InvalidTimeout(timeout.clone()))?;
+            config.timeout = Some(Duration::from_secs(secs));
+        }
+        config.net = NetConfig::from_env()?;
+        Ok(config)
+    }
+
+    /// Returns the headers sent with every request, including the authorization header.
+    fn header_map(&self) -> Result<HeaderMap, Error> {
+        let mut map = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| Error::InvalidHeader(name.clone()))?;
+            let value =
+                HeaderValue::from_str(value).map_err(|_| Error::InvalidHeader(name.to_string()))?;
+            map.insert(name, value);
+        }
+        let authorization = match (&self.bearer_token, &self.basic_auth) {
+            (Some(token), _) => Some(format!("Bearer {}", token)),
+            (None, Some((user, password))) => Some(format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", user, password))
+            )),
+            (None, None) => None,
+        };
+        if let Some(authorization) = authorization {
+            let mut value = HeaderValue::from_str(&authorization)
+                .map_err(|_| Error::InvalidHeader(AUTHORIZATION.to_string()))?;
+            value.set_sensitive(true);
+            map.insert(AUTHORIZATION, value);
+        }
+        Ok(map)
+    }
+
+    /// Builds the RPC client.
+    /// With more than one URL, requests go to the healthiest provider (ranked by success rate
+    /// and latency), falling back to the others on failure.
+    pub fn client(&self) -> Result<RpcClient, Error> {
+        let mut builder = Client::builder().default_headers(self.header_map()?);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let http_client = self
+            .net
+            .apply_to_rpc_builder(builder)?
+            .build()
+            .map_err(|e| Error::Http(e.to_string()))?;
+
+        match self.urls.as_slice() {
+            [] => Err(Error::NoUrls),
+            [url] => Ok(RpcClient::new_http_with_client(http_client, url.clone())),
+            urls => {
+                let transports: Vec<_> = urls
+                    .iter()
+                    .map(|url| Http::with_client(http_client.clone(), url.clone()))
+                    .collect();
+                // Query a single provider at a time, rotating to the next best one on failure
+                Ok(RpcClient::new(FallbackService::new(transports, 1), false))
+            }
+        }
+    }
+}
+
+/// Checks whether the provider rejected a `eth_getLogs` request because of its block range or
+/// number of results (e.g. "query returned more than 10000 results"), rather than a transient
+/// failure. Rate limits are transient.
+pub fn is_log_range_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    if ["rate limit", "too many requests", "429"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+    {
+        return false;
+    }
+    [
+        "query returned more than",
+        "block range",
+        "range is too large",
+        "range too large",
+        "too many blocks",
+        "too many results",
+        "response size",
+        "exceed",
+        "limit",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+/// Pulls the logs of a block range in batches, for providers that cap the range (or the
+/// number of results) of `eth_getLogs`. A batch rejected for its size is split in half, and
+/// the batch size grows back after every successful batch. Other failures are retried with
+/// exponential backoff.
+#[derive(Debug, Clone)]
+pub struct LogFetcher {
+    max_blocks: u64,
+    batch_blocks: u64,
+    retry_delay: Duration,
+}
+
+impl LogFetcher {
+    pub fn new(max_blocks: u64) -> Self {
+        let max_blocks = max_blocks.max(1);
+        Self {
+            max_blocks,
+            batch_blocks: max_blocks,
+            retry_delay: LOGS_RETRY_DELAY,
+        }
+    }
+
+    /// Reads the largest block range of a request from `CHAIN_RPC_LOGS_MAX_BLOCKS`.
+    pub fn from_env() -> Result<Self, Error> {
+        let max_blocks = match std::env::var("CHAIN_RPC_LOGS_MAX_BLOCKS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| Error::InvalidLogsMaxBlocks(value.clone()))?,
+            Err(_) => DEFAULT_LOGS_MAX_BLOCKS,
+        };
+        Ok(Self::new(max_blocks))
+    }
+
+    /// Block range of the next request, adapted to the rejections of the provider.
+    pub fn batch_blocks(&self) -> u64 {
+        self.batch_blocks
+    }
+
+    /// Returns the logs of the filter in the blocks `from_block..=to_block`, the block range
+    /// of the filter being ignored.
+    pub async fn get_logs<P: Provider>(
+        &mut self,
+        provider: &P,
+        filter: &Filter,
+        from_block: u64,
+        to_block: u64,
+    ) -> TransportResult<Vec<Log>> {
+        let mut logs = Vec::new();
+        let mut start = from_block;
+        let mut attempts = 0;
+        while start <= to_block {
+            let end = to_block.min(start.saturating_add(self.batch_blocks - 1));
+            let batch = filter.clone().from_block(start).to_block(end);
+            match provider.get_logs(&batch).await {
+                Ok(batch_logs) => {
+                    logs.extend(batch_logs);
+                    attempts = 0;
+                    self.batch_blocks = self.batch_blocks.saturating_mul(2).min(self.max_blocks);
+                    if end == to_block {
+                        break;
+                    }
+                    start = end + 1;
+                }
+                Err(e) if end > start && is_log_range_error(&e.to_string()) => {
+                    self.batch_blocks = (end - start).div_ceil(2).max(1);
+                    debug!(
+                        "Logs of blocks {}..={} rejected ({}), retrying {} blocks at a time",
+                        start, end, e, self.batch_blocks
+                    );
+                }
+                Err(e) => {
+                    attempts += 1;
+                    if attempts >= LOGS_MAX_ATTEMPTS {
+                        return Err(e);
+                    }
+                    let delay = self.retry_delay * 2u32.pow(attempts - 1);
+                    warn!(
+                        "Failed to get the logs of blocks {}..={}: {}, retrying in {:?}",
+                        start, end, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        Ok(logs)
+    }
+}
+
+/// Parses a semicolon separated list of `Name: value` headers.
+fn parse_headers(headers: &str) -> Result<Vec<(String, String)>, Error> {
+    headers
+        .split(';')
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .map(|h| {
+            let (name, value) = h
+                .split_once(':')
+                .ok_or_else(|| Error::InvalidHeader(h.to_string()))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("No RPC URL provided")]
+    NoUrls,
+    #[error("Invalid RPC URL '{0}': {1}")]
+    InvalidUrl(String, String),
+    #[error("Invalid RPC header: {0}")]
+    InvalidHeader(String),
+    #[error("Invalid RPC basic auth, expected 'user:password'")]
+    InvalidBasicAuth,
+    #[error("Invalid RPC timeout: {0}")]
+    InvalidTimeout(String),
+    #[error("Invalid CHAIN_RPC_LOGS_MAX_BLOCKS: {0}")]
+    InvalidLogsMaxBlocks(String),
+    #[error("Failed to build HTTP client: {0}")]
+    Http(String),
+    #[error(transparent)]
+    Net(#[from] net::Error),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_config() {
+        let config = RpcConfig::new("https://a.example, https://b.example").unwrap();
+        assert_eq!(config.urls.len(), 2);
+        assert!(RpcConfig::new(" , ").is_err());
+
+        let headers = parse_headers("X-Api-Key: abc; X-Other:def").unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                ("X-Api-Key".to_string(), "abc".to_string()),
+                ("X-Other".to_string(), "def".to_string()),
+            ]
+        );
+        assert!(parse_headers("no-separator").is_err());
+    }
+
+    #[test]
+    fn should_set_authorization_header() {
+        let mut config = RpcConfig::new("https://a.example").unwrap();
+        config.basic_auth = Some(("user".to_string(), "pass".to_string()));
+        let map = config.header_map().unwrap();
+        assert_eq!(map[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+
+        config.bearer_token = Some("token".to_string());
+        let map = config.header_map().unwrap();
+        assert_eq!(map[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn should_detect_log_range_errors() {
+        assert!(is_log_range_error(
+            "server returned an error response: error code -32005: query returned more than 10000 results"
+        ));
+        assert!(is_log_range_error(
+            "eth_getLogs is limited to a 2000 block range"
+        ));
+        assert!(!is_log_range_error(
+            "HTTP error 429 with body: rate limit exceeded"
+        ));
+        assert!(!is_log_range_error("connection reset by peer"));
+    }
+
+    #[tokio::test]
+    async fn should_split_log_ranges() {
+        use alloy::providers::ProviderBuilder;
+        use alloy::transports::mock::Asserter;
+
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        let mut fetcher = LogFetcher::new(100);
+        fetcher.retry_delay = Duration::ZERO;
+
+        // 0..=99 rejected, 0..=49 and 50..=99 pulled, then a transient failure is retried
+        asserter.push_failure_msg("query returned more than 10000 results");
+        asserter.push_success(&Vec::<Log>::new());
+        asserter.push_success(&Vec::<Log>::new());
+        let logs = fetcher
+            .get_logs(&provider, &Filter::new(), 0, 99)
+            .await
+            .unwrap();
+        assert!(logs.is_empty());
+        assert_eq!(fetcher.batch_blocks(), 100);
+
+        asserter.push_failure_msg("connection reset by peer");
+        asserter.push_success(&Vec::<Log>::new());
+        fetcher
+            .get_logs(&provider, &Filter::new(), 100, 150)
+            .await
+            .unwrap();
+
+        // A single block can't be split
+        asserter.push_failure_msg("query returned more than 10000 results");
+        for _ in 1..LOGS_MAX_ATTEMPTS {
+            asserter.push_failure_msg("query returned more than 10000 results");
+        }
+        assert!(fetcher
+            .get_logs(&provider, &Filter::new(), 7, 7)
+            .await
+            .is_err());
+    }
+}
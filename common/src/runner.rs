@@ -1,14 +1,28 @@
 use crate::{
-    algos::{et::eigen_trust_run, sr::sybil_rank_run},
-    merkle::{self, fixed::DenseMerkleTree, hash_leaf, Hash},
+    algos::{
+        checkpoint::ScoreCheckpoint,
+        disk::{eigen_trust_run_disk, sybil_rank_run_disk},
+        et::{apply_distrust, eigen_trust_run},
+        partitioned::PARTITIONED_MIN_EDGES,
+        sr::sybil_rank_run,
+        ComputeReport,
+    },
+    hashing,
+    hooks::{self, WasmHook},
+    merkle::{self, Hash},
+    params::{AlgoParams, EigenTrustParams, NegativeEigenTrustParams, SybilRankParams},
+    ranking::rank_order,
+    scheme::{Precision, Scheme},
+    trust_matrix::DiskTrustMatrix,
+    value::Value,
     ScoreEntry, TrustEntry,
 };
 use getset::Getters;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
-use sha3::Keccak256;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::info;
 
 /// Local trust object.
@@ -19,42 +33,42 @@ use tracing::info;
 /// It also stores the sum of the trust values assigned to all peers.
 #[derive(Debug, Clone, Serialize, Deserialize, Getters)]
 #[getset(get = "pub")]
-pub struct OutboundLocalTrust {
+pub struct OutboundLocalTrust<V> {
     /// The trust values that a node assigns to its peers.
     ///
     /// The `outbound_trust_scores` vector stores the trust values that a node
     /// assigns to its peers. The trust values are represented as a vector of
     /// floats, where each element in the vector corresponds to the trust value
     /// assigned to a particular peer.
-    outbound_trust_scores: BTreeMap<u64, f32>,
+    outbound_trust_scores: BTreeMap<u64, V>,
     /// The sum of the trust values assigned to all peers.
     ///
     /// The `outbound_sum` value stores the sum of the trust values assigned to
     /// all peers. The sum is used to normalize the trust values such that they
     /// add up to 1.
-    outbound_sum: f32,
+    outbound_sum: V,
 }
 
-impl Default for OutboundLocalTrust {
+impl<V: Value> Default for OutboundLocalTrust<V> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl OutboundLocalTrust {
+impl<V: Value> OutboundLocalTrust<V> {
     pub fn new() -> Self {
         Self {
             outbound_trust_scores: BTreeMap::new(),
-            outbound_sum: 0.0,
+            outbound_sum: V::ZERO,
         }
     }
 
-    pub fn set_outbound_trust_scores(&mut self, outbound_trust_scores: BTreeMap<u64, f32>) {
+    pub fn set_outbound_trust_scores(&mut self, outbound_trust_scores: BTreeMap<u64, V>) {
         self.outbound_trust_scores = outbound_trust_scores;
         self.outbound_sum = self.outbound_trust_scores.values().sum();
     }
 
-    pub fn from_score_map(score_map: &BTreeMap<u64, f32>) -> Self {
+    pub fn from_score_map(score_map: &BTreeMap<u64, V>) -> Self {
         let outbound_trust_scores = score_map.clone();
         let outbound_sum = outbound_trust_scores.values().sum();
         Self {
@@ -65,10 +79,10 @@ impl OutboundLocalTrust {
 
     pub fn norm(&self) -> Self {
         let mut outbound_trust_scores = self.outbound_trust_scores.clone();
-        for (_, score) in outbound_trust_scores.iter_mut() {
+        for score in outbound_trust_scores.values_mut() {
             *score /= self.outbound_sum;
         }
-        let outbound_sum = 1.0;
+        let outbound_sum = V::ONE;
         OutboundLocalTrust {
             outbound_trust_scores,
             outbound_sum,
@@ -76,7 +90,7 @@ impl OutboundLocalTrust {
     }
 
     /*----------------- BTreeMap similar utils -----------------*/
-    pub fn get(&self, peer_id: &u64) -> Option<f32> {
+    pub fn get(&self, peer_id: &u64) -> Option<V> {
         self.outbound_trust_scores.get(peer_id).copied()
     }
 
@@ -89,103 +103,349 @@ impl OutboundLocalTrust {
             .outbound_trust_scores
             .get(peer_id)
             .copied()
-            .unwrap_or(0.0);
+            .unwrap_or(V::ZERO);
         self.outbound_sum -= to_be_removed;
         self.outbound_trust_scores.remove(peer_id);
     }
 
-    pub fn insert(&mut self, peer_id: u64, value: f32) {
+    /// Sets the trust in the peer, a zero value removing it.
+    pub fn apply(&mut self, peer_id: u64, value: V) {
+        if value == V::ZERO {
+            if self.contains_key(&peer_id) {
+                self.remove(&peer_id);
+            }
+        } else {
+            self.insert(peer_id, value);
+        }
+    }
+
+    pub fn insert(&mut self, peer_id: u64, value: V) {
         let prev_value = self
             .outbound_trust_scores
             .get(&peer_id)
             .copied()
-            .unwrap_or(0.0);
+            .unwrap_or(V::ZERO);
         self.outbound_sum -= prev_value;
         self.outbound_sum += value;
         self.outbound_trust_scores.insert(peer_id, value);
     }
 }
 
-#[derive(Getters)]
-#[getset(get = "pub")]
+/// Runner of the algorithms, computing with the values of the precision of its jobs (see
+/// `Value`): the trust and seed values are converted to it as they are added, so a runner only
+/// computes the scores of the jobs of its precision.
+#[derive(Clone)]
 pub struct ComputeRunner {
+    inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    F32(Runner<f32>),
+    F64(Runner<f64>),
+}
+
+/// Evaluates the expression with the runner of the precision.
+macro_rules! with_runner {
+    ($inner:expr, $runner:ident => $body:expr) => {
+        match $inner {
+            Inner::F32($runner) => $body,
+            Inner::F64($runner) => $body,
+        }
+    };
+}
+
+impl ComputeRunner {
+    pub fn new(precision: Precision) -> Self {
+        let inner = match precision {
+            Precision::F32 => Inner::F32(Runner::new()),
+            Precision::F64 => Inner::F64(Runner::new()),
+        };
+        Self { inner }
+    }
+
+    /// Precision of the values the runner computes with.
+    pub fn precision(&self) -> Precision {
+        match self.inner {
+            Inner::F32(_) => Precision::F32,
+            Inner::F64(_) => Precision::F64,
+        }
+    }
+
+    /// Saves the EigenTrust scores to the checkpoint while computing, resuming from it if
+    /// it exists. The checkpoint is keyed by the seed, so it is cleared when replacing it.
+    pub fn with_checkpoint(mut self, checkpoint: ScoreCheckpoint) -> Self {
+        with_runner!(&mut self.inner, runner => runner.checkpoint = Some(checkpoint));
+        self
+    }
+
+    /// Accepts negative trust entries, kept apart as distrust for
+    /// `compute_negative_et`. The other algorithms refuse to run with distrust. Negative
+    /// entries aren't supported with the trust matrix on disk.
+    pub fn with_negative_trust(mut self) -> Self {
+        with_runner!(&mut self.inner, runner => runner.negative_trust = true);
+        self
+    }
+
+    /// Runs EigenTrust partitioned from this many trust edges, instead of
+    /// `PARTITIONED_MIN_EDGES`. Only meant for benchmarks: the nodes computing and verifying a
+    /// job must use the same threshold, the scores of both computations differing by floating
+    /// point rounding.
+    pub fn with_partition_min_edges(mut self, edges: usize) -> Self {
+        with_runner!(&mut self.inner, runner => runner.partition_min_edges = edges);
+        self
+    }
+
+    /// Keeps the trust matrix in temporary files of the directory rather than in memory, for
+    /// graphs whose edges don't fit in memory. Only the peer indices and the scores are held
+    /// in memory, the edges being streamed from disk at each iteration. The scores are the
+    /// same as with the matrix in memory, up to floating point rounding.
+    pub fn with_disk_trust(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        with_runner!(&mut self.inner, runner => runner.disk_dir = Some(dir));
+        self
+    }
+
+    /// Number of peers indexed, by the trust and the seed maps.
+    pub fn count(&self) -> u64 {
+        with_runner!(&self.inner, runner => runner.count)
+    }
+
+    pub fn indices(&self) -> &HashMap<String, u64> {
+        with_runner!(&self.inner, runner => &runner.indices)
+    }
+
+    /// Diagnostics of the last compute, `None` until the scores are computed.
+    pub fn report(&self) -> &Option<ComputeReport> {
+        with_runner!(&self.inner, runner => &runner.report)
+    }
+
+    /// Adds the trust entries to the trust map. Entries are consumed one at a time, so a
+    /// streaming iterator (e.g. a `TrustEntryReader`) never has to be collected first.
+    /// A trust matrix on disk is built once, by a single call.
+    pub fn update_trust_map(
+        &mut self,
+        trust_entries: impl IntoIterator<Item = TrustEntry>,
+    ) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.update_trust_map(trust_entries))
+    }
+
+    pub fn update_seed_map(&mut self, seed_entries: Vec<ScoreEntry>) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.update_seed_map(seed_entries))
+    }
+
+    /// Replaces the seed map, keeping the trust map and the indices of its peers, so that the
+    /// scores for another seed are computed without building the trust map again. The peers
+    /// only in the previous seed are forgotten: the scores are the same as the ones of a
+    /// runner built from the same trust entries and the new seed.
+    pub fn replace_seed_map(&mut self, seed_entries: Vec<ScoreEntry>) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.replace_seed_map(seed_entries))
+    }
+
+    /// Compute the EigenTrust scores. The convergence diagnostics are kept in `report`.
+    pub fn compute_et(&mut self, params: &EigenTrustParams) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.compute_et(params))
+    }
+
+    /// Compute the EigenTrust scores of the positive trust entries, minus the distrust of the
+    /// negative ones (see `et::apply_distrust`).
+    pub fn compute_negative_et(&mut self, params: &NegativeEigenTrustParams) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.compute_negative_et(params))
+    }
+
+    /// Compute the SybilRank scores. The diagnostics are kept in `report`.
+    pub fn compute_sr(&mut self, params: &SybilRankParams) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.compute_sr(params))
+    }
+
+    /// Compute the scores with the algorithm of the params. New algorithms are dispatched
+    /// here, for the computer, the audits and the local commands to run them alike.
+    /// Params without a scheme to commit the scores with, or of another precision than the
+    /// runner, are rejected before computing.
+    pub fn compute(&mut self, params: &AlgoParams) -> Result<(), Error> {
+        params.scheme().map_err(Error::Hash)?;
+        if params.precision() != self.precision() {
+            return Err(Error::PrecisionMismatch(
+                params.precision(),
+                self.precision(),
+            ));
+        }
+        match params {
+            AlgoParams::EigenTrust(params) => self.compute_et(params),
+            AlgoParams::SybilRank(params) => self.compute_sr(params),
+            AlgoParams::NegativeEigenTrust(params) => self.compute_negative_et(params),
+        }
+    }
+
+    /// Apply the post-processing hook to the computed scores.
+    /// Hooks take and return f32 scores, so the scores are rounded to f32 precision.
+    pub fn apply_hook(&mut self, hook: &WasmHook) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.apply_hook(hook))
+    }
+
+    /// Sorts the compute results in ranking order, so the scores and the compute tree follow it.
+    pub fn sort_by_rank(&mut self) {
+        with_runner!(&mut self.inner, runner => runner.sort_by_rank())
+    }
+
+    /// Create the compute tree, with the leaf encoding and the hash function of the scheme.
+    /// Only the root is kept, leaves are streamed into the tree without being collected.
+    pub fn create_compute_tree(&mut self, scheme: &Scheme) -> Result<(), Error> {
+        with_runner!(&mut self.inner, runner => runner.create_compute_tree(scheme))
+    }
+
+    /// Get the compute scores.
+    pub fn get_compute_scores(&self) -> Result<Vec<ScoreEntry>, Error> {
+        with_runner!(&self.inner, runner => runner.get_compute_scores())
+    }
+
+    /// Get the compute tree root hash.
+    pub fn get_root_hash(&self) -> Result<Hash, Error> {
+        with_runner!(&self.inner, runner => runner.get_root_hash())
+    }
+}
+
+/// Trust and seed maps of a runner, with the values of a precision.
+#[derive(Clone)]
+struct Runner<V> {
     count: u64,
+    /// Number of peers indexed by the trust map, the following ones being only in the seed.
+    trust_count: u64,
     indices: HashMap<String, u64>,
     rev_indices: HashMap<u64, String>,
-    local_trust: BTreeMap<u64, OutboundLocalTrust>,
-    seed_trust: BTreeMap<u64, f32>,
-    compute_tree: Option<DenseMerkleTree<Keccak256>>,
-    compute_results: Vec<(u64, f32)>,
+    local_trust: BTreeMap<u64, OutboundLocalTrust<V>>,
+    /// Absolute values of the negative trust entries, when they are accepted.
+    distrust: BTreeMap<u64, OutboundLocalTrust<V>>,
+    /// Whether negative trust entries are accepted, for the algorithms using them.
+    negative_trust: bool,
+    seed_trust: BTreeMap<u64, V>,
+    compute_root: Option<Hash>,
+    compute_results: Vec<(u64, V)>,
+    /// Diagnostics of the last compute, `None` until the scores are computed.
+    report: Option<ComputeReport>,
+    /// Directory of the trust matrix files, when it is kept on disk.
+    disk_dir: Option<PathBuf>,
+    /// Trust matrix kept on disk instead of `local_trust`, shared by the clones of the runner.
+    disk_trust: Option<Arc<DiskTrustMatrix>>,
+    /// Checkpoint of the EigenTrust scores of the current seed.
+    checkpoint: Option<ScoreCheckpoint>,
+    /// Edges of the trust matrix from which EigenTrust runs partitioned.
+    partition_min_edges: usize,
 }
 
-impl ComputeRunner {
-    pub fn new() -> Self {
+impl<V: Value> Runner<V> {
+    fn new() -> Self {
         Self {
             count: 0,
+            trust_count: 0,
             indices: HashMap::new(),
             rev_indices: HashMap::new(),
             local_trust: BTreeMap::new(),
+            distrust: BTreeMap::new(),
+            negative_trust: false,
             seed_trust: BTreeMap::new(),
-            compute_tree: None,
+            compute_root: None,
             compute_results: Vec::new(),
+            report: None,
+            disk_dir: None,
+            disk_trust: None,
+            checkpoint: None,
+            partition_min_edges: PARTITIONED_MIN_EDGES,
         }
     }
 
-    pub fn update_trust_map(&mut self, trust_entries: Vec<TrustEntry>) -> Result<(), Error> {
+    /// Returns the index of the peer, indexing it if it is new.
+    fn index_of(&mut self, id: &String) -> u64 {
+        if let Some(i) = self.indices.get(id) {
+            return *i;
+        }
+        let curr_count = self.count;
+        self.indices.insert(id.clone(), curr_count);
+        self.rev_indices.insert(curr_count, id.clone());
+        self.count += 1;
+        curr_count
+    }
+
+    fn update_trust_map(
+        &mut self,
+        trust_entries: impl IntoIterator<Item = TrustEntry>,
+    ) -> Result<(), Error> {
+        if let Some(dir) = self.disk_dir.clone() {
+            return self.build_disk_trust(&dir, trust_entries);
+        }
         for entry in trust_entries {
-            let from_index = if let Some(i) = self.indices.get(entry.from()) {
-                *i
-            } else {
-                let curr_count = self.count;
-                self.indices.insert(entry.from().clone(), curr_count);
-                self.rev_indices.insert(curr_count, entry.from().clone());
-                self.count += 1;
-                curr_count
-            };
-            let to_index = if let Some(i) = self.indices.get(entry.to()) {
-                *i
+            let value = V::from_f64(*entry.value());
+            if !value.is_finite() || (value < V::ZERO && !self.negative_trust) {
+                return Err(Error::InvalidValue(entry.from().clone(), *entry.value()));
+            }
+            let from_index = self.index_of(entry.from());
+            let to_index = self.index_of(entry.to());
+            // The last entry of an edge wins, whatever its sign
+            let (trust, distrust) = if value < V::ZERO {
+                (V::ZERO, V::ZERO - value)
             } else {
-                let curr_count = self.count;
-                self.indices.insert(entry.to().clone(), curr_count);
-                self.rev_indices.insert(curr_count, entry.to().clone());
-                self.count += 1;
-                curr_count
+                (value, V::ZERO)
             };
-
-            let from_map = self
-                .local_trust
+            self.local_trust
                 .entry(from_index)
-                .or_insert(OutboundLocalTrust::new());
-            let is_zero = entry.value() == &0.0;
-            let exists = from_map.contains_key(&to_index);
-            if is_zero && exists {
-                from_map.remove(&to_index);
-            } else if !is_zero {
-                from_map.insert(to_index, *entry.value());
+                .or_default()
+                .apply(to_index, trust);
+            if self.negative_trust {
+                self.distrust
+                    .entry(from_index)
+                    .or_default()
+                    .apply(to_index, distrust);
             }
         }
+        self.trust_count = self.count;
         info!("LT_MAP_UPDATE");
 
         Ok(())
     }
 
-    pub fn update_seed_map(&mut self, seed_entries: Vec<ScoreEntry>) -> Result<(), Error> {
+    fn build_disk_trust(
+        &mut self,
+        dir: &PathBuf,
+        trust_entries: impl IntoIterator<Item = TrustEntry>,
+    ) -> Result<(), Error> {
+        if self.disk_trust.is_some() || self.count > 0 {
+            return Err(Error::Misc(
+                "The trust matrix on disk is built from a single update".to_string(),
+            ));
+        }
+        let mut builder = DiskTrustMatrix::builder(dir).map_err(Error::TrustMatrix)?;
+        for entry in trust_entries {
+            let value = V::from_f64(*entry.value());
+            if !value.is_finite() || value < V::ZERO {
+                return Err(Error::InvalidValue(entry.from().clone(), *entry.value()));
+            }
+            let from_index = self.index_of(entry.from());
+            let to_index = self.index_of(entry.to());
+            builder
+                .push(from_index, to_index, value.to_f64())
+                .map_err(Error::TrustMatrix)?;
+        }
+        let matrix = builder.finish(self.count).map_err(Error::TrustMatrix)?;
+        self.trust_count = self.count;
+        self.disk_trust = Some(Arc::new(matrix));
+        info!("LT_MAP_UPDATE_DISK");
+
+        Ok(())
+    }
+
+    fn update_seed_map(&mut self, seed_entries: Vec<ScoreEntry>) -> Result<(), Error> {
         for entry in seed_entries {
-            let index = if let Some(i) = self.indices.get(entry.id()) {
-                *i
-            } else {
-                let curr_count = self.count;
-                self.indices.insert(entry.id().clone(), curr_count);
-                self.rev_indices.insert(curr_count, entry.id().clone());
-                self.count += 1;
-                curr_count
-            };
-            let is_zero = entry.value() == &0.0;
+            let value = V::from_f64(*entry.value());
+            if !value.is_finite() || value < V::ZERO {
+                return Err(Error::InvalidValue(entry.id().clone(), *entry.value()));
+            }
+            let index = self.index_of(entry.id());
+            let is_zero = value == V::ZERO;
             let exists = self.seed_trust.contains_key(&index);
             if is_zero && exists {
                 self.seed_trust.remove(&index);
             } else if !is_zero {
-                self.seed_trust.insert(index, *entry.value());
+                self.seed_trust.insert(index, value);
             }
         }
         info!("ST_MAP_UPDATE");
@@ -193,53 +453,159 @@ impl ComputeRunner {
         Ok(())
     }
 
-    /// Compute the EigenTrust scores.
-    pub fn compute_et(&mut self, alpha: Option<f32>, delta: Option<f32>) -> Result<(), Error> {
+    fn replace_seed_map(&mut self, seed_entries: Vec<ScoreEntry>) -> Result<(), Error> {
+        for index in self.trust_count..self.count {
+            if let Some(id) = self.rev_indices.remove(&index) {
+                self.indices.remove(&id);
+            }
+        }
+        self.count = self.trust_count;
+        self.seed_trust.clear();
+        self.compute_root = None;
+        self.compute_results.clear();
+        self.report = None;
+        self.checkpoint = None;
+        self.update_seed_map(seed_entries)
+    }
+
+    /// Checks there is no distrust, for the algorithms without negative trust entries.
+    fn check_no_distrust(&self) -> Result<(), Error> {
+        if self
+            .distrust
+            .values()
+            .any(|row| !row.outbound_trust_scores().is_empty())
+        {
+            return Err(Error::UnexpectedDistrust);
+        }
+        Ok(())
+    }
+
+    /// Checks there is at least one peer to compute the scores for.
+    ///
+    /// Small inputs have the following semantics, for all algorithms:
+    /// - no trust and no seed entries: `Error::EmptyInput`,
+    /// - no seed entries (or only zero seed values): every peer is seeded uniformly,
+    /// - peers without outbound trust distribute their trust to the seed peers,
+    ///   so a single peer graph results in that peer having a score of 1.0.
+    fn check_input(&self) -> Result<(), Error> {
+        if self.count == 0 {
+            return Err(Error::EmptyInput);
+        }
+        Ok(())
+    }
+
+    fn compute_et(&mut self, params: &EigenTrustParams) -> Result<(), Error> {
         info!("COMPUTE_RUN_ET");
-        let res = eigen_trust_run(
-            self.local_trust.clone(),
-            self.seed_trust.clone(),
-            self.count,
-            alpha,
-            delta,
-        );
+        self.check_no_distrust()?;
+        self.run_et(params)
+    }
+
+    fn run_et(&mut self, params: &EigenTrustParams) -> Result<(), Error> {
+        self.check_input()?;
+        let (res, report) = match &self.disk_trust {
+            Some(matrix) => eigen_trust_run_disk(
+                matrix,
+                self.seed_trust.clone(),
+                self.count,
+                params.alpha,
+                params.delta,
+                params.max_iter,
+                self.checkpoint.as_ref(),
+            )
+            .map_err(Error::TrustMatrix)?,
+            None => eigen_trust_run(
+                self.local_trust.clone(),
+                self.seed_trust.clone(),
+                self.count,
+                params.alpha,
+                params.delta,
+                params.max_iter,
+                self.checkpoint.as_ref(),
+                self.partition_min_edges,
+            ),
+        };
         self.compute_results = res;
+        self.report = Some(report);
         Ok(())
     }
 
-    /// Compute the SybilRank scores.
-    pub fn compute_sr(&mut self, walk_length: Option<u32>) -> Result<(), Error> {
-        info!("COMPUTE_RUN_SR");
-        let res = sybil_rank_run(
-            self.local_trust.clone(),
-            self.seed_trust.clone(),
-            self.count,
-            walk_length,
+    fn compute_negative_et(&mut self, params: &NegativeEigenTrustParams) -> Result<(), Error> {
+        info!("COMPUTE_RUN_NEGATIVE_ET");
+        if let Some(weight) = params
+            .distrust_weight
+            .filter(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(Error::InvalidValue("distrust_weight".to_string(), weight));
+        }
+        self.run_et(&params.et)?;
+        // Peers with only negative entries trust the seed peers, as the ones without entries
+        self.compute_results = apply_distrust(
+            std::mem::take(&mut self.compute_results),
+            &self.distrust,
+            params.distrust_weight,
         );
+        Ok(())
+    }
+
+    fn compute_sr(&mut self, params: &SybilRankParams) -> Result<(), Error> {
+        info!("COMPUTE_RUN_SR");
+        self.check_input()?;
+        self.check_no_distrust()?;
+        let (res, report) = match &self.disk_trust {
+            Some(matrix) => sybil_rank_run_disk(
+                matrix,
+                self.seed_trust.clone(),
+                self.count,
+                params.walk_length,
+            )
+            .map_err(Error::TrustMatrix)?,
+            None => sybil_rank_run(
+                self.local_trust.clone(),
+                self.seed_trust.clone(),
+                self.count,
+                params.walk_length,
+            ),
+        };
         self.compute_results = res;
+        self.report = Some(report);
         Ok(())
     }
 
-    /// Create the compute tree.
-    pub fn create_compute_tree(&mut self) -> Result<(), Error> {
-        info!("CREATE_COMPUTE_TREE");
-        let score_hashes: Vec<Hash> = self
+    fn apply_hook(&mut self, hook: &WasmHook) -> Result<(), Error> {
+        info!("APPLY_HOOK: {}", hook.id());
+        let mut values: Vec<f32> = self
             .compute_results
-            .par_iter()
-            .map(|(_, x)| hash_leaf::<Keccak256>(x.to_be_bytes().to_vec()))
+            .iter()
+            .map(|(_, v)| v.to_f64() as f32)
             .collect();
-        let compute_tree =
-            DenseMerkleTree::<Keccak256>::new(score_hashes).map_err(Error::Merkle)?;
-        info!(
-            "COMPUTE_TREE_ROOT_HASH: {}",
-            compute_tree.root().map_err(Error::Merkle)?
+        hook.run(&mut values).map_err(Error::Hook)?;
+        for ((_, v), new_v) in self.compute_results.iter_mut().zip(values) {
+            *v = V::from_f64(new_v as f64);
+        }
+        Ok(())
+    }
+
+    fn sort_by_rank(&mut self) {
+        info!("SORT_BY_RANK");
+        let rev_indices = &self.rev_indices;
+        let id = |index: &u64| rev_indices.get(index).map_or("", |id| id.as_str());
+        self.compute_results
+            .sort_by(|a, b| rank_order((id(&a.0), a.1.to_f64()), (id(&b.0), b.1.to_f64())));
+    }
+
+    fn create_compute_tree(&mut self, scheme: &Scheme) -> Result<(), Error> {
+        info!("CREATE_COMPUTE_TREE: {}", scheme.version);
+        let compute_root = scheme.hash.root_from_iter(
+            self.compute_results
+                .iter()
+                .map(|(_, x)| scheme.hash_leaf(x.to_f64())),
         );
-        self.compute_tree = Some(compute_tree);
+        info!("COMPUTE_TREE_ROOT_HASH: {}", compute_root);
+        self.compute_root = Some(compute_root);
         Ok(())
     }
 
-    /// Get the compute scores.
-    pub fn get_compute_scores(&self) -> Result<Vec<ScoreEntry>, Error> {
+    fn get_compute_scores(&self) -> Result<Vec<ScoreEntry>, Error> {
         let index_to_address: HashMap<&u64, &String> =
             self.indices.iter().map(|(k, v)| (v, k)).collect();
 
@@ -248,20 +614,17 @@ impl ComputeRunner {
             let address = index_to_address
                 .get(index)
                 .ok_or(Error::IndexToAddressNotFound(*index))?;
-            let score_entry = ScoreEntry::new((*address).clone(), *val);
+            let score_entry = ScoreEntry::new((*address).clone(), val.to_f64());
             entries.push(score_entry);
         }
         Ok(entries)
     }
 
-    /// Get the compute tree root hash.
-    pub fn get_root_hash(&self) -> Result<Hash, Error> {
+    fn get_root_hash(&self) -> Result<Hash, Error> {
         let ct_tree_root = self
-            .compute_tree
-            .as_ref()
-            .map(|ct| ct.root())
-            .unwrap()
-            .map_err(Error::Merkle)?;
+            .compute_root
+            .clone()
+            .ok_or(Error::Merkle(merkle::Error::RootNotFound))?;
         Ok(ct_tree_root)
     }
 }
@@ -276,7 +639,398 @@ pub enum Error {
     Merkle(merkle::Error),
     #[error("Misc Error: {0}")]
     Misc(String),
+    #[error("Hook Error: {0}")]
+    Hook(hooks::Error),
+    #[error("Hash Error: {0}")]
+    Hash(hashing::Error),
+    #[error("Trust matrix I/O error: {0}")]
+    TrustMatrix(std::io::Error),
     /// The index to address mapping for the domain are not found.
     #[error("IndexToAddressNotFound Error: {0}")]
     IndexToAddressNotFound(u64),
+    /// Neither trust nor seed entries were provided.
+    #[error("Empty input: no trust or seed entries")]
+    EmptyInput,
+    /// Trust or seed value is negative or not a finite number.
+    #[error("Invalid value for '{0}': {1}")]
+    InvalidValue(String, f64),
+    /// Negative trust entries given to an algorithm without distrust.
+    #[error("Negative trust entries are only supported by EigenTrust with negative trust")]
+    UnexpectedDistrust,
+    /// Params of another precision than the values of the runner.
+    #[error("Scores of {0} precision asked to a runner computing with {1} values")]
+    PrecisionMismatch(Precision, Precision),
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ComputeRunner, Error};
+    use crate::{
+        params::{AlgoParams, EigenTrustParams, NegativeEigenTrustParams, SybilRankParams},
+        scheme::{self, Precision},
+        ScoreEntry, TrustEntry,
+    };
+
+    fn trust(from: &str, to: &str, value: f64) -> TrustEntry {
+        TrustEntry::new(from.to_string(), to.to_string(), value)
+    }
+
+    #[test]
+    fn should_reject_empty_input() {
+        let mut runner = ComputeRunner::new(Precision::F64);
+        runner.update_trust_map(Vec::new()).unwrap();
+        runner.update_seed_map(Vec::new()).unwrap();
+        assert!(matches!(
+            runner.compute_et(&EigenTrustParams::default()),
+            Err(Error::EmptyInput)
+        ));
+        assert!(matches!(
+            runner.compute_sr(&SybilRankParams::default()),
+            Err(Error::EmptyInput)
+        ));
+    }
+
+    #[test]
+    fn should_dispatch_on_algo_params() {
+        let runner = || {
+            let mut runner = ComputeRunner::new(Precision::F32);
+            runner
+                .update_trust_map(vec![trust("a", "b", 1.0), trust("b", "c", 1.0)])
+                .unwrap();
+            runner
+                .update_seed_map(vec![ScoreEntry::new("a".to_string(), 1.0)])
+                .unwrap();
+            runner
+        };
+
+        let et = EigenTrustParams {
+            alpha: Some(0.3),
+            ..Default::default()
+        };
+        let mut dispatched = runner();
+        dispatched
+            .compute(&AlgoParams::EigenTrust(et.clone()))
+            .unwrap();
+        let mut direct = runner();
+        direct.compute_et(&et).unwrap();
+        assert_eq!(
+            dispatched.get_compute_scores().unwrap(),
+            direct.get_compute_scores().unwrap()
+        );
+
+        let sr = SybilRankParams {
+            walk_length: Some(3),
+            ..Default::default()
+        };
+        let mut dispatched = runner();
+        dispatched
+            .compute(&AlgoParams::SybilRank(sr.clone()))
+            .unwrap();
+        let mut direct = runner();
+        direct.compute_sr(&sr).unwrap();
+        assert_eq!(
+            dispatched.get_compute_scores().unwrap(),
+            direct.get_compute_scores().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_report_convergence() {
+        let mut runner = ComputeRunner::new(Precision::F64);
+        runner
+            .update_trust_map(vec![
+                trust("a", "b", 1.0),
+                trust("b", "c", 1.0),
+                trust("d", "e", 1.0),
+            ])
+            .unwrap();
+        runner
+            .update_seed_map(vec![ScoreEntry::new("a".to_string(), 1.0)])
+            .unwrap();
+        assert!(runner.report().is_none());
+
+        runner.compute_et(&EigenTrustParams::default()).unwrap();
+        let report = runner.report().clone().unwrap();
+        assert_eq!(report.converged, Some(true));
+        assert!(report.final_delta.unwrap() <= crate::algos::et::DELTA);
+        // `c` gets outbound trust, `d` is unreachable from the seed
+        assert_eq!((report.lt_size_before, report.lt_size_after), (3, 3));
+        assert_eq!((report.seed_size_before, report.seed_size_after), (1, 1));
+
+        let params = EigenTrustParams {
+            max_iter: Some(1),
+            delta: Some(0.0),
+            ..Default::default()
+        };
+        runner.compute_et(&params).unwrap();
+        let report = runner.report().clone().unwrap();
+        assert_eq!((report.iterations, report.converged), (1, Some(false)));
+
+        let sr = SybilRankParams {
+            walk_length: Some(4),
+            ..Default::default()
+        };
+        runner.compute_sr(&sr).unwrap();
+        let report = runner.report().clone().unwrap();
+        assert_eq!((report.iterations, report.final_delta), (4, None));
+
+        runner.replace_seed_map(Vec::new()).unwrap();
+        assert!(runner.report().is_none());
+    }
+
+    #[test]
+    fn should_compute_on_disk_trust() {
+        let trust_entries = vec![
+            trust("a", "b", 1.0),
+            trust("a", "c", 3.0),
+            trust("b", "c", 2.0),
+            trust("c", "a", 1.0),
+            // Overridden then removed edges
+            trust("b", "a", 5.0),
+            trust("b", "a", 0.5),
+            trust("c", "d", 1.0),
+            trust("c", "d", 0.0),
+            // Unreachable from the seed
+            trust("e", "f", 1.0),
+        ];
+        let seed = || {
+            vec![
+                ScoreEntry::new("a".to_string(), 1.0),
+                // Only in the seed
+                ScoreEntry::new("g".to_string(), 2.0),
+            ]
+        };
+        let runners = || {
+            let mut memory = ComputeRunner::new(Precision::F64);
+            memory.update_trust_map(trust_entries.clone()).unwrap();
+            memory.update_seed_map(seed()).unwrap();
+            let mut disk = ComputeRunner::new(Precision::F64).with_disk_trust(std::env::temp_dir());
+            disk.update_trust_map(trust_entries.clone()).unwrap();
+            disk.update_seed_map(seed()).unwrap();
+            (memory, disk)
+        };
+        let assert_close = |memory: &ComputeRunner, disk: &ComputeRunner| {
+            let memory_scores = memory.get_compute_scores().unwrap();
+            let disk_scores = disk.get_compute_scores().unwrap();
+            assert_eq!(memory_scores.len(), disk_scores.len());
+            for (a, b) in memory_scores.iter().zip(&disk_scores) {
+                assert_eq!(a.id(), b.id());
+                assert!((a.value() - b.value()).abs() < 1e-12, "{:?} != {:?}", a, b);
+            }
+            assert_eq!(memory.report().is_some(), disk.report().is_some());
+        };
+
+        let (mut memory, mut disk) = runners();
+        memory.compute_et(&EigenTrustParams::default()).unwrap();
+        disk.compute_et(&EigenTrustParams::default()).unwrap();
+        assert_close(&memory, &disk);
+        let memory_report = memory.report().clone().unwrap();
+        let disk_report = disk.report().clone().unwrap();
+        assert_eq!(memory_report.iterations, disk_report.iterations);
+        assert_eq!(memory_report.lt_size_before, disk_report.lt_size_before);
+        assert_eq!(memory_report.lt_size_after, disk_report.lt_size_after);
+
+        memory.compute_sr(&SybilRankParams::default()).unwrap();
+        disk.compute_sr(&SybilRankParams::default()).unwrap();
+        assert_close(&memory, &disk);
+
+        // A zero seed falls back to seeding every peer
+        memory
+            .replace_seed_map(vec![ScoreEntry::new("e".to_string(), 0.0)])
+            .unwrap();
+        disk.replace_seed_map(vec![ScoreEntry::new("e".to_string(), 0.0)])
+            .unwrap();
+        memory.compute_et(&EigenTrustParams::default()).unwrap();
+        disk.compute_et(&EigenTrustParams::default()).unwrap();
+        assert_close(&memory, &disk);
+
+        // The matrix on disk is built at once
+        assert!(matches!(
+            disk.update_trust_map(vec![trust("a", "e", 1.0)]),
+            Err(Error::Misc(_))
+        ));
+    }
+
+    #[test]
+    fn should_reuse_trust_map_with_another_seed() {
+        let trust_entries = vec![
+            trust("a", "b", 1.0),
+            trust("b", "c", 2.0),
+            trust("c", "a", 1.0),
+        ];
+        let seed = |ids: &[&str]| {
+            ids.iter()
+                .map(|id| ScoreEntry::new(id.to_string(), 1.0))
+                .collect::<Vec<_>>()
+        };
+
+        let mut reused = ComputeRunner::new(Precision::F64);
+        reused.update_trust_map(trust_entries.clone()).unwrap();
+        // "x" and "y" are only in the first seed, and get indices after the trust peers
+        reused.update_seed_map(seed(&["a", "x", "y"])).unwrap();
+        reused.compute_et(&EigenTrustParams::default()).unwrap();
+        reused.replace_seed_map(seed(&["b", "z"])).unwrap();
+        reused.compute_et(&EigenTrustParams::default()).unwrap();
+
+        let mut fresh = ComputeRunner::new(Precision::F64);
+        fresh.update_trust_map(trust_entries).unwrap();
+        fresh.update_seed_map(seed(&["b", "z"])).unwrap();
+        fresh.compute_et(&EigenTrustParams::default()).unwrap();
+
+        assert_eq!(reused.count(), fresh.count());
+        assert_eq!(reused.indices(), fresh.indices());
+        assert_eq!(
+            reused.get_compute_scores().unwrap(),
+            fresh.get_compute_scores().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_values() {
+        let mut runner = ComputeRunner::new(Precision::F64);
+        assert!(matches!(
+            runner.update_trust_map(vec![trust("a", "b", -1.0)]),
+            Err(Error::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            runner.update_seed_map(vec![ScoreEntry::new("a".to_string(), f64::NAN)]),
+            Err(Error::InvalidValue(_, _))
+        ));
+    }
+
+    #[test]
+    fn should_compute_with_negative_trust() {
+        let trust_entries = vec![
+            trust("a", "b", 1.0),
+            trust("b", "a", 1.0),
+            trust("a", "c", 1.0),
+            trust("c", "a", 1.0),
+            trust("b", "c", -1.0),
+        ];
+        let seed = vec![ScoreEntry::new("a".to_string(), 1.0)];
+        let mut runner = ComputeRunner::new(Precision::F64).with_negative_trust();
+        runner.update_trust_map(trust_entries.clone()).unwrap();
+        runner.update_seed_map(seed.clone()).unwrap();
+        let et = EigenTrustParams {
+            precision: Some(Precision::F64),
+            ..Default::default()
+        };
+        assert!(matches!(
+            runner.compute(&AlgoParams::EigenTrust(et.clone())),
+            Err(Error::UnexpectedDistrust)
+        ));
+
+        // Without distrust, b and c are symmetric
+        let positive: Vec<_> = trust_entries
+            .iter()
+            .filter(|entry| *entry.value() > 0.0)
+            .cloned()
+            .collect();
+        let mut reference = ComputeRunner::new(Precision::F64);
+        reference.update_trust_map(positive).unwrap();
+        reference.update_seed_map(seed).unwrap();
+        reference.compute_et(&EigenTrustParams::default()).unwrap();
+        let score = |runner: &ComputeRunner, id: &str| {
+            let scores = runner.get_compute_scores().unwrap();
+            *scores.iter().find(|s| s.id() == id).unwrap().value()
+        };
+        assert_eq!(score(&reference, "b"), score(&reference, "c"));
+
+        let params = NegativeEigenTrustParams {
+            et,
+            distrust_weight: Some(0.5),
+        };
+        runner
+            .compute(&AlgoParams::NegativeEigenTrust(params))
+            .unwrap();
+        assert_eq!(score(&runner, "a"), score(&reference, "a"));
+        assert_eq!(score(&runner, "b"), score(&reference, "b"));
+        assert_eq!(
+            score(&runner, "c"),
+            score(&reference, "c") - 0.5 * score(&reference, "b")
+        );
+
+        let params = NegativeEigenTrustParams {
+            distrust_weight: Some(-1.0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            runner.compute_negative_et(&params),
+            Err(Error::InvalidValue(_, _))
+        ));
+    }
+
+    #[test]
+    fn should_compute_single_peer() {
+        let mut runner = ComputeRunner::new(Precision::F64);
+        runner
+            .update_seed_map(vec![ScoreEntry::new("a".to_string(), 1.0)])
+            .unwrap();
+        runner.compute_et(&EigenTrustParams::default()).unwrap();
+        let scores = runner.get_compute_scores().unwrap();
+        assert_eq!(scores, vec![ScoreEntry::new("a".to_string(), 1.0)]);
+
+        runner.compute_sr(&SybilRankParams::default()).unwrap();
+        let scores = runner.get_compute_scores().unwrap();
+        assert_eq!(scores, vec![ScoreEntry::new("a".to_string(), 1.0)]);
+
+        runner.create_compute_tree(scheme::current()).unwrap();
+        assert!(runner.get_root_hash().is_ok());
+    }
+
+    #[test]
+    fn should_seed_uniformly_without_seed() {
+        let mut runner = ComputeRunner::new(Precision::F64);
+        runner
+            .update_trust_map(vec![trust("a", "b", 1.0), trust("b", "a", 1.0)])
+            .unwrap();
+        runner.compute_et(&EigenTrustParams::default()).unwrap();
+        let scores = runner.get_compute_scores().unwrap();
+        assert_eq!(scores.len(), 2);
+        for score in scores {
+            assert!((score.value() - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn should_compute_with_the_precision_of_the_params() {
+        let trust_entries = vec![
+            trust("a", "b", 1.0),
+            trust("b", "c", 3.0),
+            trust("c", "a", 7.0),
+            trust("c", "b", 1.0),
+        ];
+        let run = |precision| {
+            let mut runner = ComputeRunner::new(precision);
+            runner.update_trust_map(trust_entries.clone()).unwrap();
+            let params = AlgoParams::EigenTrust(EigenTrustParams {
+                precision: Some(precision),
+                ..Default::default()
+            });
+            runner.compute(&params).unwrap();
+            runner
+                .create_compute_tree(params.scheme().unwrap())
+                .unwrap();
+            runner
+        };
+
+        let f32_runner = run(Precision::F32);
+        assert_eq!(f32_runner.precision(), Precision::F32);
+        // Scores computed with f32 values are written and committed as they are
+        for score in f32_runner.get_compute_scores().unwrap() {
+            assert_eq!(*score.value(), *score.value() as f32 as f64);
+        }
+        let f64_runner = run(Precision::F64);
+        assert_ne!(
+            f32_runner.get_root_hash().unwrap(),
+            f64_runner.get_root_hash().unwrap()
+        );
+
+        // Jobs of another precision are rejected
+        let mut runner = f64_runner.clone();
+        assert!(matches!(
+            runner.compute(&AlgoParams::EigenTrust(EigenTrustParams::default())),
+            Err(Error::PrecisionMismatch(Precision::F32, Precision::F64))
+        ));
+    }
 }
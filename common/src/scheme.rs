@@ -0,0 +1,225 @@
+use crate::hashing::{self, HashAlgorithm, HashDomain};
+use crate::merkle::Hash;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Scheme used by the first releases: big-endian f32 leaves hashed with Keccak256 into a
+/// dense merkle tree, in the order of the scores artifact.
+pub const SCHEME_V1: &str = "keccak256-f32be-dense-merkle-v1";
+/// Same as `SCHEME_V1`, with big-endian f64 leaves.
+pub const SCHEME_V2: &str = "keccak256-f64be-dense-merkle-v2";
+/// Same as `SCHEME_V2`, with the scores tree hashed with BLAKE3. The meta tree combining the
+/// sub-job commitments is still hashed with Keccak256, as it is committed on-chain.
+pub const SCHEME_V3: &str = "blake3-f64be-dense-merkle-v3";
+
+/// Scheme new artifacts are produced with, unless their job asks for another precision.
+/// Kept at the f32 scheme, so jobs produced before the f64 one commit to the same leaves.
+pub const CURRENT_SCHEME_VERSION: &str = SCHEME_V1;
+
+/// Precision of the committed scores, set by the `precision` job param.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+    /// Scores are rounded to f32 and committed as f32 leaves, as by the first releases.
+    #[default]
+    F32,
+    /// Scores are committed as f64 leaves.
+    F64,
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f32" => Ok(Self::F32),
+            "f64" => Ok(Self::F64),
+            _ => Err(format!("Unknown precision: {}", s)),
+        }
+    }
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+impl Precision {
+    /// Domain of the scores trees of the precision: the manager contract verifies the f32
+    /// leaves, the f64 ones are only verified off-chain.
+    pub fn domain(&self) -> HashDomain {
+        match self {
+            Self::F32 => HashDomain::OnChain,
+            Self::F64 => HashDomain::OffChain,
+        }
+    }
+
+    /// Formats the score as written to the scores artifacts: f32 scores with the shortest
+    /// representation of the f32 value, as by the first releases.
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Self::F32 => (value as f32).to_string(),
+            Self::F64 => value.to_string(),
+        }
+    }
+
+    /// Parses a score as written to the scores artifacts, f32 scores as f32 values.
+    pub fn parse(&self, value: &str) -> Result<f64, std::num::ParseFloatError> {
+        match self {
+            Self::F32 => value.parse::<f32>().map(f64::from),
+            Self::F64 => value.parse(),
+        }
+    }
+}
+
+/// Set of rules used to encode scores and commit to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Scheme {
+    pub version: &'static str,
+    /// Encoding of a score before it is hashed into a leaf.
+    pub leaf_encoding: &'static str,
+    /// Hash function used for the leaves and the nodes of the scores tree.
+    pub hash: HashAlgorithm,
+    /// Shape of the scores and meta trees.
+    pub tree: &'static str,
+    /// Order of the leaves in the scores tree.
+    pub ordering: &'static str,
+    /// Format of the meta artifacts (job descriptions, results, manifests).
+    pub meta_format: &'static str,
+    #[serde(skip)]
+    pub precision: Precision,
+}
+
+impl Scheme {
+    /// Encodes the score as committed to.
+    pub fn leaf_bytes(&self, value: f64) -> Vec<u8> {
+        match self.precision {
+            Precision::F32 => (value as f32).to_be_bytes().to_vec(),
+            Precision::F64 => value.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Hashes the score into a leaf of the scores tree.
+    pub fn hash_leaf(&self, value: f64) -> Hash {
+        self.hash.hash_leaf(self.leaf_bytes(value))
+    }
+}
+
+/// All schemes, oldest first.
+pub const SCHEMES: &[Scheme] = &[
+    Scheme {
+        version: SCHEME_V1,
+        leaf_encoding: "f32-be",
+        hash: HashAlgorithm::Keccak256,
+        tree:
+            "dense-merkle, padded to a power of two, sub-job commitments combined into a meta tree",
+        ordering: "order of the rows in the scores artifact",
+        meta_format: "json, addressed by the keccak256 hash of its bytes",
+        precision: Precision::F32,
+    },
+    Scheme {
+        version: SCHEME_V2,
+        leaf_encoding: "f64-be",
+        hash: HashAlgorithm::Keccak256,
+        tree:
+            "dense-merkle, padded to a power of two, sub-job commitments combined into a meta tree",
+        ordering: "order of the rows in the scores artifact",
+        meta_format: "json, addressed by the keccak256 hash of its bytes",
+        precision: Precision::F64,
+    },
+    Scheme {
+        version: SCHEME_V3,
+        leaf_encoding: "f64-be",
+        hash: HashAlgorithm::Blake3,
+        tree: "dense-merkle, padded to a power of two, sub-job commitments combined into a keccak256 meta tree",
+        ordering: "order of the rows in the scores artifact",
+        meta_format: "json, addressed by the keccak256 hash of its bytes",
+        precision: Precision::F64,
+    },
+];
+
+/// Returns the scheme with the given version, if it is known.
+pub fn find(version: &str) -> Option<&'static Scheme> {
+    SCHEMES.iter().find(|s| s.version == version)
+}
+
+/// Returns the scheme every new artifact is produced with, by default.
+pub fn current() -> &'static Scheme {
+    find(CURRENT_SCHEME_VERSION).expect("current scheme must be listed")
+}
+
+/// Returns the Keccak256 scheme committing to scores of the given precision.
+pub fn for_precision(precision: Precision) -> &'static Scheme {
+    select(precision, HashAlgorithm::Keccak256).expect("scheme must be listed")
+}
+
+/// Returns the scheme committing to scores of the given precision with the hash function,
+/// or an error if the function can't be used for the trees of the precision (e.g. BLAKE3 for
+/// the f32 scores verified on-chain).
+pub fn select(
+    precision: Precision,
+    hash: HashAlgorithm,
+) -> Result<&'static Scheme, hashing::Error> {
+    hash.check(precision.domain())?;
+    Ok(SCHEMES
+        .iter()
+        .find(|s| s.precision == precision && s.hash == hash)
+        .expect("supported hash functions must have a scheme"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_list_current_scheme() {
+        assert_eq!(current().version, CURRENT_SCHEME_VERSION);
+        assert!(find("unknown").is_none());
+        for (i, scheme) in SCHEMES.iter().enumerate() {
+            assert!(SCHEMES[i + 1..].iter().all(|s| s.version != scheme.version));
+        }
+    }
+
+    #[test]
+    fn should_encode_leaves_with_the_scheme_precision() {
+        let value = 0.1f64;
+        let v1 = for_precision(Precision::F32);
+        assert_eq!(v1.version, SCHEME_V1);
+        assert_eq!(v1.leaf_bytes(value), 0.1f32.to_be_bytes());
+        assert_eq!(Precision::F32.format(0.1f32 as f64), "0.1");
+        assert_eq!(Precision::F32.parse("0.1"), Ok(0.1f32 as f64));
+
+        let v2 = for_precision(Precision::F64);
+        assert_eq!(v2.version, SCHEME_V2);
+        assert_eq!(v2.leaf_bytes(value), value.to_be_bytes());
+        assert_eq!(Precision::F64.format(0.1f32 as f64), "0.10000000149011612");
+        assert_eq!(Precision::F64.parse("0.1"), Ok(0.1));
+        assert_ne!(v1.hash_leaf(value), v2.hash_leaf(value));
+    }
+
+    #[test]
+    fn should_select_schemes_by_hash_domain() {
+        for scheme in SCHEMES {
+            assert!(scheme.hash.supports(scheme.precision.domain()));
+            assert_eq!(select(scheme.precision, scheme.hash), Ok(scheme));
+        }
+        let v3 = select(Precision::F64, HashAlgorithm::Blake3).unwrap();
+        assert_eq!(v3.version, SCHEME_V3);
+        assert_ne!(
+            v3.hash_leaf(0.1),
+            for_precision(Precision::F64).hash_leaf(0.1)
+        );
+        assert_eq!(
+            select(Precision::F32, HashAlgorithm::Blake3),
+            Err(hashing::Error::Unsupported(
+                HashAlgorithm::Blake3,
+                HashDomain::OnChain
+            ))
+        );
+    }
+}
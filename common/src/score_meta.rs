@@ -0,0 +1,180 @@
+use crate::ranking::{ranks, sort_scores, RankingMode};
+use crate::scheme::Precision;
+use crate::ScoreEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+
+/// The node is in the seed (pre-trust) set of the job.
+pub const FLAG_SEED: &str = "seed";
+/// The score is tied with at least another one.
+pub const FLAG_TIED: &str = "tied";
+/// The score is zero.
+pub const FLAG_ZERO: &str = "zero";
+/// Separator of the flags in the flags column.
+const FLAGS_SEPARATOR: char = '|';
+
+/// Metadata of a single score, as listed in the extended scores artifact.
+///
+/// The extended artifact (`i,v,r,p,f`) is a sidecar of the committed scores artifact, produced
+/// when the job params ask for it (`metadata=true`). Its rows are in the order of the scores
+/// artifact, so row `n` describes the leaf `n` of the scores tree, but it isn't committed to:
+/// the canonical scores artifact and its commitment are the same with or without it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreMetadata {
+    pub id: String,
+    pub value: f64,
+    /// 1-based rank of the score, ties sharing a rank.
+    pub rank: u64,
+    /// Percentage of the scores lower than or equal to this one, the top score being at 100.
+    pub percentile: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+}
+
+/// Builds the metadata of the scores, in their order. Ranks are assigned with the ranking
+/// mode of the job, competition ranking (1, 2, 2, 4) if it has none.
+pub fn build_metadata(
+    scores: &[ScoreEntry],
+    seed_ids: &HashSet<String>,
+    mode: Option<RankingMode>,
+) -> Vec<ScoreMetadata> {
+    let mut sorted = scores.to_vec();
+    sort_scores(&mut sorted);
+    let sorted_ranks = ranks(&sorted, mode.unwrap_or(RankingMode::Competition));
+
+    // Number of scores lower than or equal to each value, counted from the bottom of the ranking
+    let total = scores.len();
+    let mut at_or_below = vec![0; total];
+    let mut i = 0;
+    while i < total {
+        let mut j = i;
+        while j + 1 < total && sorted[j + 1].value() == sorted[i].value() {
+            j += 1;
+        }
+        for count in &mut at_or_below[i..=j] {
+            *count = total - i;
+        }
+        i = j + 1;
+    }
+
+    let mut by_id: HashMap<&str, (u64, f32, bool)> = HashMap::with_capacity(total);
+    for (k, score) in sorted.iter().enumerate() {
+        let tied = (k > 0 && sorted[k - 1].value() == score.value())
+            || (k + 1 < total && sorted[k + 1].value() == score.value());
+        let percentile = 100.0 * at_or_below[k] as f32 / total as f32;
+        by_id.insert(score.id(), (sorted_ranks[k], percentile, tied));
+    }
+
+    scores
+        .iter()
+        .map(|score| {
+            let (rank, percentile, tied) = by_id[score.id().as_str()];
+            let mut flags = Vec::new();
+            if seed_ids.contains(score.id()) {
+                flags.push(FLAG_SEED.to_string());
+            }
+            if tied {
+                flags.push(FLAG_TIED.to_string());
+            }
+            if *score.value() == 0.0 {
+                flags.push(FLAG_ZERO.to_string());
+            }
+            ScoreMetadata {
+                id: score.id().clone(),
+                value: *score.value(),
+                rank,
+                percentile,
+                flags,
+            }
+        })
+        .collect()
+}
+
+/// Writes the extended scores artifact (`i,v,r,p,f`), flags being separated by `|`.
+/// The leading `i,v` columns keep it readable as a plain score file, the values being
+/// formatted with the precision of the job.
+pub fn write_metadata_csv<W: Write>(
+    writer: W,
+    metadata: &[ScoreMetadata],
+    precision: Precision,
+) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(["i", "v", "r", "p", "f"])?;
+    for entry in metadata {
+        wtr.write_record([
+            entry.id.as_str(),
+            precision.format(entry.value).as_str(),
+            entry.rank.to_string().as_str(),
+            entry.percentile.to_string().as_str(),
+            entry.flags.join(&FLAGS_SEPARATOR.to_string()).as_str(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Parses the extended scores artifact.
+pub fn parse_metadata_csv<R: Read>(reader: R) -> Result<Vec<ScoreMetadata>, csv::Error> {
+    let mut rdr = csv::Reader::from_reader(reader);
+    let mut metadata = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let (id, value, rank, percentile, flags): (String, f64, u64, f32, String) =
+            record.deserialize(None)?;
+        metadata.push(ScoreMetadata {
+            id,
+            value,
+            rank,
+            percentile,
+            flags: flags
+                .split(FLAGS_SEPARATOR)
+                .filter(|flag| !flag.is_empty())
+                .map(str::to_string)
+                .collect(),
+        });
+    }
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_score_entries_from_file;
+
+    #[test]
+    fn should_build_and_parse_metadata() {
+        let scores: Vec<ScoreEntry> = [("d", 0.0), ("b", 0.5), ("c", 0.25), ("a", 0.25)]
+            .into_iter()
+            .map(|(id, v)| ScoreEntry::new(id.to_string(), v))
+            .collect();
+        let seed_ids = HashSet::from(["b".to_string()]);
+        let metadata = build_metadata(&scores, &seed_ids, None);
+
+        // Rows keep the order of the scores artifact
+        let ids: Vec<&str> = metadata.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["d", "b", "c", "a"]);
+        let ranks: Vec<u64> = metadata.iter().map(|m| m.rank).collect();
+        assert_eq!(ranks, [4, 1, 2, 2]);
+        let percentiles: Vec<f32> = metadata.iter().map(|m| m.percentile).collect();
+        assert_eq!(percentiles, [25.0, 100.0, 75.0, 75.0]);
+        assert_eq!(metadata[0].flags, [FLAG_ZERO]);
+        assert_eq!(metadata[1].flags, [FLAG_SEED]);
+        assert_eq!(metadata[2].flags, [FLAG_TIED]);
+
+        let dense = build_metadata(&scores, &seed_ids, Some(RankingMode::Dense));
+        assert_eq!(dense[0].rank, 3);
+
+        let mut bytes = Vec::new();
+        write_metadata_csv(&mut bytes, &metadata, Precision::F64).unwrap();
+        assert!(bytes.starts_with(b"i,v,r,p,f\nd,0,4,25,zero\n"));
+        assert_eq!(parse_metadata_csv(bytes.as_slice()).unwrap(), metadata);
+
+        // The extended artifact is still readable as a plain score file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scores-metadata.csv");
+        std::fs::write(&path, bytes).unwrap();
+        let parsed = parse_score_entries_from_file(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(parsed, scores);
+    }
+}
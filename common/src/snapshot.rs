@@ -0,0 +1,158 @@
+use crate::canonical;
+use crate::storage::artifact_key;
+use serde::{Deserialize, Serialize};
+
+/// Version of the snapshot manifests written by this release.
+pub const SNAPSHOT_MANIFEST_VERSION: u32 = 1;
+
+/// Manifest of a dataset snapshot: the trust and seed datasets a compute is requested on, and
+/// the snapshot they were derived from.
+///
+/// Manifests are content addressed, their id being the meta id of their canonical JSON (see
+/// `canonical::hash`). The dataset ids being the hashes of the datasets, the id of a manifest
+/// pins its whole lineage: changing a dataset or any ancestor changes the id of every
+/// descendant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub version: u32,
+    /// Content id (hex encoded Keccak256 hash) of the trust dataset
+    pub trust_id: String,
+    /// Content id (hex encoded Keccak256 hash) of the seed dataset
+    pub seed_id: String,
+    /// Number of entries of the trust dataset
+    pub trust_rows: u64,
+    /// Number of entries of the seed dataset
+    pub seed_rows: u64,
+    /// Unix timestamp (seconds) the snapshot was created at
+    pub created_at: u64,
+    /// Id of the manifest of the snapshot this one was derived from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl SnapshotManifest {
+    pub fn new(
+        trust_id: String,
+        trust_rows: u64,
+        seed_id: String,
+        seed_rows: u64,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            version: SNAPSHOT_MANIFEST_VERSION,
+            trust_id,
+            seed_id,
+            trust_rows,
+            seed_rows,
+            created_at,
+            parent: None,
+            description: None,
+        }
+    }
+
+    pub fn with_parent(mut self, parent: Option<String>) -> Self {
+        self.parent = parent.map(|parent| normalize_id(&parent));
+        self
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Returns the canonical JSON bytes of the manifest, along with its id.
+    pub fn encode(&self) -> Result<(Vec<u8>, String), Error> {
+        Ok(canonical::hash(self)?)
+    }
+
+    pub fn id(&self) -> Result<String, Error> {
+        Ok(self.encode()?.1)
+    }
+
+    /// Parses a manifest fetched by its id, checking it hashes to the id.
+    pub fn decode(bytes: &[u8], id: &str) -> Result<Self, Error> {
+        let manifest: Self = serde_json::from_slice(bytes)?;
+        if manifest.version > SNAPSHOT_MANIFEST_VERSION {
+            return Err(Error::UnsupportedVersion(manifest.version));
+        }
+        let expected = normalize_id(id);
+        let got = manifest.id()?;
+        if got != expected {
+            return Err(Error::IdMismatch { expected, got });
+        }
+        Ok(manifest)
+    }
+}
+
+/// Key of a snapshot manifest, under the key prefix of its tenant when there is one.
+pub fn snapshot_key(key_prefix: Option<&str>, id: &str) -> String {
+    artifact_key(key_prefix, "snapshot", &normalize_id(id))
+}
+
+fn normalize_id(id: &str) -> String {
+    id.trim_start_matches("0x").to_lowercase()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid snapshot manifest: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to encode snapshot manifest: {0}")]
+    Canonical(#[from] canonical::Error),
+    #[error("Unsupported snapshot manifest version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Snapshot manifest hashes to {got}, expected {expected}")]
+    IdMismatch { expected: String, got: String },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn manifest() -> SnapshotManifest {
+        SnapshotManifest::new("aa".repeat(32), 3, "bb".repeat(32), 2, 1_700_000_000)
+    }
+
+    #[test]
+    fn should_decode_manifests_by_id() {
+        let root = manifest();
+        let (bytes, id) = root.encode().unwrap();
+        assert_eq!(SnapshotManifest::decode(&bytes, &id).unwrap(), root);
+        // Pretty printed, or referenced with a 0x prefix
+        let pretty = serde_json::to_vec_pretty(&root).unwrap();
+        let prefixed = format!("0x{}", id.to_uppercase());
+        assert_eq!(SnapshotManifest::decode(&pretty, &prefixed).unwrap(), root);
+        assert_eq!(
+            snapshot_key(Some("acme"), &prefixed),
+            format!("acme/snapshot/{}", id)
+        );
+
+        // The parent is part of the id
+        let child = manifest().with_parent(Some(format!("0x{}", id)));
+        assert_eq!(child.parent, Some(id.clone()));
+        let (child_bytes, child_id) = child.encode().unwrap();
+        assert_ne!(child_id, id);
+        assert!(matches!(
+            SnapshotManifest::decode(&child_bytes, &id),
+            Err(Error::IdMismatch { .. })
+        ));
+
+        let mut tampered = root.clone();
+        tampered.trust_rows = 4;
+        let (tampered_bytes, _) = tampered.encode().unwrap();
+        assert!(matches!(
+            SnapshotManifest::decode(&tampered_bytes, &id),
+            Err(Error::IdMismatch { .. })
+        ));
+
+        let mut next = root;
+        next.version = SNAPSHOT_MANIFEST_VERSION + 1;
+        let (next_bytes, next_id) = next.encode().unwrap();
+        assert!(matches!(
+            SnapshotManifest::decode(&next_bytes, &next_id),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,1217 @@
+use crate::eigenda::{EigenDAError, EigenDAProxyClient};
+use alloy::hex;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Object storage the artifacts (trust, seed, scores, meta, manifests...) are read from and
+/// written to, addressed by keys such as `trust/<id>`.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Returns a human readable location of the storage, e.g. `s3://bucket`.
+    fn location(&self) -> String;
+
+    /// Reads the object, failing with `Error::NotFound` if it doesn't exist.
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error>;
+
+    /// Writes the object, replacing any previous version.
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error>;
+
+    /// Writes the object only if it doesn't exist, atomically: of concurrent writers of the
+    /// key, a single one succeeds. Returns whether the object was written.
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error>;
+
+    /// Removes the object, succeeding if it doesn't exist.
+    async fn delete(&self, key: &str) -> Result<(), Error>;
+
+    /// Returns the size of the object in bytes, `None` if it doesn't exist.
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error>;
+
+    /// Lists the keys starting with the prefix, in lexicographic order.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error>;
+
+    async fn exists(&self, key: &str) -> Result<bool, Error> {
+        Ok(self.size(key).await?.is_some())
+    }
+
+    /// Downloads the object to a local file.
+    async fn get_to_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        let data = self.get(key).await?;
+        fs::write(path, data).await.map_err(Error::Io)
+    }
+
+    /// Uploads a local file as the object.
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        let data = fs::read(path).await.map_err(Error::Io)?;
+        self.put(key, data).await
+    }
+
+    /// Returns the certificate the object is stored under, for backends addressing objects
+    /// by certificate (EigenDA), `None` otherwise.
+    async fn certificate(&self, _key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(None)
+    }
+
+    /// Makes an object stored under the certificate readable by its key, for backends
+    /// addressing objects by certificate (EigenDA). Ignored by the other backends.
+    async fn import_certificate(&self, _key: &str, _certificate: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Bucket the artifacts are stored in when none is configured.
+pub const DEFAULT_S3_BUCKET: &str = "openrank-data-dev";
+/// Region of the bucket when none is configured.
+pub const DEFAULT_S3_REGION: &str = "us-west-2";
+
+/// Location of the S3 bucket the artifacts are stored in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Config {
+    pub bucket: String,
+    pub region: String,
+    /// Endpoint of an S3 compatible service (e.g. MinIO or LocalStack), reached with
+    /// path-style addressing.
+    pub endpoint: Option<String>,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            bucket: DEFAULT_S3_BUCKET.to_string(),
+            region: DEFAULT_S3_REGION.to_string(),
+            endpoint: None,
+        }
+    }
+}
+
+impl S3Config {
+    /// Reads `S3_BUCKET`, `S3_REGION` and `S3_ENDPOINT_URL`, the defaults being used for
+    /// the unset (or empty) ones.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Self::default().with_overrides(var("S3_BUCKET"), var("S3_REGION"), var("S3_ENDPOINT_URL"))
+    }
+
+    /// Replaces the values given, e.g. by command line flags.
+    pub fn with_overrides(
+        mut self,
+        bucket: Option<String>,
+        region: Option<String>,
+        endpoint: Option<String>,
+    ) -> Self {
+        if let Some(bucket) = bucket {
+            self.bucket = bucket;
+        }
+        if let Some(region) = region {
+            self.region = region;
+        }
+        if endpoint.is_some() {
+            self.endpoint = endpoint;
+        }
+        self
+    }
+
+    /// Returns the public URL of the object, path-style under a custom endpoint.
+    pub fn object_url(&self, key: &str) -> String {
+        match &self.endpoint {
+            Some(endpoint) => format!("{}/{}/{}", endpoint.trim_end_matches('/'), self.bucket, key),
+            None => format!(
+                "https://{}.s3.{}.amazonaws.com/{}",
+                self.bucket, self.region, key
+            ),
+        }
+    }
+}
+
+/// Ranged downloads of the objects to files: the chunks of an object are fetched
+/// concurrently and written to the file in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelDownload {
+    /// Size of the chunks, in bytes.
+    pub chunk_size: u64,
+    /// Number of chunks fetched at the same time, 1 downloading objects in a single request.
+    pub concurrency: usize,
+}
+
+impl Default for ParallelDownload {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16 * 1024 * 1024,
+            concurrency: 8,
+        }
+    }
+}
+
+impl ParallelDownload {
+    /// Reads `S3_DOWNLOAD_CHUNK_MB` and `S3_DOWNLOAD_CONCURRENCY`, the defaults (16 MiB
+    /// chunks, 8 at a time) being used for the unset ones.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        let default = Self::default();
+        Self {
+            chunk_size: var("S3_DOWNLOAD_CHUNK_MB")
+                .filter(|mb| *mb > 0)
+                .map_or(default.chunk_size, |mb| mb * 1024 * 1024),
+            concurrency: var("S3_DOWNLOAD_CONCURRENCY")
+                .filter(|n| *n > 0)
+                .map_or(default.concurrency, |n| n as usize),
+        }
+    }
+}
+
+/// Returns the total size of the object from the `Content-Range` of a ranged response,
+/// e.g. `bytes 0-1023/4096`.
+fn content_range_total(content_range: &str) -> Option<u64> {
+    let (range, total) = content_range.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    start.parse::<u64>().ok()?;
+    end.parse::<u64>().ok()?;
+    total.parse().ok()
+}
+
+/// Storage backed by an S3 (or S3 compatible, e.g. MinIO) bucket.
+#[derive(Debug, Clone)]
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+    download: ParallelDownload,
+}
+
+impl S3Storage {
+    pub fn new(client: Client, bucket: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            download: ParallelDownload::default(),
+        }
+    }
+
+    pub fn with_parallel_download(mut self, download: ParallelDownload) -> Self {
+        self.download = download;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn get_object(
+        &self,
+        key: &str,
+    ) -> Result<aws_sdk_s3::operation::get_object::GetObjectOutput, Error> {
+        self.client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(se) if se.is_no_such_key() => Error::NotFound(key.to_string()),
+                _ => Error::S3(Box::new(e.into())),
+            })
+    }
+
+    /// Fetches the bytes `[start, end)` of the object, failing if it no longer has the ETag
+    /// of the previous chunks.
+    async fn get_range(
+        &self,
+        key: &str,
+        start: u64,
+        end: u64,
+        etag: Option<&str>,
+    ) -> Result<Vec<u8>, Error> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes={}-{}", start, end - 1))
+            .set_if_match(etag.map(str::to_string))
+            .send()
+            .await
+            .map_err(|e| Error::S3(Box::new(e.into())))?;
+        let bytes = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| Error::Stream(e.to_string()))?
+            .to_vec();
+        if bytes.len() as u64 != end - start {
+            return Err(Error::Stream(format!(
+                "Range {}-{} of {}: got {} bytes",
+                start,
+                end,
+                key,
+                bytes.len()
+            )));
+        }
+        Ok(bytes)
+    }
+
+    async fn write_body(file: &mut fs::File, mut body: ByteStream) -> Result<(), Error> {
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| Error::Stream(e.to_string()))?;
+            file.write_all(&chunk).await.map_err(Error::Io)?;
+        }
+        Ok(())
+    }
+
+    async fn put_object(&self, key: &str, body: ByteStream) -> Result<(), Error> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::S3(Box::new(e.into())))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for S3Storage {
+    fn location(&self) -> String {
+        format!("s3://{}", self.bucket)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        let output = self.get_object(key).await?;
+        let bytes = output
+            .body
+            .collect()
+            .await
+            .map_err(|e| Error::Stream(e.to_string()))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        self.put_object(key, ByteStream::from(data)).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error> {
+        let res = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .if_none_match("*")
+            .body(ByteStream::from(data))
+            .send()
+            .await;
+        match res {
+            Ok(_) => Ok(true),
+            // 412 when the object exists, 409 when a concurrent conditional write won
+            Err(e)
+                if e.raw_response()
+                    .is_some_and(|r| matches!(r.status().as_u16(), 409 | 412)) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(Error::S3(Box::new(e.into()))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| Error::S3(Box::new(e.into())))?;
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error> {
+        let res = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await;
+        match res {
+            Ok(head) => Ok(Some(
+                u64::try_from(head.content_length().unwrap_or_default()).unwrap_or_default(),
+            )),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(Error::S3(Box::new(e.into()))),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let res = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|e| Error::S3(Box::new(e.into())))?;
+            keys.extend(
+                res.contents()
+                    .iter()
+                    .filter_map(|o| o.key().map(str::to_string)),
+            );
+            continuation_token = res.next_continuation_token().map(str::to_string);
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Streams the object to the file, without loading it in memory. Objects larger than a
+    /// chunk are fetched with concurrent ranged requests, falling back to a single request
+    /// when the server doesn't support ranges.
+    async fn get_to_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        if self.download.concurrency <= 1 {
+            let output = self.get_object(key).await?;
+            let mut file = fs::File::create(path).await.map_err(Error::Io)?;
+            Self::write_body(&mut file, output.body).await?;
+            return file.flush().await.map_err(Error::Io);
+        }
+
+        let chunk_size = self.download.chunk_size.max(1);
+        let res = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .range(format!("bytes=0-{}", chunk_size - 1))
+            .send()
+            .await;
+        let first = match res {
+            Ok(output) => output,
+            // Empty objects have no range to satisfy
+            Err(e) if e.raw_response().is_some_and(|r| r.status().as_u16() == 416) => {
+                self.get_object(key).await?
+            }
+            Err(e) => {
+                return Err(match e.as_service_error() {
+                    Some(se) if se.is_no_such_key() => Error::NotFound(key.to_string()),
+                    _ => Error::S3(Box::new(e.into())),
+                })
+            }
+        };
+        let total = first.content_range().and_then(content_range_total);
+        let etag = first.e_tag().map(str::to_string);
+        let mut file = fs::File::create(path).await.map_err(Error::Io)?;
+        // Without a content range, the server sent the whole object
+        Self::write_body(&mut file, first.body).await?;
+        let Some(total) = total else {
+            return file.flush().await.map_err(Error::Io);
+        };
+
+        let mut chunks =
+            futures_util::stream::iter((chunk_size..total).step_by(chunk_size as usize))
+                .map(|start| {
+                    let end = (start + chunk_size).min(total);
+                    self.get_range(key, start, end, etag.as_deref())
+                })
+                .buffered(self.download.concurrency);
+        while let Some(chunk) = chunks.next().await {
+            file.write_all(&chunk?).await.map_err(Error::Io)?;
+        }
+        file.flush().await.map_err(Error::Io)
+    }
+
+    /// Streams the file to the object, without loading it in memory.
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        let body = ByteStream::from_path(path)
+            .await
+            .map_err(|e| Error::Stream(e.to_string()))?;
+        self.put_object(key, body).await
+    }
+}
+
+/// Storage backed by a local directory, each object being a file at `<root>/<key>`.
+/// Meant for tests and local runs without AWS credentials.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path of the object, rejecting keys escaping the root directory.
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        let relative = Path::new(key);
+        let valid = !key.is_empty()
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !valid {
+            return Err(Error::InvalidKey(key.to_string()));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    fn location(&self) -> String {
+        format!("file://{}", self.root.display())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(Error::NotFound(key.to_string()))
+            }
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// Writes to a temporary file renamed into place, so readers never see a partial object.
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(Error::Io)?;
+        }
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".part");
+        fs::write(&tmp_path, data).await.map_err(Error::Io)?;
+        fs::rename(&tmp_path, &path).await.map_err(Error::Io)
+    }
+
+    /// Links a temporary file into place, linking failing if the object exists.
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error> {
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(Error::Io)?;
+        }
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(format!(
+            ".{}-{}.part",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp_path, data).await.map_err(Error::Io)?;
+        let res = fs::hard_link(&tmp_path, &path).await;
+        let _ = fs::remove_file(&tmp_path).await;
+        match res {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::Io(e)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error> {
+        match fs::metadata(self.path(key)?).await {
+            Ok(metadata) if metadata.is_file() => Ok(Some(metadata.len())),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut keys = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(Error::Io(e)),
+            };
+            while let Some(entry) = entries.next_entry().await.map_err(Error::Io)? {
+                let path = entry.path();
+                if entry.file_type().await.map_err(Error::Io)?.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&self.root) else {
+                    continue;
+                };
+                let key = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if key.starts_with(prefix) && !key.ends_with(".part") {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Prefix of the certificate index of the EigenDA storage.
+pub const EIGENDA_INDEX_PREFIX: &str = "eigenda/";
+
+/// Certificate of an object stored in EigenDA, kept in the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CertificateEntry {
+    /// Hex encoded certificate, as returned by the EigenDA proxy.
+    certificate: String,
+    size: u64,
+}
+
+/// Storage keeping the objects in EigenDA blobs, through the EigenDA proxy.
+///
+/// Blobs are addressed by certificate, so the certificate of every key is recorded in an index
+/// backend (e.g. S3) under `eigenda/<key>`. Job descriptions and results also carry the
+/// certificates of their artifacts, which readers import instead of relying on the index.
+pub struct EigenDAStorage {
+    client: EigenDAProxyClient,
+    index: Arc<dyn StorageBackend>,
+    /// Certificates imported from job descriptions and results, by key.
+    imported: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl EigenDAStorage {
+    pub fn new(client: EigenDAProxyClient, index: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            client,
+            index,
+            imported: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn index_key(key: &str) -> String {
+        format!("{}{}", EIGENDA_INDEX_PREFIX, key)
+    }
+
+    async fn entry(&self, key: &str) -> Result<Option<CertificateEntry>, Error> {
+        let bytes = match self.index.get(&Self::index_key(key)).await {
+            Ok(bytes) => bytes,
+            Err(Error::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| Error::Index(format!("{}: {}", key, e)))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for EigenDAStorage {
+    fn location(&self) -> String {
+        format!("eigenda (index: {})", self.index.location())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        let certificate = self
+            .certificate(key)
+            .await?
+            .ok_or_else(|| Error::NotFound(key.to_string()))?;
+        self.client
+            .get_meta(certificate)
+            .await
+            .map_err(Error::EigenDA)
+    }
+
+    /// Disperses the object (split in blobs if needed) and records its certificate.
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let size = data.len() as u64;
+        let certificate = self.client.put_meta(data).await.map_err(Error::EigenDA)?;
+        let entry = CertificateEntry {
+            certificate: hex::encode(&certificate),
+            size,
+        };
+        let bytes = serde_json::to_vec(&entry).map_err(|e| Error::Index(e.to_string()))?;
+        self.index.put(&Self::index_key(key), bytes).await
+    }
+
+    /// Disperses the object, and records its certificate unless the key is already indexed.
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error> {
+        let size = data.len() as u64;
+        let certificate = self.client.put_meta(data).await.map_err(Error::EigenDA)?;
+        let entry = CertificateEntry {
+            certificate: hex::encode(&certificate),
+            size,
+        };
+        let bytes = serde_json::to_vec(&entry).map_err(|e| Error::Index(e.to_string()))?;
+        self.index.put_if_absent(&Self::index_key(key), bytes).await
+    }
+
+    /// Removes the certificate from the index, blobs can't be deleted.
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        self.imported.lock().unwrap().remove(key);
+        self.index.delete(&Self::index_key(key)).await
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error> {
+        if let Some(entry) = self.entry(key).await? {
+            return Ok(Some(entry.size));
+        }
+        if !self.imported.lock().unwrap().contains_key(key) {
+            return Ok(None);
+        }
+        Ok(Some(self.get(key).await?.len() as u64))
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let keys = self.index.list(&Self::index_key(prefix)).await?;
+        Ok(keys
+            .iter()
+            .filter_map(|key| key.strip_prefix(EIGENDA_INDEX_PREFIX))
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn certificate(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        if let Some(certificate) = self.imported.lock().unwrap().get(key) {
+            return Ok(Some(certificate.clone()));
+        }
+        let Some(entry) = self.entry(key).await? else {
+            return Ok(None);
+        };
+        hex::decode(&entry.certificate)
+            .map(Some)
+            .map_err(|e| Error::Index(format!("{}: {}", key, e)))
+    }
+
+    async fn import_certificate(&self, key: &str, certificate: &[u8]) -> Result<(), Error> {
+        self.imported
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), certificate.to_vec());
+        Ok(())
+    }
+}
+
+/// Keys of the CSV artifacts compressed by `CompressedStorage`.
+const COMPRESSED_PREFIXES: [&str; 3] = ["trust/", "seed/", "scores/"];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of the CSV artifacts written to the storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => Err(Error::Compression(format!(
+                "Unknown compression: {} (expected none, gzip or zstd)",
+                other
+            ))),
+        }
+    }
+}
+
+impl Compression {
+    /// Reads `STORAGE_COMPRESSION` (none, gzip or zstd), defaulting to none.
+    pub fn from_env() -> Result<Self, Error> {
+        std::env::var("STORAGE_COMPRESSION")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(Self::None))
+    }
+
+    pub fn compress(&self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let compressed = match self {
+            Self::None => return Ok(data),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&data).and_then(|_| encoder.finish())
+            }
+            Self::Zstd => zstd::encode_all(data.as_slice(), 0),
+        };
+        compressed.map_err(|e| Error::Compression(e.to_string()))
+    }
+}
+
+/// Decompresses gzip or zstd data, recognized by its magic bytes, other data being returned
+/// as is. A CSV never starts with these bytes, so plain and compressed objects can be mixed.
+pub fn decompress(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let decompressed = if data.starts_with(&ZSTD_MAGIC) {
+        zstd::decode_all(data.as_slice())
+    } else if data.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut decompressed)
+            .map(|_| decompressed)
+    } else {
+        return Ok(data);
+    };
+    decompressed.map_err(|e| Error::Compression(e.to_string()))
+}
+
+/// Decompresses the file to `dst` like `decompress`, streaming it.
+fn decompress_file(src: &Path, dst: &Path) -> Result<(), Error> {
+    let mut magic = [0u8; 4];
+    let mut file = std::fs::File::open(src).map_err(Error::Io)?;
+    let read = file.read(&mut magic).map_err(Error::Io)?;
+    let magic = &magic[..read];
+    if !magic.starts_with(&ZSTD_MAGIC) && !magic.starts_with(&GZIP_MAGIC) {
+        return std::fs::rename(src, dst).map_err(Error::Io);
+    }
+    let src = std::io::BufReader::new(std::fs::File::open(src).map_err(Error::Io)?);
+    let mut dst = std::fs::File::create(dst).map_err(Error::Io)?;
+    let copied = if magic.starts_with(&ZSTD_MAGIC) {
+        zstd::Decoder::with_buffer(src)
+            .and_then(|mut decoder| std::io::copy(&mut decoder, &mut dst))
+    } else {
+        std::io::copy(&mut GzDecoder::new(src), &mut dst)
+    };
+    copied.map_err(|e| Error::Compression(e.to_string()))?;
+    Ok(())
+}
+
+/// Whether the key is a CSV artifact, possibly under a tenant key prefix.
+fn is_compressed_key(key: &str) -> bool {
+    COMPRESSED_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix) || key.contains(&format!("/{}", prefix)))
+}
+
+/// Key of the uncompressed size of a compressed object, recorded when it is written.
+fn size_key(key: &str) -> String {
+    format!("size/{}", key)
+}
+
+/// Storage compressing the trust, seed and scores CSVs it writes, and decompressing every
+/// compressed object it reads whatever the configured compression, so that objects written
+/// before compression was enabled (or with another one) stay readable.
+///
+/// Keys, content ids and sizes are the ones of the uncompressed CSVs, so the compression is
+/// invisible to the callers. The uncompressed size of the CSVs is recorded next to them
+/// (under `size/<key>`) when they are written, so that it is known without reading them.
+pub struct CompressedStorage {
+    inner: Arc<dyn StorageBackend>,
+    compression: Compression,
+}
+
+impl CompressedStorage {
+    pub fn new(inner: Arc<dyn StorageBackend>, compression: Compression) -> Self {
+        Self { inner, compression }
+    }
+
+    /// Records the uncompressed size of the CSV, then compresses it. The size is written
+    /// first, so that it is there whenever the object is.
+    async fn record_and_compress(&self, key: &str, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let len = data.len().to_string().into_bytes();
+        self.inner.put(&size_key(key), len).await?;
+        let compression = self.compression;
+        tokio::task::spawn_blocking(move || compression.compress(data))
+            .await
+            .map_err(|e| Error::Compression(e.to_string()))?
+    }
+}
+
+#[async_trait]
+impl StorageBackend for CompressedStorage {
+    fn location(&self) -> String {
+        format!(
+            "{} ({:?} compression)",
+            self.inner.location(),
+            self.compression
+        )
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        let data = self.inner.get(key).await?;
+        tokio::task::spawn_blocking(move || decompress(data))
+            .await
+            .map_err(|e| Error::Compression(e.to_string()))?
+    }
+
+    /// Downloads the object with the inner storage (e.g. with ranged requests), then
+    /// decompresses the file, so that large objects are never held in memory.
+    async fn get_to_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        let mut download_path = path.as_os_str().to_owned();
+        download_path.push(".download");
+        let download_path = PathBuf::from(download_path);
+        self.inner.get_to_file(key, &download_path).await?;
+        let path = path.to_path_buf();
+        let res = tokio::task::spawn_blocking({
+            let download_path = download_path.clone();
+            move || decompress_file(&download_path, &path)
+        })
+        .await
+        .map_err(|e| Error::Compression(e.to_string()))?;
+        let _ = fs::remove_file(&download_path).await;
+        res
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        if !is_compressed_key(key) {
+            return self.inner.put(key, data).await;
+        }
+        let data = self.record_and_compress(key, data).await?;
+        self.inner.put(key, data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error> {
+        if !is_compressed_key(key) {
+            return self.inner.put_if_absent(key, data).await;
+        }
+        // Content addressed, an object already there has the same size
+        let data = self.record_and_compress(key, data).await?;
+        self.inner.put_if_absent(key, data).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        self.inner.delete(key).await?;
+        if !is_compressed_key(key) {
+            return Ok(());
+        }
+        match self.inner.delete(&size_key(key)).await {
+            Ok(()) | Err(Error::NotFound(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the uncompressed size recorded when the CSV was written. CSVs without one were
+    /// written uncompressed, their size being the one of the object.
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error> {
+        let size = self.inner.size(key).await?;
+        if size.is_none() || !is_compressed_key(key) {
+            return Ok(size);
+        }
+        match self.inner.get(&size_key(key)).await {
+            Ok(recorded) => String::from_utf8_lossy(&recorded)
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| Error::Compression(format!("Invalid size of {}: {}", key, e))),
+            Err(Error::NotFound(_)) => Ok(size),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Checks the object only, never reading it.
+    async fn exists(&self, key: &str) -> Result<bool, Error> {
+        Ok(self.inner.size(key).await?.is_some())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        self.inner.list(prefix).await
+    }
+
+    async fn certificate(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        self.inner.certificate(key).await
+    }
+
+    async fn import_certificate(&self, key: &str, certificate: &[u8]) -> Result<(), Error> {
+        self.inner.import_certificate(key, certificate).await
+    }
+}
+
+/// Whether the key prefix of a tenant or deployment is valid, as it is used both in object keys
+/// and as a directory name: letters, digits, '-', '_' and '.', not starting with a '.'.
+pub fn is_valid_key_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && !prefix.starts_with('.')
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Key of an artifact (e.g. `trust/<id>`), under the key prefix of its tenant when there is one
+/// (e.g. `acme/trust/<id>`).
+pub fn artifact_key(key_prefix: Option<&str>, kind: &str, id: &str) -> String {
+    match key_prefix {
+        Some(prefix) => format!("{}/{}/{}", prefix, kind, id),
+        None => format!("{}/{}", kind, id),
+    }
+}
+
+/// Storage keeping the objects under a key prefix of the inner storage (e.g. `tenant-a/`), so
+/// that several deployments share a bucket without their keys colliding.
+///
+/// The prefix is invisible to the callers: keys are given and listed without it.
+pub struct PrefixedStorage {
+    inner: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+impl PrefixedStorage {
+    pub fn new(inner: Arc<dyn StorageBackend>, prefix: &str) -> Self {
+        Self {
+            inner,
+            prefix: format!("{}/", prefix.trim_matches('/')),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for PrefixedStorage {
+    fn location(&self) -> String {
+        format!("{} (prefix: {})", self.inner.location(), self.prefix)
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        self.inner.get(&self.key(key)).await
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        self.inner.put(&self.key(key), data).await
+    }
+
+    async fn put_if_absent(&self, key: &str, data: Vec<u8>) -> Result<bool, Error> {
+        self.inner.put_if_absent(&self.key(key), data).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        self.inner.delete(&self.key(key)).await
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>, Error> {
+        self.inner.size(&self.key(key)).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let keys = self.inner.list(&self.key(prefix)).await?;
+        Ok(keys
+            .iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix))
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn get_to_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        self.inner.get_to_file(&self.key(key), path).await
+    }
+
+    async fn put_file(&self, key: &str, path: &Path) -> Result<(), Error> {
+        self.inner.put_file(&self.key(key), path).await
+    }
+
+    async fn certificate(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        self.inner.certificate(&self.key(key)).await
+    }
+
+    async fn import_certificate(&self, key: &str, certificate: &[u8]) -> Result<(), Error> {
+        self.inner
+            .import_certificate(&self.key(key), certificate)
+            .await
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Object not found: {0}")]
+    NotFound(String),
+    #[error("Invalid object key: {0}")]
+    InvalidKey(String),
+    #[error("IO error: {0}")]
+    Io(std::io::Error),
+    #[error("S3 error: {0}")]
+    S3(Box<aws_sdk_s3::Error>),
+    #[error("Stream error: {0}")]
+    Stream(String),
+    #[error("EigenDA error: {0}")]
+    EigenDA(EigenDAError),
+    #[error("Invalid EigenDA index entry: {0}")]
+    Index(String),
+    #[error("Compression error: {0}")]
+    Compression(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_content_range() {
+        assert_eq!(content_range_total("bytes 0-1023/4096"), Some(4096));
+        assert_eq!(content_range_total("bytes 4000-4095/4096"), Some(4096));
+        // Unknown total size
+        assert_eq!(content_range_total("bytes 0-1023/*"), None);
+        assert_eq!(content_range_total("bytes */4096"), None);
+        assert_eq!(content_range_total("items 0-1/2"), None);
+    }
+
+    #[test]
+    fn should_build_object_urls() {
+        let config = S3Config::default();
+        assert_eq!(
+            config.object_url("scores/ab"),
+            "https://openrank-data-dev.s3.us-west-2.amazonaws.com/scores/ab"
+        );
+
+        let config = config.with_overrides(
+            Some("scores-bucket".to_string()),
+            None,
+            Some("http://localhost:9000/".to_string()),
+        );
+        assert_eq!(config.region, DEFAULT_S3_REGION);
+        assert_eq!(
+            config.object_url("scores/ab"),
+            "http://localhost:9000/scores-bucket/scores/ab"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_store_objects_on_local_disk() {
+        let root = std::env::temp_dir().join(format!("openrank-storage-{}", std::process::id()));
+        let storage = LocalStorage::new(&root);
+
+        storage.put("trust/a", b"1,2,3".to_vec()).await.unwrap();
+        storage.put("trust/b", b"4".to_vec()).await.unwrap();
+        storage.put("seed/a", b"5".to_vec()).await.unwrap();
+        assert_eq!(storage.get("trust/a").await.unwrap(), b"1,2,3");
+        assert_eq!(storage.size("trust/a").await.unwrap(), Some(5));
+        assert_eq!(
+            storage.list("trust/").await.unwrap(),
+            vec!["trust/a".to_string(), "trust/b".to_string()]
+        );
+
+        assert!(!storage
+            .put_if_absent("trust/b", b"6".to_vec())
+            .await
+            .unwrap());
+        assert!(storage
+            .put_if_absent("trust/c", b"7".to_vec())
+            .await
+            .unwrap());
+        assert_eq!(storage.get("trust/b").await.unwrap(), b"4");
+        assert_eq!(storage.get("trust/c").await.unwrap(), b"7");
+
+        storage.delete("trust/a").await.unwrap();
+        assert!(!storage.exists("trust/a").await.unwrap());
+        assert!(matches!(
+            storage.get("trust/a").await,
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            storage.get("../outside").await,
+            Err(Error::InvalidKey(_))
+        ));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn should_build_tenant_artifact_keys() {
+        assert_eq!(artifact_key(None, "trust", "ab"), "trust/ab");
+        assert_eq!(artifact_key(Some("acme"), "scores", "ab"), "acme/scores/ab");
+        assert!(is_compressed_key("acme/trust/ab"));
+        assert!(!is_compressed_key("acme/meta/ab"));
+
+        assert!(is_valid_key_prefix("tenant-a.v2"));
+        assert!(!is_valid_key_prefix(""));
+        assert!(!is_valid_key_prefix(".."));
+        assert!(!is_valid_key_prefix("acme/trust"));
+    }
+
+    #[tokio::test]
+    async fn should_scope_keys_under_prefix() {
+        let root = std::env::temp_dir().join(format!("openrank-prefix-{}", std::process::id()));
+        let local: Arc<dyn StorageBackend> = Arc::new(LocalStorage::new(&root));
+        let tenant_a = PrefixedStorage::new(local.clone(), "tenant-a/");
+        let tenant_b = PrefixedStorage::new(local.clone(), "tenant-b");
+
+        tenant_a.put("manifest/1", b"a".to_vec()).await.unwrap();
+        tenant_b.put("manifest/1", b"b".to_vec()).await.unwrap();
+        assert_eq!(tenant_a.get("manifest/1").await.unwrap(), b"a");
+        assert_eq!(tenant_b.get("manifest/1").await.unwrap(), b"b");
+        assert_eq!(local.get("tenant-a/manifest/1").await.unwrap(), b"a");
+        assert_eq!(
+            tenant_a.list("manifest/").await.unwrap(),
+            vec!["manifest/1".to_string()]
+        );
+
+        tenant_a.delete("manifest/1").await.unwrap();
+        assert!(!tenant_a.exists("manifest/1").await.unwrap());
+        assert!(tenant_b.exists("manifest/1").await.unwrap());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_compress_csv_artifacts() {
+        let root = std::env::temp_dir().join(format!("openrank-compress-{}", std::process::id()));
+        let local: Arc<dyn StorageBackend> = Arc::new(LocalStorage::new(&root));
+        let csv = b"i,j,v\na,b,1.0\nb,c,2.0\n".to_vec();
+
+        local.put("trust/plain", csv.clone()).await.unwrap();
+        let gzip = CompressedStorage::new(local.clone(), Compression::Gzip);
+        gzip.put("trust/gzip", csv.clone()).await.unwrap();
+        let zstd = CompressedStorage::new(local.clone(), Compression::Zstd);
+        zstd.put("trust/zstd", csv.clone()).await.unwrap();
+        zstd.put("meta/a", csv.clone()).await.unwrap();
+
+        assert!(local
+            .get("trust/zstd")
+            .await
+            .unwrap()
+            .starts_with(&ZSTD_MAGIC));
+        assert!(local
+            .get("trust/gzip")
+            .await
+            .unwrap()
+            .starts_with(&GZIP_MAGIC));
+        assert_eq!(local.get("meta/a").await.unwrap(), csv);
+        for key in ["trust/plain", "trust/gzip", "trust/zstd", "meta/a"] {
+            assert_eq!(zstd.size(key).await.unwrap(), Some(csv.len() as u64));
+            assert!(zstd.exists(key).await.unwrap());
+        }
+        // The recorded size is used, the object isn't read
+        local.put("trust/zstd", ZSTD_MAGIC.to_vec()).await.unwrap();
+        assert_eq!(
+            gzip.size("trust/zstd").await.unwrap(),
+            Some(csv.len() as u64)
+        );
+        zstd.put("trust/zstd", csv.clone()).await.unwrap();
+        assert!(!zstd.exists("trust/missing").await.unwrap());
+        // Every object is readable whatever the compression it was written with
+        for key in ["trust/plain", "trust/gzip", "trust/zstd", "meta/a"] {
+            assert_eq!(gzip.get(key).await.unwrap(), csv);
+            let path = root.join("download.csv");
+            gzip.get_to_file(key, &path).await.unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), csv);
+        }
+        zstd.delete("trust/zstd").await.unwrap();
+        assert!(!local.exists("size/trust/zstd").await.unwrap());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
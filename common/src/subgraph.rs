@@ -0,0 +1,178 @@
+use crate::blocklist::{check_id, parse_id_list, Blocklist, Error};
+use crate::{ScoreEntry, TrustEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Ids a job is restricted to: compute runs over the subgraph they induce, i.e. the edges
+/// whose endpoints are both included.
+///
+/// Uses the same format as the `Blocklist`, and is stored under `include/{id}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeList {
+    id: String,
+    ids: HashSet<String>,
+}
+
+impl IncludeList {
+    /// Parses the include-list from its bytes.
+    pub fn new(bytes: &[u8]) -> Result<Self, Error> {
+        let (id, ids) = parse_id_list(bytes)?;
+        Ok(Self { id, ids })
+    }
+
+    /// Parses the include-list, checking the bytes match the expected include-list id.
+    pub fn from_bytes_with_id(bytes: &[u8], include_list_id: &str) -> Result<Self, Error> {
+        let include_list = Self::new(bytes)?;
+        check_id(&include_list.id, include_list_id)?;
+        Ok(include_list)
+    }
+
+    /// Returns the include-list id (hex encoded Keccak256 hash of the file bytes).
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+}
+
+/// Number of trust edges and seed entries kept and dropped by a `NodeFilter`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterStats {
+    pub kept_edges: usize,
+    pub dropped_edges: usize,
+    pub kept_seeds: usize,
+    pub dropped_seeds: usize,
+}
+
+/// Nodes a job runs over: the included ones if there is an include-list, minus the blocked
+/// ones. Applied identically by the computer and the verification commands.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NodeFilter<'a> {
+    pub blocklist: Option<&'a Blocklist>,
+    pub include_list: Option<&'a IncludeList>,
+}
+
+impl<'a> NodeFilter<'a> {
+    pub fn new(blocklist: Option<&'a Blocklist>, include_list: Option<&'a IncludeList>) -> Self {
+        Self {
+            blocklist,
+            include_list,
+        }
+    }
+
+    /// Checks whether the filter drops nothing.
+    pub fn is_noop(&self) -> bool {
+        self.blocklist.is_none() && self.include_list.is_none()
+    }
+
+    pub fn keeps_node(&self, id: &str) -> bool {
+        self.include_list.is_none_or(|list| list.contains(id))
+            && !self.blocklist.is_some_and(|list| list.contains(id))
+    }
+
+    /// Checks whether the edge is in the subgraph, i.e. both endpoints are kept.
+    pub fn keeps_edge(&self, from: &str, to: &str) -> bool {
+        self.keeps_node(from) && self.keeps_node(to)
+    }
+
+    /// Keeps the trust entry if it is in the subgraph, updating the stats.
+    /// Meant to be called while parsing, so dropped edges are never collected.
+    pub fn keep_trust(&self, entry: &TrustEntry, stats: &mut FilterStats) -> bool {
+        let keep = self.keeps_edge(entry.from(), entry.to());
+        if keep {
+            stats.kept_edges += 1;
+        } else {
+            stats.dropped_edges += 1;
+        }
+        keep
+    }
+
+    /// Keeps the seed entry if its node is kept, updating the stats.
+    pub fn keep_seed(&self, entry: &ScoreEntry, stats: &mut FilterStats) -> bool {
+        let keep = self.keeps_node(entry.id());
+        if keep {
+            stats.kept_seeds += 1;
+        } else {
+            stats.dropped_seeds += 1;
+        }
+        keep
+    }
+
+    pub fn filter_trust(
+        &self,
+        entries: Vec<TrustEntry>,
+        stats: &mut FilterStats,
+    ) -> Vec<TrustEntry> {
+        entries
+            .into_iter()
+            .filter(|e| self.keep_trust(e, stats))
+            .collect()
+    }
+
+    pub fn filter_seed(
+        &self,
+        entries: Vec<ScoreEntry>,
+        stats: &mut FilterStats,
+    ) -> Vec<ScoreEntry> {
+        entries
+            .into_iter()
+            .filter(|e| self.keep_seed(e, stats))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_induce_subgraph() {
+        let include_list = IncludeList::new(b"alice\nbob\ncarol\n").unwrap();
+        let blocklist = Blocklist::new(b"carol\n").unwrap();
+        let trust = vec![
+            TrustEntry::new("alice".into(), "bob".into(), 1.0),
+            TrustEntry::new("alice".into(), "dave".into(), 1.0),
+            TrustEntry::new("bob".into(), "carol".into(), 1.0),
+            TrustEntry::new("erin".into(), "frank".into(), 1.0),
+        ];
+
+        let mut stats = FilterStats::default();
+        let filter = NodeFilter::new(None, Some(&include_list));
+        let kept = filter.filter_trust(trust.clone(), &mut stats);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(stats.kept_edges, 2);
+        assert_eq!(stats.dropped_edges, 2);
+
+        // Blocked nodes are dropped even if included
+        let mut stats = FilterStats::default();
+        let filter = NodeFilter::new(Some(&blocklist), Some(&include_list));
+        let kept = filter.filter_trust(trust.clone(), &mut stats);
+        assert_eq!(
+            kept,
+            vec![TrustEntry::new("alice".into(), "bob".into(), 1.0)]
+        );
+        let seed = vec![
+            ScoreEntry::new("alice".into(), 1.0),
+            ScoreEntry::new("carol".into(), 1.0),
+            ScoreEntry::new("dave".into(), 1.0),
+        ];
+        assert_eq!(filter.filter_seed(seed, &mut stats).len(), 1);
+        assert_eq!(stats.dropped_seeds, 2);
+
+        let mut stats = FilterStats::default();
+        assert_eq!(
+            NodeFilter::default().filter_trust(trust, &mut stats).len(),
+            4
+        );
+    }
+}
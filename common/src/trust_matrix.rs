@@ -0,0 +1,297 @@
+use crate::runner::OutboundLocalTrust;
+use crate::value::Value;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Bytes of an edge in the spool file: from, to and value.
+const SPOOL_RECORD_LEN: usize = 24;
+/// Bytes of an edge in the edges file: to and value, the row being given by the offsets.
+const EDGE_RECORD_LEN: usize = 16;
+/// Edges grouped in memory at once while writing the edges file, 1 GiB of records.
+pub const DEFAULT_BUFFER_EDGES: usize = 64 * 1024 * 1024;
+
+/// Trust matrix kept on disk, for graphs whose edges don't fit in memory.
+///
+/// The edges are stored in a compressed sparse row layout: the edges file holds the outbound
+/// edges of each peer next to each other, in the order they were added, and only the offsets
+/// of the rows are kept in memory. Rows are read back one at a time, either streamed in order
+/// or looked up by peer, and fold their edges the same way `ComputeRunner::update_trust_map`
+/// does: later values override earlier ones, and zero values remove the edge.
+///
+/// The files are temporary and removed when the matrix is dropped.
+#[derive(Debug)]
+pub struct DiskTrustMatrix {
+    edges: NamedTempFile,
+    /// Edges of row `i` are the records `offsets[i]..offsets[i + 1]` of the edges file.
+    offsets: Vec<u64>,
+    /// Number of peers with at least one edge, including zero ones.
+    num_rows: usize,
+}
+
+impl DiskTrustMatrix {
+    /// Starts a matrix whose files are created in the directory.
+    pub fn builder(dir: impl AsRef<Path>) -> std::io::Result<DiskTrustMatrixBuilder> {
+        let spool = NamedTempFile::new_in(dir.as_ref())?;
+        let writer = BufWriter::new(spool.reopen()?);
+        Ok(DiskTrustMatrixBuilder {
+            dir: dir.as_ref().to_path_buf(),
+            spool,
+            writer,
+            degrees: Vec::new(),
+            buffer_edges: DEFAULT_BUFFER_EDGES,
+        })
+    }
+
+    /// Number of peers with outbound edges, as the number of rows of an in-memory matrix.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Number of peers the rows are indexed over, the last ones possibly without edges.
+    pub fn len(&self) -> u64 {
+        (self.offsets.len() - 1) as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of edges stored, before folding the overridden and zero ones.
+    pub fn num_edges(&self) -> u64 {
+        self.offsets.last().copied().unwrap_or(0)
+    }
+
+    /// Streams the rows in peer order, empty rows included, with the values of the precision
+    /// of the computation.
+    pub fn rows<V: Value>(&self) -> std::io::Result<RowReader<'_, V>> {
+        Ok(RowReader {
+            matrix: self,
+            reader: BufReader::new(self.edges.reopen()?),
+            next: 0,
+            value: PhantomData,
+        })
+    }
+
+    /// Opens a reader looking up rows by peer.
+    pub fn lookup(&self) -> std::io::Result<RowLookup<'_>> {
+        Ok(RowLookup {
+            matrix: self,
+            file: self.edges.reopen()?,
+        })
+    }
+
+    fn row_len(&self, index: u64) -> usize {
+        match self.offsets.get(index as usize + 1) {
+            Some(end) => (end - self.offsets[index as usize]) as usize,
+            None => 0,
+        }
+    }
+}
+
+/// Folds the edge records of a row into its outbound trust.
+fn fold_row<V: Value>(bytes: &[u8]) -> OutboundLocalTrust<V> {
+    let mut row = OutboundLocalTrust::new();
+    for record in bytes.chunks(EDGE_RECORD_LEN) {
+        let to = u64::from_le_bytes(record[..8].try_into().expect("8 bytes"));
+        let value = f64::from_le_bytes(record[8..].try_into().expect("8 bytes"));
+        row.apply(to, V::from_f64(value));
+    }
+    row
+}
+
+/// Builder of a `DiskTrustMatrix`. Edges are appended to a spool file as they come, then
+/// grouped by row into the edges file, in passes of at most `buffer_edges` edges.
+pub struct DiskTrustMatrixBuilder {
+    dir: PathBuf,
+    spool: NamedTempFile,
+    writer: BufWriter<File>,
+    /// Number of edges of each row, zero ones included.
+    degrees: Vec<u64>,
+    buffer_edges: usize,
+}
+
+impl DiskTrustMatrixBuilder {
+    /// Sets the number of edges grouped in memory at once. Rows with more edges are grouped
+    /// alone.
+    pub fn with_buffer_edges(mut self, buffer_edges: usize) -> Self {
+        self.buffer_edges = buffer_edges.max(1);
+        self
+    }
+
+    /// Appends an edge. Values are expected to be validated by the caller.
+    pub fn push(&mut self, from: u64, to: u64, value: f64) -> std::io::Result<()> {
+        let from_index = from as usize;
+        if from_index >= self.degrees.len() {
+            self.degrees.resize(from_index + 1, 0);
+        }
+        self.degrees[from_index] += 1;
+        let mut record = [0; SPOOL_RECORD_LEN];
+        record[..8].copy_from_slice(&from.to_le_bytes());
+        record[8..16].copy_from_slice(&to.to_le_bytes());
+        record[16..].copy_from_slice(&value.to_le_bytes());
+        self.writer.write_all(&record)
+    }
+
+    /// Writes the edges file, with the rows of `count` peers.
+    pub fn finish(mut self, count: u64) -> std::io::Result<DiskTrustMatrix> {
+        self.writer.flush()?;
+        self.degrees
+            .resize(self.degrees.len().max(count as usize), 0);
+        let num_rows = self.degrees.iter().filter(|d| **d > 0).count();
+        let mut offsets = Vec::with_capacity(self.degrees.len() + 1);
+        offsets.push(0);
+        for degree in &self.degrees {
+            offsets.push(offsets[offsets.len() - 1] + degree);
+        }
+        drop(self.degrees);
+
+        let edges = NamedTempFile::new_in(&self.dir)?;
+        let mut writer = BufWriter::new(edges.reopen()?);
+        let num_peers = offsets.len() - 1;
+        let mut start = 0;
+        while start < num_peers {
+            // Rows [start, end) whose edges fit in the buffer, at least one
+            let mut end = start + 1;
+            while end < num_peers && offsets[end + 1] - offsets[start] <= self.buffer_edges as u64 {
+                end += 1;
+            }
+            let base = offsets[start];
+            let mut buffer = vec![0; (offsets[end] - base) as usize * EDGE_RECORD_LEN];
+            let mut cursors: Vec<u64> = offsets[start..end].iter().map(|o| o - base).collect();
+
+            let mut spool = BufReader::new(self.spool.reopen()?);
+            let mut record = [0; SPOOL_RECORD_LEN];
+            loop {
+                match spool.read_exact(&mut record) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                    Err(e) => return Err(e),
+                }
+                let from = u64::from_le_bytes(record[..8].try_into().expect("8 bytes")) as usize;
+                if from < start || from >= end {
+                    continue;
+                }
+                let position = cursors[from - start] as usize * EDGE_RECORD_LEN;
+                buffer[position..position + EDGE_RECORD_LEN].copy_from_slice(&record[8..]);
+                cursors[from - start] += 1;
+            }
+            writer.write_all(&buffer)?;
+            start = end;
+        }
+        writer.flush()?;
+
+        Ok(DiskTrustMatrix {
+            edges,
+            offsets,
+            num_rows,
+        })
+    }
+}
+
+/// Streams the rows of a `DiskTrustMatrix` in peer order.
+pub struct RowReader<'a, V> {
+    matrix: &'a DiskTrustMatrix,
+    reader: BufReader<File>,
+    next: u64,
+    value: PhantomData<V>,
+}
+
+impl<V: Value> Iterator for RowReader<'_, V> {
+    type Item = std::io::Result<(u64, OutboundLocalTrust<V>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.matrix.len() {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+        let mut bytes = vec![0; self.matrix.row_len(index) * EDGE_RECORD_LEN];
+        Some(
+            self.reader
+                .read_exact(&mut bytes)
+                .map(|_| (index, fold_row(&bytes))),
+        )
+    }
+}
+
+/// Looks up the rows of a `DiskTrustMatrix` by peer.
+pub struct RowLookup<'a> {
+    matrix: &'a DiskTrustMatrix,
+    file: File,
+}
+
+impl RowLookup<'_> {
+    /// Returns the row of the peer, empty for peers without edges.
+    pub fn row<V: Value>(&mut self, index: u64) -> std::io::Result<OutboundLocalTrust<V>> {
+        let len = self.matrix.row_len(index);
+        if len == 0 {
+            return Ok(OutboundLocalTrust::new());
+        }
+        let offset = self.matrix.offsets[index as usize] * EDGE_RECORD_LEN as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = vec![0; len * EDGE_RECORD_LEN];
+        self.file.read_exact(&mut bytes)?;
+        Ok(fold_row(&bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiskTrustMatrix;
+
+    #[test]
+    fn should_group_edges_by_row() {
+        let dir = std::env::temp_dir();
+        let edges = [
+            (2, 0, 1.0),
+            (0, 1, 2.0),
+            (2, 1, 3.0),
+            (0, 2, 1.0),
+            // Overrides then removes an edge
+            (0, 1, 4.0),
+            (2, 0, 0.0),
+            (3, 3, 0.0),
+        ];
+        // A buffer of 2 edges writes the rows in several passes, the rows over it alone
+        for buffer_edges in [1, 2, 64] {
+            let mut builder = DiskTrustMatrix::builder(&dir)
+                .unwrap()
+                .with_buffer_edges(buffer_edges);
+            for (from, to, value) in edges {
+                builder.push(from, to, value).unwrap();
+            }
+            let matrix = builder.finish(5).unwrap();
+            assert_eq!(
+                (matrix.len(), matrix.num_rows(), matrix.num_edges()),
+                (5, 3, 7)
+            );
+
+            let rows: Vec<_> = matrix.rows::<f64>().unwrap().map(|r| r.unwrap()).collect();
+            assert_eq!(rows.len(), 5);
+            assert_eq!(rows[0].1.get(&1), Some(4.0));
+            assert_eq!(*rows[0].1.outbound_sum(), 5.0);
+            assert!(rows[1].1.outbound_trust_scores().is_empty());
+            assert!(!rows[2].1.contains_key(&0));
+            assert_eq!(*rows[2].1.outbound_sum(), 3.0);
+            // Zero edges leave an empty row
+            assert!(rows[3].1.outbound_trust_scores().is_empty());
+
+            let mut lookup = matrix.lookup().unwrap();
+            assert_eq!(lookup.row::<f64>(2).unwrap().get(&1), Some(3.0));
+            assert_eq!(lookup.row::<f64>(0).unwrap().get(&2), Some(1.0));
+            assert!(lookup
+                .row::<f64>(4)
+                .unwrap()
+                .outbound_trust_scores()
+                .is_empty());
+            assert!(lookup
+                .row::<f64>(9)
+                .unwrap()
+                .outbound_trust_scores()
+                .is_empty());
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{ScoreEntry, TrustEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Changes to a trust graph, applied on top of a snapshot. An entry sets the weight of its
+/// edge and a zero weight removes the edge, as in `ComputeRunner::update_trust_map`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustUpdate {
+    /// Position of the update in its sequence, updates being applied in increasing order.
+    pub seq_number: u64,
+    pub entries: Vec<TrustEntry>,
+}
+
+/// Changes to a seed vector, applied on top of a snapshot. An entry sets the value of its
+/// peer and a zero value removes the peer, as in `ComputeRunner::update_seed_map`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeedUpdate {
+    /// Position of the update in its sequence, updates being applied in increasing order.
+    pub seq_number: u64,
+    pub entries: Vec<ScoreEntry>,
+}
+
+/// Checks the sequence numbers are strictly increasing, so that no update is applied twice
+/// or out of order.
+pub fn check_order(seq_numbers: impl IntoIterator<Item = u64>) -> Result<(), Error> {
+    let mut previous: Option<u64> = None;
+    for seq_number in seq_numbers {
+        if let Some(previous) = previous.filter(|p| *p >= seq_number) {
+            return Err(Error::OutOfOrder {
+                previous,
+                seq_number,
+            });
+        }
+        previous = Some(seq_number);
+    }
+    Ok(())
+}
+
+/// Applies the changes to the snapshot. Entries keep their position in the snapshot and new
+/// ones are appended in the order they are added, so the same snapshot and updates always
+/// give the same entries, and the same content id once written.
+fn apply<E, K: Eq + Hash>(
+    base: Vec<E>,
+    changes: impl IntoIterator<Item = E>,
+    key: impl Fn(&E) -> K,
+    value: impl Fn(&E) -> f64,
+) -> Result<Vec<E>, Error> {
+    let mut entries: Vec<Option<E>> = Vec::with_capacity(base.len());
+    let mut positions: HashMap<K, usize> = HashMap::with_capacity(base.len());
+    // The last change of an entry wins, also for the duplicates of the snapshot
+    for entry in base.into_iter().chain(changes) {
+        let entry_value = value(&entry);
+        if !entry_value.is_finite() || entry_value < 0.0 {
+            return Err(Error::InvalidValue(entry_value));
+        }
+        let entry_key = key(&entry);
+        let entry = (entry_value != 0.0).then_some(entry);
+        match positions.get(&entry_key) {
+            // A removed entry set again gets its previous position back
+            Some(position) => entries[*position] = entry,
+            None if entry.is_some() => {
+                positions.insert(entry_key, entries.len());
+                entries.push(entry);
+            }
+            None => {}
+        }
+    }
+    Ok(entries.into_iter().flatten().collect())
+}
+
+/// Applies the updates, in order, to the trust snapshot.
+pub fn apply_trust_updates(
+    base: Vec<TrustEntry>,
+    updates: Vec<TrustUpdate>,
+) -> Result<Vec<TrustEntry>, Error> {
+    check_order(updates.iter().map(|u| u.seq_number))?;
+    apply(
+        base,
+        updates.into_iter().flat_map(|u| u.entries),
+        |e| (e.from().clone(), e.to().clone()),
+        |e| *e.value(),
+    )
+}
+
+/// Applies the updates, in order, to the seed snapshot.
+pub fn apply_seed_updates(
+    base: Vec<ScoreEntry>,
+    updates: Vec<SeedUpdate>,
+) -> Result<Vec<ScoreEntry>, Error> {
+    check_order(updates.iter().map(|u| u.seq_number))?;
+    apply(
+        base,
+        updates.into_iter().flat_map(|u| u.entries),
+        |e| e.id().clone(),
+        |e| *e.value(),
+    )
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Update {seq_number} applied after update {previous}")]
+    OutOfOrder { previous: u64, seq_number: u64 },
+    #[error("Invalid value: {0}")]
+    InvalidValue(f64),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trust(from: &str, to: &str, value: f64) -> TrustEntry {
+        TrustEntry::new(from.to_string(), to.to_string(), value)
+    }
+
+    #[test]
+    fn should_apply_trust_updates_in_order() {
+        let base = vec![
+            trust("a", "b", 1.0),
+            trust("b", "c", 2.0),
+            trust("c", "a", 3.0),
+        ];
+        let updates = vec![
+            TrustUpdate {
+                seq_number: 1,
+                entries: vec![trust("a", "b", 0.0), trust("c", "b", 4.0)],
+            },
+            TrustUpdate {
+                seq_number: 2,
+                entries: vec![trust("b", "c", 5.0), trust("a", "b", 6.0)],
+            },
+        ];
+        let snapshot = apply_trust_updates(base, updates).unwrap();
+        assert_eq!(
+            snapshot,
+            vec![
+                trust("a", "b", 6.0),
+                trust("b", "c", 5.0),
+                trust("c", "a", 3.0),
+                trust("c", "b", 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_reject_out_of_order_updates() {
+        let updates = vec![
+            SeedUpdate {
+                seq_number: 2,
+                entries: vec![ScoreEntry::new("a".to_string(), 1.0)],
+            },
+            SeedUpdate {
+                seq_number: 2,
+                entries: vec![],
+            },
+        ];
+        assert!(matches!(
+            apply_seed_updates(Vec::new(), updates),
+            Err(Error::OutOfOrder {
+                previous: 2,
+                seq_number: 2
+            })
+        ));
+    }
+}
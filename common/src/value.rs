@@ -0,0 +1,102 @@
+use crate::scheme::Precision;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, Sub, SubAssign};
+
+/// Floating point type the trust and the scores are computed with, following the precision of
+/// the job: `f32` for `Precision::F32`, so that the scores of the jobs without a precision are
+/// computed with the same arithmetic as by the first releases, and `f64` for `Precision::F64`.
+///
+/// Entries hold f64 values, which represent every f32 value exactly: `from_f64` only rounds
+/// values that weren't parsed with the precision.
+pub trait Value:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Display
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + AddAssign
+    + SubAssign
+    + DivAssign
+    + Sum<Self>
+    + for<'a> Sum<&'a Self>
+    + Serialize
+    + DeserializeOwned
+    + 'static
+{
+    const PRECISION: Precision;
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Rounds the value to the nearest one of the type.
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts the value to f64, exactly.
+    fn to_f64(self) -> f64;
+
+    fn abs(self) -> Self;
+
+    fn max(self, other: Self) -> Self;
+
+    fn is_finite(self) -> bool;
+}
+
+impl Value for f32 {
+    const PRECISION: Precision = Precision::F32;
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f32::max(self, other)
+    }
+
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+}
+
+impl Value for f64 {
+    const PRECISION: Precision = Precision::F64;
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f64::max(self, other)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
@@ -1,16 +1,37 @@
-use crate::BUCKET_NAME;
+use crate::datasets::DatasetKind;
+use crate::progress::{format_bytes, ProgressBar, ProgressReader};
 use alloy::hex::{self};
-use aws_sdk_s3::{primitives::ByteStream, Client, Error as AwsError};
+use alloy::primitives::Address;
+use clap::ValueEnum;
 use openrank_common::{
+    blocklist::{self, Blocklist},
+    canonical,
+    format::{self, DataFormat},
+    hooks::{self, WasmHook},
+    identity::SignedNodeIdentity,
+    manifest::SignedComputeManifest,
+    merkle::Hash,
+    normalize::{
+        canonicalize_seed, canonicalize_signed_trust, canonicalize_trust, DuplicatePolicy,
+    },
+    params::{AlgoParams, EigenTrustParams, SybilRankParams},
     runner::{self, ComputeRunner},
-    ScoreEntry, TrustEntry,
+    scheme::Precision,
+    score_meta::parse_metadata_csv,
+    snapshot::SnapshotManifest,
+    storage::{artifact_key, Error as StorageError, StorageBackend},
+    subgraph::{FilterStats, IncludeList, NodeFilter},
+    updates::{apply_seed_updates, apply_trust_updates, SeedUpdate, TrustUpdate},
+    ScoreEntry, ScoreEntryReader, TrustEntry, TrustEntryReader,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{BufWriter, Read, Write},
+    io::{BufReader, BufWriter, Read, Write},
     path::Path,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tracing::{debug, info};
 
@@ -20,7 +41,7 @@ fn validate_trust_csv(path: &str) -> Result<(), csv::Error> {
     let mut reader = csv::Reader::from_reader(file);
     for result in reader.records() {
         let record: csv::StringRecord = result?;
-        let (_, _, _): (String, String, f32) = record.deserialize(None)?;
+        let (_, _, _): (String, String, f64) = record.deserialize(None)?;
     }
     Ok(())
 }
@@ -31,227 +52,964 @@ fn validate_score_csv(path: &str) -> Result<(), csv::Error> {
     let mut reader = csv::Reader::from_reader(file);
     for result in reader.records() {
         let record: csv::StringRecord = result?;
-        let (_, _): (String, f32) = record.deserialize(None)?;
+        let (_, _): (String, f64) = record.deserialize(None)?;
     }
     Ok(())
 }
 
-pub async fn upload_trust(client: Client, path: String) -> Result<String, AwsError> {
+/// Returns the content id and the number of entries of a trust or seed CSV, validating it.
+pub fn dataset_file_stats(kind: DatasetKind, path: &str) -> Result<(String, u64), csv::Error> {
+    let bytes = std::fs::read(path)?;
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    let mut rows = 0;
+    for result in reader.records() {
+        let record: csv::StringRecord = result?;
+        match kind {
+            DatasetKind::Trust => {
+                let (_, _, _): (String, String, f64) = record.deserialize(None)?;
+            }
+            DatasetKind::Seed => {
+                let (_, _): (String, f64) = record.deserialize(None)?;
+            }
+        }
+        rows += 1;
+    }
+    Ok((hex::encode(Keccak256::digest(&bytes)), rows))
+}
+
+/// Builds the manifest of a snapshot of the trust and seed CSVs, created now.
+pub fn create_snapshot(
+    trust_path: &str,
+    seed_path: &str,
+    parent: Option<String>,
+    description: Option<String>,
+) -> Result<SnapshotManifest, csv::Error> {
+    let (trust_id, trust_rows) = dataset_file_stats(DatasetKind::Trust, trust_path)?;
+    let (seed_id, seed_rows) = dataset_file_stats(DatasetKind::Seed, seed_path)?;
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    Ok(
+        SnapshotManifest::new(trust_id, trust_rows, seed_id, seed_rows, created_at)
+            .with_parent(parent)
+            .with_description(description),
+    )
+}
+
+pub async fn upload_trust(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    path: String,
+) -> Result<String, StorageError> {
     let mut f = File::open(path.clone()).unwrap();
     let mut file_bytes = Vec::new();
     f.read_to_end(&mut file_bytes).unwrap();
-    let body = ByteStream::from(file_bytes.clone());
+    let body = file_bytes.clone();
 
     let mut hasher = Keccak256::new();
-    hasher.write_all(&mut file_bytes).unwrap();
+    hasher.write_all(&file_bytes).unwrap();
     let hash = hasher.finalize().to_vec();
 
     validate_trust_csv(&path).unwrap();
 
+    let key = artifact_key(key_prefix, "trust", &hex::encode(hash.clone()));
+    if storage.exists(&key).await? {
+        info!("Already uploaded: {}", key);
+        return Ok(hex::encode(hash));
+    }
+
     info!("Uploading trust data: {}", hex::encode(hash.clone()));
 
-    client
-        .put_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("trust/{}", hex::encode(hash.clone())))
-        .body(body)
-        .send()
-        .await?;
+    storage.put(&key, body).await?;
 
     Ok(hex::encode(hash))
 }
 
-pub async fn upload_seed(client: Client, path: String) -> Result<String, AwsError> {
+pub async fn upload_seed(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    path: String,
+) -> Result<String, StorageError> {
     let mut f = File::open(path.clone()).unwrap();
     let mut file_bytes = Vec::new();
     f.read_to_end(&mut file_bytes).unwrap();
-    let body = ByteStream::from(file_bytes.clone());
+    let body = file_bytes.clone();
 
     let mut hasher = Keccak256::new();
-    hasher.write_all(&mut file_bytes).unwrap();
+    hasher.write_all(&file_bytes).unwrap();
     let hash = hasher.finalize().to_vec();
 
     validate_score_csv(&path).unwrap();
 
+    let key = artifact_key(key_prefix, "seed", &hex::encode(hash.clone()));
+    if storage.exists(&key).await? {
+        info!("Already uploaded: {}", key);
+        return Ok(hex::encode(hash));
+    }
+
     info!("Uploading seed data: {}", hex::encode(hash.clone()));
 
-    client
-        .put_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("seed/{}", hex::encode(hash.clone())))
-        .body(body)
-        .send()
-        .await?;
+    storage.put(&key, body).await?;
 
     Ok(hex::encode(hash))
 }
 
+/// Reader hashing the bytes read.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Keccak256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Uploads a trust or seed CSV without loading it in memory: the file is validated and hashed
+/// in a single streamed pass, then streamed to the storage unless a dataset with the same
+/// content id was already uploaded. Returns the content id. The progress bar covers the
+/// validation only, the storage reporting no progress of the upload.
+pub async fn upload_dataset_file(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    kind: DatasetKind,
+    path: &str,
+) -> Result<String, StorageError> {
+    let file = File::open(path).map_err(StorageError::Io)?;
+    let len = file.metadata().map_err(StorageError::Io)?.len();
+    let mut progress = ProgressBar::new("Validating", len);
+    let mut reader = HashingReader {
+        inner: ProgressReader::new(BufReader::new(file), &mut progress),
+        hasher: Keccak256::new(),
+    };
+    let validation = {
+        let mut csv_reader = csv::Reader::from_reader(&mut reader);
+        match kind {
+            DatasetKind::Trust => csv_reader
+                .deserialize::<(String, String, f64)>()
+                .try_for_each(|record| record.map(|_| ())),
+            DatasetKind::Seed => csv_reader
+                .deserialize::<(String, f64)>()
+                .try_for_each(|record| record.map(|_| ())),
+        }
+    };
+    let id = hex::encode(reader.hasher.finalize());
+    progress.finish();
+    if let Err(e) = validation {
+        panic!("Invalid {:?} CSV {}: {}", kind, path, e);
+    }
+
+    let key = artifact_key(key_prefix, kind.prefix(), &id);
+    if storage.exists(&key).await? {
+        info!("Already uploaded: {}", key);
+        return Ok(id);
+    }
+    info!("Uploading {} ({})", key, format_bytes(len));
+    storage.put_file(&key, Path::new(path)).await?;
+    Ok(id)
+}
+
+/// Writes the canonical form of a trust or seed file to `out_path` (see `normalize`): ids and
+/// values stripped of whitespace, duplicates merged with the policy, entries sorted by id.
+/// The input is a CSV or Parquet file, the output is written in `format`. Returns the number
+/// of entries read and written. Negative trust entries are kept with `negative_trust`, for
+/// EigenTrust with negative trust, and rejected otherwise.
+pub fn normalize_dataset_file(
+    kind: DatasetKind,
+    path: &str,
+    out_path: &str,
+    policy: DuplicatePolicy,
+    format: DataFormat,
+    negative_trust: bool,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let mut file = File::open(path)?;
+    let parquet = DataFormat::of_file(&mut file)? == DataFormat::Parquet;
+    let out = BufWriter::new(File::create(out_path)?);
+    let counts = match kind {
+        DatasetKind::Trust => {
+            let entries: Vec<TrustEntry> = if parquet {
+                format::trust_entries(file, Precision::F64)?.collect::<Result<_, _>>()?
+            } else {
+                TrustEntryReader::trimmed(BufReader::new(file)).collect::<Result<_, _>>()?
+            };
+            let read = entries.len();
+            let entries = if negative_trust {
+                canonicalize_signed_trust(entries, policy)?
+            } else {
+                canonicalize_trust(entries, policy)?
+            };
+            format::write_trust(out, &entries, format)?;
+            (read, entries.len())
+        }
+        DatasetKind::Seed => {
+            let entries: Vec<ScoreEntry> = if parquet {
+                format::score_entries(file, Precision::F64)?.collect::<Result<_, _>>()?
+            } else {
+                ScoreEntryReader::trimmed(BufReader::new(file)).collect::<Result<_, _>>()?
+            };
+            let read = entries.len();
+            let entries = canonicalize_seed(entries, policy)?;
+            format::write_scores(out, &entries, format)?;
+            (read, entries.len())
+        }
+    };
+    Ok(counts)
+}
+
+pub async fn upload_hook(
+    storage: &dyn StorageBackend,
+    path: String,
+) -> Result<String, StorageError> {
+    let mut f = File::open(path.clone()).unwrap();
+    let mut file_bytes = Vec::new();
+    f.read_to_end(&mut file_bytes).unwrap();
+
+    // Make sure the module compiles before uploading it
+    let hook = WasmHook::new(&file_bytes).unwrap();
+    let hook_id = hook.id().to_string();
+
+    info!("Uploading hook: {}", hook_id);
+
+    storage
+        .put(&format!("hooks/{}", hook_id), file_bytes)
+        .await?;
+
+    Ok(hook_id)
+}
+
+pub async fn upload_blocklist(
+    storage: &dyn StorageBackend,
+    path: String,
+) -> Result<String, StorageError> {
+    let file_bytes = std::fs::read(&path).unwrap();
+
+    // Make sure the file parses before uploading it
+    let blocklist = Blocklist::new(&file_bytes).unwrap();
+    let blocklist_id = blocklist.id().to_string();
+
+    info!(
+        "Uploading blocklist of {} ids: {}",
+        blocklist.len(),
+        blocklist_id
+    );
+
+    storage
+        .put(&format!("blocklist/{}", blocklist_id), file_bytes)
+        .await?;
+
+    Ok(blocklist_id)
+}
+
+pub async fn upload_include_list(
+    storage: &dyn StorageBackend,
+    path: String,
+) -> Result<String, StorageError> {
+    let file_bytes = std::fs::read(&path).unwrap();
+
+    // Make sure the file parses before uploading it
+    let include_list = IncludeList::new(&file_bytes).unwrap();
+    let include_list_id = include_list.id().to_string();
+
+    info!(
+        "Uploading include-list of {} ids: {}",
+        include_list.len(),
+        include_list_id
+    );
+
+    storage
+        .put(&format!("include/{}", include_list_id), file_bytes)
+        .await?;
+
+    Ok(include_list_id)
+}
+
+/// Reads an include-list from the given path.
+pub fn read_include_list(path: &str) -> Result<IncludeList, blocklist::Error> {
+    let bytes = std::fs::read(path).map_err(|e| blocklist::Error::Utf8(e.to_string()))?;
+    IncludeList::new(&bytes)
+}
+
+/// Reads a blocklist from the given path.
+pub fn read_blocklist(path: &str) -> Result<Blocklist, blocklist::Error> {
+    let bytes = std::fs::read(path).map_err(|e| blocklist::Error::Utf8(e.to_string()))?;
+    Blocklist::new(&bytes)
+}
+
+/// Reads and compiles a WASM post-processing hook from the given path.
+pub fn read_hook(path: &str) -> Result<WasmHook, hooks::Error> {
+    let bytes = std::fs::read(path).map_err(|e| hooks::Error::Wasm(e.to_string()))?;
+    WasmHook::new(&bytes)
+}
+
+/// Checks downloaded bytes hash to their content id, panicking on a corrupted or tampered
+/// artifact like the other checks of the SDK.
+fn check_content_id(kind: &str, id: &str, bytes: &[u8]) {
+    assert_eq!(
+        hex::encode(Keccak256::digest(bytes)),
+        id.trim_start_matches("0x").to_lowercase(),
+        "{} doesn't match its id",
+        kind
+    );
+}
+
 pub async fn _download_trust(
-    client: Client,
+    storage: &dyn StorageBackend,
     trust_id: String,
     path: String,
-) -> Result<(), AwsError> {
+) -> Result<(), StorageError> {
     let mut file = File::create(path).unwrap();
-    let mut res = client
-        .get_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("trust/{}", trust_id))
-        .send()
-        .await?;
-    while let Some(bytes) = res.body.next().await {
-        file.write(&bytes.unwrap()).unwrap();
-    }
+    let bytes = storage.get(&format!("trust/{}", trust_id)).await?;
+    check_content_id("Trust", &trust_id, &bytes);
+    file.write_all(&bytes).unwrap();
     Ok(())
 }
 
-pub async fn _download_seed(client: Client, seed_id: String, path: String) -> Result<(), AwsError> {
+pub async fn _download_seed(
+    storage: &dyn StorageBackend,
+    seed_id: String,
+    path: String,
+) -> Result<(), StorageError> {
     let mut file = File::create(path).unwrap();
-    let mut res = client
-        .get_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("seed/{}", seed_id))
-        .send()
-        .await?;
-    while let Some(bytes) = res.body.next().await {
-        file.write(&bytes.unwrap()).unwrap();
-    }
+    let bytes = storage.get(&format!("seed/{}", seed_id)).await?;
+    check_content_id("Seed", &seed_id, &bytes);
+    file.write_all(&bytes).unwrap();
     Ok(())
 }
 
+/// Downloads the scores to `path` in the format, sorted from the highest score, keeping only the
+/// `top` ones if given. Ranked scores keep their committed order, and their bytes as CSV.
 pub async fn download_scores(
-    client: Client,
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
     scores_id: String,
     path: String,
-) -> Result<(), AwsError> {
-    // Download the scores data from S3
-    let mut res = client
-        .get_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("scores/{}", scores_id))
-        .send()
+    format: DataFormat,
+    top: Option<usize>,
+) -> Result<(), StorageError> {
+    // Download the scores data from the storage
+    let csv_bytes = storage
+        .get(&artifact_key(key_prefix, "scores", &scores_id))
         .await?;
-    debug!("{:?}", res);
+    debug!(
+        "Downloaded {} bytes of ScoresId({})",
+        csv_bytes.len(),
+        scores_id
+    );
+    check_content_id("Scores", &scores_id, &csv_bytes);
+
+    // Write sorted scores to a temporary file first, so a partial download is never mistaken
+    // for a complete one
+    let tmp_path = format!("{}.part", path);
+    let ranked = csv_bytes.starts_with(b"i,v,r\n");
+    if ranked && format == DataFormat::Csv {
+        // Ranked scores are already in ranking order, keep them as committed
+        let bytes = match top {
+            Some(top) => truncate_csv(&csv_bytes, top).expect("Failed to parse CSV data"),
+            None => csv_bytes,
+        };
+        std::fs::write(&tmp_path, &bytes).expect("Failed to write CSV file");
+    } else {
+        // Parse CSV bytes into ScoreEntry objects
+        let mut scores = match DataFormat::detect(&csv_bytes) {
+            DataFormat::Parquet => format::score_entries_from_bytes(csv_bytes, Precision::F64)
+                .and_then(|entries| entries.collect())
+                .expect("Failed to parse Parquet data"),
+            _ => parse_csv_to_scores(&csv_bytes, Precision::F64).expect("Failed to parse CSV data"),
+        };
+
+        // Sort scores from highest to lowest value, unless already in ranking order
+        if !ranked {
+            scores.sort_by(|a, b| {
+                b.value()
+                    .partial_cmp(a.value())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        if let Some(top) = top {
+            scores.truncate(top);
+        }
+        let file = BufWriter::new(File::create(&tmp_path).expect("Failed to create scores file"));
+        format::write_scores(file, &scores, format).expect("Failed to write scores file");
+    }
+    std::fs::rename(&tmp_path, &path).expect("Failed to rename scores file");
 
-    // Collect all bytes into a vector
-    let mut csv_bytes = Vec::new();
-    while let Some(bytes) = res.body.next().await {
-        csv_bytes.extend_from_slice(&bytes.unwrap());
+    Ok(())
+}
+
+/// Keeps the header and the first `top` records of a CSV.
+fn truncate_csv(bytes: &[u8], top: usize) -> Result<Vec<u8>, csv::Error> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(reader.headers()?)?;
+    for record in reader.records().take(top) {
+        wtr.write_record(&record?)?;
     }
+    wtr.into_inner().map_err(|e| e.into_error().into())
+}
 
-    // Parse CSV bytes into ScoreEntry objects
-    let mut scores = parse_csv_to_scores(&csv_bytes).expect("Failed to parse CSV data");
+/// Downloads the extended scores artifact (rank, percentile and flags of every score) to `path`,
+/// keeping the order of the committed scores artifact.
+pub async fn download_score_metadata(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    metadata_id: String,
+    path: String,
+) -> Result<(), StorageError> {
+    let csv_bytes = storage
+        .get(&artifact_key(key_prefix, "scores-meta", &metadata_id))
+        .await?;
+    debug!(
+        "Downloaded {} bytes of MetadataId({})",
+        csv_bytes.len(),
+        metadata_id
+    );
+    check_content_id("Scores metadata", &metadata_id, &csv_bytes);
+    parse_metadata_csv(csv_bytes.as_slice()).expect("Failed to parse scores metadata");
+
+    let tmp_path = format!("{}.part", path);
+    std::fs::write(&tmp_path, &csv_bytes).expect("Failed to write CSV file");
+    std::fs::rename(&tmp_path, &path).expect("Failed to rename CSV file");
 
-    // Sort scores from highest to lowest value
-    scores.sort_by(|a, b| {
-        b.value()
-            .partial_cmp(a.value())
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    Ok(())
+}
 
-    // Write sorted scores to CSV file
-    write_scores_to_csv(&scores, &path).expect("Failed to write CSV file");
+/// Merges downloaded scores files into a single CSV, with the sub-job name as the source column.
+pub fn merge_scores(files: &[(String, String)], out_path: &str) -> Result<(), csv::Error> {
+    let mut wtr = csv::Writer::from_path(out_path)?;
+    wtr.write_record(["source", "i", "v"])?;
+    for (source, path) in files {
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.records() {
+            let record = result?;
+            let id = record.get(0).unwrap_or("");
+            let value = record.get(1).unwrap_or("0.0");
+            wtr.write_record([source.as_str(), id, value])?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
 
+/// Downloads the trust snapshot, applies the update files to it in order (the first file
+/// being update 1) and writes the resulting trust CSV to `out_path`. Values are read and
+/// written with the precision.
+pub async fn apply_trust_update_files(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    base_id: &str,
+    update_paths: &[String],
+    out_path: &str,
+    precision: Precision,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = storage
+        .get(&artifact_key(key_prefix, "trust", base_id))
+        .await?;
+    check_content_id("Trust", base_id, &bytes);
+    let base =
+        format::trust_entries_from_bytes(bytes, precision)?.collect::<Result<Vec<_>, _>>()?;
+    let mut updates = Vec::new();
+    for (path, seq_number) in update_paths.iter().zip(1..) {
+        let entries =
+            format::trust_entries(File::open(path)?, precision)?.collect::<Result<Vec<_>, _>>()?;
+        updates.push(TrustUpdate {
+            seq_number,
+            entries,
+        });
+    }
+    let entries = apply_trust_updates(base, updates)?;
+
+    write_trust_to_csv(&entries, out_path, precision)?;
+    info!(
+        "Applied {} updates to trust {}: {} entries",
+        update_paths.len(),
+        base_id,
+        entries.len()
+    );
     Ok(())
 }
 
+/// Downloads the seed snapshot, applies the update files to it in order (the first file
+/// being update 1) and writes the resulting seed CSV to `out_path`. Values are read and
+/// written with the precision.
+pub async fn apply_seed_update_files(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    base_id: &str,
+    update_paths: &[String],
+    out_path: &str,
+    precision: Precision,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = storage
+        .get(&artifact_key(key_prefix, "seed", base_id))
+        .await?;
+    check_content_id("Seed", base_id, &bytes);
+    let base =
+        format::score_entries_from_bytes(bytes, precision)?.collect::<Result<Vec<_>, _>>()?;
+    let mut updates = Vec::new();
+    for (path, seq_number) in update_paths.iter().zip(1..) {
+        let entries =
+            format::score_entries(File::open(path)?, precision)?.collect::<Result<Vec<_>, _>>()?;
+        updates.push(SeedUpdate {
+            seq_number,
+            entries,
+        });
+    }
+    let entries = apply_seed_updates(base, updates)?;
+
+    write_scores_to_csv(&entries, out_path, precision)?;
+    info!(
+        "Applied {} updates to seed {}: {} entries",
+        update_paths.len(),
+        base_id,
+        entries.len()
+    );
+    Ok(())
+}
+
+/// Parses a scores artifact, CSV (ranked or not) or Parquet, with the precision of its job.
+pub fn parse_scores(bytes: Vec<u8>, precision: Precision) -> Result<Vec<ScoreEntry>, csv::Error> {
+    match DataFormat::detect(&bytes) {
+        DataFormat::Parquet => format::score_entries_from_bytes(bytes, precision)?.collect(),
+        _ => parse_csv_to_scores(&bytes, precision),
+    }
+}
+
 /// Parse CSV bytes into a vector of ScoreEntry objects
-fn parse_csv_to_scores(csv_bytes: &[u8]) -> Result<Vec<ScoreEntry>, csv::Error> {
+fn parse_csv_to_scores(
+    csv_bytes: &[u8],
+    precision: Precision,
+) -> Result<Vec<ScoreEntry>, csv::Error> {
     let mut reader = csv::Reader::from_reader(csv_bytes);
     let mut scores = Vec::new();
 
     for result in reader.records() {
         let record = result?;
         let id: String = record.get(0).unwrap_or("").to_string();
-        let value: f32 = record.get(1).unwrap_or("0.0").parse().unwrap_or(0.0);
+        let value = precision
+            .parse(record.get(1).unwrap_or("0.0"))
+            .unwrap_or(0.0);
         scores.push(ScoreEntry::new(id, value));
     }
 
     Ok(scores)
 }
 
-/// Write ScoreEntry objects to CSV file with i,v headers
+/// Write ScoreEntry objects to CSV file with i,v headers, values formatted with the precision
 fn write_scores_to_csv(
     scores: &[ScoreEntry],
     file_path: &str,
+    precision: Precision,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let file = File::create(file_path)?;
     let mut wtr = csv::Writer::from_writer(file);
 
     // Write header
-    wtr.write_record(&["i", "v"])?;
+    wtr.write_record(["i", "v"])?;
 
     // Write scores
     for score in scores {
-        wtr.write_record(&[score.id(), &score.value().to_string()])?;
+        wtr.write_record([score.id(), &precision.format(*score.value())])?;
     }
 
     wtr.flush()?;
     Ok(())
 }
 
-pub async fn upload_meta<T: Serialize>(client: Client, meta: T) -> Result<String, AwsError> {
-    let mut bytes = serde_json::to_vec(&meta).unwrap();
-    let body = ByteStream::from(bytes.clone());
+/// Write TrustEntry objects to CSV file with i,j,v headers, values formatted with the precision
+fn write_trust_to_csv(
+    entries: &[TrustEntry],
+    file_path: &str,
+    precision: Precision,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(file_path)?;
+    let mut wtr = csv::Writer::from_writer(BufWriter::new(file));
 
-    let mut hasher = Keccak256::new();
-    hasher.write_all(&mut bytes).unwrap();
-    let hash = hasher.finalize().to_vec();
-    client
-        .put_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("meta/{}", hex::encode(hash.clone())))
-        .body(body)
-        .send()
-        .await?;
-    Ok(hex::encode(hash))
+    wtr.write_record(["i", "j", "v"])?;
+    for entry in entries {
+        wtr.write_record([entry.from(), entry.to(), &precision.format(*entry.value())])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
 }
 
-pub async fn download_meta<T: DeserializeOwned>(
-    client: Client,
-    meta_id: String,
-) -> Result<T, AwsError> {
-    let res = client
-        .get_object()
-        .bucket(BUCKET_NAME)
-        .key(format!("meta/{}", meta_id))
-        .send()
-        .await?;
-    let res_bytes = res.body.collect().await.unwrap();
-    let meta: T = serde_json::from_slice(res_bytes.to_vec().as_slice()).unwrap();
-    Ok(meta)
+/// Trust files of a compute request without a seed file, every mismatch being listed.
+#[derive(thiserror::Error, Debug)]
+#[error("Trust and seed files don't pair:\n{}", .0.join("\n"))]
+pub struct PairingError(pub Vec<String>);
+
+/// Pairs the trust files of a compute request with their seed files, by name unless the
+/// mapping (trust file name -> seed file name) says otherwise, a seed file being shared by any
+/// number of trust files. Returns the (trust file, seed file) pairs in the order of the trust
+/// files.
+pub fn pair_dataset_files(
+    trust_files: &[String],
+    seed_files: &[String],
+    mapping: &HashMap<String, String>,
+) -> Result<Vec<(String, String)>, PairingError> {
+    let mut mismatches = Vec::new();
+    let mut unknown: Vec<&String> = mapping
+        .keys()
+        .filter(|trust_file| !trust_files.contains(trust_file))
+        .collect();
+    unknown.sort();
+    for trust_file in unknown {
+        mismatches.push(format!("Mapping of {}: no such trust file", trust_file));
+    }
+    let mut pairs = Vec::new();
+    for trust_file in trust_files {
+        match mapping.get(trust_file) {
+            Some(seed_file) if seed_files.contains(seed_file) => {
+                pairs.push((trust_file.clone(), seed_file.clone()))
+            }
+            Some(seed_file) => mismatches.push(format!(
+                "Trust file {}: mapped seed file {} not found",
+                trust_file, seed_file
+            )),
+            None if seed_files.contains(trust_file) => {
+                pairs.push((trust_file.clone(), trust_file.clone()))
+            }
+            None => mismatches.push(format!(
+                "Trust file {}: no seed file of the same name, nor mapping",
+                trust_file
+            )),
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(PairingError(mismatches));
+    }
+    Ok(pairs)
+}
+
+/// Reads a pairing file, a JSON object mapping trust file names to seed file names.
+pub fn read_pairing_file(path: &Path) -> Result<HashMap<String, String>, std::io::Error> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// A job of a jobs manifest, referencing datasets already uploaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub name: String,
+    /// Trust dataset alias or content id
+    pub trust: String,
+    /// Seed dataset alias or content id
+    pub seed: String,
+    pub algo_id: u32,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub hook_id: Option<String>,
+    #[serde(default)]
+    pub blocklist_id: Option<String>,
+    #[serde(default)]
+    pub include_list_id: Option<String>,
+}
+
+/// Reads a jobs manifest, a JSON array of `JobSpec`.
+pub fn read_job_manifest(path: &Path) -> Result<Vec<JobSpec>, std::io::Error> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+pub async fn upload_meta<T: Serialize>(
+    storage: &dyn StorageBackend,
+    meta: T,
+) -> Result<String, StorageError> {
+    let (bytes, meta_id) = canonical::hash(&meta).unwrap();
+    storage.put(&format!("meta/{}", meta_id), bytes).await?;
+    Ok(meta_id)
+}
+
+pub async fn download_manifest(
+    storage: &dyn StorageBackend,
+    compute_id: String,
+) -> Result<SignedComputeManifest, StorageError> {
+    let res_bytes = storage.get(&format!("manifest/{}", compute_id)).await?;
+    let manifest: SignedComputeManifest = serde_json::from_slice(&res_bytes).unwrap();
+    Ok(manifest)
+}
+
+/// Restricts the graph to the nodes kept by the filter, as the computer does before compute.
+fn apply_filter(
+    trust_entries: &[TrustEntry],
+    seed_entries: &[ScoreEntry],
+    filter: NodeFilter,
+) -> (Vec<TrustEntry>, Vec<ScoreEntry>) {
+    if filter.is_noop() {
+        return (trust_entries.to_vec(), seed_entries.to_vec());
+    }
+    let mut stats = FilterStats::default();
+    let trust_entries = filter.filter_trust(trust_entries.to_vec(), &mut stats);
+    let seed_entries = filter.filter_seed(seed_entries.to_vec(), &mut stats);
+    info!(
+        "Subgraph: kept {} edges and {} seeds, dropped {} edges and {} seeds",
+        stats.kept_edges, stats.kept_seeds, stats.dropped_edges, stats.dropped_seeds
+    );
+    (trust_entries, seed_entries)
 }
 
 pub async fn compute_local(
     trust_entries: &[TrustEntry],
     seed_entries: &[ScoreEntry],
-    alpha: Option<f32>,
-    delta: Option<f32>,
+    params: &EigenTrustParams,
+    hook: Option<&WasmHook>,
+    filter: NodeFilter<'_>,
 ) -> Result<Vec<ScoreEntry>, runner::Error> {
-    let mut runner = ComputeRunner::new();
-    runner.update_trust_map(trust_entries.to_vec())?;
-    runner.update_seed_map(seed_entries.to_vec())?;
-    runner.compute_et(alpha, delta)?;
-    let scores = runner.get_compute_scores()?;
-    Ok(scores)
+    let params = AlgoParams::EigenTrust(params.clone());
+    compute_local_params(trust_entries, seed_entries, &params, hook, filter).await
 }
 
 pub async fn compute_local_sr(
     trust_entries: &[TrustEntry],
     seed_entries: &[ScoreEntry],
-    walk_length: Option<u32>,
+    params: &SybilRankParams,
+    hook: Option<&WasmHook>,
+    filter: NodeFilter<'_>,
+) -> Result<Vec<ScoreEntry>, runner::Error> {
+    let params = AlgoParams::SybilRank(params.clone());
+    compute_local_params(trust_entries, seed_entries, &params, hook, filter).await
+}
+
+/// Algorithm run by the local commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LocalAlgo {
+    /// EigenTrust
+    Et,
+    /// SybilRank
+    Sr,
+}
+
+impl LocalAlgo {
+    /// Builds the params of the algorithm, ignoring the ones of the other algorithm.
+    pub fn params(
+        self,
+        alpha: Option<f64>,
+        delta: Option<f64>,
+        max_iter: Option<u32>,
+        walk_length: Option<u32>,
+        precision: Option<Precision>,
+    ) -> AlgoParams {
+        match self {
+            Self::Et => AlgoParams::EigenTrust(EigenTrustParams {
+                alpha,
+                delta,
+                max_iter,
+                ranking: None,
+                metadata: None,
+                precision,
+                hash: None,
+            }),
+            Self::Sr => AlgoParams::SybilRank(SybilRankParams {
+                walk_length,
+                ranking: None,
+                metadata: None,
+                precision,
+                hash: None,
+            }),
+        }
+    }
+}
+
+/// Computes the scores with the algorithm of the params.
+pub async fn compute_local_params(
+    trust_entries: &[TrustEntry],
+    seed_entries: &[ScoreEntry],
+    params: &AlgoParams,
+    hook: Option<&WasmHook>,
+    filter: NodeFilter<'_>,
 ) -> Result<Vec<ScoreEntry>, runner::Error> {
-    let mut runner = ComputeRunner::new();
-    runner.update_trust_map(trust_entries.to_vec())?;
-    runner.update_seed_map(seed_entries.to_vec())?;
-    runner.compute_sr(walk_length)?;
+    let runner = run_local(trust_entries, seed_entries, params, hook, filter)?;
+    runner.get_compute_scores()
+}
+
+/// Computes the scores with the algorithm of the params, and their commitment (the root of
+/// the scores tree) as posted by the computer.
+pub async fn compute_local_commitment(
+    trust_entries: &[TrustEntry],
+    seed_entries: &[ScoreEntry],
+    params: &AlgoParams,
+    hook: Option<&WasmHook>,
+    filter: NodeFilter<'_>,
+) -> Result<(Vec<ScoreEntry>, Hash), runner::Error> {
+    let mut runner = run_local(trust_entries, seed_entries, params, hook, filter)?;
     let scores = runner.get_compute_scores()?;
-    Ok(scores)
+    runner.create_compute_tree(params.scheme().map_err(runner::Error::Hash)?)?;
+    Ok((scores, runner.get_root_hash()?))
+}
+
+/// Runs the compute of the params with their precision, sorting the scores as the computer
+/// does.
+fn run_local(
+    trust_entries: &[TrustEntry],
+    seed_entries: &[ScoreEntry],
+    params: &AlgoParams,
+    hook: Option<&WasmHook>,
+    filter: NodeFilter<'_>,
+) -> Result<ComputeRunner, runner::Error> {
+    let mut runner = ComputeRunner::new(params.precision());
+    if params.accepts_negative_trust() {
+        runner = runner.with_negative_trust();
+    }
+    let (trust_entries, seed_entries) = apply_filter(trust_entries, seed_entries, filter);
+    runner.update_trust_map(trust_entries)?;
+    runner.update_seed_map(seed_entries)?;
+    runner.compute(params)?;
+    if let Some(hook) = hook {
+        runner.apply_hook(hook)?;
+    }
+    if params.ranking().is_some() {
+        runner.sort_by_rank();
+    }
+    Ok(runner)
+}
+
+/// Outcome of checking a scores file against the scores recomputed locally.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LocalVerification {
+    /// Number of scores found in both.
+    pub checked: usize,
+    /// Ids of the recomputed scores missing from the file.
+    pub missing: Vec<String>,
+    /// Ids of the file not in the recomputed scores.
+    pub unexpected: Vec<String>,
+    /// Number of scores differing by more than the tolerance.
+    pub mismatched: usize,
+    /// Largest absolute difference between two scores of the same id.
+    pub max_diff: f64,
+}
+
+impl LocalVerification {
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.mismatched == 0
+    }
+}
+
+/// Recomputes the scores and compares them, by id, with the given ones.
+pub async fn verify_local(
+    trust_entries: &[TrustEntry],
+    seed_entries: &[ScoreEntry],
+    scores: &[ScoreEntry],
+    params: &AlgoParams,
+    tolerance: f64,
+    filter: NodeFilter<'_>,
+) -> Result<LocalVerification, runner::Error> {
+    let recomputed =
+        compute_local_params(trust_entries, seed_entries, params, None, filter).await?;
+    Ok(compare_scores(&recomputed, scores, tolerance))
+}
+
+/// Compares the scores, by id, with the recomputed ones.
+pub fn compare_scores(
+    recomputed: &[ScoreEntry],
+    scores: &[ScoreEntry],
+    tolerance: f64,
+) -> LocalVerification {
+    let mut expected: HashMap<&str, f64> = recomputed
+        .iter()
+        .map(|s| (s.id().as_str(), *s.value()))
+        .collect();
+    let mut verification = LocalVerification::default();
+    for score in scores {
+        let Some(value) = expected.remove(score.id().as_str()) else {
+            verification.unexpected.push(score.id().clone());
+            continue;
+        };
+        let diff = (value - score.value()).abs();
+        verification.checked += 1;
+        verification.max_diff = verification.max_diff.max(diff);
+        if diff > tolerance {
+            verification.mismatched += 1;
+        }
+    }
+    verification.missing = expected.into_keys().map(str::to_string).collect();
+    verification.missing.sort();
+    verification
 }
 
 pub fn save_json_to_file<T: Serialize>(data: T, file: &Path) -> Result<(), std::io::Error> {
-    let file = File::create(file.to_path_buf())?;
+    let file = File::create(file)?;
     let mut writer = BufWriter::new(file);
     serde_json::to_writer(&mut writer, &data)?;
     writer.flush()?;
     Ok(())
 }
+
+/// Known node entry in the node registry config
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryNode {
+    /// Base URL of the node's server
+    pub url: String,
+    /// Expected address of the node's signing wallet
+    pub address: Address,
+}
+
+/// Reads the node registry config (JSON list of nodes) from the given path.
+pub fn read_node_registry(path: &Path) -> Result<Vec<RegistryNode>, std::io::Error> {
+    let file = File::open(path)?;
+    let nodes = serde_json::from_reader(file)?;
+    Ok(nodes)
+}
+
+/// Fetches the signed identity from the node's `/identity` endpoint.
+pub async fn fetch_node_identity(url: &str) -> Result<SignedNodeIdentity, reqwest::Error> {
+    let identity_url = format!("{}/identity", url.trim_end_matches('/'));
+    reqwest::Client::new()
+        .get(identity_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn should_pair_dataset_files() {
+        let trust_files = names(&["a.csv", "b.csv", "c.csv"]);
+        let seed_files = names(&["a.csv", "seed.csv"]);
+
+        // By name, then through the mapping, a seed file being shared
+        let mapping = HashMap::from([
+            ("b.csv".to_string(), "seed.csv".to_string()),
+            ("c.csv".to_string(), "seed.csv".to_string()),
+        ]);
+        let pairs = pair_dataset_files(&trust_files, &seed_files, &mapping).unwrap();
+        assert_eq!(
+            pairs,
+            [
+                ("a.csv", "a.csv"),
+                ("b.csv", "seed.csv"),
+                ("c.csv", "seed.csv")
+            ]
+            .map(|(t, s)| (t.to_string(), s.to_string()))
+        );
+
+        // Every mismatch is reported: an unknown mapping key, a mapped seed file missing and
+        // a trust file without a seed file
+        let mapping = HashMap::from([
+            ("d.csv".to_string(), "a.csv".to_string()),
+            ("b.csv".to_string(), "missing.csv".to_string()),
+        ]);
+        let err = pair_dataset_files(&trust_files, &seed_files, &mapping).unwrap_err();
+        assert_eq!(
+            err.0,
+            [
+                "Mapping of d.csv: no such trust file",
+                "Trust file b.csv: mapped seed file missing.csv not found",
+                "Trust file c.csv: no seed file of the same name, nor mapping",
+            ]
+        );
+    }
+}
@@ -0,0 +1,676 @@
+use crate::actions::{
+    compare_scores, compute_local_commitment, download_manifest, download_score_metadata,
+    download_scores, parse_scores, upload_meta, upload_seed, upload_trust, LocalVerification,
+};
+use crate::sol::OpenRankManager::{self, OpenRankManagerInstance};
+use alloy::hex::{self, FromHex};
+use alloy::primitives::{Address, FixedBytes, TxHash, U256};
+use alloy::providers::{PendingTransactionError, Provider};
+use aws_config::{BehaviorVersion, Region, SdkConfig};
+use aws_credential_types::Credentials;
+use aws_sdk_s3::config::SharedCredentialsProvider;
+use aws_sdk_s3::Client;
+use futures_util::StreamExt;
+use openrank_common::blocklist::{self, Blocklist};
+use openrank_common::contract::{self, ManagerReader, MetaComputeResult};
+use openrank_common::eigenda::EigenDAProxyClient;
+use openrank_common::format::{self, DataFormat};
+use openrank_common::hooks::{self, WasmHook};
+use openrank_common::manifest::{self, ComputeManifest, SubJobManifest};
+use openrank_common::merkle::{self, fixed::DenseMerkleTree, Hash};
+use openrank_common::net::NetConfig;
+use openrank_common::params::{self, AlgoParams};
+use openrank_common::proof::{self, verify_score_proof, ScoreProof};
+use openrank_common::runner;
+use openrank_common::scheme;
+use openrank_common::storage::{
+    CompressedStorage, Compression, EigenDAStorage, Error as StorageError, LocalStorage,
+    ParallelDownload, S3Config, S3Storage, StorageBackend,
+};
+use openrank_common::subgraph::{IncludeList, NodeFilter};
+use openrank_common::{JobDescription, JobResult, ScoreEntry};
+use serde::{de::DeserializeOwned, Serialize};
+use sha3::{Digest, Keccak256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Maximum number of parallel downloads of the scores, when none is given.
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 8;
+/// Time between two reads of the result when awaiting it, when none is given.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A meta compute request submitted to the manager contract.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComputeRequest {
+    pub compute_id: U256,
+    /// Content id of the job descriptions of the request.
+    pub meta_id: String,
+    pub tx_hash: TxHash,
+}
+
+/// Options of `OpenRankClient::download_scores`.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Only the sub-jobs whose name matches the pattern are downloaded.
+    pub filter: Option<glob::Pattern>,
+    /// Maximum number of parallel downloads.
+    pub concurrency: usize,
+    /// Format of the scores files, the ones other than CSV being named with the format as
+    /// extension.
+    pub format: DataFormat,
+    /// Only the highest scores of every sub-job are kept.
+    pub top: Option<usize>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            concurrency: DEFAULT_DOWNLOAD_CONCURRENCY,
+            format: DataFormat::Csv,
+            top: None,
+        }
+    }
+}
+
+/// Scores file of a downloaded sub-job.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadedScores {
+    /// Name of the sub-job.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Outcome of the verification of a compute result, by recomputing its sub-jobs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultVerification {
+    pub compute_id: U256,
+    pub computer: Address,
+    /// Names of the sub-jobs whose recomputed commitment differs from the posted one.
+    pub mismatched_sub_jobs: Vec<String>,
+    /// Whether the posted sub-job commitments make up the posted meta commitment.
+    pub meta_commitment_matches: bool,
+}
+
+impl ResultVerification {
+    pub fn is_valid(&self) -> bool {
+        self.mismatched_sub_jobs.is_empty() && self.meta_commitment_matches
+    }
+}
+
+/// Outcome of the verification of a sub-job, recomputed from its job description.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubJobVerification {
+    pub name: String,
+    /// Whether the recomputed commitment is the posted one.
+    pub commitment_matches: bool,
+    /// Comparison of the posted scores with the recomputed ones.
+    pub scores: LocalVerification,
+    /// Why the sub-job couldn't be recomputed (missing artifact, unsupported params...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SubJobVerification {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none() && self.commitment_matches && self.scores.is_valid()
+    }
+}
+
+/// Outcome of the verification of a compute result from the request and result stored by the
+/// manager contract, without trusting the manifest of the computer.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteVerification {
+    pub compute_id: U256,
+    pub computer: Address,
+    pub job_description_id: String,
+    pub results_id: String,
+    pub sub_jobs: Vec<SubJobVerification>,
+    /// Whether the tree of the recomputed commitments has the posted meta commitment as root.
+    pub meta_commitment_matches: bool,
+}
+
+impl RemoteVerification {
+    pub fn is_valid(&self) -> bool {
+        self.meta_commitment_matches && self.sub_jobs.iter().all(SubJobVerification::is_valid)
+    }
+}
+
+/// Client of an OpenRank deployment, to embed the workflow of the CLI in other programs:
+/// uploading datasets, requesting a compute, then downloading and verifying its scores.
+pub struct OpenRankClient<P: Provider> {
+    manager: OpenRankManagerInstance<P>,
+    reader: ManagerReader<P>,
+    storage: Arc<dyn StorageBackend>,
+    /// Key prefix of the tenant the datasets are uploaded under.
+    key_prefix: Option<String>,
+}
+
+impl<P: Provider + Clone> OpenRankClient<P> {
+    /// Creates a client of the manager contract, through a provider with a wallet to submit
+    /// requests, and of the storage holding the artifacts.
+    pub fn new(provider: P, manager_address: Address, storage: Arc<dyn StorageBackend>) -> Self {
+        Self {
+            manager: OpenRankManager::new(manager_address, provider.clone()),
+            reader: ManagerReader::new(manager_address, provider),
+            storage,
+            key_prefix: None,
+        }
+    }
+
+    /// Stores the datasets and scores of the requests under the key prefix of a tenant (e.g.
+    /// `acme/trust/<id>`), for deployments restricting the tenants to their own keys.
+    pub fn with_key_prefix(mut self, key_prefix: Option<String>) -> Self {
+        self.key_prefix = key_prefix;
+        self
+    }
+
+    pub fn storage(&self) -> &dyn StorageBackend {
+        self.storage.as_ref()
+    }
+
+    pub fn key_prefix(&self) -> Option<&str> {
+        self.key_prefix.as_deref()
+    }
+
+    /// Uploads a trust file, returning its content id.
+    pub async fn upload_trust(&self, path: &str) -> Result<String, Error> {
+        Ok(upload_trust(self.storage(), self.key_prefix(), path.to_string()).await?)
+    }
+
+    /// Uploads a seed file, returning its content id.
+    pub async fn upload_seed(&self, path: &str) -> Result<String, Error> {
+        Ok(upload_seed(self.storage(), self.key_prefix(), path.to_string()).await?)
+    }
+
+    /// Uploads the job descriptions and submits them as a meta compute request. Jobs without a
+    /// key prefix get the one of the client.
+    pub async fn submit_compute_request(
+        &self,
+        jobs: Vec<JobDescription>,
+    ) -> Result<ComputeRequest, Error> {
+        let jobs: Vec<JobDescription> = jobs
+            .into_iter()
+            .map(|job| match job.key_prefix {
+                Some(_) => job,
+                None => job.with_key_prefix(self.key_prefix.clone()),
+            })
+            .collect();
+        let meta_id = upload_meta(self.storage(), jobs).await?;
+        let meta_id_bytes = FixedBytes::from_hex(&meta_id).expect("Meta id is a hash");
+
+        // Get the return value (computeId) from the transaction
+        let compute_id = self
+            .manager
+            .submitMetaComputeRequest(meta_id_bytes)
+            .call()
+            .await?;
+
+        let pending_tx = self
+            .manager
+            .submitMetaComputeRequest(meta_id_bytes)
+            .send()
+            .await?;
+        let receipt = pending_tx.get_receipt().await?;
+        let tx_hash = receipt.transaction_hash;
+
+        info!("Meta Job ID: {}", meta_id);
+        info!("Tx Hash: {}", tx_hash);
+        info!("Compute ID: {}", compute_id);
+
+        Ok(ComputeRequest {
+            compute_id,
+            meta_id,
+            tx_hash,
+        })
+    }
+
+    /// Waits for the result of a compute request to be posted, reading it every
+    /// `poll_interval`, and gives up after `timeout` if set.
+    pub async fn await_result(
+        &self,
+        compute_id: U256,
+        poll_interval: Duration,
+        timeout: Option<Duration>,
+    ) -> Result<MetaComputeResult, Error> {
+        let started = Instant::now();
+        loop {
+            if let Some(result) = self.reader.result(compute_id).await? {
+                return Ok(result);
+            }
+            if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                return Err(Error::Timeout(compute_id));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Downloads the scores of the sub-jobs of a compute result to `out_dir`, with their
+    /// extended scores when requested, after checking the manifest of the result. Files
+    /// already in `out_dir` are complete downloads, and are kept.
+    pub async fn download_scores(
+        &self,
+        compute_id: U256,
+        out_dir: &Path,
+        options: &DownloadOptions,
+    ) -> Result<Vec<DownloadedScores>, Error> {
+        let (_, manifest) = self.verified_manifest(compute_id).await?;
+        std::fs::create_dir_all(out_dir)?;
+
+        let sub_jobs: Vec<_> = manifest
+            .sub_jobs
+            .iter()
+            .filter(|sj| options.filter.as_ref().is_none_or(|p| p.matches(&sj.name)))
+            .collect();
+        info!(
+            "Downloading {} of {} sub-jobs",
+            sub_jobs.len(),
+            manifest.sub_jobs.len()
+        );
+
+        let downloads = futures_util::stream::iter(sub_jobs.into_iter().map(|sj| async move {
+            let path = out_dir.join(&sj.name).display().to_string();
+            // Files are written atomically, so an existing file is a complete download
+            if let Some(metadata_id) = &sj.metadata_id {
+                let metadata_path = format!("{}.meta.csv", path);
+                if !Path::new(&metadata_path).exists() {
+                    download_score_metadata(
+                        self.storage(),
+                        sj.key_prefix.as_deref(),
+                        metadata_id.clone(),
+                        metadata_path,
+                    )
+                    .await?;
+                }
+            }
+            let scores_path = match options.format {
+                DataFormat::Csv => path,
+                format => format!("{}.{}", path, format),
+            };
+            if Path::new(&scores_path).exists() {
+                info!("Skipping already downloaded sub-job: {}", sj.name);
+            } else {
+                download_scores(
+                    self.storage(),
+                    sj.key_prefix.as_deref(),
+                    sj.scores_id.clone(),
+                    scores_path.clone(),
+                    options.format,
+                    options.top,
+                )
+                .await?;
+            }
+            Ok::<_, Error>(DownloadedScores {
+                name: sj.name.clone(),
+                path: PathBuf::from(scores_path),
+            })
+        }))
+        .buffered(options.concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+        downloads.into_iter().collect()
+    }
+
+    /// Verifies a compute result: checks its manifest, recomputes every sub-job from the
+    /// inputs it lists and compares the commitments with the posted ones.
+    pub async fn verify(&self, compute_id: U256) -> Result<ResultVerification, Error> {
+        let (result, manifest) = self.verified_manifest(compute_id).await?;
+        let mut mismatched_sub_jobs = Vec::new();
+        let mut commitments = Vec::new();
+        for sub_job in &manifest.sub_jobs {
+            let params = AlgoParams::parse(sub_job.algo_id, &sub_job.params, false)?;
+            let (_, commitment) = self.recompute(sub_job, &params).await?;
+            if hex::encode(commitment.inner()) != sub_job.commitment {
+                info!("Commitment mismatch for sub-job: {}", sub_job.name);
+                mismatched_sub_jobs.push(sub_job.name.clone());
+            }
+            let posted = hex::decode(&sub_job.commitment).map_err(|e| {
+                Error::Integrity(format!("Invalid commitment of {}: {}", sub_job.name, e))
+            })?;
+            commitments.push(Hash::from_slice(&posted));
+        }
+        let meta_commitment = DenseMerkleTree::<Keccak256>::new(commitments)?.root()?;
+        Ok(ResultVerification {
+            compute_id,
+            computer: result.computer,
+            mismatched_sub_jobs,
+            meta_commitment_matches: meta_commitment.inner() == &result.meta_commitment.0,
+        })
+    }
+
+    /// Verifies a historical compute result from the chain alone: reads the request and the
+    /// result posted for the compute id, downloads the job descriptions and the job results
+    /// they reference, then recomputes every sub-job from its trust and seed data. Each
+    /// sub-job passes if its recomputed commitment is the posted one and its posted scores
+    /// are within `tolerance` of the recomputed ones; the result passes if every sub-job does
+    /// and the recomputed commitments make up the posted meta commitment.
+    pub async fn verify_remote(
+        &self,
+        compute_id: U256,
+        tolerance: f64,
+    ) -> Result<RemoteVerification, Error> {
+        let result = self
+            .reader
+            .result(compute_id)
+            .await?
+            .ok_or(Error::NoResult(compute_id))?;
+        let (job_description_id, jobs, job_results) = self.posted_meta(&result).await?;
+        let results_id = hex::encode(result.results_id);
+
+        let mut sub_jobs = Vec::new();
+        let mut commitments = Some(Vec::new());
+        for (job, job_result) in jobs.iter().zip(&job_results) {
+            let mut verification = SubJobVerification {
+                name: job.name.clone(),
+                commitment_matches: false,
+                scores: LocalVerification::default(),
+                error: None,
+            };
+            match self.recompute_sub_job(job, job_result, tolerance).await {
+                Ok((commitment, scores)) => {
+                    verification.commitment_matches =
+                        hex::encode(commitment.inner()) == job_result.commitment;
+                    verification.scores = scores;
+                    if let Some(commitments) = commitments.as_mut() {
+                        commitments.push(commitment);
+                    }
+                }
+                Err(e) => {
+                    info!("Failed to recompute sub-job {}: {}", job.name, e);
+                    verification.error = Some(e.to_string());
+                    commitments = None;
+                }
+            }
+            sub_jobs.push(verification);
+        }
+        let meta_commitment_matches = match commitments {
+            Some(commitments) => {
+                let meta_commitment = DenseMerkleTree::<Keccak256>::new(commitments)?.root()?;
+                meta_commitment.inner() == &result.meta_commitment.0
+            }
+            None => false,
+        };
+        Ok(RemoteVerification {
+            compute_id,
+            computer: result.computer,
+            job_description_id,
+            results_id,
+            sub_jobs,
+            meta_commitment_matches,
+        })
+    }
+
+    /// Verifies a score proof, as served by the `/score-proof` endpoint of a node, against the
+    /// meta commitment posted on-chain for its compute. See `proof::verify_score_proof`.
+    pub async fn verify_score_proof(&self, proof: &ScoreProof) -> Result<(), Error> {
+        let compute_id: U256 = proof
+            .compute_id
+            .parse()
+            .map_err(|e| Error::Integrity(format!("Invalid compute id in proof: {}", e)))?;
+        let result = self
+            .reader
+            .result(compute_id)
+            .await?
+            .ok_or(Error::NoResult(compute_id))?;
+        verify_score_proof(proof, &Hash::from_slice(result.meta_commitment.as_slice()))?;
+        Ok(())
+    }
+
+    /// Recomputes a sub-job, returning its commitment and the comparison of its posted scores
+    /// with the recomputed ones.
+    async fn recompute_sub_job(
+        &self,
+        job: &JobDescription,
+        job_result: &JobResult,
+        tolerance: f64,
+    ) -> Result<(Hash, LocalVerification), Error> {
+        let sub_job = SubJobManifest::new(job, job_result);
+        let params = AlgoParams::parse(sub_job.algo_id, &sub_job.params, false)?;
+        let (recomputed, commitment) = self.recompute(&sub_job, &params).await?;
+        let scores_bytes = self
+            .download_checked(
+                &job.scores_key(&job_result.scores_id),
+                &job_result.scores_id,
+            )
+            .await?;
+        let scores = parse_scores(scores_bytes, params.precision())?;
+        Ok((commitment, compare_scores(&recomputed, &scores, tolerance)))
+    }
+
+    /// Downloads a meta artifact (job descriptions or results), checking its content id.
+    async fn download_meta<T: DeserializeOwned>(&self, meta_id: &str) -> Result<T, Error> {
+        let bytes = self
+            .download_checked(&format!("meta/{}", meta_id), meta_id)
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Reads the job descriptions and the job results referenced by a posted result and its
+    /// request, returning them with the id of the job descriptions.
+    async fn posted_meta(
+        &self,
+        result: &MetaComputeResult,
+    ) -> Result<(String, Vec<JobDescription>, Vec<JobResult>), Error> {
+        let compute_id = result.compute_id;
+        let request = self
+            .reader
+            .request(compute_id)
+            .await?
+            .ok_or(Error::NoRequest(compute_id))?;
+        let job_description_id = hex::encode(request.job_description_id);
+        let jobs: Vec<JobDescription> = self.download_meta(&job_description_id).await?;
+        let job_results: Vec<JobResult> =
+            self.download_meta(&hex::encode(result.results_id)).await?;
+        if jobs.len() != job_results.len() {
+            return Err(Error::Integrity(format!(
+                "{} job descriptions for {} job results",
+                jobs.len(),
+                job_results.len()
+            )));
+        }
+        Ok((job_description_id, jobs, job_results))
+    }
+
+    /// Reads the posted result and its manifest, checking the manifest was signed by the
+    /// computer that posted the result, with a scheme this SDK knows. A result without an
+    /// uploaded manifest, e.g. when the computer stopped right after posting it, gets its
+    /// manifest rebuilt from the job descriptions and job results referenced on-chain.
+    pub async fn verified_manifest(
+        &self,
+        compute_id: U256,
+    ) -> Result<(MetaComputeResult, ComputeManifest), Error> {
+        let result = self
+            .reader
+            .result(compute_id)
+            .await?
+            .ok_or(Error::NoResult(compute_id))?;
+        let signed_manifest = match download_manifest(self.storage(), compute_id.to_string()).await
+        {
+            Ok(signed_manifest) => signed_manifest,
+            Err(StorageError::NotFound(_)) => {
+                info!("No manifest uploaded for {}, rebuilding it", compute_id);
+                let (job_description_id, jobs, job_results) = self.posted_meta(&result).await?;
+                let manifest = ComputeManifest::new(
+                    compute_id.to_string(),
+                    result.computer,
+                    job_description_id,
+                    hex::encode(result.results_id),
+                    hex::encode(result.meta_commitment),
+                    &jobs,
+                    &job_results,
+                );
+                return Ok((result, manifest));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        signed_manifest.verify()?;
+        if signed_manifest.manifest.computer != result.computer {
+            return Err(Error::Integrity(
+                "Manifest was not signed by the computer that posted the results".to_string(),
+            ));
+        }
+        let version = &signed_manifest.manifest.proof_spec_version;
+        if scheme::find(version).is_none() {
+            return Err(Error::Integrity(format!(
+                "Unknown scheme '{}', upgrade the SDK",
+                version
+            )));
+        }
+        Ok((result, signed_manifest.manifest))
+    }
+
+    /// Recomputes the scores and the commitment of a sub-job from the artifacts listed in the
+    /// manifest, reading them with the precision of its params.
+    async fn recompute(
+        &self,
+        sub_job: &SubJobManifest,
+        params: &AlgoParams,
+    ) -> Result<(Vec<ScoreEntry>, Hash), Error> {
+        let precision = params.precision();
+        let trust = self
+            .download_checked(&sub_job.trust_key(), &sub_job.trust_id)
+            .await?;
+        let trust_entries =
+            format::trust_entries_from_bytes(trust, precision)?.collect::<Result<Vec<_>, _>>()?;
+        let seed = self
+            .download_checked(&sub_job.seed_key(), &sub_job.seed_id)
+            .await?;
+        let seed_entries =
+            format::score_entries_from_bytes(seed, precision)?.collect::<Result<Vec<_>, _>>()?;
+        let blocklist = match &sub_job.blocklist_id {
+            Some(id) => {
+                let bytes = self.storage.get(&format!("blocklist/{}", id)).await?;
+                Some(Blocklist::from_bytes_with_id(&bytes, id)?)
+            }
+            None => None,
+        };
+        let include_list = match &sub_job.include_list_id {
+            Some(id) => {
+                let bytes = self.storage.get(&format!("include/{}", id)).await?;
+                Some(IncludeList::from_bytes_with_id(&bytes, id)?)
+            }
+            None => None,
+        };
+        let hook = match &sub_job.hook_id {
+            Some(id) => {
+                let bytes = self.storage.get(&format!("hooks/{}", id)).await?;
+                Some(WasmHook::from_bytes_with_id(&bytes, id)?)
+            }
+            None => None,
+        };
+        Ok(compute_local_commitment(
+            &trust_entries,
+            &seed_entries,
+            params,
+            hook.as_ref(),
+            NodeFilter::new(blocklist.as_ref(), include_list.as_ref()),
+        )
+        .await?)
+    }
+
+    /// Downloads a dataset, checking its bytes hash to its content id.
+    async fn download_checked(&self, key: &str, id: &str) -> Result<Vec<u8>, Error> {
+        let bytes = self.storage.get(key).await?;
+        if hex::encode(Keccak256::digest(&bytes)) != id {
+            return Err(Error::Integrity(format!(
+                "Downloaded {} doesn't hash to its content id {}",
+                key, id
+            )));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Creates the S3 client from the AWS credentials, reaching S3 compatible services
+/// (e.g. MinIO) through the custom endpoint when set.
+pub fn s3_client(bucket: &S3Config, net: &NetConfig, credentials: Credentials) -> Client {
+    let config = SdkConfig::builder()
+        .region(Some(Region::new(bucket.region.clone())))
+        .credentials_provider(SharedCredentialsProvider::new(credentials))
+        .http_client(net.s3_http_client().unwrap())
+        .behavior_version(BehaviorVersion::latest())
+        .build();
+    let mut s3_config = aws_sdk_s3::config::Builder::from(&config);
+    if let Some(endpoint) = &bucket.endpoint {
+        s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+    }
+    Client::from_conf(s3_config.build())
+}
+
+/// Creates the storage of the artifacts configured by the environment, as the CLI does. The
+/// AWS credentials are only needed when the artifacts are stored in S3.
+pub fn storage_from_env(
+    bucket: &S3Config,
+    net: &NetConfig,
+    credentials: Option<Credentials>,
+) -> Arc<dyn StorageBackend> {
+    // A local directory replaces S3 when set, to run without AWS credentials
+    let mut storage: Arc<dyn StorageBackend> = match std::env::var("STORAGE_DIR") {
+        Ok(dir) if !dir.is_empty() => Arc::new(LocalStorage::new(dir)),
+        _ => {
+            let credentials = credentials.expect(
+                "AWS credentials must be set: AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY, \
+                 or `openrank config set`",
+            );
+            Arc::new(
+                S3Storage::new(s3_client(bucket, net, credentials), &bucket.bucket)
+                    .with_parallel_download(ParallelDownload::from_env()),
+            )
+        }
+    };
+    // Artifacts are stored in EigenDA when a proxy is set, the storage above keeping the index
+    if let Ok(proxy_url) = std::env::var("EIGENDA_PROXY_URL") {
+        storage = Arc::new(EigenDAStorage::new(
+            EigenDAProxyClient::with_client(proxy_url, net.reqwest_client().unwrap()),
+            storage,
+        ));
+    }
+    // The CSV artifacts are compressed when STORAGE_COMPRESSION is set, and compressed objects
+    // are always read back transparently
+    Arc::new(CompressedStorage::new(
+        storage,
+        Compression::from_env().unwrap(),
+    ))
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("Contract error: {0}")]
+    Contract(#[from] alloy::contract::Error),
+    #[error("Transaction error: {0}")]
+    Transaction(#[from] PendingTransactionError),
+    #[error("Manager error: {0}")]
+    Manager(#[from] contract::Error),
+    #[error("Manifest error: {0}")]
+    Manifest(#[from] manifest::Error),
+    #[error("Params error: {0}")]
+    Params(#[from] params::Error),
+    #[error("Compute error: {0}")]
+    Compute(#[from] runner::Error),
+    #[error("Merkle error: {0}")]
+    Merkle(#[from] merkle::Error),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Blocklist error: {0}")]
+    Blocklist(#[from] blocklist::Error),
+    #[error("Hook error: {0}")]
+    Hook(#[from] hooks::Error),
+    #[error("Proof error: {0}")]
+    Proof(#[from] proof::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("No request made for compute id {0}")]
+    NoRequest(U256),
+    #[error("No result posted for compute id {0}")]
+    NoResult(U256),
+    #[error("Timed out waiting for the result of compute id {0}")]
+    Timeout(U256),
+    #[error("Integrity error: {0}")]
+    Integrity(String),
+}
@@ -0,0 +1,285 @@
+use aws_credential_types::Credentials;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing to another config file than `~/.openrank/config.toml`.
+pub const CONFIG_PATH_VAR: &str = "OPENRANK_CONFIG";
+/// Environment variable selecting the profile, overriding the default one of the file.
+pub const PROFILE_VAR: &str = "OPENRANK_PROFILE";
+
+/// A value of the configuration, resolved at runtime from (by priority) a command line flag,
+/// an environment variable, the selected profile, then the top level of the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConfigKey {
+    ChainRpcUrl,
+    ManagerAddress,
+    ServerUrl,
+    ServerApiKey,
+    S3Bucket,
+    S3Region,
+    S3Endpoint,
+    KeyPrefix,
+    AwsAccessKeyId,
+    AwsSecretAccessKey,
+}
+
+impl ConfigKey {
+    pub const ALL: [ConfigKey; 10] = [
+        Self::ChainRpcUrl,
+        Self::ManagerAddress,
+        Self::ServerUrl,
+        Self::ServerApiKey,
+        Self::S3Bucket,
+        Self::S3Region,
+        Self::S3Endpoint,
+        Self::KeyPrefix,
+        Self::AwsAccessKeyId,
+        Self::AwsSecretAccessKey,
+    ];
+
+    /// Environment variable overriding the config file.
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            Self::ChainRpcUrl => "CHAIN_RPC_URL",
+            Self::ManagerAddress => "OPENRANK_MANAGER_ADDRESS",
+            Self::ServerUrl => "OPENRANK_SERVER_URL",
+            Self::ServerApiKey => "OPENRANK_SERVER_API_KEY",
+            Self::S3Bucket => "S3_BUCKET",
+            Self::S3Region => "S3_REGION",
+            Self::S3Endpoint => "S3_ENDPOINT_URL",
+            Self::KeyPrefix => "OPENRANK_KEY_PREFIX",
+            Self::AwsAccessKeyId => "AWS_ACCESS_KEY_ID",
+            Self::AwsSecretAccessKey => "AWS_SECRET_ACCESS_KEY",
+        }
+    }
+
+    /// Whether the value is a secret, masked when shown.
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Self::ServerApiKey | Self::AwsSecretAccessKey)
+    }
+}
+
+impl Display for ConfigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_possible_value().expect("No skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// Where a resolved value comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Flag,
+    Env,
+    Profile,
+    File,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flag => write!(f, "flag"),
+            Self::Env => write!(f, "env"),
+            Self::Profile => write!(f, "profile"),
+            Self::File => write!(f, "file"),
+        }
+    }
+}
+
+/// Values of the config file, at its top level or in a profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigValues {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_rpc_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manager_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_bucket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aws_access_key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aws_secret_access_key: Option<String>,
+}
+
+impl ConfigValues {
+    fn value_mut(&mut self, key: ConfigKey) -> &mut Option<String> {
+        match key {
+            ConfigKey::ChainRpcUrl => &mut self.chain_rpc_url,
+            ConfigKey::ManagerAddress => &mut self.manager_address,
+            ConfigKey::ServerUrl => &mut self.server_url,
+            ConfigKey::ServerApiKey => &mut self.server_api_key,
+            ConfigKey::S3Bucket => &mut self.s3_bucket,
+            ConfigKey::S3Region => &mut self.s3_region,
+            ConfigKey::S3Endpoint => &mut self.s3_endpoint,
+            ConfigKey::KeyPrefix => &mut self.key_prefix,
+            ConfigKey::AwsAccessKeyId => &mut self.aws_access_key_id,
+            ConfigKey::AwsSecretAccessKey => &mut self.aws_secret_access_key,
+        }
+    }
+
+    /// Returns the value of the key, empty values counting as unset.
+    pub fn get(&self, key: ConfigKey) -> Option<&String> {
+        let value = match key {
+            ConfigKey::ChainRpcUrl => &self.chain_rpc_url,
+            ConfigKey::ManagerAddress => &self.manager_address,
+            ConfigKey::ServerUrl => &self.server_url,
+            ConfigKey::ServerApiKey => &self.server_api_key,
+            ConfigKey::S3Bucket => &self.s3_bucket,
+            ConfigKey::S3Region => &self.s3_region,
+            ConfigKey::S3Endpoint => &self.s3_endpoint,
+            ConfigKey::KeyPrefix => &self.key_prefix,
+            ConfigKey::AwsAccessKeyId => &self.aws_access_key_id,
+            ConfigKey::AwsSecretAccessKey => &self.aws_secret_access_key,
+        };
+        value.as_ref().filter(|v| !v.is_empty())
+    }
+
+    /// Sets the value of the key, or removes it with `None`.
+    pub fn set(&mut self, key: ConfigKey, value: Option<String>) {
+        *self.value_mut(key) = value;
+    }
+}
+
+/// The config file, `~/.openrank/config.toml` by default: values at its top level, shared by
+/// all profiles, and named profiles (e.g. `[profiles.testnet]`) overriding them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Profile used when none is selected by `--profile` or `OPENRANK_PROFILE`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub values: ConfigValues,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ConfigValues>,
+    /// Profile the values are resolved with.
+    #[serde(skip)]
+    selected: Option<String>,
+}
+
+impl Config {
+    /// Path of the config file: `OPENRANK_CONFIG` when set, `~/.openrank/config.toml`
+    /// otherwise.
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os(CONFIG_PATH_VAR).filter(|p| !p.is_empty()) {
+            return PathBuf::from(path);
+        }
+        let home = std::env::var_os("HOME").unwrap_or_default();
+        Path::new(&home).join(".openrank").join("config.toml")
+    }
+
+    /// Reads the config file, a missing file being an empty config.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the config file, creating its directory.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Selects the profile to resolve the values with: the given one (e.g. by `--profile`),
+    /// then `OPENRANK_PROFILE`, then the default profile of the file. Without any, only the top
+    /// level values are used.
+    pub fn select_profile(&mut self, profile: Option<String>) -> Result<(), Error> {
+        let profile = profile
+            .or_else(|| std::env::var(PROFILE_VAR).ok())
+            .or_else(|| self.profile.clone())
+            .filter(|p| !p.is_empty());
+        if let Some(name) = &profile {
+            if !self.profiles.contains_key(name) {
+                return Err(Error::UnknownProfile(name.clone()));
+            }
+        }
+        self.selected = profile;
+        Ok(())
+    }
+
+    /// Name of the profile the values are resolved with.
+    pub fn selected_profile(&self) -> Option<&str> {
+        self.selected.as_deref()
+    }
+
+    /// Returns the values of the profile, or the top level values without one, to be edited.
+    pub fn values_mut(&mut self, profile: Option<&str>) -> &mut ConfigValues {
+        match profile {
+            Some(name) => self.profiles.entry(name.to_string()).or_default(),
+            None => &mut self.values,
+        }
+    }
+
+    /// Resolves the value of the key: the flag when given, then the environment variable, then
+    /// the selected profile, then the top level of the config file. Empty values count as unset.
+    pub fn resolve_with_source(
+        &self,
+        key: ConfigKey,
+        flag: Option<String>,
+    ) -> Option<(String, ConfigSource)> {
+        let non_empty = |value: &String| !value.is_empty();
+        let profile = self
+            .selected
+            .as_ref()
+            .and_then(|name| self.profiles.get(name));
+        flag.filter(non_empty)
+            .map(|v| (v, ConfigSource::Flag))
+            .or_else(|| {
+                let env = std::env::var(key.env_var()).ok().filter(non_empty);
+                env.map(|v| (v, ConfigSource::Env))
+            })
+            .or_else(|| {
+                let value = profile.and_then(|values| values.get(key)).cloned();
+                value.map(|v| (v, ConfigSource::Profile))
+            })
+            .or_else(|| {
+                let value = self.values.get(key).cloned();
+                value.map(|v| (v, ConfigSource::File))
+            })
+    }
+
+    pub fn resolve(&self, key: ConfigKey, flag: Option<String>) -> Option<String> {
+        self.resolve_with_source(key, flag).map(|(value, _)| value)
+    }
+
+    /// Resolves the AWS access key pair, when both keys are set.
+    pub fn aws_credentials(&self) -> Option<Credentials> {
+        let access_key_id = self.resolve(ConfigKey::AwsAccessKeyId, None)?;
+        let secret_access_key = self.resolve(ConfigKey::AwsSecretAccessKey, None)?;
+        Some(Credentials::from_keys(
+            access_key_id,
+            secret_access_key,
+            None,
+        ))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Config parse error: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Config write error: {0}")]
+    Write(#[from] toml::ser::Error),
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
+}
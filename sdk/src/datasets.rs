@@ -0,0 +1,324 @@
+use clap::ValueEnum;
+use openrank_common::snapshot::{self, snapshot_key, SnapshotManifest};
+use openrank_common::storage::{artifact_key, Error as StorageError, StorageBackend};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default path of the local dataset registry.
+pub const DEFAULT_REGISTRY_PATH: &str = "./datasets.json";
+/// Key of the registry shared through the bucket.
+pub const SHARED_REGISTRY_KEY: &str = "registry/datasets.json";
+/// Default path of the journal of the files uploaded by compute requests.
+pub const DEFAULT_UPLOAD_JOURNAL_PATH: &str = "./uploads.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetKind {
+    Trust,
+    Seed,
+}
+
+impl DatasetKind {
+    /// Prefix of the keys the datasets of this kind are stored under.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Trust => "trust",
+            Self::Seed => "seed",
+        }
+    }
+}
+
+/// A content id an alias pointed to, at some point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    pub kind: DatasetKind,
+    pub id: String,
+    /// Unix timestamp (seconds) the alias was pointed to the id.
+    pub registered_at: u64,
+}
+
+/// Registry of human-readable aliases (e.g. "farcaster-follows-2024-06") for trust and
+/// seed content ids. Every alias keeps its full history, the latest entry being the current one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatasetRegistry {
+    pub aliases: BTreeMap<String, Vec<AliasEntry>>,
+}
+
+/// Checks whether the reference is a raw content id rather than an alias.
+fn is_content_id(reference: &str) -> bool {
+    let hex = reference.trim_start_matches("0x");
+    hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl DatasetRegistry {
+    /// Reads the registry, a missing file being an empty registry.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Points the alias to the content id, keeping the previous ids in its history.
+    pub fn register(&mut self, alias: &str, kind: DatasetKind, id: &str) {
+        let registered_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let history = self.aliases.entry(alias.to_string()).or_default();
+        if history.last().is_some_and(|e| e.kind == kind && e.id == id) {
+            return;
+        }
+        history.push(AliasEntry {
+            kind,
+            id: id.trim_start_matches("0x").to_string(),
+            registered_at,
+        });
+    }
+
+    /// Returns the current entry of the alias.
+    pub fn current(&self, alias: &str) -> Option<&AliasEntry> {
+        self.aliases.get(alias).and_then(|history| history.last())
+    }
+
+    /// Resolves a dataset reference (alias or content id) to a content id.
+    pub fn resolve(&self, reference: &str, kind: DatasetKind) -> Result<String, Error> {
+        if is_content_id(reference) {
+            return Ok(reference.trim_start_matches("0x").to_string());
+        }
+        let entry = self
+            .current(reference)
+            .ok_or_else(|| Error::UnknownAlias(reference.to_string()))?;
+        if entry.kind != kind {
+            return Err(Error::KindMismatch {
+                alias: reference.to_string(),
+                expected: kind,
+                actual: entry.kind,
+            });
+        }
+        Ok(entry.id.clone())
+    }
+
+    /// Returns the aliases currently pointing to the content id.
+    pub fn aliases_of(&self, id: &str) -> Vec<String> {
+        self.aliases
+            .iter()
+            .filter(|(_, history)| history.last().is_some_and(|e| e.id == id))
+            .map(|(alias, _)| alias.clone())
+            .collect()
+    }
+
+    /// Merges another registry into this one, combining the histories of every alias.
+    pub fn merge(&mut self, other: DatasetRegistry) {
+        for (alias, entries) in other.aliases {
+            let history = self.aliases.entry(alias).or_default();
+            for entry in entries {
+                if !history.contains(&entry) {
+                    history.push(entry);
+                }
+            }
+            history.sort_by_key(|e| e.registered_at);
+        }
+    }
+}
+
+/// A file uploaded as a dataset, along with its size and modification time at the upload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub kind: DatasetKind,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+    pub size: u64,
+    /// Unix timestamp (milliseconds) of the last modification of the file.
+    pub modified_ms: u64,
+}
+
+/// Journal of the files uploaded by compute requests, keyed by their canonical path, so that
+/// a request interrupted while uploading its files doesn't hash and upload them all again when
+/// rerun. A record only stands while the file keeps the size and modification time it had.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UploadJournal {
+    pub uploads: BTreeMap<String, UploadRecord>,
+}
+
+impl UploadJournal {
+    /// Reads the journal, a missing file being an empty journal.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Writes the journal through a temporary file, so that a request interrupted while saving
+    /// it leaves the previous journal intact.
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".part");
+        let bytes = serde_json::to_vec_pretty(self)?;
+        std::fs::write(&tmp_path, bytes)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Returns the content id the file was uploaded with, unless it changed since.
+    pub fn lookup(
+        &self,
+        path: &Path,
+        kind: DatasetKind,
+        key_prefix: Option<&str>,
+    ) -> Result<Option<String>, std::io::Error> {
+        let (key, size, modified_ms) = file_state(path)?;
+        Ok(self
+            .uploads
+            .get(&key)
+            .filter(|r| {
+                r.kind == kind
+                    && r.key_prefix.as_deref() == key_prefix
+                    && r.size == size
+                    && r.modified_ms == modified_ms
+            })
+            .map(|r| r.id.clone()))
+    }
+
+    /// Records the upload of the file with the content id.
+    pub fn record(
+        &mut self,
+        path: &Path,
+        kind: DatasetKind,
+        key_prefix: Option<&str>,
+        id: &str,
+    ) -> Result<(), std::io::Error> {
+        let (key, size, modified_ms) = file_state(path)?;
+        self.uploads.insert(
+            key,
+            UploadRecord {
+                kind,
+                id: id.to_string(),
+                key_prefix: key_prefix.map(ToString::to_string),
+                size,
+                modified_ms,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Returns the canonical path, the size and the modification time of the file.
+fn file_state(path: &Path) -> Result<(String, u64, u64), std::io::Error> {
+    let metadata = std::fs::metadata(path)?;
+    let modified_ms = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let key = std::fs::canonicalize(path)?.display().to_string();
+    Ok((key, metadata.len(), modified_ms))
+}
+
+/// Downloads the shared registry, a missing object being an empty registry.
+pub async fn download_shared_registry(
+    storage: &dyn StorageBackend,
+) -> Result<DatasetRegistry, StorageError> {
+    match storage.get(SHARED_REGISTRY_KEY).await {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes).unwrap()),
+        Err(StorageError::NotFound(_)) => Ok(DatasetRegistry::default()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn upload_shared_registry(
+    storage: &dyn StorageBackend,
+    registry: &DatasetRegistry,
+) -> Result<(), StorageError> {
+    let bytes = serde_json::to_vec_pretty(registry).unwrap();
+    storage.put(SHARED_REGISTRY_KEY, bytes).await?;
+    Ok(())
+}
+
+/// A snapshot manifest, along with its id.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotEntry {
+    pub id: String,
+    #[serde(flatten)]
+    pub manifest: SnapshotManifest,
+}
+
+/// Uploads the snapshot manifest under its id, returning the id. Its datasets and its parent
+/// must have been uploaded first.
+pub async fn publish_snapshot(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    manifest: &SnapshotManifest,
+) -> Result<String, Error> {
+    let mut required = vec![
+        artifact_key(key_prefix, DatasetKind::Trust.prefix(), &manifest.trust_id),
+        artifact_key(key_prefix, DatasetKind::Seed.prefix(), &manifest.seed_id),
+    ];
+    if let Some(parent) = &manifest.parent {
+        required.push(snapshot_key(key_prefix, parent));
+    }
+    for key in required {
+        if !storage.exists(&key).await? {
+            return Err(Error::NotUploaded(key));
+        }
+    }
+    let (bytes, id) = manifest.encode()?;
+    storage.put(&snapshot_key(key_prefix, &id), bytes).await?;
+    Ok(id)
+}
+
+/// Downloads the snapshot manifest, checking it hashes to its id.
+pub async fn fetch_snapshot(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    id: &str,
+) -> Result<SnapshotManifest, Error> {
+    let bytes = storage.get(&snapshot_key(key_prefix, id)).await?;
+    Ok(SnapshotManifest::decode(&bytes, id)?)
+}
+
+/// Downloads the snapshot manifest and all its ancestors, the snapshot first and its root last.
+pub async fn fetch_snapshot_lineage(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    id: &str,
+) -> Result<Vec<SnapshotEntry>, Error> {
+    let mut lineage = Vec::new();
+    let mut next = Some(id.trim_start_matches("0x").to_lowercase());
+    while let Some(id) = next {
+        let manifest = fetch_snapshot(storage, key_prefix, &id).await?;
+        next = manifest.parent.clone();
+        lineage.push(SnapshotEntry { id, manifest });
+    }
+    Ok(lineage)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unknown dataset alias: {0}")]
+    UnknownAlias(String),
+    #[error("Alias '{alias}' is a {actual:?} dataset, expected {expected:?}")]
+    KindMismatch {
+        alias: String,
+        expected: DatasetKind,
+        actual: DatasetKind,
+    },
+    #[error("{0} was not uploaded")]
+    NotUploaded(String),
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Snapshot(#[from] snapshot::Error),
+}
@@ -0,0 +1,12 @@
+pub mod actions;
+pub mod client;
+pub mod config;
+pub mod datasets;
+pub mod progress;
+pub mod sol;
+pub mod sweep;
+pub mod verification;
+pub mod watch;
+
+pub use client::OpenRankClient;
+pub use openrank_common::proof::{verify_score_proof, ScoreProof};
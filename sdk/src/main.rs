@@ -1,44 +1,75 @@
-mod actions;
-mod sol;
-
-use crate::actions::save_json_to_file;
-use crate::sol::OpenRankManager::{MetaComputeRequestEvent, MetaComputeResultEvent};
 use actions::{
-    compute_local, compute_local_sr, download_meta, download_scores, upload_meta, upload_seed,
-    upload_trust,
+    apply_seed_update_files, apply_trust_update_files, compute_local_params, compute_local_sr,
+    create_snapshot, fetch_node_identity, merge_scores, normalize_dataset_file, pair_dataset_files,
+    read_blocklist, read_hook, read_include_list, read_job_manifest, read_node_registry,
+    read_pairing_file, upload_blocklist, upload_dataset_file, upload_hook, upload_include_list,
+    upload_seed, upload_trust, verify_local, LocalAlgo,
 };
 use alloy::eips::BlockNumberOrTag;
 use alloy::hex::{FromHex, ToHexExt};
 use alloy::primitives::{Address, FixedBytes, Uint};
 use alloy::providers::{Provider, ProviderBuilder};
-use alloy::rpc::client::RpcClient;
 use alloy::rpc::types::Log;
 use alloy::signers::local::coins_bip39::English;
 use alloy::signers::local::MnemonicBuilder;
-use alloy::transports::http::reqwest::Url;
-use aws_config::{BehaviorVersion, Region, SdkConfig};
-use aws_credential_types::Credentials;
-use aws_sdk_s3::config::SharedCredentialsProvider;
-use aws_sdk_s3::Client;
 use clap::{Parser, Subcommand};
 use dotenv::dotenv;
 use futures_util::StreamExt;
+use openrank::actions::{self, save_json_to_file};
+use openrank::client::{
+    storage_from_env, DownloadOptions, OpenRankClient, DEFAULT_DOWNLOAD_CONCURRENCY,
+};
+use openrank::config::{Config, ConfigKey};
+use openrank::datasets::{
+    download_shared_registry, fetch_snapshot, fetch_snapshot_lineage, publish_snapshot,
+    upload_shared_registry, DatasetKind, DatasetRegistry, UploadJournal, DEFAULT_REGISTRY_PATH,
+    DEFAULT_UPLOAD_JOURNAL_PATH,
+};
+use openrank::sol::OpenRankManager;
+use openrank::sol::OpenRankManager::{
+    MetaChallengeEvent, MetaComputeRequestEvent, MetaComputeResultEvent,
+};
+use openrank::verification::build_verification_manifest;
+use openrank::{sweep, verify_score_proof, watch, ScoreProof};
+use openrank_common::attestation::AttestationReport;
+use openrank_common::challenge::{BlockRef, ChallengeRecord, ChallengeWindow};
+use openrank_common::contract::ManagerReader;
+use openrank_common::format::{self, DataFormat};
+use openrank_common::hashing::HashAlgorithm;
+use openrank_common::inspect::{self, DatasetReport};
 use openrank_common::logs::setup_tracing;
+use openrank_common::merkle::Hash;
+use openrank_common::metrics::{self, Comparison, DEFAULT_TOP_K};
+use openrank_common::normalize::DuplicatePolicy;
+use openrank_common::params::{
+    AlgoParams, EigenTrustParams, NegativeEigenTrustParams, ParamsSchema, SybilRankParams,
+    ALGO_ID_EIGEN_TRUST, ALGO_ID_SYBIL_RANK,
+};
+use openrank_common::ranking::{write_ranked_csv, RankingMode};
+use openrank_common::rpc::{LogFetcher, RpcConfig};
+use openrank_common::scheme::{self, Precision};
+use openrank_common::storage::{artifact_key, is_valid_key_prefix, S3Config, StorageBackend};
+use openrank_common::subgraph::NodeFilter;
 use openrank_common::{
-    parse_score_entries_from_file, parse_trust_entries_from_file, JobDescription, JobMetadata,
-    JobResult,
+    parse_score_entries_from_file, JobDescription, JobMetadata, ScoreEntry, TrustEntry,
 };
-use sol::OpenRankManager;
 use std::collections::HashMap;
 use std::fs::{read_dir, File};
+use std::io::BufWriter;
 use std::path::Path;
 use std::process::Command;
 use std::str::FromStr;
+use std::time::Duration;
 
 use tokio::fs::{self, create_dir_all};
-use tracing::info;
+use tracing::{info, warn};
 
 const BLOCK_NUMBER_HISTORY: u64 = 1000;
+/// Time challenges are still watched for after the window closes, for the last blocks of the
+/// window to be polled.
+const CHALLENGE_WATCH_MARGIN: Duration = Duration::from_secs(30);
+const DEFAULT_WATCH_INTERVAL: u64 = 10;
+const DEFAULT_VERIFY_TOLERANCE: f64 = 1e-6;
 
 #[derive(Debug, Clone, Subcommand)]
 /// The method to call.
@@ -48,6 +79,23 @@ enum Method {
         compute_id: String,
         #[arg(long)]
         out_dir: Option<String>,
+        /// Only download sub-jobs whose name matches the glob pattern
+        #[arg(long)]
+        filter: Option<String>,
+        /// Maximum number of parallel downloads
+        #[arg(long, default_value_t = DEFAULT_DOWNLOAD_CONCURRENCY)]
+        concurrency: usize,
+        /// Also merge all downloaded scores into a single CSV with a source column, written to
+        /// the given path. It is kept out of the output directory, which holds the sub-jobs
+        #[arg(long, value_name = "PATH")]
+        merge: Option<String>,
+        /// Format of the scores files: csv, json, jsonl or parquet (needs the parquet
+        /// feature). Files other than CSV are named with the format as extension
+        #[arg(long, default_value_t = DataFormat::Csv)]
+        format: DataFormat,
+        /// Only keep the N highest scores of every sub-job
+        #[arg(long)]
+        top: Option<usize>,
     },
     #[command(about = "Watch for compute job completion and download results")]
     ComputeWatch {
@@ -55,21 +103,128 @@ enum Method {
         #[arg(long)]
         out_dir: Option<String>,
     },
+    #[command(
+        about = "Watch for challenges of a compute result until its challenge window closes"
+    )]
+    ChallengeWatch { compute_id: String },
+    #[command(about = "Submit a compute request for the jobs of a manifest, on uploaded datasets")]
+    ComputeRequestManifest {
+        /// JSON file listing the jobs: name, trust and seed (alias or content id), algo_id
+        /// and params
+        #[arg(long)]
+        from_manifest: String,
+    },
     #[command(about = "Submit a compute request with trust and seed data")]
     ComputeRequestEt {
-        trust_folder_path: String,
-        seed_folder_path: String,
+        #[arg(
+            required_unless_present_any = ["trust", "snapshot"],
+            conflicts_with_all = ["trust", "snapshot"]
+        )]
+        trust_folder_path: Option<String>,
+        #[arg(
+            required_unless_present_any = ["seed", "snapshot"],
+            conflicts_with_all = ["seed", "snapshot"]
+        )]
+        seed_folder_path: Option<String>,
+        /// Trust dataset alias or content id, instead of uploading a folder
+        #[arg(long, requires = "seed")]
+        trust: Option<String>,
+        /// Seed dataset alias or content id, instead of uploading a folder
+        #[arg(long, requires = "trust")]
+        seed: Option<String>,
+        /// Id of a published snapshot manifest, providing the trust and seed datasets
+        #[arg(long, conflicts_with_all = ["trust", "seed"])]
+        snapshot: Option<String>,
+        /// JSON file mapping trust file names to seed file names, the trust files without an
+        /// entry being paired with the seed file of the same name
+        #[arg(long, requires = "trust_folder_path")]
+        pairing: Option<String>,
+        #[arg(long)]
+        alpha: Option<f64>,
+        #[arg(long)]
+        delta: Option<f64>,
+        #[arg(long)]
+        max_iter: Option<u32>,
+        /// Emit the scores in ranking order with a rank column, ties sharing a rank
+        /// (dense: 1, 2, 2, 3 or competition: 1, 2, 2, 4)
+        #[arg(long)]
+        ranking: Option<RankingMode>,
+        /// Precision of the committed scores: f32 (default, as committed by earlier
+        /// releases) or f64
+        #[arg(long)]
+        precision: Option<Precision>,
+        /// Hash function of the scores tree: keccak256 (default) or blake3, for f64 scores
+        /// only as the f32 ones are verified on-chain
         #[arg(long)]
-        alpha: Option<f32>,
+        hash: Option<HashAlgorithm>,
+        /// Also produce the extended scores artifact, with the rank, percentile and flags
+        /// of every score
         #[arg(long)]
-        delta: Option<f32>,
+        metadata: bool,
+        /// Path to a WASM post-processing hook applied to the scores
+        #[arg(long)]
+        hook: Option<String>,
+        /// Path to a file listing ids (one per line) removed from the graph before compute
+        #[arg(long)]
+        blocklist: Option<String>,
+        /// Path to a file listing ids (one per line) the graph is restricted to: only the
+        /// edges between two listed ids are kept
+        #[arg(long)]
+        include_list: Option<String>,
     },
     #[command(about = "Submit a SybilRank compute request with trust and seed data")]
     ComputeRequestSr {
-        trust_folder_path: String,
-        seed_folder_path: String,
+        #[arg(
+            required_unless_present_any = ["trust", "snapshot"],
+            conflicts_with_all = ["trust", "snapshot"]
+        )]
+        trust_folder_path: Option<String>,
+        #[arg(
+            required_unless_present_any = ["seed", "snapshot"],
+            conflicts_with_all = ["seed", "snapshot"]
+        )]
+        seed_folder_path: Option<String>,
+        /// Trust dataset alias or content id, instead of uploading a folder
+        #[arg(long, requires = "seed")]
+        trust: Option<String>,
+        /// Seed dataset alias or content id, instead of uploading a folder
+        #[arg(long, requires = "trust")]
+        seed: Option<String>,
+        /// Id of a published snapshot manifest, providing the trust and seed datasets
+        #[arg(long, conflicts_with_all = ["trust", "seed"])]
+        snapshot: Option<String>,
+        /// JSON file mapping trust file names to seed file names, the trust files without an
+        /// entry being paired with the seed file of the same name
+        #[arg(long, requires = "trust_folder_path")]
+        pairing: Option<String>,
         #[arg(long)]
         walk_length: Option<u32>,
+        /// Emit the scores in ranking order with a rank column, ties sharing a rank
+        /// (dense: 1, 2, 2, 3 or competition: 1, 2, 2, 4)
+        #[arg(long)]
+        ranking: Option<RankingMode>,
+        /// Precision of the committed scores: f32 (default, as committed by earlier
+        /// releases) or f64
+        #[arg(long)]
+        precision: Option<Precision>,
+        /// Hash function of the scores tree: keccak256 (default) or blake3, for f64 scores
+        /// only as the f32 ones are verified on-chain
+        #[arg(long)]
+        hash: Option<HashAlgorithm>,
+        /// Also produce the extended scores artifact, with the rank, percentile and flags
+        /// of every score
+        #[arg(long)]
+        metadata: bool,
+        /// Path to a WASM post-processing hook applied to the scores
+        #[arg(long)]
+        hook: Option<String>,
+        /// Path to a file listing ids (one per line) removed from the graph before compute
+        #[arg(long)]
+        blocklist: Option<String>,
+        /// Path to a file listing ids (one per line) the graph is restricted to: only the
+        /// edges between two listed ids are kept
+        #[arg(long)]
+        include_list: Option<String>,
     },
     #[command(about = "Compute OpenRank scores locally using trust and seed data")]
     ComputeLocalEt {
@@ -78,9 +233,40 @@ enum Method {
         #[arg(long)]
         out_path: Option<String>,
         #[arg(long)]
-        alpha: Option<f32>,
+        alpha: Option<f64>,
+        #[arg(long)]
+        delta: Option<f64>,
+        #[arg(long)]
+        max_iter: Option<u32>,
+        /// Emit the scores in ranking order with a rank column, ties sharing a rank
+        /// (dense: 1, 2, 2, 3 or competition: 1, 2, 2, 4)
+        #[arg(long)]
+        ranking: Option<RankingMode>,
+        /// Precision of the committed scores: f32 (default, as committed by earlier
+        /// releases) or f64
         #[arg(long)]
-        delta: Option<f32>,
+        precision: Option<Precision>,
+        /// Path to a WASM post-processing hook applied to the scores
+        #[arg(long)]
+        hook: Option<String>,
+        /// Path to a file listing ids (one per line) removed from the graph before compute
+        #[arg(long)]
+        blocklist: Option<String>,
+        /// Path to a file listing ids (one per line) the graph is restricted to: only the
+        /// edges between two listed ids are kept
+        #[arg(long)]
+        include_list: Option<String>,
+        /// Format of the scores: csv, json, jsonl or parquet (needs the parquet feature and
+        /// --out-path). Ranked scores are written as CSV only
+        #[arg(long, default_value_t = DataFormat::Csv)]
+        format: DataFormat,
+        /// Run EigenTrust with negative trust (algo_id 3): the negative trust entries are
+        /// distrust, subtracted from the scores of the peers
+        #[arg(long)]
+        negative_trust: bool,
+        /// Weight of the distrust, with --negative-trust (1.0 by default)
+        #[arg(long, requires = "negative_trust")]
+        distrust_weight: Option<f64>,
     },
     #[command(about = "Compute SybilRank scores locally using trust and seed data")]
     ComputeLocalSr {
@@ -90,13 +276,503 @@ enum Method {
         out_path: Option<String>,
         #[arg(long)]
         walk_length: Option<u32>,
+        /// Emit the scores in ranking order with a rank column, ties sharing a rank
+        /// (dense: 1, 2, 2, 3 or competition: 1, 2, 2, 4)
+        #[arg(long)]
+        ranking: Option<RankingMode>,
+        /// Precision of the committed scores: f32 (default, as committed by earlier
+        /// releases) or f64
+        #[arg(long)]
+        precision: Option<Precision>,
+        /// Path to a WASM post-processing hook applied to the scores
+        #[arg(long)]
+        hook: Option<String>,
+        /// Path to a file listing ids (one per line) removed from the graph before compute
+        #[arg(long)]
+        blocklist: Option<String>,
+        /// Path to a file listing ids (one per line) the graph is restricted to: only the
+        /// edges between two listed ids are kept
+        #[arg(long)]
+        include_list: Option<String>,
+        /// Format of the scores: csv, json, jsonl or parquet (needs the parquet feature and
+        /// --out-path). Ranked scores are written as CSV only
+        #[arg(long, default_value_t = DataFormat::Csv)]
+        format: DataFormat,
+    },
+    #[command(about = "Verify a scores file by recomputing the scores locally")]
+    VerifyLocal {
+        trust_path: String,
+        seed_path: String,
+        scores_path: String,
+        #[arg(long, value_enum, default_value_t = LocalAlgo::Et)]
+        algo: LocalAlgo,
+        #[arg(long)]
+        alpha: Option<f64>,
+        #[arg(long)]
+        delta: Option<f64>,
+        #[arg(long)]
+        max_iter: Option<u32>,
+        #[arg(long)]
+        walk_length: Option<u32>,
+        /// Precision the scores were committed with: f32 (default) or f64
+        #[arg(long)]
+        precision: Option<Precision>,
+        /// Largest difference allowed between a score and its recomputed value
+        #[arg(long, default_value_t = DEFAULT_VERIFY_TOLERANCE)]
+        tolerance: f64,
+        /// Path to a file listing ids (one per line) removed from the graph before compute
+        #[arg(long)]
+        blocklist: Option<String>,
+        /// Path to a file listing ids (one per line) the graph is restricted to: only the
+        /// edges between two listed ids are kept
+        #[arg(long)]
+        include_list: Option<String>,
+    },
+    #[command(about = "Compare two score files with rank correlation, overlap and churn metrics")]
+    CompareScores {
+        a_path: String,
+        b_path: String,
+        /// Number of top entries compared by the overlap and churn metrics
+        #[arg(long, default_value_t = DEFAULT_TOP_K)]
+        top_k: usize,
+        /// Print the metrics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(about = "Report statistics and common issues of a trust and seed dataset")]
+    Inspect {
+        trust_path: String,
+        seed_path: String,
+        /// Number of buckets of the histogram of the trust values, not built when omitted
+        #[arg(long)]
+        histogram: Option<usize>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(about = "Run EigenTrust locally over a grid of parameters and compare the rankings")]
+    SweepEt {
+        trust_path: String,
+        seed_path: String,
+        /// Comma separated alpha values
+        #[arg(long, value_delimiter = ',')]
+        alpha: Vec<f64>,
+        /// Comma separated delta values
+        #[arg(long, value_delimiter = ',')]
+        delta: Vec<f64>,
+        /// Comma separated max iteration values
+        #[arg(long, value_delimiter = ',')]
+        max_iter: Vec<u32>,
+        #[arg(long, default_value = "./sweep")]
+        out_dir: String,
+        /// Number of top entries compared by the overlap and churn metrics
+        #[arg(long, default_value_t = DEFAULT_TOP_K)]
+        top_k: usize,
+    },
+    #[command(about = "Run SybilRank locally over a grid of parameters and compare the rankings")]
+    SweepSr {
+        trust_path: String,
+        seed_path: String,
+        /// Comma separated walk length values
+        #[arg(long, value_delimiter = ',')]
+        walk_length: Vec<u32>,
+        #[arg(long, default_value = "./sweep")]
+        out_dir: String,
+        /// Number of top entries compared by the overlap and churn metrics
+        #[arg(long, default_value_t = DEFAULT_TOP_K)]
+        top_k: usize,
+    },
+    #[command(
+        about = "Watch a directory for trust/seed CSV pairs and compute their scores locally"
+    )]
+    Watch {
+        /// Directory of the `<name>.trust.csv` and `<name>.seed.csv` pairs, the scores being
+        /// written to `<name>.scores.csv`
+        dir: String,
+        #[arg(long, value_enum, default_value_t = LocalAlgo::Et)]
+        algo: LocalAlgo,
+        #[arg(long)]
+        alpha: Option<f64>,
+        #[arg(long)]
+        delta: Option<f64>,
+        #[arg(long)]
+        max_iter: Option<u32>,
+        #[arg(long)]
+        walk_length: Option<u32>,
+        /// Seconds between two scans of the directory
+        #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL)]
+        interval: u64,
+        /// Scan the directory once and exit
+        #[arg(long)]
+        once: bool,
     },
     #[command(about = "Initialize a new OpenRank project configuration")]
     Init { path: String },
+    #[command(about = "Set or show the configuration of the CLI (~/.openrank/config.toml)")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    #[command(about = "Manage the network profiles of the config file (e.g. testnet, mainnet)")]
+    Profiles {
+        #[command(subcommand)]
+        action: ProfilesAction,
+    },
     #[command(about = "Display the current OpenRank manager contract address")]
     ShowManagerAddress,
     #[command(about = "Verify a score proof from the server against the smart contract")]
     VerifyScoreProof { compute_id: String, user_id: String },
+    #[command(about = "Query and verify identities of known nodes from a registry config")]
+    VerifyIdentities { registry_path: String },
+    #[command(about = "Point a dataset alias to a trust or seed file (uploaded) or content id")]
+    DatasetRegister {
+        alias: String,
+        #[arg(value_enum)]
+        kind: DatasetKind,
+        /// Path to the CSV file to upload, or the content id of an uploaded dataset
+        source: String,
+        /// Also publish the alias to the registry shared through the bucket
+        #[arg(long)]
+        shared: bool,
+    },
+    #[command(about = "Validate and upload a trust CSV, printing its content id")]
+    UploadTrust {
+        path: String,
+        /// Upload the canonical form of the file (see `normalize`)
+        #[arg(long)]
+        canonicalize: bool,
+        /// How duplicate edges are merged when canonicalizing: last or sum
+        #[arg(long, default_value_t = DuplicatePolicy::Last)]
+        duplicates: DuplicatePolicy,
+    },
+    #[command(about = "Validate and upload a seed CSV, printing its content id")]
+    UploadSeed {
+        path: String,
+        /// Upload the canonical form of the file (see `normalize`)
+        #[arg(long)]
+        canonicalize: bool,
+        /// How duplicate peers are merged when canonicalizing: last or sum
+        #[arg(long, default_value_t = DuplicatePolicy::Last)]
+        duplicates: DuplicatePolicy,
+    },
+    #[command(
+        about = "Write the canonical form of a trust or seed CSV: trimmed, deduplicated, sorted"
+    )]
+    Normalize {
+        #[arg(value_enum)]
+        kind: DatasetKind,
+        path: String,
+        #[arg(long)]
+        out_path: String,
+        /// How duplicate entries are merged: last (as the compute does) or sum
+        #[arg(long, default_value_t = DuplicatePolicy::Last)]
+        duplicates: DuplicatePolicy,
+        /// Format of the output: csv, json, jsonl or parquet (needs the parquet feature)
+        #[arg(long, default_value_t = DataFormat::Csv)]
+        format: DataFormat,
+        /// Keep the negative trust entries (distrust), for EigenTrust with negative trust
+        /// (algo_id 3)
+        #[arg(long)]
+        negative_trust: bool,
+    },
+    #[command(about = "Apply update files to a trust or seed dataset and upload the result")]
+    ApplyUpdates {
+        #[arg(value_enum)]
+        kind: DatasetKind,
+        /// Alias or content id of the dataset the updates apply to
+        base: String,
+        /// CSV files of the updates, applied in the given order
+        #[arg(required = true)]
+        updates: Vec<String>,
+        /// Point this alias to the updated dataset
+        #[arg(long)]
+        alias: Option<String>,
+        /// Path of the updated CSV, kept after the upload
+        #[arg(long)]
+        out_path: Option<String>,
+        /// Precision the values are read and written with: f64 (default, keeping them as
+        /// given) or f32, as parsed by f32 jobs
+        #[arg(long)]
+        precision: Option<Precision>,
+    },
+    #[command(about = "List dataset aliases and their history")]
+    DatasetList {
+        /// Include the aliases of the registry shared through the bucket
+        #[arg(long)]
+        shared: bool,
+    },
+    #[command(about = "Write the manifest of a snapshot of trust and seed CSVs")]
+    SnapshotCreate {
+        trust_path: String,
+        seed_path: String,
+        /// Id of the manifest of the snapshot this one is derived from
+        #[arg(long)]
+        parent: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        /// Also upload the trust and seed files
+        #[arg(long)]
+        upload: bool,
+        /// Path of the manifest, `./snapshot-<id>.json` by default
+        #[arg(long)]
+        out_path: Option<String>,
+    },
+    #[command(about = "Publish a snapshot manifest to the bucket, printing its id")]
+    SnapshotPublish { path: String },
+    #[command(about = "Fetch a snapshot manifest by id, checking it hashes to the id")]
+    SnapshotFetch {
+        id: String,
+        /// Also fetch its ancestors, printing the lineage from the snapshot to its root
+        #[arg(long)]
+        lineage: bool,
+        #[arg(long)]
+        out_path: Option<String>,
+    },
+    #[command(about = "List the score and commitment schemes known to this version")]
+    ShowSchemes,
+    #[command(
+        about = "Check the TEE attestation of a compute job is bound to its on-chain result"
+    )]
+    VerifyAttestation {
+        compute_id: String,
+        /// Write the raw quote to this path, to verify it with the tooling of the TEE vendor
+        #[arg(long)]
+        quote_path: Option<String>,
+    },
+    #[command(about = "Re-verify a compute result posted on-chain by recomputing its sub-jobs")]
+    VerifyRemote {
+        compute_id: String,
+        /// Largest difference allowed between a posted score and its recomputed value
+        #[arg(long, default_value_t = DEFAULT_VERIFY_TOLERANCE)]
+        tolerance: f64,
+        /// Print the verification as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(about = "Export a self-contained verification manifest for a compute job")]
+    ExportVerificationManifest {
+        compute_id: String,
+        #[arg(long)]
+        out_path: Option<String>,
+    },
+}
+
+/// Returns the path of the local dataset registry.
+fn dataset_registry_path() -> String {
+    std::env::var("OPENRANK_DATASETS_PATH").unwrap_or(DEFAULT_REGISTRY_PATH.to_string())
+}
+
+/// Loads the local dataset registry, merged with the shared one.
+async fn load_dataset_registry(storage: &dyn StorageBackend) -> DatasetRegistry {
+    let mut registry = DatasetRegistry::load(Path::new(&dataset_registry_path())).unwrap();
+    registry.merge(download_shared_registry(storage).await.unwrap());
+    registry
+}
+
+/// Returns the (name, trust id, seed id) of every job to request, either uploading the
+/// trust and seed folders (files are paired by name or by the pairing file), resolving dataset
+/// references or fetching a snapshot manifest.
+#[allow(clippy::too_many_arguments)]
+async fn job_inputs(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    trust_folder_path: Option<String>,
+    seed_folder_path: Option<String>,
+    trust: Option<String>,
+    seed: Option<String>,
+    snapshot: Option<String>,
+    pairing: Option<String>,
+) -> Result<Vec<(String, String, String)>, Box<dyn std::error::Error>> {
+    if let Some(snapshot) = snapshot {
+        let manifest = fetch_snapshot(storage, key_prefix, &snapshot).await?;
+        info!(
+            "Using snapshot {}: Trust({}, {} rows), Seed({}, {} rows)",
+            snapshot, manifest.trust_id, manifest.trust_rows, manifest.seed_id, manifest.seed_rows
+        );
+        return Ok(vec![(snapshot, manifest.trust_id, manifest.seed_id)]);
+    }
+    if let (Some(trust), Some(seed)) = (trust, seed) {
+        let registry = load_dataset_registry(storage).await;
+        let trust_id = registry.resolve(&trust, DatasetKind::Trust)?;
+        let seed_id = registry.resolve(&seed, DatasetKind::Seed)?;
+        info!(
+            "Using datasets: Trust({} -> {}), Seed({} -> {})",
+            trust, trust_id, seed, seed_id
+        );
+        return Ok(vec![(trust, trust_id, seed_id)]);
+    }
+
+    // Every trust file must have a seed file before anything is uploaded
+    let trust_folder_path = trust_folder_path.ok_or("Missing trust folder")?;
+    let seed_folder_path = seed_folder_path.ok_or("Missing seed folder")?;
+    let mapping = match pairing {
+        Some(path) => read_pairing_file(Path::new(&path))
+            .map_err(|e| format!("Failed to read pairing file {}: {}", path, e))?,
+        None => HashMap::new(),
+    };
+    let trust_files = folder_file_names(&trust_folder_path)?;
+    if trust_files.is_empty() {
+        return Err(format!("No trust file in {}", trust_folder_path).into());
+    }
+    let seed_files = folder_file_names(&seed_folder_path)?;
+    let pairs = pair_dataset_files(&trust_files, &seed_files, &mapping)?;
+    for seed_file in &seed_files {
+        if !pairs.iter().any(|(_, paired)| paired == seed_file) {
+            warn!("Seed file {} is not paired with any trust file", seed_file);
+        }
+    }
+
+    let mut journal = UploadJournal::load(Path::new(&upload_journal_path()))?;
+    let mut seed_ids: HashMap<String, String> = HashMap::new();
+    let mut inputs = Vec::new();
+    for (trust_file, seed_file) in pairs {
+        let trust_path = Path::new(&trust_folder_path).join(&trust_file);
+        let trust_id = upload_request_file(
+            storage,
+            key_prefix,
+            &mut journal,
+            DatasetKind::Trust,
+            &trust_path,
+        )
+        .await?;
+        let seed_id = match seed_ids.get(&seed_file) {
+            Some(seed_id) => seed_id.clone(),
+            None => {
+                let seed_path = Path::new(&seed_folder_path).join(&seed_file);
+                let seed_id = upload_request_file(
+                    storage,
+                    key_prefix,
+                    &mut journal,
+                    DatasetKind::Seed,
+                    &seed_path,
+                )
+                .await?;
+                seed_ids.insert(seed_file, seed_id.clone());
+                seed_id
+            }
+        };
+        inputs.push((trust_file, trust_id, seed_id));
+    }
+    Ok(inputs)
+}
+
+/// Returns the path of the journal of the files uploaded by compute requests.
+fn upload_journal_path() -> String {
+    std::env::var("OPENRANK_UPLOADS_PATH").unwrap_or(DEFAULT_UPLOAD_JOURNAL_PATH.to_string())
+}
+
+/// Uploads a trust or seed file of a compute request, unless the journal records it as
+/// uploaded and it still is. The journal is saved after every upload, so that a request
+/// interrupted while uploading resumes where it stopped.
+async fn upload_request_file(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    journal: &mut UploadJournal,
+    kind: DatasetKind,
+    path: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(id) = journal.lookup(path, kind, key_prefix)? {
+        let key = artifact_key(key_prefix, kind.prefix(), &id);
+        if storage.exists(&key).await? {
+            info!("Already uploaded: {} ({})", path.display(), key);
+            return Ok(id);
+        }
+    }
+    let id = upload_dataset_file(storage, key_prefix, kind, &path.display().to_string()).await?;
+    journal.record(path, kind, key_prefix, &id)?;
+    journal.save(Path::new(&upload_journal_path()))?;
+    Ok(id)
+}
+
+/// Names of the files of the folder, sorted.
+fn folder_file_names(folder_path: &str) -> Result<Vec<String>, String> {
+    let error = |e: std::io::Error| format!("Failed to read folder {}: {}", folder_path, e);
+    let mut names = Vec::new();
+    for entry in read_dir(folder_path).map_err(error)? {
+        let path = entry.map_err(error)?.path();
+        if path.is_file() {
+            names.push(path.file_name().unwrap().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Builds the job descriptions of a jobs manifest, resolving its dataset references and
+/// checking its params, nothing being uploaded.
+async fn manifest_job_descriptions(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    manifest_path: &str,
+) -> Vec<JobDescription> {
+    let jobs = read_job_manifest(Path::new(manifest_path)).unwrap();
+    assert!(!jobs.is_empty(), "No job in the manifest");
+    let registry = load_dataset_registry(storage).await;
+    let mut jds = Vec::new();
+    for job in jobs {
+        let trust_id = registry.resolve(&job.trust, DatasetKind::Trust).unwrap();
+        let seed_id = registry.resolve(&job.seed, DatasetKind::Seed).unwrap();
+        let trust_key = artifact_key(key_prefix, "trust", &trust_id);
+        let seed_key = artifact_key(key_prefix, "seed", &seed_id);
+        for key in [&trust_key, &seed_key] {
+            assert!(
+                storage.exists(key).await.unwrap(),
+                "Job {}: {} was not uploaded",
+                job.name,
+                key
+            );
+        }
+        let params = AlgoParams::parse(job.algo_id, &job.params, true)
+            .unwrap_or_else(|e| panic!("Job {}: {}", job.name, e));
+        let trust_cert = storage.certificate(&trust_key).await.unwrap();
+        let seed_cert = storage.certificate(&seed_key).await.unwrap();
+        info!(
+            "Job {}: Trust({}), Seed({}), AlgoId({})",
+            job.name, trust_id, seed_id, job.algo_id
+        );
+        let job_description = JobDescription::new(
+            job.name,
+            trust_id,
+            seed_id,
+            params.algo_id(),
+            params.to_map(),
+        )
+        .with_hook_id(job.hook_id)
+        .with_blocklist_id(job.blocklist_id)
+        .with_include_list_id(job.include_list_id)
+        .with_certificates(
+            trust_cert.map(|c| c.encode_hex()),
+            seed_cert.map(|c| c.encode_hex()),
+        )
+        .with_key_prefix(key_prefix.map(str::to_string));
+        jds.push(job_description);
+    }
+    jds
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ConfigAction {
+    #[command(about = "Set a value of the config file, in the profile given by --profile")]
+    Set {
+        #[arg(value_enum)]
+        key: ConfigKey,
+        value: String,
+    },
+    #[command(about = "Remove a value from the config file, in the profile given by --profile")]
+    Unset {
+        #[arg(value_enum)]
+        key: ConfigKey,
+    },
+    #[command(about = "Make a profile the default one")]
+    Use { profile: String },
+    #[command(about = "Show the resolved values and where they come from")]
+    Show,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ProfilesAction {
+    #[command(about = "List the profiles and their values, marking the selected one")]
+    List,
 }
 
 #[derive(Parser, Debug)]
@@ -104,9 +780,177 @@ enum Method {
 struct Args {
     #[command(subcommand)]
     method: Method,
+    /// Profile of the config file to use, overriding `OPENRANK_PROFILE` and the default profile
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// RPC URL of the chain, overriding `CHAIN_RPC_URL` and the config file
+    #[arg(long, global = true)]
+    rpc_url: Option<String>,
+    /// Address of the manager contract, overriding `OPENRANK_MANAGER_ADDRESS` and the config
+    /// file
+    #[arg(long, global = true)]
+    manager_address: Option<String>,
+    /// S3 bucket of the artifacts, overriding `S3_BUCKET` and the config file
+    #[arg(long, global = true)]
+    s3_bucket: Option<String>,
+    /// Region of the S3 bucket, overriding `S3_REGION` and the config file
+    #[arg(long, global = true)]
+    s3_region: Option<String>,
+    /// Endpoint of an S3 compatible service (e.g. MinIO), overriding `S3_ENDPOINT_URL` and the
+    /// config file
+    #[arg(long, global = true)]
+    s3_endpoint: Option<String>,
+    /// Key prefix of the tenant the datasets and scores are stored under (e.g. `acme` for
+    /// `acme/trust/<id>`), overriding `OPENRANK_KEY_PREFIX` and the config file
+    #[arg(long, global = true)]
+    key_prefix: Option<String>,
+}
+
+/// Writes ranked scores (`i,v,r`) to the output path, or prints them. The bytes are the same
+/// as the scores artifact of a ranked compute job of the precision.
+async fn write_ranked_scores(
+    scores: &[ScoreEntry],
+    mode: RankingMode,
+    precision: Precision,
+    out_path: Option<String>,
+) {
+    let mut bytes = Vec::new();
+    write_ranked_csv(&mut bytes, scores, mode, precision).unwrap();
+    if let Some(output_path) = out_path {
+        if let Some(parent) = Path::new(&output_path).parent() {
+            create_dir_all(parent).await.unwrap();
+        }
+        fs::write(&output_path, bytes).await.unwrap();
+        println!("Ranked scores saved to {}", output_path);
+    } else {
+        println!("{:?}", String::from_utf8(bytes));
+    }
+}
+
+/// Writes the scores in the format to the output path, or prints them, Parquet having no
+/// printable form.
+async fn write_formatted_scores(
+    scores: &[ScoreEntry],
+    format: DataFormat,
+    out_path: Option<String>,
+) {
+    if let Some(output_path) = out_path {
+        if let Some(parent) = Path::new(&output_path).parent() {
+            create_dir_all(parent).await.unwrap();
+        }
+        let file = BufWriter::new(File::create(&output_path).unwrap());
+        format::write_scores(file, scores, format).unwrap();
+        println!("Scores saved to {}", output_path);
+    } else {
+        assert!(
+            format != DataFormat::Parquet,
+            "--out-path is required to write Parquet"
+        );
+        format::write_scores(std::io::stdout(), scores, format).unwrap();
+    }
+}
+
+/// Prints the comparison metrics of two score sets.
+fn print_comparison(comparison: &Comparison) {
+    let fmt = |v: Option<f64>| v.map_or("undefined".to_string(), |v| format!("{:.4}", v));
+    println!(
+        "  common: {}, kendall_tau: {}, spearman_rho: {}, top_{}_overlap: {:.4}",
+        comparison.common,
+        fmt(comparison.kendall_tau),
+        fmt(comparison.spearman_rho),
+        comparison.top_k,
+        comparison.top_k_overlap
+    );
+    let churn = &comparison.churn;
+    println!(
+        "  added: {}, removed: {}, entered_top_k: {}, left_top_k: {}, mean_rank_shift: {:.2}, max_rank_shift: {}",
+        churn.added,
+        churn.removed,
+        churn.entered_top_k,
+        churn.left_top_k,
+        churn.mean_rank_shift,
+        churn.max_rank_shift
+    );
 }
 
-const BUCKET_NAME: &str = "openrank-data-dev";
+/// Uploads a trust or seed CSV, or its canonical form when a duplicate policy is given. The
+/// canonical form rejects negative trust entries: trust with distrust is normalized with
+/// `normalize --negative-trust` before the upload.
+async fn upload_dataset(
+    storage: &dyn StorageBackend,
+    key_prefix: Option<&str>,
+    kind: DatasetKind,
+    path: &str,
+    canonicalize: Option<DuplicatePolicy>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let Some(policy) = canonicalize else {
+        return Ok(upload_dataset_file(storage, key_prefix, kind, path).await?);
+    };
+    let canonical_path = std::env::temp_dir().join(format!(
+        "openrank-{}-{}.csv",
+        kind.prefix(),
+        std::process::id()
+    ));
+    let canonical_path = canonical_path.to_string_lossy().to_string();
+    let (read, written) =
+        normalize_dataset_file(kind, path, &canonical_path, policy, DataFormat::Csv, false)?;
+    info!("Canonicalized {}: {} entries, {} kept", path, read, written);
+    let res = upload_dataset_file(storage, key_prefix, kind, &canonical_path).await;
+    let _ = std::fs::remove_file(&canonical_path);
+    Ok(res?)
+}
+
+fn print_dataset_report(report: &DatasetReport) {
+    let trust = &report.trust;
+    println!("Trust:");
+    println!(
+        "  nodes: {}, edges: {}, entries: {}",
+        trust.nodes, trust.edges, trust.entries
+    );
+    println!(
+        "  duplicate_edges: {}, self_trust: {}, negative_values: {}, dangling_nodes: {}, zero_sum_rows: {}",
+        trust.duplicate_edges,
+        trust.self_trust,
+        trust.negative_values,
+        trust.dangling_nodes,
+        trust.zero_sum_rows
+    );
+    let seed = &report.seed;
+    println!("Seed:");
+    println!(
+        "  entries: {}, in_graph: {}, not_in_graph: {}, weight_coverage: {:.4}, graph_coverage: {:.4}",
+        seed.entries, seed.in_graph, seed.not_in_graph, seed.weight_coverage, seed.graph_coverage
+    );
+    if let Some(histogram) = &report.histogram {
+        println!("Trust values:");
+        for ((low, high), count) in histogram.bounds().zip(&histogram.counts) {
+            println!("  [{:.4}, {:.4}]: {}", low, high, count);
+        }
+    }
+}
+
+/// Prints the runs of a sweep and the comparison between each pair of runs.
+fn print_sweep_summary(summary: &sweep::SweepSummary) {
+    for run in &summary.runs {
+        println!("{}: {} scores", run.label, run.scores_count);
+    }
+    for c in &summary.comparisons {
+        println!("{} vs {}:", c.a, c.b);
+        print_comparison(&c.metrics);
+    }
+}
+
+/// Returns the value of the command line flag of the key, if it has one.
+fn cli_flag(cli: &Args, key: ConfigKey) -> Option<String> {
+    match key {
+        ConfigKey::ChainRpcUrl => cli.rpc_url.clone(),
+        ConfigKey::ManagerAddress => cli.manager_address.clone(),
+        ConfigKey::S3Bucket => cli.s3_bucket.clone(),
+        ConfigKey::S3Region => cli.s3_region.clone(),
+        ConfigKey::S3Endpoint => cli.s3_endpoint.clone(),
+        _ => None,
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -117,29 +961,93 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .install_default()
         .expect("Failed to install rustls crypto provider");
 
-    let rpc_url = option_env!("CHAIN_RPC_URL")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("CHAIN_RPC_URL").ok())
-        .expect("CHAIN_RPC_URL must be set at compile time or runtime");
-    let manager_address = option_env!("OPENRANK_MANAGER_ADDRESS")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("OPENRANK_MANAGER_ADDRESS").ok())
-        .expect("OPENRANK_MANAGER_ADDRESS must be set at compile time or runtime");
-    let aws_access_key_id = option_env!("AWS_ACCESS_KEY_ID")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("AWS_ACCESS_KEY_ID").ok())
-        .expect("AWS_ACCESS_KEY_ID must be set at compile time or runtime");
-    let aws_secret_access_key = option_env!("AWS_SECRET_ACCESS_KEY")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("AWS_SECRET_ACCESS_KEY").ok())
-        .expect("AWS_SECRET_ACCESS_KEY must be set at compile time or runtime");
-    let credentials = Credentials::from_keys(&aws_access_key_id, &aws_secret_access_key, None);
-    let config = SdkConfig::builder()
-        .region(Some(Region::new("us-west-2")))
-        .credentials_provider(SharedCredentialsProvider::new(credentials))
-        .behavior_version(BehaviorVersion::latest())
-        .build();
-    let client = Client::new(&config);
+    // Flags take precedence over the environment, itself over the profile and the config file
+    let config_path = Config::default_path();
+    let mut config = Config::load(&config_path).unwrap();
+    if let Method::Config { action } = &cli.method {
+        let profile = cli.profile.as_deref();
+        match action {
+            ConfigAction::Set { key, value } => {
+                config.values_mut(profile).set(*key, Some(value.clone()))
+            }
+            ConfigAction::Unset { key } => config.values_mut(profile).set(*key, None),
+            ConfigAction::Use { profile } => {
+                assert!(
+                    config.profiles.contains_key(profile),
+                    "Unknown profile: {}",
+                    profile
+                );
+                config.profile = Some(profile.clone());
+            }
+            ConfigAction::Show => {
+                config.select_profile(cli.profile.clone()).unwrap();
+                println!("# {}", config_path.display());
+                if let Some(profile) = config.selected_profile() {
+                    println!("# profile: {}", profile);
+                }
+                for key in ConfigKey::ALL {
+                    match config.resolve_with_source(key, cli_flag(&cli, key)) {
+                        Some((_, source)) if key.is_secret() => {
+                            println!("{} = <hidden> ({})", key, source)
+                        }
+                        Some((value, source)) => println!("{} = {} ({})", key, value, source),
+                        None => println!("{} is not set", key),
+                    }
+                }
+                return Ok(());
+            }
+        }
+        config.save(&config_path).unwrap();
+        return Ok(());
+    }
+    config.select_profile(cli.profile.clone()).unwrap();
+    if let Method::Profiles { action } = &cli.method {
+        match action {
+            ProfilesAction::List => {
+                for (name, values) in &config.profiles {
+                    let marker = if config.selected_profile() == Some(name) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{} {}", marker, name);
+                    for key in ConfigKey::ALL {
+                        match values.get(key) {
+                            Some(_) if key.is_secret() => println!("    {} = <hidden>", key),
+                            Some(value) => println!("    {} = {}", key, value),
+                            None => {}
+                        }
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let rpc_url = config
+        .resolve(ConfigKey::ChainRpcUrl, cli.rpc_url.clone())
+        .expect("CHAIN_RPC_URL must be set, with --rpc-url or `openrank config set chain-rpc-url`");
+    let rpc_config = RpcConfig::from_env(&rpc_url).unwrap();
+    let manager_address = config
+        .resolve(ConfigKey::ManagerAddress, cli.manager_address.clone())
+        .expect(
+            "OPENRANK_MANAGER_ADDRESS must be set, with --manager-address or \
+             `openrank config set manager-address`",
+        );
+    let bucket = S3Config::default().with_overrides(
+        config.resolve(ConfigKey::S3Bucket, cli.s3_bucket.clone()),
+        config.resolve(ConfigKey::S3Region, cli.s3_region.clone()),
+        config.resolve(ConfigKey::S3Endpoint, cli.s3_endpoint.clone()),
+    );
+    let storage = storage_from_env(&bucket, &rpc_config.net, config.aws_credentials());
+    let key_prefix = config.resolve(ConfigKey::KeyPrefix, cli.key_prefix.clone());
+    if let Some(prefix) = &key_prefix {
+        assert!(
+            is_valid_key_prefix(prefix),
+            "Invalid key prefix '{}', use letters, digits, '-', '_' and '.'",
+            prefix
+        );
+    }
 
     let manager_address = Address::from_hex(manager_address).unwrap();
 
@@ -147,7 +1055,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Method::DownloadScores {
             compute_id,
             out_dir,
+            filter,
+            concurrency,
+            merge,
+            format,
+            top,
         } => {
+            assert!(
+                merge.is_none() || format == DataFormat::Csv,
+                "--merge needs the scores as CSV"
+            );
             let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
             let wallet = MnemonicBuilder::<English>::default()
                 .phrase(mnemonic)
@@ -157,42 +1074,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap();
             let provider = ProviderBuilder::new()
                 .wallet(wallet)
-                .connect_client(RpcClient::new_http(Url::parse(&rpc_url).unwrap()));
-            let manager_contract = OpenRankManager::new(manager_address, provider.clone());
-            let compute_id_uint = Uint::<256, 4>::from_str(&compute_id).unwrap();
-            let compute_request = manager_contract
-                .metaComputeRequests(compute_id_uint)
-                .call()
-                .await
-                .unwrap();
-            let compute_result = manager_contract
-                .metaComputeResults(compute_id_uint)
-                .call()
-                .await
-                .unwrap();
-            let job_requests: Vec<JobDescription> = download_meta(
-                client.clone(),
-                compute_request.jobDescriptionId.encode_hex(),
-            )
-            .await
-            .unwrap();
-            let job_results: Vec<JobResult> =
-                download_meta(client.clone(), compute_result.resultsId.encode_hex())
-                    .await
-                    .unwrap();
+                .connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage);
             let mut out_dir = out_dir.unwrap_or("./scores".to_string());
             if out_dir.ends_with("/") {
                 out_dir.pop();
             }
-            create_dir_all(&out_dir).await.unwrap();
-            for (job_request, job_result) in job_requests.iter().zip(job_results) {
-                download_scores(
-                    client.clone(),
-                    job_result.scores_id.clone(),
-                    format!("{}/{}", out_dir, job_request.name),
+            let options = DownloadOptions {
+                filter: filter.map(|f| glob::Pattern::new(&f).unwrap()),
+                concurrency,
+                format,
+                top,
+            };
+            let downloads = client
+                .download_scores(
+                    Uint::from_str(&compute_id).unwrap(),
+                    Path::new(&out_dir),
+                    &options,
                 )
                 .await
                 .unwrap();
+
+            if let Some(merged_path) = merge {
+                let files: Vec<(String, String)> = downloads
+                    .into_iter()
+                    .map(|d| (d.name, d.path.display().to_string()))
+                    .collect();
+                merge_scores(&files, &merged_path).unwrap();
+                info!("Merged scores written to {}", merged_path);
             }
         }
         Method::ComputeWatch {
@@ -208,27 +1117,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap();
             let provider = ProviderBuilder::new()
                 .wallet(wallet)
-                .connect_client(RpcClient::new_http(Url::parse(&rpc_url).unwrap()));
+                .connect_client(rpc_config.client().unwrap());
             let manager_contract = OpenRankManager::new(manager_address, provider.clone());
             let current_block = provider.get_block_number().await.unwrap();
-            let starting_block = (current_block - BLOCK_NUMBER_HISTORY).max(0);
+            let starting_block = current_block - BLOCK_NUMBER_HISTORY;
 
             let mut job_metadata = JobMetadata::new();
             let request_logs_filter = manager_contract
                 .MetaComputeRequestEvent_filter()
-                .from_block(BlockNumberOrTag::Number(starting_block))
-                .to_block(BlockNumberOrTag::Latest)
                 .topic1(Uint::from_str(&compute_id).unwrap())
                 .filter;
             let results_log_filter = manager_contract
                 .MetaComputeResultEvent_filter()
-                .from_block(BlockNumberOrTag::Number(starting_block))
-                .to_block(BlockNumberOrTag::Latest)
                 .topic1(Uint::from_str(&compute_id).unwrap())
                 .filter;
 
-            let request_logs = provider.get_logs(&request_logs_filter).await.unwrap();
-            let results_logs = provider.get_logs(&results_log_filter).await.unwrap();
+            let mut log_fetcher = LogFetcher::from_env().unwrap();
+            let request_logs = log_fetcher
+                .get_logs(
+                    &provider,
+                    &request_logs_filter,
+                    starting_block,
+                    current_block,
+                )
+                .await
+                .unwrap();
+            let results_logs = log_fetcher
+                .get_logs(
+                    &provider,
+                    &results_log_filter,
+                    starting_block,
+                    current_block,
+                )
+                .await
+                .unwrap();
 
             for log in request_logs {
                 job_metadata.set_request_tx_hash(log.transaction_hash.unwrap());
@@ -279,11 +1201,110 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 print!("{}", serde_json::to_string(&job_metadata).unwrap())
             }
         }
+        Method::ChallengeWatch { compute_id } => {
+            let provider = ProviderBuilder::new().connect_client(rpc_config.client().unwrap());
+            let manager_contract = OpenRankManager::new(manager_address, provider.clone());
+            let compute_id_uint = Uint::<256, 4>::from_str(&compute_id).unwrap();
+            let compute_result = ManagerReader::new(manager_address, provider.clone())
+                .result(compute_id_uint)
+                .await
+                .unwrap()
+                .expect("No result posted for the compute id");
+            let window =
+                ChallengeWindow::Seconds(manager_contract.CHALLENGE_WINDOW().call().await.unwrap());
+            let current_block = provider.get_block_number().await.unwrap();
+
+            let challenge_record = |event: MetaChallengeEvent, log: &Log| {
+                let manager_contract = manager_contract.clone();
+                let tx_hash = log.transaction_hash;
+                async move {
+                    let challenge = manager_contract
+                        .metaChallenges(event.computeId)
+                        .call()
+                        .await
+                        .unwrap();
+                    ChallengeRecord {
+                        compute_id: event.computeId,
+                        sub_job_id: event.subJobId,
+                        challenger: challenge.challenger,
+                        timestamp: challenge.timestamp.try_into().unwrap(),
+                        tx_hash,
+                    }
+                }
+            };
+
+            let challenge_logs_filter = manager_contract
+                .MetaChallengeEvent_filter()
+                .topic1(compute_id_uint)
+                .filter;
+            let challenge_logs = LogFetcher::from_env()
+                .unwrap()
+                .get_logs(
+                    &provider,
+                    &challenge_logs_filter,
+                    current_block.saturating_sub(BLOCK_NUMBER_HISTORY),
+                    current_block,
+                )
+                .await
+                .unwrap();
+            let mut challenges = Vec::new();
+            for log in challenge_logs {
+                let event = log.log_decode::<MetaChallengeEvent>().unwrap().inner.data;
+                challenges.push(challenge_record(event, &log).await);
+            }
+
+            // Not challenged yet: watch until the window of the result closes
+            let latest = provider
+                .get_block_by_number(BlockNumberOrTag::Latest)
+                .await
+                .unwrap()
+                .expect("No latest block");
+            let latest = BlockRef::new(latest.header.number, latest.header.timestamp);
+            let result_block = BlockRef::new(0, compute_result.timestamp);
+            if challenges.is_empty() && window.is_open(&result_block, &latest) {
+                let remaining = window.remaining(&result_block, &latest);
+                info!("No challenge yet, watching for {}s", remaining);
+                let mut challenge_stream = manager_contract
+                    .MetaChallengeEvent_filter()
+                    .from_block(BlockNumberOrTag::Number(current_block))
+                    .topic1(compute_id_uint)
+                    .watch()
+                    .await
+                    .unwrap()
+                    .into_stream();
+                let deadline = Duration::from_secs(remaining) + CHALLENGE_WATCH_MARGIN;
+                if let Ok(Some(res)) = tokio::time::timeout(deadline, challenge_stream.next()).await
+                {
+                    let (event, log): (MetaChallengeEvent, Log) = res.unwrap();
+                    challenges.push(challenge_record(event, &log).await);
+                }
+            }
+
+            for challenge in &challenges {
+                println!("{}", serde_json::to_string(challenge).unwrap());
+            }
+            if !challenges.is_empty() {
+                std::process::exit(1);
+            }
+            info!("Challenge window closed without challenge");
+        }
         Method::ComputeRequestEt {
             trust_folder_path,
             seed_folder_path,
+            trust,
+            seed,
+            snapshot,
+            pairing,
             alpha,
             delta,
+            max_iter,
+            ranking,
+            precision,
+            hash,
+            metadata,
+            hook,
+            blocklist,
+            include_list,
         } => {
             let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
             let wallet = MnemonicBuilder::<English>::default()
@@ -294,72 +1315,108 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap();
             let provider = ProviderBuilder::new()
                 .wallet(wallet)
-                .connect_client(RpcClient::new_http(Url::parse(&rpc_url).unwrap()));
-            let manager_contract = OpenRankManager::new(manager_address, provider.clone());
-
-            let trust_paths = read_dir(trust_folder_path).unwrap();
-            let mut trust_map = HashMap::new();
-            for path in trust_paths {
-                let path = path.unwrap().path();
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                let display = path.display().to_string();
-                let res = upload_trust(client.clone(), display).await.unwrap();
-                trust_map.insert(file_name.to_string(), res);
-            }
-
-            let seed_paths = read_dir(seed_folder_path).unwrap();
-            let mut seed_map = HashMap::new();
-            for path in seed_paths {
-                let path = path.unwrap().path();
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                let display = path.display().to_string();
-                let res = upload_seed(client.clone(), display).await.unwrap();
-                seed_map.insert(file_name.to_string(), res);
-            }
-
+                .connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage.clone())
+                .with_key_prefix(key_prefix.clone());
+
+            let inputs = job_inputs(
+                storage.as_ref(),
+                key_prefix.as_deref(),
+                trust_folder_path,
+                seed_folder_path,
+                trust,
+                seed,
+                snapshot.clone(),
+                pairing,
+            )
+            .await?;
+
+            let hook_id = match hook {
+                Some(path) => Some(upload_hook(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+            let blocklist_id = match blocklist {
+                Some(path) => Some(upload_blocklist(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+            let include_list_id = match include_list {
+                Some(path) => Some(upload_include_list(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+
+            scheme::select(precision.unwrap_or_default(), hash.unwrap_or_default())
+                .unwrap_or_else(|e| panic!("{}", e));
+            let params = EigenTrustParams {
+                alpha,
+                delta,
+                max_iter,
+                ranking,
+                metadata: metadata.then_some(true),
+                precision,
+                hash,
+            };
             let mut jds = Vec::new();
-            for (trust_file, trust_id) in trust_map {
-                let seed_id = seed_map.get(&trust_file).unwrap();
-                let mut params = HashMap::new();
-                if let Some(a) = alpha {
-                    params.insert("alpha".to_string(), a.to_string());
-                }
-                if let Some(d) = delta {
-                    params.insert("delta".to_string(), d.to_string());
-                }
-                let job_description =
-                    JobDescription::new(trust_id, trust_file, seed_id.clone(), 1, params);
+            for (name, trust_id, seed_id) in inputs {
+                let trust_key = artifact_key(key_prefix.as_deref(), "trust", &trust_id);
+                let seed_key = artifact_key(key_prefix.as_deref(), "seed", &seed_id);
+                let trust_cert = storage.certificate(&trust_key).await?;
+                let seed_cert = storage.certificate(&seed_key).await?;
+                let job_description = JobDescription::new(
+                    name,
+                    trust_id,
+                    seed_id,
+                    ALGO_ID_EIGEN_TRUST,
+                    params.to_map(),
+                )
+                .with_hook_id(hook_id.clone())
+                .with_blocklist_id(blocklist_id.clone())
+                .with_include_list_id(include_list_id.clone())
+                .with_snapshot_id(snapshot.clone())
+                .with_certificates(
+                    trust_cert.map(|c| c.encode_hex()),
+                    seed_cert.map(|c| c.encode_hex()),
+                );
                 jds.push(job_description);
             }
 
-            let meta_id = upload_meta(client, jds).await?;
-            let meta_id_bytes = FixedBytes::from_hex(meta_id.clone()).unwrap();
-
-            // Get the return value (computeId) from the transaction
-            let compute_id = manager_contract
-                .submitMetaComputeRequest(meta_id_bytes)
-                .call()
-                .await
-                .unwrap();
-
-            let pending_tx = manager_contract
-                .submitMetaComputeRequest(meta_id_bytes)
-                .send()
-                .await
+            let request = client.submit_compute_request(jds).await.unwrap();
+            println!("{}", request.compute_id);
+        }
+        Method::ComputeRequestManifest { from_manifest } => {
+            let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
+            let wallet = MnemonicBuilder::<English>::default()
+                .phrase(mnemonic)
+                .index(0)
+                .unwrap()
+                .build()
                 .unwrap();
-            let receipt = pending_tx.get_receipt().await.unwrap();
-            let tx_hash = receipt.transaction_hash;
-
-            info!("Meta Job ID: {}", meta_id);
-            info!("Tx Hash: {}", tx_hash);
-            info!("Compute ID: {}", compute_id);
-
-            println!("{}", compute_id);
+            let provider = ProviderBuilder::new()
+                .wallet(wallet)
+                .connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage.clone())
+                .with_key_prefix(key_prefix.clone());
+
+            let jds =
+                manifest_job_descriptions(storage.as_ref(), key_prefix.as_deref(), &from_manifest)
+                    .await;
+            let request = client.submit_compute_request(jds).await.unwrap();
+            println!("{}", request.compute_id);
         }
         Method::ComputeRequestSr {
             trust_folder_path,
             seed_folder_path,
+            trust,
+            seed,
+            snapshot,
+            pairing,
             walk_length,
+            ranking,
+            precision,
+            hash,
+            metadata,
+            hook,
+            blocklist,
+            include_list,
         } => {
             let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
             let wallet = MnemonicBuilder::<English>::default()
@@ -370,64 +1427,70 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap();
             let provider = ProviderBuilder::new()
                 .wallet(wallet)
-                .connect_client(RpcClient::new_http(Url::parse(&rpc_url).unwrap()));
-            let manager_contract = OpenRankManager::new(manager_address, provider);
-
-            let trust_paths = read_dir(trust_folder_path).unwrap();
-            let mut trust_map = HashMap::new();
-            for path in trust_paths {
-                let path = path.unwrap().path();
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                let display = path.display().to_string();
-                let res = upload_trust(client.clone(), display).await.unwrap();
-                trust_map.insert(file_name.to_string(), res);
-            }
-
-            let seed_paths = read_dir(seed_folder_path).unwrap();
-            let mut seed_map = HashMap::new();
-            for path in seed_paths {
-                let path = path.unwrap().path();
-                let file_name = path.file_name().unwrap().to_str().unwrap();
-                let display = path.display().to_string();
-                let res = upload_seed(client.clone(), display).await.unwrap();
-                seed_map.insert(file_name.to_string(), res);
-            }
-
+                .connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage.clone())
+                .with_key_prefix(key_prefix.clone());
+
+            let inputs = job_inputs(
+                storage.as_ref(),
+                key_prefix.as_deref(),
+                trust_folder_path,
+                seed_folder_path,
+                trust,
+                seed,
+                snapshot.clone(),
+                pairing,
+            )
+            .await?;
+
+            let hook_id = match hook {
+                Some(path) => Some(upload_hook(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+            let blocklist_id = match blocklist {
+                Some(path) => Some(upload_blocklist(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+            let include_list_id = match include_list {
+                Some(path) => Some(upload_include_list(storage.as_ref(), path).await.unwrap()),
+                None => None,
+            };
+
+            scheme::select(precision.unwrap_or_default(), hash.unwrap_or_default())
+                .unwrap_or_else(|e| panic!("{}", e));
+            let params = SybilRankParams {
+                walk_length,
+                ranking,
+                metadata: metadata.then_some(true),
+                precision,
+                hash,
+            };
             let mut jds = Vec::new();
-            for (trust_file, trust_id) in trust_map {
-                let seed_id = seed_map.get(&trust_file).unwrap();
-                let mut params = HashMap::new();
-                if let Some(wl) = walk_length {
-                    params.insert("walk_length".to_string(), wl.to_string());
-                }
-                let job_description =
-                    JobDescription::new(trust_id, trust_file, seed_id.clone(), 2, params);
+            for (name, trust_id, seed_id) in inputs {
+                let trust_key = artifact_key(key_prefix.as_deref(), "trust", &trust_id);
+                let seed_key = artifact_key(key_prefix.as_deref(), "seed", &seed_id);
+                let trust_cert = storage.certificate(&trust_key).await?;
+                let seed_cert = storage.certificate(&seed_key).await?;
+                let job_description = JobDescription::new(
+                    name,
+                    trust_id,
+                    seed_id,
+                    ALGO_ID_SYBIL_RANK,
+                    params.to_map(),
+                )
+                .with_hook_id(hook_id.clone())
+                .with_blocklist_id(blocklist_id.clone())
+                .with_include_list_id(include_list_id.clone())
+                .with_snapshot_id(snapshot.clone())
+                .with_certificates(
+                    trust_cert.map(|c| c.encode_hex()),
+                    seed_cert.map(|c| c.encode_hex()),
+                );
                 jds.push(job_description);
             }
 
-            let meta_id = upload_meta(client, jds).await?;
-            let meta_id_bytes = FixedBytes::from_hex(meta_id.clone()).unwrap();
-
-            // Get the return value (computeId) from the transaction
-            let compute_id = manager_contract
-                .submitMetaComputeRequest(meta_id_bytes)
-                .call()
-                .await
-                .unwrap();
-
-            let pending_tx = manager_contract
-                .submitMetaComputeRequest(meta_id_bytes)
-                .send()
-                .await
-                .unwrap();
-            let receipt = pending_tx.get_receipt().await.unwrap();
-            let tx_hash = receipt.transaction_hash;
-
-            info!("Meta Job ID: {}", meta_id);
-            info!("Tx Hash: {}", tx_hash);
-            info!("Compute ID: {}", compute_id);
-
-            println!("{}", compute_id);
+            let request = client.submit_compute_request(jds).await.unwrap();
+            println!("{}", request.compute_id);
         }
         Method::ComputeLocalEt {
             trust_path,
@@ -435,17 +1498,64 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             out_path,
             alpha,
             delta,
+            max_iter,
+            ranking,
+            precision,
+            hook,
+            blocklist,
+            include_list,
+            format,
+            negative_trust,
+            distrust_weight,
         } => {
+            assert!(
+                ranking.is_none() || format == DataFormat::Csv,
+                "Ranked scores are written as CSV only"
+            );
+            let params = EigenTrustParams {
+                alpha,
+                delta,
+                max_iter,
+                ranking,
+                metadata: None,
+                precision,
+                hash: None,
+            };
+            let params = if negative_trust {
+                AlgoParams::NegativeEigenTrust(NegativeEigenTrustParams {
+                    et: params,
+                    distrust_weight,
+                })
+            } else {
+                AlgoParams::EigenTrust(params)
+            };
+            let precision = params.precision();
+
+            // Values are parsed with the precision the scores are computed with
             let f = File::open(trust_path).unwrap();
-            let trust_entries = parse_trust_entries_from_file(f).unwrap();
+            let trust_entries: Vec<TrustEntry> = format::trust_entries(f, precision)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
 
             // Read CSV, to get a list of `ScoreEntry`
             let f = File::open(seed_path).unwrap();
-            let seed_entries = parse_score_entries_from_file(f).unwrap();
-
-            let mut scores_vec = compute_local(&trust_entries, &seed_entries, alpha, delta)
-                .await
+            let seed_entries: Vec<ScoreEntry> = format::score_entries(f, precision)
+                .unwrap()
+                .collect::<Result<_, _>>()
                 .unwrap();
+            let hook = hook.map(|path| read_hook(&path).unwrap());
+            let blocklist = blocklist.map(|path| read_blocklist(&path).unwrap());
+            let include_list = include_list.map(|path| read_include_list(&path).unwrap());
+            let mut scores_vec = compute_local_params(
+                &trust_entries,
+                &seed_entries,
+                &params,
+                hook.as_ref(),
+                NodeFilter::new(blocklist.as_ref(), include_list.as_ref()),
+            )
+            .await
+            .unwrap();
 
             // Sort scores by value in descending order (highest scores first)
             scores_vec.sort_by(|a, b| {
@@ -454,24 +1564,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .unwrap_or(std::cmp::Ordering::Equal)
             });
 
-            if let Some(output_path) = out_path {
+            if let Some(mode) = ranking {
+                write_ranked_scores(&scores_vec, mode, precision, out_path).await;
+            } else if format != DataFormat::Csv {
+                write_formatted_scores(&scores_vec, format, out_path).await;
+            } else if let Some(output_path) = out_path {
                 // Create parent directories if they don't exist
                 if let Some(parent) = std::path::Path::new(&output_path).parent() {
                     create_dir_all(parent).await.unwrap();
                 }
                 let scores_file = File::create(output_path).unwrap();
                 let mut wtr = csv::Writer::from_writer(scores_file);
-                wtr.write_record(&["i", "v"]).unwrap();
+                wtr.write_record(["i", "v"]).unwrap();
                 for x in scores_vec {
-                    wtr.write_record(&[x.id(), x.value().to_string().as_str()])
+                    wtr.write_record([x.id(), precision.format(*x.value()).as_str()])
                         .unwrap();
                 }
             } else {
                 let scores_wrt = Vec::new();
                 let mut wtr = csv::Writer::from_writer(scores_wrt);
-                wtr.write_record(&["i", "v"]).unwrap();
+                wtr.write_record(["i", "v"]).unwrap();
                 for x in scores_vec {
-                    wtr.write_record(&[x.id(), x.value().to_string().as_str()])
+                    wtr.write_record([x.id(), precision.format(*x.value()).as_str()])
                         .unwrap();
                 }
                 let res = wtr.into_inner().unwrap();
@@ -483,17 +1597,51 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             seed_path,
             out_path,
             walk_length,
+            ranking,
+            precision,
+            hook,
+            blocklist,
+            include_list,
+            format,
         } => {
+            assert!(
+                ranking.is_none() || format == DataFormat::Csv,
+                "Ranked scores are written as CSV only"
+            );
+            let params = SybilRankParams {
+                walk_length,
+                ranking,
+                metadata: None,
+                precision,
+                hash: None,
+            };
+            let precision = precision.unwrap_or_default();
+
+            // Values are parsed with the precision the scores are computed with
             let f = File::open(trust_path).unwrap();
-            let trust_entries = parse_trust_entries_from_file(f).unwrap();
+            let trust_entries: Vec<TrustEntry> = format::trust_entries(f, precision)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
 
             // Read CSV, to get a list of `ScoreEntry`
             let f = File::open(seed_path).unwrap();
-            let seed_entries = parse_score_entries_from_file(f).unwrap();
-
-            let mut scores_vec = compute_local_sr(&trust_entries, &seed_entries, walk_length)
-                .await
+            let seed_entries: Vec<ScoreEntry> = format::score_entries(f, precision)
+                .unwrap()
+                .collect::<Result<_, _>>()
                 .unwrap();
+            let hook = hook.map(|path| read_hook(&path).unwrap());
+            let blocklist = blocklist.map(|path| read_blocklist(&path).unwrap());
+            let include_list = include_list.map(|path| read_include_list(&path).unwrap());
+            let mut scores_vec = compute_local_sr(
+                &trust_entries,
+                &seed_entries,
+                &params,
+                hook.as_ref(),
+                NodeFilter::new(blocklist.as_ref(), include_list.as_ref()),
+            )
+            .await
+            .unwrap();
 
             // Sort scores by value in descending order (highest scores first)
             scores_vec.sort_by(|a, b| {
@@ -502,12 +1650,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .unwrap_or(std::cmp::Ordering::Equal)
             });
 
-            if let Some(output_path) = out_path {
+            if let Some(mode) = ranking {
+                write_ranked_scores(&scores_vec, mode, precision, out_path).await;
+            } else if format != DataFormat::Csv {
+                write_formatted_scores(&scores_vec, format, out_path).await;
+            } else if let Some(output_path) = out_path {
                 let mut wtr = csv::Writer::from_path(output_path.clone()).unwrap();
-                wtr.write_record(&["id", "value"]).unwrap();
+                wtr.write_record(["id", "value"]).unwrap();
 
                 for entry in scores_vec {
-                    wtr.write_record(&[entry.id(), entry.value().to_string().as_str()])
+                    wtr.write_record([entry.id(), precision.format(*entry.value()).as_str()])
                         .unwrap();
                 }
                 wtr.flush().unwrap();
@@ -515,15 +1667,139 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("SybilRank scores saved to {}", output_path);
             } else {
                 let mut wtr = csv::Writer::from_writer(vec![]);
-                wtr.write_record(&["id", "value"]).unwrap();
+                wtr.write_record(["id", "value"]).unwrap();
                 for entry in scores_vec {
-                    wtr.write_record(&[entry.id(), entry.value().to_string().as_str()])
+                    wtr.write_record([entry.id(), precision.format(*entry.value()).as_str()])
                         .unwrap();
                 }
                 let res = wtr.into_inner().unwrap();
                 println!("{:?}", String::from_utf8(res));
             }
         }
+        Method::VerifyLocal {
+            trust_path,
+            seed_path,
+            scores_path,
+            algo,
+            alpha,
+            delta,
+            max_iter,
+            walk_length,
+            precision,
+            tolerance,
+            blocklist,
+            include_list,
+        } => {
+            let params = algo.params(alpha, delta, max_iter, walk_length, precision);
+            let precision = params.precision();
+            let trust_entries: Vec<TrustEntry> =
+                format::trust_entries(File::open(trust_path).unwrap(), precision)
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+            let seed_entries: Vec<ScoreEntry> =
+                format::score_entries(File::open(seed_path).unwrap(), precision)
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+            let scores = parse_score_entries_from_file(File::open(scores_path).unwrap()).unwrap();
+            let blocklist = blocklist.map(|path| read_blocklist(&path).unwrap());
+            let include_list = include_list.map(|path| read_include_list(&path).unwrap());
+            let verification = verify_local(
+                &trust_entries,
+                &seed_entries,
+                &scores,
+                &params,
+                tolerance,
+                NodeFilter::new(blocklist.as_ref(), include_list.as_ref()),
+            )
+            .await
+            .unwrap();
+            println!("{}", serde_json::to_string_pretty(&verification).unwrap());
+            if !verification.is_valid() {
+                std::process::exit(1);
+            }
+        }
+        Method::CompareScores {
+            a_path,
+            b_path,
+            top_k,
+            json,
+        } => {
+            let a = parse_score_entries_from_file(File::open(a_path).unwrap()).unwrap();
+            let b = parse_score_entries_from_file(File::open(b_path).unwrap()).unwrap();
+            let comparison = metrics::compare(&a, &b, top_k);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison).unwrap());
+            } else {
+                print_comparison(&comparison);
+            }
+        }
+        Method::Inspect {
+            trust_path,
+            seed_path,
+            histogram,
+            json,
+        } => {
+            let report = inspect::inspect(
+                format::trust_entries(File::open(trust_path).unwrap(), Precision::F64).unwrap(),
+                format::score_entries(File::open(seed_path).unwrap(), Precision::F64).unwrap(),
+                histogram,
+            )
+            .unwrap();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            } else {
+                print_dataset_report(&report);
+            }
+        }
+        Method::SweepEt {
+            trust_path,
+            seed_path,
+            alpha,
+            delta,
+            max_iter,
+            out_dir,
+            top_k,
+        } => {
+            let grid = sweep::eigen_trust_grid(&alpha, &delta, &max_iter);
+            let summary = sweep::sweep_to_dir(&trust_path, &seed_path, &grid, &out_dir, top_k);
+            print_sweep_summary(&summary);
+            println!("Sweep results saved to {}", out_dir);
+        }
+        Method::SweepSr {
+            trust_path,
+            seed_path,
+            walk_length,
+            out_dir,
+            top_k,
+        } => {
+            let grid = sweep::sybil_rank_grid(&walk_length);
+            let summary = sweep::sweep_to_dir(&trust_path, &seed_path, &grid, &out_dir, top_k);
+            print_sweep_summary(&summary);
+            println!("Sweep results saved to {}", out_dir);
+        }
+        Method::Watch {
+            dir,
+            algo,
+            alpha,
+            delta,
+            max_iter,
+            walk_length,
+            interval,
+            once,
+        } => {
+            let params = algo.params(alpha, delta, max_iter, walk_length, None);
+            let dir = Path::new(&dir);
+            if once {
+                let processed = watch::scan(dir, &params).await.unwrap();
+                println!("Processed {} dataset pair(s)", processed.len());
+            } else {
+                watch::watch(dir, &params, Duration::from_secs(interval))
+                    .await
+                    .unwrap();
+            }
+        }
         Method::Init { path } => {
             // Ensure target directory exists
             if let Err(e) = create_dir_all(&path).await {
@@ -533,7 +1809,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             // Check if git is available
             let git_check = std::process::Command::new("git")
-                .args(&["--version"])
+                .args(["--version"])
                 .output();
             match git_check {
                 Ok(output) if output.status.success() => {
@@ -547,7 +1823,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             // Check if git lfs is available
             let lfs_output = std::process::Command::new("git")
-                .args(&["lfs", "version"])
+                .args(["lfs", "version"])
                 .output()
                 .unwrap();
             if !lfs_output.status.success() {
@@ -557,7 +1833,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             // Clone the repository with shallow clone (no history)
             let output = std::process::Command::new("git")
-                .args(&[
+                .args([
                     "clone",
                     "--depth",
                     "1",
@@ -578,7 +1854,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
 
             let remove_origin_output = Command::new("git")
-                .args(&["remote", "remove", "origin"])
+                .args(["remote", "remove", "origin"])
                 .current_dir(&path)
                 .output()
                 .unwrap();
@@ -595,7 +1871,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             // Change to the datasets directory and pull LFS files
             let lfs_pull_output = std::process::Command::new("git")
-                .args(&["lfs", "pull"])
+                .args(["lfs", "pull"])
                 .current_dir(&path)
                 .output()
                 .unwrap();
@@ -609,7 +1885,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
 
             let cleanup_output = Command::new("rm")
-                .args(&["-rf", ".git", ".gitattributes", ".gitignore"])
+                .args(["-rf", ".git", ".gitattributes", ".gitignore"])
                 .current_dir(&path)
                 .output()
                 .unwrap();
@@ -631,16 +1907,220 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("Initialization completed!");
         }
+        Method::Config { .. } | Method::Profiles { .. } => {
+            unreachable!("Handled before resolving the configuration")
+        }
         Method::ShowManagerAddress => {
             println!("{}", manager_address);
         }
+        Method::UploadTrust {
+            path,
+            canonicalize,
+            duplicates,
+        } => {
+            let id = upload_dataset(
+                storage.as_ref(),
+                key_prefix.as_deref(),
+                DatasetKind::Trust,
+                &path,
+                canonicalize.then_some(duplicates),
+            )
+            .await?;
+            println!("{}", id);
+        }
+        Method::UploadSeed {
+            path,
+            canonicalize,
+            duplicates,
+        } => {
+            let id = upload_dataset(
+                storage.as_ref(),
+                key_prefix.as_deref(),
+                DatasetKind::Seed,
+                &path,
+                canonicalize.then_some(duplicates),
+            )
+            .await?;
+            println!("{}", id);
+        }
+        Method::Normalize {
+            kind,
+            path,
+            out_path,
+            duplicates,
+            format,
+            negative_trust,
+        } => {
+            let (read, written) =
+                normalize_dataset_file(kind, &path, &out_path, duplicates, format, negative_trust)?;
+            println!("{} entries read, {} written to {}", read, written, out_path);
+        }
+        Method::DatasetRegister {
+            alias,
+            kind,
+            source,
+            shared,
+        } => {
+            let id = if Path::new(&source).is_file() {
+                match kind {
+                    DatasetKind::Trust => {
+                        upload_trust(storage.as_ref(), key_prefix.as_deref(), source).await?
+                    }
+                    DatasetKind::Seed => {
+                        upload_seed(storage.as_ref(), key_prefix.as_deref(), source).await?
+                    }
+                }
+            } else {
+                // Not a file, must be a content id
+                DatasetRegistry::default().resolve(&source, kind).unwrap()
+            };
+
+            let path = dataset_registry_path();
+            let mut registry = DatasetRegistry::load(Path::new(&path)).unwrap();
+            registry.register(&alias, kind, &id);
+            registry.save(Path::new(&path)).unwrap();
+            if shared {
+                let mut shared_registry = download_shared_registry(storage.as_ref()).await?;
+                shared_registry.register(&alias, kind, &id);
+                upload_shared_registry(storage.as_ref(), &shared_registry).await?;
+            }
+            info!(
+                "Registered dataset: Alias({}), Kind({:?}), Id({})",
+                alias, kind, id
+            );
+            println!("{}", id);
+        }
+        Method::ApplyUpdates {
+            kind,
+            base,
+            updates,
+            alias,
+            out_path,
+            precision,
+        } => {
+            let precision = precision.unwrap_or(Precision::F64);
+            let registry = load_dataset_registry(storage.as_ref()).await;
+            let base_id = registry.resolve(&base, kind).unwrap();
+            let path = out_path.unwrap_or(format!("./{}-updated.csv", base_id));
+            let id = match kind {
+                DatasetKind::Trust => {
+                    apply_trust_update_files(
+                        storage.as_ref(),
+                        key_prefix.as_deref(),
+                        &base_id,
+                        &updates,
+                        &path,
+                        precision,
+                    )
+                    .await
+                    .unwrap();
+                    upload_trust(storage.as_ref(), key_prefix.as_deref(), path).await?
+                }
+                DatasetKind::Seed => {
+                    apply_seed_update_files(
+                        storage.as_ref(),
+                        key_prefix.as_deref(),
+                        &base_id,
+                        &updates,
+                        &path,
+                        precision,
+                    )
+                    .await
+                    .unwrap();
+                    upload_seed(storage.as_ref(), key_prefix.as_deref(), path).await?
+                }
+            };
+
+            if let Some(alias) = alias {
+                let path = dataset_registry_path();
+                let mut registry = DatasetRegistry::load(Path::new(&path)).unwrap();
+                registry.register(&alias, kind, &id);
+                registry.save(Path::new(&path)).unwrap();
+                info!(
+                    "Registered dataset: Alias({}), Kind({:?}), Id({})",
+                    alias, kind, id
+                );
+            }
+            println!("{}", id);
+        }
+        Method::DatasetList { shared } => {
+            let registry = if shared {
+                load_dataset_registry(storage.as_ref()).await
+            } else {
+                DatasetRegistry::load(Path::new(&dataset_registry_path())).unwrap()
+            };
+            println!("{}", serde_json::to_string_pretty(&registry).unwrap());
+        }
+        Method::SnapshotCreate {
+            trust_path,
+            seed_path,
+            parent,
+            description,
+            upload,
+            out_path,
+        } => {
+            let manifest = create_snapshot(&trust_path, &seed_path, parent, description).unwrap();
+            if upload {
+                for (kind, path) in [
+                    (DatasetKind::Trust, &trust_path),
+                    (DatasetKind::Seed, &seed_path),
+                ] {
+                    upload_dataset_file(storage.as_ref(), key_prefix.as_deref(), kind, path)
+                        .await?;
+                }
+            }
+            let id = manifest.id().unwrap();
+            let path = out_path.unwrap_or(format!("./snapshot-{}.json", id));
+            save_json_to_file(&manifest, Path::new(&path)).unwrap();
+            info!(
+                "Created snapshot {}: Trust({}, {} rows), Seed({}, {} rows), written to {}",
+                id,
+                manifest.trust_id,
+                manifest.trust_rows,
+                manifest.seed_id,
+                manifest.seed_rows,
+                path
+            );
+            println!("{}", id);
+        }
+        Method::SnapshotPublish { path } => {
+            let manifest = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+            let id = publish_snapshot(storage.as_ref(), key_prefix.as_deref(), &manifest)
+                .await
+                .unwrap();
+            info!("Published snapshot: {}", id);
+            println!("{}", id);
+        }
+        Method::SnapshotFetch {
+            id,
+            lineage,
+            out_path,
+        } => {
+            let json = if lineage {
+                let lineage = fetch_snapshot_lineage(storage.as_ref(), key_prefix.as_deref(), &id)
+                    .await
+                    .unwrap();
+                serde_json::to_string_pretty(&lineage).unwrap()
+            } else {
+                let manifest = fetch_snapshot(storage.as_ref(), key_prefix.as_deref(), &id)
+                    .await
+                    .unwrap();
+                serde_json::to_string_pretty(&manifest).unwrap()
+            };
+            match out_path {
+                Some(path) => std::fs::write(path, json).unwrap(),
+                None => println!("{}", json),
+            }
+        }
+        Method::ShowSchemes => {
+            println!("{}", serde_json::to_string_pretty(scheme::SCHEMES).unwrap());
+        }
         Method::VerifyScoreProof {
             compute_id,
             user_id,
         } => {
-            let server_url = option_env!("OPENRANK_SERVER_URL")
-                .map(|s| s.to_string())
-                .or_else(|| std::env::var("OPENRANK_SERVER_URL").ok())
+            let server_url = config
+                .resolve(ConfigKey::ServerUrl, None)
                 .unwrap_or_else(|| "http://localhost:3000".to_string());
 
             let mnemonic = std::env::var("MNEMONIC").expect("MNEMONIC must be set.");
@@ -652,19 +2132,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .unwrap();
             let provider = ProviderBuilder::new()
                 .wallet(wallet)
-                .connect_client(RpcClient::new_http(Url::parse(&rpc_url).unwrap()));
+                .connect_client(rpc_config.client().unwrap());
             let manager_contract = OpenRankManager::new(manager_address, provider.clone());
 
-            // Call the server to get the proof
+            // Call the server to get the proof, of the jobs of our manager
             let proof_url = format!(
-                "{}/score-proof?compute_id={}&user_id={}",
-                server_url, compute_id, user_id
+                "{}/score-proof?compute_id={}&user_id={}&manager={}",
+                server_url, compute_id, user_id, manager_address
             );
             info!("Fetching proof from: {}", proof_url);
 
             let http_client = reqwest::Client::new();
-            let response = http_client
-                .get(&proof_url)
+            let mut request = http_client.get(&proof_url);
+            // Sent to the servers restricting the score endpoints to API keys
+            if let Some(api_key) = config.resolve(ConfigKey::ServerApiKey, None) {
+                request = request.header("x-api-key", api_key);
+            }
+            let response = request
                 .send()
                 .await
                 .expect("Failed to fetch proof from server");
@@ -675,68 +2159,205 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 return Ok(());
             }
 
-            let proof: serde_json::Value = response
+            let proof: ScoreProof = response
                 .json()
                 .await
                 .expect("Failed to parse proof response");
 
             info!("Received proof: {:?}", proof);
 
-            // Extract proof fields
-            let score = proof["score"].as_f64().expect("Missing score") as f32;
-            let score_bytes: [u8; 4] = score.to_be_bytes();
-            let score_index = proof["score_index"].as_u64().expect("Missing score_index") as usize;
-            let meta_index = proof["meta_index"].as_u64().expect("Missing meta_index") as usize;
-
-            let scores_tree_path: Vec<FixedBytes<32>> = proof["scores_tree_path"]
-                .as_array()
-                .expect("Missing scores_tree_path")
-                .iter()
-                .map(|h| {
-                    let hex_str = h.as_str().expect("Invalid hash");
-                    FixedBytes::from_hex(hex_str).expect("Invalid hex")
-                })
-                .collect();
-
-            let scores_tree_root: FixedBytes<32> = FixedBytes::from_hex(
-                proof["scores_tree_root"]
-                    .as_str()
-                    .expect("Missing scores_tree_root"),
-            )
-            .expect("Invalid scores_tree_root hex");
-
-            let meta_tree_path: Vec<FixedBytes<32>> = proof["meta_tree_path"]
-                .as_array()
-                .expect("Missing meta_tree_path")
-                .iter()
-                .map(|h| {
-                    let hex_str = h.as_str().expect("Invalid hash");
-                    FixedBytes::from_hex(hex_str).expect("Invalid hex")
-                })
-                .collect();
+            let proof_scheme = scheme::find(&proof.scheme_version).unwrap_or_else(|| {
+                panic!("Unknown scheme '{}', upgrade the SDK", proof.scheme_version)
+            });
 
-            // Call the smart contract to verify
+            // Check the proof locally first, against the meta commitment posted on-chain
             let compute_id_uint = Uint::<256, 4>::from_str(&compute_id).unwrap();
-            let score_bytes_fixed = FixedBytes::<4>::from_slice(&score_bytes);
-
-            let result = manager_contract
-                .verifyScoreProof(
-                    compute_id_uint,
-                    score_bytes_fixed,
-                    Uint::<256, 4>::from(score_index),
-                    scores_tree_path,
-                    scores_tree_root,
-                    Uint::<256, 4>::from(meta_index),
-                    meta_tree_path,
-                )
-                .call()
+            let compute_result = ManagerReader::new(manager_address, provider.clone())
+                .result(compute_id_uint)
                 .await
-                .expect("Failed to call verifyScoreProof");
+                .unwrap()
+                .expect("No result posted for the compute id");
+            let local_result = match verify_score_proof(
+                &proof,
+                &Hash::from_slice(compute_result.meta_commitment.as_slice()),
+            ) {
+                Ok(()) => "true".to_string(),
+                Err(e) => format!("false ({})", e),
+            };
+
+            // The contract hashes f32 leaves only, proofs of f64 scores are checked locally
+            let result = if proof_scheme.precision == Precision::F32 {
+                let to_fixed = |path: &[Hash]| {
+                    path.iter()
+                        .map(|h| FixedBytes::<32>::from(*h.inner()))
+                        .collect::<Vec<_>>()
+                };
+                let score_bytes_fixed =
+                    FixedBytes::<4>::from_slice(&proof_scheme.leaf_bytes(proof.score));
+                let result = manager_contract
+                    .verifyScoreProof(
+                        compute_id_uint,
+                        score_bytes_fixed,
+                        Uint::<256, 4>::from(proof.score_index),
+                        to_fixed(&proof.scores_tree_path),
+                        FixedBytes::from(*proof.scores_tree_root.inner()),
+                        Uint::<256, 4>::from(proof.meta_index),
+                        to_fixed(&proof.meta_tree_path),
+                    )
+                    .call()
+                    .await
+                    .expect("Failed to call verifyScoreProof");
+                result.to_string()
+            } else {
+                format!("unsupported for {} scores", proof_scheme.leaf_encoding)
+            };
 
             println!("User: {}", user_id);
-            println!("Score: {}", score);
+            println!("Score: {}", proof.score);
+            if let Some(metadata) = &proof.metadata {
+                println!("Rank: {}", metadata.rank);
+                println!("Percentile: {}", metadata.percentile);
+            }
+            println!("Local verification result: {}", local_result);
             println!("Verification result: {}", result);
         }
+        Method::VerifyIdentities { registry_path } => {
+            let nodes = read_node_registry(Path::new(&registry_path))
+                .expect("Failed to read node registry");
+
+            for node in nodes {
+                let signed_identity = match fetch_node_identity(&node.url).await {
+                    Ok(identity) => identity,
+                    Err(e) => {
+                        println!("{}: FAILED (could not fetch identity: {})", node.url, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = signed_identity.verify() {
+                    println!("{}: FAILED ({})", node.url, e);
+                    continue;
+                }
+                let identity = signed_identity.identity;
+                if identity.address != node.address {
+                    println!(
+                        "{}: FAILED (expected address {}, got {})",
+                        node.url, node.address, identity.address
+                    );
+                    continue;
+                }
+                println!(
+                    "{}: OK (address: {}, roles: {:?}, algo_ids: {:?}, version: {}, domains: {:?}, attestation: {})",
+                    node.url,
+                    identity.address,
+                    identity.roles,
+                    identity.algo_ids,
+                    identity.version,
+                    identity.domains,
+                    identity.attestation.is_some()
+                );
+            }
+        }
+        Method::VerifyAttestation {
+            compute_id,
+            quote_path,
+        } => {
+            let provider = ProviderBuilder::new().connect_client(rpc_config.client().unwrap());
+            let manager = ManagerReader::new(manager_address, provider);
+            let compute_id_uint = Uint::<256, 4>::from_str(&compute_id).unwrap();
+            let compute_result = manager
+                .result(compute_id_uint)
+                .await
+                .unwrap()
+                .expect("No result posted for the compute id");
+            let results_id = compute_result.results_id.encode_hex();
+            let bytes = storage
+                .get(&format!("attestation/{}", results_id))
+                .await
+                .expect("No attestation uploaded for the results");
+            let report: AttestationReport = serde_json::from_slice(&bytes).unwrap();
+            report
+                .check_binding(&compute_result.meta_commitment.encode_hex())
+                .unwrap();
+            if let Some(quote_path) = quote_path {
+                std::fs::write(&quote_path, report.quote_bytes().unwrap()).unwrap();
+            }
+            println!(
+                "Attestation OK: Tee({:?}), ResultsId({}), MetaCommitment({})",
+                report.tee, results_id, report.meta_commitment
+            );
+            println!("The quote itself must be verified with the tooling of the TEE vendor");
+        }
+        Method::VerifyRemote {
+            compute_id,
+            tolerance,
+            json,
+        } => {
+            let provider = ProviderBuilder::new().connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage);
+            let verification = client
+                .verify_remote(Uint::from_str(&compute_id).unwrap(), tolerance)
+                .await
+                .unwrap();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&verification).unwrap());
+            } else {
+                let status = |valid: bool| if valid { "PASS" } else { "FAIL" };
+                for sub_job in &verification.sub_jobs {
+                    match &sub_job.error {
+                        Some(error) => println!("{} {}: {}", status(false), sub_job.name, error),
+                        None => println!(
+                            "{} {}: commitment {}, {} scores checked, {} mismatched, {} missing, {} unexpected, max diff {:e}",
+                            status(sub_job.is_valid()),
+                            sub_job.name,
+                            if sub_job.commitment_matches { "matches" } else { "differs" },
+                            sub_job.scores.checked,
+                            sub_job.scores.mismatched,
+                            sub_job.scores.missing.len(),
+                            sub_job.scores.unexpected.len(),
+                            sub_job.scores.max_diff,
+                        ),
+                    }
+                }
+                println!(
+                    "{} meta commitment of ComputeId({}), Computer({})",
+                    status(verification.meta_commitment_matches),
+                    verification.compute_id,
+                    verification.computer
+                );
+                println!("{}", status(verification.is_valid()));
+            }
+            if !verification.is_valid() {
+                std::process::exit(1);
+            }
+        }
+        Method::ExportVerificationManifest {
+            compute_id,
+            out_path,
+        } => {
+            let provider = ProviderBuilder::new().connect_client(rpc_config.client().unwrap());
+            let client = OpenRankClient::new(provider, manager_address, storage.clone());
+            let compute_id_uint = Uint::<256, 4>::from_str(&compute_id).unwrap();
+            let (compute_result, compute_manifest) =
+                client.verified_manifest(compute_id_uint).await.unwrap();
+            assert_eq!(
+                compute_manifest.meta_commitment,
+                compute_result.meta_commitment.encode_hex(),
+                "Manifest commitment doesn't match the on-chain commitment"
+            );
+
+            let manifest = build_verification_manifest(
+                &compute_manifest,
+                manager_address.to_string(),
+                &bucket,
+                &load_dataset_registry(storage.as_ref()).await,
+            );
+
+            if let Some(out_path) = out_path {
+                save_json_to_file(manifest, Path::new(&out_path)).unwrap();
+            } else {
+                println!("{}", serde_json::to_string_pretty(&manifest).unwrap());
+            }
+        }
     };
 
     Ok(())
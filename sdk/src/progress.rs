@@ -0,0 +1,103 @@
+use std::io::{IsTerminal, Read, Write};
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 30;
+/// Minimum time between two draws of the bar.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of an operation over a number of bytes, drawn on stderr when it is a terminal
+/// (so that it doesn't end up in logs or piped output).
+pub struct ProgressBar {
+    label: String,
+    total: u64,
+    done: u64,
+    drawn_at: Option<Instant>,
+    enabled: bool,
+}
+
+impl ProgressBar {
+    pub fn new(label: impl Into<String>, total: u64) -> Self {
+        Self {
+            label: label.into(),
+            total,
+            done: 0,
+            drawn_at: None,
+            enabled: std::io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn inc(&mut self, n: u64) {
+        self.done = (self.done + n).min(self.total);
+        if self
+            .drawn_at
+            .is_none_or(|at| at.elapsed() >= REDRAW_INTERVAL)
+        {
+            self.draw();
+        }
+    }
+
+    /// Draws the bar complete and moves to the next line.
+    pub fn finish(&mut self) {
+        self.done = self.total;
+        self.draw();
+        if self.enabled {
+            eprintln!();
+        }
+    }
+
+    fn draw(&mut self) {
+        self.drawn_at = Some(Instant::now());
+        if !self.enabled {
+            return;
+        }
+        let ratio = match self.total {
+            0 => 1.0,
+            total => self.done as f64 / total as f64,
+        };
+        let filled = (ratio * WIDTH as f64) as usize;
+        eprint!(
+            "\r{} [{}{}] {:>3}% {}/{}",
+            self.label,
+            "#".repeat(filled),
+            ".".repeat(WIDTH - filled),
+            (ratio * 100.0) as u32,
+            format_bytes(self.done),
+            format_bytes(self.total),
+        );
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Reader advancing a progress bar with the bytes read.
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a mut ProgressBar,
+}
+
+impl<'a, R: Read> ProgressReader<'a, R> {
+    pub fn new(inner: R, progress: &'a mut ProgressBar) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.inc(n as u64);
+        Ok(n)
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -0,0 +1,215 @@
+use openrank_common::{
+    format,
+    metrics::{self, Comparison},
+    params::{AlgoParams, EigenTrustParams, SybilRankParams},
+    runner::{self, ComputeRunner},
+    ScoreEntry, TrustEntry,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File};
+use std::path::Path;
+
+/// Builds the cartesian product of the EigenTrust parameter lists.
+/// An empty list leaves the parameter unset (algorithm default).
+pub fn eigen_trust_grid(alphas: &[f64], deltas: &[f64], max_iters: &[u32]) -> Vec<AlgoParams> {
+    let mut grid = Vec::new();
+    for alpha in or_default(alphas) {
+        for delta in or_default(deltas) {
+            for max_iter in or_default(max_iters) {
+                grid.push(AlgoParams::EigenTrust(EigenTrustParams {
+                    alpha,
+                    delta,
+                    max_iter,
+                    ranking: None,
+                    metadata: None,
+                    precision: None,
+                    hash: None,
+                }));
+            }
+        }
+    }
+    grid
+}
+
+/// Builds the SybilRank parameter grid. An empty list runs with the default walk length.
+pub fn sybil_rank_grid(walk_lengths: &[u32]) -> Vec<AlgoParams> {
+    or_default(walk_lengths)
+        .into_iter()
+        .map(|walk_length| {
+            AlgoParams::SybilRank(SybilRankParams {
+                walk_length,
+                ranking: None,
+                metadata: None,
+                precision: None,
+                hash: None,
+            })
+        })
+        .collect()
+}
+
+fn or_default<T: Copy>(values: &[T]) -> Vec<Option<T>> {
+    if values.is_empty() {
+        vec![None]
+    } else {
+        values.iter().copied().map(Some).collect()
+    }
+}
+
+/// Returns a file name friendly label of the params, e.g. `alpha=0.5_max_iter=20`.
+pub fn params_label(params: &AlgoParams) -> String {
+    let parts: Vec<String> = match params {
+        AlgoParams::EigenTrust(p) => [
+            p.alpha.map(|v| format!("alpha={}", v)),
+            p.delta.map(|v| format!("delta={}", v)),
+            p.max_iter.map(|v| format!("max_iter={}", v)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        AlgoParams::NegativeEigenTrust(p) => [
+            p.et.alpha.map(|v| format!("alpha={}", v)),
+            p.et.delta.map(|v| format!("delta={}", v)),
+            p.et.max_iter.map(|v| format!("max_iter={}", v)),
+            p.distrust_weight.map(|v| format!("distrust_weight={}", v)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        AlgoParams::SybilRank(p) => p
+            .walk_length
+            .map(|v| format!("walk_length={}", v))
+            .into_iter()
+            .collect(),
+    };
+    if parts.is_empty() {
+        return "default".to_string();
+    }
+    parts.join("_")
+}
+
+/// Runs the algorithm for every point of the grid, in parallel.
+/// The runner, already loaded with the trust and seed data, is cloned for each run so
+/// the graph is only parsed once. The points of the grid must have the precision of the
+/// runner.
+pub fn run_sweep(
+    base: &ComputeRunner,
+    grid: &[AlgoParams],
+) -> Result<Vec<Vec<ScoreEntry>>, runner::Error> {
+    grid.par_iter()
+        .map(|params| {
+            let mut runner = base.clone();
+            runner.compute(params)?;
+            runner.get_compute_scores()
+        })
+        .collect()
+}
+
+/// Comparison between the scores of two runs of the sweep.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunComparison {
+    pub a: String,
+    pub b: String,
+    #[serde(flatten)]
+    pub metrics: Comparison,
+}
+
+/// Compares every pair of runs.
+pub fn compare_runs(
+    labels: &[String],
+    runs: &[Vec<ScoreEntry>],
+    top_k: usize,
+) -> Vec<RunComparison> {
+    let mut comparisons = Vec::new();
+    for i in 0..runs.len() {
+        for j in i + 1..runs.len() {
+            comparisons.push(RunComparison {
+                a: labels[i].clone(),
+                b: labels[j].clone(),
+                metrics: metrics::compare(&runs[i], &runs[j], top_k),
+            });
+        }
+    }
+    comparisons
+}
+
+/// A single run of the sweep, as recorded in the summary.
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepRun {
+    pub label: String,
+    pub params: HashMap<String, String>,
+    pub scores_path: String,
+    pub scores_count: usize,
+}
+
+/// Summary of a sweep, written next to the score files.
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepSummary {
+    pub algo_id: u32,
+    pub runs: Vec<SweepRun>,
+    pub comparisons: Vec<RunComparison>,
+}
+
+/// Runs the sweep over the trust and seed files, writing one score file per run
+/// (`<label>.csv`) and the summary (`summary.json`) to the output directory.
+pub fn sweep_to_dir(
+    trust_path: &str,
+    seed_path: &str,
+    grid: &[AlgoParams],
+    out_dir: &str,
+    top_k: usize,
+) -> SweepSummary {
+    // The grid shares the precision of its first point, the default one for the built grids
+    let precision = grid.first().map(AlgoParams::precision).unwrap_or_default();
+    let trust_entries: Vec<TrustEntry> =
+        format::trust_entries(File::open(trust_path).unwrap(), precision)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    let seed_entries: Vec<ScoreEntry> =
+        format::score_entries(File::open(seed_path).unwrap(), precision)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    let mut base = ComputeRunner::new(precision);
+    base.update_trust_map(trust_entries).unwrap();
+    base.update_seed_map(seed_entries).unwrap();
+
+    let mut runs = run_sweep(&base, grid).unwrap();
+    let labels: Vec<String> = grid.iter().map(params_label).collect();
+
+    create_dir_all(out_dir).unwrap();
+    let mut sweep_runs = Vec::new();
+    for ((params, label), scores) in grid.iter().zip(&labels).zip(runs.iter_mut()) {
+        // Sort scores by value in descending order (highest scores first)
+        scores.sort_by(|a, b| {
+            b.value()
+                .partial_cmp(a.value())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let scores_path = Path::new(out_dir).join(format!("{}.csv", label));
+        let mut wtr = csv::Writer::from_path(&scores_path).unwrap();
+        wtr.write_record(["i", "v"]).unwrap();
+        for x in scores.iter() {
+            wtr.write_record([x.id(), precision.format(*x.value()).as_str()])
+                .unwrap();
+        }
+        wtr.flush().unwrap();
+        sweep_runs.push(SweepRun {
+            label: label.clone(),
+            params: params.to_map(),
+            scores_path: scores_path.display().to_string(),
+            scores_count: scores.len(),
+        });
+    }
+
+    let summary = SweepSummary {
+        algo_id: grid.first().map(|p| p.algo_id()).unwrap_or_default(),
+        runs: sweep_runs,
+        comparisons: compare_runs(&labels, &runs, top_k),
+    };
+    let file = File::create(Path::new(out_dir).join("summary.json")).unwrap();
+    serde_json::to_writer_pretty(file, &summary).unwrap();
+    summary
+}
@@ -0,0 +1,187 @@
+use crate::datasets::DatasetRegistry;
+use openrank_common::{
+    manifest::{self, ComputeManifest},
+    params::{AlgoParams, ALGO_ID_EIGEN_TRUST, ALGO_ID_NEGATIVE_EIGEN_TRUST, ALGO_ID_SYBIL_RANK},
+    storage::S3Config,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Artifact referenced by the verification manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactRef {
+    /// Content id (Keccak256 hash of the file bytes)
+    pub id: String,
+    /// Public URL the artifact can be downloaded from
+    pub url: String,
+}
+
+/// Verification details of a single sub-job.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubJobManifest {
+    pub index: usize,
+    pub name: String,
+    pub algo_id: u32,
+    pub params: HashMap<String, String>,
+    pub trust: ArtifactRef,
+    pub seed: ArtifactRef,
+    pub scores: ArtifactRef,
+    /// Ids removed from the graph before compute
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocklist: Option<ArtifactRef>,
+    /// Ids the graph is restricted to before compute
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_list: Option<ArtifactRef>,
+    /// Dataset aliases currently pointing to the trust and seed ids
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trust_aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub seed_aliases: Vec<String>,
+    pub commitment: String,
+    /// Version of the scheme of the commitment
+    pub commitment_scheme: String,
+    /// Commands to independently recompute the sub-job scores
+    pub commands: Vec<String>,
+}
+
+/// Self-contained manifest describing how to verify a compute job.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationManifest {
+    pub compute_id: String,
+    pub manager_address: String,
+    pub job_description_id: String,
+    pub results_id: String,
+    pub meta_commitment: String,
+    pub commitment_scheme: String,
+    pub job_description: ArtifactRef,
+    pub results: ArtifactRef,
+    pub sub_jobs: Vec<SubJobManifest>,
+    /// Steps to follow to verify the whole compute job
+    pub instructions: Vec<String>,
+}
+
+fn artifact(bucket: &S3Config, key: &str, id: &str) -> ArtifactRef {
+    ArtifactRef {
+        id: id.to_string(),
+        url: bucket.object_url(key),
+    }
+}
+
+/// Returns the commands used to recompute the scores of a single sub-job.
+fn recompute_commands(
+    job: &manifest::SubJobManifest,
+    trust: &ArtifactRef,
+    seed: &ArtifactRef,
+    blocklist: Option<&ArtifactRef>,
+    include_list: Option<&ArtifactRef>,
+) -> Vec<String> {
+    let mut commands = vec![
+        format!("curl -sSf -o trust.csv {}", trust.url),
+        format!("curl -sSf -o seed.csv {}", seed.url),
+    ];
+    if let Some(blocklist) = blocklist {
+        commands.push(format!("curl -sSf -o blocklist.txt {}", blocklist.url));
+    }
+    if let Some(include_list) = include_list {
+        commands.push(format!("curl -sSf -o include.txt {}", include_list.url));
+    }
+    let subcommand = match job.algo_id {
+        ALGO_ID_EIGEN_TRUST => "compute-local-et",
+        ALGO_ID_SYBIL_RANK => "compute-local-sr",
+        ALGO_ID_NEGATIVE_EIGEN_TRUST => "compute-local-et --negative-trust",
+        _ => return commands,
+    };
+    let mut flags: Vec<String> = AlgoParams::parse(job.algo_id, &job.params, false)
+        .map(|p| p.to_map())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(k, v)| format!("--{} {}", k.replace('_', "-"), v))
+        .collect();
+    flags.sort();
+    let mut command = format!(
+        "openrank {} trust.csv seed.csv --out-path scores.csv",
+        subcommand
+    );
+    for flag in flags {
+        command.push(' ');
+        command.push_str(&flag);
+    }
+    if blocklist.is_some() {
+        command.push_str(" --blocklist blocklist.txt");
+    }
+    if include_list.is_some() {
+        command.push_str(" --include-list include.txt");
+    }
+    commands.push(command);
+    commands
+}
+
+/// Builds the verification manifest from the compute manifest.
+pub fn build_verification_manifest(
+    manifest: &ComputeManifest,
+    manager_address: String,
+    bucket: &S3Config,
+    registry: &DatasetRegistry,
+) -> VerificationManifest {
+    let sub_jobs = manifest
+        .sub_jobs
+        .iter()
+        .enumerate()
+        .map(|(index, job)| {
+            let trust = artifact(bucket, &job.trust_key(), &job.trust_id);
+            let seed = artifact(bucket, &job.seed_key(), &job.seed_id);
+            let scores = artifact(bucket, &job.scores_key(), &job.scores_id);
+            let blocklist = job
+                .blocklist_id
+                .as_ref()
+                .map(|id| artifact(bucket, &format!("blocklist/{}", id), id));
+            let include_list = job
+                .include_list_id
+                .as_ref()
+                .map(|id| artifact(bucket, &format!("include/{}", id), id));
+            let commands = recompute_commands(
+                job,
+                &trust,
+                &seed,
+                blocklist.as_ref(),
+                include_list.as_ref(),
+            );
+            SubJobManifest {
+                index,
+                name: job.name.clone(),
+                algo_id: job.algo_id,
+                params: job.params.clone(),
+                trust,
+                seed,
+                scores,
+                blocklist,
+                include_list,
+                trust_aliases: registry.aliases_of(&job.trust_id),
+                seed_aliases: registry.aliases_of(&job.seed_id),
+                commitment: job.commitment.clone(),
+                commitment_scheme: manifest.scheme_version(job),
+                commands,
+            }
+        })
+        .collect();
+
+    let instructions = vec![
+        "Download every artifact and check its Keccak256 hash matches its id.".to_string(),
+        "For each sub-job, run its commands to recompute the scores.".to_string(),
+        "Hash each score value, encoded as a big-endian f32 (keccak256-f32be-dense-merkle-v1 scheme) or f64 (keccak256-f64be-dense-merkle-v2 and blake3-f64be-dense-merkle-v3 schemes) according to the sub-job commitment scheme, in the order of the scores artifact, and build a dense merkle tree with the hash function of the scheme (Keccak256, or BLAKE3 for v3); its root must equal the sub-job commitment.".to_string(),
+        "Build a dense merkle tree from the sub-job commitments in order, hashed with Keccak256 whatever their scheme; its root must equal the meta commitment posted on-chain.".to_string(),
+    ];
+
+    VerificationManifest {
+        compute_id: manifest.compute_id.clone(),
+        manager_address,
+        job_description: artifact(bucket, "meta", &manifest.job_description_id),
+        results: artifact(bucket, "meta", &manifest.results_id),
+        job_description_id: manifest.job_description_id.clone(),
+        results_id: manifest.results_id.clone(),
+        meta_commitment: manifest.meta_commitment.clone(),
+        commitment_scheme: manifest.proof_spec_version.clone(),
+        sub_jobs,
+        instructions,
+    }
+}
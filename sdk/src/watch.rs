@@ -0,0 +1,239 @@
+use crate::actions::compute_local_params;
+use alloy::hex;
+use openrank_common::format;
+use openrank_common::params::AlgoParams;
+use openrank_common::runner;
+use openrank_common::scheme::Precision;
+use openrank_common::subgraph::NodeFilter;
+use openrank_common::{ScoreEntry, TrustEntry};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Name of the manifest of processed datasets, kept in the watched directory.
+pub const MANIFEST_FILE: &str = ".watch-manifest.json";
+pub const TRUST_SUFFIX: &str = ".trust.csv";
+pub const SEED_SUFFIX: &str = ".seed.csv";
+pub const SCORES_SUFFIX: &str = ".scores.csv";
+/// Files modified more recently are assumed to still be written, and left for the next scan.
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// A dataset pair as processed by the watcher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessedEntry {
+    /// Keccak256 hash of the trust file.
+    pub trust_hash: String,
+    /// Keccak256 hash of the seed file.
+    pub seed_hash: String,
+    pub algo_id: u32,
+    pub params: BTreeMap<String, String>,
+    pub scores_path: String,
+    /// Unix timestamp (seconds) of the compute.
+    pub processed_at: u64,
+}
+
+/// Manifest of the processed dataset pairs, by name, so only new or changed pairs are
+/// recomputed (also across restarts).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchManifest {
+    pub datasets: BTreeMap<String, ProcessedEntry>,
+}
+
+impl WatchManifest {
+    /// Reads the manifest, a missing file being an empty manifest.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// A trust and seed file sharing the same name, e.g. `follows.trust.csv` and `follows.seed.csv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetPair {
+    pub name: String,
+    pub trust_path: PathBuf,
+    pub seed_path: PathBuf,
+}
+
+impl DatasetPair {
+    /// Path of the scores file, written next to the inputs.
+    pub fn scores_path(&self) -> PathBuf {
+        self.trust_path
+            .with_file_name(format!("{}{}", self.name, SCORES_SUFFIX))
+    }
+}
+
+fn is_settled(path: &Path) -> bool {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age >= SETTLE_TIME))
+}
+
+/// Lists the settled dataset pairs of the directory, sorted by name.
+pub fn find_pairs(dir: &Path) -> Result<Vec<DatasetPair>, std::io::Error> {
+    let mut pairs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let trust_path = entry?.path();
+        let Some(name) = trust_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(TRUST_SUFFIX))
+        else {
+            continue;
+        };
+        let seed_path = dir.join(format!("{}{}", name, SEED_SUFFIX));
+        if !seed_path.is_file() || !is_settled(&trust_path) || !is_settled(&seed_path) {
+            continue;
+        }
+        pairs.push(DatasetPair {
+            name: name.to_string(),
+            trust_path: trust_path.clone(),
+            seed_path,
+        });
+    }
+    pairs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(pairs)
+}
+
+fn hash_file(path: &Path) -> Result<String, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = Keccak256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn write_scores(
+    path: &Path,
+    scores: &[ScoreEntry],
+    precision: Precision,
+) -> Result<(), csv::Error> {
+    // Written to a temporary file first, so readers never see a partial scores file
+    let tmp_path = path.with_extension("csv.part");
+    let mut wtr = csv::Writer::from_path(&tmp_path)?;
+    wtr.write_record(["i", "v"])?;
+    for x in scores {
+        wtr.write_record([x.id(), precision.format(*x.value()).as_str()])?;
+    }
+    wtr.flush()?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Computes the scores of the pair, writing them next to the inputs.
+async fn process_pair(
+    pair: &DatasetPair,
+    params: &AlgoParams,
+    trust_hash: String,
+    seed_hash: String,
+) -> Result<ProcessedEntry, Error> {
+    let precision = params.precision();
+    let trust_entries: Vec<TrustEntry> =
+        format::trust_entries(File::open(&pair.trust_path)?, precision)?
+            .collect::<Result<_, _>>()?;
+    let seed_entries: Vec<ScoreEntry> =
+        format::score_entries(File::open(&pair.seed_path)?, precision)?
+            .collect::<Result<_, _>>()?;
+    let mut scores = compute_local_params(
+        &trust_entries,
+        &seed_entries,
+        params,
+        None,
+        NodeFilter::default(),
+    )
+    .await?;
+    // Sort scores by value in descending order (highest scores first)
+    scores.sort_by(|a, b| {
+        b.value()
+            .partial_cmp(a.value())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let scores_path = pair.scores_path();
+    write_scores(&scores_path, &scores, precision)?;
+    Ok(ProcessedEntry {
+        trust_hash,
+        seed_hash,
+        algo_id: params.algo_id(),
+        params: params.to_map().into_iter().collect(),
+        scores_path: scores_path.display().to_string(),
+        processed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    })
+}
+
+/// Scans the directory once, computing the scores of the new or changed pairs (or all of them
+/// if the params changed). Returns the names of the processed pairs.
+pub async fn scan(dir: &Path, params: &AlgoParams) -> Result<Vec<String>, Error> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let mut manifest = WatchManifest::load(&manifest_path)?;
+    let algo_params: BTreeMap<String, String> = params.to_map().into_iter().collect();
+    let mut processed = Vec::new();
+    for pair in find_pairs(dir)? {
+        let trust_hash = hash_file(&pair.trust_path)?;
+        let seed_hash = hash_file(&pair.seed_path)?;
+        let up_to_date = manifest.datasets.get(&pair.name).is_some_and(|entry| {
+            entry.trust_hash == trust_hash
+                && entry.seed_hash == seed_hash
+                && entry.algo_id == params.algo_id()
+                && entry.params == algo_params
+                && Path::new(&entry.scores_path).is_file()
+        });
+        if up_to_date {
+            continue;
+        }
+
+        info!("Computing scores of {}", pair.name);
+        match process_pair(&pair, params, trust_hash, seed_hash).await {
+            Ok(entry) => {
+                info!("Scores of {} saved to {}", pair.name, entry.scores_path);
+                manifest.datasets.insert(pair.name.clone(), entry);
+                manifest.save(&manifest_path)?;
+                processed.push(pair.name);
+            }
+            // A bad pair is reported and retried on the next scan, without stopping the watcher
+            Err(e) => warn!("Failed to compute scores of {}: {}", pair.name, e),
+        }
+    }
+    Ok(processed)
+}
+
+/// Watches the directory, scanning it every interval.
+pub async fn watch(dir: &Path, params: &AlgoParams, interval: Duration) -> Result<(), Error> {
+    info!("Watching {} for dataset pairs", dir.display());
+    loop {
+        scan(dir, params).await?;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Compute error: {0}")]
+    Compute(#[from] runner::Error),
+}